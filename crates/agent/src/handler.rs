@@ -34,6 +34,7 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use rust_pipeline::prelude::*;
 use tokio::sync::{broadcast, Mutex};
+use tracing::Instrument;
 
 use agentos_librarian::Librarian;
 use agentos_events::{ContentBlock, ShimReport, ToolDefinition, ToolResultBlock};
@@ -43,6 +44,7 @@ use agentos_organism::AgentConfig;
 use agentos_events::{ConversationEntry, PipelineEvent};
 use agentos_routing::{RouteDecision, SemanticRouter};
 
+use super::shims::ShimRouting;
 use super::state::{AgentState, AgentThread, PendingToolCall};
 use super::translate;
 
@@ -79,6 +81,9 @@ pub struct CodingAgentHandler {
     /// providers ignore the field even when present). Step 5's
     /// shim-expert agent owns the lifecycle of this value.
     shim_config: Option<ShimAttachment>,
+    /// Live composition + canary selection from the kernel's shim
+    /// store; takes precedence over `shim_config` once connected.
+    shim_routing: Option<ShimRouting>,
 }
 
 /// Type alias — generic agent handler (same implementation, data-driven identity).
@@ -108,6 +113,7 @@ impl CodingAgentHandler {
             max_tokens: 4096,
             model: None,
             shim_config: None,
+            shim_routing: None,
         }
     }
 
@@ -132,6 +138,7 @@ impl CodingAgentHandler {
             max_tokens: config.max_tokens,
             model: config.model.clone(),
             shim_config: None,
+            shim_routing: None,
         }
     }
 
@@ -156,6 +163,7 @@ impl CodingAgentHandler {
             max_tokens: 4096,
             model: None,
             shim_config: None,
+            shim_routing: None,
         }
     }
 
@@ -180,6 +188,7 @@ impl CodingAgentHandler {
             max_tokens: 4096,
             model: None,
             shim_config: None,
+            shim_routing: None,
         }
     }
 
@@ -221,6 +230,12 @@ impl CodingAgentHandler {
         self.shim_config = config;
     }
 
+    /// Route LLM calls through a shim store's live composition (and any
+    /// running canary), recording each response's shim outcomes.
+    pub fn set_shim_routing(&mut self, routing: Option<ShimRouting>) {
+        self.shim_routing = routing;
+    }
+
 
     /// Emit an AgentResponse event if an event sender is attached.
     ///
@@ -314,6 +329,7 @@ impl CodingAgentHandler {
     /// own 5-minute timeout as a second safety net.
     async fn call_opus(
         &self,
+        thread_id: &str,
         thread: &mut AgentThread,
     ) -> Result<agentos_llm::types::MessagesResponse, String> {
         // Optional: curate context before the API call
@@ -332,20 +348,66 @@ impl CodingAgentHandler {
             }
         }
 
+        let selection = match &self.shim_routing {
+            Some(routing) => routing.select(thread_id).await,
+            None => None,
+        };
+        let shim_config = match &selection {
+            Some(s) => Some(s.attachment.clone()),
+            None => self.shim_config.clone(),
+        };
+
         let pool = self.pool.lock().await;
+        // Metrics label by the model we asked for; the response's `model`
+        // may be a dated snapshot id, and failed calls have no response.
+        let model = self
+            .model
+            .clone()
+            .unwrap_or_else(|| pool.default_model().to_string());
         let fut = pool.complete_with_tools_and_shims(
             self.model.as_deref(),
             thread.messages.clone(),
             self.max_tokens,
             Some(&system),
             self.tool_definitions.clone(),
-            self.shim_config.clone(),
+            shim_config,
         );
 
-        let response = match tokio::time::timeout(std::time::Duration::from_secs(300), fut).await {
-            Ok(result) => result.map_err(|e| format!("LLM API error: {e}"))?,
-            Err(_) => return Err("LLM API call timed out after 5 minutes".into()),
+        let span = tracing::info_span!(
+            "llm.call",
+            model = %model,
+            agent = %self.name,
+            input_tokens = tracing::field::Empty,
+            output_tokens = tracing::field::Empty,
+            otel.status_code = tracing::field::Empty,
+        );
+        let started = std::time::Instant::now();
+        let outcome = match tokio::time::timeout(std::time::Duration::from_secs(300), fut)
+            .instrument(span.clone())
+            .await
+        {
+            Ok(result) => result.map_err(|e| format!("LLM API error: {e}")),
+            Err(_) => Err("LLM API call timed out after 5 minutes".into()),
         };
+        agentos_telemetry::record_llm_call(&model, &self.name, started.elapsed(), outcome.is_ok());
+        agentos_telemetry::trace::record_outcome(&span, outcome.is_ok());
+        let response = outcome?;
+        span.record("input_tokens", response.usage.input_tokens);
+        span.record("output_tokens", response.usage.output_tokens);
+        agentos_telemetry::record_llm_tokens(
+            &model,
+            &self.name,
+            response.usage.input_tokens.into(),
+            response.usage.output_tokens.into(),
+        );
+
+        // Usage feeds the TUI token counters and the server's budgets.
+        self.maybe_emit(PipelineEvent::TokenUsage {
+            thread_id: thread_id.to_string(),
+            model: response.model.clone(),
+            input_tokens: response.usage.input_tokens,
+            output_tokens: response.usage.output_tokens,
+        });
 
         // Stash cortex's shim outcomes on the thread; the next
         // AgentResponse event surfaces them. None for non-cortex
@@ -356,6 +418,12 @@ impl CodingAgentHandler {
             active_steers: m.active_steers.clone(),
             signals: m.signals.clone(),
         });
+        drop(pool);
+        if let (Some(routing), Some(selection), Some(metadata)) =
+            (&self.shim_routing, &selection, &response.shim_metadata)
+        {
+            routing.record(&selection.composition_hash, metadata).await;
+        }
 
         Ok(response)
     }
@@ -453,13 +521,14 @@ impl CodingAgentHandler {
     /// the routing loop handles it. Otherwise, normal dispatch.
     async fn dispatch_or_route(
         &self,
+        thread_id: &str,
         thread: &mut AgentThread,
         action: ResponseAction,
         allowed_tools: &[String],
     ) -> HandlerResult {
         match action {
             ResponseAction::FinalText { blocks, text } if self.semantic_router.is_some() => {
-                self.dispatch_with_routing(thread, blocks, text, allowed_tools, 0)
+                self.dispatch_with_routing(thread_id, thread, blocks, text, allowed_tools, 0)
                     .await
             }
             _ => Self::dispatch_response(thread, action),
@@ -497,6 +566,7 @@ impl CodingAgentHandler {
     /// - Recurses up to `max_routing_iterations` times
     async fn dispatch_with_routing(
         &self,
+        thread_id: &str,
         thread: &mut AgentThread,
         blocks: Vec<ContentBlock>,
         text: String,
@@ -528,7 +598,7 @@ impl CodingAgentHandler {
 
                 // Call Opus again — it sees the result in context
                 let response = self
-                    .call_opus(thread)
+                    .call_opus(thread_id, thread)
                     .await
                     .map_err(PipelineError::Handler)?;
                 let action = self.process_response(&response);
@@ -540,6 +610,7 @@ impl CodingAgentHandler {
                     } => {
                         // Recurse: Opus might express another tool intent
                         Box::pin(self.dispatch_with_routing(
+                            thread_id,
                             thread,
                             new_blocks,
                             new_text,
@@ -558,7 +629,7 @@ impl CodingAgentHandler {
 
                 // Call Opus again with the failure note
                 let response = self
-                    .call_opus(thread)
+                    .call_opus(thread_id, thread)
                     .await
                     .map_err(PipelineError::Handler)?;
                 let action = self.process_response(&response);
@@ -594,6 +665,20 @@ enum ResponseAction {
 #[async_trait]
 impl Handler for CodingAgentHandler {
     async fn handle(&self, payload: ValidatedPayload, ctx: HandlerContext) -> HandlerResult {
+        let span = tracing::info_span!(
+            "agent.turn",
+            agent = %self.name,
+            thread_id = %ctx.thread_id,
+            payload_tag = %payload.tag,
+        );
+        self.turn(payload, ctx).instrument(span).await
+    }
+}
+
+impl CodingAgentHandler {
+    /// One pass of the state machine: a new task or a tool result in,
+    /// a tool call (`Send`) or the final answer (`Reply`) out.
+    async fn turn(&self, payload: ValidatedPayload, ctx: HandlerContext) -> HandlerResult {
         let xml_str = String::from_utf8_lossy(&payload.xml);
         let thread_id = ctx.thread_id.clone();
 
@@ -690,7 +775,7 @@ impl Handler for CodingAgentHandler {
                         agent_name: self.name.clone(),
                     });
 
-                    let response = match self.call_opus(thread).await {
+                    let response = match self.call_opus(&thread_id, thread).await {
                         Ok(r) => r,
                         Err(e) => {
                             self.emit_error(&thread_id, &e);
@@ -712,7 +797,7 @@ impl Handler for CodingAgentHandler {
                     }
 
                     let result = self
-                        .dispatch_or_route(thread, action, &[])
+                        .dispatch_or_route(&thread_id, thread, action, &[])
                         .await;
                     self.maybe_emit_response(&thread_id, thread, &result);
                     self.maybe_emit_conversation(&thread_id, thread);
@@ -742,7 +827,7 @@ impl Handler for CodingAgentHandler {
                 agent_name: self.name.clone(),
             });
 
-            let response = match self.call_opus(thread).await {
+            let response = match self.call_opus(&thread_id, thread).await {
                 Ok(r) => r,
                 Err(e) => {
                     self.emit_error(&thread_id, &e);
@@ -764,7 +849,7 @@ impl Handler for CodingAgentHandler {
             }
 
             let result = self
-                .dispatch_or_route(thread, action, &[])
+                .dispatch_or_route(&thread_id, thread, action, &[])
                 .await;
             self.maybe_emit_response(&thread_id, thread, &result);
             self.maybe_emit_conversation(&thread_id, thread);
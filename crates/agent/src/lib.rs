@@ -9,6 +9,7 @@
 //! - `translate`: JSON ↔ XML translation for tool calls/responses
 //! - `state`: Per-thread state machine (Ready → AwaitingTools → ...)
 //! - `handler`: CodingAgentHandler — the stateful Handler impl
+//! - `shims`: Live shim-store composition + canary routing, runtime stats
 //! - `prompts`: System prompt templates
 //! - `ralph`: Ralph Method story decomposition
 
@@ -17,6 +18,7 @@ pub mod middleware;
 pub mod permissions;
 pub mod prompts;
 pub mod ralph;
+pub mod shims;
 pub mod state;
 pub mod tools;
 pub mod translate;
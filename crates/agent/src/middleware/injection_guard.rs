@@ -128,6 +128,7 @@ impl Middleware for InjectionGuard {
         let injection_detected = scan_for_injection(&result);
 
         if injection_detected {
+            agentos_telemetry::record_injection_detected(&meta.from);
             // Emit event for activity log
             self.emit(PipelineEvent::InjectionDetected {
                 thread_id: meta.thread_id.clone(),
@@ -115,6 +115,10 @@ impl Middleware for PermissionGate {
                 Ok(PostDispatchVerdict::PassThrough(response))
             }
             PermissionTier::Deny => {
+                agentos_telemetry::record_permission_denial(
+                    &to,
+                    agentos_telemetry::DENIED_BY_POLICY,
+                );
                 self.emit(PipelineEvent::ToolApproval {
                     thread_id: meta.thread_id.clone(),
                     agent_name: meta.to.clone(),
@@ -141,6 +145,7 @@ impl Middleware for PermissionGate {
                         // TUI disconnected — headless mode, auto-approve
                         return Ok(PostDispatchVerdict::PassThrough(response));
                     }
+                    agentos_telemetry::record_permission_prompt(&to);
                     match resp_rx.await {
                         Ok(ApprovalVerdict::Approved) => {
                             self.emit(PipelineEvent::ToolApproval {
@@ -152,6 +157,10 @@ impl Middleware for PermissionGate {
                             Ok(PostDispatchVerdict::PassThrough(response))
                         }
                         Ok(ApprovalVerdict::Denied) | Err(_) => {
+                            agentos_telemetry::record_permission_denial(
+                                &to,
+                                agentos_telemetry::DENIED_BY_USER,
+                            );
                             self.emit(PipelineEvent::ToolApproval {
                                 thread_id: meta.thread_id.clone(),
                                 agent_name: meta.to.clone(),
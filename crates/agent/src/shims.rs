@@ -0,0 +1,309 @@
+//! Per-agent shim routing and runtime stats.
+//!
+//! An agent whose YAML names a `shim_store` reads the store's composition
+//! from the kernel on every LLM call instead of once at build, so a
+//! rollback or canary promotion takes effect on the next call without a
+//! restart. While a canary runs, each thread is pinned to the candidate
+//! or the current composition by `ShimCanary::serves_candidate`. After
+//! each response, cortex's `ShimMetadata` is recorded against the
+//! composition that produced it. Observations are held in memory and
+//! handed to the kernel (`Kernel::record_shim_activations`) in batches,
+//! every [`FLUSH_EVERY`] responses or once [`FLUSH_INTERVAL`] has passed
+//! since the last flush, so responses don't take the kernel lock or
+//! rewrite `activation_state.json` one by one. [`flush_periodically`]
+//! sends quiet agents' batches out every [`FLUSH_INTERVAL`] even when no
+//! further response arrives; the pipeline runs it alongside the agents
+//! and calls [`ShimRouting::flush`] once more on shutdown.
+//!
+//! The kernel is opened by `AgentPipelineBuilder::build`, after the
+//! handlers exist, so it arrives through a shared [`KernelCell`]. Until
+//! it is set the handler falls back to its build-time `shim_config` and
+//! records nothing.
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use agentos_kernel::shim_stats::ShimObservation;
+use agentos_kernel::Kernel;
+use agentos_llm::types::{ShimAttachment, ShimMetadata};
+use tokio::sync::Mutex;
+
+/// Kernel handle shared by every agent's [`ShimRouting`], filled in once
+/// the pipeline has opened the kernel.
+pub type KernelCell = Arc<OnceLock<Arc<Mutex<Kernel>>>>;
+
+/// Pending observations that trigger a flush to the kernel.
+pub const FLUSH_EVERY: usize = 32;
+
+/// Age of the last flush after which the next response flushes.
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The composition chosen for one LLM call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShimSelection {
+    /// Content hash of the composition; stats are keyed by it.
+    pub composition_hash: String,
+    pub attachment: ShimAttachment,
+    /// Whether the thread landed on the canary candidate.
+    pub canary: bool,
+}
+
+/// Picks an agent's composition per thread and records what it did.
+#[derive(Clone)]
+pub struct ShimRouting {
+    store: String,
+    kernel: KernelCell,
+    /// Parsed compositions by content hash.
+    parsed: Arc<std::sync::Mutex<HashMap<String, ShimAttachment>>>,
+    pending: Arc<std::sync::Mutex<Pending>>,
+}
+
+/// Observations not yet handed to the kernel.
+struct Pending {
+    observations: Vec<ShimObservation>,
+    flushed_at: Instant,
+}
+
+impl ShimRouting {
+    pub fn new(store: impl Into<String>, kernel: KernelCell) -> Self {
+        Self {
+            store: store.into(),
+            kernel,
+            parsed: Arc::default(),
+            pending: Arc::new(std::sync::Mutex::new(Pending {
+                observations: Vec::new(),
+                flushed_at: Instant::now(),
+            })),
+        }
+    }
+
+    pub fn store(&self) -> &str {
+        &self.store
+    }
+
+    /// The composition `thread_id` should run with. None until the
+    /// kernel is connected, or when the store or its composition is
+    /// unusable.
+    pub async fn select(&self, thread_id: &str) -> Option<ShimSelection> {
+        let kernel = self.kernel.get()?;
+        let (composition_hash, bytes, canary) = {
+            let k = kernel.lock().await;
+            let store = k.shim_store();
+            match store.canary_for(&self.store) {
+                Some(c) if c.serves_candidate(thread_id) => {
+                    (c.composition_hash.clone(), c.composition_bytes.clone(), true)
+                }
+                _ => (
+                    store.composition_hash_for(&self.store)?,
+                    store.composition_bytes_for(&self.store)?.to_vec(),
+                    false,
+                ),
+            }
+        };
+        let attachment = self.parse(&composition_hash, &bytes)?;
+        Some(ShimSelection {
+            composition_hash,
+            attachment,
+            canary,
+        })
+    }
+
+    /// Queue one response's shim outcomes for the store's stats, flushing
+    /// the batch when it is due. Stats are advisory; failures are logged,
+    /// not surfaced.
+    pub async fn record(&self, composition_hash: &str, metadata: &ShimMetadata) {
+        if self.kernel.get().is_none() {
+            return;
+        }
+        let due = {
+            let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+            pending.observations.push(observation(composition_hash, metadata));
+            pending.observations.len() >= FLUSH_EVERY || pending.flushed_at.elapsed() >= FLUSH_INTERVAL
+        };
+        if due {
+            self.flush().await;
+        }
+    }
+
+    /// Hand every pending observation to the kernel in one write.
+    pub async fn flush(&self) {
+        let Some(kernel) = self.kernel.get() else {
+            return;
+        };
+        let batch = {
+            let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+            pending.flushed_at = Instant::now();
+            std::mem::take(&mut pending.observations)
+        };
+        if batch.is_empty() {
+            return;
+        }
+        if let Err(e) = kernel
+            .lock()
+            .await
+            .record_shim_activations(&self.store, &batch)
+        {
+            tracing::warn!(
+                shim_store = %self.store,
+                dropped = batch.len(),
+                error = %e,
+                "failed to record shim activations"
+            );
+        }
+    }
+
+    fn parse(&self, composition_hash: &str, bytes: &[u8]) -> Option<ShimAttachment> {
+        let mut parsed = self.parsed.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(attachment) = parsed.get(composition_hash) {
+            return Some(attachment.clone());
+        }
+        let attachment = if bytes.is_empty() || bytes == b"{}" {
+            ShimAttachment::default()
+        } else {
+            match serde_json::from_slice::<ShimAttachment>(bytes) {
+                Ok(a) => a,
+                Err(e) => {
+                    tracing::warn!(
+                        shim_store = %self.store,
+                        hash = %composition_hash,
+                        error = %e,
+                        "composition does not parse as ShimAttachment; keeping build-time shims"
+                    );
+                    return None;
+                }
+            }
+        };
+        parsed.insert(composition_hash.to_string(), attachment.clone());
+        Some(attachment)
+    }
+}
+
+/// Flush each routing's pending observations every `period`, until the
+/// future is dropped (the pipeline aborts it on shutdown).
+pub async fn flush_periodically(routings: Vec<ShimRouting>, period: Duration) {
+    let mut ticker = tokio::time::interval(period);
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        for routing in &routings {
+            routing.flush().await;
+        }
+    }
+}
+
+/// The kernel's view of one response's [`ShimMetadata`].
+pub fn observation(composition_hash: &str, metadata: &ShimMetadata) -> ShimObservation {
+    ShimObservation {
+        composition_hash: composition_hash.to_string(),
+        gate_decisions: metadata.gate_decisions.clone(),
+        active_steers: metadata.active_steers.clone(),
+        silent: metadata.silent,
+        signals: metadata.signals.clone(),
+        prefill_ms: metadata.prefill_ms,
+        generation_ms: metadata.generation_ms,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn canary_threads_get_the_candidate_and_stats_follow_the_hash() {
+        let dir = TempDir::new().unwrap();
+        let mut kernel = Kernel::open(dir.path()).unwrap();
+        kernel.create_shim_store("bob", vec![]).unwrap();
+        let cell = KernelCell::default();
+        let routing = ShimRouting::new("bob", cell.clone());
+        assert!(routing.select("t1").await.is_none(), "unconnected routing selects nothing");
+
+        kernel
+            .start_shim_canary("bob", br#"{"gate_shims":["g"]}"#.to_vec(), 1.0)
+            .unwrap();
+        let kernel = Arc::new(Mutex::new(kernel));
+        assert!(cell.set(kernel.clone()).is_ok());
+
+        let selection = routing.select("t1").await.unwrap();
+        assert!(selection.canary);
+        assert_eq!(selection.attachment.gate_shims, vec!["g".to_string()]);
+
+        let metadata = ShimMetadata {
+            gate_decisions: HashMap::from([("g".to_string(), 0.9)]),
+            ..Default::default()
+        };
+        routing.record(&selection.composition_hash, &metadata).await;
+        routing.flush().await;
+
+        kernel.lock().await.abort_shim_canary("bob").unwrap();
+        let selection = routing.select("t1").await.unwrap();
+        assert!(!selection.canary);
+        assert!(selection.attachment.gate_shims.is_empty());
+
+        let k = kernel.lock().await;
+        let stats = k.shim_store().activation_for("bob").unwrap();
+        assert_eq!(stats.shims["g"].gate_fires, 1);
+        assert!(!stats.compositions.contains_key(&selection.composition_hash));
+    }
+
+    /// A kernel with an empty "bob" store, and routing connected to it.
+    fn connected(dir: &TempDir) -> (Arc<Mutex<Kernel>>, ShimRouting) {
+        let mut kernel = Kernel::open(dir.path()).unwrap();
+        kernel.create_shim_store("bob", vec![]).unwrap();
+        let kernel = Arc::new(Mutex::new(kernel));
+        let cell = KernelCell::default();
+        assert!(cell.set(kernel.clone()).is_ok());
+        (kernel, ShimRouting::new("bob", cell))
+    }
+
+    fn gate_metadata() -> ShimMetadata {
+        ShimMetadata {
+            gate_decisions: HashMap::from([("g".to_string(), 0.9)]),
+            ..Default::default()
+        }
+    }
+
+    async fn gate_fires(kernel: &Mutex<Kernel>) -> u64 {
+        kernel
+            .lock()
+            .await
+            .shim_store()
+            .activation_for("bob")
+            .and_then(|stats| stats.shims.get("g").map(|s| s.gate_fires))
+            .unwrap_or(0)
+    }
+
+    #[tokio::test]
+    async fn observations_reach_the_kernel_in_batches() {
+        let dir = TempDir::new().unwrap();
+        let (kernel, routing) = connected(&dir);
+        let hash = routing.select("t1").await.unwrap().composition_hash;
+        let metadata = gate_metadata();
+
+        for _ in 0..FLUSH_EVERY - 1 {
+            routing.record(&hash, &metadata).await;
+        }
+        assert_eq!(gate_fires(&kernel).await, 0);
+        routing.record(&hash, &metadata).await;
+        assert_eq!(gate_fires(&kernel).await, FLUSH_EVERY as u64);
+
+        routing.record(&hash, &metadata).await;
+        routing.flush().await;
+        assert_eq!(gate_fires(&kernel).await, FLUSH_EVERY as u64 + 1);
+    }
+
+    #[tokio::test]
+    async fn quiet_batches_go_out_on_the_timer() {
+        let dir = TempDir::new().unwrap();
+        let (kernel, routing) = connected(&dir);
+        let hash = routing.select("t1").await.unwrap().composition_hash;
+
+        routing.record(&hash, &gate_metadata()).await;
+        assert_eq!(gate_fires(&kernel).await, 0);
+        let flusher = tokio::spawn(flush_periodically(vec![routing.clone()], Duration::from_millis(10)));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        flusher.abort();
+        assert_eq!(gate_fires(&kernel).await, 1);
+    }
+}
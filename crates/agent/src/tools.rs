@@ -225,7 +225,7 @@ pub fn cortex_embed_definition() -> ToolDefinition {
 pub fn shim_train_definition() -> ToolDefinition {
     ToolDefinition {
         name: "shim-train".into(),
-        description: "Train a shim FFN by invoking the Python trainer subprocess. Reads (vector, label) JSONL, exports an ONNX file plus metrics.json. Read metrics.json before deciding whether to register the trained shim.".into(),
+        description: "Train a shim FFN with the configured trainer (native Rust or the Python subprocess). Reads (vector, label) JSONL, exports an ONNX file plus metrics.json. Read metrics.json before deciding whether to register the trained shim.".into(),
         input_schema: serde_json::json!({
             "type": "object",
             "properties": {
@@ -264,6 +264,10 @@ pub fn shim_train_definition() -> ToolDefinition {
                 "seed": {
                     "type": "integer",
                     "description": "Random seed (default: 42)"
+                },
+                "patience": {
+                    "type": "integer",
+                    "description": "Native trainer: stop after this many epochs without a better validation accuracy (default: 10)"
                 }
             },
             "required": ["input", "output_dir"]
@@ -275,7 +279,7 @@ pub fn shim_train_definition() -> ToolDefinition {
 pub fn shim_store_definition() -> ToolDefinition {
     ToolDefinition {
         name: "shim-store".into(),
-        description: "Manage cortex shim_stores via the kernel's fourth pillar. A shim_store is a named directory of ONNX shim weights + composition rules + per-shim metadata. Updates require an agent restart to take effect (v1).".into(),
+        description: "Manage cortex shim_stores via the kernel's fourth pillar. A shim_store is a named directory of ONNX shim weights + composition rules + per-shim metadata. Composition changes, canaries and rollbacks reach agents using the store on their next LLM call; `stats` reports per-shim fire rates and latency, `eval` scores a candidate composition against the current one on labeled JSONL.".into(),
         input_schema: serde_json::json!({
             "type": "object",
             "properties": {
@@ -289,7 +293,13 @@ pub fn shim_store_definition() -> ToolDefinition {
                         "update-composition",
                         "add-shim",
                         "retire-shim",
-                        "list-shims"
+                        "list-shims",
+                        "stats",
+                        "eval",
+                        "start-canary",
+                        "promote-canary",
+                        "abort-canary",
+                        "rollback"
                     ],
                     "description": "Operation to perform"
                 },
@@ -303,7 +313,19 @@ pub fn shim_store_definition() -> ToolDefinition {
                 },
                 "composition": {
                     "type": "string",
-                    "description": "JSON-serialized ShimAttachment (required for update-composition)"
+                    "description": "JSON-serialized ShimAttachment (required for update-composition and start-canary; eval's candidate, defaulting to the running canary)"
+                },
+                "fraction": {
+                    "type": "string",
+                    "description": "Share of threads served by the canary, in (0, 1] (required for start-canary)"
+                },
+                "data": {
+                    "type": "string",
+                    "description": "Workspace path of labeled {\"vector\", \"label\"} JSONL (required for eval)"
+                },
+                "target": {
+                    "type": "string",
+                    "description": "Outcome counted as predicting a positive label: respond | silent | signal:<name> | steer:<id> | gate:<id> (eval; default respond)"
                 },
                 "store": {
                     "type": "string",
@@ -3,10 +3,13 @@
 //! Usage: bitnet-chat <model.gguf> [--temp 0.7] [--top-k 40] [--top-p 0.9] [--max-tokens 256]
 
 use std::io::{self, BufRead, Write};
-use std::time::Instant;
+use std::ops::ControlFlow;
+use std::time::{Duration, Instant};
 
+use agentos_bitnet::layers::generation::{GenerateOptions, StopReason};
 use agentos_bitnet::layers::sampler::SamplerConfig;
-use agentos_bitnet::loader::load_model;
+use agentos_bitnet::layers::kv_cache::KvCacheFormat;
+use agentos_bitnet::loader::{load_model_with, LoadOptions};
 
 fn main() {
     let args: Vec<String> = std::env::args().collect();
@@ -20,6 +23,15 @@ fn main() {
         eprintln!("  --top-p <f32>       Nucleus sampling (default: 0.9)");
         eprintln!("  --max-tokens <n>    Max tokens per response (default: 256)");
         eprintln!("  --max-seq <n>       Max sequence length / KV cache (default: 2048)");
+        eprintln!("  --rep-penalty <f32> Repetition penalty (default: 1.2)");
+        eprintln!("  --rep-window <n>    Repetition penalty window (default: 64)");
+        eprintln!("  --freq-penalty <f32>     Frequency penalty (default: 0)");
+        eprintln!("  --presence-penalty <f32> Presence penalty (default: 0)");
+        eprintln!("  --min-p <f32>       Min-p sampling (default: 0)");
+        eprintln!("  --stop <text>       Extra stop sequence (repeatable)");
+        eprintln!("  --max-time <secs>   Time limit per response");
+        eprintln!("  --kv-cache <fmt>    KV cache format: f32 or int8 (default: f32)");
+        eprintln!("  --mmap              Memory-map the model file (needs the `mmap` feature)");
         std::process::exit(1);
     }
 
@@ -31,6 +43,13 @@ fn main() {
     let mut max_seq_len = 2048usize;
     let mut rep_penalty = 1.2f32;
     let mut rep_window = 64usize;
+    let mut frequency_penalty = 0.0f32;
+    let mut presence_penalty = 0.0f32;
+    let mut min_p = 0.0f32;
+    let mut extra_stops: Vec<String> = Vec::new();
+    let mut max_time: Option<Duration> = None;
+    let mut kv_cache = KvCacheFormat::F32;
+    let mut mmap = false;
 
     // Parse optional args
     let mut i = 2;
@@ -64,6 +83,38 @@ fn main() {
                 rep_window = args[i + 1].parse().expect("invalid --rep-window value");
                 i += 2;
             }
+            "--freq-penalty" => {
+                frequency_penalty = args[i + 1].parse().expect("invalid --freq-penalty value");
+                i += 2;
+            }
+            "--presence-penalty" => {
+                presence_penalty = args[i + 1].parse().expect("invalid --presence-penalty value");
+                i += 2;
+            }
+            "--min-p" => {
+                min_p = args[i + 1].parse().expect("invalid --min-p value");
+                i += 2;
+            }
+            "--stop" => {
+                extra_stops.push(args[i + 1].clone());
+                i += 2;
+            }
+            "--max-time" => {
+                let secs: f64 = args[i + 1].parse().expect("invalid --max-time value");
+                max_time = Some(Duration::from_secs_f64(secs));
+                i += 2;
+            }
+            "--kv-cache" => {
+                kv_cache = args[i + 1].parse().unwrap_or_else(|e: String| {
+                    eprintln!("{e}");
+                    std::process::exit(1);
+                });
+                i += 2;
+            }
+            "--mmap" => {
+                mmap = true;
+                i += 1;
+            }
             _ => {
                 eprintln!("Unknown option: {}", args[i]);
                 std::process::exit(1);
@@ -74,7 +125,7 @@ fn main() {
     // Load model
     eprintln!("Loading model from: {}", model_path);
     let start = Instant::now();
-    let loaded = match load_model(model_path) {
+    let loaded = match load_model_with(model_path, &LoadOptions { kv_cache, mmap }) {
         Ok(m) => m,
         Err(e) => {
             eprintln!("Failed to load model: {}", e);
@@ -109,9 +160,17 @@ fn main() {
         eprintln!("Chat mode: instruct model detected, wrapping with <|user|>/<|assistant|> template");
     }
 
+    // Role markers that signal the model is starting a new turn. Stop on
+    // their text, and on their token IDs where they are single control
+    // tokens (which decode to nothing).
+    let stop_markers = ["<|user|>", "<|assistant|>", "<|system|>", "<|endoftext|>"];
+    let mut stop_tokens = vec![tokenizer.eos_token_id()];
+    stop_tokens.extend(stop_markers.iter().filter_map(|m| tokenizer.token_id(m)));
+    let mut stop_sequences: Vec<String> = stop_markers.iter().map(|m| m.to_string()).collect();
+    stop_sequences.extend(extra_stops);
+
     // Pre-allocate KV cache for the full session
     let mut cache = model.create_kv_cache(max_seq_len);
-    let mut total_tokens = 0usize;
 
     let stdin = io::stdin();
     let mut stdout = io::stdout();
@@ -133,7 +192,6 @@ fn main() {
         // Special commands
         if line == "/clear" || line == "/reset" {
             cache.clear();
-            total_tokens = 0;
             eprintln!("[KV cache cleared]");
             continue;
         }
@@ -159,101 +217,81 @@ fn main() {
         };
 
         // Tokenize input
-        let add_bos = total_tokens == 0; // BOS only on first turn
+        let add_bos = cache.seq_len() == 0; // BOS only on first turn
         let input_tokens = tokenizer.encode(&formatted_input, add_bos);
         let n_input = input_tokens.len();
 
         // Check if we have room
-        if total_tokens + n_input + max_tokens > max_seq_len {
+        let used = cache.seq_len();
+        if used + n_input + max_tokens > max_seq_len {
             eprintln!(
                 "[Warning: approaching context limit ({}/{}). Use /clear to reset.]",
-                total_tokens + n_input,
+                used + n_input,
                 max_seq_len,
             );
-            if total_tokens + n_input >= max_seq_len {
+            if used + n_input >= max_seq_len {
                 eprintln!("[Context full. Use /clear to reset.]");
                 continue;
             }
         }
 
-        // Prefill the input
-        let prefill_start = Instant::now();
-        let prefill_logits = model.forward_cached(&input_tokens, &mut cache);
-        let prefill_time = prefill_start.elapsed();
-        total_tokens += n_input;
-
-        let sampler_config = SamplerConfig {
-            temperature,
-            top_k,
-            top_p,
-            repetition_penalty: rep_penalty,
-            repetition_window: rep_window,
-        };
         let seed = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_nanos() as u64;
-        let mut sampler = agentos_bitnet::layers::sampler::Sampler::new(sampler_config, seed);
+        let options = GenerateOptions {
+            max_tokens,
+            sampler: SamplerConfig {
+                temperature,
+                top_k,
+                top_p,
+                repetition_penalty: rep_penalty,
+                repetition_window: rep_window,
+                frequency_penalty,
+                presence_penalty,
+                min_p,
+                ..Default::default()
+            },
+            seed,
+            stop_tokens: stop_tokens.clone(),
+            stop_sequences: stop_sequences.clone(),
+            max_time,
+        };
 
-        // Sample first token from prefill
-        let last_logits_start = (n_input - 1) * model.vocab_size();
-        let last_logits = &prefill_logits[last_logits_start..last_logits_start + model.vocab_size()];
-        let mut next_token = sampler.sample(last_logits);
+        // Prefill, then stream tokens to stdout as they are sampled
+        let start = Instant::now();
+        let mut first_token_at = None;
+        let generation = model.generate_cached(&input_tokens, &mut cache, &options, Some(tokenizer), |event| {
+            first_token_at.get_or_insert_with(Instant::now);
+            print!("{}", event.text);
+            stdout.flush().unwrap();
+            ControlFlow::Continue(())
+        });
+        let end = Instant::now();
+        println!(); // newline after generation
 
+        let prefill_time = first_token_at.unwrap_or(end) - start;
         eprintln!(
             "[prefill: {} tokens in {:.1}ms | {:.0} tok/s]",
             n_input,
             prefill_time.as_secs_f64() * 1000.0,
             n_input as f64 / prefill_time.as_secs_f64(),
         );
-
-        // Decode loop — stream tokens to stdout
-        let decode_start = Instant::now();
-        let mut generated = 0usize;
-        let mut recent_text = String::new();
-
-        let eos = tokenizer.eos_token_id();
-
-        // Role markers that signal the model is starting a new turn
-        let stop_markers = ["<|user|>", "<|assistant|>", "<|system|>", "<|endoftext|>"];
-
-        while generated < max_tokens && next_token != eos && total_tokens < max_seq_len {
-            // Decode the token and check for role markers
-            let text = tokenizer.decode(&[next_token]);
-
-            // Track recent text to detect multi-token stop markers
-            recent_text.push_str(&text);
-            if recent_text.len() > 50 {
-                recent_text = recent_text[recent_text.len() - 50..].to_string();
-            }
-
-            // Stop if we hit a role marker (model is trying to continue the template)
-            if stop_markers.iter().any(|m| recent_text.contains(m)) {
-                break;
-            }
-
-            print!("{}", text);
-            stdout.flush().unwrap();
-
-            total_tokens += 1;
-            generated += 1;
-
-            // Forward one token
-            let logits = model.forward_cached(&[next_token], &mut cache);
-            next_token = sampler.sample(&logits);
-        }
-
-        let decode_time = decode_start.elapsed();
-        println!(); // newline after generation
-
-        if generated > 0 {
+        let generated = generation.tokens.len();
+        if let (Some(first), true) = (first_token_at, generated > 1) {
+            let decode_time = end - first;
             eprintln!(
                 "[decode: {} tokens in {:.1}ms | {:.1} tok/s]",
                 generated,
                 decode_time.as_secs_f64() * 1000.0,
-                generated as f64 / decode_time.as_secs_f64(),
+                (generated - 1) as f64 / decode_time.as_secs_f64(),
             );
         }
+        match generation.stop_reason {
+            StopReason::MaxTime => eprintln!("[stopped: time limit]"),
+            StopReason::ContextFull => eprintln!("[stopped: context full. Use /clear to reset.]"),
+            _ => {}
+        }
     }
 
     eprintln!("\nBye!");
@@ -1,15 +1,23 @@
 //! Dump GGUF metadata and tensor info from a model file.
+//!
+//! Usage: gguf-info <model.gguf> [--mmap] [--load]
+//!
+//! `--mmap` opens the file memory-mapped (`mmap` feature); `--load` also
+//! loads the model and reports how much of it ended up resident.
 
+use agentos_bitnet::loader::{load_model_with, LoadOptions};
 use agentos_bitnet::GgufFile;
 
 fn main() {
     let args: Vec<String> = std::env::args().collect();
     if args.len() < 2 {
-        eprintln!("Usage: gguf-info <model.gguf>");
+        eprintln!("Usage: gguf-info <model.gguf> [--mmap] [--load]");
         std::process::exit(1);
     }
+    let mmap = args[2..].iter().any(|a| a == "--mmap");
+    let load = args[2..].iter().any(|a| a == "--load");
 
-    let gguf = GgufFile::open(&args[1]).expect("failed to open GGUF file");
+    let gguf = open(&args[1], mmap);
 
     println!("=== Metadata ===");
     let mut keys: Vec<&String> = gguf.metadata().keys().collect();
@@ -38,4 +46,57 @@ fn main() {
         let info = &gguf.tensors()[name];
         println!("  {name}: {:?} {:?} ({} elements)", info.ggml_type, info.shape, info.n_elements);
     }
+
+    println!("\n=== Storage ===");
+    println!("  tensor data: {}", mb(gguf.tensor_data_bytes() as usize));
+    if gguf.is_mapped() {
+        println!("  mapped:      {} (virtual; pages fault in on use)", mb(gguf.mapped_bytes()));
+    } else {
+        println!("  mapped:      no (tensors are read into owned buffers)");
+    }
+
+    if load {
+        let loaded = load_model_with(&args[1], &LoadOptions { mmap, ..Default::default() })
+            .expect("failed to load model");
+        println!("\n=== Resident after load ===");
+        match resident_set() {
+            Some((anon, file)) => {
+                println!("  owned buffers (RssAnon): {}", mb(anon));
+                println!("  mapped pages  (RssFile): {}", mb(file));
+            }
+            None => println!("  (resident size not available on this platform)"),
+        }
+        drop(loaded);
+    }
+}
+
+#[cfg(feature = "mmap")]
+fn open(path: &str, mmap: bool) -> GgufFile {
+    if mmap {
+        return GgufFile::open_mmap(path).expect("failed to map GGUF file");
+    }
+    GgufFile::open(path).expect("failed to open GGUF file")
+}
+
+#[cfg(not(feature = "mmap"))]
+fn open(path: &str, mmap: bool) -> GgufFile {
+    if mmap {
+        eprintln!("warning: built without the `mmap` feature; reading normally");
+    }
+    GgufFile::open(path).expect("failed to open GGUF file")
+}
+
+fn mb(bytes: usize) -> String {
+    format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+/// `(RssAnon, RssFile)` in bytes from `/proc/self/status` (Linux only).
+fn resident_set() -> Option<(usize, usize)> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let field = |name: &str| -> Option<usize> {
+        let line = status.lines().find(|l| l.starts_with(name))?;
+        let kb: usize = line.split_whitespace().nth(1)?.parse().ok()?;
+        Some(kb * 1024)
+    };
+    Some((field("RssAnon:")?, field("RssFile:")?))
 }
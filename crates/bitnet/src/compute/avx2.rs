@@ -32,6 +32,30 @@ impl ComputeBackend for Avx2Backend {
         // SAFETY: We only construct Avx2Backend after checking is_x86_feature_detected!("avx2")
         unsafe { avx2_ternary_matvec(weights, input) }
     }
+
+    fn ternary_matmul(&self, weights: &TernaryTensor, inputs: &[i8], batch: usize) -> Vec<i32> {
+        assert_eq!(inputs.len(), batch * weights.cols(), "dimension mismatch");
+        // SAFETY: We only construct Avx2Backend after checking is_x86_feature_detected!("avx2")
+        unsafe { avx2_ternary_matmul(weights, inputs, batch) }
+    }
+
+    fn dot_q8(&self, x: &[f32], q: &[i8], scales: &[f32], block: usize) -> f32 {
+        check_q8_shape(x.len(), q.len(), scales.len(), block);
+        // SAFETY: AVX2 checked at construction; lengths checked above.
+        unsafe { avx2_dot_q8(x, q, scales, block) }
+    }
+
+    fn axpy_q8(&self, y: &mut [f32], a: f32, q: &[i8], scales: &[f32], block: usize) {
+        check_q8_shape(y.len(), q.len(), scales.len(), block);
+        // SAFETY: AVX2 checked at construction; lengths checked above.
+        unsafe { avx2_axpy_q8(y, a, q, scales, block) }
+    }
+}
+
+fn check_q8_shape(n: usize, q_len: usize, n_scales: usize, block: usize) {
+    assert_eq!(n, q_len, "dimension mismatch");
+    assert!(block > 0, "block must be non-zero");
+    assert_eq!(n_scales, q_len.div_ceil(block), "one scale per block");
 }
 
 /// SIMD unpack: 8 packed bytes → 32 sign bytes via nibble lookup.
@@ -172,6 +196,141 @@ unsafe fn avx2_ternary_matvec(weights: &TernaryTensor, input: &[i8]) -> Vec<i32>
     output
 }
 
+/// AVX2 ternary matrix-matrix product over a batch of activation vectors.
+///
+/// Same kernel as [`avx2_ternary_matvec`], but each 32-weight chunk is
+/// unpacked once and applied to every vector in the batch, with one
+/// accumulator register per vector. Output is `[batch, rows]`.
+///
+/// # Safety
+/// Caller must ensure AVX2 is available.
+#[target_feature(enable = "avx2")]
+unsafe fn avx2_ternary_matmul(weights: &TernaryTensor, inputs: &[i8], batch: usize) -> Vec<i32> {
+    let rows = weights.rows();
+    let cols = weights.cols();
+    let mut output = vec![0i32; batch * rows];
+
+    let packed = weights.packed_data();
+    let bytes_per_row = cols.div_ceil(4);
+
+    let ones_u8 = _mm256_set1_epi8(1);
+    let ones_16 = _mm256_set1_epi16(1);
+    let mut acc = vec![_mm256_setzero_si256(); batch];
+
+    for row in 0..rows {
+        let row_start = row * bytes_per_row;
+        let row_data = &packed[row_start..row_start + bytes_per_row];
+        acc.fill(_mm256_setzero_si256());
+        let mut col = 0;
+
+        while col + 32 <= cols {
+            let signs_v = unpack_32_ternary_simd(row_data[col / 4..].as_ptr());
+            for (b, acc_b) in acc.iter_mut().enumerate() {
+                let acts_v = _mm256_loadu_si256(inputs[b * cols + col..].as_ptr() as *const __m256i);
+                let products = _mm256_sign_epi8(acts_v, signs_v);
+                let sum_pairs = _mm256_maddubs_epi16(ones_u8, products);
+                let sum_quads = _mm256_madd_epi16(sum_pairs, ones_16);
+                *acc_b = _mm256_add_epi32(*acc_b, sum_quads);
+            }
+            col += 32;
+        }
+
+        for (b, &acc_b) in acc.iter().enumerate() {
+            let input = &inputs[b * cols..(b + 1) * cols];
+            let mut row_acc = hsum_epi32(acc_b);
+            for (c, &x) in input.iter().enumerate().skip(col) {
+                let bits = (row_data[c / 4] >> ((c % 4) * 2)) & 0b11;
+                row_acc += match bits {
+                    0b00 => -(x as i32),
+                    0b10 => x as i32,
+                    _ => 0,
+                };
+            }
+            output[b * rows + row] = row_acc;
+        }
+    }
+
+    output
+}
+
+/// Load 8 int8 values and widen them to 8 f32 lanes.
+///
+/// # Safety
+/// Requires AVX2; `ptr` must be valid for 8 bytes.
+#[target_feature(enable = "avx2")]
+#[inline]
+unsafe fn load_8_i8_as_ps(ptr: *const i8) -> __m256 {
+    let bytes = _mm_loadl_epi64(ptr as *const __m128i);
+    _mm256_cvtepi32_ps(_mm256_cvtepi8_epi32(bytes))
+}
+
+/// Dot product of f32 with block-scaled int8, 8 lanes at a time.
+/// Each block is summed unscaled, then multiplied by its scale once.
+///
+/// # Safety
+/// Requires AVX2; `x.len() == q.len()` and one scale per block.
+#[target_feature(enable = "avx2")]
+unsafe fn avx2_dot_q8(x: &[f32], q: &[i8], scales: &[f32], block: usize) -> f32 {
+    let mut total = 0.0f32;
+    for (b, &scale) in scales.iter().enumerate() {
+        let start = b * block;
+        let end = (start + block).min(q.len());
+        let mut acc = _mm256_setzero_ps();
+        let mut i = start;
+        while i + 8 <= end {
+            let qv = load_8_i8_as_ps(q.as_ptr().add(i));
+            let xv = _mm256_loadu_ps(x.as_ptr().add(i));
+            acc = _mm256_add_ps(acc, _mm256_mul_ps(xv, qv));
+            i += 8;
+        }
+        let mut sum = hsum_ps(acc);
+        while i < end {
+            sum += x[i] * q[i] as f32;
+            i += 1;
+        }
+        total += sum * scale;
+    }
+    total
+}
+
+/// `y += a * q` for block-scaled int8 `q`, 8 lanes at a time.
+///
+/// # Safety
+/// Requires AVX2; `y.len() == q.len()` and one scale per block.
+#[target_feature(enable = "avx2")]
+unsafe fn avx2_axpy_q8(y: &mut [f32], a: f32, q: &[i8], scales: &[f32], block: usize) {
+    for (b, &scale) in scales.iter().enumerate() {
+        let start = b * block;
+        let end = (start + block).min(q.len());
+        let f = a * scale;
+        let fv = _mm256_set1_ps(f);
+        let mut i = start;
+        while i + 8 <= end {
+            let qv = load_8_i8_as_ps(q.as_ptr().add(i));
+            let yp = y.as_mut_ptr().add(i);
+            _mm256_storeu_ps(yp, _mm256_add_ps(_mm256_loadu_ps(yp), _mm256_mul_ps(fv, qv)));
+            i += 8;
+        }
+        while i < end {
+            y[i] += f * q[i] as f32;
+            i += 1;
+        }
+    }
+}
+
+/// Horizontal sum of 8 packed f32 values in a __m256.
+///
+/// # Safety
+/// Requires AVX2.
+#[target_feature(enable = "avx2")]
+#[inline]
+unsafe fn hsum_ps(v: __m256) -> f32 {
+    let sum128 = _mm_add_ps(_mm256_castps256_ps128(v), _mm256_extractf128_ps(v, 1));
+    let sum64 = _mm_add_ps(sum128, _mm_movehl_ps(sum128, sum128));
+    let sum32 = _mm_add_ss(sum64, _mm_shuffle_ps(sum64, sum64, 0b01));
+    _mm_cvtss_f32(sum32)
+}
+
 /// Horizontal sum of 8 packed i32 values in a __m256i.
 ///
 /// # Safety
@@ -349,4 +508,48 @@ mod tests {
         let y_avx2 = Avx2Backend.ternary_matvec(&w, &input);
         assert_eq!(y_scalar, y_avx2);
     }
+
+    #[test]
+    fn avx2_matmul_matches_scalar() {
+        if !std::arch::is_x86_feature_detected!("avx2") {
+            return;
+        }
+        let scalar = super::super::scalar::ScalarBackend;
+        for (cols, batch) in [(32usize, 1usize), (64, 3), (100, 4), (256, 8)] {
+            let rows = 5;
+            let values: Vec<i8> = (0..rows * cols).map(|i| (i % 3) as i8 - 1).collect();
+            let w = weights_from_i8(&values, rows, cols);
+            let inputs: Vec<i8> = (0..batch * cols).map(|i| ((i * 31 % 254) as i32 - 127) as i8).collect();
+            assert_eq!(
+                Avx2Backend.ternary_matmul(&w, &inputs, batch),
+                scalar.ternary_matmul(&w, &inputs, batch),
+                "mismatch at cols={cols} batch={batch}"
+            );
+        }
+    }
+
+    #[test]
+    fn avx2_q8_kernels_match_scalar() {
+        if !std::arch::is_x86_feature_detected!("avx2") {
+            return;
+        }
+        let scalar = super::super::scalar::ScalarBackend;
+        for (n, block) in [(64usize, 32usize), (40, 32), (128, 32), (13, 8), (7, 32)] {
+            let x: Vec<f32> = (0..n).map(|i| ((i * 17 % 23) as f32 - 11.0) / 3.0).collect();
+            let q: Vec<i8> = (0..n).map(|i| ((i * 29 % 255) as i32 - 127) as i8).collect();
+            let scales: Vec<f32> = (0..n.div_ceil(block)).map(|b| 0.01 * (b + 1) as f32).collect();
+
+            let want = scalar.dot_q8(&x, &q, &scales, block);
+            let got = Avx2Backend.dot_q8(&x, &q, &scales, block);
+            assert!((want - got).abs() <= 1e-4 * want.abs().max(1.0), "dot n={n}: {want} vs {got}");
+
+            let mut y_scalar = x.clone();
+            let mut y_avx2 = x.clone();
+            scalar.axpy_q8(&mut y_scalar, 0.7, &q, &scales, block);
+            Avx2Backend.axpy_q8(&mut y_avx2, 0.7, &q, &scales, block);
+            for (a, b) in y_scalar.iter().zip(&y_avx2) {
+                assert!((a - b).abs() < 1e-5, "axpy n={n}: {a} vs {b}");
+            }
+        }
+    }
 }
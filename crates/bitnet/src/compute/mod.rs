@@ -38,6 +38,22 @@ pub trait ComputeBackend: Send + Sync + std::fmt::Debug {
     /// Returns: i32 accumulator per output feature.
     fn ternary_matvec(&self, weights: &TernaryTensor, input: &[i8]) -> Vec<i32>;
 
+    /// Ternary matrix-matrix product over a batch of activation vectors.
+    ///
+    /// `inputs`: `batch` quantized vectors of `in_features` each, row-major.
+    /// Returns: `[batch, out_features]` i32 accumulators, row-major.
+    /// Backends override this to unpack each weight row once per batch.
+    fn ternary_matmul(&self, weights: &TernaryTensor, inputs: &[i8], batch: usize) -> Vec<i32> {
+        assert_eq!(inputs.len(), batch * weights.cols(), "dimension mismatch");
+        if batch == 0 {
+            return Vec::new();
+        }
+        inputs
+            .chunks_exact(weights.cols())
+            .flat_map(|x| self.ternary_matvec(weights, x))
+            .collect()
+    }
+
     /// RMS normalization: x_i * (w_i / rms), where rms = sqrt(mean(x²) + eps).
     fn rmsnorm(&self, input: &[f32], weight: &[f32], eps: f32) -> Vec<f32> {
         let n = input.len();
@@ -60,6 +76,28 @@ pub trait ComputeBackend: Send + Sync + std::fmt::Debug {
     fn elementwise_mul(&self, a: &[f32], b: &[f32]) -> Vec<f32> {
         a.iter().zip(b).map(|(&x, &y)| x * y).collect()
     }
+
+    /// Dot product of f32 `x` with block-scaled int8 `q`, where element
+    /// `i` of `q` stands for `q[i] * scales[i / block]`. Used for
+    /// attention scores against an int8 KV cache.
+    fn dot_q8(&self, x: &[f32], q: &[i8], scales: &[f32], block: usize) -> f32 {
+        x.chunks(block)
+            .zip(q.chunks(block))
+            .zip(scales)
+            .map(|((x, q), &s)| s * x.iter().zip(q).map(|(&a, &b)| a * b as f32).sum::<f32>())
+            .sum()
+    }
+
+    /// `y += a * q` for block-scaled int8 `q` (see [`dot_q8`](Self::dot_q8)).
+    /// Used to accumulate attention output from an int8 KV cache.
+    fn axpy_q8(&self, y: &mut [f32], a: f32, q: &[i8], scales: &[f32], block: usize) {
+        for ((y, q), &s) in y.chunks_mut(block).zip(q.chunks(block)).zip(scales) {
+            let f = a * s;
+            for (y, &q) in y.iter_mut().zip(q) {
+                *y += f * q as f32;
+            }
+        }
+    }
 }
 
 /// Capability flags detected at runtime.
@@ -150,6 +188,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn default_q8_kernels() {
+        let backend = scalar::ScalarBackend;
+        let x = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let q = [1i8, -1, 2, 0, 3];
+        // Blocks of 2: [1,-1]·0.5, [2,0]·2.0, [3]·1.0
+        let scales = [0.5, 2.0, 1.0];
+        let dot = backend.dot_q8(&x, &q, &scales, 2);
+        assert!((dot - (0.5 * (1.0 - 2.0) + 2.0 * 6.0 + 15.0)).abs() < 1e-6);
+
+        let mut y = [1.0; 4];
+        backend.axpy_q8(&mut y, 2.0, &q[..4], &scales[..2], 2);
+        assert_eq!(y, [2.0, 0.0, 9.0, 1.0]);
+    }
+
     #[test]
     fn default_softmax() {
         let backend = scalar::ScalarBackend;
@@ -3,7 +3,7 @@
 //! Wraps the existing I2S kernel — no SIMD, works on any platform.
 
 use crate::tensor::TernaryTensor;
-use crate::ops::matmul::ternary_matvec as scalar_ternary_matvec;
+use crate::ops::matmul::{ternary_matmul as scalar_ternary_matmul, ternary_matvec as scalar_ternary_matvec};
 use super::ComputeBackend;
 
 /// Portable scalar backend — the baseline.
@@ -16,6 +16,10 @@ impl ComputeBackend for ScalarBackend {
     fn ternary_matvec(&self, weights: &TernaryTensor, input: &[i8]) -> Vec<i32> {
         scalar_ternary_matvec(weights, input)
     }
+
+    fn ternary_matmul(&self, weights: &TernaryTensor, inputs: &[i8], batch: usize) -> Vec<i32> {
+        scalar_ternary_matmul(weights, inputs, batch)
+    }
 }
 
 #[cfg(test)]
@@ -41,4 +45,13 @@ mod tests {
         let y = backend.ternary_matvec(&w, &x);
         assert_eq!(y, vec![30]); // 10 - 20 + 0 + 40
     }
+
+    #[test]
+    fn scalar_matmul_batch() {
+        let backend = ScalarBackend;
+        let w = weights_from_i8(&[1, -1, 0, 1, 0, 1, 1, -1], 2, 4);
+        let x = vec![10i8, 20, 30, 40, -1, -2, -3, -4];
+        let y = backend.ternary_matmul(&w, &x, 2);
+        assert_eq!(y, vec![30, 10, -3, -1]);
+    }
 }
@@ -0,0 +1,160 @@
+//! Hidden-state embeddings from the base model (`embedding` feature).
+//!
+//! [`ModelEmbedder`] runs text through a [`TransformerModel`] and pools the
+//! hidden states of a chosen layer into one unit vector, implementing
+//! [`EmbeddingProvider`] so routing, code search and shim dataset
+//! preparation can use the same model that serves generation, with no
+//! cortex round trip.
+
+use std::sync::Arc;
+
+use agentos_embedding::{Embedding, EmbeddingProvider};
+
+use crate::layers::model::{HiddenLayer, Pooling, TransformerModel};
+use crate::tokenizer::Tokenizer;
+
+/// Default cap on tokens per input; longer text is truncated.
+pub const DEFAULT_MAX_TOKENS: usize = 512;
+
+/// An [`EmbeddingProvider`] backed by a transformer's hidden states.
+pub struct ModelEmbedder {
+    model: Arc<TransformerModel>,
+    tokenizer: Arc<Tokenizer>,
+    name: String,
+    layer: HiddenLayer,
+    pooling: Pooling,
+    max_tokens: usize,
+}
+
+impl ModelEmbedder {
+    /// Embed with the final hidden state and mean pooling. `name`
+    /// identifies the model weights in [`model_id`](EmbeddingProvider::model_id).
+    pub fn new(model: Arc<TransformerModel>, tokenizer: Arc<Tokenizer>, name: impl Into<String>) -> Self {
+        Self {
+            model,
+            tokenizer,
+            name: name.into(),
+            layer: HiddenLayer::Final,
+            pooling: Pooling::Mean,
+            max_tokens: DEFAULT_MAX_TOKENS,
+        }
+    }
+
+    /// Read hidden states from `layer` instead of the final one.
+    pub fn with_layer(mut self, layer: HiddenLayer) -> Self {
+        if let HiddenLayer::Block(n) = layer {
+            assert!(n < self.model.n_layers(), "block {n} out of range ({} blocks)", self.model.n_layers());
+        }
+        self.layer = layer;
+        self
+    }
+
+    /// Pool with `pooling` instead of the mean.
+    pub fn with_pooling(mut self, pooling: Pooling) -> Self {
+        self.pooling = pooling;
+        self
+    }
+
+    /// Cap the tokens per input (including BOS).
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+        assert!(max_tokens >= 2, "max_tokens must leave room for BOS and one token");
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    /// The layer hidden states are read from.
+    pub fn layer(&self) -> HiddenLayer { self.layer }
+
+    /// The pooling strategy.
+    pub fn pooling(&self) -> Pooling { self.pooling }
+
+    /// Tokens to run for `text`: BOS plus the text, truncated to
+    /// `max_tokens`. Mean pooling keeps the head of long inputs; last-token
+    /// pooling keeps the tail, since that is the position it reads.
+    fn tokens(&self, text: &str) -> Vec<u32> {
+        let mut tokens = self.tokenizer.encode(text, true);
+        if tokens.len() > self.max_tokens {
+            match self.pooling {
+                Pooling::Mean => tokens.truncate(self.max_tokens),
+                Pooling::LastToken => {
+                    let cut = tokens.len() - (self.max_tokens - 1);
+                    tokens.drain(1..cut);
+                }
+            }
+        }
+        tokens
+    }
+}
+
+impl EmbeddingProvider for ModelEmbedder {
+    fn embed(&self, text: &str) -> Embedding {
+        let dim = self.model.embed_dim();
+        let tokens = self.tokens(text);
+        if tokens.len() < 2 {
+            return vec![0.0; dim];
+        }
+        let hidden = self.model.hidden_states(&tokens, self.layer);
+        self.pooling.sentence_vector(&hidden, dim)
+    }
+
+    fn dimensions(&self) -> usize {
+        self.model.embed_dim()
+    }
+
+    fn model_id(&self) -> String {
+        let layer = match self.layer {
+            HiddenLayer::Final => "final".to_string(),
+            HiddenLayer::Block(n) => format!("block{n}"),
+        };
+        format!("bitnet:{}:{layer}:{}:{}", self.name, self.pooling, self.model.embed_dim())
+    }
+}
+
+impl std::fmt::Debug for ModelEmbedder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ModelEmbedder")
+            .field("name", &self.name)
+            .field("layer", &self.layer)
+            .field("pooling", &self.pooling)
+            .field("max_tokens", &self.max_tokens)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::generation::tests::make_tokenizer;
+    use crate::layers::model::tests::make_test_model;
+
+    fn embedder() -> ModelEmbedder {
+        ModelEmbedder::new(Arc::new(make_test_model(2, false)), Arc::new(make_tokenizer()), "test")
+    }
+
+    #[test]
+    fn embeds_unit_vectors() {
+        let e = embedder();
+        assert_eq!(e.dimensions(), 8);
+        assert_eq!(e.model_id(), "bitnet:test:final:mean:8");
+
+        let a = e.embed("ab");
+        let norm: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5);
+        assert_eq!(a, e.embed("ab"));
+        assert_ne!(a, e.embed("cd"));
+        assert_eq!(e.embed(""), vec![0.0; 8]);
+    }
+
+    #[test]
+    fn last_token_truncation_keeps_tail() {
+        let e = embedder()
+            .with_layer(HiddenLayer::Block(0))
+            .with_pooling(Pooling::LastToken)
+            .with_max_tokens(3);
+        assert_eq!(e.model_id(), "bitnet:test:block0:last:8");
+        let long = e.tokenizer.encode("abcd", true);
+        assert!(long.len() > 3);
+        let tokens = e.tokens("abcd");
+        assert_eq!(tokens, [&long[..1], &long[long.len() - 2..]].concat());
+    }
+}
@@ -2,18 +2,27 @@
 //!
 //! Implements the GGUF v3 format as used by llama.cpp and BitNet.cpp.
 //! Supports ternary tensor types TQ1_0 and TQ2_0, plus F32/F16/BF16 for
-//! non-quantized layers (embeddings, norms, output heads).
+//! non-quantized layers (embeddings, norms, output heads) and the GGML
+//! block quantizations Q8_0, Q4_0, Q4_K and Q6_K used by standard
+//! LLaMA/Qwen2 models (see [`crate::ops::dequant`]).
 //!
-//! No mmap, no unsafe, no external ML deps — just std + thiserror.
+//! No unsafe, no external ML deps — just std + thiserror. With the `mmap`
+//! feature, [`GgufFile::open_mmap`] maps the file (via [`crate::mmap`]) and
+//! block-quantized tensors load as zero-copy views of the mapping.
 
 use std::collections::HashMap;
 use std::io::{Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
+#[cfg(feature = "mmap")]
+use std::sync::Arc;
 
 use thiserror::Error;
 use tracing::{debug, info};
 
-use crate::tensor::{FloatTensor, Ternary, TernaryTensor};
+use crate::ops::dequant;
+#[cfg(feature = "mmap")]
+use crate::mmap::MappedFile;
+use crate::tensor::{FloatTensor, QuantTensor, Ternary, TernaryTensor, WeightBytes};
 
 // ---------------------------------------------------------------------------
 // Constants
@@ -65,6 +74,9 @@ pub enum GgufError {
     #[error("tensor shape mismatch: expected {expected} elements, got {actual}")]
     TensorShapeMismatch { expected: u64, actual: u64 },
 
+    #[error("tensor '{0}' is not a 2D matrix of whole quantization blocks")]
+    InvalidTensorShape(String),
+
     #[error("missing metadata key: {0}")]
     MissingMetadata(String),
 
@@ -89,6 +101,16 @@ pub type Result<T> = std::result::Result<T, GgufError>;
 pub enum GgmlType {
     F32 = 0,
     F16 = 1,
+    /// 32-weight blocks: f16 scale + 32 × 4-bit.
+    Q4_0 = 2,
+    /// 32-weight blocks: f16 scale + 32 × i8.
+    Q8_0 = 8,
+    /// 256-weight super-blocks with 6-bit sub-block scales and mins.
+    #[allow(non_camel_case_types)] // ggml's name
+    Q4_K = 12,
+    /// 256-weight super-blocks of 6-bit values with i8 sub-block scales.
+    #[allow(non_camel_case_types)]
+    Q6_K = 14,
     BF16 = 30,
     TQ1_0 = 34,
     TQ2_0 = 35,
@@ -103,6 +125,10 @@ impl TryFrom<u32> for GgmlType {
         match v {
             0 => Ok(GgmlType::F32),
             1 => Ok(GgmlType::F16),
+            2 => Ok(GgmlType::Q4_0),
+            8 => Ok(GgmlType::Q8_0),
+            12 => Ok(GgmlType::Q4_K),
+            14 => Ok(GgmlType::Q6_K),
             30 => Ok(GgmlType::BF16),
             34 => Ok(GgmlType::TQ1_0),
             35 => Ok(GgmlType::TQ2_0),
@@ -355,7 +381,7 @@ impl<R: Read + Seek> GgufReader<R> {
 /// Convert an IEEE 754 half-precision (f16) value to f32.
 ///
 /// Layout: 1 sign | 5 exponent | 10 mantissa
-fn f16_to_f32(bits: u16) -> f32 {
+pub(crate) fn f16_to_f32(bits: u16) -> f32 {
     let sign = ((bits >> 15) & 1) as u32;
     let exp = ((bits >> 10) & 0x1F) as u32;
     let mant = (bits & 0x3FF) as u32;
@@ -383,7 +409,7 @@ fn f16_to_f32(bits: u16) -> f32 {
 }
 
 /// Convert a BF16 value to f32. BF16 is just the upper 16 bits of f32.
-fn bf16_to_f32(bits: u16) -> f32 {
+pub(crate) fn bf16_to_f32(bits: u16) -> f32 {
     f32::from_bits((bits as u32) << 16)
 }
 
@@ -640,7 +666,7 @@ fn unpack_tq1_0(data: &[u8], n_elements: u64) -> (TernaryTensor, f32) {
 // Float tensor loading
 // ---------------------------------------------------------------------------
 
-/// Load f32/f16/bf16 tensor data and convert to f32 vec.
+/// Load f32/f16/bf16 (or block-quantized) tensor data and convert to f32 vec.
 fn load_float_data(data: &[u8], ggml_type: GgmlType, n_elements: u64) -> Vec<f32> {
     let n = n_elements as usize;
     match ggml_type {
@@ -671,6 +697,11 @@ fn load_float_data(data: &[u8], ggml_type: GgmlType, n_elements: u64) -> Vec<f32
             }
             out
         }
+        GgmlType::Q8_0 | GgmlType::Q4_0 | GgmlType::Q4_K | GgmlType::Q6_K => {
+            let mut out = vec![0.0f32; n];
+            dequant::dequantize(ggml_type, data, &mut out);
+            out
+        }
         _ => panic!("load_float_data called with non-float type: {:?}", ggml_type),
     }
 }
@@ -687,6 +718,9 @@ pub struct GgufFile {
     tensor_data_offset: u64,
     alignment: usize,
     path: PathBuf,
+    /// The whole file, when opened with [`GgufFile::open_mmap`].
+    #[cfg(feature = "mmap")]
+    mapped: Option<Arc<MappedFile>>,
 }
 
 impl GgufFile {
@@ -791,9 +825,55 @@ impl GgufFile {
             tensor_data_offset,
             alignment,
             path: PathBuf::new(),
+            #[cfg(feature = "mmap")]
+            mapped: None,
         })
     }
 
+    /// Open a GGUF file by memory-mapping it.
+    ///
+    /// Tensor data is never read up front: block-quantized and float
+    /// weights loaded through [`Self::load_quant`] are views of the mapping,
+    /// and ternary tensors are repacked straight from it (none of the GGML
+    /// ternary packings match the kernel layout), so there is no second
+    /// raw copy of the file in memory.
+    #[cfg(feature = "mmap")]
+    pub fn open_mmap<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = Arc::new(MappedFile::open(&path)?);
+        let mut gguf = Self::open_reader(std::io::Cursor::new(file.bytes()))?;
+
+        // Reject truncated files here rather than panicking on a view later.
+        for info in gguf.tensors.values() {
+            let (offset, len) = gguf.tensor_range(info);
+            if offset + len > file.len() {
+                return Err(GgufError::Io(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    format!("tensor '{}' extends past the end of the file", info.name),
+                )));
+            }
+        }
+
+        info!(path = %path.display(), bytes = file.len(), "GGUF file memory-mapped");
+        gguf.path = path;
+        gguf.mapped = Some(file);
+        Ok(gguf)
+    }
+
+    /// Whether this file was opened with [`Self::open_mmap`].
+    pub fn is_mapped(&self) -> bool {
+        self.mapped_bytes() > 0
+    }
+
+    /// Size of the memory mapping in bytes (0 when not mapped).
+    pub fn mapped_bytes(&self) -> usize {
+        #[cfg(feature = "mmap")]
+        if let Some(file) = &self.mapped {
+            return file.len();
+        }
+        0
+    }
+
     /// All metadata key-value pairs.
     pub fn metadata(&self) -> &HashMap<String, MetadataValue> {
         &self.metadata
@@ -864,7 +944,16 @@ impl GgufFile {
             .map(|s| s.to_string());
 
         Ok(ModelConfig {
-            vocab_size: get_u32("vocab_size")?,
+            // llama.cpp omits `{arch}.vocab_size` for most converted models;
+            // fall back to the tokenizer's vocabulary length.
+            vocab_size: match get_u32("vocab_size") {
+                Err(GgufError::MissingMetadata(_)) => self
+                    .get_metadata("tokenizer.ggml.tokens")
+                    .and_then(|v| v.as_array())
+                    .map(|tokens| tokens.len() as u32)
+                    .ok_or_else(|| GgufError::MissingMetadata(format!("{arch}.vocab_size")))?,
+                other => other?,
+            },
             embedding_dim: get_u32("embedding_length")?,
             n_layers: get_u32("block_count")?,
             n_heads: get_u32("attention.head_count")?,
@@ -889,6 +978,14 @@ impl GgufFile {
         self.tensors.get(name)
     }
 
+    /// Total size in bytes of all tensor data.
+    pub fn tensor_data_bytes(&self) -> u64 {
+        self.tensors
+            .values()
+            .map(|t| tensor_byte_size(t.ggml_type, t.n_elements, self.alignment) as u64)
+            .sum()
+    }
+
     /// Load a ternary tensor (TQ1_0 or TQ2_0) by name.
     ///
     /// Returns `(TernaryTensor, weight_scale)` ready for `BitLinear::new()`.
@@ -899,7 +996,7 @@ impl GgufFile {
             .get(name)
             .ok_or_else(|| GgufError::MissingMetadata(name.to_string()))?;
 
-        let data = self.read_tensor_data(info)?;
+        let data = self.tensor_bytes(info)?;
 
         let (mut tensor, scale) = match info.ggml_type {
             GgmlType::TQ2_0 => unpack_tq2_0(&data, info.n_elements),
@@ -931,14 +1028,15 @@ impl GgufFile {
         Ok((tensor, scale))
     }
 
-    /// Load a float tensor (F32, F16, or BF16) by name.
+    /// Load a float tensor (F32, F16, or BF16) by name. Block-quantized
+    /// tensors (Q8_0, Q4_0, Q4_K, Q6_K) are dequantized.
     pub fn load_float(&self, name: &str) -> Result<FloatTensor> {
         let info = self
             .tensors
             .get(name)
             .ok_or_else(|| GgufError::MissingMetadata(name.to_string()))?;
 
-        let data = self.read_tensor_data(info)?;
+        let data = self.tensor_bytes(info)?;
         let float_data = load_float_data(&data, info.ggml_type, info.n_elements);
 
         debug!(
@@ -951,6 +1049,19 @@ impl GgufFile {
         Ok(FloatTensor::new(float_data, info.shape.clone()))
     }
 
+    /// Load a 2D weight matrix by name, keeping its storage format.
+    ///
+    /// Accepts F32/F16/BF16 and the block quantizations Q8_0, Q4_0, Q4_K
+    /// and Q6_K; ternary types must go through [`Self::load_ternary`].
+    pub fn load_quant(&self, name: &str) -> Result<QuantTensor> {
+        let info = self
+            .tensors
+            .get(name)
+            .ok_or_else(|| GgufError::MissingMetadata(name.to_string()))?;
+        let data = self.tensor_bytes(info)?;
+        quant_tensor(info, data)
+    }
+
     /// Load the first `n` raw bytes of a tensor (for diagnostics).
     pub fn load_raw_bytes(&self, name: &str, n: usize) -> Result<Vec<u8>> {
         let info = self
@@ -970,13 +1081,33 @@ impl GgufFile {
         Ok(buf)
     }
 
+    /// Absolute byte range `(offset, len)` of a tensor's data in the file.
+    fn tensor_range(&self, info: &TensorInfo) -> (usize, usize) {
+        let offset = (self.tensor_data_offset + info.offset) as usize;
+        (offset, tensor_byte_size(info.ggml_type, info.n_elements, self.alignment))
+    }
+
+    /// A tensor's raw bytes: a view of the mapping when mapped, otherwise
+    /// read from the file.
+    fn tensor_bytes(&self, info: &TensorInfo) -> Result<WeightBytes> {
+        #[cfg(feature = "mmap")]
+        if let Some(file) = &self.mapped {
+            let (offset, len) = self.tensor_range(info);
+            return Ok(WeightBytes::Mapped { file: file.clone(), offset, len });
+        }
+        self.read_tensor_data(info).map(WeightBytes::Owned)
+    }
+
     /// Read raw tensor bytes from the file.
     fn read_tensor_data(&self, info: &TensorInfo) -> Result<Vec<u8>> {
-        let byte_size = tensor_byte_size(info.ggml_type, info.n_elements, self.alignment);
+        let (abs_offset, byte_size) = self.tensor_range(info);
+        #[cfg(feature = "mmap")]
+        if let Some(file) = &self.mapped {
+            return Ok(file.bytes()[abs_offset..abs_offset + byte_size].to_vec());
+        }
 
-        let abs_offset = self.tensor_data_offset + info.offset;
         let mut file = std::fs::File::open(&self.path)?;
-        file.seek(SeekFrom::Start(abs_offset))?;
+        file.seek(SeekFrom::Start(abs_offset as u64))?;
 
         let mut buf = vec![0u8; byte_size];
         file.read_exact(&mut buf)?;
@@ -1045,6 +1176,42 @@ impl GgufFile {
         let float_data = load_float_data(&buf, info.ggml_type, info.n_elements);
         Ok(FloatTensor::new(float_data, info.shape.clone()))
     }
+
+    /// Load a quantized weight matrix from an in-memory reader source.
+    pub fn load_quant_from_reader<R: Read + Seek>(
+        &self,
+        name: &str,
+        mut reader: R,
+    ) -> Result<QuantTensor> {
+        let info = self
+            .tensors
+            .get(name)
+            .ok_or_else(|| GgufError::MissingMetadata(name.to_string()))?;
+
+        let byte_size = tensor_byte_size(info.ggml_type, info.n_elements, self.alignment);
+        let abs_offset = self.tensor_data_offset + info.offset;
+        reader.seek(SeekFrom::Start(abs_offset))?;
+
+        let mut buf = vec![0u8; byte_size];
+        reader.read_exact(&mut buf)?;
+        quant_tensor(info, buf)
+    }
+}
+
+/// Wrap raw tensor bytes as a [`QuantTensor`], checking type and shape.
+fn quant_tensor(info: &TensorInfo, data: impl Into<WeightBytes>) -> Result<QuantTensor> {
+    let Some((block_elements, _)) = dequant::block_layout(info.ggml_type) else {
+        return Err(GgufError::UnsupportedTensorType(info.ggml_type as u32));
+    };
+    let (rows, cols) = match info.shape[..] {
+        [rows, cols] => (rows, cols),
+        [cols] => (1, cols),
+        _ => return Err(GgufError::InvalidTensorShape(info.name.clone())),
+    };
+    if cols % block_elements != 0 {
+        return Err(GgufError::InvalidTensorShape(info.name.clone()));
+    }
+    Ok(QuantTensor::new(info.ggml_type, data, rows, cols))
 }
 
 impl std::fmt::Debug for GgufFile {
@@ -1087,6 +1254,11 @@ fn tensor_byte_size(ggml_type: GgmlType, n_elements: u64, _alignment: usize) ->
             // packed 2-bit data + 4-byte float scale
             n.div_ceil(4) + 4
         }
+        GgmlType::Q8_0 | GgmlType::Q4_0 | GgmlType::Q4_K | GgmlType::Q6_K => {
+            let (block_elements, block_bytes) =
+                dequant::block_layout(ggml_type).expect("block-quantized type");
+            n.div_ceil(block_elements) * block_bytes
+        }
     }
 }
 
@@ -1146,6 +1318,17 @@ mod tests {
             self.metadata.push((key.to_string(), 9, encoded));
         }
 
+        fn add_metadata_array_string(&mut self, key: &str, vals: &[&str]) {
+            let mut encoded = Vec::new();
+            encoded.extend_from_slice(&8u32.to_le_bytes()); // elem type = string
+            encoded.extend_from_slice(&(vals.len() as u64).to_le_bytes());
+            for v in vals {
+                encoded.extend_from_slice(&(v.len() as u64).to_le_bytes());
+                encoded.extend_from_slice(v.as_bytes());
+            }
+            self.metadata.push((key.to_string(), 9, encoded));
+        }
+
         fn add_tensor(&mut self, name: &str, shape: &[u64], type_code: u32, data: Vec<u8>) {
             // Shape stored innermost-first in GGUF, so reverse our outermost-first
             let mut gguf_shape = shape.to_vec();
@@ -1510,6 +1693,10 @@ mod tests {
         assert_eq!(GgmlType::try_from(30).unwrap(), GgmlType::BF16);
         assert_eq!(GgmlType::try_from(34).unwrap(), GgmlType::TQ1_0);
         assert_eq!(GgmlType::try_from(35).unwrap(), GgmlType::TQ2_0);
+        assert_eq!(GgmlType::try_from(2).unwrap(), GgmlType::Q4_0);
+        assert_eq!(GgmlType::try_from(8).unwrap(), GgmlType::Q8_0);
+        assert_eq!(GgmlType::try_from(12).unwrap(), GgmlType::Q4_K);
+        assert_eq!(GgmlType::try_from(14).unwrap(), GgmlType::Q6_K);
         assert!(GgmlType::try_from(99).is_err());
     }
 
@@ -1550,6 +1737,25 @@ mod tests {
         assert!(matches!(err, GgufError::MissingMetadata(_)));
     }
 
+    #[test]
+    fn model_config_vocab_from_tokenizer() {
+        let mut b = GgufBuilder::new();
+        b.add_metadata_string("general.architecture", "qwen2");
+        b.add_metadata_u32("qwen2.embedding_length", 64);
+        b.add_metadata_u32("qwen2.block_count", 2);
+        b.add_metadata_u32("qwen2.attention.head_count", 4);
+        b.add_metadata_u32("qwen2.attention.head_count_kv", 2);
+        b.add_metadata_u32("qwen2.context_length", 512);
+        b.add_metadata_u32("qwen2.feed_forward_length", 128);
+        b.add_metadata_f32("qwen2.rope.freq_base", 1_000_000.0);
+        b.add_metadata_f32("qwen2.attention.layer_norm_rms_epsilon", 1e-6);
+        b.add_metadata_array_string("tokenizer.ggml.tokens", &["a", "b", "c"]);
+        let data = b.build();
+
+        let gguf = GgufFile::open_reader(Cursor::new(data)).unwrap();
+        assert_eq!(gguf.model_config().unwrap().vocab_size, 3);
+    }
+
     // -- End-to-end: synthetic GGUF with real tensor data --
 
     fn make_f16_bytes(val: f32) -> [u8; 2] {
@@ -1606,6 +1812,78 @@ mod tests {
         assert!((ft.data()[1] - -1.0).abs() < f32::EPSILON);
     }
 
+    #[test]
+    fn end_to_end_q8_0_tensor() {
+        let values: Vec<f32> = (0..2 * 64).map(|i| (i as f32 * 0.37).sin()).collect();
+        let mut b = GgufBuilder::new();
+        b.add_tensor("blk.0.attn_q.weight", &[2, 64], GgmlType::Q8_0 as u32, dequant::quantize_q8_0(&values));
+        let data = b.build();
+
+        let gguf = GgufFile::open_reader(Cursor::new(data.clone())).unwrap();
+        assert_eq!(gguf.tensor_data_bytes(), 4 * 34);
+        let qt = gguf
+            .load_quant_from_reader("blk.0.attn_q.weight", Cursor::new(data.clone()))
+            .unwrap();
+        assert_eq!((qt.ggml_type(), qt.rows(), qt.cols()), (GgmlType::Q8_0, 2, 64));
+
+        // load_float dequantizes to the same values
+        let ft = gguf
+            .load_float_from_reader("blk.0.attn_q.weight", Cursor::new(data))
+            .unwrap();
+        assert_eq!(ft.shape(), &[2, 64]);
+        assert_eq!(ft.data(), &qt.dequantize()[..]);
+        for (a, b) in values.iter().zip(ft.data()) {
+            assert!((a - b).abs() < 1.0 / 127.0);
+        }
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mmap_quant_tensors_are_views() {
+        use std::io::Write;
+
+        let values: Vec<f32> = (0..2 * 64).map(|i| (i as f32 * 0.21).cos()).collect();
+        let mut b = GgufBuilder::new();
+        b.add_tensor("w", &[2, 64], GgmlType::Q8_0 as u32, dequant::quantize_q8_0(&values));
+        b.add_tensor("norm", &[2], GgmlType::F32 as u32, [1.5f32, -2.0].iter().flat_map(|v| v.to_le_bytes()).collect());
+        let data = b.build();
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&data).unwrap();
+
+        let gguf = GgufFile::open_mmap(file.path()).unwrap();
+        assert!(gguf.is_mapped());
+        assert_eq!(gguf.mapped_bytes(), data.len());
+
+        let mapped = gguf.load_quant("w").unwrap();
+        assert!(mapped.is_mapped());
+        let owned = gguf.load_quant_from_reader("w", Cursor::new(data.clone())).unwrap();
+        assert!(!owned.is_mapped());
+        assert_eq!(mapped.dequantize(), owned.dequantize());
+        assert_eq!(gguf.load_float("norm").unwrap().data(), &[1.5, -2.0]);
+
+        // A file cut short inside a tensor is rejected at open time.
+        let info = gguf.tensor_info("norm").unwrap();
+        let norm_end = gguf.tensor_range(info).0 + 8;
+        let mut short = tempfile::NamedTempFile::new().unwrap();
+        short.write_all(&data[..norm_end - 4]).unwrap();
+        assert!(matches!(GgufFile::open_mmap(short.path()), Err(GgufError::Io(_))));
+    }
+
+    #[test]
+    fn load_quant_rejects_ternary_and_partial_blocks() {
+        let mut b = GgufBuilder::new();
+        b.add_tensor("t", &[256], GgmlType::TQ2_0 as u32, vec![0u8; TQ2_BLOCK_BYTES]);
+        b.add_tensor("f", &[2, 3], GgmlType::F32 as u32, vec![0u8; 24]);
+        let data = b.build();
+
+        let gguf = GgufFile::open_reader(Cursor::new(data.clone())).unwrap();
+        assert!(matches!(
+            gguf.load_quant_from_reader("t", Cursor::new(data.clone())),
+            Err(GgufError::UnsupportedTensorType(35))
+        ));
+        assert_eq!(gguf.load_quant_from_reader("f", Cursor::new(data)).unwrap().cols(), 3);
+    }
+
     #[test]
     fn end_to_end_tq2_tensor() {
         let mut b = GgufBuilder::new();
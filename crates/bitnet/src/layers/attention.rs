@@ -1,4 +1,4 @@
-//! Multi-Head Attention with ternary Q/K/V projections.
+//! Multi-Head Attention with ternary (or standard) Q/K/V projections.
 //!
 //! Implements Grouped Query Attention (GQA) as used in LLaMA 2/3 and
 //! BitNet b1.58. Q has `n_heads` heads while K/V share `n_kv_heads`
@@ -6,28 +6,31 @@
 //! compute while preserving model quality.
 //!
 //! Forward pass for a sequence of length `seq_len`:
-//!   1. Project input through ternary Q, K, V, O projections (BitLinear)
+//!   1. Project input through Q, K, V, O projections (BitLinear, or
+//!      DenseLinear for standard models)
 //!   2. Apply RoPE to Q and K
 //!   3. Reshape into heads
 //!   4. Expand KV heads to match Q head count (GQA repeat)
 //!   5. Scaled dot-product attention with causal mask
 //!   6. Concatenate heads and project through O
 
-use crate::layers::bitlinear::BitLinear;
-use crate::layers::kv_cache::KvCache;
+use crate::compute::scalar::ScalarBackend;
+use crate::compute::ComputeBackend;
+use crate::layers::linear::Linear;
+use crate::layers::kv_cache::{KvCache, KvRow, KV_BLOCK};
 use crate::layers::rmsnorm::RmsNorm;
 use crate::layers::rope::{RoPE, RoPELayout};
 
 /// Multi-head attention with ternary weight projections.
 pub struct MultiHeadAttention {
     /// Query projection (embed_dim → n_heads * head_dim).
-    q_proj: BitLinear,
+    q_proj: Linear,
     /// Key projection (embed_dim → n_kv_heads * head_dim).
-    k_proj: BitLinear,
+    k_proj: Linear,
     /// Value projection (embed_dim → n_kv_heads * head_dim).
-    v_proj: BitLinear,
+    v_proj: Linear,
     /// Output projection (n_heads * head_dim → embed_dim).
-    o_proj: BitLinear,
+    o_proj: Linear,
     /// Rotary position embeddings for Q and K.
     rope: RoPE,
     /// Optional sub-normalization applied to concatenated heads before O projection.
@@ -51,10 +54,10 @@ impl MultiHeadAttention {
     /// - `rope_base`: frequency base for RoPE (typically 10000.0)
     #[allow(clippy::too_many_arguments)]
     pub fn new(
-        q_proj: BitLinear,
-        k_proj: BitLinear,
-        v_proj: BitLinear,
-        o_proj: BitLinear,
+        q_proj: impl Into<Linear>,
+        k_proj: impl Into<Linear>,
+        v_proj: impl Into<Linear>,
+        o_proj: impl Into<Linear>,
         n_heads: usize,
         n_kv_heads: usize,
         head_dim: usize,
@@ -70,16 +73,17 @@ impl MultiHeadAttention {
     /// Create a multi-head attention layer with explicit RoPE layout.
     #[allow(clippy::too_many_arguments)]
     pub fn with_rope_layout(
-        q_proj: BitLinear,
-        k_proj: BitLinear,
-        v_proj: BitLinear,
-        o_proj: BitLinear,
+        q_proj: impl Into<Linear>,
+        k_proj: impl Into<Linear>,
+        v_proj: impl Into<Linear>,
+        o_proj: impl Into<Linear>,
         n_heads: usize,
         n_kv_heads: usize,
         head_dim: usize,
         rope_base: f32,
         rope_layout: RoPELayout,
     ) -> Self {
+        let (q_proj, k_proj, v_proj, o_proj) = (q_proj.into(), k_proj.into(), v_proj.into(), o_proj.into());
         assert!(n_heads > 0 && n_kv_heads > 0, "head counts must be positive");
         assert!(
             n_heads.is_multiple_of(n_kv_heads),
@@ -272,43 +276,86 @@ impl MultiHeadAttention {
         let kv_dim = self.n_kv_heads * self.head_dim;
         let start_pos = cache.len();
 
-        // 1. Project new tokens through Q, K, V
-        let mut q_all = Vec::with_capacity(seq_len * q_dim);
-        let mut k_new = Vec::with_capacity(seq_len * kv_dim);
-        let mut v_new = Vec::with_capacity(seq_len * kv_dim);
-
-        for t in 0..seq_len {
-            let token = &input[t * embed_dim..(t + 1) * embed_dim];
-            q_all.extend_from_slice(&self.q_proj.forward(token));
-            k_new.extend_from_slice(&self.k_proj.forward(token));
-            v_new.extend_from_slice(&self.v_proj.forward(token));
-        }
+        // 1. Project new tokens through Q, K, V (one ternary matmul each)
+        let mut q_all = self.q_proj.forward_rows(input, seq_len);
+        let mut k_new = self.k_proj.forward_rows(input, seq_len);
+        let v_new = self.v_proj.forward_rows(input, seq_len);
 
         // 2. Apply RoPE to Q and new K
         for t in 0..seq_len {
             let pos = start_pos + t;
-            for h in 0..self.n_heads {
-                let offset = t * q_dim + h * self.head_dim;
-                let slice = q_all[offset..offset + self.head_dim].to_vec();
-                let rotated = self.rope.forward(&slice, pos);
-                q_all[offset..offset + self.head_dim].copy_from_slice(&rotated);
-            }
-            for h in 0..self.n_kv_heads {
-                let offset = t * kv_dim + h * self.head_dim;
-                let slice = k_new[offset..offset + self.head_dim].to_vec();
-                let rotated = self.rope.forward(&slice, pos);
-                k_new[offset..offset + self.head_dim].copy_from_slice(&rotated);
-            }
+            self.rotate_heads(&mut q_all[t * q_dim..(t + 1) * q_dim], pos);
+            self.rotate_heads(&mut k_new[t * kv_dim..(t + 1) * kv_dim], pos);
         }
 
         // 3. Append new K/V to cache (already RoPE'd)
         cache.append(&k_new, &v_new);
 
         // 4. Compute attention: Q attends to full cached K/V
-        let total_seq = cache.len(); // all positions including new ones
         let mut output = vec![0.0f32; seq_len * q_dim];
+        self.attend(&q_all, seq_len, cache, &mut output);
+
+        // 5. Output projection (with optional sub-norm before O)
+        self.project_out(output, seq_len)
+    }
+
+    /// Forward pass for one new token in each of several independent
+    /// sequences — the batched decode step.
+    ///
+    /// Row `b` of `input` is the next token of the sequence cached in
+    /// `caches[b]`, at position `caches[b].len()`. The Q/K/V/O projections
+    /// run as one ternary matmul over the whole batch; attention runs per
+    /// sequence against its own cache.
+    ///
+    /// `input`: flat f32 of shape `[caches.len(), embed_dim]`.
+    /// Returns: flat f32 vec of shape `[caches.len(), embed_dim]`.
+    pub fn forward_batch_cached(&self, input: &[f32], caches: &mut [&mut KvCache]) -> Vec<f32> {
+        let batch = caches.len();
+        assert_eq!(input.len(), batch * self.embed_dim(), "input shape mismatch");
+
+        let q_dim = self.n_heads * self.head_dim;
+        let kv_dim = self.n_kv_heads * self.head_dim;
+
+        let mut q_all = self.q_proj.forward_rows(input, batch);
+        let mut k_new = self.k_proj.forward_rows(input, batch);
+        let v_new = self.v_proj.forward_rows(input, batch);
+
+        let mut output = vec![0.0f32; batch * q_dim];
+        for (b, cache) in caches.iter_mut().enumerate() {
+            let pos = cache.len();
+            let q = &mut q_all[b * q_dim..(b + 1) * q_dim];
+            let k = &mut k_new[b * kv_dim..(b + 1) * kv_dim];
+            self.rotate_heads(q, pos);
+            self.rotate_heads(k, pos);
+            cache.append(k, &v_new[b * kv_dim..(b + 1) * kv_dim]);
+            self.attend(q, 1, cache, &mut output[b * q_dim..(b + 1) * q_dim]);
+        }
+
+        self.project_out(output, batch)
+    }
+
+    /// Apply RoPE at `pos` to each `head_dim` slice of `heads`.
+    fn rotate_heads(&self, heads: &mut [f32], pos: usize) {
+        for head in heads.chunks_exact_mut(self.head_dim) {
+            let rotated = self.rope.forward(head, pos);
+            head.copy_from_slice(&rotated);
+        }
+    }
+
+    /// Causal attention of the last `seq_len` cached positions' queries
+    /// (`q_all`, `[seq_len, n_heads * head_dim]`) over the cache,
+    /// accumulated into `output` of the same shape.
+    fn attend(&self, q_all: &[f32], seq_len: usize, cache: &KvCache, output: &mut [f32]) {
+        let q_dim = self.n_heads * self.head_dim;
+        let total_seq = cache.len(); // all positions including new ones
+        let start_pos = total_seq - seq_len;
         let scale = 1.0 / (self.head_dim as f32).sqrt();
         let hpg = self.heads_per_group();
+        // An int8 cache is read through the projections' backend kernels.
+        let backend: &dyn ComputeBackend = match self.q_proj.backend() {
+            Some(backend) => backend.as_ref(),
+            None => &ScalarBackend,
+        };
 
         for qh in 0..self.n_heads {
             let kv_h = qh / hpg;
@@ -323,39 +370,40 @@ impl MultiHeadAttention {
                 let mut scores = Vec::with_capacity(attend_len);
 
                 for s in 0..attend_len {
-                    let k_vec = cache.key_at(s, kv_h);
-                    let dot: f32 = q_vec
-                        .iter()
-                        .zip(k_vec.iter())
-                        .map(|(&a, &b)| a * b)
-                        .sum();
+                    let dot = match cache.key_row(s, kv_h) {
+                        KvRow::F32(k_vec) => q_vec.iter().zip(k_vec).map(|(&a, &b)| a * b).sum(),
+                        KvRow::Int8 { values, scales } => backend.dot_q8(q_vec, values, scales, KV_BLOCK),
+                    };
                     scores.push(dot * scale);
                 }
 
                 softmax_inplace(&mut scores);
 
-                let out_off = t * q_dim + qh * self.head_dim;
+                let out = &mut output[q_off..q_off + self.head_dim];
                 for (s, &weight) in scores.iter().enumerate() {
-                    let v_vec = cache.value_at(s, kv_h);
-                    for d in 0..self.head_dim {
-                        output[out_off + d] += weight * v_vec[d];
+                    match cache.value_row(s, kv_h) {
+                        KvRow::F32(v_vec) => {
+                            for (o, &v) in out.iter_mut().zip(v_vec) {
+                                *o += weight * v;
+                            }
+                        }
+                        KvRow::Int8 { values, scales } => backend.axpy_q8(out, weight, values, scales, KV_BLOCK),
                     }
                 }
             }
         }
+    }
 
-        // 5. Output projection (with optional sub-norm before O)
-        let mut final_output = Vec::with_capacity(seq_len * embed_dim);
-        for t in 0..seq_len {
-            let head_concat = &output[t * q_dim..(t + 1) * q_dim];
-            let normed = match &self.o_sub_norm {
-                Some(norm) => norm.forward(head_concat),
-                None => head_concat.to_vec(),
-            };
-            final_output.extend_from_slice(&self.o_proj.forward(&normed));
+    /// Optional sub-norm, then the O projection, over `n_rows` rows of
+    /// concatenated heads.
+    fn project_out(&self, mut heads: Vec<f32>, n_rows: usize) -> Vec<f32> {
+        if let Some(norm) = &self.o_sub_norm {
+            for row in heads.chunks_exact_mut(self.n_heads * self.head_dim) {
+                let normed = norm.forward(row);
+                row.copy_from_slice(&normed);
+            }
         }
-
-        final_output
+        self.o_proj.forward_rows(&heads, n_rows)
     }
 }
 
@@ -405,6 +453,7 @@ fn softmax_inplace(values: &mut [f32]) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::layers::bitlinear::BitLinear;
     use crate::tensor::{Ternary, TernaryTensor};
 
     // Helper: create a BitLinear with specific ternary weights.
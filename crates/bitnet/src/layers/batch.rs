@@ -0,0 +1,371 @@
+//! Continuous batching — several independent generations on one model.
+//!
+//! `BatchScheduler` keeps up to `max_batch` sequences in flight. Each
+//! [`step`](BatchScheduler::step) admits waiting requests into free slots
+//! (prefilling each prompt into its own KV cache), samples one token for
+//! every running sequence, and advances all of them together with one
+//! [`TransformerModel::forward_batch`] call. A sequence that finishes frees
+//! its slot for the next request at the following step, so short requests
+//! never wait for long ones to drain.
+//!
+//! Stop conditions are those of `generate_stream`: `max_tokens`, stop
+//! tokens, stop sequences (with a tokenizer), `max_time` measured from
+//! admission, a full cache, and [`cancel`](BatchScheduler::cancel).
+//!
+//! ```text
+//!   submit ─► waiting ──admit/prefill──► running ──step──► Finished
+//!                          ▲                │
+//!                          └── free slot ◄──┘
+//! ```
+
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use crate::layers::generation::{GenerateOptions, Generation, StopReason, TextStream};
+use crate::layers::kv_cache::ModelKvCache;
+use crate::layers::model::TransformerModel;
+use crate::layers::sampler::Sampler;
+use crate::tokenizer::Tokenizer;
+
+/// Handle for a submitted request.
+pub type SequenceId = u64;
+
+/// What a [`BatchScheduler::step`] produced for one sequence.
+#[derive(Debug, Clone)]
+pub enum BatchEvent {
+    /// A sampled token and the text it made final, as in `StreamEvent`.
+    /// `token` is `None` for the last flush of held-back text.
+    Token {
+        id: SequenceId,
+        token: Option<u32>,
+        text: String,
+    },
+    /// The sequence is done and has left the batch.
+    Finished { id: SequenceId, generation: Generation },
+}
+
+struct Request {
+    id: SequenceId,
+    prompt: Vec<u32>,
+    options: GenerateOptions,
+}
+
+struct Running<'t> {
+    id: SequenceId,
+    options: GenerateOptions,
+    cache: ModelKvCache,
+    sampler: Sampler,
+    stream: Option<TextStream<'t>>,
+    tokens: Vec<u32>,
+    /// Logits for the next token to sample.
+    logits: Vec<f32>,
+    started: Instant,
+    cancelled: bool,
+}
+
+/// Steps many sequences through one model together.
+pub struct BatchScheduler<'m> {
+    model: &'m TransformerModel,
+    tokenizer: Option<&'m Tokenizer>,
+    max_batch: usize,
+    next_id: SequenceId,
+    waiting: VecDeque<Request>,
+    running: Vec<Running<'m>>,
+}
+
+impl<'m> BatchScheduler<'m> {
+    /// A scheduler running at most `max_batch` sequences at once.
+    pub fn new(model: &'m TransformerModel, max_batch: usize) -> Self {
+        assert!(max_batch > 0, "max_batch must be at least 1");
+        Self {
+            model,
+            tokenizer: None,
+            max_batch,
+            next_id: 0,
+            waiting: VecDeque::new(),
+            running: Vec::new(),
+        }
+    }
+
+    /// Decode text and apply stop sequences with `tokenizer`.
+    pub fn with_tokenizer(mut self, tokenizer: &'m Tokenizer) -> Self {
+        self.tokenizer = Some(tokenizer);
+        self
+    }
+
+    /// Queue a generation. It starts at the next step with a free slot.
+    pub fn submit(&mut self, prompt: Vec<u32>, options: GenerateOptions) -> SequenceId {
+        assert!(!prompt.is_empty(), "prompt must not be empty");
+        let id = self.next_id;
+        self.next_id += 1;
+        self.waiting.push_back(Request { id, prompt, options });
+        id
+    }
+
+    /// Stop a sequence. A waiting one is dropped without an event; a
+    /// running one finishes as `Cancelled` at the next step. Returns
+    /// whether `id` was known.
+    pub fn cancel(&mut self, id: SequenceId) -> bool {
+        if let Some(at) = self.waiting.iter().position(|r| r.id == id) {
+            self.waiting.remove(at);
+            return true;
+        }
+        match self.running.iter_mut().find(|r| r.id == id) {
+            Some(running) => {
+                running.cancelled = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sequences currently in the batch.
+    pub fn running(&self) -> usize {
+        self.running.len()
+    }
+
+    /// Requests waiting for a slot.
+    pub fn waiting(&self) -> usize {
+        self.waiting.len()
+    }
+
+    /// Whether there is nothing left to do.
+    pub fn is_idle(&self) -> bool {
+        self.running.is_empty() && self.waiting.is_empty()
+    }
+
+    /// Admit waiting requests, sample one token per running sequence and
+    /// advance the batch.
+    pub fn step(&mut self) -> Vec<BatchEvent> {
+        self.admit();
+
+        let mut events = Vec::new();
+        let mut finished = Vec::new();
+        for (i, seq) in self.running.iter_mut().enumerate() {
+            if let Some(reason) = seq.sample(&mut events) {
+                finished.push((i, reason));
+            }
+        }
+
+        // Finished sequences leave the batch before the forward pass.
+        for (i, reason) in finished.into_iter().rev() {
+            self.running.remove(i).finish(reason, &mut events);
+        }
+
+        if !self.running.is_empty() {
+            let tokens: Vec<u32> = self.running.iter().map(|s| *s.tokens.last().unwrap()).collect();
+            let mut caches: Vec<&mut ModelKvCache> = self.running.iter_mut().map(|s| &mut s.cache).collect();
+            let logits = self.model.forward_batch(&tokens, &mut caches);
+            let vocab = self.model.vocab_size();
+            for (seq, row) in self.running.iter_mut().zip(logits.chunks_exact(vocab)) {
+                seq.logits.clear();
+                seq.logits.extend_from_slice(row);
+            }
+        }
+        events
+    }
+
+    /// Step until every submitted request has finished; returns the
+    /// generations in completion order.
+    pub fn run_to_completion(&mut self) -> Vec<(SequenceId, Generation)> {
+        let mut done = Vec::new();
+        while !self.is_idle() {
+            for event in self.step() {
+                if let BatchEvent::Finished { id, generation } = event {
+                    done.push((id, generation));
+                }
+            }
+        }
+        done
+    }
+
+    /// Fill free slots from the queue, prefilling each prompt.
+    fn admit(&mut self) {
+        while self.running.len() < self.max_batch {
+            let Some(request) = self.waiting.pop_front() else { break };
+            let started = Instant::now();
+            let mut cache = self.model.create_kv_cache(request.prompt.len() + request.options.max_tokens);
+            let prefill = self.model.forward_cached(&request.prompt, &mut cache);
+            let vocab = self.model.vocab_size();
+            let logits = prefill[prefill.len() - vocab..].to_vec();
+            self.running.push(Running {
+                id: request.id,
+                sampler: Sampler::new(request.options.sampler.clone(), request.options.seed),
+                stream: self.tokenizer.map(|t| TextStream::new(t, &request.options.stop_sequences)),
+                options: request.options,
+                cache,
+                tokens: Vec::new(),
+                logits,
+                started,
+                cancelled: false,
+            });
+        }
+    }
+}
+
+impl Running<'_> {
+    /// Sample the next token and check the stop conditions, in the same
+    /// order as `generate_cached`. Returns why the sequence ended, if it did.
+    fn sample(&mut self, events: &mut Vec<BatchEvent>) -> Option<StopReason> {
+        if self.cancelled {
+            return Some(StopReason::Cancelled);
+        }
+        if self.tokens.len() >= self.options.max_tokens {
+            return Some(StopReason::MaxTokens);
+        }
+        let token = self.sampler.sample(&self.logits);
+        if self.options.stop_tokens.contains(&token) {
+            return Some(StopReason::StopToken(token));
+        }
+        self.tokens.push(token);
+
+        let step = self.stream.as_mut().map(|s| s.push(token));
+        let text = step.as_ref().map_or(String::new(), |s| s.text.clone());
+        events.push(BatchEvent::Token { id: self.id, token: Some(token), text });
+        if let Some(stop) = step.and_then(|s| s.stop) {
+            return Some(StopReason::StopSequence(stop));
+        }
+        if self.options.max_time.is_some_and(|limit| self.started.elapsed() >= limit) {
+            return Some(StopReason::MaxTime);
+        }
+        if self.tokens.len() >= self.options.max_tokens {
+            return Some(StopReason::MaxTokens);
+        }
+        if self.cache.seq_len() >= self.cache.max_seq_len() {
+            return Some(StopReason::ContextFull);
+        }
+        None
+    }
+
+    /// Flush held-back text and report the finished generation.
+    fn finish(self, stop_reason: StopReason, events: &mut Vec<BatchEvent>) {
+        let text = match self.stream {
+            Some(mut stream) => {
+                if !matches!(stop_reason, StopReason::StopSequence(_)) {
+                    let rest = stream.finish();
+                    if !rest.is_empty() {
+                        events.push(BatchEvent::Token { id: self.id, token: None, text: rest });
+                    }
+                }
+                stream.text().to_string()
+            }
+            None => String::new(),
+        };
+        events.push(BatchEvent::Finished {
+            id: self.id,
+            generation: Generation { tokens: self.tokens, text, stop_reason },
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::generation::tests::make_tokenizer;
+    use crate::layers::model::tests::make_test_model;
+    use crate::layers::sampler::SamplerConfig;
+    use std::ops::ControlFlow;
+
+    fn options(max_tokens: usize, seed: u64) -> GenerateOptions {
+        GenerateOptions {
+            max_tokens,
+            seed,
+            sampler: SamplerConfig { temperature: 0.9, top_k: 0, top_p: 1.0, ..Default::default() },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn batched_generations_match_sequential() {
+        let model = make_test_model(2, false);
+        let requests = [
+            (vec![0u32, 1, 2], options(6, 1)),
+            (vec![3u32], options(3, 2)),
+            (vec![4u32, 5, 6, 7, 1], options(8, 3)),
+            (vec![2u32, 2], options(5, 4)),
+        ];
+
+        let mut scheduler = BatchScheduler::new(&model, 3);
+        let ids: Vec<_> = requests.iter().map(|(p, o)| scheduler.submit(p.clone(), o.clone())).collect();
+        let mut done = scheduler.run_to_completion();
+        done.sort_by_key(|(id, _)| *id);
+        assert_eq!(done.iter().map(|(id, _)| *id).collect::<Vec<_>>(), ids);
+
+        for ((prompt, opts), (_, batched)) in requests.iter().zip(&done) {
+            let alone = model.generate_stream(prompt, opts, None, |_| ControlFlow::Continue(()));
+            assert_eq!(batched.tokens, alone.tokens, "prompt {prompt:?}");
+            assert_eq!(batched.stop_reason, alone.stop_reason);
+        }
+    }
+
+    #[test]
+    fn slots_are_refilled_as_sequences_finish() {
+        let model = make_test_model(1, false);
+        let mut scheduler = BatchScheduler::new(&model, 2);
+        scheduler.submit(vec![0], options(1, 0));
+        scheduler.submit(vec![1], options(4, 0));
+        scheduler.submit(vec![2], options(2, 0));
+        assert_eq!(scheduler.waiting(), 3);
+
+        // Step 1: two admitted, first finishes after its only token.
+        let events = scheduler.step();
+        assert!(events.iter().any(|e| matches!(e, BatchEvent::Finished { id: 0, .. })));
+        assert_eq!((scheduler.running(), scheduler.waiting()), (1, 1));
+
+        // Step 2: the third request takes the free slot.
+        scheduler.step();
+        assert_eq!((scheduler.running(), scheduler.waiting()), (2, 0));
+        scheduler.run_to_completion();
+        assert!(scheduler.is_idle());
+    }
+
+    #[test]
+    fn cancel_waiting_and_running() {
+        let model = make_test_model(1, false);
+        let mut scheduler = BatchScheduler::new(&model, 4);
+        let queued = scheduler.submit(vec![1], options(10, 0));
+        let running = scheduler.submit(vec![1], options(10, 0));
+
+        assert!(scheduler.cancel(queued));
+        scheduler.step();
+        assert!(scheduler.cancel(running));
+        assert!(!scheduler.cancel(999));
+
+        let done = scheduler.run_to_completion();
+        assert_eq!(done.len(), 1);
+        assert_eq!(done[0].0, running);
+        assert_eq!(done[0].1.stop_reason, StopReason::Cancelled);
+        assert_eq!(done[0].1.tokens.len(), 1);
+    }
+
+    #[test]
+    fn text_and_stop_sequences_match_sequential() {
+        let model = make_test_model(2, false);
+        let tokenizer = make_tokenizer();
+        let stops = GenerateOptions { stop_sequences: vec!["bc".into(), "dd".into()], ..options(12, 5) };
+        let requests = [(vec![3u32, 4], stops), (vec![5u32], options(7, 6))];
+
+        let mut scheduler = BatchScheduler::new(&model, 2).with_tokenizer(&tokenizer);
+        for (prompt, opts) in &requests {
+            scheduler.submit(prompt.clone(), opts.clone());
+        }
+        let mut streamed = vec![String::new(); requests.len()];
+        let mut done = Vec::new();
+        while !scheduler.is_idle() {
+            for event in scheduler.step() {
+                match event {
+                    BatchEvent::Token { id, text, .. } => streamed[id as usize].push_str(&text),
+                    BatchEvent::Finished { id, generation } => done.push((id, generation)),
+                }
+            }
+        }
+        done.sort_by_key(|(id, _)| *id);
+
+        for ((prompt, opts), (id, batched)) in requests.iter().zip(&done) {
+            let alone = model.generate_stream(prompt, opts, Some(&tokenizer), |_| ControlFlow::Continue(()));
+            assert_eq!(batched.text, alone.text);
+            assert_eq!(batched.stop_reason, alone.stop_reason);
+            assert_eq!(streamed[*id as usize], alone.text);
+        }
+    }
+}
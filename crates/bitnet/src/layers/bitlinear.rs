@@ -14,8 +14,8 @@
 use std::sync::Arc;
 
 use crate::tensor::{TernaryTensor, FloatTensor};
-use crate::ops::matmul::ternary_matvec;
-use crate::ops::quantize::quantize_absmax;
+use crate::ops::matmul::{ternary_matmul, ternary_matvec};
+use crate::ops::quantize::{quantize_absmax, quantize_per_token};
 use crate::compute::ComputeBackend;
 
 /// A ternary linear layer: y = W · x (no bias).
@@ -80,20 +80,45 @@ impl BitLinear {
         FloatTensor::new(out, vec![self.out_features()])
     }
 
+    /// Forward pass for `n_rows` vectors at once through the backend's
+    /// ternary matmul. Each row is quantized with its own scale, so the
+    /// result matches calling [`forward`](Self::forward) per row.
+    ///
+    /// Input: f32 slice of shape `[n_rows, in_features]`.
+    /// Returns: flat f32 vec of shape `[n_rows, out_features]`.
+    pub fn forward_rows(&self, inputs: &[f32], n_rows: usize) -> Vec<f32> {
+        let in_features = self.in_features();
+        assert_eq!(inputs.len(), n_rows * in_features, "input dimension mismatch");
+        if n_rows == 0 {
+            return Vec::new();
+        }
+
+        let (x_quant, act_scales) = quantize_per_token(inputs, in_features);
+        let y_int = match &self.backend {
+            Some(backend) => backend.ternary_matmul(&self.weights, &x_quant, n_rows),
+            None => ternary_matmul(&self.weights, &x_quant, n_rows),
+        };
+
+        let out_features = self.out_features();
+        y_int
+            .chunks_exact(out_features)
+            .zip(&act_scales)
+            .flat_map(|(row, &act_scale)| {
+                let combined_scale = act_scale * self.weight_scale;
+                row.iter().map(move |&acc| acc as f32 * combined_scale)
+            })
+            .collect()
+    }
+
     /// Batch forward pass for multiple vectors.
     ///
     /// Input: f32 slice of shape (batch_size × in_features).
     /// Returns: Vec of f32 output vectors.
     pub fn forward_batch(&self, inputs: &[f32], batch_size: usize) -> Vec<Vec<f32>> {
-        let in_features = self.in_features();
-        assert_eq!(inputs.len(), batch_size * in_features);
-
-        (0..batch_size)
-            .map(|b| {
-                let start = b * in_features;
-                let end = start + in_features;
-                self.forward(&inputs[start..end])
-            })
+        assert_eq!(inputs.len(), batch_size * self.in_features());
+        self.forward_rows(inputs, batch_size)
+            .chunks_exact(self.out_features())
+            .map(<[f32]>::to_vec)
             .collect()
     }
 
@@ -102,6 +127,9 @@ impl BitLinear {
 
     /// The weight scale factor γ.
     pub fn weight_scale(&self) -> f32 { self.weight_scale }
+
+    /// The compute backend, if one was set.
+    pub fn backend(&self) -> Option<&Arc<dyn ComputeBackend>> { self.backend.as_ref() }
 }
 
 impl std::fmt::Debug for BitLinear {
@@ -176,6 +204,15 @@ mod tests {
         assert!((outputs[1][0] - -1.0).abs() < 0.05);
     }
 
+    #[test]
+    fn forward_rows_matches_forward() {
+        let layer = make_bitlinear(&[1, -1, 0, 1, 0, 1, 1, -1, -1, 0, 1, 1], 3, 4, 0.3);
+        let inputs = vec![0.5f32, -1.0, 2.0, 0.25, 3.0, 0.1, -0.7, 1.5];
+        let rows = layer.forward_rows(&inputs, 2);
+        assert_eq!(&rows[..3], &layer.forward(&inputs[..4])[..]);
+        assert_eq!(&rows[3..], &layer.forward(&inputs[4..])[..]);
+    }
+
     #[test]
     fn debug_format() {
         let layer = make_bitlinear(&[1, 0, 0, 1], 2, 2, 0.5);
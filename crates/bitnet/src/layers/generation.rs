@@ -0,0 +1,303 @@
+//! Streaming generation — options, stop conditions and incremental text.
+//!
+//! `TransformerModel::generate_stream` samples one token at a time and
+//! hands each to a callback as soon as it is sampled, together with the
+//! text it completes. Generation ends on the first of:
+//!
+//! - `max_tokens` new tokens
+//! - a stop token ID (not included in the output)
+//! - a stop sequence in the decoded text (not included in the text)
+//! - the `max_time` wall-clock limit
+//! - a full KV cache
+//! - the callback returning `ControlFlow::Break`
+//!
+//! Stop sequences are matched on text, so they need a tokenizer. Text
+//! that might be the start of a stop sequence is held back until the
+//! next tokens decide it, so a streamed stop sequence is never shown.
+
+use std::ops::ControlFlow;
+use std::time::Duration;
+
+use crate::layers::sampler::SamplerConfig;
+use crate::tokenizer::Tokenizer;
+
+/// Everything that controls one generation call.
+#[derive(Debug, Clone)]
+pub struct GenerateOptions {
+    /// Maximum number of new tokens.
+    pub max_tokens: usize,
+    /// Sampling strategy.
+    pub sampler: SamplerConfig,
+    /// RNG seed for sampling.
+    pub seed: u64,
+    /// Token IDs that end generation.
+    pub stop_tokens: Vec<u32>,
+    /// Strings that end generation, matched on decoded text.
+    pub stop_sequences: Vec<String>,
+    /// Wall-clock limit for the whole call, prefill included.
+    pub max_time: Option<Duration>,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        Self {
+            max_tokens: 256,
+            sampler: SamplerConfig::default(),
+            seed: 42,
+            stop_tokens: Vec::new(),
+            stop_sequences: Vec::new(),
+            max_time: None,
+        }
+    }
+}
+
+impl GenerateOptions {
+    /// Options for `max_tokens` new tokens with the given sampler.
+    pub fn new(max_tokens: usize, sampler: SamplerConfig) -> Self {
+        Self {
+            max_tokens,
+            sampler,
+            ..Default::default()
+        }
+    }
+}
+
+/// Why generation ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StopReason {
+    /// Produced `max_tokens` tokens.
+    MaxTokens,
+    /// Sampled this stop token.
+    StopToken(u32),
+    /// The decoded text produced this stop sequence.
+    StopSequence(String),
+    /// Ran past `max_time`.
+    MaxTime,
+    /// The KV cache has no room for another token.
+    ContextFull,
+    /// The callback (or a [`HiddenHook`]) asked to stop.
+    Cancelled,
+}
+
+/// One step of a streamed generation.
+#[derive(Debug, Clone, Copy)]
+pub struct StreamEvent<'a> {
+    /// The token just sampled. `None` for the final flush of text held
+    /// back for a stop sequence that never completed.
+    pub token: Option<u32>,
+    /// Text that became final with this step; may be empty.
+    pub text: &'a str,
+}
+
+/// The result of a generation call.
+#[derive(Debug, Clone)]
+pub struct Generation {
+    /// The new tokens, without any stop token. Tokens forming a stop
+    /// sequence are included.
+    pub tokens: Vec<u32>,
+    /// Decoded text of the new tokens, cut before any stop sequence.
+    /// Empty without a tokenizer.
+    pub text: String,
+    pub stop_reason: StopReason,
+}
+
+/// A hook between the last transformer block and the output head.
+///
+/// `TransformerModel::generate_hooked` hands it the final-normed hidden
+/// states: all prompt rows once after prefill, then the row behind each
+/// sampled token, which it may modify before logits are computed. Shim
+/// executors use it to run gates on the prompt and steer the decode.
+pub trait HiddenHook {
+    /// Called once with the prompt's hidden states `[prompt_len,
+    /// embed_dim]`. `Break` ends generation before any token is sampled.
+    fn prefill(&mut self, hidden: &[f32]) -> ControlFlow<()> {
+        let _ = hidden;
+        ControlFlow::Continue(())
+    }
+
+    /// Called with the hidden state the next token is sampled from.
+    fn step(&mut self, hidden: &mut [f32]) {
+        let _ = hidden;
+    }
+}
+
+/// Incremental decoder that finds stop sequences across token boundaries.
+///
+/// Decodes the whole generated sequence on every push, since decoding a
+/// token alone loses context (SentencePiece word boundaries, multi-byte
+/// characters split over byte tokens).
+pub struct TextStream<'t> {
+    tokenizer: &'t Tokenizer,
+    stop_sequences: Vec<String>,
+    tokens: Vec<u32>,
+    /// Byte length of the decoded text already released.
+    released: usize,
+    /// Everything released so far.
+    output: String,
+}
+
+/// What a push to a [`TextStream`] produced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextStep {
+    /// Newly released text.
+    pub text: String,
+    /// The stop sequence that just completed, if any. `text` ends where it starts.
+    pub stop: Option<String>,
+}
+
+impl<'t> TextStream<'t> {
+    pub fn new(tokenizer: &'t Tokenizer, stop_sequences: &[String]) -> Self {
+        Self {
+            tokenizer,
+            stop_sequences: stop_sequences.iter().filter(|s| !s.is_empty()).cloned().collect(),
+            tokens: Vec::new(),
+            released: 0,
+            output: String::new(),
+        }
+    }
+
+    /// Add a token and release whatever text is now final.
+    pub fn push(&mut self, token: u32) -> TextStep {
+        self.tokens.push(token);
+        let decoded = self.tokenizer.decode(&self.tokens);
+        let Some(pending) = decoded.get(self.released..) else {
+            return TextStep { text: String::new(), stop: None };
+        };
+
+        let earliest = self
+            .stop_sequences
+            .iter()
+            .filter_map(|stop| pending.find(stop.as_str()).map(|at| (at, stop)))
+            .min_by_key(|(at, _)| *at);
+        if let Some((at, stop)) = earliest {
+            let text = pending[..at].to_string();
+            self.output.push_str(&text);
+            self.released = decoded.len();
+            return TextStep { text, stop: Some(stop.clone()) };
+        }
+
+        // A trailing U+FFFD may be a multi-byte character still arriving.
+        let mut end = pending.trim_end_matches('\u{FFFD}').len();
+        let held = self
+            .stop_sequences
+            .iter()
+            .map(|stop| longest_prefix_suffix(&pending[..end], stop))
+            .max()
+            .unwrap_or(0);
+        end -= held;
+
+        let text = pending[..end].to_string();
+        self.output.push_str(&text);
+        self.released += end;
+        TextStep { text, stop: None }
+    }
+
+    /// Release any text still held back.
+    pub fn finish(&mut self) -> String {
+        let decoded = self.tokenizer.decode(&self.tokens);
+        let rest = decoded.get(self.released..).unwrap_or("").to_string();
+        self.output.push_str(&rest);
+        self.released = decoded.len();
+        rest
+    }
+
+    /// All text released so far.
+    pub fn text(&self) -> &str {
+        &self.output
+    }
+}
+
+/// Length of the longest proper prefix of `stop` that `text` ends with.
+fn longest_prefix_suffix(text: &str, stop: &str) -> usize {
+    (1..stop.len())
+        .rev()
+        .filter(|&n| stop.is_char_boundary(n))
+        .find(|&n| text.ends_with(&stop[..n]))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::tokenizer::TokenType;
+
+    /// SentencePiece vocab of eight tokens, matching the test models'.
+    pub(crate) fn make_tokenizer() -> Tokenizer {
+        let vocab = ["<unk>", "<s>", "</s>", "\u{2581}a", "b", "c", "\u{2581}END", "d"];
+        let types = [
+            TokenType::Unknown,
+            TokenType::Control,
+            TokenType::Control,
+            TokenType::Normal,
+            TokenType::Normal,
+            TokenType::Normal,
+            TokenType::Normal,
+            TokenType::Normal,
+        ];
+        Tokenizer::from_parts(
+            vocab.iter().map(|s| s.to_string()).collect(),
+            vec![0.0; vocab.len()],
+            types.to_vec(),
+            1,
+            2,
+        )
+        .unwrap()
+    }
+
+    fn stops(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn releases_text_with_word_boundaries() {
+        let tok = make_tokenizer();
+        let mut stream = TextStream::new(&tok, &[]);
+        let parts: Vec<String> = [3, 4, 3, 5].iter().map(|&t| stream.push(t).text).collect();
+        assert_eq!(parts, vec!["a", "b", " a", "c"]);
+        assert_eq!(stream.text(), "ab ac");
+    }
+
+    #[test]
+    fn holds_back_partial_stop_sequence() {
+        let tok = make_tokenizer();
+        let mut stream = TextStream::new(&tok, &stops(&["bc"]));
+        assert_eq!(stream.push(3).text, "a");
+        // "b" might start "bc": held.
+        assert_eq!(stream.push(4).text, "");
+        // "bd" is not a stop: released.
+        assert_eq!(stream.push(7).text, "bd");
+        stream.push(4);
+        let step = stream.push(5);
+        assert_eq!(step, TextStep { text: String::new(), stop: Some("bc".into()) });
+        assert_eq!(stream.text(), "abd");
+    }
+
+    #[test]
+    fn stop_sequence_within_one_token() {
+        let tok = make_tokenizer();
+        let mut stream = TextStream::new(&tok, &stops(&["EN", "zzz"]));
+        stream.push(3);
+        let step = stream.push(6);
+        assert_eq!(step.text, " ");
+        assert_eq!(step.stop.as_deref(), Some("EN"));
+        assert_eq!(stream.text(), "a ");
+    }
+
+    #[test]
+    fn finish_flushes_held_text() {
+        let tok = make_tokenizer();
+        let mut stream = TextStream::new(&tok, &stops(&["bc"]));
+        stream.push(3);
+        stream.push(4);
+        assert_eq!(stream.finish(), "b");
+        assert_eq!(stream.text(), "ab");
+    }
+
+    #[test]
+    fn prefix_suffix_overlap() {
+        assert_eq!(longest_prefix_suffix("xab", "abc"), 2);
+        assert_eq!(longest_prefix_suffix("xa", "abc"), 1);
+        assert_eq!(longest_prefix_suffix("abc", "abc"), 0);
+        assert_eq!(longest_prefix_suffix("", "abc"), 0);
+    }
+}
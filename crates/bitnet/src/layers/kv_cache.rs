@@ -7,18 +7,77 @@
 //! Each transformer layer gets its own `KvCache` instance. The full model
 //! holds a `Vec<KvCache>`, one per layer.
 //!
-//! Layout: K and V are stored as flat f32 arrays of shape
+//! Layout: K and V are stored as flat arrays of shape
 //! `[max_seq_len, n_kv_heads, head_dim]`, filled incrementally as tokens
-//! are generated.
+//! are generated. In the [`KvCacheFormat::Int8`] format each element is
+//! one signed byte, and every [`KV_BLOCK`] elements of a head share an
+//! absmax f32 scale stored alongside, `[max_seq_len, n_kv_heads, blocks]`.
+//! Attention reads those bytes directly through [`KvCache::key_row`].
+
+use std::borrow::Cow;
+
+/// Elements per int8 quantization block within one head.
+pub const KV_BLOCK: usize = 32;
+
+/// How a [`KvCache`] stores keys and values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KvCacheFormat {
+    /// Full precision, 4 bytes per element.
+    #[default]
+    F32,
+    /// One byte per element plus a 4-byte scale per [`KV_BLOCK`]
+    /// elements: about 3.5× smaller than f32 at 32-wide blocks.
+    Int8,
+}
+
+impl std::str::FromStr for KvCacheFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "f32" => Ok(Self::F32),
+            "int8" | "i8" | "q8" => Ok(Self::Int8),
+            other => Err(format!("unknown KV cache format '{other}' (expected f32 or int8)")),
+        }
+    }
+}
+
+/// One cached key or value vector (`head_dim` elements), as stored.
+#[derive(Debug, Clone, Copy)]
+pub enum KvRow<'a> {
+    F32(&'a [f32]),
+    /// Element `i` is `values[i] as f32 * scales[i / KV_BLOCK]`.
+    Int8 { values: &'a [i8], scales: &'a [f32] },
+}
+
+impl KvRow<'_> {
+    /// The row as f32, dequantizing if needed.
+    pub fn to_f32(&self) -> Cow<'_, [f32]> {
+        match *self {
+            KvRow::F32(row) => Cow::Borrowed(row),
+            KvRow::Int8 { values, scales } => Cow::Owned(dequantize(values, scales)),
+        }
+    }
+}
+
+enum Storage {
+    F32 {
+        k: Vec<f32>,
+        v: Vec<f32>,
+    },
+    Int8 {
+        k: Vec<i8>,
+        v: Vec<i8>,
+        k_scales: Vec<f32>,
+        v_scales: Vec<f32>,
+    },
+}
 
 /// Pre-allocated key/value cache for one transformer layer.
 pub struct KvCache {
-    /// Cached key projections (already RoPE'd).
-    /// Shape: `[max_seq_len, n_kv_heads * head_dim]` stored flat.
-    k_cache: Vec<f32>,
-    /// Cached value projections.
+    /// Cached key projections (already RoPE'd) and value projections.
     /// Shape: `[max_seq_len, n_kv_heads * head_dim]` stored flat.
-    v_cache: Vec<f32>,
+    storage: Storage,
     /// Number of KV heads.
     n_kv_heads: usize,
     /// Dimension per head.
@@ -30,12 +89,31 @@ pub struct KvCache {
 }
 
 impl KvCache {
-    /// Create a new cache pre-allocated for `max_seq_len` positions.
+    /// Create a new f32 cache pre-allocated for `max_seq_len` positions.
     pub fn new(n_kv_heads: usize, head_dim: usize, max_seq_len: usize) -> Self {
+        Self::with_format(n_kv_heads, head_dim, max_seq_len, KvCacheFormat::F32)
+    }
+
+    /// Create a new cache in the given storage format.
+    pub fn with_format(n_kv_heads: usize, head_dim: usize, max_seq_len: usize, format: KvCacheFormat) -> Self {
         let kv_dim = n_kv_heads * head_dim;
+        let storage = match format {
+            KvCacheFormat::F32 => Storage::F32 {
+                k: vec![0.0f32; max_seq_len * kv_dim],
+                v: vec![0.0f32; max_seq_len * kv_dim],
+            },
+            KvCacheFormat::Int8 => {
+                let n_scales = max_seq_len * n_kv_heads * head_dim.div_ceil(KV_BLOCK);
+                Storage::Int8 {
+                    k: vec![0i8; max_seq_len * kv_dim],
+                    v: vec![0i8; max_seq_len * kv_dim],
+                    k_scales: vec![0.0f32; n_scales],
+                    v_scales: vec![0.0f32; n_scales],
+                }
+            }
+        };
         Self {
-            k_cache: vec![0.0f32; max_seq_len * kv_dim],
-            v_cache: vec![0.0f32; max_seq_len * kv_dim],
+            storage,
             n_kv_heads,
             head_dim,
             max_seq_len,
@@ -43,6 +121,14 @@ impl KvCache {
         }
     }
 
+    /// Storage format.
+    pub fn format(&self) -> KvCacheFormat {
+        match self.storage {
+            Storage::F32 { .. } => KvCacheFormat::F32,
+            Storage::Int8 { .. } => KvCacheFormat::Int8,
+        }
+    }
+
     /// Number of positions currently cached.
     pub fn len(&self) -> usize {
         self.len
@@ -73,12 +159,18 @@ impl KvCache {
         self.head_dim
     }
 
+    /// Scales per head in the int8 format.
+    fn blocks_per_head(&self) -> usize {
+        self.head_dim.div_ceil(KV_BLOCK)
+    }
+
     /// Append K and V vectors for one or more new positions.
     ///
     /// `keys`: flat slice of `[n_new, n_kv_heads * head_dim]`.
     /// `values`: flat slice of `[n_new, n_kv_heads * head_dim]`.
     ///
-    /// These should already have RoPE applied (for keys).
+    /// These should already have RoPE applied (for keys). An int8 cache
+    /// quantizes them here.
     pub fn append(&mut self, keys: &[f32], values: &[f32]) {
         let kv_dim = self.kv_dim();
         assert_eq!(keys.len() % kv_dim, 0, "keys length must be multiple of kv_dim");
@@ -95,47 +187,100 @@ impl KvCache {
 
         let start = self.len * kv_dim;
         let end = start + n_new * kv_dim;
-        self.k_cache[start..end].copy_from_slice(keys);
-        self.v_cache[start..end].copy_from_slice(values);
+        let head_dim = self.head_dim;
+        let blocks = self.blocks_per_head();
+        let scale_start = self.len * self.n_kv_heads * blocks;
+        match &mut self.storage {
+            Storage::F32 { k, v } => {
+                k[start..end].copy_from_slice(keys);
+                v[start..end].copy_from_slice(values);
+            }
+            Storage::Int8 { k, v, k_scales, v_scales } => {
+                quantize_heads(keys, head_dim, &mut k[start..end], &mut k_scales[scale_start..]);
+                quantize_heads(values, head_dim, &mut v[start..end], &mut v_scales[scale_start..]);
+            }
+        }
         self.len += n_new;
     }
 
-    /// Get the cached key vector for a specific position and KV head.
-    ///
-    /// Returns a slice of length `head_dim`.
-    pub fn key_at(&self, pos: usize, kv_head: usize) -> &[f32] {
+    /// The cached key vector for a position and KV head, as stored.
+    pub fn key_row(&self, pos: usize, kv_head: usize) -> KvRow<'_> {
+        self.row(pos, kv_head, true)
+    }
+
+    /// The cached value vector for a position and KV head, as stored.
+    pub fn value_row(&self, pos: usize, kv_head: usize) -> KvRow<'_> {
+        self.row(pos, kv_head, false)
+    }
+
+    fn row(&self, pos: usize, kv_head: usize, key: bool) -> KvRow<'_> {
         debug_assert!(pos < self.len);
         debug_assert!(kv_head < self.n_kv_heads);
-        let kv_dim = self.kv_dim();
-        let offset = pos * kv_dim + kv_head * self.head_dim;
-        &self.k_cache[offset..offset + self.head_dim]
+        let offset = pos * self.kv_dim() + kv_head * self.head_dim;
+        let range = offset..offset + self.head_dim;
+        match &self.storage {
+            Storage::F32 { k, v } => KvRow::F32(if key { &k[range] } else { &v[range] }),
+            Storage::Int8 { k, v, k_scales, v_scales } => {
+                let blocks = self.blocks_per_head();
+                let s = (pos * self.n_kv_heads + kv_head) * blocks;
+                let (values, scales) = if key { (k, k_scales) } else { (v, v_scales) };
+                KvRow::Int8 { values: &values[range], scales: &scales[s..s + blocks] }
+            }
+        }
+    }
+
+    /// Get the cached key vector for a specific position and KV head.
+    ///
+    /// Returns `head_dim` values, dequantized for an int8 cache.
+    pub fn key_at(&self, pos: usize, kv_head: usize) -> Cow<'_, [f32]> {
+        self.row_f32(pos, kv_head, true)
     }
 
     /// Get the cached value vector for a specific position and KV head.
     ///
-    /// Returns a slice of length `head_dim`.
-    pub fn value_at(&self, pos: usize, kv_head: usize) -> &[f32] {
-        debug_assert!(pos < self.len);
-        debug_assert!(kv_head < self.n_kv_heads);
-        let kv_dim = self.kv_dim();
-        let offset = pos * kv_dim + kv_head * self.head_dim;
-        &self.v_cache[offset..offset + self.head_dim]
+    /// Returns `head_dim` values, dequantized for an int8 cache.
+    pub fn value_at(&self, pos: usize, kv_head: usize) -> Cow<'_, [f32]> {
+        self.row_f32(pos, kv_head, false)
     }
 
-    /// Get all cached keys as a flat slice up to current length.
+    fn row_f32(&self, pos: usize, kv_head: usize, key: bool) -> Cow<'_, [f32]> {
+        match self.row(pos, kv_head, key) {
+            KvRow::F32(row) => Cow::Borrowed(row),
+            KvRow::Int8 { values, scales } => Cow::Owned(dequantize(values, scales)),
+        }
+    }
+
+    /// Get all cached keys up to current length, dequantized for an
+    /// int8 cache.
     ///
     /// Shape: `[len, n_kv_heads * head_dim]`.
-    pub fn keys(&self) -> &[f32] {
-        let end = self.len * self.kv_dim();
-        &self.k_cache[..end]
+    pub fn keys(&self) -> Cow<'_, [f32]> {
+        self.all_f32(true)
     }
 
-    /// Get all cached values as a flat slice up to current length.
+    /// Get all cached values up to current length, dequantized for an
+    /// int8 cache.
     ///
     /// Shape: `[len, n_kv_heads * head_dim]`.
-    pub fn values(&self) -> &[f32] {
+    pub fn values(&self) -> Cow<'_, [f32]> {
+        self.all_f32(false)
+    }
+
+    fn all_f32(&self, key: bool) -> Cow<'_, [f32]> {
         let end = self.len * self.kv_dim();
-        &self.v_cache[..end]
+        match &self.storage {
+            Storage::F32 { k, v } => Cow::Borrowed(if key { &k[..end] } else { &v[..end] }),
+            Storage::Int8 { k, v, k_scales, v_scales } => {
+                let (values, scales) = if key { (k, k_scales) } else { (v, v_scales) };
+                let blocks = self.blocks_per_head();
+                let out = values[..end]
+                    .chunks(self.head_dim)
+                    .zip(scales.chunks(blocks))
+                    .flat_map(|(row, s)| dequantize(row, s))
+                    .collect();
+                Cow::Owned(out)
+            }
+        }
     }
 
     /// Reset the cache (reuse the allocation).
@@ -145,15 +290,44 @@ impl KvCache {
 
     /// Memory usage in bytes.
     pub fn memory_bytes(&self) -> usize {
-        self.k_cache.len() * 4 + self.v_cache.len() * 4
+        match &self.storage {
+            Storage::F32 { k, v } => k.len() * 4 + v.len() * 4,
+            Storage::Int8 { k, v, k_scales, v_scales } => k.len() + v.len() + (k_scales.len() + v_scales.len()) * 4,
+        }
     }
 }
 
+/// Quantize `[n, n_heads * head_dim]` f32 rows into int8, one absmax
+/// scale per [`KV_BLOCK`] elements of each head.
+fn quantize_heads(src: &[f32], head_dim: usize, dst: &mut [i8], scales: &mut [f32]) {
+    let blocks = src
+        .chunks(head_dim)
+        .zip(dst.chunks_mut(head_dim))
+        .flat_map(|(s, d)| s.chunks(KV_BLOCK).zip(d.chunks_mut(KV_BLOCK)));
+    for ((s, d), scale) in blocks.zip(scales.iter_mut()) {
+        let absmax = s.iter().fold(0.0f32, |m, &x| m.max(x.abs()));
+        *scale = absmax / 127.0;
+        let inv = if absmax > 0.0 { 127.0 / absmax } else { 0.0 };
+        for (q, &x) in d.iter_mut().zip(s) {
+            *q = (x * inv).round().clamp(-127.0, 127.0) as i8;
+        }
+    }
+}
+
+fn dequantize(values: &[i8], scales: &[f32]) -> Vec<f32> {
+    values
+        .chunks(KV_BLOCK)
+        .zip(scales)
+        .flat_map(|(block, &scale)| block.iter().map(move |&q| q as f32 * scale))
+        .collect()
+}
+
 impl std::fmt::Debug for KvCache {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "KvCache(kv_heads={}, head_dim={}, len={}/{}, {:.1}KB)",
+            "KvCache({:?}, kv_heads={}, head_dim={}, len={}/{}, {:.1}KB)",
+            self.format(),
             self.n_kv_heads,
             self.head_dim,
             self.len,
@@ -172,12 +346,28 @@ pub struct ModelKvCache {
 impl ModelKvCache {
     /// Create caches for all layers.
     pub fn new(n_layers: usize, n_kv_heads: usize, head_dim: usize, max_seq_len: usize) -> Self {
+        Self::with_format(n_layers, n_kv_heads, head_dim, max_seq_len, KvCacheFormat::F32)
+    }
+
+    /// Create caches for all layers in the given storage format.
+    pub fn with_format(
+        n_layers: usize,
+        n_kv_heads: usize,
+        head_dim: usize,
+        max_seq_len: usize,
+        format: KvCacheFormat,
+    ) -> Self {
         let layers = (0..n_layers)
-            .map(|_| KvCache::new(n_kv_heads, head_dim, max_seq_len))
+            .map(|_| KvCache::with_format(n_kv_heads, head_dim, max_seq_len, format))
             .collect();
         Self { layers }
     }
 
+    /// Storage format of the layer caches.
+    pub fn format(&self) -> KvCacheFormat {
+        self.layers.first().map(|c| c.format()).unwrap_or_default()
+    }
+
     /// Get the cache for a specific layer.
     pub fn layer(&self, idx: usize) -> &KvCache {
         &self.layers[idx]
@@ -198,6 +388,11 @@ impl ModelKvCache {
         self.layers.first().map(|c| c.len()).unwrap_or(0)
     }
 
+    /// Maximum sequence length the caches can hold.
+    pub fn max_seq_len(&self) -> usize {
+        self.layers.first().map(|c| c.max_seq_len()).unwrap_or(0)
+    }
+
     /// Reset all layer caches.
     pub fn clear(&mut self) {
         for cache in &mut self.layers {
@@ -209,6 +404,121 @@ impl ModelKvCache {
     pub fn memory_bytes(&self) -> usize {
         self.layers.iter().map(|c| c.memory_bytes()).sum()
     }
+
+    /// Copy out the filled positions of every layer. Snapshots are always
+    /// f32; an int8 cache dequantizes here and requantizes on restore,
+    /// which reproduces the same int8 values.
+    pub fn snapshot(&self) -> KvSnapshot {
+        let first = &self.layers[0];
+        KvSnapshot {
+            n_kv_heads: first.n_kv_heads(),
+            head_dim: first.head_dim(),
+            len: self.seq_len(),
+            layers: self
+                .layers
+                .iter()
+                .map(|c| (c.keys().to_vec(), c.values().to_vec()))
+                .collect(),
+        }
+    }
+
+    /// Whether `snapshot` was taken from a cache of this shape.
+    pub fn fits(&self, snapshot: &KvSnapshot) -> bool {
+        let first = &self.layers[0];
+        snapshot.layers.len() == self.layers.len()
+            && snapshot.n_kv_heads == first.n_kv_heads()
+            && snapshot.head_dim == first.head_dim()
+            && snapshot.len <= first.max_seq_len()
+    }
+
+    /// Replace the contents with `snapshot`. Generation can continue from
+    /// the end of the snapshotted sequence.
+    pub fn restore(&mut self, snapshot: &KvSnapshot) {
+        assert!(self.fits(snapshot), "snapshot does not fit this cache: {snapshot:?} into {self:?}");
+        for (cache, (keys, values)) in self.layers.iter_mut().zip(&snapshot.layers) {
+            cache.clear();
+            if !keys.is_empty() {
+                cache.append(keys, values);
+            }
+        }
+    }
+}
+
+/// The filled part of a [`ModelKvCache`], without its spare capacity —
+/// the attention state after some token sequence, restorable into any
+/// cache of the same shape.
+#[derive(Clone)]
+pub struct KvSnapshot {
+    n_kv_heads: usize,
+    head_dim: usize,
+    len: usize,
+    /// Keys and values per layer, each `[len, n_kv_heads * head_dim]`.
+    layers: Vec<(Vec<f32>, Vec<f32>)>,
+}
+
+impl KvSnapshot {
+    /// Rebuild a snapshot from per-layer keys and values.
+    /// Returns `None` if the buffers don't match the shape.
+    pub fn from_layers(n_kv_heads: usize, head_dim: usize, layers: Vec<(Vec<f32>, Vec<f32>)>) -> Option<Self> {
+        let kv_dim = n_kv_heads * head_dim;
+        let first = layers.first()?;
+        if kv_dim == 0 || first.0.len() % kv_dim != 0 {
+            return None;
+        }
+        let len = first.0.len() / kv_dim;
+        let consistent = layers.iter().all(|(k, v)| k.len() == len * kv_dim && v.len() == len * kv_dim);
+        consistent.then_some(Self { n_kv_heads, head_dim, len, layers })
+    }
+
+    /// Number of cached positions.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no positions are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of layers.
+    pub fn n_layers(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Number of KV heads.
+    pub fn n_kv_heads(&self) -> usize {
+        self.n_kv_heads
+    }
+
+    /// Head dimension.
+    pub fn head_dim(&self) -> usize {
+        self.head_dim
+    }
+
+    /// Keys and values of one layer, each `[len, n_kv_heads * head_dim]`.
+    pub fn layer(&self, idx: usize) -> (&[f32], &[f32]) {
+        let (k, v) = &self.layers[idx];
+        (k, v)
+    }
+
+    /// Memory usage in bytes.
+    pub fn memory_bytes(&self) -> usize {
+        self.layers.iter().map(|(k, v)| (k.len() + v.len()) * 4).sum()
+    }
+}
+
+impl std::fmt::Debug for KvSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "KvSnapshot(layers={}, kv_heads={}, head_dim={}, len={}, {:.1}KB)",
+            self.layers.len(),
+            self.n_kv_heads,
+            self.head_dim,
+            self.len,
+            self.memory_bytes() as f64 / 1024.0,
+        )
+    }
 }
 
 impl std::fmt::Debug for ModelKvCache {
@@ -286,15 +596,15 @@ mod tests {
         cache.append(&keys1, &values1);
 
         // Check pos 0, head 0
-        assert_eq!(cache.key_at(0, 0), &[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(&*cache.key_at(0, 0), &[1.0, 2.0, 3.0, 4.0]);
         // Check pos 0, head 1
-        assert_eq!(cache.key_at(0, 1), &[5.0, 6.0, 7.0, 8.0]);
+        assert_eq!(&*cache.key_at(0, 1), &[5.0, 6.0, 7.0, 8.0]);
         // Check pos 1, head 0
-        assert_eq!(cache.key_at(1, 0), &[21.0, 22.0, 23.0, 24.0]);
+        assert_eq!(&*cache.key_at(1, 0), &[21.0, 22.0, 23.0, 24.0]);
 
         // Values
-        assert_eq!(cache.value_at(0, 0), &[11.0, 12.0, 13.0, 14.0]);
-        assert_eq!(cache.value_at(1, 1), &[35.0, 36.0, 37.0, 38.0]);
+        assert_eq!(&*cache.value_at(0, 0), &[11.0, 12.0, 13.0, 14.0]);
+        assert_eq!(&*cache.value_at(1, 1), &[35.0, 36.0, 37.0, 38.0]);
     }
 
     #[test]
@@ -304,8 +614,8 @@ mod tests {
         let values = vec![5.0, 6.0, 7.0, 8.0];
         cache.append(&keys, &values);
 
-        assert_eq!(cache.keys(), &[1.0, 2.0, 3.0, 4.0]);
-        assert_eq!(cache.values(), &[5.0, 6.0, 7.0, 8.0]);
+        assert_eq!(&*cache.keys(), &[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(&*cache.values(), &[5.0, 6.0, 7.0, 8.0]);
     }
 
     #[test]
@@ -385,6 +695,103 @@ mod tests {
         // ~92MB for a 2B model at 2K context — reasonable
     }
 
+    #[test]
+    fn snapshot_restores_into_larger_cache() {
+        let mut mc = ModelKvCache::new(2, 2, 4, 10);
+        let kv_dim = mc.layer(0).kv_dim();
+        for i in 0..2 {
+            let k: Vec<f32> = (0..3 * kv_dim).map(|x| (x + i * 100) as f32).collect();
+            mc.layer_mut(i).append(&k, &vec![i as f32; 3 * kv_dim]);
+        }
+        let snap = mc.snapshot();
+        assert_eq!(snap.len(), 3);
+        assert_eq!(snap.memory_bytes(), 2 * 2 * 3 * kv_dim * 4);
+
+        let mut bigger = ModelKvCache::new(2, 2, 4, 100);
+        assert!(bigger.fits(&snap));
+        bigger.restore(&snap);
+        assert_eq!(bigger.seq_len(), 3);
+        assert_eq!(bigger.layer(1).keys(), mc.layer(1).keys());
+        assert_eq!(&*bigger.layer(1).value_at(2, 1), &[1.0; 4]);
+
+        // Wrong shape or too little room: doesn't fit.
+        assert!(!ModelKvCache::new(3, 2, 4, 100).fits(&snap));
+        assert!(!ModelKvCache::new(2, 2, 4, 2).fits(&snap));
+
+        let (k, v) = snap.layer(0);
+        let rebuilt = KvSnapshot::from_layers(2, 4, vec![(k.to_vec(), v.to_vec()); 2]).unwrap();
+        assert_eq!(rebuilt.len(), 3);
+        assert!(KvSnapshot::from_layers(2, 4, vec![(vec![0.0; 9], vec![0.0; 9])]).is_none());
+    }
+
+    // -- Int8 format --
+
+    #[test]
+    fn int8_roundtrip_within_block_error() {
+        // head_dim 40: one full 32-block and a short 8-block per head.
+        let mut cache = KvCache::with_format(2, 40, 10, KvCacheFormat::Int8);
+        assert_eq!(cache.format(), KvCacheFormat::Int8);
+        let keys: Vec<f32> = (0..3 * 80).map(|i| ((i * 37 % 101) as f32 - 50.0) / 7.0).collect();
+        let values: Vec<f32> = keys.iter().map(|x| x * 0.01).collect();
+        cache.append(&keys, &values);
+
+        let restored = cache.keys();
+        for (block, want) in restored.chunks(KV_BLOCK).zip(keys.chunks(KV_BLOCK)) {
+            let absmax = want.iter().fold(0.0f32, |m, x| m.max(x.abs()));
+            for (&got, &want) in block.iter().zip(want) {
+                assert!((got - want).abs() <= absmax / 254.0 + 1e-6, "{got} vs {want}");
+            }
+        }
+        match cache.value_row(2, 1) {
+            KvRow::Int8 { values, scales } => {
+                assert_eq!(values.len(), 40);
+                assert_eq!(scales.len(), 2);
+            }
+            KvRow::F32(_) => panic!("expected int8 row"),
+        }
+        assert_eq!(cache.value_at(2, 1).len(), 40);
+    }
+
+    #[test]
+    fn int8_zero_block_stays_zero() {
+        let mut cache = KvCache::with_format(1, 4, 2, KvCacheFormat::Int8);
+        cache.append(&[0.0; 4], &[0.0; 4]);
+        assert_eq!(&*cache.key_at(0, 0), &[0.0; 4]);
+    }
+
+    #[test]
+    fn int8_memory_is_smaller() {
+        let f32_cache = KvCache::new(4, 64, 2048);
+        let int8 = KvCache::with_format(4, 64, 2048, KvCacheFormat::Int8);
+        // 2 buffers × 2048 × 256 bytes + 2 × 2048 × 4 heads × 2 blocks × 4 bytes
+        assert_eq!(int8.memory_bytes(), 2 * 2048 * 256 + 2 * 2048 * 4 * 2 * 4);
+        assert!(f32_cache.memory_bytes() as f64 / int8.memory_bytes() as f64 > 3.5);
+    }
+
+    #[test]
+    fn int8_snapshot_restore_is_stable() {
+        let mut mc = ModelKvCache::with_format(2, 2, 8, 10, KvCacheFormat::Int8);
+        assert_eq!(mc.format(), KvCacheFormat::Int8);
+        for i in 0..2 {
+            let k: Vec<f32> = (0..3 * 16).map(|x| ((x + i) as f32).sin()).collect();
+            mc.layer_mut(i).append(&k, &k);
+        }
+        let snap = mc.snapshot();
+        let mut other = ModelKvCache::with_format(2, 2, 8, 10, KvCacheFormat::Int8);
+        other.restore(&snap);
+        for i in 0..2 {
+            let (a, b) = (other.layer(i).keys(), mc.layer(i).keys());
+            assert!(a.iter().zip(b.iter()).all(|(x, y)| (x - y).abs() < 1e-6));
+        }
+    }
+
+    #[test]
+    fn parse_format() {
+        assert_eq!("int8".parse::<KvCacheFormat>(), Ok(KvCacheFormat::Int8));
+        assert_eq!("F32".parse::<KvCacheFormat>(), Ok(KvCacheFormat::F32));
+        assert!("fp8".parse::<KvCacheFormat>().is_err());
+    }
+
     #[test]
     fn debug_format_cache() {
         let cache = KvCache::new(4, 64, 2048);
@@ -424,8 +831,8 @@ mod tests {
         assert_eq!(cache.len(), 8); // 5 prompt + 3 generated
 
         // Can retrieve any position
-        assert_eq!(cache.key_at(0, 0), &[1.0; 4]); // prompt
-        assert_eq!(cache.key_at(5, 0), &[10.0; 4]); // first generated
-        assert_eq!(cache.key_at(7, 0), &[12.0; 4]); // third generated
+        assert_eq!(&*cache.key_at(0, 0), &[1.0; 4]); // prompt
+        assert_eq!(&*cache.key_at(5, 0), &[10.0; 4]); // first generated
+        assert_eq!(&*cache.key_at(7, 0), &[12.0; 4]); // third generated
     }
 }
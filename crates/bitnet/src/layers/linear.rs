@@ -0,0 +1,182 @@
+//! Linear projections for ternary and standard models.
+//!
+//! BitNet models use [`BitLinear`] everywhere. Standard LLaMA/Qwen2 GGUF
+//! models store their projections as Q8_0/Q4_0/Q4_K/Q6_K (or F16/F32)
+//! and use [`DenseLinear`] instead: `y = W · x + b` with W decoded block
+//! by block and accumulated in f32. [`Linear`] lets attention and the FFN
+//! hold either.
+
+use std::sync::Arc;
+
+use crate::compute::ComputeBackend;
+use crate::layers::bitlinear::BitLinear;
+use crate::ops::dequant::{quant_matmul, quant_matvec};
+use crate::tensor::QuantTensor;
+
+/// A standard linear layer over GGML-quantized weights, with optional bias.
+pub struct DenseLinear {
+    /// Weights (out_features × in_features) in their GGUF storage format.
+    weights: QuantTensor,
+    /// Optional bias (Qwen2 uses one on the Q/K/V projections).
+    bias: Option<Vec<f32>>,
+}
+
+impl DenseLinear {
+    /// Create a bias-free layer.
+    pub fn new(weights: QuantTensor) -> Self {
+        Self { weights, bias: None }
+    }
+
+    /// Create a layer with a bias of length `out_features`.
+    pub fn with_bias(weights: QuantTensor, bias: Vec<f32>) -> Self {
+        assert_eq!(bias.len(), weights.rows(), "bias length must match out_features");
+        Self { weights, bias: Some(bias) }
+    }
+
+    /// Output features (rows).
+    pub fn out_features(&self) -> usize { self.weights.rows() }
+
+    /// Input features (columns).
+    pub fn in_features(&self) -> usize { self.weights.cols() }
+
+    /// Access the underlying weights.
+    pub fn weights(&self) -> &QuantTensor { &self.weights }
+
+    /// The bias, if any.
+    pub fn bias(&self) -> Option<&[f32]> { self.bias.as_deref() }
+
+    /// Forward pass for a single vector.
+    pub fn forward(&self, input: &[f32]) -> Vec<f32> {
+        let mut out = quant_matvec(&self.weights, input);
+        self.add_bias(&mut out);
+        out
+    }
+
+    /// Forward pass for `n_rows` vectors (`[n_rows, in_features]` →
+    /// `[n_rows, out_features]`), decoding each weight block once.
+    pub fn forward_rows(&self, inputs: &[f32], n_rows: usize) -> Vec<f32> {
+        assert_eq!(inputs.len(), n_rows * self.in_features(), "input dimension mismatch");
+        if n_rows == 0 {
+            return Vec::new();
+        }
+        let mut out = quant_matmul(&self.weights, inputs, n_rows);
+        self.add_bias(&mut out);
+        out
+    }
+
+    fn add_bias(&self, out: &mut [f32]) {
+        if let Some(bias) = &self.bias {
+            for row in out.chunks_exact_mut(bias.len()) {
+                for (y, b) in row.iter_mut().zip(bias) {
+                    *y += b;
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for DenseLinear {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "DenseLinear({}→{}, {:?}{})",
+            self.in_features(),
+            self.out_features(),
+            self.weights.ggml_type(),
+            if self.bias.is_some() { ", bias" } else { "" },
+        )
+    }
+}
+
+/// A projection inside attention or the FFN: ternary or standard.
+#[derive(Debug)]
+pub enum Linear {
+    /// BitNet ternary projection.
+    Ternary(BitLinear),
+    /// Standard quantized/float projection.
+    Dense(DenseLinear),
+}
+
+impl Linear {
+    /// Output features (rows).
+    pub fn out_features(&self) -> usize {
+        match self {
+            Linear::Ternary(l) => l.out_features(),
+            Linear::Dense(l) => l.out_features(),
+        }
+    }
+
+    /// Input features (columns).
+    pub fn in_features(&self) -> usize {
+        match self {
+            Linear::Ternary(l) => l.in_features(),
+            Linear::Dense(l) => l.in_features(),
+        }
+    }
+
+    /// Forward pass for a single vector.
+    pub fn forward(&self, input: &[f32]) -> Vec<f32> {
+        match self {
+            Linear::Ternary(l) => l.forward(input),
+            Linear::Dense(l) => l.forward(input),
+        }
+    }
+
+    /// Forward pass for `n_rows` vectors at once.
+    pub fn forward_rows(&self, inputs: &[f32], n_rows: usize) -> Vec<f32> {
+        match self {
+            Linear::Ternary(l) => l.forward_rows(inputs, n_rows),
+            Linear::Dense(l) => l.forward_rows(inputs, n_rows),
+        }
+    }
+
+    /// The compute backend of a ternary projection. Dense projections
+    /// don't carry one, so callers fall back to the scalar kernels.
+    pub fn backend(&self) -> Option<&Arc<dyn ComputeBackend>> {
+        match self {
+            Linear::Ternary(l) => l.backend(),
+            Linear::Dense(_) => None,
+        }
+    }
+}
+
+impl From<BitLinear> for Linear {
+    fn from(layer: BitLinear) -> Self { Linear::Ternary(layer) }
+}
+
+impl From<DenseLinear> for Linear {
+    fn from(layer: DenseLinear) -> Self { Linear::Dense(layer) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gguf::GgmlType;
+    use crate::ops::dequant::quantize_q8_0;
+
+    fn f32_weights(values: &[f32], rows: usize, cols: usize) -> QuantTensor {
+        let data: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        QuantTensor::new(GgmlType::F32, data, rows, cols)
+    }
+
+    #[test]
+    fn dense_forward_with_bias() {
+        let layer = DenseLinear::with_bias(f32_weights(&[1.0, 2.0, 0.0, -1.0], 2, 2), vec![0.5, -0.5]);
+        assert_eq!(layer.forward(&[3.0, 4.0]), vec![11.5, -4.5]);
+        assert_eq!(layer.forward_rows(&[3.0, 4.0, 1.0, 0.0], 2), vec![11.5, -4.5, 1.5, -0.5]);
+    }
+
+    #[test]
+    fn dense_q8_forward_rows_matches_forward() {
+        let values: Vec<f32> = (0..4 * 64).map(|i| ((i * 37 % 17) as f32 - 8.0) / 8.0).collect();
+        let layer: Linear = DenseLinear::new(QuantTensor::new(GgmlType::Q8_0, quantize_q8_0(&values), 4, 64)).into();
+        let inputs: Vec<f32> = (0..3 * 64).map(|i| (i as f32 * 0.1).sin()).collect();
+        let batched = layer.forward_rows(&inputs, 3);
+        for (row, input) in inputs.chunks_exact(64).enumerate() {
+            let single = layer.forward(input);
+            for (a, b) in single.iter().zip(&batched[row * 4..row * 4 + 4]) {
+                assert!((a - b).abs() < 1e-5);
+            }
+        }
+    }
+}
@@ -5,9 +5,13 @@
 //! forward pass in `transformer.rs`.
 
 pub mod attention;
+pub mod batch;
 pub mod bitlinear;
+pub mod generation;
 pub mod kv_cache;
+pub mod linear;
 pub mod model;
+pub mod prefix_cache;
 pub mod rmsnorm;
 pub mod rope;
 pub mod sampler;
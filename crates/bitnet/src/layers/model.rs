@@ -10,12 +10,19 @@
 //! The model operates on token ID sequences and produces logit vectors
 //! over the vocabulary for each position.
 
+use std::ops::ControlFlow;
+use std::time::Instant;
+
 use crate::layers::bitlinear::BitLinear;
-use crate::layers::kv_cache::ModelKvCache;
+use crate::layers::linear::DenseLinear;
+use crate::layers::generation::{GenerateOptions, Generation, HiddenHook, StopReason, StreamEvent, TextStream};
+use crate::layers::kv_cache::{KvCacheFormat, ModelKvCache};
+use crate::layers::prefix_cache::PrefixCache;
 use crate::layers::rmsnorm::RmsNorm;
 use crate::layers::sampler::{Sampler, SamplerConfig};
 use crate::layers::transformer::TransformerBlock;
 use crate::tensor::FloatTensor;
+use crate::tokenizer::Tokenizer;
 use rayon::prelude::*;
 
 /// Number of vocabulary entries per parallel chunk for tied embedding projection.
@@ -93,6 +100,8 @@ pub struct TransformerModel {
     vocab_size: usize,
     /// Embedding dimension.
     embed_dim: usize,
+    /// Storage format of the KV caches this model creates.
+    kv_format: KvCacheFormat,
 }
 
 /// Output projection: either a learned weight matrix or tied to embedding.
@@ -102,11 +111,83 @@ pub enum OutputProjection {
     /// Separate learned float projection (embed_dim → vocab_size).
     /// Used when output.weight is F16/F32 (e.g., Falcon3 1.58-bit models).
     Float(FloatTensor),
+    /// Separate learned block-quantized projection (embed_dim → vocab_size),
+    /// e.g. the Q6_K `output.weight` of a standard LLaMA/Qwen2 model.
+    Dense(DenseLinear),
     /// Tied to embedding weights — project by multiplying by E^T.
     /// Stores a reference shape; the embedding table is used directly.
     TiedEmbedding,
 }
 
+/// Which hidden state [`TransformerModel::hidden_states`] returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HiddenLayer {
+    /// The final hidden state, after the final norm (what the output
+    /// projection sees).
+    #[default]
+    Final,
+    /// The output of block `n` (0-based), before any final norm. Middle
+    /// layers often embed better than the last one.
+    Block(usize),
+}
+
+/// How per-token hidden states are reduced to one vector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Pooling {
+    /// Average over all tokens.
+    #[default]
+    Mean,
+    /// The last token's state (it has attended to the whole input).
+    LastToken,
+}
+
+impl Pooling {
+    /// Pool `[n_rows, dim]` hidden states into one `dim` vector.
+    pub fn pool(self, hidden: &[f32], dim: usize) -> Vec<f32> {
+        assert!(!hidden.is_empty() && hidden.len().is_multiple_of(dim), "hidden states must be [n_rows, dim]");
+        match self {
+            Pooling::Mean => {
+                let n_rows = hidden.len() / dim;
+                let mut out = vec![0.0f32; dim];
+                for row in hidden.chunks_exact(dim) {
+                    for (o, h) in out.iter_mut().zip(row) {
+                        *o += h;
+                    }
+                }
+                out.iter_mut().for_each(|o| *o /= n_rows as f32);
+                out
+            }
+            Pooling::LastToken => hidden[hidden.len() - dim..].to_vec(),
+        }
+    }
+
+    /// A unit-length sentence vector from `[n_rows, dim]` hidden states of
+    /// a BOS-prefixed input: rows after the BOS are pooled, then
+    /// L2-normalized. BOS carries no content and its state is
+    /// near-identical for every input, so leaving it out keeps short
+    /// texts apart. A lone BOS row gives a zero vector.
+    pub fn sentence_vector(self, hidden: &[f32], dim: usize) -> Vec<f32> {
+        if hidden.len() <= dim {
+            return vec![0.0; dim];
+        }
+        let mut vector = self.pool(&hidden[dim..], dim);
+        let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            vector.iter_mut().for_each(|x| *x /= norm);
+        }
+        vector
+    }
+}
+
+impl std::fmt::Display for Pooling {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Pooling::Mean => "mean",
+            Pooling::LastToken => "last",
+        })
+    }
+}
+
 impl TransformerModel {
     /// Create a transformer model from its components.
     pub fn new(
@@ -135,6 +216,10 @@ impl TransformerModel {
                 assert_eq!(tensor.shape()[0], vocab_size, "float output proj rows must be vocab_size");
                 assert_eq!(tensor.shape()[1], embed_dim, "float output proj cols must be embed_dim");
             }
+            OutputProjection::Dense(ref proj) => {
+                assert_eq!(proj.in_features(), embed_dim, "output proj input must be embed_dim");
+                assert_eq!(proj.out_features(), vocab_size, "output proj output must be vocab_size");
+            }
             OutputProjection::TiedEmbedding => {}
         }
 
@@ -145,9 +230,21 @@ impl TransformerModel {
             output_proj,
             vocab_size,
             embed_dim,
+            kv_format: KvCacheFormat::F32,
         }
     }
 
+    /// Storage format of the KV caches `create_kv_cache` and the
+    /// generation methods create.
+    pub fn kv_cache_format(&self) -> KvCacheFormat {
+        self.kv_format
+    }
+
+    /// Set the KV cache storage format.
+    pub fn set_kv_cache_format(&mut self, format: KvCacheFormat) {
+        self.kv_format = format;
+    }
+
     /// Vocabulary size.
     pub fn vocab_size(&self) -> usize {
         self.vocab_size
@@ -178,49 +275,15 @@ impl TransformerModel {
         assert!(seq_len > 0, "must have at least one token");
 
         // 1. Embedding lookup
-        let mut hidden = Vec::with_capacity(seq_len * self.embed_dim);
-        let embed_data = self.embedding.data();
-        for &tok in tokens {
-            assert!(
-                (tok as usize) < self.vocab_size,
-                "token ID {tok} out of range (vocab_size={})",
-                self.vocab_size
-            );
-            let start = tok as usize * self.embed_dim;
-            hidden.extend_from_slice(&embed_data[start..start + self.embed_dim]);
-        }
+        let mut hidden = self.embed(tokens);
 
         // 2. Pass through transformer blocks
         for block in &self.blocks {
             hidden = block.forward(&hidden, seq_len, start_pos);
         }
 
-        // 3. Final norm (per token)
-        let mut normed = Vec::with_capacity(hidden.len());
-        for t in 0..seq_len {
-            let start = t * self.embed_dim;
-            let token_hidden = &hidden[start..start + self.embed_dim];
-            normed.extend_from_slice(&self.final_norm.forward(token_hidden));
-        }
-
-        // 4. Output projection → logits
-        match &self.output_proj {
-            OutputProjection::Linear(proj) => {
-                let mut logits = Vec::with_capacity(seq_len * self.vocab_size);
-                for t in 0..seq_len {
-                    let start = t * self.embed_dim;
-                    let token_hidden = &normed[start..start + self.embed_dim];
-                    logits.extend_from_slice(&proj.forward(token_hidden));
-                }
-                logits
-            }
-            OutputProjection::Float(ref weight) => {
-                float_output_projection(&normed, weight.data(), seq_len, self.vocab_size, self.embed_dim)
-            }
-            OutputProjection::TiedEmbedding => {
-                float_output_projection(&normed, embed_data, seq_len, self.vocab_size, self.embed_dim)
-            }
-        }
+        // 3-4. Final norm and output projection → logits
+        self.logits(&hidden, seq_len)
     }
 
     /// Forward pass returning only the logits for the last token.
@@ -233,6 +296,39 @@ impl TransformerModel {
         all_logits[last_start..last_start + self.vocab_size].to_vec()
     }
 
+    /// Hidden states for `tokens` (no KV cache, positions from 0).
+    ///
+    /// Returns `[seq_len, embed_dim]`: the final-normed states for
+    /// [`HiddenLayer::Final`], or the raw output of block `n` for
+    /// [`HiddenLayer::Block`] (later blocks are skipped).
+    pub fn hidden_states(&self, tokens: &[u32], layer: HiddenLayer) -> Vec<f32> {
+        let seq_len = tokens.len();
+        assert!(seq_len > 0, "must have at least one token");
+        let n_blocks = match layer {
+            HiddenLayer::Final => self.blocks.len(),
+            HiddenLayer::Block(n) => {
+                assert!(n < self.blocks.len(), "block {n} out of range ({} blocks)", self.blocks.len());
+                n + 1
+            }
+        };
+
+        let mut hidden = self.embed(tokens);
+        for block in &self.blocks[..n_blocks] {
+            hidden = block.forward(&hidden, seq_len, 0);
+        }
+
+        if layer == HiddenLayer::Final {
+            hidden = self.final_norm_rows(&hidden);
+        }
+        hidden
+    }
+
+    /// One `embed_dim` vector for `tokens`: [`hidden_states`](Self::hidden_states)
+    /// reduced with `pooling`.
+    pub fn pooled_hidden(&self, tokens: &[u32], layer: HiddenLayer, pooling: Pooling) -> Vec<f32> {
+        pooling.pool(&self.hidden_states(tokens, layer), self.embed_dim)
+    }
+
     /// Forward pass with KV cache for incremental generation.
     ///
     /// `tokens`: the new token(s) to process.
@@ -243,7 +339,57 @@ impl TransformerModel {
         assert!(seq_len > 0, "must have at least one token");
 
         // 1. Embedding lookup
-        let mut hidden = Vec::with_capacity(seq_len * self.embed_dim);
+        let mut hidden = self.embed(tokens);
+
+        // 2. Pass through transformer blocks with cache
+        for (layer_idx, block) in self.blocks.iter().enumerate() {
+            hidden = block.forward_cached(&hidden, seq_len, cache.layer_mut(layer_idx));
+        }
+
+        // 3-4. Final norm and output projection → logits
+        self.logits(&hidden, seq_len)
+    }
+
+    /// Like [`forward_cached`](Self::forward_cached), but returning the
+    /// last block's output `[seq_len, embed_dim]` instead of logits.
+    pub fn forward_cached_hidden(&self, tokens: &[u32], cache: &mut ModelKvCache) -> Vec<f32> {
+        let seq_len = tokens.len();
+        assert!(seq_len > 0, "must have at least one token");
+
+        let mut hidden = self.embed(tokens);
+        for (layer_idx, block) in self.blocks.iter().enumerate() {
+            hidden = block.forward_cached(&hidden, seq_len, cache.layer_mut(layer_idx));
+        }
+        hidden
+    }
+
+    /// Batched decode step: one new token for each of several independent
+    /// sequences, each with its own cache.
+    ///
+    /// `tokens[b]` is the next token of the sequence in `caches[b]`; the
+    /// sequences may be at different lengths. All projections run as one
+    /// ternary matmul over the batch, so this is much cheaper than one
+    /// [`forward_cached`](Self::forward_cached) call per sequence.
+    /// Returns: logits `[batch, vocab_size]`, identical to what
+    /// `forward_cached` gives for each sequence alone.
+    pub fn forward_batch(&self, tokens: &[u32], caches: &mut [&mut ModelKvCache]) -> Vec<f32> {
+        let batch = tokens.len();
+        assert_eq!(batch, caches.len(), "one cache per token");
+        if batch == 0 {
+            return Vec::new();
+        }
+
+        let mut hidden = self.embed(tokens);
+        for (layer_idx, block) in self.blocks.iter().enumerate() {
+            let mut layer_caches: Vec<_> = caches.iter_mut().map(|c| c.layer_mut(layer_idx)).collect();
+            hidden = block.forward_batch_cached(&hidden, &mut layer_caches);
+        }
+        self.logits(&hidden, batch)
+    }
+
+    /// Embedding rows for `tokens`, `[tokens.len(), embed_dim]`.
+    fn embed(&self, tokens: &[u32]) -> Vec<f32> {
+        let mut hidden = Vec::with_capacity(tokens.len() * self.embed_dim);
         let embed_data = self.embedding.data();
         for &tok in tokens {
             assert!(
@@ -254,45 +400,47 @@ impl TransformerModel {
             let start = tok as usize * self.embed_dim;
             hidden.extend_from_slice(&embed_data[start..start + self.embed_dim]);
         }
+        hidden
+    }
 
-        // 2. Pass through transformer blocks with cache
-        for (layer_idx, block) in self.blocks.iter().enumerate() {
-            hidden = block.forward_cached(&hidden, seq_len, cache.layer_mut(layer_idx));
-        }
+    /// Final norm and output projection of `n_rows` hidden states.
+    fn logits(&self, hidden: &[f32], n_rows: usize) -> Vec<f32> {
+        self.project(&self.final_norm_rows(hidden), n_rows)
+    }
 
-        // 3. Final norm (per token)
+    /// The final norm applied to each `embed_dim` row of `hidden`.
+    fn final_norm_rows(&self, hidden: &[f32]) -> Vec<f32> {
         let mut normed = Vec::with_capacity(hidden.len());
-        for t in 0..seq_len {
-            let start = t * self.embed_dim;
-            let token_hidden = &hidden[start..start + self.embed_dim];
+        for token_hidden in hidden.chunks_exact(self.embed_dim) {
             normed.extend_from_slice(&self.final_norm.forward(token_hidden));
         }
+        normed
+    }
 
-        // 4. Output projection → logits
+    /// Output projection of `n_rows` final-normed hidden states.
+    fn project(&self, normed: &[f32], n_rows: usize) -> Vec<f32> {
         match &self.output_proj {
-            OutputProjection::Linear(proj) => {
-                let mut logits = Vec::with_capacity(seq_len * self.vocab_size);
-                for t in 0..seq_len {
-                    let start = t * self.embed_dim;
-                    let token_hidden = &normed[start..start + self.embed_dim];
-                    logits.extend_from_slice(&proj.forward(token_hidden));
-                }
-                logits
-            }
+            OutputProjection::Linear(proj) => proj.forward_rows(normed, n_rows),
+            OutputProjection::Dense(proj) => proj.forward_rows(normed, n_rows),
             OutputProjection::Float(ref weight) => {
-                float_output_projection(&normed, weight.data(), seq_len, self.vocab_size, self.embed_dim)
+                float_output_projection(normed, weight.data(), n_rows, self.vocab_size, self.embed_dim)
             }
             OutputProjection::TiedEmbedding => {
-                float_output_projection(&normed, embed_data, seq_len, self.vocab_size, self.embed_dim)
+                float_output_projection(normed, self.embedding.data(), n_rows, self.vocab_size, self.embed_dim)
             }
         }
     }
 
-    /// Create a KV cache sized for this model.
+    /// Create a KV cache sized for this model, in its KV cache format.
     pub fn create_kv_cache(&self, max_seq_len: usize) -> ModelKvCache {
+        self.create_kv_cache_with(max_seq_len, self.kv_format)
+    }
+
+    /// Create a KV cache sized for this model in the given format.
+    pub fn create_kv_cache_with(&self, max_seq_len: usize, format: KvCacheFormat) -> ModelKvCache {
         let n_kv_heads = self.blocks[0].attention().n_kv_heads();
         let head_dim = self.blocks[0].attention().head_dim();
-        ModelKvCache::new(self.blocks.len(), n_kv_heads, head_dim, max_seq_len)
+        ModelKvCache::with_format(self.blocks.len(), n_kv_heads, head_dim, max_seq_len, format)
     }
 
     /// Generate tokens autoregressively.
@@ -312,35 +460,225 @@ impl TransformerModel {
         seed: u64,
         stop_token: Option<u32>,
     ) -> Vec<u32> {
-        assert!(!prompt.is_empty(), "prompt must not be empty");
-        let max_seq_len = prompt.len() + max_tokens;
-        let mut cache = self.create_kv_cache(max_seq_len);
-        let mut sampler = Sampler::new(sampler_config, seed);
+        let options = GenerateOptions {
+            max_tokens,
+            sampler: sampler_config,
+            seed,
+            stop_tokens: stop_token.into_iter().collect(),
+            ..Default::default()
+        };
+        let generation = self.generate_stream(prompt, &options, None, |_| ControlFlow::Continue(()));
         let mut sequence = prompt.to_vec();
+        sequence.extend(generation.tokens);
+        sequence
+    }
 
-        // Prefill: process all prompt tokens at once
-        let prefill_logits = self.forward_cached(prompt, &mut cache);
-        let last_logits_start = (prompt.len() - 1) * self.vocab_size;
-        let last_logits = &prefill_logits[last_logits_start..last_logits_start + self.vocab_size];
-        let mut next_token = sampler.sample(last_logits);
+    /// Generate tokens, passing each to `on_token` as soon as it is sampled.
+    ///
+    /// With a `tokenizer`, events carry decoded text and
+    /// `options.stop_sequences` apply; without one, text is empty.
+    /// The KV cache is sized for the prompt plus `options.max_tokens`.
+    pub fn generate_stream<F>(
+        &self,
+        prompt: &[u32],
+        options: &GenerateOptions,
+        tokenizer: Option<&Tokenizer>,
+        on_token: F,
+    ) -> Generation
+    where
+        F: FnMut(StreamEvent<'_>) -> ControlFlow<()>,
+    {
+        let mut cache = self.create_kv_cache(prompt.len() + options.max_tokens);
+        self.generate_cached(prompt, &mut cache, options, tokenizer, on_token)
+    }
 
-        if stop_token == Some(next_token) {
-            return sequence;
+    /// Like [`generate_stream`](Self::generate_stream), but starting from
+    /// the longest prefix of `prompt` in `prefixes` and prefilling only
+    /// the rest. The state after the prompt and generated tokens is added
+    /// to `prefixes`, so the next turn of the conversation reuses it.
+    pub fn generate_from_cache<F>(
+        &self,
+        prompt: &[u32],
+        prefixes: &mut PrefixCache,
+        options: &GenerateOptions,
+        tokenizer: Option<&Tokenizer>,
+        on_token: F,
+    ) -> Generation
+    where
+        F: FnMut(StreamEvent<'_>) -> ControlFlow<()>,
+    {
+        assert!(!prompt.is_empty(), "prompt must not be empty");
+        let mut cache = self.create_kv_cache(prompt.len() + options.max_tokens);
+        let reused = self.restore_prefix(prompt, prefixes, &mut cache);
+        let generation = self.generate_cached(&prompt[reused..], &mut cache, options, tokenizer, on_token);
+
+        let mut seen: Vec<u32> = prompt.iter().chain(&generation.tokens).copied().collect();
+        seen.truncate(cache.seq_len());
+        if seen.len() > reused {
+            prefixes.insert(&seen, cache.snapshot());
         }
-        sequence.push(next_token);
+        generation
+    }
 
-        // Decode: one token at a time
-        for _ in 1..max_tokens {
-            let logits = self.forward_cached(&[next_token], &mut cache);
-            next_token = sampler.sample(&logits);
+    /// Prefill `prefix` — a shared system prompt, say — into `prefixes`
+    /// so generations whose prompts start with it skip that work.
+    pub fn cache_prefix(&self, prefix: &[u32], prefixes: &mut PrefixCache) {
+        if prefix.is_empty() || prefixes.contains(prefix) {
+            return;
+        }
+        let mut cache = self.create_kv_cache(prefix.len());
+        let reused = self.restore_prefix(prefix, prefixes, &mut cache);
+        self.forward_cached(&prefix[reused..], &mut cache);
+        prefixes.insert(prefix, cache.snapshot());
+    }
 
-            if stop_token == Some(next_token) {
-                break;
+    /// Restore the longest cached proper prefix of `tokens` into `cache`,
+    /// returning its length. At least one token is left to prefill, since
+    /// sampling needs its logits.
+    fn restore_prefix(&self, tokens: &[u32], prefixes: &mut PrefixCache, cache: &mut ModelKvCache) -> usize {
+        match prefixes.longest_prefix(tokens, tokens.len() - 1) {
+            Some(hit) if cache.fits(&hit.snapshot) => {
+                cache.restore(&hit.snapshot);
+                hit.len
             }
-            sequence.push(next_token);
+            _ => 0,
         }
+    }
 
-        sequence
+    /// Like [`generate_stream`](Self::generate_stream), but continuing
+    /// from whatever `cache` already holds — for multi-turn sessions.
+    /// The prompt and every generated token except the last are left in
+    /// the cache.
+    pub fn generate_cached<F>(
+        &self,
+        prompt: &[u32],
+        cache: &mut ModelKvCache,
+        options: &GenerateOptions,
+        tokenizer: Option<&Tokenizer>,
+        on_token: F,
+    ) -> Generation
+    where
+        F: FnMut(StreamEvent<'_>) -> ControlFlow<()>,
+    {
+        self.decode(prompt, cache, options, tokenizer, None, on_token)
+    }
+
+    /// Like [`generate_cached`](Self::generate_cached), with `hook` seeing
+    /// the prompt's final hidden states after prefill and adjusting the
+    /// hidden state behind every sampled token.
+    pub fn generate_hooked<F>(
+        &self,
+        prompt: &[u32],
+        cache: &mut ModelKvCache,
+        options: &GenerateOptions,
+        tokenizer: Option<&Tokenizer>,
+        hook: &mut dyn HiddenHook,
+        on_token: F,
+    ) -> Generation
+    where
+        F: FnMut(StreamEvent<'_>) -> ControlFlow<()>,
+    {
+        self.decode(prompt, cache, options, tokenizer, Some(hook), on_token)
+    }
+
+    fn decode<F>(
+        &self,
+        prompt: &[u32],
+        cache: &mut ModelKvCache,
+        options: &GenerateOptions,
+        tokenizer: Option<&Tokenizer>,
+        mut hook: Option<&mut dyn HiddenHook>,
+        mut on_token: F,
+    ) -> Generation
+    where
+        F: FnMut(StreamEvent<'_>) -> ControlFlow<()>,
+    {
+        assert!(!prompt.is_empty(), "prompt must not be empty");
+        let started = Instant::now();
+        let mut sampler = Sampler::new(options.sampler.clone(), options.seed);
+        let mut stream = tokenizer.map(|t| TextStream::new(t, &options.stop_sequences));
+        let mut tokens = Vec::new();
+        if cache.seq_len() + prompt.len() > cache.max_seq_len() {
+            return Generation { tokens, text: String::new(), stop_reason: StopReason::ContextFull };
+        }
+
+        // Prefill: process all prompt tokens at once
+        let Some(mut logits) = self.next_logits(prompt, cache, hook.as_deref_mut(), true) else {
+            return Generation { tokens, text: String::new(), stop_reason: StopReason::Cancelled };
+        };
+
+        // Decode: one token at a time
+        let stop_reason = loop {
+            if tokens.len() >= options.max_tokens {
+                break StopReason::MaxTokens;
+            }
+            let token = sampler.sample(&logits);
+            if options.stop_tokens.contains(&token) {
+                break StopReason::StopToken(token);
+            }
+            tokens.push(token);
+
+            let step = stream.as_mut().map(|s| s.push(token));
+            let text = step.as_ref().map_or("", |s| s.text.as_str());
+            let flow = on_token(StreamEvent { token: Some(token), text });
+            if let Some(stop) = step.and_then(|s| s.stop) {
+                break StopReason::StopSequence(stop);
+            }
+            if flow.is_break() {
+                break StopReason::Cancelled;
+            }
+            if options.max_time.is_some_and(|limit| started.elapsed() >= limit) {
+                break StopReason::MaxTime;
+            }
+            if tokens.len() >= options.max_tokens {
+                break StopReason::MaxTokens;
+            }
+            if cache.seq_len() >= cache.max_seq_len() {
+                break StopReason::ContextFull;
+            }
+            logits = self
+                .next_logits(&[token], cache, hook.as_deref_mut(), false)
+                .expect("only prefill can stop");
+        };
+
+        let text = match stream {
+            Some(mut stream) => {
+                if !matches!(stop_reason, StopReason::StopSequence(_)) {
+                    let rest = stream.finish();
+                    if !rest.is_empty() {
+                        let _ = on_token(StreamEvent { token: None, text: &rest });
+                    }
+                }
+                stream.text().to_string()
+            }
+            None => String::new(),
+        };
+        Generation { tokens, text, stop_reason }
+    }
+
+    /// Logits of the last of `tokens` after running them through `cache`,
+    /// passing the final hidden states through `hook` when there is one.
+    /// `None` if the hook stopped generation at prefill.
+    fn next_logits(
+        &self,
+        tokens: &[u32],
+        cache: &mut ModelKvCache,
+        hook: Option<&mut (dyn HiddenHook + '_)>,
+        prefill: bool,
+    ) -> Option<Vec<f32>> {
+        let Some(hook) = hook else {
+            let mut logits = self.forward_cached(tokens, cache);
+            logits.drain(..(tokens.len() - 1) * self.vocab_size);
+            return Some(logits);
+        };
+
+        let normed = self.final_norm_rows(&self.forward_cached_hidden(tokens, cache));
+        if prefill && hook.prefill(&normed).is_break() {
+            return None;
+        }
+        let mut last = normed[normed.len() - self.embed_dim..].to_vec();
+        hook.step(&mut last);
+        Some(self.project(&last, 1))
     }
 }
 
@@ -355,6 +693,7 @@ impl std::fmt::Debug for TransformerModel {
             match &self.output_proj {
                 OutputProjection::Linear(_) => "linear",
                 OutputProjection::Float(_) => "float",
+                OutputProjection::Dense(_) => "dense",
                 OutputProjection::TiedEmbedding => "tied",
             },
         )
@@ -366,7 +705,7 @@ impl std::fmt::Debug for TransformerModel {
 // ---------------------------------------------------------------------------
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use crate::layers::attention::MultiHeadAttention;
     use crate::layers::swiglu::SwiGLU;
@@ -426,7 +765,7 @@ mod tests {
     }
 
     // Build a tiny test model: vocab=8, embed=8, 1 layer, 2 heads
-    fn make_test_model(n_layers: usize, tied: bool) -> TransformerModel {
+    pub(crate) fn make_test_model(n_layers: usize, tied: bool) -> TransformerModel {
         let vocab_size = 8;
         let embed_dim = 8;
         let n_heads = 2;
@@ -646,6 +985,30 @@ mod tests {
         }
     }
 
+    #[test]
+    fn forward_batch_matches_forward_cached() {
+        let model = make_random_model();
+        let prompts: [&[u32]; 3] = [&[1, 2, 3], &[4], &[5, 6, 7, 8, 9]];
+        let next = [10u32, 11, 12];
+
+        let mut solo = Vec::new();
+        let mut caches = Vec::new();
+        for (prompt, &tok) in prompts.iter().zip(&next) {
+            let mut a = model.create_kv_cache(16);
+            model.forward_cached(prompt, &mut a);
+            solo.extend(model.forward_cached(&[tok], &mut a));
+
+            let mut b = model.create_kv_cache(16);
+            model.forward_cached(prompt, &mut b);
+            caches.push(b);
+        }
+
+        let mut refs: Vec<&mut ModelKvCache> = caches.iter_mut().collect();
+        let batched = model.forward_batch(&next, &mut refs);
+        assert_eq!(batched, solo);
+        assert_eq!(caches.iter().map(|c| c.seq_len()).collect::<Vec<_>>(), vec![4, 2, 6]);
+    }
+
     #[test]
     fn create_kv_cache_correct_size() {
         let model = make_test_model(3, false);
@@ -654,6 +1017,109 @@ mod tests {
         assert_eq!(cache.seq_len(), 0);
     }
 
+    // -- Int8 KV cache --
+
+    /// Ternary weights from a fixed LCG, so attention is non-trivial.
+    fn make_random_bitlinear(rows: usize, cols: usize, seed: u64) -> BitLinear {
+        let mut state = seed;
+        let weights: Vec<i8> = (0..rows * cols)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                ((state >> 33) % 3) as i8 - 1
+            })
+            .collect();
+        let mut proj = make_bitlinear(&weights, rows, cols, 0.05);
+        proj.set_backend(crate::compute::detect());
+        proj
+    }
+
+    /// vocab=32, embed=80, 2 query heads sharing 1 KV head of dim 40 (one
+    /// full int8 block and one partial), 2 layers.
+    fn make_random_model() -> TransformerModel {
+        let (vocab_size, embed_dim, n_heads, n_kv_heads, head_dim, intermediate) = (32, 80, 2, 1, 40, 96);
+        let embed_data: Vec<f32> = (0..vocab_size * embed_dim)
+            .map(|i| ((i * 7919 % 211) as f32 - 105.0) / 50.0)
+            .collect();
+        let embedding = FloatTensor::new(embed_data, vec![vocab_size, embed_dim]);
+        let blocks = (0..2u64)
+            .map(|l| {
+                let seed = |i: u64| l * 100 + i;
+                let attention = MultiHeadAttention::new(
+                    make_random_bitlinear(n_heads * head_dim, embed_dim, seed(1)),
+                    make_random_bitlinear(n_kv_heads * head_dim, embed_dim, seed(2)),
+                    make_random_bitlinear(n_kv_heads * head_dim, embed_dim, seed(3)),
+                    make_random_bitlinear(embed_dim, n_heads * head_dim, seed(4)),
+                    n_heads, n_kv_heads, head_dim, 10000.0,
+                );
+                let ffn = SwiGLU::new(
+                    make_random_bitlinear(intermediate, embed_dim, seed(5)),
+                    make_random_bitlinear(intermediate, embed_dim, seed(6)),
+                    make_random_bitlinear(embed_dim, intermediate, seed(7)),
+                );
+                TransformerBlock::new(
+                    RmsNorm::new(vec![1.0; embed_dim], 1e-5),
+                    attention,
+                    RmsNorm::new(vec![1.0; embed_dim], 1e-5),
+                    ffn,
+                )
+            })
+            .collect();
+        let final_norm = RmsNorm::new(vec![1.0; embed_dim], 1e-5);
+        TransformerModel::new(embedding, blocks, final_norm, OutputProjection::TiedEmbedding)
+    }
+
+    /// Decode `tokens` one at a time; returns every step's logits and the
+    /// perplexity of each next token.
+    fn decode_perplexity(model: &TransformerModel, tokens: &[u32], format: KvCacheFormat) -> (Vec<Vec<f32>>, f64) {
+        let mut cache = model.create_kv_cache_with(tokens.len(), format);
+        let mut all = Vec::new();
+        let mut nll = 0.0f64;
+        for pair in tokens.windows(2) {
+            let logits = model.forward_cached(&pair[..1], &mut cache);
+            let max = logits.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+            let log_sum = logits.iter().map(|&l| ((l - max) as f64).exp()).sum::<f64>().ln();
+            nll -= (logits[pair[1] as usize] - max) as f64 - log_sum;
+            all.push(logits);
+        }
+        (all, (nll / (tokens.len() - 1) as f64).exp())
+    }
+
+    #[test]
+    fn int8_kv_cache_matches_f32() {
+        let model = make_random_model();
+        let tokens: Vec<u32> = (0..48u32).map(|i| (i * 13 + i * i) % 32).collect();
+
+        let (f32_logits, f32_ppl) = decode_perplexity(&model, &tokens, KvCacheFormat::F32);
+        let (int8_logits, int8_ppl) = decode_perplexity(&model, &tokens, KvCacheFormat::Int8);
+
+        let rel = (int8_ppl - f32_ppl).abs() / f32_ppl;
+        assert!(rel < 0.01, "perplexity f32 {f32_ppl:.4} vs int8 {int8_ppl:.4}");
+
+        let mut max_diff = 0.0f32;
+        let mut argmax_agree = 0;
+        for (a, b) in f32_logits.iter().zip(&int8_logits) {
+            max_diff = a.iter().zip(b).map(|(x, y)| (x - y).abs()).fold(max_diff, f32::max);
+            argmax_agree += usize::from(Sampler::greedy().sample(a) == Sampler::greedy().sample(b));
+        }
+        let spread = f32_logits.iter().flatten().fold(0.0f32, |m, x| m.max(x.abs()));
+        assert!(max_diff < 0.05 * spread, "max logit diff {max_diff} (logit range {spread})");
+        assert!(argmax_agree * 10 >= f32_logits.len() * 9, "{argmax_agree}/{} argmax agree", f32_logits.len());
+    }
+
+    #[test]
+    fn model_kv_format_selects_cache() {
+        let mut model = make_test_model(2, false);
+        assert_eq!(model.create_kv_cache(16).format(), KvCacheFormat::F32);
+        model.set_kv_cache_format(KvCacheFormat::Int8);
+        assert_eq!(model.kv_cache_format(), KvCacheFormat::Int8);
+        let cache = model.create_kv_cache(16);
+        assert_eq!(cache.format(), KvCacheFormat::Int8);
+        assert!(cache.memory_bytes() < model.create_kv_cache_with(16, KvCacheFormat::F32).memory_bytes());
+
+        let out = model.generate(&[0, 1], 4, SamplerConfig::greedy(), 0, None);
+        assert_eq!(out.len(), 2 + 4);
+    }
+
     // -- Generation --
 
     #[test]
@@ -717,6 +1183,202 @@ mod tests {
         }
     }
 
+    #[test]
+    fn generate_stream_yields_each_token() {
+        let model = make_test_model(1, false);
+        let options = GenerateOptions::new(6, SamplerConfig::greedy());
+        let mut streamed = Vec::new();
+        let generation = model.generate_stream(&[0, 1], &options, None, |event| {
+            streamed.push(event.token.unwrap());
+            ControlFlow::Continue(())
+        });
+        assert_eq!(generation.stop_reason, StopReason::MaxTokens);
+        assert_eq!(streamed, generation.tokens);
+        assert_eq!(generation.tokens.len(), 6);
+        assert_eq!(model.generate(&[0, 1], 6, SamplerConfig::greedy(), 1, None)[2..], streamed[..]);
+    }
+
+    /// Records the prompt rows and forces every sampled token to `force`.
+    struct ForceToken {
+        prompt_rows: usize,
+        force: Option<usize>,
+    }
+
+    impl HiddenHook for ForceToken {
+        fn prefill(&mut self, hidden: &[f32]) -> ControlFlow<()> {
+            self.prompt_rows = hidden.len() / 8;
+            if self.force.is_some() { ControlFlow::Continue(()) } else { ControlFlow::Break(()) }
+        }
+
+        fn step(&mut self, hidden: &mut [f32]) {
+            hidden.fill(0.0);
+            hidden[self.force.unwrap()] = 10.0;
+        }
+    }
+
+    #[test]
+    fn generate_hooked_steers_and_stops() {
+        let model = make_test_model(1, false);
+        let options = GenerateOptions::new(4, SamplerConfig::greedy());
+
+        let mut hook = ForceToken { prompt_rows: 0, force: Some(5) };
+        let mut cache = model.create_kv_cache(8);
+        let steered = model.generate_hooked(&[0, 1, 2], &mut cache, &options, None, &mut hook, |_| ControlFlow::Continue(()));
+        assert_eq!(hook.prompt_rows, 3);
+        assert_eq!(steered.tokens, vec![5; 4]);
+
+        let mut hook = ForceToken { prompt_rows: 0, force: None };
+        let mut cache = model.create_kv_cache(8);
+        let silent = model.generate_hooked(&[0, 1], &mut cache, &options, None, &mut hook, |_| ControlFlow::Continue(()));
+        assert!(silent.tokens.is_empty());
+        assert_eq!(silent.stop_reason, StopReason::Cancelled);
+        assert_eq!(cache.seq_len(), 2);
+    }
+
+    #[test]
+    fn generate_stream_cancel_and_stop_tokens() {
+        let model = make_test_model(1, false);
+        let options = GenerateOptions::new(20, SamplerConfig::top_k(8, 5.0));
+
+        let mut calls = 0;
+        let generation = model.generate_stream(&[0], &options, None, |_| {
+            calls += 1;
+            if calls == 3 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+        });
+        assert_eq!(generation.stop_reason, StopReason::Cancelled);
+        assert_eq!(generation.tokens.len(), 3);
+
+        let free = model.generate_stream(&[0], &options, None, |_| ControlFlow::Continue(()));
+        let stop = free.tokens[2];
+        let options = GenerateOptions { stop_tokens: vec![99, stop], ..options };
+        let stopped = model.generate_stream(&[0], &options, None, |_| ControlFlow::Continue(()));
+        assert_eq!(stopped.stop_reason, StopReason::StopToken(stop));
+        assert!(!stopped.tokens.contains(&stop));
+        assert_eq!(stopped.tokens[..], free.tokens[..stopped.tokens.len()]);
+    }
+
+    #[test]
+    fn generate_stream_stop_sequence_and_text() {
+        let model = make_test_model(1, false);
+        let tokenizer = crate::layers::generation::tests::make_tokenizer();
+        // Only text-bearing tokens (3..8) can be sampled.
+        let mut sampler = SamplerConfig::top_k(8, 5.0);
+        for banned in 0..3 {
+            sampler.logit_bias.insert(banned, f32::NEG_INFINITY);
+        }
+        let options = GenerateOptions::new(12, sampler);
+        let free = model.generate_stream(&[0], &options, Some(&tokenizer), |_| ControlFlow::Continue(()));
+        assert_eq!(free.text, tokenizer.decode(&free.tokens));
+
+        // Stop on the text of the fourth token onwards.
+        let cut = tokenizer.decode(&free.tokens[..3]).len();
+        let stop = free.text[cut..].chars().take(2).collect::<String>();
+        let options = GenerateOptions { stop_sequences: vec![stop.clone()], ..options };
+        let mut streamed = String::new();
+        let stopped = model.generate_stream(&[0], &options, Some(&tokenizer), |event| {
+            streamed.push_str(event.text);
+            ControlFlow::Continue(())
+        });
+        assert_eq!(stopped.stop_reason, StopReason::StopSequence(stop.clone()));
+        assert_eq!(streamed, stopped.text);
+        assert!(free.text.starts_with(&stopped.text));
+        assert!(!stopped.text.contains(&stop));
+        assert!(stopped.text.len() <= cut);
+    }
+
+    #[test]
+    fn generate_stream_limits() {
+        let model = make_test_model(1, false);
+        let options = GenerateOptions {
+            max_time: Some(std::time::Duration::ZERO),
+            ..GenerateOptions::new(10, SamplerConfig::greedy())
+        };
+        let generation = model.generate_stream(&[0], &options, None, |_| ControlFlow::Continue(()));
+        assert_eq!(generation.stop_reason, StopReason::MaxTime);
+        assert_eq!(generation.tokens.len(), 1);
+
+        let mut cache = model.create_kv_cache(4);
+        let options = GenerateOptions::new(10, SamplerConfig::greedy());
+        let generation = model.generate_cached(&[0, 1], &mut cache, &options, None, |_| ControlFlow::Continue(()));
+        assert_eq!(generation.stop_reason, StopReason::ContextFull);
+        assert_eq!(generation.tokens.len(), 3);
+        assert_eq!(cache.seq_len(), 4);
+
+        let generation = model.generate_cached(&[0], &mut cache, &options, None, |_| ControlFlow::Continue(()));
+        assert_eq!(generation.stop_reason, StopReason::ContextFull);
+        assert!(generation.tokens.is_empty());
+    }
+
+    #[test]
+    fn generate_from_cache_matches_cold_generation() {
+        let model = make_test_model(2, false);
+        let options = GenerateOptions::new(4, SamplerConfig::greedy());
+        let mut prefixes = PrefixCache::default();
+        let system = [0u32, 1, 2, 3];
+        model.cache_prefix(&system, &mut prefixes);
+        assert!(prefixes.contains(&system));
+
+        let turn1 = [0u32, 1, 2, 3, 4, 5];
+        let cold = model.generate_stream(&turn1, &options, None, |_| ControlFlow::Continue(()));
+        let warm = model.generate_from_cache(&turn1, &mut prefixes, &options, None, |_| ControlFlow::Continue(()));
+        assert_eq!(warm.tokens, cold.tokens);
+        assert_eq!(prefixes.stats().reused_tokens, 4);
+
+        // The next turn extends the first turn's prompt and reply.
+        let mut turn2: Vec<u32> = turn1.to_vec();
+        turn2.extend(&warm.tokens);
+        turn2.push(6);
+        let cold = model.generate_stream(&turn2, &options, None, |_| ControlFlow::Continue(()));
+        let warm = model.generate_from_cache(&turn2, &mut prefixes, &options, None, |_| ControlFlow::Continue(()));
+        assert_eq!(warm.tokens, cold.tokens);
+        assert_eq!(prefixes.stats().reused_tokens, 4 + turn1.len() as u64 + 3);
+
+        // Same logits either way.
+        let mut restored = model.create_kv_cache(16);
+        let hit = prefixes.longest_prefix(&turn2, turn2.len() - 1).unwrap();
+        restored.restore(&hit.snapshot);
+        let mut fresh = model.create_kv_cache(16);
+        model.forward_cached(&turn2[..hit.len], &mut fresh);
+        let a = model.forward_cached(&turn2[hit.len..], &mut restored);
+        let b = model.forward_cached(&turn2[hit.len..], &mut fresh);
+        for (x, y) in a.iter().zip(&b) {
+            assert!((x - y).abs() < 1e-5, "{x} vs {y}");
+        }
+    }
+
+    // -- Hidden states --
+
+    #[test]
+    fn hidden_states_final_feeds_logits() {
+        let model = make_test_model(2, false);
+        let hidden = model.hidden_states(&[0, 3, 5], HiddenLayer::Final);
+        assert_eq!(hidden.len(), 3 * model.embed_dim());
+        let logits = model.forward(&[0, 3, 5], 0);
+        for (a, b) in model.logits(&hidden, 3).iter().zip(&logits) {
+            assert!((a - b).abs() < 1e-4, "{a} vs {b}");
+        }
+    }
+
+    #[test]
+    fn hidden_states_block_stops_early() {
+        let three = make_test_model(3, false);
+        let early = three.hidden_states(&[1, 2], HiddenLayer::Block(0));
+        let manual = three.blocks[0].forward(&three.embed(&[1, 2]), 2, 0);
+        assert_eq!(early, manual);
+        assert_ne!(early, three.hidden_states(&[1, 2], HiddenLayer::Block(2)));
+    }
+
+    #[test]
+    fn pooling_mean_and_last() {
+        let hidden = [1.0, 2.0, 3.0, 6.0];
+        assert_eq!(Pooling::Mean.pool(&hidden, 2), vec![2.0, 4.0]);
+        assert_eq!(Pooling::LastToken.pool(&hidden, 2), vec![3.0, 6.0]);
+
+        let model = make_test_model(1, false);
+        let pooled = model.pooled_hidden(&[4, 6], HiddenLayer::Final, Pooling::LastToken);
+        assert_eq!(pooled, model.hidden_states(&[4, 6], HiddenLayer::Final)[8..].to_vec());
+    }
+
     #[test]
     fn generate_tied_embedding() {
         let model = make_test_model(1, true);
@@ -0,0 +1,587 @@
+//! Prompt prefix cache — reuse attention state across generations.
+//!
+//! Agent turns re-send a long identical system prompt and history. The
+//! prefix cache keeps [`KvSnapshot`]s of the KV cache after token
+//! sequences it has seen, keyed by a hash of the sequence; a new
+//! generation restores the longest cached prefix of its prompt and only
+//! prefills the rest (`TransformerModel::generate_from_cache`).
+//!
+//! Snapshots are held in memory up to a byte budget, least recently used
+//! evicted first. With the `kv-store` feature and [`PrefixCache::with_spill`],
+//! evicted snapshots are written to a `KvCacheStore` instead of dropped,
+//! and read back when a prompt needs them again — also after a restart.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use crate::layers::kv_cache::KvSnapshot;
+
+/// Default memory budget for resident snapshots: 512 MiB.
+pub const DEFAULT_BUDGET_BYTES: usize = 512 * 1024 * 1024;
+
+/// A cached prefix of a prompt.
+#[derive(Debug, Clone)]
+pub struct PrefixHit {
+    /// Number of prompt tokens the snapshot covers.
+    pub len: usize,
+    pub snapshot: Arc<KvSnapshot>,
+}
+
+/// Counters for a [`PrefixCache`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrefixCacheStats {
+    /// Lookups that found a prefix.
+    pub hits: u64,
+    /// Lookups that found nothing.
+    pub misses: u64,
+    /// Prompt tokens whose prefill hits saved.
+    pub reused_tokens: u64,
+    /// Cached sequences, resident or spilled.
+    pub entries: usize,
+    /// Bytes of resident snapshots.
+    pub resident_bytes: usize,
+    /// Snapshots evicted from memory (spilled or dropped).
+    pub evictions: u64,
+}
+
+struct Entry {
+    tokens: Vec<u32>,
+    /// `None` while only on disk.
+    snapshot: Option<Arc<KvSnapshot>>,
+    last_used: u64,
+    /// Whether the store holds a copy.
+    #[cfg(feature = "kv-store")]
+    spilled: bool,
+}
+
+/// LRU cache of KV snapshots keyed by token sequence.
+pub struct PrefixCache {
+    budget_bytes: usize,
+    resident_bytes: usize,
+    /// Sequence hash → entry.
+    entries: HashMap<u64, Entry>,
+    /// Sequence length → number of entries of that length, so lookups
+    /// only hash the prompt prefixes that could match.
+    lengths: BTreeMap<usize, usize>,
+    clock: u64,
+    stats: PrefixCacheStats,
+    #[cfg(feature = "kv-store")]
+    spill: Option<spill::Spill>,
+}
+
+impl PrefixCache {
+    /// An empty cache keeping at most `budget_bytes` of snapshots in memory.
+    pub fn new(budget_bytes: usize) -> Self {
+        Self {
+            budget_bytes,
+            resident_bytes: 0,
+            entries: HashMap::new(),
+            lengths: BTreeMap::new(),
+            clock: 0,
+            stats: PrefixCacheStats::default(),
+            #[cfg(feature = "kv-store")]
+            spill: None,
+        }
+    }
+
+    /// Remember the KV state after `tokens`. `snapshot` must cover exactly
+    /// `tokens`. Snapshots larger than the whole budget are not kept.
+    pub fn insert(&mut self, tokens: &[u32], snapshot: KvSnapshot) {
+        assert_eq!(snapshot.len(), tokens.len(), "snapshot must cover the token sequence");
+        let bytes = snapshot.memory_bytes();
+        if tokens.is_empty() || bytes > self.budget_bytes {
+            return;
+        }
+        let key = sequence_hash(tokens);
+        self.clock += 1;
+        if let Some(old) = self.entries.remove(&key) {
+            self.forget(&old);
+        }
+        self.make_room(bytes);
+        *self.lengths.entry(tokens.len()).or_insert(0) += 1;
+        self.resident_bytes += bytes;
+        self.entries.insert(
+            key,
+            Entry {
+                tokens: tokens.to_vec(),
+                snapshot: Some(Arc::new(snapshot)),
+                last_used: self.clock,
+                #[cfg(feature = "kv-store")]
+                spilled: false,
+            },
+        );
+    }
+
+    /// The longest cached prefix of `tokens` no longer than `max_len`.
+    pub fn longest_prefix(&mut self, tokens: &[u32], max_len: usize) -> Option<PrefixHit> {
+        let max_len = max_len.min(tokens.len());
+        let candidates: Vec<usize> = self.lengths.range(1..=max_len).rev().map(|(&len, _)| len).collect();
+        let mut hashes = PrefixHashes::new(tokens);
+        for len in candidates {
+            let key = hashes.up_to(len);
+            let matches = self.entries.get(&key).is_some_and(|e| e.tokens == tokens[..len]);
+            if !matches {
+                continue;
+            }
+            if let Some(snapshot) = self.resident(key) {
+                self.stats.hits += 1;
+                self.stats.reused_tokens += len as u64;
+                return Some(PrefixHit { len, snapshot });
+            }
+        }
+        self.stats.misses += 1;
+        None
+    }
+
+    /// Whether exactly `tokens` is cached.
+    pub fn contains(&self, tokens: &[u32]) -> bool {
+        self.entries
+            .get(&sequence_hash(tokens))
+            .is_some_and(|e| e.tokens == tokens)
+    }
+
+    /// Number of cached sequences, resident or spilled.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Bytes of snapshots held in memory.
+    pub fn resident_bytes(&self) -> usize {
+        self.resident_bytes
+    }
+
+    /// Memory budget for resident snapshots.
+    pub fn budget_bytes(&self) -> usize {
+        self.budget_bytes
+    }
+
+    pub fn stats(&self) -> PrefixCacheStats {
+        PrefixCacheStats {
+            entries: self.entries.len(),
+            resident_bytes: self.resident_bytes,
+            ..self.stats
+        }
+    }
+
+    /// Drop every cached sequence, including spilled copies.
+    pub fn clear(&mut self) {
+        #[cfg(feature = "kv-store")]
+        if let Some(spill) = &self.spill {
+            for (&key, entry) in &self.entries {
+                if entry.spilled {
+                    spill.remove(key);
+                }
+            }
+        }
+        self.entries.clear();
+        self.lengths.clear();
+        self.resident_bytes = 0;
+    }
+
+    /// The snapshot of entry `key`, loading it from the spill store if
+    /// it was evicted. Marks the entry used.
+    fn resident(&mut self, key: u64) -> Option<Arc<KvSnapshot>> {
+        self.clock += 1;
+        let entry = self.entries.get_mut(&key)?;
+        entry.last_used = self.clock;
+        if let Some(snapshot) = &entry.snapshot {
+            return Some(snapshot.clone());
+        }
+        self.load_spilled(key)
+    }
+
+    #[cfg(feature = "kv-store")]
+    fn load_spilled(&mut self, key: u64) -> Option<Arc<KvSnapshot>> {
+        let loaded = self.spill.as_ref().and_then(|s| s.load(key));
+        let Some(snapshot) = loaded.filter(|s| s.memory_bytes() <= self.budget_bytes) else {
+            // Unreadable, or too big to hold: forget it.
+            if let Some(entry) = self.entries.remove(&key) {
+                self.forget(&entry);
+            }
+            return None;
+        };
+        self.make_room(snapshot.memory_bytes());
+        self.resident_bytes += snapshot.memory_bytes();
+        let snapshot = Arc::new(snapshot);
+        self.entries.get_mut(&key)?.snapshot = Some(snapshot.clone());
+        Some(snapshot)
+    }
+
+    #[cfg(not(feature = "kv-store"))]
+    fn load_spilled(&mut self, _key: u64) -> Option<Arc<KvSnapshot>> {
+        None
+    }
+
+    /// Evict least recently used snapshots until `bytes` more fit.
+    fn make_room(&mut self, bytes: usize) {
+        while self.resident_bytes + bytes > self.budget_bytes {
+            let victim = self
+                .entries
+                .iter()
+                .filter(|(_, e)| e.snapshot.is_some())
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(&key, _)| key);
+            let Some(key) = victim else {
+                break;
+            };
+            self.evict(key);
+        }
+    }
+
+    /// Move entry `key` out of memory: to the spill store if there is
+    /// one, otherwise out of the cache.
+    fn evict(&mut self, key: u64) {
+        self.stats.evictions += 1;
+        #[cfg(feature = "kv-store")]
+        if let Some(spill) = &self.spill {
+            let entry = self.entries.get_mut(&key).expect("victim exists");
+            let snapshot = entry.snapshot.take().expect("victim is resident");
+            self.resident_bytes -= snapshot.memory_bytes();
+            if entry.spilled || spill.save(key, &entry.tokens, &snapshot) {
+                entry.spilled = true;
+                return;
+            }
+            let entry = self.entries.remove(&key).expect("victim exists");
+            self.forget(&entry);
+            return;
+        }
+        if let Some(entry) = self.entries.remove(&key) {
+            self.forget(&entry);
+        }
+    }
+
+    /// Update the bookkeeping for an entry removed from `entries`.
+    fn forget(&mut self, entry: &Entry) {
+        if let Some(snapshot) = &entry.snapshot {
+            self.resident_bytes -= snapshot.memory_bytes();
+        }
+        if let Some(count) = self.lengths.get_mut(&entry.tokens.len()) {
+            *count -= 1;
+            if *count == 0 {
+                self.lengths.remove(&entry.tokens.len());
+            }
+        }
+    }
+}
+
+impl Default for PrefixCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_BUDGET_BYTES)
+    }
+}
+
+impl std::fmt::Debug for PrefixCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "PrefixCache(entries={}, {:.1}/{:.1}MB)",
+            self.entries.len(),
+            self.resident_bytes as f64 / (1024.0 * 1024.0),
+            self.budget_bytes as f64 / (1024.0 * 1024.0),
+        )
+    }
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// FNV-1a over the tokens' little-endian bytes.
+fn sequence_hash(tokens: &[u32]) -> u64 {
+    let mut hashes = PrefixHashes::new(tokens);
+    hashes.up_to(tokens.len())
+}
+
+/// Incremental hashes of a sequence's prefixes, for lookups walking
+/// from the longest prefix down.
+struct PrefixHashes<'a> {
+    tokens: &'a [u32],
+    /// `hashes[i]` is the hash of `tokens[..i]`.
+    hashes: Vec<u64>,
+}
+
+impl<'a> PrefixHashes<'a> {
+    fn new(tokens: &'a [u32]) -> Self {
+        Self { tokens, hashes: vec![FNV_OFFSET] }
+    }
+
+    fn up_to(&mut self, len: usize) -> u64 {
+        while self.hashes.len() <= len {
+            let mut hash = *self.hashes.last().expect("seeded");
+            for byte in self.tokens[self.hashes.len() - 1].to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+            self.hashes.push(hash);
+        }
+        self.hashes[len]
+    }
+}
+
+#[cfg(feature = "kv-store")]
+mod spill {
+    //! Snapshot persistence through `agentos-kv-store`.
+    //!
+    //! Each snapshot is stored as one "user" of the store, named
+    //! `{namespace}-{hash:016x}`: one `Float32` entry per layer holding
+    //! its keys and values, plus an entry at [`TOKENS_LAYER`] holding the
+    //! token sequence so the index can be rebuilt on open.
+
+    use std::sync::Arc;
+
+    use agentos_kv_store::{CacheFormat, KvCacheEntry, KvCacheStore, StoreError};
+    use tracing::warn;
+
+    use super::*;
+
+    /// Pseudo-layer holding a snapshot's token sequence.
+    pub(super) const TOKENS_LAYER: u32 = 9999;
+
+    pub(super) struct Spill {
+        store: Arc<KvCacheStore>,
+        namespace: String,
+        /// Shape of the model's cache, for rebuilding snapshots.
+        n_kv_heads: usize,
+        head_dim: usize,
+    }
+
+    impl Spill {
+        fn user(&self, key: u64) -> String {
+            format!("{}-{key:016x}", self.namespace)
+        }
+
+        pub(super) fn save(&self, key: u64, tokens: &[u32], snapshot: &KvSnapshot) -> bool {
+            let created_at = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0);
+            let mut entries: Vec<KvCacheEntry> = (0..snapshot.n_layers())
+                .map(|layer| {
+                    let (keys, values) = snapshot.layer(layer);
+                    KvCacheEntry {
+                        layer: layer as u32,
+                        seq_start: 0,
+                        seq_len: snapshot.len() as u32,
+                        key_data: f32_bytes(keys),
+                        value_data: f32_bytes(values),
+                        format: CacheFormat::Float32,
+                        created_at,
+                    }
+                })
+                .collect();
+            entries.push(KvCacheEntry {
+                layer: TOKENS_LAYER,
+                seq_start: 0,
+                seq_len: tokens.len() as u32,
+                key_data: tokens.iter().flat_map(|t| t.to_le_bytes()).collect(),
+                value_data: Vec::new(),
+                format: CacheFormat::Float32,
+                created_at,
+            });
+            let user = self.user(key);
+            match self.store.append(&user, &entries).and_then(|_| self.store.flush(&user)) {
+                Ok(()) => true,
+                Err(e) => {
+                    warn!("prefix cache: failed to spill {user}: {e}");
+                    false
+                }
+            }
+        }
+
+        pub(super) fn load(&self, key: u64) -> Option<KvSnapshot> {
+            let user = self.user(key);
+            let mut entries = match self.store.load_recent(&user, usize::MAX) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    warn!("prefix cache: failed to load {user}: {e}");
+                    return None;
+                }
+            };
+            entries.retain(|e| e.layer != TOKENS_LAYER && e.format == CacheFormat::Float32);
+            entries.sort_by_key(|e| e.layer);
+            let layers = entries
+                .iter()
+                .map(|e| (f32_values(&e.key_data), f32_values(&e.value_data)))
+                .collect();
+            KvSnapshot::from_layers(self.n_kv_heads, self.head_dim, layers)
+        }
+
+        pub(super) fn remove(&self, key: u64) {
+            if let Err(e) = self.store.evict(&self.user(key)) {
+                warn!("prefix cache: failed to remove {}: {e}", self.user(key));
+            }
+        }
+    }
+
+    impl PrefixCache {
+        /// Spill evicted snapshots to `store` under `namespace`, and index
+        /// the snapshots already there. The namespace must identify the
+        /// model (snapshots are only valid for the model that made them)
+        /// and must not contain `:`. `n_kv_heads` and `head_dim` give the
+        /// model's cache shape.
+        pub fn with_spill(
+            mut self,
+            store: Arc<KvCacheStore>,
+            namespace: &str,
+            n_kv_heads: usize,
+            head_dim: usize,
+        ) -> Result<Self, StoreError> {
+            let prefix = format!("{namespace}-");
+            for user in store.list_users()? {
+                let Some(key) = user
+                    .strip_prefix(&prefix)
+                    .and_then(|hex| u64::from_str_radix(hex, 16).ok())
+                else {
+                    continue;
+                };
+                let Some(entry) = store.load_layer(&user, TOKENS_LAYER)?.pop() else {
+                    continue;
+                };
+                let tokens: Vec<u32> = entry
+                    .key_data
+                    .chunks_exact(4)
+                    .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                    .collect();
+                if tokens.is_empty() || self.entries.contains_key(&key) {
+                    continue;
+                }
+                *self.lengths.entry(tokens.len()).or_insert(0) += 1;
+                self.entries.insert(
+                    key,
+                    Entry {
+                        tokens,
+                        snapshot: None,
+                        last_used: 0,
+                        spilled: true,
+                    },
+                );
+            }
+            self.spill = Some(Spill {
+                store,
+                namespace: namespace.to_string(),
+                n_kv_heads,
+                head_dim,
+            });
+            Ok(self)
+        }
+    }
+
+    fn f32_bytes(values: &[f32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    fn f32_values(bytes: &[u8]) -> Vec<f32> {
+        bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::kv_cache::ModelKvCache;
+
+    /// Snapshot of `len` positions whose values encode `seed`.
+    fn snapshot(len: usize, seed: f32) -> KvSnapshot {
+        let mut cache = ModelKvCache::new(2, 1, 2, 64);
+        for layer in 0..2 {
+            let data: Vec<f32> = (0..len * 2).map(|i| seed + i as f32).collect();
+            cache.layer_mut(layer).append(&data, &data);
+        }
+        cache.snapshot()
+    }
+
+    #[test]
+    fn finds_longest_prefix() {
+        let mut cache = PrefixCache::default();
+        cache.insert(&[1, 2], snapshot(2, 0.0));
+        cache.insert(&[1, 2, 3, 4], snapshot(4, 10.0));
+        cache.insert(&[9, 9, 9], snapshot(3, 20.0));
+
+        let hit = cache.longest_prefix(&[1, 2, 3, 4, 5], 4).unwrap();
+        assert_eq!(hit.len, 4);
+        assert_eq!(hit.snapshot.layer(0).0[0], 10.0);
+
+        // Capped below the long entry, or diverging from it: the short one.
+        assert_eq!(cache.longest_prefix(&[1, 2, 3, 4, 5], 3).unwrap().len, 2);
+        assert_eq!(cache.longest_prefix(&[1, 2, 3, 7, 5], 5).unwrap().len, 2);
+        assert!(cache.longest_prefix(&[2, 1], 2).is_none());
+        assert!(cache.contains(&[9, 9, 9]));
+        assert!(!cache.contains(&[9, 9]));
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.reused_tokens), (3, 1, 8));
+        assert_eq!(stats.entries, 3);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let one = snapshot(4, 0.0).memory_bytes();
+        let mut cache = PrefixCache::new(one * 2);
+        cache.insert(&[1, 1, 1, 1], snapshot(4, 0.0));
+        cache.insert(&[2, 2, 2, 2], snapshot(4, 0.0));
+        // Touch the first so the second is the oldest.
+        assert!(cache.longest_prefix(&[1, 1, 1, 1], 4).is_some());
+        cache.insert(&[3, 3, 3, 3], snapshot(4, 0.0));
+
+        assert!(cache.contains(&[1, 1, 1, 1]));
+        assert!(!cache.contains(&[2, 2, 2, 2]));
+        assert!(cache.contains(&[3, 3, 3, 3]));
+        assert_eq!(cache.resident_bytes(), one * 2);
+        assert_eq!(cache.stats().evictions, 1);
+
+        // Bigger than the whole budget: not kept.
+        cache.insert(&[4; 10], snapshot(10, 0.0));
+        assert!(!cache.contains(&[4; 10]));
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn reinsert_replaces_and_clear_empties() {
+        let mut cache = PrefixCache::default();
+        cache.insert(&[5, 6], snapshot(2, 0.0));
+        cache.insert(&[5, 6], snapshot(2, 1.0));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.resident_bytes(), snapshot(2, 0.0).memory_bytes());
+        assert_eq!(cache.longest_prefix(&[5, 6, 7], 3).unwrap().snapshot.layer(1).0[0], 1.0);
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.resident_bytes(), 0);
+        assert!(cache.longest_prefix(&[5, 6, 7], 3).is_none());
+    }
+
+    #[cfg(feature = "kv-store")]
+    #[test]
+    fn spills_to_store_and_reloads() {
+        use agentos_kv_store::KvCacheStore;
+
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(KvCacheStore::open(dir.path()).unwrap());
+        let one = snapshot(4, 0.0).memory_bytes();
+        let mut cache = PrefixCache::new(one).with_spill(store.clone(), "test-model", 1, 2).unwrap();
+        cache.insert(&[1, 2, 3, 4], snapshot(4, 10.0));
+        cache.insert(&[5, 6, 7, 8], snapshot(4, 20.0));
+        // The first was spilled, not dropped.
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.resident_bytes(), one);
+
+        let hit = cache.longest_prefix(&[1, 2, 3, 4, 0], 4).unwrap();
+        assert_eq!(hit.len, 4);
+        assert_eq!(hit.snapshot.layer(1).1[3], 13.0);
+        assert_eq!(cache.resident_bytes(), one);
+
+        // A fresh cache over the same store finds both (the second was
+        // spilled when the first came back).
+        let mut reopened = PrefixCache::new(one).with_spill(store, "test-model", 1, 2).unwrap();
+        assert_eq!(reopened.len(), 2);
+        assert_eq!(reopened.longest_prefix(&[5, 6, 7, 8, 9], 4).unwrap().snapshot.layer(0).0[0], 20.0);
+        assert_eq!(reopened.longest_prefix(&[1, 2, 3, 4, 9], 4).unwrap().len, 4);
+    }
+}
@@ -8,7 +8,13 @@
 //!
 //! Temperature scaling is applied before any filtering. Temperature < 1.0
 //! sharpens the distribution (more deterministic), > 1.0 flattens it
-//! (more random).
+//! (more random). Min-p drops tokens far less likely than the best one.
+//!
+//! Before any of that, raw logits are adjusted: repetition penalty over a
+//! recent window, frequency and presence penalties over everything this
+//! sampler has produced, then per-token logit bias.
+
+use std::collections::HashMap;
 
 /// Configuration for token sampling.
 #[derive(Debug, Clone)]
@@ -25,6 +31,18 @@ pub struct SamplerConfig {
     /// Number of recent tokens to consider for repetition penalty.
     /// 0 = disabled (no penalty window).
     pub repetition_window: usize,
+    /// Subtracted from a token's logit once per time it was sampled.
+    /// 0.0 = disabled. Typical: 0.1–1.0.
+    pub frequency_penalty: f32,
+    /// Subtracted from a token's logit if it was sampled at all.
+    /// 0.0 = disabled. Typical: 0.1–1.0.
+    pub presence_penalty: f32,
+    /// Min-p: drop tokens whose probability is below `min_p` times the
+    /// top token's. 0.0 = disabled. Typical: 0.05–0.1.
+    pub min_p: f32,
+    /// Added to the logits of the given token IDs. `f32::NEG_INFINITY`
+    /// bans a token.
+    pub logit_bias: HashMap<u32, f32>,
 }
 
 impl Default for SamplerConfig {
@@ -35,6 +53,10 @@ impl Default for SamplerConfig {
             top_p: 1.0,
             repetition_penalty: 1.0,
             repetition_window: 0,
+            frequency_penalty: 0.0,
+            presence_penalty: 0.0,
+            min_p: 0.0,
+            logit_bias: HashMap::new(),
         }
     }
 }
@@ -75,6 +97,8 @@ pub struct Sampler {
     rng_state: u64,
     /// Ring buffer of recently generated token IDs for repetition penalty.
     recent_tokens: Vec<u32>,
+    /// How often each token has been sampled, for frequency/presence penalties.
+    counts: HashMap<u32, u32>,
 }
 
 impl Sampler {
@@ -84,6 +108,7 @@ impl Sampler {
             config,
             rng_state: if seed == 0 { 1 } else { seed },
             recent_tokens: Vec::new(),
+            counts: HashMap::new(),
         }
     }
 
@@ -98,84 +123,58 @@ impl Sampler {
     /// Returns the selected token ID.
     pub fn sample(&mut self, logits: &[f32]) -> u32 {
         assert!(!logits.is_empty(), "logits must not be empty");
-
-        // 0. Apply repetition penalty
-        let logits = if self.config.repetition_penalty > 1.0 && !self.recent_tokens.is_empty() {
-            let mut penalized = logits.to_vec();
-            for &tok in &self.recent_tokens {
-                let idx = tok as usize;
-                if idx < penalized.len() {
-                    if penalized[idx] > 0.0 {
-                        penalized[idx] /= self.config.repetition_penalty;
-                    } else {
-                        penalized[idx] *= self.config.repetition_penalty;
-                    }
-                }
-            }
-            penalized
-        } else {
-            logits.to_vec()
-        };
-        let logits = &logits;
+        let logits = self.adjust(logits);
 
         // Temperature 0 or top_k=1: pure greedy
         if self.config.temperature <= 0.0 || self.config.top_k == 1 {
-            let token = argmax(logits) as u32;
+            let token = argmax(&logits) as u32;
             self.record_token(token);
             return token;
         }
 
-        // 1. Apply temperature
+        // Temperature, then softmax, then filter and sample
         let mut scaled: Vec<f32> = logits.iter().map(|&l| l / self.config.temperature).collect();
-
-        // 2. Convert to probabilities via softmax
         softmax_inplace(&mut scaled);
+        let token = self.sample_from_probs(&scaled);
+        self.record_token(token);
+        token
+    }
 
-        // 3. Build sorted index (descending probability)
-        let mut indices: Vec<usize> = (0..scaled.len()).collect();
-        indices.sort_unstable_by(|&a, &b| scaled[b].partial_cmp(&scaled[a]).unwrap());
+    /// Apply repetition, frequency and presence penalties and logit bias.
+    fn adjust(&self, logits: &[f32]) -> Vec<f32> {
+        let mut adjusted = logits.to_vec();
 
-        // 4. Apply top-k filter
-        let mut candidates = indices.len();
-        if self.config.top_k > 0 && self.config.top_k < candidates {
-            candidates = self.config.top_k;
+        if self.config.repetition_penalty > 1.0 {
+            for &tok in &self.recent_tokens {
+                if let Some(logit) = adjusted.get_mut(tok as usize) {
+                    if *logit > 0.0 {
+                        *logit /= self.config.repetition_penalty;
+                    } else {
+                        *logit *= self.config.repetition_penalty;
+                    }
+                }
+            }
         }
 
-        // 5. Apply top-p (nucleus) filter
-        if self.config.top_p < 1.0 {
-            let mut cumulative = 0.0f32;
-            for i in 0..candidates {
-                cumulative += scaled[indices[i]];
-                if cumulative >= self.config.top_p {
-                    candidates = i + 1;
-                    break;
+        if self.config.frequency_penalty != 0.0 || self.config.presence_penalty != 0.0 {
+            for (&tok, &count) in &self.counts {
+                if let Some(logit) = adjusted.get_mut(tok as usize) {
+                    *logit -= count as f32 * self.config.frequency_penalty + self.config.presence_penalty;
                 }
             }
         }
 
-        // 6. Renormalize the candidate probabilities
-        let total: f32 = indices[..candidates].iter().map(|&i| scaled[i]).sum();
-        let inv_total = if total > 0.0 { 1.0 / total } else { 1.0 };
-
-        // 7. Sample from the filtered distribution
-        let r = self.next_f32();
-        let mut cumulative = 0.0f32;
-        for &idx in &indices[..candidates] {
-            cumulative += scaled[idx] * inv_total;
-            if r < cumulative {
-                self.record_token(idx as u32);
-                return idx as u32;
+        for (&tok, &bias) in &self.config.logit_bias {
+            if let Some(logit) = adjusted.get_mut(tok as usize) {
+                *logit += bias;
             }
         }
-
-        // Fallback (floating point edge case)
-        let token = indices[candidates - 1] as u32;
-        self.record_token(token);
-        token
+        adjusted
     }
 
-    /// Record a token in the recent tokens ring buffer.
+    /// Record a sampled token for the penalties.
     fn record_token(&mut self, token: u32) {
+        *self.counts.entry(token).or_insert(0) += 1;
         if self.config.repetition_window > 0 {
             self.recent_tokens.push(token);
             if self.recent_tokens.len() > self.config.repetition_window {
@@ -187,11 +186,13 @@ impl Sampler {
     /// Sample and also return the probability of the chosen token.
     pub fn sample_with_prob(&mut self, logits: &[f32]) -> (u32, f32) {
         assert!(!logits.is_empty());
+        let logits = self.adjust(logits);
 
         if self.config.temperature <= 0.0 || self.config.top_k == 1 {
-            let idx = argmax(logits);
-            let mut probs = logits.to_vec();
+            let idx = argmax(&logits);
+            let mut probs = logits;
             softmax_inplace(&mut probs);
+            self.record_token(idx as u32);
             return (idx as u32, probs[idx]);
         }
 
@@ -199,10 +200,12 @@ impl Sampler {
         softmax_inplace(&mut scaled);
 
         let token = self.sample_from_probs(&scaled);
+        self.record_token(token);
         (token, scaled[token as usize])
     }
 
-    /// Internal: sample from a probability distribution (already softmaxed).
+    /// Internal: sample from a probability distribution (already softmaxed),
+    /// after top-k, min-p and top-p filtering.
     fn sample_from_probs(&mut self, probs: &[f32]) -> u32 {
         let mut indices: Vec<usize> = (0..probs.len()).collect();
         indices.sort_unstable_by(|&a, &b| probs[b].partial_cmp(&probs[a]).unwrap());
@@ -212,6 +215,11 @@ impl Sampler {
             candidates = self.config.top_k;
         }
 
+        if self.config.min_p > 0.0 {
+            let floor = probs[indices[0]] * self.config.min_p;
+            candidates = indices[..candidates].iter().take_while(|&&i| probs[i] >= floor).count().max(1);
+        }
+
         if self.config.top_p < 1.0 {
             let mut cumulative = 0.0f32;
             for i in 0..candidates {
@@ -497,6 +505,80 @@ mod tests {
         assert!(prob > 0.0 && prob <= 1.0, "probability should be in (0, 1]");
     }
 
+    // -- Min-p --
+
+    #[test]
+    fn min_p_drops_unlikely_tokens() {
+        let config = SamplerConfig {
+            min_p: 0.5,
+            ..Default::default()
+        };
+        let mut sampler = Sampler::new(config, 42);
+        // exp(-1) ≈ 0.37 of the top token: dropped. exp(-0.1) ≈ 0.9: kept.
+        let logits = vec![5.0, 4.9, 4.0, 0.0];
+        let mut seen = std::collections::HashSet::new();
+        for _ in 0..200 {
+            seen.insert(sampler.sample(&logits));
+        }
+        assert_eq!(seen, [0, 1].into_iter().collect());
+    }
+
+    // -- Penalties and bias --
+
+    #[test]
+    fn logit_bias_bans_and_promotes() {
+        let mut config = SamplerConfig::greedy();
+        config.logit_bias.insert(1, f32::NEG_INFINITY);
+        config.logit_bias.insert(3, 10.0);
+        let mut sampler = Sampler::new(config, 1);
+        assert_eq!(sampler.sample(&[1.0, 5.0, 3.0, 0.0]), 3);
+
+        let mut config = SamplerConfig::greedy();
+        config.logit_bias.insert(1, f32::NEG_INFINITY);
+        let mut sampler = Sampler::new(config, 1);
+        assert_eq!(sampler.sample(&[1.0, 5.0, 3.0, 0.0]), 2);
+    }
+
+    #[test]
+    fn frequency_penalty_grows_with_count() {
+        let config = SamplerConfig {
+            frequency_penalty: 0.6,
+            ..SamplerConfig::greedy()
+        };
+        let mut sampler = Sampler::new(config, 1);
+        let logits = vec![2.0, 1.0];
+        // 2.0 → 1.4 → 0.8: token 0 wins twice, then token 1 overtakes.
+        let seq: Vec<u32> = (0..3).map(|_| sampler.sample(&logits)).collect();
+        assert_eq!(seq, vec![0, 0, 1]);
+    }
+
+    #[test]
+    fn presence_penalty_is_flat() {
+        let config = SamplerConfig {
+            presence_penalty: 0.5,
+            ..SamplerConfig::greedy()
+        };
+        let mut sampler = Sampler::new(config, 1);
+        let logits = vec![2.0, 1.0];
+        // Token 0 is penalized once to 1.5 no matter how often it repeats.
+        let seq: Vec<u32> = (0..4).map(|_| sampler.sample(&logits)).collect();
+        assert_eq!(seq, vec![0, 0, 0, 0]);
+        assert_eq!(sampler.adjust(&logits), vec![1.5, 1.0]);
+    }
+
+    #[test]
+    fn repetition_penalty_uses_window() {
+        let config = SamplerConfig {
+            repetition_penalty: 4.0,
+            repetition_window: 1,
+            ..SamplerConfig::greedy()
+        };
+        let mut sampler = Sampler::new(config, 1);
+        let logits = vec![2.0, 1.0];
+        let seq: Vec<u32> = (0..4).map(|_| sampler.sample(&logits)).collect();
+        assert_eq!(seq, vec![0, 1, 0, 1]);
+    }
+
     // -- RNG reproducibility --
 
     #[test]
@@ -10,8 +10,11 @@
 //! - `down_proj`: intermediate_size → embed_dim (ternary BitLinear)
 //! - SiLU(x) = x · σ(x) = x / (1 + exp(-x))
 //! - ⊙ = element-wise multiplication
+//!
+//! Standard (non-ternary) models use `DenseLinear` projections instead; see
+//! [`crate::layers::linear`].
 
-use crate::layers::bitlinear::BitLinear;
+use crate::layers::linear::Linear;
 use crate::layers::rmsnorm::RmsNorm;
 
 /// Gated feed-forward network with ternary projections.
@@ -20,11 +23,11 @@ use crate::layers::rmsnorm::RmsNorm;
 /// BitNet-style (squared ReLU activation).
 pub struct SwiGLU {
     /// Gate projection (embed_dim → intermediate_size).
-    gate_proj: BitLinear,
+    gate_proj: Linear,
     /// Up projection (embed_dim → intermediate_size).
-    up_proj: BitLinear,
+    up_proj: Linear,
     /// Down projection (intermediate_size → embed_dim).
-    down_proj: BitLinear,
+    down_proj: Linear,
     /// Optional sub-normalization applied to intermediate (before down projection).
     /// BitNet b1.58 uses this — shape is [intermediate_size].
     sub_norm: Option<RmsNorm>,
@@ -34,17 +37,18 @@ pub struct SwiGLU {
 
 impl SwiGLU {
     /// Create a gated FFN with SiLU activation (standard SwiGLU).
-    pub fn new(gate_proj: BitLinear, up_proj: BitLinear, down_proj: BitLinear) -> Self {
+    pub fn new(gate_proj: impl Into<Linear>, up_proj: impl Into<Linear>, down_proj: impl Into<Linear>) -> Self {
         Self::with_activation(gate_proj, up_proj, down_proj, GateActivation::SiLU)
     }
 
     /// Create a gated FFN with a specific activation function.
     pub fn with_activation(
-        gate_proj: BitLinear,
-        up_proj: BitLinear,
-        down_proj: BitLinear,
+        gate_proj: impl Into<Linear>,
+        up_proj: impl Into<Linear>,
+        down_proj: impl Into<Linear>,
         activation: GateActivation,
     ) -> Self {
+        let (gate_proj, up_proj, down_proj) = (gate_proj.into(), up_proj.into(), down_proj.into());
         Self::check_dims(&gate_proj, &up_proj, &down_proj);
         Self {
             gate_proj,
@@ -57,9 +61,9 @@ impl SwiGLU {
 
     /// Create a gated FFN with sub-normalization before the down projection.
     pub fn with_sub_norm(
-        gate_proj: BitLinear,
-        up_proj: BitLinear,
-        down_proj: BitLinear,
+        gate_proj: impl Into<Linear>,
+        up_proj: impl Into<Linear>,
+        down_proj: impl Into<Linear>,
         sub_norm: RmsNorm,
     ) -> Self {
         Self::with_sub_norm_and_activation(gate_proj, up_proj, down_proj, sub_norm, GateActivation::SiLU)
@@ -67,12 +71,13 @@ impl SwiGLU {
 
     /// Create a gated FFN with sub-norm and a specific activation function.
     pub fn with_sub_norm_and_activation(
-        gate_proj: BitLinear,
-        up_proj: BitLinear,
-        down_proj: BitLinear,
+        gate_proj: impl Into<Linear>,
+        up_proj: impl Into<Linear>,
+        down_proj: impl Into<Linear>,
         sub_norm: RmsNorm,
         activation: GateActivation,
     ) -> Self {
+        let (gate_proj, up_proj, down_proj) = (gate_proj.into(), up_proj.into(), down_proj.into());
         Self::check_dims(&gate_proj, &up_proj, &down_proj);
         Self {
             gate_proj,
@@ -83,7 +88,7 @@ impl SwiGLU {
         }
     }
 
-    fn check_dims(gate_proj: &BitLinear, up_proj: &BitLinear, down_proj: &BitLinear) {
+    fn check_dims(gate_proj: &Linear, up_proj: &Linear, down_proj: &Linear) {
         assert_eq!(
             gate_proj.in_features(),
             up_proj.in_features(),
@@ -156,13 +161,25 @@ impl SwiGLU {
             "input shape mismatch"
         );
 
-        let mut output = Vec::with_capacity(seq_len * self.out_features());
-        for t in 0..seq_len {
-            let start = t * embed_dim;
-            let token = &input[start..start + embed_dim];
-            output.extend_from_slice(&self.forward(token));
+        // All tokens go through each projection as one ternary matmul.
+        let gate = self.gate_proj.forward_rows(input, seq_len);
+        let up = self.up_proj.forward_rows(input, seq_len);
+
+        let act = self.activation;
+        let mut intermediate: Vec<f32> = gate
+            .iter()
+            .zip(up.iter())
+            .map(|(&g, &u)| apply_activation(g, act) * u)
+            .collect();
+
+        if let Some(ref norm) = self.sub_norm {
+            for row in intermediate.chunks_exact_mut(self.intermediate_size()) {
+                let normed = norm.forward(row);
+                row.copy_from_slice(&normed);
+            }
         }
-        output
+
+        self.down_proj.forward_rows(&intermediate, seq_len)
     }
 }
 
@@ -230,6 +247,7 @@ fn apply_activation(x: f32, act: GateActivation) -> f32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::layers::bitlinear::BitLinear;
     use crate::tensor::{Ternary, TernaryTensor};
 
     fn make_bitlinear(weights: &[i8], rows: usize, cols: usize, scale: f32) -> BitLinear {
@@ -151,14 +151,30 @@ impl TransformerBlock {
         let normed_for_attn = self.norm_sequence(&self.attn_norm, input, seq_len);
         let attn_out = self.attention.forward_cached(&normed_for_attn, seq_len, cache);
 
-        let mut h = Vec::with_capacity(input.len());
-        for (x, a) in input.iter().zip(attn_out.iter()) {
-            h.push(x + a);
-        }
-
         // 2. FFN sub-block with residual
-        let normed_for_ffn = self.norm_sequence(&self.ffn_norm, &h, seq_len);
-        let ffn_out = self.ffn.forward_sequence(&normed_for_ffn, seq_len);
+        self.residual_ffn(input, &attn_out, seq_len)
+    }
+
+    /// Batched decode step: one new token for each sequence in `caches`.
+    ///
+    /// `input`: flat f32 slice of shape `[caches.len(), embed_dim]`, row
+    /// `b` belonging to the sequence cached in `caches[b]`.
+    /// Returns: flat f32 vec of the same shape.
+    pub fn forward_batch_cached(&self, input: &[f32], caches: &mut [&mut KvCache]) -> Vec<f32> {
+        let batch = caches.len();
+        assert_eq!(input.len(), batch * self.embed_dim(), "input shape mismatch");
+
+        let normed_for_attn = self.norm_sequence(&self.attn_norm, input, batch);
+        let attn_out = self.attention.forward_batch_cached(&normed_for_attn, caches);
+        self.residual_ffn(input, &attn_out, batch)
+    }
+
+    /// `h = input + attn_out`, then `h + ffn(norm(h))`, over `n_rows` rows.
+    fn residual_ffn(&self, input: &[f32], attn_out: &[f32], n_rows: usize) -> Vec<f32> {
+        let mut h: Vec<f32> = input.iter().zip(attn_out).map(|(x, a)| x + a).collect();
+
+        let normed_for_ffn = self.norm_sequence(&self.ffn_norm, &h, n_rows);
+        let ffn_out = self.ffn.forward_sequence(&normed_for_ffn, n_rows);
 
         for (h_val, f_val) in h.iter_mut().zip(ffn_out.iter()) {
             *h_val += f_val;
@@ -10,6 +10,8 @@
 //! - **I2S**: 2-bit packed weights, scalar add/sub/skip loop with SIMD.
 //! - **LUT (TL1)**: Group 2 weights → 4-bit index into 9-entry precomputed table.
 //!   Replaces arithmetic with table lookup.
+//! - **Dequant**: GGML Q8_0/Q4_0/Q4_K/Q6_K blocks for standard (non-ternary)
+//!   LLaMA/Qwen2 models, decoded a block at a time with f32 accumulation.
 //!
 //! ## Design constraints
 //!
@@ -24,8 +26,13 @@ pub mod layers;
 pub mod gguf;
 pub mod tokenizer;
 pub mod loader;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+#[cfg(feature = "embedding")]
+pub mod embedder;
 
-pub use tensor::{TernaryTensor, ActivationTensor, Ternary};
+pub use tensor::{TernaryTensor, ActivationTensor, QuantTensor, Ternary, WeightBytes};
 pub use gguf::{GgufFile, GgufError, GgmlType, TensorInfo, ModelConfig, MetadataValue};
 pub use tokenizer::Tokenizer;
-pub use loader::{load_model, LoadedModel};
+pub use loader::{load_model, load_model_with, LoadOptions, LoadedModel};
+pub use layers::model::{HiddenLayer, Pooling};
@@ -16,13 +16,21 @@
 //! - `blk.{i}.ffn_sub_norm.weight` — pre-down-projection sub-norm (BitNet b1.58)
 //! - `output_norm.weight` — final RMSNorm (float)
 //! - `output.weight` — output projection (ternary, or absent if tied)
+//!
+//! Standard LLaMA/Qwen2 GGUFs use the same names with Q8_0/Q4_0/Q4_K/Q6_K
+//! or F16/F32 projections, which load as [`DenseLinear`] instead of
+//! [`BitLinear`]. Qwen2 adds `blk.{i}.attn_{q,k,v}.bias`.
+
+use tracing::{info, warn};
 
-use tracing::info;
+use std::sync::Arc;
 
-use crate::compute;
-use crate::gguf::{GgufFile, GgufError, ModelConfig};
+use crate::compute::{self, ComputeBackend};
+use crate::gguf::{GgmlType, GgufFile, GgufError, ModelConfig};
 use crate::layers::attention::MultiHeadAttention;
 use crate::layers::bitlinear::BitLinear;
+use crate::layers::kv_cache::KvCacheFormat;
+use crate::layers::linear::{DenseLinear, Linear};
 use crate::layers::model::{OutputProjection, TransformerModel};
 use crate::layers::rmsnorm::RmsNorm;
 use crate::layers::rope::RoPELayout;
@@ -40,16 +48,33 @@ pub struct LoadedModel {
     pub config: ModelConfig,
 }
 
+/// Runtime choices made at load time.
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    /// Storage format of the KV caches the model creates. `Int8` cuts
+    /// cache memory roughly 3.5× at a small accuracy cost.
+    pub kv_cache: KvCacheFormat,
+    /// Memory-map the GGUF file instead of reading tensors into buffers.
+    /// Quantized weights are then used in place, so peak RSS stays near
+    /// the decoded (ternary/float) size. Needs the `mmap` feature.
+    pub mmap: bool,
+}
+
 /// Load a transformer model and tokenizer from a GGUF file.
 ///
 /// This reads all tensor data into memory — for a 2B model at ternary
 /// precision, that's roughly ~400MB of weights.
 pub fn load_model(path: &str) -> Result<LoadedModel, GgufError> {
+    load_model_with(path, &LoadOptions::default())
+}
+
+/// [`load_model`] with explicit [`LoadOptions`].
+pub fn load_model_with(path: &str, options: &LoadOptions) -> Result<LoadedModel, GgufError> {
     // Hardware detection and boot banner
     let hw = crate::compute::device::HardwareInfo::detect();
     hw.print_boot_banner();
 
-    let gguf = GgufFile::open(path)?;
+    let gguf = open_gguf(path, options.mmap)?;
     let config = gguf.model_config()?;
     let tokenizer = Tokenizer::from_gguf(&gguf)?;
 
@@ -58,10 +83,7 @@ pub fn load_model(path: &str) -> Result<LoadedModel, GgufError> {
         config.n_layers,
         config.embedding_dim,
         config.vocab_size,
-        // Ternary weights: 2 bits per param, plus float norms/embeddings
-        (config.vocab_size * config.embedding_dim) as f64 * 4.0 / (1024.0 * 1024.0)
-            + (config.n_layers * config.embedding_dim * config.embedding_dim * 7) as f64 * 0.25
-                / (1024.0 * 1024.0),
+        gguf.tensor_data_bytes() as f64 / (1024.0 * 1024.0),
     );
 
     info!(
@@ -90,14 +112,15 @@ pub fn load_model(path: &str) -> Result<LoadedModel, GgufError> {
     // Determine RoPE layout.
     // BitNet models from HuggingFace use halved (NeoX) RoPE convention.
     // BitNet.cpp's converter does NOT apply the Q/K weight permutation,
-    // so we need halved RoPE for bitnet architectures.
+    // so we need halved RoPE for bitnet architectures. llama.cpp's converter
+    // doesn't permute Qwen2 either (it's NEOX in llama.cpp too).
     let rope_layout = match config.rope_type {
         2 => {
             info!("using halved (NeoX/HF) RoPE layout");
             RoPELayout::Halved
         }
-        _ if arch.contains("bitnet") => {
-            info!("detected bitnet architecture, using halved (NeoX/HF) RoPE layout");
+        _ if arch.contains("bitnet") || arch.starts_with("qwen2") => {
+            info!(arch, "using halved (NeoX/HF) RoPE layout for architecture");
             RoPELayout::Halved
         }
         _ => {
@@ -135,30 +158,22 @@ pub fn load_model(path: &str) -> Result<LoadedModel, GgufError> {
     let mut blocks = Vec::with_capacity(config.n_layers as usize);
 
     for i in 0..config.n_layers as usize {
-        // Attention projections (ternary) — plain BitLinear, no sub-norms inside
-        let (q_w, q_s) = gguf.load_ternary(&format!("blk.{i}.attn_q.weight"))?;
-        let (k_w, k_s) = gguf.load_ternary(&format!("blk.{i}.attn_k.weight"))?;
-        let (v_w, v_s) = gguf.load_ternary(&format!("blk.{i}.attn_v.weight"))?;
-        let (o_w, o_s) = gguf.load_ternary(&format!("blk.{i}.attn_output.weight"))?;
-
-        let q_proj = BitLinear::with_backend(q_w, q_s, backend.clone());
-        let k_proj = BitLinear::with_backend(k_w, k_s, backend.clone());
-        let v_proj = BitLinear::with_backend(v_w, v_s, backend.clone());
-        let o_proj = BitLinear::with_backend(o_w, o_s, backend.clone());
+        // Attention projections — ternary BitLinear or standard DenseLinear,
+        // no sub-norms inside
+        let q_proj = load_linear(&gguf, &format!("blk.{i}.attn_q"), &backend)?;
+        let k_proj = load_linear(&gguf, &format!("blk.{i}.attn_k"), &backend)?;
+        let v_proj = load_linear(&gguf, &format!("blk.{i}.attn_v"), &backend)?;
+        let o_proj = load_linear(&gguf, &format!("blk.{i}.attn_output"), &backend)?;
 
         let attention = MultiHeadAttention::with_rope_layout(
             q_proj, k_proj, v_proj, o_proj,
             n_heads, n_kv_heads, head_dim, config.rope_theta, rope_layout,
         );
 
-        // FFN projections (ternary)
-        let (gate_w, gate_s) = gguf.load_ternary(&format!("blk.{i}.ffn_gate.weight"))?;
-        let (up_w, up_s) = gguf.load_ternary(&format!("blk.{i}.ffn_up.weight"))?;
-        let (down_w, down_s) = gguf.load_ternary(&format!("blk.{i}.ffn_down.weight"))?;
-
-        let gate_proj = BitLinear::with_backend(gate_w, gate_s, backend.clone());
-        let up_proj = BitLinear::with_backend(up_w, up_s, backend.clone());
-        let down_proj = BitLinear::with_backend(down_w, down_s, backend.clone());
+        // FFN projections
+        let gate_proj = load_linear(&gguf, &format!("blk.{i}.ffn_gate"), &backend)?;
+        let up_proj = load_linear(&gguf, &format!("blk.{i}.ffn_up"), &backend)?;
+        let down_proj = load_linear(&gguf, &format!("blk.{i}.ffn_down"), &backend)?;
 
         // Optional ffn_sub_norm [intermediate_dim]: applied between activation(gate)⊙up and down
         let ffn = if gguf.tensor_info(&format!("blk.{i}.ffn_sub_norm.weight")).is_some() {
@@ -198,7 +213,6 @@ pub fn load_model(path: &str) -> Result<LoadedModel, GgufError> {
 
     // Output projection: check if "output.weight" exists and its type
     let output_proj = if let Some(out_info) = gguf.tensor_info("output.weight") {
-        use crate::gguf::GgmlType;
         match out_info.ggml_type {
             GgmlType::F32 | GgmlType::F16 | GgmlType::BF16 => {
                 let out_tensor = gguf.load_float("output.weight")?;
@@ -209,6 +223,16 @@ pub fn load_model(path: &str) -> Result<LoadedModel, GgufError> {
                 );
                 OutputProjection::Float(out_tensor)
             }
+            GgmlType::Q8_0 | GgmlType::Q4_0 | GgmlType::Q4_K | GgmlType::Q6_K => {
+                let out_w = gguf.load_quant("output.weight")?;
+                info!(
+                    rows = out_w.rows(),
+                    cols = out_w.cols(),
+                    dtype = ?out_info.ggml_type,
+                    "loaded output projection (quantized)"
+                );
+                OutputProjection::Dense(DenseLinear::new(out_w))
+            }
             _ => {
                 // Ternary (I2S, TQ1, TQ2)
                 let (out_w, out_s) = gguf.load_ternary("output.weight")?;
@@ -225,11 +249,14 @@ pub fn load_model(path: &str) -> Result<LoadedModel, GgufError> {
         OutputProjection::TiedEmbedding
     };
 
-    let model = TransformerModel::new(embedding, blocks, final_norm, output_proj);
+    let mut model = TransformerModel::new(embedding, blocks, final_norm, output_proj);
+    model.set_kv_cache_format(options.kv_cache);
     info!(
         vocab = model.vocab_size(),
         embed = model.embed_dim(),
         layers = model.n_layers(),
+        kv_cache = ?options.kv_cache,
+        mapped_bytes = gguf.mapped_bytes(),
         "model loaded successfully"
     );
 
@@ -250,3 +277,47 @@ pub fn load_model(path: &str) -> Result<LoadedModel, GgufError> {
         config,
     })
 }
+
+/// Open `path`, memory-mapped when requested and compiled in.
+fn open_gguf(path: &str, mmap: bool) -> Result<GgufFile, GgufError> {
+    #[cfg(feature = "mmap")]
+    if mmap {
+        return GgufFile::open_mmap(path);
+    }
+    if mmap {
+        warn!("built without the `mmap` feature; reading tensors into memory");
+    }
+    GgufFile::open(path)
+}
+
+/// Load the projection `{prefix}.weight`: ternary types become a
+/// [`BitLinear`] on `backend`; float and block-quantized types become a
+/// [`DenseLinear`], with `{prefix}.bias` if the file has one.
+fn load_linear(
+    gguf: &GgufFile,
+    prefix: &str,
+    backend: &Arc<dyn ComputeBackend>,
+) -> Result<Linear, GgufError> {
+    let name = format!("{prefix}.weight");
+    let info = gguf
+        .tensor_info(&name)
+        .ok_or_else(|| GgufError::MissingMetadata(name.clone()))?;
+
+    match info.ggml_type {
+        GgmlType::TQ1_0 | GgmlType::TQ2_0 | GgmlType::I2S => {
+            let (w, scale) = gguf.load_ternary(&name)?;
+            Ok(BitLinear::with_backend(w, scale, backend.clone()).into())
+        }
+        _ => {
+            let w = gguf.load_quant(&name)?;
+            let bias_name = format!("{prefix}.bias");
+            let layer = if gguf.tensor_info(&bias_name).is_some() {
+                let bias = gguf.load_float(&bias_name)?;
+                DenseLinear::with_bias(w, bias.data().to_vec())
+            } else {
+                DenseLinear::new(w)
+            };
+            Ok(layer.into())
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! Memory-mapped GGUF files (`mmap` feature).
+//!
+//! This is the only place the loader uses `unsafe`: mapping the file.
+//! Everything else sees a plain `&[u8]` borrowed from [`MappedFile`], and
+//! tensors hold an `Arc<MappedFile>` so the mapping outlives every view.
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// A read-only memory mapping of a whole file.
+pub struct MappedFile {
+    map: memmap2::Mmap,
+}
+
+impl MappedFile {
+    /// Map `path` read-only.
+    ///
+    /// The file must not be truncated or rewritten while mapped — model
+    /// files are treated as immutable once published.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the mapping is read-only and private to this process; the
+        // only hazard is another process modifying the file underneath us,
+        // which model files never are (they are written once, then loaded).
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(Self { map })
+    }
+
+    /// The mapped bytes.
+    #[inline]
+    pub fn bytes(&self) -> &[u8] { &self.map }
+
+    /// Mapped length in bytes.
+    #[inline]
+    pub fn len(&self) -> usize { self.map.len() }
+
+    /// Whether the mapped file is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool { self.map.is_empty() }
+}
+
+impl std::fmt::Debug for MappedFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MappedFile({} bytes)", self.map.len())
+    }
+}
@@ -0,0 +1,433 @@
+//! GGML block quantizations — dequantization and matvec kernels.
+//!
+//! Standard (non-ternary) GGUF models store their weights in fixed-size
+//! blocks, each carrying its own f16 scale(s):
+//!
+//! - **Q8_0**: 32 weights, `d: f16` + 32 × i8. `w = d·q`.
+//! - **Q4_0**: 32 weights, `d: f16` + 16 bytes of nibbles. `w = d·(q − 8)`.
+//! - **Q4_K**: 256-weight super-block of 8 × 32 sub-blocks, `d, dmin: f16`,
+//!   12 bytes of packed 6-bit sub-block scales/mins, 128 bytes of nibbles.
+//!   `w = d·sc·q − dmin·m`.
+//! - **Q6_K**: 256-weight super-block of 16 × 16 sub-blocks, 128 bytes of
+//!   low nibbles, 64 bytes of high 2-bit pairs, 16 × i8 scales, `d: f16`.
+//!   `w = d·sc·(q − 32)`.
+//!
+//! Weights stay quantized in memory. The matvec kernels decode one block at
+//! a time into a stack buffer and accumulate in f32, so a row never exists
+//! in dequantized form. F32/F16/BF16 share the same paths, which lets a
+//! single weight type serve every standard-model projection.
+
+use rayon::prelude::*;
+
+use crate::gguf::{bf16_to_f32, f16_to_f32, GgmlType};
+use crate::tensor::QuantTensor;
+
+/// Weights per Q8_0 / Q4_0 block.
+pub const QK: usize = 32;
+/// Weights per K-quant super-block.
+pub const QK_K: usize = 256;
+
+const Q8_0_BYTES: usize = 2 + QK;
+const Q4_0_BYTES: usize = 2 + QK / 2;
+const Q4_K_BYTES: usize = 2 + 2 + 12 + QK_K / 2;
+const Q6_K_BYTES: usize = QK_K / 2 + QK_K / 4 + QK_K / 16 + 2;
+
+/// Float elements decoded per chunk by the shared chunk loop.
+const FLOAT_CHUNK: usize = 64;
+
+/// `(elements, bytes)` per block of a dense GGML type, or `None` for the
+/// ternary types (which load through `GgufFile::load_ternary`).
+pub fn block_layout(ggml_type: GgmlType) -> Option<(usize, usize)> {
+    match ggml_type {
+        GgmlType::F32 => Some((1, 4)),
+        GgmlType::F16 | GgmlType::BF16 => Some((1, 2)),
+        GgmlType::Q8_0 => Some((QK, Q8_0_BYTES)),
+        GgmlType::Q4_0 => Some((QK, Q4_0_BYTES)),
+        GgmlType::Q4_K => Some((QK_K, Q4_K_BYTES)),
+        GgmlType::Q6_K => Some((QK_K, Q6_K_BYTES)),
+        GgmlType::TQ1_0 | GgmlType::TQ2_0 | GgmlType::I2S => None,
+    }
+}
+
+/// Dequantize `data` (a whole number of blocks) into `out`.
+pub fn dequantize(ggml_type: GgmlType, data: &[u8], out: &mut [f32]) {
+    for_each_chunk(ggml_type, data, |start, values| {
+        out[start..start + values.len()].copy_from_slice(values);
+    });
+}
+
+/// Dot product of one quantized row with an f32 vector.
+pub fn dot(ggml_type: GgmlType, row: &[u8], x: &[f32]) -> f32 {
+    let mut acc = 0.0f32;
+    for_each_chunk(ggml_type, row, |start, values| {
+        acc += values.iter().zip(&x[start..]).map(|(w, v)| w * v).sum::<f32>();
+    });
+    acc
+}
+
+/// `y = W · x` for a quantized weight matrix. Rows run in parallel.
+pub fn quant_matvec(weights: &QuantTensor, input: &[f32]) -> Vec<f32> {
+    assert_eq!(input.len(), weights.cols(), "input dimension mismatch");
+    (0..weights.rows())
+        .into_par_iter()
+        .map(|r| dot(weights.ggml_type(), weights.row(r), input))
+        .collect()
+}
+
+/// `Y = X · Wᵀ` for `batch` input rows (`[batch, cols]`), returning
+/// `[batch, rows]`. Each weight block is decoded once per batch rather than
+/// once per input row.
+pub fn quant_matmul(weights: &QuantTensor, inputs: &[f32], batch: usize) -> Vec<f32> {
+    let (rows, cols) = (weights.rows(), weights.cols());
+    assert_eq!(inputs.len(), batch * cols, "input dimension mismatch");
+    if batch == 1 {
+        return quant_matvec(weights, inputs);
+    }
+
+    let per_row: Vec<Vec<f32>> = (0..rows)
+        .into_par_iter()
+        .map(|r| {
+            let mut acc = vec![0.0f32; batch];
+            for_each_chunk(weights.ggml_type(), weights.row(r), |start, values| {
+                for (b, slot) in acc.iter_mut().enumerate() {
+                    let x = &inputs[b * cols + start..];
+                    *slot += values.iter().zip(x).map(|(w, v)| w * v).sum::<f32>();
+                }
+            });
+            acc
+        })
+        .collect();
+
+    let mut out = vec![0.0f32; batch * rows];
+    for (r, acc) in per_row.iter().enumerate() {
+        for (b, &v) in acc.iter().enumerate() {
+            out[b * rows + r] = v;
+        }
+    }
+    out
+}
+
+/// Decode `data` block by block, calling `f(element_offset, values)` for
+/// each decoded chunk.
+fn for_each_chunk(ggml_type: GgmlType, data: &[u8], mut f: impl FnMut(usize, &[f32])) {
+    let (elements, bytes) = block_layout(ggml_type)
+        .unwrap_or_else(|| panic!("{ggml_type:?} is not a dense GGML type"));
+    assert_eq!(data.len() % bytes, 0, "data is not a whole number of {ggml_type:?} blocks");
+
+    // Float types are "blocks" of one element; group them to keep the
+    // per-chunk overhead small.
+    let group = if elements == 1 { FLOAT_CHUNK } else { 1 };
+    let mut buf = [0.0f32; QK_K];
+    for (i, chunk) in data.chunks(bytes * group).enumerate() {
+        let n = chunk.len() / bytes * elements;
+        let out = &mut buf[..n];
+        match ggml_type {
+            GgmlType::F32 => {
+                for (o, b) in out.iter_mut().zip(chunk.chunks_exact(4)) {
+                    *o = f32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+                }
+            }
+            GgmlType::F16 => {
+                for (o, b) in out.iter_mut().zip(chunk.chunks_exact(2)) {
+                    *o = f16_to_f32(u16::from_le_bytes([b[0], b[1]]));
+                }
+            }
+            GgmlType::BF16 => {
+                for (o, b) in out.iter_mut().zip(chunk.chunks_exact(2)) {
+                    *o = bf16_to_f32(u16::from_le_bytes([b[0], b[1]]));
+                }
+            }
+            GgmlType::Q8_0 => decode_q8_0(chunk, out),
+            GgmlType::Q4_0 => decode_q4_0(chunk, out),
+            GgmlType::Q4_K => decode_q4_k(chunk, out),
+            GgmlType::Q6_K => decode_q6_k(chunk, out),
+            GgmlType::TQ1_0 | GgmlType::TQ2_0 | GgmlType::I2S => unreachable!(),
+        }
+        f(i * elements * group, out);
+    }
+}
+
+#[inline]
+fn read_f16(bytes: &[u8], offset: usize) -> f32 {
+    f16_to_f32(u16::from_le_bytes([bytes[offset], bytes[offset + 1]]))
+}
+
+fn decode_q8_0(block: &[u8], out: &mut [f32]) {
+    let d = read_f16(block, 0);
+    for (o, &q) in out.iter_mut().zip(&block[2..]) {
+        *o = d * (q as i8) as f32;
+    }
+}
+
+fn decode_q4_0(block: &[u8], out: &mut [f32]) {
+    let d = read_f16(block, 0);
+    let qs = &block[2..];
+    for j in 0..QK / 2 {
+        out[j] = d * ((qs[j] & 0x0F) as i32 - 8) as f32;
+        out[j + QK / 2] = d * ((qs[j] >> 4) as i32 - 8) as f32;
+    }
+}
+
+/// Unpack the 6-bit scale and min of Q4_K sub-block `j` from the 12-byte
+/// scales field.
+#[inline]
+fn q4_k_scale_min(j: usize, q: &[u8]) -> (u8, u8) {
+    if j < 4 {
+        (q[j] & 63, q[j + 4] & 63)
+    } else {
+        (
+            (q[j + 4] & 0x0F) | ((q[j - 4] >> 6) << 4),
+            (q[j + 4] >> 4) | ((q[j] >> 6) << 4),
+        )
+    }
+}
+
+fn decode_q4_k(block: &[u8], out: &mut [f32]) {
+    let d = read_f16(block, 0);
+    let dmin = read_f16(block, 2);
+    let scales = &block[4..16];
+    let qs = &block[16..];
+
+    // Each 32 bytes of nibbles hold two sub-blocks: low nibbles, then high.
+    for pair in 0..4 {
+        let q = &qs[pair * 32..pair * 32 + 32];
+        let (sc_lo, m_lo) = q4_k_scale_min(2 * pair, scales);
+        let (sc_hi, m_hi) = q4_k_scale_min(2 * pair + 1, scales);
+        let (d_lo, min_lo) = (d * sc_lo as f32, dmin * m_lo as f32);
+        let (d_hi, min_hi) = (d * sc_hi as f32, dmin * m_hi as f32);
+        let y = &mut out[pair * 64..pair * 64 + 64];
+        for l in 0..32 {
+            y[l] = d_lo * (q[l] & 0x0F) as f32 - min_lo;
+            y[l + 32] = d_hi * (q[l] >> 4) as f32 - min_hi;
+        }
+    }
+}
+
+fn decode_q6_k(block: &[u8], out: &mut [f32]) {
+    let ql = &block[..QK_K / 2];
+    let qh = &block[QK_K / 2..QK_K / 2 + QK_K / 4];
+    let scales = &block[QK_K / 2 + QK_K / 4..QK_K / 2 + QK_K / 4 + QK_K / 16];
+    let d = read_f16(block, Q6_K_BYTES - 2);
+
+    for half in 0..2 {
+        let ql = &ql[half * 64..];
+        let qh = &qh[half * 32..];
+        let sc = &scales[half * 8..];
+        let y = &mut out[half * 128..half * 128 + 128];
+        for l in 0..32 {
+            let is = l / 16;
+            let q1 = ((ql[l] & 0x0F) | ((qh[l] & 3) << 4)) as i32 - 32;
+            let q2 = ((ql[l + 32] & 0x0F) | (((qh[l] >> 2) & 3) << 4)) as i32 - 32;
+            let q3 = ((ql[l] >> 4) | (((qh[l] >> 4) & 3) << 4)) as i32 - 32;
+            let q4 = ((ql[l + 32] >> 4) | (((qh[l] >> 6) & 3) << 4)) as i32 - 32;
+            y[l] = d * (sc[is] as i8) as f32 * q1 as f32;
+            y[l + 32] = d * (sc[is + 2] as i8) as f32 * q2 as f32;
+            y[l + 64] = d * (sc[is + 4] as i8) as f32 * q3 as f32;
+            y[l + 96] = d * (sc[is + 6] as i8) as f32 * q4 as f32;
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Quantization (Q8_0 / Q4_0)
+// ---------------------------------------------------------------------------
+
+/// Quantize f32 values (a multiple of 32) to Q8_0 blocks.
+pub fn quantize_q8_0(values: &[f32]) -> Vec<u8> {
+    assert_eq!(values.len() % QK, 0, "Q8_0 needs a multiple of {QK} values");
+    let mut out = Vec::with_capacity(values.len() / QK * Q8_0_BYTES);
+    for block in values.chunks_exact(QK) {
+        let amax = block.iter().fold(0.0f32, |m, v| m.max(v.abs()));
+        let d = amax / 127.0;
+        let id = if d > 0.0 { 1.0 / d } else { 0.0 };
+        out.extend_from_slice(&f32_to_f16(d).to_le_bytes());
+        out.extend(block.iter().map(|v| (v * id).round() as i8 as u8));
+    }
+    out
+}
+
+/// Quantize f32 values (a multiple of 32) to Q4_0 blocks.
+pub fn quantize_q4_0(values: &[f32]) -> Vec<u8> {
+    assert_eq!(values.len() % QK, 0, "Q4_0 needs a multiple of {QK} values");
+    let mut out = Vec::with_capacity(values.len() / QK * Q4_0_BYTES);
+    for block in values.chunks_exact(QK) {
+        // Signed absmax maps to −8, so the full [-8, 7] range is used.
+        let max = block.iter().fold(0.0f32, |m, &v| if v.abs() > m.abs() { v } else { m });
+        let d = max / -8.0;
+        let id = if d != 0.0 { 1.0 / d } else { 0.0 };
+        out.extend_from_slice(&f32_to_f16(d).to_le_bytes());
+        let q = |v: f32| ((v * id + 8.5) as u8).min(15);
+        for j in 0..QK / 2 {
+            out.push(q(block[j]) | (q(block[j + QK / 2]) << 4));
+        }
+    }
+    out
+}
+
+/// Convert f32 to IEEE half precision (round to nearest).
+pub(crate) fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exp = ((bits >> 23) & 0xFF) as i32;
+    let mant = bits & 0x7F_FFFF;
+
+    if exp == 0xFF {
+        return sign | 0x7C00 | if mant != 0 { 0x200 } else { 0 };
+    }
+    let e = exp - 127 + 15;
+    if e >= 0x1F {
+        return sign | 0x7C00;
+    }
+    if e <= 0 {
+        if e < -10 {
+            return sign;
+        }
+        // Subnormal half: shift the implicit-one mantissa into place.
+        let full = mant | 0x80_0000;
+        let shift = (14 - e) as u32;
+        let half = (full + (1 << (shift - 1))) >> shift;
+        return sign | half as u16;
+    }
+    let mut half = ((e as u32) << 10) | (mant >> 13);
+    if mant & 0x1000 != 0 {
+        half += 1; // may carry into the exponent, which is still correct
+    }
+    sign | half as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lcg_values(n: usize, seed: u64) -> Vec<f32> {
+        let mut state = seed;
+        (0..n)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                ((state >> 33) as f32 / (1u64 << 31) as f32) * 2.0 - 1.0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn f16_roundtrip() {
+        for &v in &[0.0f32, 1.0, -2.5, 0.1, 65504.0, 1e-5, -3.0e-7] {
+            let back = f16_to_f32(f32_to_f16(v));
+            assert!((back - v).abs() <= v.abs() * 1e-3 + 6e-8, "{v} -> {back}");
+        }
+    }
+
+    #[test]
+    fn q8_0_roundtrip() {
+        let values = lcg_values(64, 1);
+        let data = quantize_q8_0(&values);
+        assert_eq!(data.len(), 2 * Q8_0_BYTES);
+        let mut out = vec![0.0; 64];
+        dequantize(GgmlType::Q8_0, &data, &mut out);
+        for (a, b) in values.iter().zip(&out) {
+            assert!((a - b).abs() < 1.0 / 127.0, "{a} vs {b}");
+        }
+    }
+
+    #[test]
+    fn q4_0_roundtrip() {
+        let values = lcg_values(64, 2);
+        let data = quantize_q4_0(&values);
+        assert_eq!(data.len(), 2 * Q4_0_BYTES);
+        let mut out = vec![0.0; 64];
+        dequantize(GgmlType::Q4_0, &data, &mut out);
+        // One step is absmax/8; the value opposite the signed max clamps
+        // to 7 steps, so the worst-case error is a full step.
+        for (block, decoded) in values.chunks(QK).zip(out.chunks(QK)) {
+            let step = block.iter().fold(0.0f32, |m, v| m.max(v.abs())) / 8.0;
+            for (a, b) in block.iter().zip(decoded) {
+                assert!((a - b).abs() <= step * 1.01, "{a} vs {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn q4_k_layout() {
+        // Sub-block j has scale j+1 and min j; nibbles cycle 0..15.
+        let mut block = Vec::with_capacity(Q4_K_BYTES);
+        block.extend_from_slice(&f32_to_f16(0.5).to_le_bytes());
+        block.extend_from_slice(&f32_to_f16(0.25).to_le_bytes());
+        block.extend_from_slice(&[1, 2, 3, 4, 0, 1, 2, 3]);
+        for j in 4..8u8 {
+            block.push((j + 1) | (j << 4));
+        }
+        block.extend((0..128u32).map(|i| ((i % 16) | (((i + 5) % 16) << 4)) as u8));
+
+        let mut out = vec![0.0; QK_K];
+        dequantize(GgmlType::Q4_K, &block, &mut out);
+        for (p, &y) in out.iter().enumerate() {
+            let sub = p / 32;
+            let l = (p % 64) % 32;
+            let nibble = if sub % 2 == 0 { l % 16 } else { (l + 5) % 16 };
+            let expected = 0.5 * (sub + 1) as f32 * nibble as f32 - 0.25 * sub as f32;
+            assert_eq!(y, expected, "element {p}");
+        }
+    }
+
+    #[test]
+    fn q6_k_layout() {
+        let q: Vec<u8> = (0..QK_K).map(|p| ((p * 7 + 3) % 64) as u8).collect();
+        let scales: Vec<i8> = (0..16).map(|i| i as i8 - 8).collect();
+        let mut ql = vec![0u8; 128];
+        let mut qh = vec![0u8; 64];
+        for half in 0..2 {
+            for l in 0..32 {
+                let [q1, q2, q3, q4] = [0, 32, 64, 96].map(|k| q[half * 128 + l + k]);
+                ql[half * 64 + l] = (q1 & 0x0F) | ((q3 & 0x0F) << 4);
+                ql[half * 64 + l + 32] = (q2 & 0x0F) | ((q4 & 0x0F) << 4);
+                qh[half * 32 + l] = (q1 >> 4) | ((q2 >> 4) << 2) | ((q3 >> 4) << 4) | ((q4 >> 4) << 6);
+            }
+        }
+        let mut block = ql;
+        block.extend_from_slice(&qh);
+        block.extend(scales.iter().map(|&s| s as u8));
+        block.extend_from_slice(&f32_to_f16(0.125).to_le_bytes());
+        assert_eq!(block.len(), Q6_K_BYTES);
+
+        let mut out = vec![0.0; QK_K];
+        dequantize(GgmlType::Q6_K, &block, &mut out);
+        for (p, &y) in out.iter().enumerate() {
+            let expected = 0.125 * scales[p / 16] as f32 * (q[p] as i32 - 32) as f32;
+            assert_eq!(y, expected, "element {p}");
+        }
+    }
+
+    #[test]
+    fn matvec_matches_dequantized() {
+        let (rows, cols) = (6, 64);
+        let values = lcg_values(rows * cols, 3);
+        let x = lcg_values(cols, 4);
+        for (ty, data) in [
+            (GgmlType::Q8_0, quantize_q8_0(&values)),
+            (GgmlType::Q4_0, quantize_q4_0(&values)),
+            (GgmlType::F32, values.iter().flat_map(|v| v.to_le_bytes()).collect()),
+        ] {
+            let w = QuantTensor::new(ty, data, rows, cols);
+            let dense = w.dequantize();
+            let y = quant_matvec(&w, &x);
+            for r in 0..rows {
+                let expected: f32 = dense[r * cols..(r + 1) * cols].iter().zip(&x).map(|(a, b)| a * b).sum();
+                assert!((y[r] - expected).abs() < 1e-4, "{ty:?} row {r}");
+            }
+        }
+    }
+
+    #[test]
+    fn matmul_matches_matvec() {
+        let (rows, cols, batch) = (5, 64, 3);
+        let w = QuantTensor::new(GgmlType::Q4_0, quantize_q4_0(&lcg_values(rows * cols, 5)), rows, cols);
+        let inputs = lcg_values(batch * cols, 6);
+        let y = quant_matmul(&w, &inputs, batch);
+        for b in 0..batch {
+            let single = quant_matvec(&w, &inputs[b * cols..(b + 1) * cols]);
+            for r in 0..rows {
+                assert!((y[b * rows + r] - single[r]).abs() < 1e-5);
+            }
+        }
+    }
+}
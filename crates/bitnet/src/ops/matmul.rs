@@ -98,6 +98,39 @@ pub fn ternary_matvec_scaled(
     raw.iter().map(|&acc| acc as f32 * combined_scale).collect()
 }
 
+/// Ternary matrix-matrix product over a batch: Y = X · Wᵀ.
+///
+/// `inputs` is `[batch, in_features]` row-major; returns `[batch, out_features]`.
+/// Each weight row is unpacked once and applied to every input, so the
+/// unpacking cost is shared across the batch.
+pub fn ternary_matmul(weights: &TernaryTensor, inputs: &[i8], batch: usize) -> Vec<i32> {
+    let rows = weights.rows();
+    let cols = weights.cols();
+    assert_eq!(inputs.len(), batch * cols, "dimension mismatch");
+
+    let mut output = vec![0i32; batch * rows];
+    let mut bits = vec![0u8; cols];
+    for row in 0..rows {
+        unpack_row_bits(weights, row, &mut bits);
+        for (b, x) in inputs.chunks_exact(cols).enumerate() {
+            output[b * rows + row] = bits.iter().zip(x).map(|(&w, &a)| ternary_mul_i8(w, a)).sum();
+        }
+    }
+    output
+}
+
+/// Unpack one row's 2-bit weight codes into `out` (length `cols`).
+fn unpack_row_bits(weights: &TernaryTensor, row: usize, out: &mut [u8]) {
+    let (row_bytes, start_offset, _) = weights.row_bytes(row);
+    let slots = row_bytes
+        .iter()
+        .flat_map(|&byte| (0..4).map(move |slot| (byte >> (slot * 2)) & 0b11))
+        .skip(start_offset);
+    for (dst, bits) in out.iter_mut().zip(slots) {
+        *dst = bits;
+    }
+}
+
 /// Batch ternary matmul: Y = W · X^T for multiple input vectors.
 ///
 /// `inputs` is a slice of activation vectors, all of length `in_features`.
@@ -230,6 +263,16 @@ mod tests {
         assert_eq!(results[1], vec![0, 0]);
     }
 
+    #[test]
+    fn matmul_matches_matvec() {
+        // 3 × 5: rows straddle byte boundaries.
+        let w = weights_from_i8(&[1, -1, 0, 1, -1, 0, 0, 1, 1, -1, -1, -1, 1, 0, 1], 3, 5);
+        let x = vec![1i8, 2, 3, 4, 5, -5, -4, -3, -2, -1];
+        let y = ternary_matmul(&w, &x, 2);
+        assert_eq!(&y[..3], &ternary_matvec(&w, &x[..5])[..]);
+        assert_eq!(&y[3..], &ternary_matvec(&w, &x[5..])[..]);
+    }
+
     // Verify no multiplication happens — the computation is purely additive.
     #[test]
     fn accumulator_range() {
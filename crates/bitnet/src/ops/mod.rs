@@ -9,7 +9,11 @@
 //!   table. Replaces all arithmetic with table lookups.
 //!
 //! - **Quantize** (`quantize`): Absmax 8-bit activation quantization.
+//!
+//! - **Dequant** (`dequant`): GGML block formats (Q8_0, Q4_0, Q4_K, Q6_K)
+//!   for standard non-ternary models — block decode and f32 matvec.
 
 pub mod matmul;
 pub mod lut;
 pub mod quantize;
+pub mod dequant;
@@ -4,6 +4,14 @@
 //! Activations are quantized to 8-bit integers with an f32 scale factor.
 
 use std::fmt;
+use std::ops::Deref;
+#[cfg(feature = "mmap")]
+use std::sync::Arc;
+
+use crate::gguf::GgmlType;
+#[cfg(feature = "mmap")]
+use crate::mmap::MappedFile;
+use crate::ops::dequant;
 
 // ---------------------------------------------------------------------------
 // Ternary value
@@ -309,6 +317,117 @@ impl fmt::Debug for FloatTensor {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Quantized tensor — GGML block formats for standard (non-ternary) weights
+// ---------------------------------------------------------------------------
+
+/// Raw weight bytes: an owned buffer, or a zero-copy view into a
+/// memory-mapped GGUF file (`mmap` feature).
+#[derive(Clone)]
+pub enum WeightBytes {
+    /// Bytes read into (or decoded to) an owned buffer.
+    Owned(Vec<u8>),
+    /// `len` bytes at `offset` within a mapped file.
+    #[cfg(feature = "mmap")]
+    Mapped {
+        file: Arc<MappedFile>,
+        offset: usize,
+        len: usize,
+    },
+}
+
+impl WeightBytes {
+    /// Whether these bytes are a view of a mapped file rather than owned.
+    pub fn is_mapped(&self) -> bool {
+        match self {
+            WeightBytes::Owned(_) => false,
+            #[cfg(feature = "mmap")]
+            WeightBytes::Mapped { .. } => true,
+        }
+    }
+}
+
+impl Deref for WeightBytes {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        match self {
+            WeightBytes::Owned(data) => data,
+            #[cfg(feature = "mmap")]
+            WeightBytes::Mapped { file, offset, len } => &file.bytes()[*offset..*offset + *len],
+        }
+    }
+}
+
+impl From<Vec<u8>> for WeightBytes {
+    fn from(data: Vec<u8>) -> Self { WeightBytes::Owned(data) }
+}
+
+/// A 2D weight matrix kept in its GGUF storage format (Q8_0, Q4_0, Q4_K,
+/// Q6_K, or F32/F16/BF16). Rows are decoded a block at a time by the
+/// kernels in [`crate::ops::dequant`].
+#[derive(Clone)]
+pub struct QuantTensor {
+    ggml_type: GgmlType,
+    data: WeightBytes,
+    rows: usize,
+    cols: usize,
+    row_bytes: usize,
+}
+
+impl QuantTensor {
+    /// Wrap raw GGUF bytes (owned or mapped) for a `rows × cols` matrix of
+    /// `ggml_type`. The kernels read the GGUF block layout directly, so a
+    /// mapped tensor is never copied.
+    ///
+    /// Panics if the type is ternary, `cols` is not a whole number of
+    /// blocks, or `data` has the wrong length.
+    pub fn new(ggml_type: GgmlType, data: impl Into<WeightBytes>, rows: usize, cols: usize) -> Self {
+        let data = data.into();
+        let (block_elements, block_bytes) = dequant::block_layout(ggml_type)
+            .unwrap_or_else(|| panic!("{ggml_type:?} is not a dense GGML type"));
+        assert_eq!(cols % block_elements, 0, "cols must be a multiple of the {ggml_type:?} block size");
+        let row_bytes = cols / block_elements * block_bytes;
+        assert_eq!(data.len(), rows * row_bytes, "data length must match shape");
+        Self { ggml_type, data, rows, cols, row_bytes }
+    }
+
+    #[inline]
+    pub fn ggml_type(&self) -> GgmlType { self.ggml_type }
+
+    #[inline]
+    pub fn rows(&self) -> usize { self.rows }
+
+    #[inline]
+    pub fn cols(&self) -> usize { self.cols }
+
+    /// Raw bytes of one row.
+    #[inline]
+    pub fn row(&self, row: usize) -> &[u8] {
+        &self.data[row * self.row_bytes..(row + 1) * self.row_bytes]
+    }
+
+    /// Storage size in bytes.
+    pub fn byte_len(&self) -> usize { self.data.len() }
+
+    /// Whether the weights are a zero-copy view of a mapped file.
+    pub fn is_mapped(&self) -> bool { self.data.is_mapped() }
+
+    /// Dequantize the whole matrix (row-major f32).
+    pub fn dequantize(&self) -> Vec<f32> {
+        let mut out = vec![0.0; self.rows * self.cols];
+        dequant::dequantize(self.ggml_type, &self.data, &mut out);
+        out
+    }
+}
+
+impl fmt::Debug for QuantTensor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "QuantTensor({:?}, {}x{})", self.ggml_type, self.rows, self.cols)
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -4,9 +4,13 @@
 //! Project-level config: `.agentos/config.yaml` (default model alias only, safe to commit)
 //!
 //! Resolution: project config → user config → env var fallback → error.
+//!
+//! `models.yaml` may also carry a `prices` table (USD per million
+//! tokens, keyed by model id or alias). The server uses it to turn
+//! `MessagesResponse.usage` into dollar budgets.
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
@@ -21,6 +25,21 @@ pub struct ProviderConfig {
     pub models: HashMap<String, String>, // alias → model ID
 }
 
+/// Token prices for one model, in USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+}
+
+impl ModelPrice {
+    /// Dollar cost of one call's usage.
+    pub fn cost_usd(&self, input_tokens: u32, output_tokens: u32) -> f64 {
+        (input_tokens as f64 * self.input_per_mtok + output_tokens as f64 * self.output_per_mtok)
+            / 1_000_000.0
+    }
+}
+
 /// Top-level models configuration (user-level file).
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ModelsConfig {
@@ -28,6 +47,9 @@ pub struct ModelsConfig {
     pub providers: HashMap<String, ProviderConfig>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub default: Option<String>,
+    /// Model id or alias → price. See [`ModelsConfig::price_for`].
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub prices: HashMap<String, ModelPrice>,
 }
 
 /// Project-level config (no secrets — safe to commit).
@@ -118,6 +140,13 @@ impl ModelsConfig {
         config
     }
 
+    /// Load a specific models file (no project merge, no env fallback).
+    pub fn load_file(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        serde_yaml::from_str(&content).map_err(|e| format!("Invalid {}: {e}", path.display()))
+    }
+
     /// Load just the user-level config file.
     fn load_user_config() -> Self {
         let Some(path) = user_config_dir() else {
@@ -204,6 +233,30 @@ impl ModelsConfig {
         None
     }
 
+    /// Price for a model as reported in `MessagesResponse.model`.
+    ///
+    /// Lookup order: exact key, then an alias key whose model id
+    /// matches, then the longest key that prefixes the model id (so
+    /// `claude-haiku-4-5` prices the dated `claude-haiku-4-5-20251001`).
+    pub fn price_for(&self, model: &str) -> Option<ModelPrice> {
+        if let Some(p) = self.prices.get(model) {
+            return Some(*p);
+        }
+        if let Some(p) = self.prices.iter().find_map(|(key, p)| {
+            self.providers
+                .values()
+                .any(|prov| prov.models.get(key).is_some_and(|id| id == model))
+                .then_some(*p)
+        }) {
+            return Some(p);
+        }
+        self.prices
+            .iter()
+            .filter(|(key, _)| model.starts_with(key.as_str()))
+            .max_by_key(|(key, _)| key.len())
+            .map(|(_, p)| *p)
+    }
+
     /// Get a flat list of all configured models.
     pub fn all_models(&self) -> Vec<ModelEntry> {
         let mut entries = Vec::new();
@@ -345,6 +398,31 @@ default: sonnet
         assert_eq!(anthropic.models.len(), 2);
     }
 
+    #[test]
+    fn price_table_resolves_ids_aliases_and_dated_ids() {
+        let yaml = r#"
+providers:
+  anthropic:
+    models:
+      sonnet: claude-sonnet-4-6
+prices:
+  sonnet: { input_per_mtok: 3.0, output_per_mtok: 15.0 }
+  claude-haiku-4-5: { input_per_mtok: 1.0, output_per_mtok: 5.0 }
+  claude-opus-4-6: { input_per_mtok: 15.0, output_per_mtok: 75.0 }
+"#;
+        let config: ModelsConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(config.price_for("claude-opus-4-6").unwrap().input_per_mtok, 15.0);
+        assert_eq!(config.price_for("claude-sonnet-4-6").unwrap().output_per_mtok, 15.0);
+        assert_eq!(
+            config.price_for("claude-haiku-4-5-20251001").unwrap().input_per_mtok,
+            1.0
+        );
+        assert!(config.price_for("gpt-4o").is_none());
+
+        let cost = config.price_for("sonnet").unwrap().cost_usd(1_000_000, 100_000);
+        assert!((cost - 4.5).abs() < 1e-9);
+    }
+
     #[test]
     fn round_trip_yaml() {
         let config = sample_config();
@@ -18,4 +18,13 @@ pub enum ShimClientError {
 
     #[error("invalid manifest: {0}")]
     InvalidManifest(String),
+
+    #[error("invalid shim weights: {0}")]
+    InvalidWeights(String),
+
+    #[error("invalid training data: {0}")]
+    InvalidDataset(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
 }
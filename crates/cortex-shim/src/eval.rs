@@ -0,0 +1,298 @@
+//! Offline evaluation of shim compositions (`eval` feature).
+//!
+//! Before a composition goes live (or onto a canary) the shim-expert
+//! replays a labeled JSONL — the same `{"vector", "label"}` rows
+//! [`train`](crate::train) consumes — through it and through the store's
+//! current composition, and compares the two. Each row's vector is fed
+//! to the gate shims as their pooled input, the composition's rules
+//! resolve the gate decisions into the [`ShimMetadata`] a live response
+//! would carry, and an [`EvalTarget`] says which outcome counts as a
+//! positive prediction. Labels are positive when non-zero.
+//!
+//! Only gate shims run; steers shape generation, which needs a model, so
+//! a steer target scores whether the rules *activate* it.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use agentos_kernel::shim_store::ShimStore;
+use agentos_llm::types::{ShimAttachment, ShimMetadata};
+use serde::Serialize;
+
+use crate::error::ShimClientError;
+use crate::ffn::ShimFfn;
+use crate::manifest::{ShimManifest, ShimOutput, ShimPhase};
+use crate::train::{Dataset, SplitMetrics};
+
+/// Parse a store's `composition.json` as the [`ShimAttachment`] it holds.
+pub fn composition(store: &ShimStore, store_name: &str) -> Result<ShimAttachment, ShimClientError> {
+    let bytes = store
+        .composition_bytes_for(store_name)
+        .ok_or_else(|| ShimClientError::NotFound(format!("shim_store `{store_name}`")))?;
+    if bytes.is_empty() || bytes == b"{}" {
+        return Ok(ShimAttachment::default());
+    }
+    serde_json::from_slice(bytes)
+        .map_err(|e| ShimClientError::InvalidManifest(format!("composition of `{store_name}`: {e}")))
+}
+
+/// Parse raw composition bytes; empty and `{}` mean no shims.
+pub fn parse_composition(bytes: &[u8]) -> Result<ShimAttachment, ShimClientError> {
+    if bytes.is_empty() || bytes == b"{}" {
+        return Ok(ShimAttachment::default());
+    }
+    serde_json::from_slice(bytes).map_err(|e| ShimClientError::InvalidManifest(format!("composition: {e}")))
+}
+
+/// Which composition outcome counts as predicting a positive label.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalTarget {
+    /// The response is not silenced.
+    Respond,
+    /// The rules silence the response.
+    Silent,
+    /// The rules raise this signal.
+    Signal(String),
+    /// This steer ends up active.
+    Steer(String),
+    /// This gate's decision is above `GATE_FIRE_THRESHOLD`.
+    Gate(String),
+}
+
+impl EvalTarget {
+    pub fn matches(&self, metadata: &ShimMetadata) -> bool {
+        match self {
+            Self::Respond => !metadata.silent,
+            Self::Silent => metadata.silent,
+            Self::Signal(s) => metadata.signals.contains(s),
+            Self::Steer(id) => metadata.active_steers.contains(id),
+            Self::Gate(id) => metadata
+                .gate_decisions
+                .get(id)
+                .is_some_and(|&d| d > agentos_kernel::shim_stats::GATE_FIRE_THRESHOLD),
+        }
+    }
+}
+
+impl FromStr for EvalTarget {
+    type Err = ShimClientError;
+
+    /// `respond`, `silent`, `signal:<name>`, `steer:<id>` or `gate:<id>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let named = |prefix: &str| s.strip_prefix(prefix).filter(|n| !n.is_empty()).map(str::to_string);
+        match s {
+            "respond" => Ok(Self::Respond),
+            "silent" => Ok(Self::Silent),
+            _ => named("signal:")
+                .map(Self::Signal)
+                .or_else(|| named("steer:").map(Self::Steer))
+                .or_else(|| named("gate:").map(Self::Gate))
+                .ok_or_else(|| ShimClientError::InvalidDataset(format!("unknown eval target `{s}`"))),
+        }
+    }
+}
+
+impl fmt::Display for EvalTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Respond => f.write_str("respond"),
+            Self::Silent => f.write_str("silent"),
+            Self::Signal(s) => write!(f, "signal:{s}"),
+            Self::Steer(id) => write!(f, "steer:{id}"),
+            Self::Gate(id) => write!(f, "gate:{id}"),
+        }
+    }
+}
+
+/// A store's gate shims, decoded and ready to score vectors.
+#[derive(Debug, Clone, Default)]
+pub struct GateBank {
+    gates: HashMap<String, (ShimFfn, ShimOutput)>,
+}
+
+impl GateBank {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load every active gate shim of `store_name`.
+    pub fn load(store: &ShimStore, store_name: &str) -> Result<Self, ShimClientError> {
+        let records = store
+            .shims_in(store_name)
+            .ok_or_else(|| ShimClientError::NotFound(format!("shim_store `{store_name}`")))?;
+        let mut bank = Self::new();
+        for record in records.values() {
+            let manifest: ShimManifest = serde_json::from_slice(&record.manifest_json)
+                .map_err(|e| ShimClientError::InvalidManifest(format!("shim `{}`: {e}", record.shim_id)))?;
+            if manifest.phase != ShimPhase::Gate {
+                continue;
+            }
+            let bytes = std::fs::read(&record.onnx_path).map_err(|e| {
+                ShimClientError::InvalidWeights(format!("{}: {e}", record.onnx_path.display()))
+            })?;
+            let output = ShimOutput::parse(&manifest.output_shape.kind)?;
+            bank.insert(manifest.id, ShimFfn::from_onnx(&bytes)?, output);
+        }
+        Ok(bank)
+    }
+
+    pub fn insert(&mut self, id: impl Into<String>, ffn: ShimFfn, output: ShimOutput) {
+        self.gates.insert(id.into(), (ffn, output));
+    }
+
+    pub fn len(&self) -> usize {
+        self.gates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.gates.is_empty()
+    }
+
+    /// What `attachment` would report for a prompt whose pooled vector
+    /// is `vector`.
+    pub fn outcome(&self, attachment: &ShimAttachment, vector: &[f32]) -> Result<ShimMetadata, ShimClientError> {
+        let gate_decisions = attachment
+            .gate_shims
+            .iter()
+            .map(|id| {
+                let (ffn, output) = self.gates.get(id).ok_or_else(|| ShimClientError::NotFound(id.clone()))?;
+                if ffn.input_dim() != vector.len() {
+                    return Err(ShimClientError::InvalidDataset(format!(
+                        "gate `{id}` takes {} inputs, the data has {}",
+                        ffn.input_dim(),
+                        vector.len()
+                    )));
+                }
+                Ok((id.clone(), output.decision(&ffn.forward(vector))))
+            })
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        let action = attachment.resolve_rules(&gate_decisions);
+        Ok(ShimMetadata {
+            silent: action.is_some_and(|a| a.silent),
+            active_steers: attachment.active_steers(action),
+            signals: action.and_then(|a| a.signal.clone()).into_iter().collect(),
+            gate_decisions,
+            prefill_ms: None,
+            generation_ms: None,
+        })
+    }
+}
+
+/// How well one composition predicts the labels.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EvalReport {
+    pub target: String,
+    /// Of the rows the composition predicted positive, the share labeled positive.
+    pub precision: f64,
+    /// Of the rows labeled positive, the share the composition predicted positive.
+    pub recall: f64,
+    pub accuracy: f64,
+    /// Two-class metrics; class 1 is the positive label.
+    pub metrics: SplitMetrics,
+}
+
+/// A candidate composition scored against the store's current one.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EvalComparison {
+    pub current: EvalReport,
+    pub candidate: EvalReport,
+}
+
+/// Replay `data` through `attachment`.
+pub fn evaluate(
+    bank: &GateBank,
+    attachment: &ShimAttachment,
+    data: &Dataset,
+    target: &EvalTarget,
+) -> Result<EvalReport, ShimClientError> {
+    let pairs = (0..data.len())
+        .map(|i| {
+            let predicted = target.matches(&bank.outcome(attachment, data.vector(i))?);
+            Ok((usize::from(data.labels()[i] != 0), usize::from(predicted)))
+        })
+        .collect::<Result<Vec<_>, ShimClientError>>()?;
+    let metrics = SplitMetrics::from_predictions(pairs, 2);
+    Ok(EvalReport {
+        target: target.to_string(),
+        precision: metrics.per_class[1].precision,
+        recall: metrics.per_class[1].recall,
+        accuracy: metrics.accuracy,
+        metrics,
+    })
+}
+
+/// Score `candidate` (raw composition bytes) and the current composition
+/// of `store_name` on the same data, with the store's gate shims.
+pub fn compare(
+    store: &ShimStore,
+    store_name: &str,
+    candidate: &[u8],
+    data: &Dataset,
+    target: &EvalTarget,
+) -> Result<EvalComparison, ShimClientError> {
+    let bank = GateBank::load(store, store_name)?;
+    Ok(EvalComparison {
+        current: evaluate(&bank, &composition(store, store_name)?, data, target)?,
+        candidate: evaluate(&bank, &parse_composition(candidate)?, data, target)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agentos_llm::types::{ShimAction, ShimCondition, ShimRule};
+
+    use crate::ffn::DenseLayer;
+
+    /// A scalar gate on 2-d vectors whose logit is `scale * x[0]`.
+    fn gate(scale: f32) -> ShimFfn {
+        ShimFfn::new(vec![DenseLayer::new(vec![scale, 0.0], vec![0.0], 2, 1).unwrap()]).unwrap()
+    }
+
+    fn silence_below(gt: f32) -> ShimAttachment {
+        ShimAttachment {
+            gate_shims: vec!["should_respond".into()],
+            shim_rules: vec![
+                ShimRule::If {
+                    condition: ShimCondition { gate: "should_respond".into(), gt },
+                    action: ShimAction::default(),
+                },
+                ShimRule::Else { action: ShimAction { silent: true, ..Default::default() } },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn targets_round_trip_through_strings() {
+        for s in ["respond", "silent", "signal:escalate", "steer:polite", "gate:should_respond"] {
+            assert_eq!(s.parse::<EvalTarget>().unwrap().to_string(), s);
+        }
+        assert!("signal:".parse::<EvalTarget>().is_err());
+        assert!("louder".parse::<EvalTarget>().is_err());
+    }
+
+    #[test]
+    fn stricter_threshold_trades_recall_for_precision() {
+        let mut bank = GateBank::new();
+        bank.insert("should_respond", gate(4.0), ShimOutput::Scalar);
+        // x[0] in [-1, 1]; labeled positive above -0.25, so a 0.5 cut
+        // (x > 0) misses some positives and a 0.2 cut (x > -0.35) lets in
+        // a negative.
+        let rows: Vec<(f32, usize)> = vec![(-1.0, 0), (-0.5, 0), (-0.3, 0), (-0.2, 1), (0.1, 1), (0.6, 1), (1.0, 1)];
+        let data = Dataset::new(
+            rows.iter().map(|(x, _)| vec![*x, 0.0]).collect(),
+            rows.iter().map(|(_, l)| *l).collect(),
+        )
+        .unwrap();
+
+        let strict = evaluate(&bank, &silence_below(0.5), &data, &EvalTarget::Respond).unwrap();
+        assert_eq!((strict.precision, strict.recall), (1.0, 0.75));
+        let loose = evaluate(&bank, &silence_below(0.2), &data, &EvalTarget::Respond).unwrap();
+        assert_eq!((loose.precision, loose.recall), (0.8, 1.0));
+
+        let missing = ShimAttachment { gate_shims: vec!["nope".into()], ..Default::default() };
+        assert!(evaluate(&bank, &missing, &data, &EvalTarget::Respond).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+//! Shim FFN weights and forward pass.
+//!
+//! A shim is a small MLP over a hidden-state vector: linear layers with
+//! ReLU between them and raw logits at the output, exactly the
+//! `ShimFFN` `train_shim.py` builds. [`ShimFfn`] holds the weights in
+//! `nn.Linear` layout and converts to and from the ONNX files the shim
+//! store keeps ([`crate::onnx`]).
+
+use crate::error::ShimClientError;
+use crate::onnx;
+
+/// One linear layer: `y = W · x + b`, with `W` stored `[out_dim, in_dim]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DenseLayer {
+    weight: Vec<f32>,
+    bias: Vec<f32>,
+    in_dim: usize,
+    out_dim: usize,
+}
+
+impl DenseLayer {
+    pub fn new(weight: Vec<f32>, bias: Vec<f32>, in_dim: usize, out_dim: usize) -> Result<Self, ShimClientError> {
+        if in_dim == 0 || out_dim == 0 || weight.len() != in_dim * out_dim || bias.len() != out_dim {
+            return Err(ShimClientError::InvalidWeights(format!(
+                "layer {in_dim}→{out_dim} needs {} weights and {out_dim} biases, got {} and {}",
+                in_dim * out_dim,
+                weight.len(),
+                bias.len()
+            )));
+        }
+        Ok(Self { weight, bias, in_dim, out_dim })
+    }
+
+    pub fn in_dim(&self) -> usize {
+        self.in_dim
+    }
+
+    pub fn out_dim(&self) -> usize {
+        self.out_dim
+    }
+
+    /// Weights, row-major `[out_dim, in_dim]`.
+    pub fn weight(&self) -> &[f32] {
+        &self.weight
+    }
+
+    pub fn bias(&self) -> &[f32] {
+        &self.bias
+    }
+
+    pub fn forward(&self, input: &[f32]) -> Vec<f32> {
+        self.weight
+            .chunks_exact(self.in_dim)
+            .zip(&self.bias)
+            .map(|(row, b)| b + row.iter().zip(input).map(|(w, x)| w * x).sum::<f32>())
+            .collect()
+    }
+}
+
+/// A shim MLP: `input_dim → hidden… → output_dim`, ReLU between layers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShimFfn {
+    layers: Vec<DenseLayer>,
+}
+
+impl ShimFfn {
+    /// Chain `layers`; each layer's input must match the previous output.
+    pub fn new(layers: Vec<DenseLayer>) -> Result<Self, ShimClientError> {
+        if layers.is_empty() {
+            return Err(ShimClientError::InvalidWeights("FFN has no layers".into()));
+        }
+        if let Some(pair) = layers.windows(2).find(|p| p[0].out_dim != p[1].in_dim) {
+            return Err(ShimClientError::InvalidWeights(format!(
+                "layer output {} does not feed layer input {}",
+                pair[0].out_dim, pair[1].in_dim
+            )));
+        }
+        Ok(Self { layers })
+    }
+
+    /// Load from ONNX bytes (as stored in a shim store).
+    pub fn from_onnx(bytes: &[u8]) -> Result<Self, ShimClientError> {
+        onnx::decode(bytes)
+    }
+
+    /// Encode as ONNX bytes cortex and the shim store accept.
+    pub fn to_onnx(&self) -> Vec<u8> {
+        onnx::encode(self)
+    }
+
+    pub fn layers(&self) -> &[DenseLayer] {
+        &self.layers
+    }
+
+    pub fn input_dim(&self) -> usize {
+        self.layers[0].in_dim
+    }
+
+    pub fn output_dim(&self) -> usize {
+        self.layers[self.layers.len() - 1].out_dim
+    }
+
+    /// Total weights and biases.
+    pub fn param_count(&self) -> usize {
+        self.layers.iter().map(|l| l.weight.len() + l.bias.len()).sum()
+    }
+
+    /// Raw output logits for one input vector.
+    pub fn forward(&self, input: &[f32]) -> Vec<f32> {
+        assert_eq!(input.len(), self.input_dim(), "shim input dimension mismatch");
+        let last = self.layers.len() - 1;
+        let mut x = input.to_vec();
+        for (i, layer) in self.layers.iter().enumerate() {
+            x = layer.forward(&x);
+            if i < last {
+                x.iter_mut().for_each(|v| *v = v.max(0.0));
+            }
+        }
+        x
+    }
+}
+
+/// Logistic sigmoid — turns a scalar gate's logit into a probability.
+pub fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forward_applies_relu_between_layers() {
+        let ffn = ShimFfn::new(vec![
+            DenseLayer::new(vec![1.0, 0.0, 0.0, 1.0], vec![0.0, -5.0], 2, 2).unwrap(),
+            DenseLayer::new(vec![1.0, 1.0], vec![1.0], 2, 1).unwrap(),
+        ])
+        .unwrap();
+        // Second hidden unit is clamped to zero.
+        assert_eq!(ffn.forward(&[2.0, 3.0]), vec![3.0]);
+        assert_eq!(ffn.param_count(), 9);
+        assert!((sigmoid(0.0) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rejects_mismatched_layers() {
+        assert!(DenseLayer::new(vec![1.0; 5], vec![0.0; 2], 3, 2).is_err());
+        let a = DenseLayer::new(vec![1.0; 6], vec![0.0; 2], 3, 2).unwrap();
+        let b = DenseLayer::new(vec![1.0; 3], vec![0.0], 3, 1).unwrap();
+        assert!(ShimFfn::new(vec![a, b]).is_err());
+        assert!(ShimFfn::new(Vec::new()).is_err());
+    }
+}
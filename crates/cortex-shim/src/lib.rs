@@ -12,17 +12,32 @@
 //!   via `/v1/shims/...`. Where the shim-expert agent (Step 5) will
 //!   register, validate, and retire shims.
 //!
+//! Shim weights themselves are small FFNs ([`ShimFfn`], stored as ONNX);
+//! with the `local` feature, `local::LocalShimExecutor` runs them
+//! in-process against a BitNet model instead of cortex. [`train`] trains
+//! them natively from `(vector, label)` JSONL, without Python or torch.
+//! With the `eval` feature, `eval` replays the same JSONL through a
+//! store's compositions to compare a candidate against the current one.
+//!
 //! Wire spec: see `project_cortex_v1_shim_api.md` in the integration
 //! memory.
 
 pub mod client;
 pub mod embed;
 pub mod error;
+#[cfg(feature = "eval")]
+pub mod eval;
+pub mod ffn;
+#[cfg(feature = "local")]
+pub mod local;
 pub mod manifest;
+pub mod onnx;
+pub mod train;
 
 pub use client::CortexShimClient;
 pub use embed::{EmbedClient, EmbedRequest, EmbedResponse, KnownPooling, Pooling};
 pub use error::ShimClientError;
+pub use ffn::{DenseLayer, ShimFfn};
 pub use manifest::{
-    Attachment, InputShape, OutputShape, ShimDecision, ShimManifest, ShimPhase, ShimSummary,
+    Attachment, InputShape, OutputShape, ShimDecision, ShimManifest, ShimOutput, ShimPhase, ShimSummary,
 };
@@ -0,0 +1,486 @@
+//! In-process shim execution for local models (`local` feature).
+//!
+//! Cortex runs shims inside its own forward pass; AgentOS only ships a
+//! [`ShimAttachment`] over HTTP. [`LocalShimExecutor`] does the same job
+//! against a BitNet [`TransformerModel`], with the shim weights read from
+//! a kernel [`ShimStore`]:
+//!
+//! 1. Prefill the prompt.
+//! 2. Gate shims fire once on the pooled final hidden state.
+//! 3. The attachment's `shim_rules` pick an action (silence, steers,
+//!    signal) from the gate decisions.
+//! 4. Steer shims add their `hidden_delta` to the final hidden state at
+//!    every decode step.
+//!
+//! The result carries the same [`ShimMetadata`] cortex returns, so the
+//! shim pipeline can be exercised offline.
+//!
+//! Gate vectors are built like `agentos_bitnet::embedder::ModelEmbedder`
+//! builds embeddings (BOS row dropped, unit length), so shims trained on
+//! its vectors see the same distribution. Only `final`-layer attachments
+//! run here; injection shims need hooks inside the blocks and are skipped
+//! with a warning.
+
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::time::Instant;
+
+use agentos_bitnet::layers::generation::{GenerateOptions, Generation, HiddenHook};
+use agentos_bitnet::layers::model::TransformerModel;
+use agentos_bitnet::{Pooling, Tokenizer};
+use agentos_kernel::shim_store::ShimStore;
+use agentos_llm::types::{ShimAttachment, ShimMetadata, ShimRule};
+
+use crate::error::ShimClientError;
+use crate::ffn::ShimFfn;
+pub use crate::eval::composition;
+pub use crate::manifest::ShimOutput;
+use crate::manifest::{ShimManifest, ShimPhase};
+
+/// One shim ready to run: its manifest and decoded weights.
+#[derive(Debug, Clone)]
+pub struct LocalShim {
+    manifest: ShimManifest,
+    ffn: ShimFfn,
+    output: ShimOutput,
+    pooling: Option<Pooling>,
+}
+
+impl LocalShim {
+    /// Check `ffn` against `manifest`: input width is `hidden_dim`, output
+    /// width matches the output kind, and the attachment is one this
+    /// executor can run.
+    pub fn new(manifest: ShimManifest, ffn: ShimFfn) -> Result<Self, ShimClientError> {
+        let bad = |msg: String| ShimClientError::InvalidManifest(format!("shim `{}`: {msg}", manifest.id));
+        let hidden_dim = manifest.input_shape.hidden_dim as usize;
+        if ffn.input_dim() != hidden_dim {
+            return Err(bad(format!("weights take {} inputs, manifest says {hidden_dim}", ffn.input_dim())));
+        }
+        let output = ShimOutput::parse(&manifest.output_shape.kind)?;
+        let expected = match output {
+            ShimOutput::Scalar => 1,
+            ShimOutput::Category(n) => n,
+            ShimOutput::HiddenDelta => hidden_dim,
+        };
+        if ffn.output_dim() != expected {
+            let kind = &manifest.output_shape.kind;
+            return Err(bad(format!("weights give {} outputs, `{kind}` needs {expected}", ffn.output_dim())));
+        }
+        if manifest.attachment.layer != "final" {
+            return Err(bad(format!("layer `{}` is not supported locally", manifest.attachment.layer)));
+        }
+        let pooling = match manifest.attachment.pooling.as_str() {
+            "mean" => Some(Pooling::Mean),
+            "last_token" => Some(Pooling::LastToken),
+            "none" => None,
+            other => return Err(bad(format!("pooling `{other}` is not supported locally"))),
+        };
+        if manifest.phase == ShimPhase::Gate && (pooling.is_none() || output == ShimOutput::HiddenDelta) {
+            return Err(bad("gates need a pooled input and a scalar or category output".into()));
+        }
+        if manifest.phase == ShimPhase::Steer && output != ShimOutput::HiddenDelta {
+            return Err(bad("steers must output a hidden_delta".into()));
+        }
+        Ok(Self { manifest, ffn, output, pooling })
+    }
+
+    pub fn manifest(&self) -> &ShimManifest {
+        &self.manifest
+    }
+
+    pub fn ffn(&self) -> &ShimFfn {
+        &self.ffn
+    }
+
+    /// A gate's decision on an input vector: the sigmoid of a scalar
+    /// output, or the winning class index of a category output.
+    pub fn decide(&self, input: &[f32]) -> f32 {
+        self.output.decision(&self.ffn.forward(input))
+    }
+}
+
+/// Runs a shim store's gate and steer shims around local generation.
+#[derive(Debug, Clone, Default)]
+pub struct LocalShimExecutor {
+    shims: HashMap<String, LocalShim>,
+}
+
+/// A generation and the shim outcomes that shaped it.
+#[derive(Debug, Clone)]
+pub struct LocalGeneration {
+    pub generation: Generation,
+    pub metadata: ShimMetadata,
+}
+
+impl LocalShimExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load every active shim of `store_name` from the kernel's shim store.
+    /// Injection shims and anything not attached at the `final` layer are
+    /// skipped with a warning, so they don't keep the rest from running.
+    pub fn load(store: &ShimStore, store_name: &str) -> Result<Self, ShimClientError> {
+        let records = store
+            .shims_in(store_name)
+            .ok_or_else(|| ShimClientError::NotFound(format!("shim_store `{store_name}`")))?;
+        let mut executor = Self::new();
+        for record in records.values() {
+            let manifest: ShimManifest = serde_json::from_slice(&record.manifest_json)
+                .map_err(|e| ShimClientError::InvalidManifest(format!("shim `{}`: {e}", record.shim_id)))?;
+            if manifest.phase == ShimPhase::Injection || manifest.attachment.layer != "final" {
+                tracing::warn!(
+                    shim_store = store_name,
+                    shim = %manifest.id,
+                    layer = %manifest.attachment.layer,
+                    "shim is not run locally; skipping"
+                );
+                continue;
+            }
+            let bytes = std::fs::read(&record.onnx_path).map_err(|e| {
+                ShimClientError::InvalidWeights(format!("{}: {e}", record.onnx_path.display()))
+            })?;
+            executor.insert(LocalShim::new(manifest, ShimFfn::from_onnx(&bytes)?)?);
+        }
+        Ok(executor)
+    }
+
+    pub fn insert(&mut self, shim: LocalShim) {
+        self.shims.insert(shim.manifest.id.clone(), shim);
+    }
+
+    pub fn get(&self, id: &str) -> Option<&LocalShim> {
+        self.shims.get(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.shims.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shims.is_empty()
+    }
+
+    fn shim(&self, id: &str, phase: ShimPhase, hidden_dim: usize) -> Result<&LocalShim, ShimClientError> {
+        let shim = self.shims.get(id).ok_or_else(|| ShimClientError::NotFound(id.to_string()))?;
+        if shim.manifest.phase != phase {
+            return Err(ShimClientError::InvalidManifest(format!(
+                "shim `{id}` is a {:?} shim, attached as {phase:?}",
+                shim.manifest.phase
+            )));
+        }
+        if shim.ffn.input_dim() != hidden_dim {
+            return Err(ShimClientError::InvalidManifest(format!(
+                "shim `{id}` takes {} inputs, the model's hidden size is {hidden_dim}",
+                shim.ffn.input_dim()
+            )));
+        }
+        Ok(shim)
+    }
+
+    /// Run `gates` on the final-normed prompt hidden states
+    /// `[prompt_len, hidden_dim]`, keyed by gate id.
+    pub fn gate_decisions(
+        &self,
+        gates: &[String],
+        hidden: &[f32],
+        hidden_dim: usize,
+    ) -> Result<HashMap<String, f32>, ShimClientError> {
+        gates
+            .iter()
+            .map(|id| {
+                let shim = self.shim(id, ShimPhase::Gate, hidden_dim)?;
+                let pooling = shim.pooling.unwrap_or_default();
+                Ok((id.clone(), shim.decide(&pooling.sentence_vector(hidden, hidden_dim))))
+            })
+            .collect()
+    }
+
+    /// Gate decisions plus rule evaluation: the metadata cortex would
+    /// return for this prompt, without latencies.
+    pub fn resolve(
+        &self,
+        attachment: &ShimAttachment,
+        hidden: &[f32],
+        hidden_dim: usize,
+    ) -> Result<ShimMetadata, ShimClientError> {
+        let gate_decisions = self.gate_decisions(&attachment.gate_shims, hidden, hidden_dim)?;
+        let action = attachment.resolve_rules(&gate_decisions);
+        Ok(ShimMetadata {
+            silent: action.is_some_and(|a| a.silent),
+            active_steers: attachment.active_steers(action),
+            signals: action.and_then(|a| a.signal.clone()).into_iter().collect(),
+            gate_decisions,
+            prefill_ms: None,
+            generation_ms: None,
+        })
+    }
+
+    /// Generate from `prompt` with `attachment`'s shims applied. A silent
+    /// rule outcome ends generation before any token is sampled.
+    pub fn generate(
+        &self,
+        model: &TransformerModel,
+        prompt: &[u32],
+        attachment: &ShimAttachment,
+        options: &GenerateOptions,
+        tokenizer: Option<&Tokenizer>,
+    ) -> Result<LocalGeneration, ShimClientError> {
+        let hidden_dim = model.embed_dim();
+        // Fail before prefill on anything the rules could activate.
+        let activated = attachment.shim_rules.iter().flat_map(|rule| match rule {
+            ShimRule::If { action, .. } | ShimRule::Else { action } => action.activate.iter(),
+        });
+        for id in attachment.steer_shims.iter().chain(activated) {
+            self.shim(id, ShimPhase::Steer, hidden_dim)?;
+        }
+        for id in &attachment.gate_shims {
+            self.shim(id, ShimPhase::Gate, hidden_dim)?;
+        }
+        if !attachment.inject_shims.is_empty() {
+            tracing::warn!(shims = ?attachment.inject_shims, "injection shims are not run locally; skipping");
+        }
+
+        let started = Instant::now();
+        let mut hook = ShimHook {
+            executor: self,
+            attachment,
+            hidden_dim,
+            started,
+            steers: Vec::new(),
+            metadata: None,
+            error: None,
+        };
+        let mut cache = model.create_kv_cache(prompt.len() + options.max_tokens);
+        let generation =
+            model.generate_hooked(prompt, &mut cache, options, tokenizer, &mut hook, |_| ControlFlow::Continue(()));
+        if let Some(e) = hook.error {
+            return Err(e);
+        }
+        let mut metadata = hook.metadata.unwrap_or_default();
+        let total_ms = started.elapsed().as_millis() as u64;
+        metadata.generation_ms = Some(total_ms.saturating_sub(metadata.prefill_ms.unwrap_or(0)));
+        Ok(LocalGeneration { generation, metadata })
+    }
+}
+
+/// Gates at prefill, steers at every step.
+struct ShimHook<'a> {
+    executor: &'a LocalShimExecutor,
+    attachment: &'a ShimAttachment,
+    hidden_dim: usize,
+    started: Instant,
+    steers: Vec<&'a LocalShim>,
+    metadata: Option<ShimMetadata>,
+    error: Option<ShimClientError>,
+}
+
+impl HiddenHook for ShimHook<'_> {
+    fn prefill(&mut self, hidden: &[f32]) -> ControlFlow<()> {
+        let mut metadata = match self.executor.resolve(self.attachment, hidden, self.hidden_dim) {
+            Ok(m) => m,
+            Err(e) => {
+                self.error = Some(e);
+                return ControlFlow::Break(());
+            }
+        };
+        metadata.prefill_ms = Some(self.started.elapsed().as_millis() as u64);
+        // Steers were validated before prefill.
+        self.steers = metadata.active_steers.iter().filter_map(|id| self.executor.shims.get(id)).collect();
+        let silent = metadata.silent;
+        self.metadata = Some(metadata);
+        if silent {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    }
+
+    fn step(&mut self, hidden: &mut [f32]) {
+        if self.steers.is_empty() {
+            return;
+        }
+        let input = hidden.to_vec();
+        for steer in &self.steers {
+            for (h, d) in hidden.iter_mut().zip(steer.ffn.forward(&input)) {
+                *h += d;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agentos_bitnet::layers::attention::MultiHeadAttention;
+    use agentos_bitnet::layers::linear::DenseLinear;
+    use agentos_bitnet::layers::model::OutputProjection;
+    use agentos_bitnet::layers::rmsnorm::RmsNorm;
+    use agentos_bitnet::layers::sampler::SamplerConfig;
+    use agentos_bitnet::layers::swiglu::SwiGLU;
+    use agentos_bitnet::layers::transformer::TransformerBlock;
+    use agentos_bitnet::tensor::FloatTensor;
+    use agentos_bitnet::{GgmlType, QuantTensor};
+    use agentos_llm::types::{ShimAction, ShimCondition};
+
+    use crate::ffn::{sigmoid, DenseLayer};
+    use crate::manifest::{Attachment, InputShape, OutputShape};
+
+    const DIM: usize = 8;
+
+    fn identity(rows: usize, cols: usize) -> DenseLinear {
+        let mut w = vec![0.0f32; rows * cols];
+        for i in 0..rows.min(cols) {
+            w[i * cols + i] = 1.0;
+        }
+        let bytes: Vec<u8> = w.iter().flat_map(|v| v.to_le_bytes()).collect();
+        DenseLinear::new(QuantTensor::new(GgmlType::F32, bytes, rows, cols))
+    }
+
+    /// Vocab of 8, hidden size 8, one block, identity output head.
+    fn tiny_model() -> TransformerModel {
+        let embedding: Vec<f32> = (0..DIM * DIM).map(|i| ((i * 7 % 11) as f32 - 5.0) * 0.1).collect();
+        let attention = MultiHeadAttention::new(
+            identity(DIM, DIM),
+            identity(DIM, DIM),
+            identity(DIM, DIM),
+            identity(DIM, DIM),
+            2,
+            2,
+            DIM / 2,
+            10000.0,
+        );
+        let ffn = SwiGLU::new(identity(16, DIM), identity(16, DIM), identity(DIM, 16));
+        let norm = || RmsNorm::new(vec![1.0; DIM], 1e-5);
+        let block = TransformerBlock::new(norm(), attention, norm(), ffn);
+        TransformerModel::new(
+            FloatTensor::new(embedding, vec![DIM, DIM]),
+            vec![block],
+            norm(),
+            OutputProjection::Dense(identity(DIM, DIM)),
+        )
+    }
+
+    fn manifest(id: &str, phase: ShimPhase, pooling: &str, kind: &str) -> ShimManifest {
+        ShimManifest {
+            id: id.into(),
+            version: "1".into(),
+            phase,
+            attachment: Attachment { layer: "final".into(), pooling: pooling.into() },
+            input_shape: InputShape { hidden_dim: DIM as u32 },
+            output_shape: OutputShape { kind: kind.into() },
+            description: None,
+        }
+    }
+
+    /// A gate whose logit is a constant `bias`.
+    fn constant_gate(id: &str, bias: f32) -> LocalShim {
+        let ffn = ShimFfn::new(vec![DenseLayer::new(vec![0.0; DIM], vec![bias], DIM, 1).unwrap()]).unwrap();
+        LocalShim::new(manifest(id, ShimPhase::Gate, "mean", "scalar"), ffn).unwrap()
+    }
+
+    /// A steer pushing every hidden state hard towards token `token`.
+    fn steer_to(id: &str, token: usize) -> LocalShim {
+        let mut bias = vec![0.0; DIM];
+        bias[token] = 100.0;
+        let ffn = ShimFfn::new(vec![DenseLayer::new(vec![0.0; DIM * DIM], bias, DIM, DIM).unwrap()]).unwrap();
+        LocalShim::new(manifest(id, ShimPhase::Steer, "none", "hidden_delta"), ffn).unwrap()
+    }
+
+    fn attachment(else_action: ShimAction) -> ShimAttachment {
+        ShimAttachment {
+            gate_shims: vec!["should_respond".into()],
+            steer_shims: vec!["to_three".into()],
+            inject_shims: Vec::new(),
+            shim_rules: vec![
+                ShimRule::If {
+                    condition: ShimCondition { gate: "should_respond".into(), gt: 0.5 },
+                    action: ShimAction { signal: Some("respond".into()), ..Default::default() },
+                },
+                ShimRule::Else { action: else_action },
+            ],
+        }
+    }
+
+    #[test]
+    fn gates_rules_and_steers() {
+        let model = tiny_model();
+        let options = GenerateOptions::new(3, SamplerConfig::greedy());
+        let mut executor = LocalShimExecutor::new();
+        executor.insert(constant_gate("should_respond", 4.0));
+        executor.insert(steer_to("to_three", 3));
+        executor.insert(steer_to("to_five", 5));
+
+        let out = executor
+            .generate(&model, &[1, 2, 4], &attachment(ShimAction::default()), &options, None)
+            .unwrap();
+        assert!((out.metadata.gate_decisions["should_respond"] - sigmoid(4.0)).abs() < 1e-6);
+        assert_eq!(out.metadata.signals, vec!["respond".to_string()]);
+        assert_eq!(out.metadata.active_steers, vec!["to_three".to_string()]);
+        assert_eq!(out.generation.tokens, vec![3, 3, 3]);
+        assert!(out.metadata.prefill_ms.is_some() && out.metadata.generation_ms.is_some());
+
+        // A closed gate falls to `else`, which swaps the steer set.
+        executor.insert(constant_gate("should_respond", -4.0));
+        let swap = ShimAction { activate: vec!["to_five".into()], ..Default::default() };
+        let out = executor.generate(&model, &[1, 2, 4], &attachment(swap), &options, None).unwrap();
+        assert_eq!(out.metadata.active_steers, vec!["to_five".to_string()]);
+        assert_eq!(out.generation.tokens, vec![5, 5, 5]);
+
+        // ...or chooses silence.
+        let silent = ShimAction { silent: true, ..Default::default() };
+        let out = executor.generate(&model, &[1, 2, 4], &attachment(silent), &options, None).unwrap();
+        assert!(out.metadata.silent);
+        assert!(out.generation.tokens.is_empty());
+    }
+
+    #[test]
+    fn rejects_missing_and_mismatched_shims() {
+        let model = tiny_model();
+        let options = GenerateOptions::new(2, SamplerConfig::greedy());
+        let mut executor = LocalShimExecutor::new();
+        executor.insert(constant_gate("should_respond", 1.0));
+        let err = executor.generate(&model, &[1, 2], &attachment(ShimAction::default()), &options, None);
+        assert!(matches!(err, Err(ShimClientError::NotFound(id)) if id == "to_three"));
+
+        // A gate attached as a steer.
+        executor.insert(constant_gate("to_three", 1.0));
+        let err = executor.generate(&model, &[1, 2], &attachment(ShimAction::default()), &options, None);
+        assert!(matches!(err, Err(ShimClientError::InvalidManifest(_))));
+
+        let ffn = ShimFfn::new(vec![DenseLayer::new(vec![0.0; 4], vec![0.0], 4, 1).unwrap()]).unwrap();
+        assert!(LocalShim::new(manifest("g", ShimPhase::Gate, "mean", "scalar"), ffn).is_err());
+    }
+
+    #[test]
+    fn loads_shims_from_the_kernel_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = ShimStore::open(dir.path().to_path_buf()).unwrap();
+        store.create_store("bob", vec!["tiny".into()]).unwrap();
+        for shim in [constant_gate("should_respond", 2.0), steer_to("to_three", 3)] {
+            let manifest = serde_json::to_vec(shim.manifest()).unwrap();
+            store.add_shim("bob", &shim.manifest().id, manifest, shim.ffn().to_onnx()).unwrap();
+        }
+        // An injection shim at an entrance layer can't run here, but it
+        // mustn't stop the gate and steer from loading.
+        let inject = ShimManifest {
+            attachment: Attachment { layer: "entrance:0".into(), pooling: "none".into() },
+            ..manifest("persona", ShimPhase::Injection, "none", "hidden_delta")
+        };
+        let ffn = ShimFfn::new(vec![DenseLayer::new(vec![0.0; DIM * DIM], vec![0.0; DIM], DIM, DIM).unwrap()]).unwrap();
+        store
+            .add_shim("bob", "persona", serde_json::to_vec(&inject).unwrap(), ffn.to_onnx())
+            .unwrap();
+        let composition = serde_json::to_vec(&attachment(ShimAction::default())).unwrap();
+        store.update_composition("bob", composition).unwrap();
+
+        let executor = LocalShimExecutor::load(&store, "bob").unwrap();
+        assert_eq!(executor.len(), 2);
+        assert!(executor.get("persona").is_none());
+        let attachment = super::composition(&store, "bob").unwrap();
+        let options = GenerateOptions::new(2, SamplerConfig::greedy());
+        let out = executor.generate(&tiny_model(), &[1, 2, 4], &attachment, &options, None).unwrap();
+        assert_eq!(out.generation.tokens, vec![3, 3]);
+        assert!(matches!(LocalShimExecutor::load(&store, "nobody"), Err(ShimClientError::NotFound(_))));
+    }
+}
@@ -17,6 +17,9 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::error::ShimClientError;
+use crate::ffn::sigmoid;
+
 /// Three-phase shim activation per the cortex API contract.
 ///
 /// - **Injection** shims fire on every forward pass at a hidden-layer
@@ -63,6 +66,46 @@ pub struct OutputShape {
     pub kind: String,
 }
 
+/// What a shim's output means, from `output_shape.kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShimOutput {
+    /// One logit; the decision is its sigmoid.
+    Scalar,
+    /// N logits; the decision is the argmax class index.
+    Category(usize),
+    /// A residual added to the hidden state.
+    HiddenDelta,
+}
+
+impl ShimOutput {
+    /// Parse an [`OutputShape::kind`].
+    pub fn parse(kind: &str) -> Result<Self, ShimClientError> {
+        match kind {
+            "scalar" => Ok(Self::Scalar),
+            "hidden_delta" => Ok(Self::HiddenDelta),
+            _ => kind
+                .strip_prefix("category:")
+                .and_then(|n| n.parse().ok())
+                .filter(|&n| n >= 2)
+                .map(Self::Category)
+                .ok_or_else(|| ShimClientError::InvalidManifest(format!("unknown output kind `{kind}`"))),
+        }
+    }
+
+    /// A gate's decision from its raw logits: the sigmoid of a scalar
+    /// output, or the winning class index of a category output.
+    pub fn decision(&self, logits: &[f32]) -> f32 {
+        match self {
+            Self::Scalar => sigmoid(logits[0]),
+            _ => logits
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.total_cmp(b.1))
+                .map_or(0.0, |(i, _)| i as f32),
+        }
+    }
+}
+
 /// One shim's full manifest. Cortex needs every field to wire the
 /// shim correctly.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
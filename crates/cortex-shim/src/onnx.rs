@@ -0,0 +1,536 @@
+//! Minimal ONNX codec for shim FFNs.
+//!
+//! `train_shim.py` exports shims as opset-11 graphs of `Gemm` and `Relu`
+//! nodes over float initializers (some exporter versions emit `MatMul` +
+//! `Add` instead of `Gemm`). This module decodes exactly that subset of
+//! the ONNX protobuf — enough to run shims in-process without an ONNX
+//! runtime — and encodes [`ShimFfn`]s back into the same shape, so
+//! weights trained in Rust load in cortex unchanged.
+//!
+//! Anything else in a graph (other ops, external data, non-f32 tensors)
+//! is rejected with [`ShimClientError::InvalidWeights`].
+
+use std::collections::HashMap;
+
+use crate::error::ShimClientError;
+use crate::ffn::{DenseLayer, ShimFfn};
+
+/// ONNX `TensorProto.DataType.FLOAT`.
+const FLOAT: u64 = 1;
+/// `AttributeProto.AttributeType` values we emit.
+const ATTR_FLOAT: u64 = 1;
+const ATTR_INT: u64 = 2;
+/// IR version and opset the encoder declares (matches `train_shim.py`).
+const IR_VERSION: u64 = 6;
+const OPSET: u64 = 11;
+
+/// Graph input and output names, as `train_shim.py` exports them.
+pub const INPUT_NAME: &str = "hidden_state";
+pub const OUTPUT_NAME: &str = "logits";
+
+fn invalid(msg: impl Into<String>) -> ShimClientError {
+    ShimClientError::InvalidWeights(msg.into())
+}
+
+// ── Protobuf wire format ──
+
+enum Value<'a> {
+    Varint(u64),
+    Fixed64,
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+/// Iterator over the `(field number, value)` pairs of one message.
+struct Fields<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Fields<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn varint(&mut self) -> Result<u64, ShimClientError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self.buf.get(self.pos).ok_or_else(|| invalid("truncated varint"))?;
+            self.pos += 1;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("varint too long"))
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], ShimClientError> {
+        let end = self.pos.checked_add(n).filter(|&e| e <= self.buf.len());
+        let end = end.ok_or_else(|| invalid("truncated field"))?;
+        let bytes = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn field(&mut self) -> Result<(u32, Value<'a>), ShimClientError> {
+        let key = self.varint()?;
+        let number = (key >> 3) as u32;
+        let value = match key & 7 {
+            0 => Value::Varint(self.varint()?),
+            1 => {
+                self.take(8)?;
+                Value::Fixed64
+            }
+            2 => {
+                let len = self.varint()? as usize;
+                Value::Bytes(self.take(len)?)
+            }
+            5 => {
+                let b = self.take(4)?;
+                Value::Fixed32(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            }
+            other => return Err(invalid(format!("unsupported wire type {other}"))),
+        };
+        Ok((number, value))
+    }
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Result<(u32, Value<'a>), ShimClientError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        (self.pos < self.buf.len()).then(|| self.field())
+    }
+}
+
+fn string(value: Value<'_>) -> Result<String, ShimClientError> {
+    match value {
+        Value::Bytes(b) => String::from_utf8(b.to_vec()).map_err(|_| invalid("non-UTF-8 string")),
+        _ => Err(invalid("expected a string field")),
+    }
+}
+
+// ── Decoding ──
+
+struct Node {
+    op_type: String,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    floats: HashMap<String, f32>,
+    ints: HashMap<String, i64>,
+}
+
+struct Tensor {
+    dims: Vec<usize>,
+    data: Vec<f32>,
+}
+
+fn parse_node(buf: &[u8]) -> Result<Node, ShimClientError> {
+    let mut node = Node {
+        op_type: String::new(),
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        floats: HashMap::new(),
+        ints: HashMap::new(),
+    };
+    for field in Fields::new(buf) {
+        match field? {
+            (1, v) => node.inputs.push(string(v)?),
+            (2, v) => node.outputs.push(string(v)?),
+            (4, v) => node.op_type = string(v)?,
+            (5, Value::Bytes(attr)) => {
+                let (mut name, mut f, mut i) = (String::new(), None, None);
+                for field in Fields::new(attr) {
+                    match field? {
+                        (1, v) => name = string(v)?,
+                        (2, Value::Fixed32(bits)) => f = Some(f32::from_bits(bits)),
+                        (3, Value::Varint(v)) => i = Some(v as i64),
+                        _ => {}
+                    }
+                }
+                if let Some(f) = f {
+                    node.floats.insert(name.clone(), f);
+                }
+                if let Some(i) = i {
+                    node.ints.insert(name, i);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(node)
+}
+
+fn parse_tensor(buf: &[u8]) -> Result<(String, Tensor), ShimClientError> {
+    let mut name = String::new();
+    let mut dims = Vec::new();
+    let mut data_type = 0;
+    let mut data = Vec::new();
+    for field in Fields::new(buf) {
+        match field? {
+            (1, Value::Varint(d)) => dims.push(d as usize),
+            (1, Value::Bytes(packed)) => {
+                let mut fields = Fields::new(packed);
+                while fields.pos < packed.len() {
+                    dims.push(fields.varint()? as usize);
+                }
+            }
+            (2, Value::Varint(t)) => data_type = t,
+            (4, Value::Fixed32(bits)) => data.push(f32::from_bits(bits)),
+            (4, Value::Bytes(packed)) | (9, Value::Bytes(packed)) => {
+                if packed.len() % 4 != 0 {
+                    return Err(invalid("float data is not a multiple of 4 bytes"));
+                }
+                data.extend(packed.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])));
+            }
+            (8, v) => name = string(v)?,
+            (14, Value::Varint(1)) => return Err(invalid(format!("initializer `{name}` uses external data"))),
+            _ => {}
+        }
+    }
+    if data_type != FLOAT {
+        return Err(invalid(format!("initializer `{name}` has data type {data_type}, expected float")));
+    }
+    if dims.iter().product::<usize>() != data.len() {
+        return Err(invalid(format!("initializer `{name}` has {} values for dims {dims:?}", data.len())));
+    }
+    Ok((name, Tensor { dims, data }))
+}
+
+/// Decode an ONNX model holding a Gemm/MatMul/Add/Relu FFN.
+pub fn decode(bytes: &[u8]) -> Result<ShimFfn, ShimClientError> {
+    let mut graph = None;
+    for field in Fields::new(bytes) {
+        if let (7, Value::Bytes(g)) = field? {
+            graph = Some(g);
+        }
+    }
+    let graph = graph.ok_or_else(|| invalid("model has no graph"))?;
+
+    let mut nodes = Vec::new();
+    let mut initializers = HashMap::new();
+    for field in Fields::new(graph) {
+        match field? {
+            (1, Value::Bytes(n)) => nodes.push(parse_node(n)?),
+            (5, Value::Bytes(t)) => {
+                let (name, tensor) = parse_tensor(t)?;
+                initializers.insert(name, tensor);
+            }
+            _ => {}
+        }
+    }
+    build(&nodes, &initializers)
+}
+
+/// A layer under construction: weight `[out, in]`, bias, and whether a
+/// ReLU follows it.
+struct Pending {
+    weight: Vec<f32>,
+    bias: Vec<f32>,
+    in_dim: usize,
+    out_dim: usize,
+    relu: bool,
+}
+
+fn build(nodes: &[Node], initializers: &HashMap<String, Tensor>) -> Result<ShimFfn, ShimClientError> {
+    let init = |name: &str| initializers.get(name).ok_or_else(|| invalid(format!("`{name}` is not an initializer")));
+    let mut layers: Vec<Pending> = Vec::new();
+    // The value flowing through the chain; every node must consume it.
+    let mut current: Option<&str> = None;
+
+    for node in nodes {
+        let is_data = |i: &&str| !i.is_empty() && !initializers.contains_key(*i);
+        let data: Vec<&str> = node.inputs.iter().map(String::as_str).filter(is_data).collect();
+        let [input] = data[..] else {
+            return Err(invalid(format!("`{}` node must take exactly one non-constant input", node.op_type)));
+        };
+        if current.is_some_and(|c| c != input) {
+            return Err(invalid(format!("`{}` node does not continue the layer chain", node.op_type)));
+        }
+
+        match node.op_type.as_str() {
+            "Gemm" => {
+                if node.ints.get("transA").copied().unwrap_or(0) != 0 || node.inputs[0] != input {
+                    return Err(invalid("Gemm with a transposed or constant input is not supported"));
+                }
+                let w = init(node.inputs.get(1).ok_or_else(|| invalid("Gemm without weights"))?)?;
+                let trans_b = node.ints.get("transB").copied().unwrap_or(0) != 0;
+                let alpha = node.floats.get("alpha").copied().unwrap_or(1.0);
+                let beta = node.floats.get("beta").copied().unwrap_or(1.0);
+                let mut layer = linear(w, trans_b)?;
+                layer.weight.iter_mut().for_each(|v| *v *= alpha);
+                if let Some(b) = node.inputs.get(2).filter(|b| !b.is_empty()) {
+                    add_bias(&mut layer, init(b)?, beta)?;
+                }
+                layers.push(layer);
+            }
+            "MatMul" => {
+                let w = node.inputs.iter().find(|i| initializers.contains_key(*i));
+                let w = w.ok_or_else(|| invalid("MatMul without weights"))?;
+                if node.inputs[0] != input {
+                    return Err(invalid("MatMul must multiply the input by the weights"));
+                }
+                layers.push(linear(init(w)?, false)?);
+            }
+            "Add" => {
+                let b = node.inputs.iter().find(|i| initializers.contains_key(*i));
+                let b = b.ok_or_else(|| invalid("Add without a constant bias"))?;
+                let layer = layers.last_mut().filter(|l| !l.relu);
+                let layer = layer.ok_or_else(|| invalid("Add must follow a MatMul or Gemm"))?;
+                add_bias(layer, init(b)?, 1.0)?;
+            }
+            "Relu" => {
+                let layer = layers.last_mut().filter(|l| !l.relu);
+                layer.ok_or_else(|| invalid("Relu must follow a linear layer"))?.relu = true;
+            }
+            "Identity" => {}
+            other => return Err(invalid(format!("unsupported op `{other}`"))),
+        }
+        current = Some(node.outputs.first().ok_or_else(|| invalid("node without outputs"))?);
+    }
+
+    let n = layers.len();
+    if n == 0 {
+        return Err(invalid("graph has no linear layers"));
+    }
+    if layers.iter().take(n - 1).any(|l| !l.relu) || layers[n - 1].relu {
+        return Err(invalid("expected ReLU between linear layers and raw logits at the output"));
+    }
+    let layers = layers
+        .into_iter()
+        .map(|l| DenseLayer::new(l.weight, l.bias, l.in_dim, l.out_dim))
+        .collect::<Result<Vec<_>, _>>()?;
+    ShimFfn::new(layers)
+}
+
+/// A bias-free layer from a 2-D weight: `[out, in]` when `transposed`
+/// (Gemm with `transB=1`, as `nn.Linear` stores it), else `[in, out]`.
+fn linear(w: &Tensor, transposed: bool) -> Result<Pending, ShimClientError> {
+    let [a, b] = w.dims[..] else {
+        return Err(invalid(format!("weights must be 2-D, got dims {:?}", w.dims)));
+    };
+    let (out_dim, in_dim) = if transposed { (a, b) } else { (b, a) };
+    let weight = if transposed {
+        w.data.clone()
+    } else {
+        let mut t = vec![0.0; w.data.len()];
+        for i in 0..in_dim {
+            for o in 0..out_dim {
+                t[o * in_dim + i] = w.data[i * out_dim + o];
+            }
+        }
+        t
+    };
+    Ok(Pending { weight, bias: vec![0.0; out_dim], in_dim, out_dim, relu: false })
+}
+
+fn add_bias(layer: &mut Pending, b: &Tensor, scale: f32) -> Result<(), ShimClientError> {
+    if b.data.len() != layer.out_dim {
+        return Err(invalid(format!("bias has {} values for {} outputs", b.data.len(), layer.out_dim)));
+    }
+    for (acc, v) in layer.bias.iter_mut().zip(&b.data) {
+        *acc += scale * v;
+    }
+    Ok(())
+}
+
+// ── Encoding ──
+
+/// Protobuf message writer.
+#[derive(Default)]
+struct Message(Vec<u8>);
+
+impl Message {
+    fn varint(&mut self, mut v: u64) {
+        while v >= 0x80 {
+            self.0.push(v as u8 | 0x80);
+            v >>= 7;
+        }
+        self.0.push(v as u8);
+    }
+
+    fn uint(&mut self, field: u32, v: u64) -> &mut Self {
+        self.varint(u64::from(field) << 3);
+        self.varint(v);
+        self
+    }
+
+    fn float(&mut self, field: u32, v: f32) -> &mut Self {
+        self.varint(u64::from(field) << 3 | 5);
+        self.0.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    fn bytes(&mut self, field: u32, b: &[u8]) -> &mut Self {
+        self.varint(u64::from(field) << 3 | 2);
+        self.varint(b.len() as u64);
+        self.0.extend_from_slice(b);
+        self
+    }
+
+    fn string(&mut self, field: u32, s: &str) -> &mut Self {
+        self.bytes(field, s.as_bytes())
+    }
+
+    fn message(&mut self, field: u32, m: &Message) -> &mut Self {
+        self.bytes(field, &m.0)
+    }
+}
+
+fn tensor(name: &str, dims: &[usize], data: &[f32]) -> Message {
+    let mut t = Message::default();
+    for &d in dims {
+        t.uint(1, d as u64);
+    }
+    let raw: Vec<u8> = data.iter().flat_map(|v| v.to_le_bytes()).collect();
+    t.uint(2, FLOAT).string(8, name).bytes(9, &raw);
+    t
+}
+
+/// `ValueInfoProto` for a `[batch, dim]` float tensor.
+fn value_info(name: &str, dim: usize) -> Message {
+    let mut batch = Message::default();
+    batch.string(2, "batch");
+    let mut width = Message::default();
+    width.uint(1, dim as u64);
+    let mut shape = Message::default();
+    shape.message(1, &batch).message(1, &width);
+    let mut tensor_type = Message::default();
+    tensor_type.uint(1, FLOAT).message(2, &shape);
+    let mut ty = Message::default();
+    ty.message(1, &tensor_type);
+    let mut info = Message::default();
+    info.string(1, name).message(2, &ty);
+    info
+}
+
+fn attribute_float(name: &str, v: f32) -> Message {
+    let mut a = Message::default();
+    a.string(1, name).float(2, v).uint(20, ATTR_FLOAT);
+    a
+}
+
+fn attribute_int(name: &str, v: u64) -> Message {
+    let mut a = Message::default();
+    a.string(1, name).uint(3, v).uint(20, ATTR_INT);
+    a
+}
+
+/// Encode `ffn` as an opset-11 ONNX model with the node and initializer
+/// names PyTorch gives an `nn.Sequential` of `Linear`/`ReLU`.
+pub fn encode(ffn: &ShimFfn) -> Vec<u8> {
+    let mut graph = Message::default();
+    let n = ffn.layers().len();
+    let mut current = INPUT_NAME.to_string();
+
+    for i in 0..n {
+        let prefix = format!("net.{}", 2 * i);
+        let (weight, bias) = (format!("{prefix}.weight"), format!("{prefix}.bias"));
+        let gemm_out = if i + 1 == n { OUTPUT_NAME.to_string() } else { format!("/net.{}/Gemm_output_0", 2 * i) };
+
+        let mut gemm = Message::default();
+        gemm.string(1, &current)
+            .string(1, &weight)
+            .string(1, &bias)
+            .string(2, &gemm_out)
+            .string(3, &format!("/net.{}/Gemm", 2 * i))
+            .string(4, "Gemm")
+            .message(5, &attribute_float("alpha", 1.0))
+            .message(5, &attribute_float("beta", 1.0))
+            .message(5, &attribute_int("transB", 1));
+        graph.message(1, &gemm);
+        current = gemm_out;
+
+        if i + 1 < n {
+            let relu_out = format!("/net.{}/Relu_output_0", 2 * i + 1);
+            let mut relu = Message::default();
+            relu.string(1, &current)
+                .string(2, &relu_out)
+                .string(3, &format!("/net.{}/Relu", 2 * i + 1))
+                .string(4, "Relu");
+            graph.message(1, &relu);
+            current = relu_out;
+        }
+    }
+
+    graph.string(2, "shim");
+    for (i, layer) in ffn.layers().iter().enumerate() {
+        let prefix = format!("net.{}", 2 * i);
+        graph.message(5, &tensor(&format!("{prefix}.weight"), &[layer.out_dim(), layer.in_dim()], layer.weight()));
+        graph.message(5, &tensor(&format!("{prefix}.bias"), &[layer.out_dim()], layer.bias()));
+    }
+    graph
+        .message(11, &value_info(INPUT_NAME, ffn.input_dim()))
+        .message(12, &value_info(OUTPUT_NAME, ffn.output_dim()));
+
+    let mut opset = Message::default();
+    opset.string(1, "").uint(2, OPSET);
+    let mut model = Message::default();
+    model
+        .uint(1, IR_VERSION)
+        .string(2, "agentos")
+        .message(7, &graph)
+        .message(8, &opset);
+    model.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_ffn() -> ShimFfn {
+        ShimFfn::new(vec![
+            DenseLayer::new(vec![1.0, -2.0, 0.5, 3.0, 0.0, 1.0], vec![0.1, -0.2], 3, 2).unwrap(),
+            DenseLayer::new(vec![2.0, -1.0], vec![0.5], 2, 1).unwrap(),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        let ffn = sample_ffn();
+        let back = decode(&encode(&ffn)).unwrap();
+        assert_eq!(back, ffn);
+        assert_eq!(back.forward(&[1.0, 2.0, 3.0]), ffn.forward(&[1.0, 2.0, 3.0]));
+    }
+
+    #[test]
+    fn decodes_matmul_add_graphs() {
+        // x · W + b with W stored [in, out], as some exporters write it.
+        let mut graph = Message::default();
+        let mut matmul = Message::default();
+        matmul.string(1, "x").string(1, "w").string(2, "h").string(4, "MatMul");
+        let mut add = Message::default();
+        add.string(1, "h").string(1, "b").string(2, "y").string(4, "Add");
+        graph
+            .message(1, &matmul)
+            .message(1, &add)
+            .message(5, &tensor("w", &[2, 3], &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]))
+            .message(5, &tensor("b", &[3], &[0.5, 0.0, -0.5]));
+        let mut model = Message::default();
+        model.message(7, &graph);
+
+        let ffn = decode(&model.0).unwrap();
+        assert_eq!((ffn.input_dim(), ffn.output_dim()), (2, 3));
+        assert_eq!(ffn.forward(&[1.0, 1.0]), vec![5.5, 7.0, 8.5]);
+    }
+
+    #[test]
+    fn rejects_unsupported_graphs() {
+        let mut bytes = encode(&sample_ffn());
+        assert!(decode(&bytes[..bytes.len() / 2]).is_err());
+
+        let mut graph = Message::default();
+        let mut node = Message::default();
+        node.string(1, "x").string(2, "y").string(4, "Sigmoid");
+        graph.message(1, &node);
+        let mut model = Message::default();
+        model.message(7, &graph);
+        let err = decode(&model.0).unwrap_err();
+        assert!(err.to_string().contains("Sigmoid"), "{err}");
+
+        bytes.clear();
+        assert!(decode(&bytes).is_err());
+    }
+}
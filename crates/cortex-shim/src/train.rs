@@ -0,0 +1,680 @@
+//! Native shim trainer.
+//!
+//! Trains a [`ShimFfn`] from `(vector, label)` JSONL without Python or
+//! torch, following `scripts/train_shim.py`: a stratified seeded split
+//! (5% test, 10% val, the rest train), a class-weighted sampler, Adam,
+//! BCE-with-logits for scalar shims and class-weighted cross-entropy for
+//! categories. The weights with the best validation accuracy are kept,
+//! and training stops early once validation accuracy has not improved
+//! for [`TrainConfig::patience`] epochs.
+//!
+//! [`train_to_dir`] writes the same `model.onnx` and `metrics.json` the
+//! Python script does, so the shim-expert flow and the shim store cannot
+//! tell the two trainers apart.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::ShimClientError;
+use crate::ffn::{sigmoid, DenseLayer, ShimFfn};
+
+/// File the trained weights are written to.
+pub const MODEL_FILE: &str = "model.onnx";
+
+/// File the training metrics are written to.
+pub const METRICS_FILE: &str = "metrics.json";
+
+/// Hyperparameters for one training run. Defaults match `train_shim.py`.
+#[derive(Debug, Clone)]
+pub struct TrainConfig {
+    /// Expected vector length; taken from the data when `None`.
+    pub input_dim: Option<usize>,
+    /// Hidden layer widths.
+    pub hidden: Vec<usize>,
+    /// 1 for a scalar (binary) shim, N for an N-way category.
+    pub output_dim: usize,
+    /// Maximum number of epochs.
+    pub epochs: usize,
+    pub lr: f32,
+    pub batch_size: usize,
+    pub seed: u64,
+    /// Epochs without a better validation accuracy before stopping.
+    /// `None` always runs every epoch.
+    pub patience: Option<usize>,
+    pub val_split: f64,
+    pub test_split: f64,
+}
+
+impl Default for TrainConfig {
+    fn default() -> Self {
+        Self {
+            input_dim: None,
+            hidden: vec![1024, 256],
+            output_dim: 1,
+            epochs: 30,
+            lr: 1e-3,
+            batch_size: 64,
+            seed: 42,
+            patience: Some(10),
+            val_split: 0.10,
+            test_split: 0.05,
+        }
+    }
+}
+
+impl TrainConfig {
+    fn validate(&self) -> Result<(), ShimClientError> {
+        let invalid = |msg: &str| Err(ShimClientError::InvalidDataset(msg.to_string()));
+        if self.output_dim == 0 {
+            return invalid("output_dim must be at least 1");
+        }
+        if self.hidden.contains(&0) {
+            return invalid("hidden layer widths must be non-zero");
+        }
+        if self.epochs == 0 || self.batch_size == 0 {
+            return invalid("epochs and batch_size must be non-zero");
+        }
+        if !(self.lr > 0.0 && self.lr.is_finite()) {
+            return invalid("lr must be positive");
+        }
+        if !(0.0..1.0).contains(&(self.val_split + self.test_split)) {
+            return invalid("val_split + test_split must be below 1");
+        }
+        Ok(())
+    }
+}
+
+/// Labeled vectors, all of one length.
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    vectors: Vec<f32>,
+    labels: Vec<usize>,
+    dim: usize,
+}
+
+#[derive(Deserialize)]
+struct Row {
+    vector: Vec<f32>,
+    label: usize,
+}
+
+impl Dataset {
+    /// Build from parallel vectors and labels.
+    pub fn new(vectors: Vec<Vec<f32>>, labels: Vec<usize>) -> Result<Self, ShimClientError> {
+        if vectors.len() != labels.len() {
+            return Err(ShimClientError::InvalidDataset(format!(
+                "{} vectors but {} labels",
+                vectors.len(),
+                labels.len()
+            )));
+        }
+        let mut data = Self { vectors: Vec::new(), labels, dim: 0 };
+        for (i, v) in vectors.into_iter().enumerate() {
+            data.push_vector(v, || format!("row {}", i + 1))?;
+        }
+        Ok(data)
+    }
+
+    /// Parse `{"vector": [...], "label": <int>}` lines; blank lines are skipped.
+    pub fn parse_jsonl(text: &str) -> Result<Self, ShimClientError> {
+        let mut data = Self { vectors: Vec::new(), labels: Vec::new(), dim: 0 };
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let row: Row = serde_json::from_str(line)
+                .map_err(|e| ShimClientError::InvalidDataset(format!("line {}: {e}", i + 1)))?;
+            data.push_vector(row.vector, || format!("line {}", i + 1))?;
+            data.labels.push(row.label);
+        }
+        Ok(data)
+    }
+
+    /// Read and parse a JSONL file.
+    pub fn from_jsonl(path: &Path) -> Result<Self, ShimClientError> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse_jsonl(&text).map_err(|e| match e {
+            ShimClientError::InvalidDataset(msg) => {
+                ShimClientError::InvalidDataset(format!("{}: {msg}", path.display()))
+            }
+            other => other,
+        })
+    }
+
+    fn push_vector(&mut self, vector: Vec<f32>, at: impl Fn() -> String) -> Result<(), ShimClientError> {
+        if self.vectors.is_empty() {
+            if vector.is_empty() {
+                return Err(ShimClientError::InvalidDataset(format!("{}: empty vector", at())));
+            }
+            self.dim = vector.len();
+        } else if vector.len() != self.dim {
+            return Err(ShimClientError::InvalidDataset(format!(
+                "{}: vector length {} != first row's {}",
+                at(),
+                vector.len(),
+                self.dim
+            )));
+        }
+        self.vectors.extend(vector);
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Vector length.
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    pub fn labels(&self) -> &[usize] {
+        &self.labels
+    }
+
+    /// The vector of row `i`.
+    pub fn vector(&self, i: usize) -> &[f32] {
+        &self.vectors[i * self.dim..(i + 1) * self.dim]
+    }
+
+    /// Row count per label.
+    pub fn label_distribution(&self) -> BTreeMap<usize, usize> {
+        let mut counts = BTreeMap::new();
+        for &label in &self.labels {
+            *counts.entry(label).or_default() += 1;
+        }
+        counts
+    }
+}
+
+/// Precision and recall of one class.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClassMetrics {
+    pub class: usize,
+    pub precision: f64,
+    pub recall: f64,
+    pub support: usize,
+}
+
+/// Classification quality over one set of rows.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SplitMetrics {
+    pub accuracy: f64,
+    pub total: usize,
+    pub correct: usize,
+    pub per_class: Vec<ClassMetrics>,
+    /// `confusion_matrix[true][predicted]`.
+    pub confusion_matrix: Vec<Vec<usize>>,
+}
+
+impl SplitMetrics {
+    /// Score `(true, predicted)` pairs over `num_classes` classes.
+    pub fn from_predictions(pairs: impl IntoIterator<Item = (usize, usize)>, num_classes: usize) -> Self {
+        let mut confusion = vec![vec![0usize; num_classes]; num_classes];
+        for (truth, pred) in pairs {
+            confusion[truth][pred] += 1;
+        }
+        let total: usize = confusion.iter().flatten().sum();
+        let correct: usize = (0..num_classes).map(|c| confusion[c][c]).sum();
+        let ratio = |num: usize, den: usize| if den == 0 { 0.0 } else { num as f64 / den as f64 };
+        let per_class = (0..num_classes)
+            .map(|c| {
+                let tp = confusion[c][c];
+                let predicted: usize = confusion.iter().map(|row| row[c]).sum();
+                let support: usize = confusion[c].iter().sum();
+                ClassMetrics { class: c, precision: ratio(tp, predicted), recall: ratio(tp, support), support }
+            })
+            .collect();
+        Self { accuracy: ratio(correct, total), total, correct, per_class, confusion_matrix: confusion }
+    }
+}
+
+/// Row counts of the train/val/test split.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SplitSizes {
+    pub train: usize,
+    pub val: usize,
+    pub test: usize,
+}
+
+/// Contents of `metrics.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrainMetrics {
+    pub input_dim: usize,
+    pub output_dim: usize,
+    pub hidden_dims: Vec<usize>,
+    pub epochs_run: usize,
+    /// Epoch (1-based) whose weights were kept.
+    pub best_epoch: usize,
+    pub param_count: usize,
+    pub best_val_accuracy: f64,
+    pub val: SplitMetrics,
+    pub test: SplitMetrics,
+    pub label_distribution_full: BTreeMap<usize, usize>,
+    pub split: SplitSizes,
+}
+
+/// A trained shim and how well it did.
+#[derive(Debug, Clone)]
+pub struct Trained {
+    pub ffn: ShimFfn,
+    pub metrics: TrainMetrics,
+}
+
+/// The class a shim's raw logits predict: `logit >= 0` (sigmoid ≥ 0.5)
+/// for a scalar shim, the argmax for a category.
+pub fn predict(logits: &[f32]) -> usize {
+    if let [logit] = logits {
+        return usize::from(*logit >= 0.0);
+    }
+    logits
+        .iter()
+        .enumerate()
+        .fold((0, f32::NEG_INFINITY), |best, (i, &v)| if v > best.1 { (i, v) } else { best })
+        .0
+}
+
+/// Score `ffn` on every row of `data`.
+pub fn evaluate(ffn: &ShimFfn, data: &Dataset) -> SplitMetrics {
+    let rows: Vec<usize> = (0..data.len()).collect();
+    evaluate_rows(ffn, data, &rows)
+}
+
+fn evaluate_rows(ffn: &ShimFfn, data: &Dataset, rows: &[usize]) -> SplitMetrics {
+    let num_classes = ffn.output_dim().max(2);
+    SplitMetrics::from_predictions(
+        rows.iter().map(|&i| (data.labels[i], predict(&ffn.forward(data.vector(i))))),
+        num_classes,
+    )
+}
+
+/// Train a shim on `data`.
+pub fn train(data: &Dataset, config: &TrainConfig) -> Result<Trained, ShimClientError> {
+    config.validate()?;
+    if data.is_empty() {
+        return Err(ShimClientError::InvalidDataset("no rows".into()));
+    }
+    if let Some(dim) = config.input_dim.filter(|&d| d != data.dim) {
+        return Err(ShimClientError::InvalidDataset(format!(
+            "input_dim {dim} disagrees with vector length {}",
+            data.dim
+        )));
+    }
+    let num_classes = config.output_dim.max(2);
+    if let Some(label) = data.labels.iter().find(|&&l| l >= num_classes) {
+        return Err(ShimClientError::InvalidDataset(format!(
+            "label {label} out of range for output_dim {}",
+            config.output_dim
+        )));
+    }
+
+    let mut rng = Rng::new(config.seed);
+    let (train_rows, val_rows, test_rows) = stratified_split(data, config, &mut rng);
+    if train_rows.is_empty() {
+        return Err(ShimClientError::InvalidDataset("too few rows to leave a training set".into()));
+    }
+
+    // Inverse-frequency class weights: they drive the sampler, and the
+    // cross-entropy loss for categories, so the majority class does not
+    // dominate the gradient.
+    let mut counts = vec![0usize; num_classes];
+    for &i in &train_rows {
+        counts[data.labels[i]] += 1;
+    }
+    let class_weights: Vec<f32> = counts
+        .iter()
+        .map(|&c| train_rows.len() as f32 / (c.max(1) * num_classes) as f32)
+        .collect();
+    let mut cumulative = Vec::with_capacity(train_rows.len());
+    let mut total = 0.0f64;
+    for &i in &train_rows {
+        total += class_weights[data.labels[i]] as f64;
+        cumulative.push(total);
+    }
+
+    let mut dims = vec![data.dim];
+    dims.extend(&config.hidden);
+    dims.push(config.output_dim);
+    let mut mlp = Mlp::new(&dims, &mut rng);
+    let mut adam = Adam::new(&mlp);
+    let loss_weights = (config.output_dim > 1).then_some(class_weights.as_slice());
+
+    let mut best: Option<(ShimFfn, f64, usize)> = None;
+    let mut epochs_run = 0;
+    for epoch in 1..=config.epochs {
+        epochs_run = epoch;
+        let sampled: Vec<usize> = (0..train_rows.len())
+            .map(|_| {
+                let u = rng.next_f64() * total;
+                train_rows[cumulative.partition_point(|&c| c <= u).min(train_rows.len() - 1)]
+            })
+            .collect();
+        let mut loss_sum = 0.0;
+        for batch in sampled.chunks(config.batch_size) {
+            loss_sum += mlp.backward(data, batch, loss_weights) * batch.len() as f64;
+            adam.step(&mut mlp, config.lr);
+        }
+
+        let ffn = mlp.to_ffn()?;
+        let val_acc = evaluate_rows(&ffn, data, &val_rows).accuracy;
+        tracing::debug!(epoch, train_loss = loss_sum / sampled.len() as f64, val_acc, "shim training epoch");
+        if best.as_ref().is_none_or(|(_, acc, _)| val_acc > *acc) {
+            best = Some((ffn, val_acc, epoch));
+        } else if config.patience.is_some_and(|p| epoch - best.as_ref().map_or(0, |b| b.2) >= p) {
+            break;
+        }
+    }
+
+    let (ffn, best_val_accuracy, best_epoch) = best.expect("at least one epoch ran");
+    let metrics = TrainMetrics {
+        input_dim: data.dim,
+        output_dim: config.output_dim,
+        hidden_dims: config.hidden.clone(),
+        epochs_run,
+        best_epoch,
+        param_count: ffn.param_count(),
+        best_val_accuracy,
+        val: evaluate_rows(&ffn, data, &val_rows),
+        test: evaluate_rows(&ffn, data, &test_rows),
+        label_distribution_full: data.label_distribution(),
+        split: SplitSizes { train: train_rows.len(), val: val_rows.len(), test: test_rows.len() },
+    };
+    Ok(Trained { ffn, metrics })
+}
+
+/// Train on the JSONL at `input` and write [`MODEL_FILE`] and
+/// [`METRICS_FILE`] into `output_dir`, creating it if needed.
+pub fn train_to_dir(input: &Path, output_dir: &Path, config: &TrainConfig) -> Result<TrainMetrics, ShimClientError> {
+    let data = Dataset::from_jsonl(input)?;
+    let trained = train(&data, config)?;
+    std::fs::create_dir_all(output_dir)?;
+    std::fs::write(output_dir.join(MODEL_FILE), trained.ffn.to_onnx())?;
+    let json = serde_json::to_string_pretty(&trained.metrics)
+        .map_err(|e| ShimClientError::InvalidDataset(format!("serialize metrics: {e}")))?;
+    std::fs::write(output_dir.join(METRICS_FILE), json)?;
+    Ok(trained.metrics)
+}
+
+/// Per-class shuffled split into (train, val, test) row indices. Every
+/// class gives at least one row to test and val.
+fn stratified_split(data: &Dataset, config: &TrainConfig, rng: &mut Rng) -> (Vec<usize>, Vec<usize>, Vec<usize>) {
+    let mut by_class: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (i, &label) in data.labels.iter().enumerate() {
+        by_class.entry(label).or_default().push(i);
+    }
+    let (mut train, mut val, mut test) = (Vec::new(), Vec::new(), Vec::new());
+    for rows in by_class.values_mut() {
+        rng.shuffle(rows);
+        let n = rows.len();
+        let n_test = ((n as f64 * config.test_split).round() as usize).max(1).min(n);
+        let n_val = ((n as f64 * config.val_split).round() as usize).max(1).min(n - n_test);
+        test.extend(&rows[..n_test]);
+        val.extend(&rows[n_test..n_test + n_val]);
+        train.extend(&rows[n_test + n_val..]);
+    }
+    (train, val, test)
+}
+
+/// Trainable MLP: weights `[out, in]` per layer, ReLU between layers.
+struct Mlp {
+    weights: Vec<Vec<f32>>,
+    biases: Vec<Vec<f32>>,
+    dims: Vec<usize>,
+    grad_w: Vec<Vec<f32>>,
+    grad_b: Vec<Vec<f32>>,
+}
+
+impl Mlp {
+    /// `nn.Linear` initialisation: weights and biases uniform in ±1/√in.
+    fn new(dims: &[usize], rng: &mut Rng) -> Self {
+        let mut weights = Vec::new();
+        let mut biases = Vec::new();
+        for pair in dims.windows(2) {
+            let bound = 1.0 / (pair[0] as f32).sqrt();
+            let mut uniform = |n: usize| -> Vec<f32> {
+                (0..n).map(|_| (rng.next_f64() as f32 * 2.0 - 1.0) * bound).collect()
+            };
+            weights.push(uniform(pair[0] * pair[1]));
+            biases.push(uniform(pair[1]));
+        }
+        let grad_w = weights.iter().map(|w| vec![0.0; w.len()]).collect();
+        let grad_b = biases.iter().map(|b| vec![0.0; b.len()]).collect();
+        Self { weights, biases, dims: dims.to_vec(), grad_w, grad_b }
+    }
+
+    fn to_ffn(&self) -> Result<ShimFfn, ShimClientError> {
+        let layers = self
+            .dims
+            .windows(2)
+            .zip(self.weights.iter().zip(&self.biases))
+            .map(|(pair, (w, b))| DenseLayer::new(w.clone(), b.clone(), pair[0], pair[1]))
+            .collect::<Result<Vec<_>, _>>()?;
+        ShimFfn::new(layers)
+    }
+
+    /// Fill the gradients with the mean loss gradient over `batch` and
+    /// return the mean loss. Scalar shims use BCE-with-logits; categories
+    /// use cross-entropy, weighted by `class_weights` when given.
+    fn backward(&mut self, data: &Dataset, batch: &[usize], class_weights: Option<&[f32]>) -> f64 {
+        self.grad_w.iter_mut().chain(self.grad_b.iter_mut()).for_each(|g| g.fill(0.0));
+        let n_layers = self.weights.len();
+        let norm: f32 = match class_weights {
+            Some(w) => batch.iter().map(|&i| w[data.labels[i]]).sum(),
+            None => batch.len() as f32,
+        };
+
+        let mut loss = 0.0f64;
+        let mut acts: Vec<Vec<f32>> = Vec::with_capacity(n_layers + 1);
+        for &row in batch {
+            acts.clear();
+            acts.push(data.vector(row).to_vec());
+            for l in 0..n_layers {
+                let (w, b, in_dim) = (&self.weights[l], &self.biases[l], self.dims[l]);
+                let x = &acts[l];
+                let mut z: Vec<f32> = w
+                    .chunks_exact(in_dim)
+                    .zip(b)
+                    .map(|(r, b)| b + r.iter().zip(x).map(|(w, x)| w * x).sum::<f32>())
+                    .collect();
+                if l + 1 < n_layers {
+                    z.iter_mut().for_each(|v| *v = v.max(0.0));
+                }
+                acts.push(z);
+            }
+
+            let label = data.labels[row];
+            let logits = &acts[n_layers];
+            let mut grad: Vec<f32> = if let [logit] = logits[..] {
+                let y = label as f32;
+                loss += (logit.max(0.0) - logit * y + (-logit.abs()).exp().ln_1p()) as f64;
+                vec![(sigmoid(logit) - y) / norm]
+            } else {
+                let max = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+                let exps: Vec<f32> = logits.iter().map(|v| (v - max).exp()).collect();
+                let sum: f32 = exps.iter().sum();
+                let weight = class_weights.map_or(1.0, |w| w[label]);
+                loss += (weight * (sum.ln() + max - logits[label])) as f64;
+                exps.iter()
+                    .enumerate()
+                    .map(|(c, e)| weight * (e / sum - f32::from(c == label)) / norm)
+                    .collect()
+            };
+
+            for l in (0..n_layers).rev() {
+                let x = &acts[l];
+                let in_dim = self.dims[l];
+                for ((gw, gb), g) in self.grad_w[l].chunks_exact_mut(in_dim).zip(&mut self.grad_b[l]).zip(&grad) {
+                    *gb += g;
+                    if *g != 0.0 {
+                        gw.iter_mut().zip(x).for_each(|(gw, x)| *gw += g * x);
+                    }
+                }
+                if l > 0 {
+                    let mut prev = vec![0.0f32; in_dim];
+                    for (w, g) in self.weights[l].chunks_exact(in_dim).zip(&grad) {
+                        if *g != 0.0 {
+                            prev.iter_mut().zip(w).for_each(|(p, w)| *p += g * w);
+                        }
+                    }
+                    // ReLU: no gradient through units that were clamped.
+                    prev.iter_mut().zip(x).filter(|(_, a)| **a <= 0.0).for_each(|(p, _)| *p = 0.0);
+                    grad = prev;
+                }
+            }
+        }
+        loss / norm as f64
+    }
+}
+
+/// Adam with torch's defaults (β₁ 0.9, β₂ 0.999, ε 1e-8).
+struct Adam {
+    m: Vec<Vec<f32>>,
+    v: Vec<Vec<f32>>,
+    t: i32,
+}
+
+impl Adam {
+    const BETA1: f32 = 0.9;
+    const BETA2: f32 = 0.999;
+    const EPS: f32 = 1e-8;
+
+    fn new(mlp: &Mlp) -> Self {
+        let zeros: Vec<Vec<f32>> =
+            mlp.weights.iter().chain(&mlp.biases).map(|p| vec![0.0; p.len()]).collect();
+        Self { m: zeros.clone(), v: zeros, t: 0 }
+    }
+
+    fn step(&mut self, mlp: &mut Mlp, lr: f32) {
+        self.t += 1;
+        let c1 = 1.0 - Self::BETA1.powi(self.t);
+        let c2 = 1.0 - Self::BETA2.powi(self.t);
+        let params = mlp.weights.iter_mut().chain(mlp.biases.iter_mut());
+        let grads = mlp.grad_w.iter().chain(&mlp.grad_b);
+        for (((p, g), m), v) in params.zip(grads).zip(&mut self.m).zip(&mut self.v) {
+            for (((p, g), m), v) in p.iter_mut().zip(g).zip(m.iter_mut()).zip(v.iter_mut()) {
+                *m = Self::BETA1 * *m + (1.0 - Self::BETA1) * g;
+                *v = Self::BETA2 * *v + (1.0 - Self::BETA2) * g * g;
+                *p -= lr * (*m / c1) / ((*v / c2).sqrt() + Self::EPS);
+            }
+        }
+    }
+}
+
+/// SplitMix64 — small, seedable, and good enough for shuffles and init.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = ((self.next_f64() * (i + 1) as f64) as usize).min(i);
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One noisy cluster per class around a class-specific centre.
+    fn clusters(classes: usize, per_class: usize, dim: usize) -> Dataset {
+        let mut rng = Rng::new(7);
+        let mut vectors = Vec::new();
+        let mut labels = Vec::new();
+        for c in 0..classes {
+            for _ in 0..per_class {
+                let v = (0..dim)
+                    .map(|d| if d % classes == c { 1.0 } else { 0.0 } + (rng.next_f64() as f32 - 0.5) * 0.6)
+                    .collect();
+                vectors.push(v);
+                labels.push(c);
+            }
+        }
+        Dataset::new(vectors, labels).unwrap()
+    }
+
+    fn small_config(output_dim: usize) -> TrainConfig {
+        TrainConfig { hidden: vec![16], output_dim, epochs: 40, batch_size: 16, lr: 1e-2, ..Default::default() }
+    }
+
+    #[test]
+    fn learns_a_binary_gate() {
+        let data = clusters(2, 60, 8);
+        let trained = train(&data, &small_config(1)).unwrap();
+        let m = &trained.metrics;
+        assert_eq!(m.split, SplitSizes { train: 102, val: 12, test: 6 });
+        assert_eq!(m.param_count, 8 * 16 + 16 + 16 + 1);
+        assert!(m.best_val_accuracy >= 0.9, "{m:?}");
+        assert!(evaluate(&trained.ffn, &data).accuracy >= 0.9);
+        assert_eq!(m.test.confusion_matrix.len(), 2);
+        // Same seed, same weights.
+        assert_eq!(train(&data, &small_config(1)).unwrap().ffn, trained.ffn);
+    }
+
+    #[test]
+    fn learns_categories_and_stops_early() {
+        let data = clusters(3, 40, 9);
+        let config = TrainConfig { epochs: 200, patience: Some(3), ..small_config(3) };
+        let trained = train(&data, &config).unwrap();
+        let m = &trained.metrics;
+        assert!(m.epochs_run < 200 && m.epochs_run >= m.best_epoch + 3, "{m:?}");
+        assert!(m.val.accuracy >= 0.9, "{m:?}");
+        assert_eq!(m.label_distribution_full.values().sum::<usize>(), 120);
+        assert_eq!(m.val.per_class.iter().map(|c| c.support).sum::<usize>(), m.split.val);
+    }
+
+    #[test]
+    fn writes_onnx_and_metrics() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("embedded.jsonl");
+        let data = clusters(2, 30, 4);
+        let jsonl: String = (0..data.len())
+            .map(|i| format!("{}\n", serde_json::json!({"vector": data.vector(i), "label": data.labels()[i]})))
+            .collect();
+        std::fs::write(&input, jsonl).unwrap();
+
+        let out = dir.path().join("out");
+        let metrics = train_to_dir(&input, &out, &small_config(1)).unwrap();
+        let ffn = ShimFfn::from_onnx(&std::fs::read(out.join(MODEL_FILE)).unwrap()).unwrap();
+        assert_eq!((ffn.input_dim(), ffn.output_dim()), (4, 1));
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(out.join(METRICS_FILE)).unwrap()).unwrap();
+        assert_eq!(json["test"]["accuracy"].as_f64(), Some(metrics.test.accuracy));
+        assert_eq!(json["label_distribution_full"]["1"], 30);
+        assert_eq!(json["val"]["per_class"][1]["class"], 1);
+    }
+
+    #[test]
+    fn rejects_bad_data() {
+        assert!(Dataset::parse_jsonl("{\"vector\":[1.0],\"label\":0}\n{\"vector\":[1.0,2.0],\"label\":1}").is_err());
+        assert!(Dataset::parse_jsonl("{\"vector\":[1.0],\"label\":-1}").is_err());
+        let data = Dataset::parse_jsonl("\n{\"vector\":[1.0],\"label\":2}\n").unwrap();
+        assert_eq!(data.len(), 1);
+        assert!(train(&data, &TrainConfig::default()).is_err());
+        assert!(train(&data, &TrainConfig { input_dim: Some(4), output_dim: 3, ..Default::default() }).is_err());
+    }
+}
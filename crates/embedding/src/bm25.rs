@@ -0,0 +1,305 @@
+//! BM25 lexical scoring over documents with weighted fields.
+//!
+//! Field weighting follows BM25F: a term's frequency in each field is
+//! length-normalized against that field's average, weighted, and summed
+//! before saturation, so a term in a short `name` counts for more than
+//! the same term in a long description.
+//!
+//! Scores are reported in [0, 1]: the BM25 score divided by the most any
+//! document could score for the query's known terms. That keeps a
+//! threshold meaningful across queries, as with cosine similarity.
+
+use std::collections::{HashMap, HashSet};
+
+use super::tokenize::Tokenizer;
+use super::MatchResult;
+
+/// Term-frequency saturation.
+pub const DEFAULT_K1: f32 = 1.2;
+/// Strength of length normalization.
+pub const DEFAULT_B: f32 = 0.75;
+
+struct Field {
+    name: String,
+    len: usize,
+    tf: HashMap<String, u32>,
+}
+
+struct Doc {
+    name: String,
+    fields: Vec<Field>,
+}
+
+/// Inverted index of named documents for BM25 search.
+pub struct Bm25Index {
+    tokenizer: Tokenizer,
+    k1: f32,
+    b: f32,
+    threshold: f32,
+    /// Field name → weight; unlisted fields weigh 1.
+    field_weights: HashMap<String, f32>,
+    /// Documents by slot; `None` for a removed one.
+    docs: Vec<Option<Doc>>,
+    slots: HashMap<String, usize>,
+    free: Vec<usize>,
+    /// Term → slots of documents containing it.
+    postings: HashMap<String, HashSet<usize>>,
+    /// Field name → (total length, documents having it).
+    field_lengths: HashMap<String, (usize, usize)>,
+}
+
+impl Bm25Index {
+    /// Create an index with a minimum normalized score.
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            tokenizer: Tokenizer::default(),
+            k1: DEFAULT_K1,
+            b: DEFAULT_B,
+            threshold,
+            field_weights: HashMap::new(),
+            docs: Vec::new(),
+            slots: HashMap::new(),
+            free: Vec::new(),
+            postings: HashMap::new(),
+            field_lengths: HashMap::new(),
+        }
+    }
+
+    /// Use `tokenizer` for documents and queries. Set before adding.
+    pub fn with_tokenizer(mut self, tokenizer: Tokenizer) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// Override the BM25 parameters.
+    pub fn with_params(mut self, k1: f32, b: f32) -> Self {
+        self.k1 = k1;
+        self.b = b.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Weight matches in `field` by `weight`.
+    pub fn with_field_weight(mut self, field: &str, weight: f32) -> Self {
+        self.field_weights.insert(field.to_string(), weight);
+        self
+    }
+
+    /// Add a document as (field, text) pairs, replacing any of the same name.
+    pub fn register(&mut self, name: &str, fields: &[(&str, &str)]) {
+        self.remove(name);
+        let fields: Vec<Field> = fields
+            .iter()
+            .map(|(field, text)| {
+                let tokens = self.tokenizer.tokenize(text);
+                let mut tf = HashMap::new();
+                for token in &tokens {
+                    *tf.entry(token.clone()).or_insert(0) += 1;
+                }
+                Field {
+                    name: field.to_string(),
+                    len: tokens.len(),
+                    tf,
+                }
+            })
+            .collect();
+
+        let slot = self.free.pop().unwrap_or(self.docs.len());
+        for field in &fields {
+            let totals = self.field_lengths.entry(field.name.clone()).or_insert((0, 0));
+            totals.0 += field.len;
+            totals.1 += 1;
+            for term in field.tf.keys() {
+                self.postings.entry(term.clone()).or_default().insert(slot);
+            }
+        }
+        let doc = Doc {
+            name: name.to_string(),
+            fields,
+        };
+        if slot == self.docs.len() {
+            self.docs.push(Some(doc));
+        } else {
+            self.docs[slot] = Some(doc);
+        }
+        self.slots.insert(name.to_string(), slot);
+    }
+
+    /// Remove a document by name.
+    pub fn remove(&mut self, name: &str) {
+        let Some(slot) = self.slots.remove(name) else {
+            return;
+        };
+        let Some(doc) = self.docs[slot].take() else {
+            return;
+        };
+        for field in &doc.fields {
+            if let Some(totals) = self.field_lengths.get_mut(&field.name) {
+                totals.0 -= field.len;
+                totals.1 -= 1;
+            }
+            for term in field.tf.keys() {
+                if let Some(posting) = self.postings.get_mut(term) {
+                    posting.remove(&slot);
+                    if posting.is_empty() {
+                        self.postings.remove(term);
+                    }
+                }
+            }
+        }
+        self.free.push(slot);
+    }
+
+    /// Find the best match above threshold.
+    pub fn search(&self, query: &str) -> Option<MatchResult> {
+        self.ranked(query, 1, &|_| true).into_iter().next()
+    }
+
+    /// Find the best match above threshold among `allowed` names.
+    ///
+    /// If `allowed` is empty, no matches are returned (security: empty allow-list = no access).
+    pub fn search_filtered(&self, query: &str, allowed: &[String]) -> Option<MatchResult> {
+        self.search_top_k_filtered(query, allowed, 1).into_iter().next()
+    }
+
+    /// Top K matches above threshold, best first.
+    pub fn search_top_k(&self, query: &str, k: usize) -> Vec<MatchResult> {
+        self.ranked(query, k, &|_| true)
+    }
+
+    /// Top K matches above threshold among `allowed` names, best first.
+    /// An empty `allowed` matches nothing.
+    pub fn search_top_k_filtered(&self, query: &str, allowed: &[String], k: usize) -> Vec<MatchResult> {
+        if allowed.is_empty() {
+            return Vec::new();
+        }
+        self.ranked(query, k, &|name| allowed.iter().any(|a| a == name))
+    }
+
+    /// Top K matches above threshold whose names pass `keep`, best first.
+    pub fn search_top_k_matching(&self, query: &str, k: usize, keep: &dyn Fn(&str) -> bool) -> Vec<MatchResult> {
+        self.ranked(query, k, keep)
+    }
+
+    /// Number of documents in the index.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Whether the index is empty.
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    fn ranked(&self, query: &str, k: usize, keep: &dyn Fn(&str) -> bool) -> Vec<MatchResult> {
+        let terms: HashSet<String> = self.tokenizer.tokenize(query).into_iter().collect();
+        let n = self.len() as f32;
+        let mut scores: HashMap<usize, f32> = HashMap::new();
+        let mut best_possible = 0.0;
+        for term in &terms {
+            let Some(posting) = self.postings.get(term) else {
+                continue;
+            };
+            let df = posting.len() as f32;
+            let idf = (1.0 + (n - df + 0.5) / (df + 0.5)).ln();
+            best_possible += idf;
+            for &slot in posting {
+                let Some(doc) = &self.docs[slot] else {
+                    continue;
+                };
+                let tf = self.weighted_tf(doc, term);
+                *scores.entry(slot).or_insert(0.0) += idf * tf / (self.k1 + tf);
+            }
+        }
+        if best_possible == 0.0 {
+            return Vec::new();
+        }
+
+        let mut results: Vec<MatchResult> = scores
+            .into_iter()
+            .filter_map(|(slot, score)| {
+                let doc = self.docs[slot].as_ref()?;
+                Some(MatchResult {
+                    name: doc.name.clone(),
+                    score: score / best_possible,
+                })
+            })
+            .filter(|r| r.score >= self.threshold && keep(&r.name))
+            .collect();
+        results.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+        results.truncate(k);
+        results
+    }
+
+    /// Weighted, length-normalized frequency of `term` across `doc`'s fields.
+    fn weighted_tf(&self, doc: &Doc, term: &str) -> f32 {
+        doc.fields
+            .iter()
+            .filter_map(|field| {
+                let tf = *field.tf.get(term)? as f32;
+                let (total, count) = self.field_lengths.get(&field.name).copied().unwrap_or((0, 0));
+                let avg = if count == 0 { 1.0 } else { (total as f32 / count as f32).max(1.0) };
+                let norm = 1.0 - self.b + self.b * field.len as f32 / avg;
+                let weight = self.field_weights.get(&field.name).copied().unwrap_or(1.0);
+                Some(weight * tf / norm)
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tools() -> Bm25Index {
+        let mut index = Bm25Index::new(0.0)
+            .with_tokenizer(Tokenizer::new().with_stemming(true))
+            .with_field_weight("name", 2.0);
+        index.register("file-read", &[("name", "file-read"), ("description", "Read files")]);
+        index.register("file-write", &[("name", "file-write"), ("description", "Write files")]);
+        index.register("git-diff", &[("name", "git-diff"), ("description", "Show git diff")]);
+        index.register("cargo-test", &[("name", "cargo-test"), ("description", "Run cargo test")]);
+        index
+    }
+
+    #[test]
+    fn ranks_by_matching_terms() {
+        let index = tools();
+        let best = index.search("please read the file src/main.rs").unwrap();
+        assert_eq!(best.name, "file-read");
+        assert!(best.score > 0.0 && best.score <= 1.0);
+        assert_eq!(index.search("run the tests").unwrap().name, "cargo-test");
+        assert!(index.search("philosophy of mind").is_none());
+    }
+
+    #[test]
+    fn field_weights_and_filters() {
+        let mut index = Bm25Index::new(0.0).with_field_weight("name", 3.0);
+        index.register("a", &[("name", "deploy"), ("body", "ship the build")]);
+        index.register("b", &[("name", "ship"), ("body", "deploy the build")]);
+        assert_eq!(index.search("deploy").unwrap().name, "a");
+        assert_eq!(index.search("ship").unwrap().name, "b");
+
+        let allowed = vec!["b".to_string()];
+        assert_eq!(index.search_filtered("deploy", &allowed).unwrap().name, "b");
+        assert!(index.search_filtered("deploy", &[]).is_none());
+    }
+
+    #[test]
+    fn remove_and_replace() {
+        let mut index = tools();
+        index.remove("file-read");
+        assert_eq!(index.len(), 3);
+        assert_ne!(index.search("read files").unwrap().name, "file-read");
+        index.register("file-read", &[("name", "file-read"), ("description", "Read files")]);
+        index.register("file-read", &[("name", "file-read"), ("description", "Read files")]);
+        assert_eq!(index.len(), 4);
+        assert_eq!(index.search("read files").unwrap().name, "file-read");
+    }
+
+    #[test]
+    fn threshold_applies_to_normalized_score() {
+        let mut index = tools();
+        index.threshold = 0.99;
+        assert!(index.search("read files and then show the git diff").is_none());
+    }
+}
@@ -0,0 +1,363 @@
+//! Approximate nearest-neighbour search — a hierarchical navigable small
+//! world (HNSW) graph over cosine similarity.
+//!
+//! `EmbeddingIndex` scans every entry, which is fine for a few dozen tool
+//! descriptions. A code index holds tens of thousands of chunks, so it
+//! searches this graph instead: each query visits a few hundred vectors
+//! rather than all of them.
+//!
+//! Vectors are normalized on insert, so similarity is a dot product.
+//! Removal marks a node deleted; it keeps routing searches but is never
+//! returned. Callers that remove heavily should rebuild once
+//! [`Hnsw::deleted`] outgrows [`Hnsw::len`].
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashSet};
+
+use super::Embedding;
+
+/// Neighbours per node on upper layers.
+const DEFAULT_M: usize = 16;
+/// Candidate list size while inserting.
+const DEFAULT_EF_CONSTRUCTION: usize = 100;
+
+/// A node id and its similarity to the query, ordered by similarity.
+#[derive(Clone, Copy, PartialEq)]
+struct Scored(f32, usize);
+
+impl Eq for Scored {}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then(self.1.cmp(&other.1))
+    }
+}
+
+/// HNSW graph. Node ids are assigned in insertion order from zero.
+pub struct Hnsw {
+    /// Max neighbours per node on layers above 0; layer 0 allows twice this.
+    m: usize,
+    ef_construction: usize,
+    /// Normalized vectors, by node id.
+    vectors: Vec<Embedding>,
+    /// Neighbour lists, by node id then layer.
+    links: Vec<Vec<Vec<usize>>>,
+    deleted: Vec<bool>,
+    deleted_count: usize,
+    entry: Option<usize>,
+    /// xorshift state for level assignment, so builds are reproducible.
+    rng: u64,
+}
+
+impl Hnsw {
+    /// An empty graph with the default parameters.
+    pub fn new() -> Self {
+        Self::with_params(DEFAULT_M, DEFAULT_EF_CONSTRUCTION)
+    }
+
+    /// An empty graph keeping `m` neighbours per node (2·m on the bottom
+    /// layer) and considering `ef_construction` candidates per insert.
+    pub fn with_params(m: usize, ef_construction: usize) -> Self {
+        Self {
+            m: m.max(2),
+            ef_construction: ef_construction.max(1),
+            vectors: Vec::new(),
+            links: Vec::new(),
+            deleted: Vec::new(),
+            deleted_count: 0,
+            entry: None,
+            rng: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    /// Add a vector, returning its node id.
+    pub fn insert(&mut self, vector: Embedding) -> usize {
+        let vector = normalized(vector);
+        let id = self.vectors.len();
+        let level = self.random_level();
+        self.vectors.push(vector);
+        self.links.push(vec![Vec::new(); level + 1]);
+        self.deleted.push(false);
+
+        let Some(entry) = self.entry else {
+            self.entry = Some(id);
+            return id;
+        };
+        let top = self.links[entry].len() - 1;
+        let query = self.vectors[id].clone();
+
+        let mut nearest = entry;
+        for layer in (level + 1..=top).rev() {
+            nearest = self.greedy(&query, nearest, layer);
+        }
+        for layer in (0..=level.min(top)).rev() {
+            let candidates = self.search_layer(&query, &[nearest], self.ef_construction, layer);
+            let max = self.max_links(layer);
+            let neighbours: Vec<usize> = candidates.iter().take(max).map(|s| s.1).collect();
+            for &n in &neighbours {
+                self.links[n][layer].push(id);
+                if self.links[n][layer].len() > max {
+                    self.prune(n, layer, max);
+                }
+            }
+            self.links[id][layer] = neighbours;
+            nearest = candidates[0].1;
+        }
+        if level > top {
+            self.entry = Some(id);
+        }
+        id
+    }
+
+    /// Exclude node `id` from future results.
+    pub fn remove(&mut self, id: usize) {
+        if let Some(deleted) = self.deleted.get_mut(id) {
+            if !*deleted {
+                *deleted = true;
+                self.deleted_count += 1;
+            }
+        }
+    }
+
+    /// Up to `k` live nodes most similar to `query`, best first, as
+    /// (node id, cosine similarity). `ef` is the search breadth; larger
+    /// is slower and more accurate, and it is raised to at least `k`.
+    pub fn search(&self, query: &[f32], k: usize, ef: usize) -> Vec<(usize, f32)> {
+        let Some(entry) = self.entry else {
+            return Vec::new();
+        };
+        if k == 0 || query.len() != self.vectors[entry].len() {
+            return Vec::new();
+        }
+        let query = normalized(query.to_vec());
+        let mut nearest = entry;
+        for layer in (1..self.links[entry].len()).rev() {
+            nearest = self.greedy(&query, nearest, layer);
+        }
+        // Deleted nodes take up room in the candidate list, so widen it.
+        let ef = ef.max(k) + self.deleted_count.min(ef.max(k));
+        self.search_layer(&query, &[nearest], ef, 0)
+            .into_iter()
+            .filter(|s| !self.deleted[s.1])
+            .take(k)
+            .map(|s| (s.1, s.0))
+            .collect()
+    }
+
+    /// Number of live nodes.
+    pub fn len(&self) -> usize {
+        self.vectors.len() - self.deleted_count
+    }
+
+    /// Whether there are no live nodes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of removed nodes still in the graph.
+    pub fn deleted(&self) -> usize {
+        self.deleted_count
+    }
+
+    /// Walk layer `layer` from `start` to the node nearest `query`.
+    fn greedy(&self, query: &[f32], start: usize, layer: usize) -> usize {
+        let mut best = Scored(dot(query, &self.vectors[start]), start);
+        loop {
+            let mut improved = false;
+            for &n in &self.links[best.1][layer] {
+                let s = Scored(dot(query, &self.vectors[n]), n);
+                if s > best {
+                    best = s;
+                    improved = true;
+                }
+            }
+            if !improved {
+                return best.1;
+            }
+        }
+    }
+
+    /// Best-first search of one layer, returning up to `ef` nodes, best first.
+    fn search_layer(&self, query: &[f32], entries: &[usize], ef: usize, layer: usize) -> Vec<Scored> {
+        let mut visited: HashSet<usize> = entries.iter().copied().collect();
+        // Max-heap of nodes to expand; min-heap of the best found so far.
+        let mut candidates = BinaryHeap::new();
+        let mut found = BinaryHeap::new();
+        for &e in entries {
+            let s = Scored(dot(query, &self.vectors[e]), e);
+            candidates.push(s);
+            found.push(Reverse(s));
+        }
+
+        while let Some(current) = candidates.pop() {
+            let worst = found.peek().map(|r: &Reverse<Scored>| r.0);
+            if found.len() >= ef && worst.is_some_and(|w| current < w) {
+                break;
+            }
+            for &n in &self.links[current.1][layer] {
+                if !visited.insert(n) {
+                    continue;
+                }
+                let s = Scored(dot(query, &self.vectors[n]), n);
+                let worst = found.peek().map(|r| r.0);
+                if found.len() < ef || worst.is_some_and(|w| s > w) {
+                    candidates.push(s);
+                    found.push(Reverse(s));
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+
+        let mut results: Vec<Scored> = found.into_iter().map(|r| r.0).collect();
+        results.sort_by(|a, b| b.cmp(a));
+        results
+    }
+
+    /// Keep only the `max` neighbours of `node` on `layer` nearest to it.
+    fn prune(&mut self, node: usize, layer: usize, max: usize) {
+        let base = &self.vectors[node];
+        let mut scored: Vec<Scored> = self.links[node][layer]
+            .iter()
+            .map(|&n| Scored(dot(base, &self.vectors[n]), n))
+            .collect();
+        scored.sort_by(|a, b| b.cmp(a));
+        self.links[node][layer] = scored.into_iter().take(max).map(|s| s.1).collect();
+    }
+
+    fn max_links(&self, layer: usize) -> usize {
+        if layer == 0 {
+            self.m * 2
+        } else {
+            self.m
+        }
+    }
+
+    /// Draw a level with P(level ≥ l) = m^-l.
+    fn random_level(&mut self) -> usize {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        let uniform = ((self.rng >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+        let level = -uniform.ln() / (self.m as f64).ln();
+        (level as usize).min(16)
+    }
+}
+
+impl Default for Hnsw {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn normalized(mut v: Embedding) -> Embedding {
+    let norm: f32 = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for x in &mut v {
+            *x /= norm;
+        }
+    }
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cosine_similarity;
+
+    /// Deterministic pseudo-random vectors.
+    fn vectors(count: usize, dims: usize) -> Vec<Embedding> {
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        (0..count)
+            .map(|_| {
+                (0..dims)
+                    .map(|_| {
+                        state ^= state << 13;
+                        state ^= state >> 7;
+                        state ^= state << 17;
+                        (state % 2000) as f32 / 1000.0 - 1.0
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn exact_top(data: &[Embedding], query: &[f32], k: usize) -> Vec<usize> {
+        let mut scored: Vec<(usize, f32)> = data
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i, cosine_similarity(query, v)))
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.into_iter().take(k).map(|(i, _)| i).collect()
+    }
+
+    #[test]
+    fn finds_exact_match() {
+        let data = vectors(500, 16);
+        let mut hnsw = Hnsw::new();
+        for v in &data {
+            hnsw.insert(v.clone());
+        }
+        assert_eq!(hnsw.len(), 500);
+        for i in [0, 123, 499] {
+            let hits = hnsw.search(&data[i], 1, 32);
+            assert_eq!(hits[0].0, i);
+            assert!((hits[0].1 - 1.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn recall_against_brute_force() {
+        let data = vectors(1000, 24);
+        let queries = vectors(1020, 24).split_off(1000);
+        let mut hnsw = Hnsw::new();
+        for v in &data {
+            hnsw.insert(v.clone());
+        }
+        let mut hit = 0;
+        for q in &queries {
+            let exact = exact_top(&data, q, 10);
+            let approx: Vec<usize> = hnsw.search(q, 10, 64).into_iter().map(|(i, _)| i).collect();
+            hit += exact.iter().filter(|i| approx.contains(i)).count();
+        }
+        let recall = hit as f32 / (queries.len() * 10) as f32;
+        assert!(recall > 0.9, "recall {recall}");
+    }
+
+    #[test]
+    fn removed_nodes_are_not_returned() {
+        let data = vectors(200, 8);
+        let mut hnsw = Hnsw::new();
+        for v in &data {
+            hnsw.insert(v.clone());
+        }
+        hnsw.remove(42);
+        hnsw.remove(42);
+        assert_eq!(hnsw.len(), 199);
+        assert_eq!(hnsw.deleted(), 1);
+        let hits = hnsw.search(&data[42], 5, 32);
+        assert_eq!(hits.len(), 5);
+        assert!(hits.iter().all(|(i, _)| *i != 42));
+    }
+
+    #[test]
+    fn empty_and_mismatched_queries() {
+        let mut hnsw = Hnsw::new();
+        assert!(hnsw.search(&[1.0, 0.0], 3, 10).is_empty());
+        hnsw.insert(vec![1.0, 0.0]);
+        assert!(hnsw.search(&[1.0, 0.0, 0.0], 3, 10).is_empty());
+        assert_eq!(hnsw.search(&[0.5, 0.5], 3, 10).len(), 1);
+    }
+}
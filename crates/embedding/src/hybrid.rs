@@ -0,0 +1,108 @@
+//! Hybrid ranking — fuses lexical (BM25) and dense (embedding) rankings
+//! with reciprocal rank fusion.
+//!
+//! RRF scores a result by its ranks alone, `Σ weight / (k + rank)`, so the
+//! two scorers never have to agree on a scale. Lexical search catches
+//! exact names and identifiers; dense search catches paraphrases.
+
+use std::collections::HashMap;
+
+use super::MatchResult;
+
+/// The usual RRF constant: dampens the lead of the very top ranks.
+pub const DEFAULT_RRF_K: f32 = 60.0;
+
+/// Fuse ranked lists, each with a weight, by reciprocal rank fusion.
+/// Each list must be sorted best first. The fused score replaces the
+/// scorers' own; results are sorted best first, ties by name.
+pub fn reciprocal_rank_fusion(rankings: &[(&[MatchResult], f32)], k: f32) -> Vec<MatchResult> {
+    let mut fused: HashMap<&str, f32> = HashMap::new();
+    for (ranking, weight) in rankings {
+        for (rank, result) in ranking.iter().enumerate() {
+            *fused.entry(result.name.as_str()).or_insert(0.0) += weight / (k + rank as f32 + 1.0);
+        }
+    }
+    let mut results: Vec<MatchResult> = fused
+        .into_iter()
+        .map(|(name, score)| MatchResult {
+            name: name.to_string(),
+            score,
+        })
+        .collect();
+    results.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+    results
+}
+
+/// Settings for fusing a lexical and a dense ranking.
+#[derive(Debug, Clone)]
+pub struct HybridRanker {
+    /// RRF constant.
+    pub k: f32,
+    pub lexical_weight: f32,
+    pub dense_weight: f32,
+    /// How many candidates to take from each scorer.
+    pub depth: usize,
+}
+
+impl Default for HybridRanker {
+    fn default() -> Self {
+        Self {
+            k: DEFAULT_RRF_K,
+            lexical_weight: 1.0,
+            dense_weight: 1.0,
+            depth: 50,
+        }
+    }
+}
+
+impl HybridRanker {
+    /// Fuse the two rankings, each sorted best first.
+    pub fn fuse(&self, lexical: &[MatchResult], dense: &[MatchResult]) -> Vec<MatchResult> {
+        let lexical = &lexical[..lexical.len().min(self.depth)];
+        let dense = &dense[..dense.len().min(self.depth)];
+        reciprocal_rank_fusion(&[(lexical, self.lexical_weight), (dense, self.dense_weight)], self.k)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranking(names: &[&str]) -> Vec<MatchResult> {
+        names
+            .iter()
+            .map(|n| MatchResult {
+                name: n.to_string(),
+                score: 1.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn agreement_wins() {
+        let lexical = ranking(&["a", "b", "c"]);
+        let dense = ranking(&["b", "c", "a"]);
+        let fused = HybridRanker::default().fuse(&lexical, &dense);
+        let names: Vec<&str> = fused.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["b", "a", "c"]);
+    }
+
+    #[test]
+    fn results_from_one_side_survive() {
+        let fused = HybridRanker::default().fuse(&ranking(&["only-lexical"]), &[]);
+        assert_eq!(fused.len(), 1);
+        assert!((fused[0].score - 1.0 / 61.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn weights_and_depth() {
+        let ranker = HybridRanker {
+            dense_weight: 3.0,
+            depth: 1,
+            ..HybridRanker::default()
+        };
+        let fused = ranker.fuse(&ranking(&["a", "b"]), &ranking(&["b", "a"]));
+        let names: Vec<&str> = fused.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["b", "a"]);
+    }
+}
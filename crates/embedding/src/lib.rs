@@ -2,9 +2,17 @@
 //!
 //! Pluggable embedding providers (TF-IDF today, ONNX tomorrow) produce
 //! vectors from text. The `EmbeddingIndex` stores pre-embedded tool
-//! descriptions and provides cosine similarity search.
+//! descriptions and provides cosine similarity search; [`hnsw::Hnsw`]
+//! answers the same question approximately for large collections.
+//!
+//! Lexical retrieval sits alongside: [`bm25::Bm25Index`] scores documents
+//! with weighted fields, and [`hybrid`] fuses its rankings with dense ones.
 
+pub mod bm25;
+pub mod hnsw;
+pub mod hybrid;
 pub mod tfidf;
+pub mod tokenize;
 
 /// A single embedding vector.
 pub type Embedding = Vec<f32>;
@@ -15,6 +23,12 @@ pub trait EmbeddingProvider: Send + Sync {
     fn embed(&self, text: &str) -> Embedding;
     /// Dimensionality of the embedding space.
     fn dimensions(&self) -> usize;
+    /// Identifies the model and its parameters. Vectors from providers
+    /// with different ids are not comparable, so persisted vectors are
+    /// re-embedded when it changes.
+    fn model_id(&self) -> String {
+        format!("unknown-{}", self.dimensions())
+    }
 }
 
 /// Result of a similarity search.
@@ -103,6 +117,25 @@ impl EmbeddingIndex {
         results
     }
 
+    /// Top K matches above threshold among `allowed` names, best first.
+    /// An empty `allowed` matches nothing.
+    pub fn search_top_k_filtered(&self, query: &Embedding, allowed: &[String], k: usize) -> Vec<MatchResult> {
+        let mut results: Vec<MatchResult> = self
+            .entries
+            .iter()
+            .filter(|(name, _)| allowed.iter().any(|a| a == name))
+            .map(|(name, emb)| MatchResult {
+                name: name.clone(),
+                score: cosine_similarity(query, emb),
+            })
+            .filter(|r| r.score >= self.threshold)
+            .collect();
+
+        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        results.truncate(k);
+        results
+    }
+
     /// Number of entries in the index.
     pub fn len(&self) -> usize {
         self.entries.len()
@@ -3,20 +3,16 @@
 //! Tokenizes text, builds IDF from a corpus of semantic descriptions,
 //! produces sparse TF-IDF vectors normalized to unit length for
 //! cosine similarity via dot product.
+//!
+//! A hashed provider folds terms into a fixed number of dimensions
+//! instead, so its vectors stay small for large vocabularies (source
+//! code) and terms outside the corpus still count.
 
 use std::collections::HashMap;
 
+use super::tokenize::Tokenizer;
 use super::{Embedding, EmbeddingProvider};
 
-/// Simple stop words to filter out common English words.
-const STOP_WORDS: &[&str] = &[
-    "a", "an", "the", "is", "it", "in", "on", "of", "to", "and", "or", "for", "with", "this",
-    "that", "be", "are", "was", "were", "been", "being", "have", "has", "had", "do", "does",
-    "did", "will", "would", "could", "should", "may", "might", "can", "shall", "not", "no",
-    "but", "if", "at", "by", "from", "as", "into", "about", "up", "out", "so", "its", "you",
-    "your", "i", "my", "we", "our", "they", "them", "their", "he", "she", "his", "her",
-];
-
 /// TF-IDF embedding provider.
 ///
 /// Builds a vocabulary + IDF weights from a corpus of documents (semantic descriptions).
@@ -27,8 +23,15 @@ pub struct TfIdfProvider {
     vocabulary: HashMap<String, usize>,
     /// IDF weight per dimension
     idf: Vec<f32>,
-    /// Total dimensions (vocabulary size)
+    /// Total dimensions (vocabulary size, or the bucket count if hashed)
     dims: usize,
+    /// Hash terms into `dims` buckets rather than one dimension per term.
+    hashed: bool,
+    /// IDF of a term the corpus never saw (hashed only).
+    unseen_idf: f32,
+    tokenizer: Tokenizer,
+    /// Identifies the vocabulary and weights, for [`EmbeddingProvider::model_id`].
+    id: String,
 }
 
 impl TfIdfProvider {
@@ -36,17 +39,38 @@ impl TfIdfProvider {
     ///
     /// Tokenizes all documents, builds a vocabulary, computes IDF weights.
     pub fn from_corpus(documents: &[&str]) -> Self {
+        Self::from_corpus_with(documents, Tokenizer::default())
+    }
+
+    /// Build from a corpus, tokenizing with `tokenizer`.
+    pub fn from_corpus_with(documents: &[&str], tokenizer: Tokenizer) -> Self {
+        let (vocabulary, idf) = Self::weights(documents, &tokenizer);
+        let dims = vocabulary.len();
+        Self::assemble(vocabulary, idf, dims, false, documents.len(), tokenizer)
+    }
+
+    /// Build a provider hashing terms into `dims` dimensions, with IDF
+    /// weights from `documents`. Terms not in the corpus get the weight
+    /// of the rarest term; with an empty corpus every term weighs the same.
+    pub fn hashed(documents: &[&str], dims: usize) -> Self {
+        Self::hashed_with(documents, dims, Tokenizer::default())
+    }
+
+    /// Build a hashed provider, tokenizing with `tokenizer`.
+    pub fn hashed_with(documents: &[&str], dims: usize, tokenizer: Tokenizer) -> Self {
+        let (vocabulary, idf) = Self::weights(documents, &tokenizer);
+        Self::assemble(vocabulary, idf, dims.max(1), true, documents.len(), tokenizer)
+    }
+
+    /// Vocabulary and IDF weights of a corpus.
+    fn weights(documents: &[&str], tokenizer: &Tokenizer) -> (HashMap<String, usize>, Vec<f32>) {
         let n = documents.len() as f32;
         if documents.is_empty() {
-            return Self {
-                vocabulary: HashMap::new(),
-                idf: Vec::new(),
-                dims: 0,
-            };
+            return (HashMap::new(), Vec::new());
         }
 
         // Tokenize all documents and build vocabulary
-        let tokenized: Vec<Vec<String>> = documents.iter().map(|d| tokenize(d)).collect();
+        let tokenized: Vec<Vec<String>> = documents.iter().map(|d| tokenizer.tokenize(d)).collect();
 
         let mut vocabulary: HashMap<String, usize> = HashMap::new();
         let mut doc_freq: HashMap<String, usize> = HashMap::new();
@@ -72,19 +96,56 @@ impl TfIdfProvider {
             idf[idx] = (n / df.max(1.0)).ln() + 1.0;
         }
 
+        (vocabulary, idf)
+    }
+
+    fn assemble(
+        vocabulary: HashMap<String, usize>,
+        idf: Vec<f32>,
+        dims: usize,
+        hashed: bool,
+        corpus_size: usize,
+        tokenizer: Tokenizer,
+    ) -> Self {
+        let unseen_idf = (corpus_size.max(1) as f32).ln() + 1.0;
+        let mut terms: Vec<(&String, &usize)> = vocabulary.iter().collect();
+        terms.sort();
+        let mut fingerprint = fnv1a(FNV_OFFSET, tokenizer.signature().as_bytes());
+        for (term, &idx) in terms {
+            fingerprint = fnv1a(fingerprint, term.as_bytes());
+            fingerprint = fnv1a(fingerprint, &idf[idx].to_le_bytes());
+        }
+        let kind = if hashed { "tfidf-hashed" } else { "tfidf" };
         Self {
+            id: format!("{kind}-{dims}-{fingerprint:016x}"),
             vocabulary,
             idf,
             dims,
+            hashed,
+            unseen_idf,
+            tokenizer,
+        }
+    }
+
+    /// Dimension and IDF weight of a term, if it contributes.
+    fn weight(&self, term: &str) -> Option<(usize, f32)> {
+        let known = self.vocabulary.get(term).map(|&idx| (idx, self.idf[idx]));
+        if !self.hashed {
+            return known;
         }
+        let idf = known.map_or(self.unseen_idf, |(_, idf)| idf);
+        let bucket = (fnv1a(FNV_OFFSET, term.as_bytes()) % self.dims as u64) as usize;
+        Some((bucket, idf))
     }
 
     /// Rebuild from an updated corpus (hot-reload).
     pub fn rebuild(&mut self, documents: &[&str]) {
-        let new = Self::from_corpus(documents);
-        self.vocabulary = new.vocabulary;
-        self.idf = new.idf;
-        self.dims = new.dims;
+        let tokenizer = self.tokenizer.clone();
+        *self = if self.hashed {
+            Self::hashed_with(documents, self.dims, tokenizer)
+        } else {
+            Self::from_corpus_with(documents, tokenizer)
+        };
     }
 }
 
@@ -94,7 +155,7 @@ impl EmbeddingProvider for TfIdfProvider {
             return vec![];
         }
 
-        let tokens = tokenize(text);
+        let tokens = self.tokenizer.tokenize(text);
         let mut tf: HashMap<&str, f32> = HashMap::new();
         for token in &tokens {
             *tf.entry(token.as_str()).or_insert(0.0) += 1.0;
@@ -102,9 +163,9 @@ impl EmbeddingProvider for TfIdfProvider {
 
         let mut vector = vec![0.0f32; self.dims];
         for (term, &count) in &tf {
-            if let Some(&idx) = self.vocabulary.get(*term) {
+            if let Some((idx, idf)) = self.weight(term) {
                 // TF-IDF = term frequency × inverse document frequency
-                vector[idx] = count * self.idf[idx];
+                vector[idx] += count * idf;
             }
         }
 
@@ -116,16 +177,22 @@ impl EmbeddingProvider for TfIdfProvider {
     fn dimensions(&self) -> usize {
         self.dims
     }
+
+    fn model_id(&self) -> String {
+        self.id.clone()
+    }
 }
 
-/// Tokenize text: lowercase, split on non-alphanumeric, filter stop words.
-fn tokenize(text: &str) -> Vec<String> {
-    text.to_lowercase()
-        .split(|c: char| !c.is_alphanumeric())
-        .filter(|w| !w.is_empty() && w.len() > 1)
-        .filter(|w| !STOP_WORDS.contains(w))
-        .map(|w| w.to_string())
-        .collect()
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// FNV-1a, continuing from `hash`. Stable across builds, unlike
+/// `DefaultHasher`, so hashed dimensions survive a persisted index.
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
 }
 
 /// Normalize a vector to unit length (in-place).
@@ -184,4 +251,21 @@ mod tests {
         let embedding = provider.embed("read something");
         assert_eq!(embedding.len(), provider.dimensions());
     }
+
+    #[test]
+    fn hashed_has_fixed_dims_and_handles_unseen_terms() {
+        let docs = vec!["read files from the filesystem", "execute shell commands"];
+        let provider = TfIdfProvider::hashed(&docs, 64);
+        assert_eq!(provider.dimensions(), 64);
+        let a = provider.embed("parse_config loader");
+        assert_eq!(a.len(), 64);
+        assert!(a.iter().any(|&v| v > 0.0));
+        assert!(cosine(&a, &provider.embed("config loader")) > 0.5);
+        assert_eq!(provider.model_id(), TfIdfProvider::hashed(&docs, 64).model_id());
+        assert_ne!(provider.model_id(), TfIdfProvider::hashed(&docs[..1], 64).model_id());
+    }
+
+    fn cosine(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
 }
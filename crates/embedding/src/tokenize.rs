@@ -0,0 +1,210 @@
+//! Tokenization shared by the lexical and TF-IDF scorers.
+//!
+//! Identifiers are split at `snake_case`, `kebab-case` and `camelCase`
+//! boundaries, so `loadConfig`, `load_config` and "load the config" share
+//! terms. The joined identifier is kept as well (`loadconfig`), so an
+//! exact identifier still outranks a loose mention of its parts.
+
+use std::collections::HashSet;
+
+/// Common English words carrying no meaning for retrieval.
+pub const ENGLISH_STOP_WORDS: &[&str] = &[
+    "a", "an", "the", "is", "it", "in", "on", "of", "to", "and", "or", "for", "with", "this",
+    "that", "be", "are", "was", "were", "been", "being", "have", "has", "had", "do", "does",
+    "did", "will", "would", "could", "should", "may", "might", "can", "shall", "not", "no",
+    "but", "if", "at", "by", "from", "as", "into", "about", "up", "out", "so", "its", "you",
+    "your", "i", "my", "we", "our", "they", "them", "their", "he", "she", "his", "her",
+];
+
+/// Configurable tokenizer. The default lowercases, splits identifiers,
+/// drops single characters and English stop words, and does not stem.
+#[derive(Debug, Clone)]
+pub struct Tokenizer {
+    stop_words: HashSet<String>,
+    split_identifiers: bool,
+    stem: bool,
+}
+
+impl Default for Tokenizer {
+    fn default() -> Self {
+        Self {
+            stop_words: ENGLISH_STOP_WORDS.iter().map(|w| w.to_string()).collect(),
+            split_identifiers: true,
+            stem: false,
+        }
+    }
+}
+
+impl Tokenizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the stop-word list. Words are matched lowercased.
+    pub fn with_stop_words<I, S>(mut self, words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.stop_words = words.into_iter().map(|w| w.as_ref().to_lowercase()).collect();
+        self
+    }
+
+    /// Split `snake_case`, `kebab-case` and `camelCase` identifiers.
+    pub fn with_identifier_splitting(mut self, split: bool) -> Self {
+        self.split_identifiers = split;
+        self
+    }
+
+    /// Reduce words to a crude stem (`files` → `file`, `running` → `run`).
+    pub fn with_stemming(mut self, stem: bool) -> Self {
+        self.stem = stem;
+        self
+    }
+
+    /// Terms of `text`, in order, with repeats.
+    pub fn tokenize(&self, text: &str) -> Vec<String> {
+        let mut tokens = Vec::new();
+        if !self.split_identifiers {
+            for word in text.split(|c: char| !c.is_alphanumeric()) {
+                self.push(&word.to_lowercase(), &mut tokens);
+            }
+            return tokens;
+        }
+        for ident in text.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-')) {
+            let parts: Vec<&str> = ident
+                .split(['_', '-'])
+                .flat_map(split_camel_case)
+                .filter(|p| !p.is_empty())
+                .collect();
+            for part in &parts {
+                self.push(&part.to_lowercase(), &mut tokens);
+            }
+            if parts.len() > 1 {
+                let joined = parts.concat().to_lowercase();
+                if !self.stop_words.contains(&joined) {
+                    tokens.push(joined);
+                }
+            }
+        }
+        tokens
+    }
+
+    /// Describes the configuration, for model ids of providers using it.
+    pub fn signature(&self) -> String {
+        let mut words: Vec<&str> = self.stop_words.iter().map(String::as_str).collect();
+        words.sort_unstable();
+        format!(
+            "split={},stem={},stop={}",
+            self.split_identifiers,
+            self.stem,
+            words.join(" ")
+        )
+    }
+
+    fn push(&self, word: &str, tokens: &mut Vec<String>) {
+        if word.chars().count() < 2 || self.stop_words.contains(word) {
+            return;
+        }
+        tokens.push(if self.stem { stem(word) } else { word.to_string() });
+    }
+}
+
+/// Split at lower→upper (`loadConfig`) and acronym→word (`HTTPServer`)
+/// boundaries. Digits stay with what precedes them (`sha256`).
+fn split_camel_case(ident: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = ident.char_indices().collect();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for i in 1..chars.len() {
+        let (at, c) = chars[i];
+        let prev = chars[i - 1].1;
+        let next_lower = chars.get(i + 1).is_some_and(|(_, n)| n.is_lowercase());
+        let boundary = c.is_uppercase()
+            && (prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_lower));
+        if boundary {
+            parts.push(&ident[start..at]);
+            start = at;
+        }
+    }
+    parts.push(&ident[start..]);
+    parts
+}
+
+/// Light English stemmer: plurals, `-ed`/`-ing`, final `y` and `e`. Not
+/// linguistically exact; it only has to map a word's forms to one term.
+pub fn stem(word: &str) -> String {
+    let mut w = word.to_string();
+    if w.len() <= 3 || !w.is_ascii() {
+        return w;
+    }
+    if let Some(base) = w.strip_suffix("sses") {
+        w = format!("{base}ss");
+    } else if let Some(base) = w.strip_suffix("ies") {
+        w = format!("{base}y");
+    } else if w.ends_with('s') && !w.ends_with("ss") && !w.ends_with("us") && !w.ends_with("is") {
+        w.pop();
+    }
+    for suffix in ["ing", "ed"] {
+        if let Some(base) = w.strip_suffix(suffix) {
+            if base.len() >= 3 && base.chars().any(is_vowel) {
+                w = base.to_string();
+                let bytes = w.as_bytes();
+                let n = bytes.len();
+                if bytes[n - 1] == bytes[n - 2] && !matches!(bytes[n - 1], b'l' | b's' | b'z') && !is_vowel(bytes[n - 1] as char) {
+                    w.pop();
+                }
+            }
+            break;
+        }
+    }
+    if w.len() > 3 && w.ends_with('y') && !w[..w.len() - 1].ends_with(is_vowel) {
+        w.pop();
+        w.push('i');
+    }
+    if w.len() > 4 && w.ends_with('e') {
+        w.pop();
+    }
+    w
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'a' | 'e' | 'i' | 'o' | 'u')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_identifiers() {
+        let t = Tokenizer::new();
+        assert_eq!(t.tokenize("loadConfig"), vec!["load", "config", "loadconfig"]);
+        assert_eq!(t.tokenize("load_config"), vec!["load", "config", "loadconfig"]);
+        assert_eq!(t.tokenize("file-read"), vec!["file", "read", "fileread"]);
+        assert_eq!(t.tokenize("HTTPServer sha256"), vec!["http", "server", "httpserver", "sha256"]);
+    }
+
+    #[test]
+    fn plain_mode_matches_word_splitting() {
+        let t = Tokenizer::new().with_identifier_splitting(false);
+        assert_eq!(t.tokenize("Read the loadConfig file_name"), vec!["read", "loadconfig", "file", "name"]);
+    }
+
+    #[test]
+    fn stop_words_are_configurable() {
+        let t = Tokenizer::new().with_stop_words(["Read"]);
+        assert_eq!(t.tokenize("read the file"), vec!["the", "file"]);
+        assert_ne!(t.signature(), Tokenizer::new().signature());
+    }
+
+    #[test]
+    fn stemming_conflates_forms() {
+        let t = Tokenizer::new().with_stemming(true);
+        for (a, b) in [("files", "file"), ("running", "run"), ("retries", "retry"), ("compiled", "compile"), ("searching", "search")] {
+            assert_eq!(t.tokenize(a), t.tokenize(b), "{a} vs {b}");
+        }
+        assert_eq!(stem("class"), "class");
+        assert_eq!(stem("status"), "status");
+    }
+}
@@ -2,13 +2,17 @@
 //!
 //! Contains event types, LLM message protocol types, and permission types
 //! that cross module boundaries. This crate is the foundation — imported by
-//! agent, llm, librarian, organism, tui, pipeline, and tools.
+//! agent, llm, librarian, organism, tui, pipeline, tools, and vdrive.
 
 use std::collections::HashMap;
 use async_trait::async_trait;
 use rust_pipeline::prelude::*;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+pub mod mount;
+
+pub use mount::{GlobRules, MountPolicies, MountPolicy};
+
 // ── Cortex shim event payload ──
 
 /// Cross-crate transport for cortex's shim outcomes on one turn.
@@ -44,9 +48,11 @@ pub enum PipelineEvent {
         profile: String,
         target: String,
     },
-    /// Token usage from an LLM API call.
+    /// Token usage from an LLM API call. `model` is the id the
+    /// provider reported, for pricing.
     TokenUsage {
         thread_id: String,
+        model: String,
         input_tokens: u32,
         output_tokens: u32,
     },
@@ -438,6 +444,9 @@ pub struct FsGrant {
     pub host_path: String,
     pub guest_path: String,
     pub read_only: bool,
+    /// Mount policy of every profile that can reach the tool, so the
+    /// preopen is no looser than the VDrive tools on the same folder.
+    pub mount: MountPolicy,
 }
 
 /// An environment variable grant.
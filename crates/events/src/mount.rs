@@ -0,0 +1,270 @@
+//! Mount policies — what a VDrive lets its callers see and change.
+//!
+//! Plain data, shared by the organism config (which declares them per
+//! profile and agent), the VDrive (which enforces them) and WASM
+//! filesystem grants (which must be no looser). Containment keeps paths
+//! under the drive root; a [`MountPolicy`] narrows that further: the
+//! whole mount or individual globs can be read-only, `deny` globs are
+//! invisible, and writes are metered against per-file, per-session byte
+//! and new-file budgets.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+
+/// Access rules for a mounted drive. The default restricts nothing.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MountPolicy {
+    /// Reject every mutation.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Total bytes the caller may write while the drive is mounted.
+    #[serde(default)]
+    pub max_bytes_written: Option<u64>,
+    /// Largest file the caller may write, in bytes.
+    #[serde(default)]
+    pub max_file_size: Option<u64>,
+    /// How many new files the caller may create while the drive is mounted.
+    #[serde(default)]
+    pub max_files: Option<u64>,
+    /// Globs (drive-relative) hidden from every operation.
+    #[serde(default)]
+    pub deny: GlobRules,
+    /// Globs (drive-relative) that may be read but not changed.
+    #[serde(default)]
+    pub readonly: GlobRules,
+}
+
+impl MountPolicy {
+    /// True when the policy restricts nothing.
+    pub fn is_unrestricted(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// True when any write is limited — a read-only mount, read-only
+    /// globs, or a byte/file budget.
+    pub fn restricts_writes(&self) -> bool {
+        self.read_only
+            || !self.readonly.is_empty()
+            || self.max_bytes_written.is_some()
+            || self.max_file_size.is_some()
+            || self.max_files.is_some()
+    }
+
+    /// The tighter of two policies: either read-only flag wins, the
+    /// smaller of each limit applies, and the rule lists are combined.
+    pub fn merge(&self, other: &MountPolicy) -> MountPolicy {
+        fn min(a: Option<u64>, b: Option<u64>) -> Option<u64> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            }
+        }
+        MountPolicy {
+            read_only: self.read_only || other.read_only,
+            max_bytes_written: min(self.max_bytes_written, other.max_bytes_written),
+            max_file_size: min(self.max_file_size, other.max_file_size),
+            max_files: min(self.max_files, other.max_files),
+            deny: self.deny.union(&other.deny),
+            readonly: self.readonly.union(&other.readonly),
+        }
+    }
+
+    /// Whether `rel` (drive-relative, `/`-separated) is hidden.
+    pub fn is_denied(&self, rel: &str) -> bool {
+        self.deny.covers(rel)
+    }
+
+    /// Whether `rel` may not be changed.
+    pub fn is_read_only(&self, rel: &str) -> bool {
+        self.read_only || self.readonly.covers(rel) || self.is_denied(rel)
+    }
+}
+
+/// Drive-relative globs, compiled when the list is built — an invalid
+/// glob is rejected then (or when a policy is deserialized), never
+/// skipped at match time.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<String>", into = "Vec<String>")]
+pub struct GlobRules(Vec<Pattern>);
+
+impl GlobRules {
+    pub fn new<S: AsRef<str>>(rules: impl IntoIterator<Item = S>) -> Result<Self, String> {
+        rules
+            .into_iter()
+            .map(|rule| {
+                let rule = rule.as_ref();
+                Pattern::new(rule).map_err(|e| format!("invalid glob {rule:?}: {e}"))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The rules as written.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(Pattern::as_str)
+    }
+
+    /// These rules followed by any of `other`'s not already present.
+    pub fn union(&self, other: &GlobRules) -> GlobRules {
+        let mut out = self.0.clone();
+        out.extend(other.0.iter().filter(|p| !self.0.contains(p)).cloned());
+        GlobRules(out)
+    }
+
+    /// A rule covers a path when it matches the path or any ancestor, so
+    /// `secrets` and `secrets/**` both cover `secrets/` and everything
+    /// below.
+    pub fn covers(&self, rel: &str) -> bool {
+        if self.0.is_empty() || rel.is_empty() {
+            return false;
+        }
+        let opts = MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        self.0.iter().any(|pattern| {
+            let dir_rule = pattern.as_str().strip_suffix("/**");
+            Path::new(rel).ancestors().any(|a| {
+                let a = a.to_string_lossy();
+                !a.is_empty() && (pattern.matches_with(&a, opts) || dir_rule == Some(&*a))
+            })
+        })
+    }
+}
+
+impl TryFrom<Vec<String>> for GlobRules {
+    type Error = String;
+    fn try_from(rules: Vec<String>) -> Result<Self, Self::Error> {
+        Self::new(rules)
+    }
+}
+
+impl From<GlobRules> for Vec<String> {
+    fn from(rules: GlobRules) -> Self {
+        rules.iter().map(str::to_string).collect()
+    }
+}
+
+/// Per-agent policies plus the base policy every agent gets.
+#[derive(Debug, Clone, Default)]
+pub struct MountPolicies {
+    /// Applies to every caller (the profile's policy).
+    pub base: MountPolicy,
+    /// Extra restrictions by agent (listener) name.
+    pub agents: HashMap<String, MountPolicy>,
+}
+
+impl MountPolicies {
+    /// True when neither the base nor any agent restricts anything.
+    pub fn is_unrestricted(&self) -> bool {
+        self.base.is_unrestricted() && self.agents.values().all(MountPolicy::is_unrestricted)
+    }
+
+    /// Effective policy for `agent` (the base alone when `None`).
+    pub fn effective(&self, agent: Option<&str>) -> MountPolicy {
+        match agent.and_then(|a| self.agents.get(a)) {
+            Some(p) => self.base.merge(p),
+            None => self.base.clone(),
+        }
+    }
+
+    /// [`MountPolicy::is_denied`] on `agent`'s effective policy, without
+    /// building it.
+    pub fn is_denied(&self, agent: Option<&str>, rel: &str) -> bool {
+        self.base.is_denied(rel) || self.agent(agent).is_some_and(|p| p.is_denied(rel))
+    }
+
+    /// [`MountPolicy::is_read_only`] on `agent`'s effective policy,
+    /// without building it.
+    pub fn is_read_only(&self, agent: Option<&str>, rel: &str) -> bool {
+        self.base.is_read_only(rel) || self.agent(agent).is_some_and(|p| p.is_read_only(rel))
+    }
+
+    fn agent(&self, agent: Option<&str>) -> Option<&MountPolicy> {
+        agent.and_then(|a| self.agents.get(a))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(rules: &[&str]) -> GlobRules {
+        GlobRules::new(rules).unwrap()
+    }
+
+    #[test]
+    fn rule_matching_covers_descendants() {
+        let p = MountPolicy {
+            deny: rules(&["secrets/**", "*.pem"]),
+            readonly: rules(&[".git"]),
+            ..Default::default()
+        };
+        assert!(p.is_denied("secrets"));
+        assert!(p.is_denied("secrets/a/b.txt"));
+        assert!(p.is_denied("server.pem"));
+        assert!(!p.is_denied("src/server.pem"));
+        assert!(!p.is_denied("secretsauce.txt"));
+        assert!(p.is_read_only(".git/objects/ab"));
+        assert!(!p.is_read_only("src/main.rs"));
+    }
+
+    #[test]
+    fn merge_takes_tighter_limits() {
+        let a = MountPolicy {
+            max_bytes_written: Some(100),
+            deny: rules(&["a"]),
+            ..Default::default()
+        };
+        let b = MountPolicy {
+            read_only: true,
+            max_bytes_written: Some(50),
+            max_files: Some(3),
+            deny: rules(&["a", "b"]),
+            ..Default::default()
+        };
+        let m = a.merge(&b);
+        assert!(m.read_only);
+        assert_eq!(m.max_bytes_written, Some(50));
+        assert_eq!(m.max_files, Some(3));
+        assert_eq!(m.deny.iter().collect::<Vec<_>>(), ["a", "b"]);
+    }
+
+    #[test]
+    fn invalid_glob_rejected() {
+        assert!(GlobRules::new(["secrets/**", "["]).is_err());
+        let parsed: Result<MountPolicy, _> = serde_json::from_str(r#"{"deny": ["["]}"#);
+        assert!(parsed.is_err());
+        let policy: MountPolicy = serde_json::from_str(r#"{"readonly": [".git/**"]}"#).unwrap();
+        assert!(policy.is_read_only(".git/HEAD"));
+        assert_eq!(serde_json::to_value(&policy).unwrap()["readonly"][0], ".git/**");
+    }
+
+    #[test]
+    fn policies_check_agent_rules_without_merging() {
+        let mut agents = HashMap::new();
+        agents.insert("reviewer".to_string(), MountPolicy {
+            readonly: rules(&["src/**"]),
+            ..Default::default()
+        });
+        let policies = MountPolicies {
+            base: MountPolicy {
+                deny: rules(&["secrets"]),
+                ..Default::default()
+            },
+            agents,
+        };
+        assert!(policies.is_denied(Some("reviewer"), "secrets/key"));
+        assert!(policies.is_read_only(Some("reviewer"), "src/main.rs"));
+        assert!(!policies.is_read_only(Some("coder"), "src/main.rs"));
+        assert!(!policies.is_read_only(None, "src/main.rs"));
+    }
+}
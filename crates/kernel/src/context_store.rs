@@ -564,6 +564,21 @@ impl ContextStore {
         })
     }
 
+    /// Segment bytes across all threads, split by status:
+    /// `(active, shelved, folded)`. Folded segments count their summary,
+    /// not the stashed original. Sampled for the context-tier gauges.
+    pub fn bytes_by_status(&self) -> (usize, usize, usize) {
+        let (mut active, mut shelved, mut folded) = (0, 0, 0);
+        for seg in self.contexts.values().flat_map(|c| c.segments.values()) {
+            match seg.status {
+                SegmentStatus::Active => active += seg.content.len(),
+                SegmentStatus::Shelved => shelved += seg.content.len(),
+                SegmentStatus::Folded => folded += seg.content.len(),
+            }
+        }
+        (active, shelved, folded)
+    }
+
     /// Get a segment by ID (read-only).
     pub fn get_segment(&self, thread_id: &str, segment_id: &str) -> KernelResult<&ContextSegment> {
         let ctx = self
@@ -1120,6 +1135,23 @@ mod tests {
         assert_eq!(inv.folded_count, 1);
     }
 
+    #[test]
+    fn bytes_by_status_spans_threads() {
+        let dir = TempDir::new().unwrap();
+        let mut store = ContextStore::open(&dir.path().join("contexts")).unwrap();
+        store.create("t1").unwrap();
+        store.create("t2").unwrap();
+        store.add_segment("t1", make_segment("s1", "code", b"active")).unwrap();
+        store.add_segment("t2", make_segment("s1", "msg", b"to fold")).unwrap();
+        let mut shelved = make_segment("s2", "doc", b"shelved!");
+        shelved.status = SegmentStatus::Shelved;
+        store.add_segment("t2", shelved).unwrap();
+
+        store.fold("t2", "s1", b"[f]".to_vec()).unwrap();
+
+        assert_eq!(store.bytes_by_status(), (6, 8, 3));
+    }
+
     #[test]
     fn working_set_excludes_folded() {
         let dir = TempDir::new().unwrap();
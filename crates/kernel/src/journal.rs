@@ -86,6 +86,10 @@ impl Journal {
                     }
                 }
             }
+            EntryType::JournalErase => {
+                let thread_id = String::from_utf8_lossy(&entry.payload).to_string();
+                self.erase_thread(&thread_id);
+            }
             _ => {} // not a journal op
         }
     }
@@ -183,6 +187,27 @@ impl Journal {
         count
     }
 
+    /// Remove every entry belonging to a thread (GDPR erase).
+    /// Returns the message IDs that were removed so the caller can scrub
+    /// any other records keyed by them.
+    pub fn erase_thread(&mut self, thread_id: &str) -> Vec<String> {
+        let ids: Vec<String> = self
+            .entries
+            .values()
+            .filter(|e| e.thread_id == thread_id)
+            .map(|e| e.message_id.clone())
+            .collect();
+        for id in &ids {
+            self.entries.remove(id);
+        }
+        ids
+    }
+
+    /// Build a WAL entry for erasing a thread's journal entries.
+    pub fn wal_entry_erase(thread_id: &str) -> WalEntry {
+        WalEntry::new(EntryType::JournalErase, thread_id.as_bytes().to_vec())
+    }
+
     /// Iterate over all journal entries.
     pub fn all_entries(&self) -> impl Iterator<Item = &JournalEntry> {
         self.entries.values()
@@ -374,6 +399,25 @@ mod tests {
         assert!(journal.get("forever-msg").is_some());
     }
 
+    #[test]
+    fn erase_thread_removes_only_that_thread() {
+        let dir = TempDir::new().unwrap();
+        let mut journal = Journal::open(&dir.path().join("journal.bin")).unwrap();
+        journal.log_dispatch_simple("msg-1", "t1", "a", "b");
+        journal.log_dispatch_simple("msg-2", "t1", "b", "a");
+        journal.log_dispatch_simple("msg-3", "t2", "a", "c");
+
+        let mut removed = journal.erase_thread("t1");
+        removed.sort();
+        assert_eq!(removed, vec!["msg-1".to_string(), "msg-2".to_string()]);
+        assert_eq!(journal.count(), 1);
+        assert!(journal.get("msg-3").is_some());
+
+        // Replayed erase is idempotent.
+        journal.apply_wal_entry(&Journal::wal_entry_erase("t1"));
+        assert_eq!(journal.count(), 1);
+    }
+
     #[test]
     fn journal_all_entries() {
         let dir = TempDir::new().unwrap();
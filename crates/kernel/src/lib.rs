@@ -12,6 +12,7 @@
 pub mod context_store;
 pub mod error;
 pub mod journal;
+pub mod shim_stats;
 pub mod shim_store;
 pub mod thread_table;
 pub mod wal;
@@ -19,7 +20,7 @@ pub mod wal;
 use std::path::{Path, PathBuf};
 
 use context_store::ContextStore;
-use error::KernelResult;
+use error::{KernelError, KernelResult};
 use journal::Journal;
 use shim_store::ShimStore;
 use thread_table::ThreadTable;
@@ -304,6 +305,100 @@ impl Kernel {
         Ok(())
     }
 
+    /// Erase every trace of a thread (GDPR delete).
+    ///
+    /// Drops the thread record, its context (segments and any folded
+    /// originals), and its journal entries, then compacts the WAL so
+    /// the erased content can't come back on replay: every entry whose
+    /// null-separated payload mentions the thread or one of its message
+    /// IDs is dropped, and the log is rewritten in place.
+    ///
+    /// Returns the number of earlier WAL entries scrubbed (the erase's
+    /// own bookkeeping entries aren't counted). Erasing an unknown
+    /// thread is a no-op that still succeeds — delete is idempotent.
+    pub fn erase_thread(&mut self, thread_id: &str) -> KernelResult<usize> {
+        self.erase_threads(&[thread_id.to_string()])
+    }
+
+    /// [`Self::erase_thread`] for several threads at once, with a single
+    /// WAL replay and rewrite for the lot.
+    pub fn erase_threads(&mut self, thread_ids: &[String]) -> KernelResult<usize> {
+        if thread_ids.is_empty() {
+            return Ok(0);
+        }
+        let batch: Vec<wal::WalEntry> = thread_ids
+            .iter()
+            .flat_map(|thread_id| {
+                [
+                    wal::WalEntry::new(
+                        wal::EntryType::ThreadCleanup,
+                        thread_id.as_bytes().to_vec(),
+                    ),
+                    ContextStore::wal_entry_release(thread_id),
+                    Journal::wal_entry_erase(thread_id),
+                ]
+            })
+            .collect();
+        self.wal.append_batch(&batch)?;
+
+        let mut needles = Vec::new();
+        for thread_id in thread_ids {
+            if let Some(ctx) = self.contexts.get(thread_id) {
+                let fold_refs: Vec<String> = ctx
+                    .segments
+                    .values()
+                    .filter_map(|s| s.fold_ref.clone())
+                    .collect();
+                for fr in fold_refs {
+                    self.contexts.fold_store.remove(&fr);
+                }
+            }
+            self.threads.cleanup(thread_id);
+            self.contexts.release(thread_id)?;
+            needles.extend(self.journal.erase_thread(thread_id));
+            needles.push(thread_id.clone());
+        }
+
+        // The erase batch just appended is the tail of the log; it goes
+        // too, but only what was there before counts as scrubbed.
+        let entries = self.wal.replay()?;
+        let earlier = entries.len().saturating_sub(batch.len());
+        let scrubbed = entries[..earlier]
+            .iter()
+            .filter(|e| payload_mentions(&e.payload, &needles))
+            .count();
+        let kept: Vec<wal::WalEntry> = entries
+            .into_iter()
+            .filter(|e| !payload_mentions(&e.payload, &needles))
+            .collect();
+        self.wal.rewrite(&kept)?;
+
+        tracing::info!(threads = thread_ids.len(), scrubbed, "Erased threads from kernel");
+        Ok(scrubbed)
+    }
+
+    /// `root` and every thread the kernel holds under it (`<root>/...`,
+    /// e.g. a platform instance's buffer threads), sorted. Looks at the
+    /// thread table, contexts and journal, so threads whose instance was
+    /// already evicted are still found.
+    pub fn thread_ids_under(&self, root: &str) -> Vec<String> {
+        let prefix = format!("{root}/");
+        let under = |id: &str| id == root || id.starts_with(&prefix);
+        let mut ids: Vec<String> = self
+            .threads
+            .all_records()
+            .map(|r| r.uuid.as_str())
+            .chain(self.contexts.all_thread_ids())
+            .chain(self.journal.all_entries().map(|e| e.thread_id.as_str()))
+            .filter(|id| under(id))
+            .map(str::to_string)
+            .chain(std::iter::once(root.to_string()))
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
     // ── Shim store (fourth pillar) ──
     //
     // Each method delegates the file-write + in-memory-update work to
@@ -367,6 +462,66 @@ impl Kernel {
         Ok(())
     }
 
+    /// Restore the composition before the current one (from the
+    /// store's `compositions/` archive). Returns the restored hash.
+    pub fn rollback_composition(&mut self, store_name: &str) -> KernelResult<String> {
+        let (entry, restored) = self.shims.rollback_composition(store_name)?;
+        self.wal.append(&entry)?;
+        Ok(restored)
+    }
+
+    /// Serve `composition_bytes` to `fraction` of the store's traffic
+    /// alongside the current composition.
+    pub fn start_shim_canary(
+        &mut self,
+        store_name: &str,
+        composition_bytes: Vec<u8>,
+        fraction: f32,
+    ) -> KernelResult<()> {
+        let entry = self.shims.start_canary(store_name, composition_bytes, fraction)?;
+        self.wal.append(&entry)?;
+        Ok(())
+    }
+
+    /// Make the running canary the store's composition. The update and
+    /// the canary's end commit as one WAL batch. The composition is
+    /// written first, so if that fails the canary keeps running.
+    pub fn promote_shim_canary(&mut self, store_name: &str) -> KernelResult<String> {
+        let canary = self.shims.canary_for(store_name).cloned().ok_or_else(|| {
+            KernelError::InvalidData(format!("shim_store `{store_name}` has no running canary"))
+        })?;
+        let update = self
+            .shims
+            .update_composition(store_name, canary.composition_bytes)?;
+        let end = match self.shims.end_canary(store_name) {
+            Ok((end, _)) => end,
+            Err(e) => {
+                // Promoted but still canarying: log what did happen.
+                self.wal.append(&update)?;
+                return Err(e);
+            }
+        };
+        self.wal.append_batch(&[update, end])?;
+        Ok(canary.composition_hash)
+    }
+
+    /// Stop the running canary, leaving the composition as it was.
+    pub fn abort_shim_canary(&mut self, store_name: &str) -> KernelResult<()> {
+        let (entry, _) = self.shims.end_canary(store_name)?;
+        self.wal.append(&entry)?;
+        Ok(())
+    }
+
+    /// Fold a batch of responses' shim outcomes into the store's runtime
+    /// stats. Advisory — written straight to `activation_state.json`, no WAL.
+    pub fn record_shim_activations(
+        &mut self,
+        store_name: &str,
+        observations: &[shim_stats::ShimObservation],
+    ) -> KernelResult<()> {
+        self.shims.record_activations(store_name, observations)
+    }
+
     /// Delete a shim_store entirely (directory + in-memory state).
     /// No undo. Used for orphan cleanup.
     pub fn delete_shim_store(&mut self, name: &str) -> KernelResult<()> {
@@ -421,6 +576,16 @@ impl Kernel {
     }
 }
 
+/// True if any null-separated field of `payload` equals one of `needles`.
+/// Every thread- or message-scoped WAL payload carries its IDs as whole
+/// fields, so exact field matching finds them without false positives
+/// on content that merely contains an ID as a substring.
+fn payload_mentions(payload: &[u8], needles: &[String]) -> bool {
+    payload
+        .split(|b| *b == 0)
+        .any(|field| needles.iter().any(|n| field == n.as_bytes()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -903,4 +1068,148 @@ mod tests {
         let kernel = Kernel::open(&data_dir).unwrap();
         assert!(!kernel.shim_store().exists("ephemeral"));
     }
+
+    #[test]
+    fn shim_canary_promotion_and_rollback_persist_through_restart() {
+        let dir = TempDir::new().unwrap();
+        let data_dir = dir.path().join("data");
+        let current = br#"{"v":1}"#.to_vec();
+        let candidate = br#"{"v":2}"#.to_vec();
+
+        {
+            let mut kernel = Kernel::open(&data_dir).unwrap();
+            kernel.create_shim_store("bob", vec![]).unwrap();
+            kernel.update_composition("bob", current.clone()).unwrap();
+            kernel.start_shim_canary("bob", candidate.clone(), 0.1).unwrap();
+            kernel.promote_shim_canary("bob").unwrap();
+        }
+        {
+            let mut kernel = Kernel::open(&data_dir).unwrap();
+            let store = kernel.shim_store();
+            assert!(store.canary_for("bob").is_none());
+            assert_eq!(store.composition_bytes_for("bob").unwrap(), &candidate[..]);
+            kernel.rollback_composition("bob").unwrap();
+        }
+
+        let kernel = Kernel::open(&data_dir).unwrap();
+        let store = kernel.shim_store();
+        assert_eq!(store.composition_bytes_for("bob").unwrap(), &current[..]);
+        assert_eq!(store.composition_history_for("bob").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn failed_canary_promotion_keeps_the_canary() {
+        let dir = TempDir::new().unwrap();
+        let data_dir = dir.path().join("data");
+        let candidate = br#"{"v":2}"#.to_vec();
+        let composition = data_dir.join("shim_stores/bob/composition.json");
+
+        {
+            let mut kernel = Kernel::open(&data_dir).unwrap();
+            kernel.create_shim_store("bob", vec![]).unwrap();
+            kernel.start_shim_canary("bob", candidate.clone(), 0.1).unwrap();
+            // A directory in its place makes the composition write fail.
+            std::fs::remove_file(&composition).unwrap();
+            std::fs::create_dir_all(composition.join("blocker")).unwrap();
+            assert!(kernel.promote_shim_canary("bob").is_err());
+            assert_eq!(kernel.shim_store().canary_for("bob").unwrap().composition_bytes, candidate);
+            assert!(data_dir.join("shim_stores/bob/canary.json").exists());
+        }
+        std::fs::remove_dir_all(&composition).unwrap();
+        std::fs::write(&composition, b"{}").unwrap();
+
+        let mut kernel = Kernel::open(&data_dir).unwrap();
+        assert!(kernel.shim_store().canary_for("bob").is_some());
+        kernel.promote_shim_canary("bob").unwrap();
+        assert!(kernel.shim_store().canary_for("bob").is_none());
+        assert_eq!(kernel.shim_store().composition_bytes_for("bob").unwrap(), &candidate[..]);
+    }
+
+    #[test]
+    fn erase_thread_scrubs_state_and_wal() {
+        let dir = TempDir::new().unwrap();
+        let data_dir = dir.path().join("data");
+        let secret = b"my card number is 4111".to_vec();
+
+        {
+            let mut kernel = Kernel::open(&data_dir).unwrap();
+            kernel.initialize_root("agentos", "default").unwrap();
+            kernel
+                .register_platform_thread("inst-erase", "bob", "default")
+                .unwrap();
+            kernel
+                .register_platform_thread("inst-keep", "carol", "default")
+                .unwrap();
+            let seg = context_store::ContextSegment {
+                id: "msg-001".into(),
+                tag: "message".into(),
+                content: secret.clone(),
+                status: context_store::SegmentStatus::Active,
+                relevance: 0.5,
+                created_at: 1,
+                fold_ref: None,
+            };
+            kernel
+                .wal
+                .append(&ContextStore::wal_entry_segment_add("inst-erase", &seg))
+                .unwrap();
+            kernel.contexts_mut().add_segment("inst-erase", seg).unwrap();
+            kernel
+                .dispatch_message("platform", "bob", "inst-erase", "m-erase")
+                .unwrap();
+
+            // Create + allocate, the segment, and the dispatch's three
+            // entries; the erase batch itself isn't counted.
+            let scrubbed = kernel.erase_thread("inst-erase").unwrap();
+            assert_eq!(scrubbed, 6);
+            assert!(kernel.threads().lookup("inst-erase").is_none());
+            assert!(!kernel.contexts().exists("inst-erase"));
+            assert!(kernel.journal().get("m-erase").is_none());
+            assert!(kernel.threads().lookup("inst-keep").is_some());
+        }
+
+        // The content is gone from the log itself, not just from memory.
+        let raw = std::fs::read(data_dir.join("kernel.wal")).unwrap();
+        assert!(!raw.windows(secret.len()).any(|w| w == secret.as_slice()));
+
+        // And replay doesn't resurrect anything.
+        let kernel = Kernel::open(&data_dir).unwrap();
+        assert!(kernel.threads().lookup("inst-erase").is_none());
+        assert!(!kernel.contexts().exists("inst-erase"));
+        assert!(kernel.journal().get("m-erase").is_none());
+        assert!(kernel.threads().lookup("inst-keep").is_some());
+        assert!(kernel.contexts().exists("inst-keep"));
+    }
+
+    #[test]
+    fn erase_threads_takes_an_evicted_instance_and_its_buffers_in_one_rewrite() {
+        let dir = TempDir::new().unwrap();
+        let data_dir = dir.path().join("data");
+        let mut kernel = Kernel::open(&data_dir).unwrap();
+        kernel.initialize_root("agentos", "default").unwrap();
+        kernel.register_platform_thread("inst-000001", "bob", "default").unwrap();
+        for thread_id in ["inst-000001/buf-default", "inst-000001/buf-dm", "inst-0000010/buf-default"] {
+            kernel.wal.append(&ContextStore::wal_entry_create(thread_id)).unwrap();
+            kernel.contexts_mut().create(thread_id).unwrap();
+        }
+        kernel
+            .dispatch_message("platform", "bob", "inst-000001/buf-dm", "m-dm")
+            .unwrap();
+        kernel.evict_platform_thread("inst-000001").unwrap();
+
+        let threads = kernel.thread_ids_under("inst-000001");
+        assert_eq!(
+            threads,
+            ["inst-000001", "inst-000001/buf-default", "inst-000001/buf-dm"]
+        );
+        // Register (2), two buffer contexts, the dispatch (3), evict (2).
+        assert_eq!(kernel.erase_threads(&threads).unwrap(), 9);
+        assert!(!kernel.contexts().exists("inst-000001/buf-dm"));
+        assert!(kernel.journal().get("m-dm").is_none());
+        assert!(kernel.contexts().exists("inst-0000010/buf-default"));
+        assert_eq!(kernel.erase_threads(&[]).unwrap(), 0);
+
+        let kernel = Kernel::open(&data_dir).unwrap();
+        assert_eq!(kernel.thread_ids_under("inst-000001"), ["inst-000001"]);
+    }
 }
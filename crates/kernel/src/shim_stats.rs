@@ -0,0 +1,232 @@
+//! Shim runtime stats — the contents of a store's `activation_state.json`.
+//!
+//! Every LLM response served with a store's composition reports what its
+//! shims did (cortex's `ShimMetadata`: gate decisions, active steers,
+//! silence, signals, latencies). [`ActivationState::record`] folds one
+//! such [`ShimObservation`] into running totals per shim and per
+//! composition hash, so the shim-expert can see how often a gate fires
+//! in production and compare a canary against the composition it would
+//! replace.
+//!
+//! Like the rest of the shim store the kernel doesn't depend on
+//! `agentos-llm`; callers convert `ShimMetadata` into a
+//! [`ShimObservation`]. Stats are advisory: the file is rewritten
+//! atomically on every update but not WAL-logged, so a crash loses at
+//! most the update in flight.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+/// Schema version written to `activation_state.json`. Version 1 was the
+/// empty `{}` stub, which still parses (as empty stats).
+pub const ACTIVATION_SCHEMA_VERSION: u32 = 2;
+
+/// A gate decision above this counts as the gate firing (the sigmoid
+/// midpoint for scalar gates; any class but 0 for category gates).
+pub const GATE_FIRE_THRESHOLD: f32 = 0.5;
+
+/// What the shims did on one LLM response.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShimObservation {
+    /// Content hash of the composition the request carried.
+    pub composition_hash: String,
+    pub gate_decisions: HashMap<String, f32>,
+    pub active_steers: Vec<String>,
+    pub silent: bool,
+    pub signals: Vec<String>,
+    pub prefill_ms: Option<u64>,
+    pub generation_ms: Option<u64>,
+}
+
+/// Summed latencies over the responses that reported any.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub samples: u64,
+    pub prefill_ms_total: u64,
+    pub generation_ms_total: u64,
+}
+
+impl LatencyStats {
+    fn record(&mut self, obs: &ShimObservation) {
+        if obs.prefill_ms.is_none() && obs.generation_ms.is_none() {
+            return;
+        }
+        self.samples += 1;
+        self.prefill_ms_total += obs.prefill_ms.unwrap_or(0);
+        self.generation_ms_total += obs.generation_ms.unwrap_or(0);
+    }
+
+    pub fn mean_prefill_ms(&self) -> Option<f64> {
+        ratio(self.prefill_ms_total as f64, self.samples)
+    }
+
+    pub fn mean_generation_ms(&self) -> Option<f64> {
+        ratio(self.generation_ms_total as f64, self.samples)
+    }
+}
+
+/// Running totals for one shim.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ShimStats {
+    /// Responses the shim took part in, as an evaluated gate or an
+    /// active steer.
+    pub responses: u64,
+    pub gate_evaluations: u64,
+    /// Gate evaluations above [`GATE_FIRE_THRESHOLD`].
+    pub gate_fires: u64,
+    pub decision_total: f64,
+    pub steer_activations: u64,
+    pub latency: LatencyStats,
+}
+
+impl ShimStats {
+    /// Share of gate evaluations that fired.
+    pub fn fire_rate(&self) -> Option<f64> {
+        ratio(self.gate_fires as f64, self.gate_evaluations)
+    }
+
+    pub fn mean_decision(&self) -> Option<f64> {
+        ratio(self.decision_total, self.gate_evaluations)
+    }
+}
+
+/// Running totals for one composition, keyed by its content hash.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CompositionStats {
+    pub responses: u64,
+    pub silent: u64,
+    /// Responses carrying each rule signal.
+    pub signals: BTreeMap<String, u64>,
+    /// Responses on which each gate fired.
+    pub gate_fires: BTreeMap<String, u64>,
+    pub latency: LatencyStats,
+}
+
+impl CompositionStats {
+    pub fn silence_rate(&self) -> Option<f64> {
+        ratio(self.silent as f64, self.responses)
+    }
+}
+
+/// Everything in `activation_state.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActivationState {
+    pub schema_version: u32,
+    pub shims: BTreeMap<String, ShimStats>,
+    pub compositions: BTreeMap<String, CompositionStats>,
+}
+
+impl Default for ActivationState {
+    fn default() -> Self {
+        Self {
+            schema_version: ACTIVATION_SCHEMA_VERSION,
+            shims: BTreeMap::new(),
+            compositions: BTreeMap::new(),
+        }
+    }
+}
+
+impl ActivationState {
+    /// Parse the on-disk file. Unreadable stats are reset rather than
+    /// failing the store load.
+    pub fn from_json(bytes: &[u8]) -> Self {
+        let mut state: Self = serde_json::from_slice(bytes).unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "activation_state.json unparseable; resetting stats");
+            Self::default()
+        });
+        state.schema_version = ACTIVATION_SCHEMA_VERSION;
+        state
+    }
+
+    /// Fold one response's shim outcomes into the totals.
+    pub fn record(&mut self, obs: &ShimObservation) {
+        let composition = self.compositions.entry(obs.composition_hash.clone()).or_default();
+        composition.responses += 1;
+        composition.silent += u64::from(obs.silent);
+        for signal in &obs.signals {
+            *composition.signals.entry(signal.clone()).or_default() += 1;
+        }
+        composition.latency.record(obs);
+
+        for (id, &decision) in &obs.gate_decisions {
+            let fired = decision > GATE_FIRE_THRESHOLD;
+            if fired {
+                *composition.gate_fires.entry(id.clone()).or_default() += 1;
+            }
+            let shim = self.shims.entry(id.clone()).or_default();
+            shim.gate_evaluations += 1;
+            shim.gate_fires += u64::from(fired);
+            shim.decision_total += f64::from(decision);
+        }
+        for id in &obs.active_steers {
+            self.shims.entry(id.clone()).or_default().steer_activations += 1;
+        }
+
+        let involved = obs
+            .gate_decisions
+            .keys()
+            .chain(obs.active_steers.iter().filter(|id| !obs.gate_decisions.contains_key(*id)));
+        for id in involved {
+            let shim = self.shims.entry(id.clone()).or_default();
+            shim.responses += 1;
+            shim.latency.record(obs);
+        }
+    }
+}
+
+fn ratio(num: f64, den: u64) -> Option<f64> {
+    (den > 0).then(|| num / den as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn obs(hash: &str, gate: f32, steers: &[&str], silent: bool) -> ShimObservation {
+        ShimObservation {
+            composition_hash: hash.into(),
+            gate_decisions: HashMap::from([("should_respond".to_string(), gate)]),
+            active_steers: steers.iter().map(|s| s.to_string()).collect(),
+            silent,
+            signals: if silent { vec!["quiet".into()] } else { Vec::new() },
+            prefill_ms: Some(10),
+            generation_ms: Some(90),
+        }
+    }
+
+    #[test]
+    fn records_per_shim_and_per_composition() {
+        let mut state = ActivationState::default();
+        state.record(&obs("a", 0.9, &["polite"], false));
+        state.record(&obs("a", 0.1, &[], true));
+        state.record(&obs("b", 0.7, &["polite"], false));
+
+        let gate = &state.shims["should_respond"];
+        assert_eq!((gate.responses, gate.gate_evaluations, gate.gate_fires), (3, 3, 2));
+        assert!((gate.mean_decision().unwrap() - 1.7 / 3.0).abs() < 1e-6);
+        assert_eq!(gate.latency.mean_generation_ms(), Some(90.0));
+        assert_eq!(state.shims["polite"].steer_activations, 2);
+        assert_eq!(state.shims["polite"].fire_rate(), None);
+
+        let a = &state.compositions["a"];
+        assert_eq!((a.responses, a.silent), (2, 1));
+        assert_eq!(a.silence_rate(), Some(0.5));
+        assert_eq!(a.signals["quiet"], 1);
+        assert_eq!(a.gate_fires["should_respond"], 1);
+        assert_eq!(state.compositions["b"].responses, 1);
+    }
+
+    #[test]
+    fn v1_stub_and_garbage_parse_as_empty() {
+        let state = ActivationState::from_json(b"{}");
+        assert_eq!(state, ActivationState::default());
+        assert_eq!(ActivationState::from_json(b"not json"), ActivationState::default());
+
+        let mut state = ActivationState::default();
+        state.record(&obs("a", 0.9, &[], false));
+        let json = serde_json::to_vec(&state).unwrap();
+        assert_eq!(ActivationState::from_json(&json), state);
+    }
+}
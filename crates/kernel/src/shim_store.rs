@@ -13,7 +13,10 @@
 //! <base_dir>/<store_name>/
 //! ├── manifest.json         (ShimStoreManifest)
 //! ├── composition.json      (raw bytes — kernel doesn't interpret schema)
-//! ├── activation_state.json (per-shim runtime stats; v1 stub for v3+ aging)
+//! ├── activation_state.json (per-shim runtime stats; see `shim_stats`)
+//! ├── canary.json           (running canary: candidate hash + traffic share)
+//! ├── compositions/         (every composition ever written, by content hash)
+//! │   └── <content_hash>.json
 //! ├── shims/
 //! │   ├── <shim_id>.onnx
 //! │   └── <shim_id>.manifest.json
@@ -31,6 +34,18 @@
 //! crash after WAL but before in-memory apply = replay reconstructs from
 //! WAL + disk, verifying `content_hash` (mismatch = log + drop).
 //!
+//! ## Composition history, rollback and canaries
+//!
+//! Each composition written is also archived under `compositions/` by
+//! its content hash, and the sequence of hashes the WAL records
+//! (`CompositionUpdate` pushes, `CompositionRollback` pops) is the
+//! store's composition history. `rollback_composition` restores the
+//! previous entry from the archive in one step; repeated rollbacks walk
+//! further back. A canary (`start_canary`) parks a candidate composition
+//! next to the current one with the share of traffic it should get;
+//! agents split threads between the two (`ShimCanary::serves_candidate`)
+//! until the canary is promoted or aborted.
+//!
 //! ## Schema agnosticism
 //!
 //! The kernel stores shim manifests and composition bytes as opaque
@@ -49,6 +64,7 @@ use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
 use crate::error::{KernelError, KernelResult};
+use crate::shim_stats::{ActivationState, ShimObservation};
 use crate::wal::{EntryType, WalEntry};
 
 /// Schema version for the on-disk shim_store format.
@@ -90,6 +106,37 @@ pub struct ShimRecord {
     pub content_hash: String,
 }
 
+/// A candidate composition taking a share of an agent's traffic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShimCanary {
+    /// Content hash of the candidate composition.
+    pub composition_hash: String,
+    /// Raw bytes of the candidate composition.
+    pub composition_bytes: Vec<u8>,
+    /// Share of threads (0–1] served by the candidate.
+    pub fraction: f32,
+}
+
+impl ShimCanary {
+    /// Whether the thread (or other routing key) `key` gets the
+    /// candidate. Keys hash to a stable bucket, so a conversation stays
+    /// on one composition for its whole life.
+    pub fn serves_candidate(&self, key: &str) -> bool {
+        // FNV-1a: stable across processes and releases, unlike std's hasher.
+        let hash = key.bytes().fold(0xcbf2_9ce4_8422_2325u64, |h, b| {
+            (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+        });
+        ((hash % 10_000) as f32) < self.fraction * 10_000.0
+    }
+}
+
+/// On-disk form of `canary.json`; the bytes live in the archive.
+#[derive(Serialize, Deserialize)]
+struct CanaryFile {
+    composition_hash: String,
+    fraction: f32,
+}
+
 /// In-memory state for one shim_store.
 #[derive(Debug, Clone)]
 pub struct ShimStoreState {
@@ -98,6 +145,13 @@ pub struct ShimStoreState {
     pub composition_bytes: Vec<u8>,
     /// Active (non-retired) shims, keyed by shim id.
     pub shims: HashMap<String, ShimRecord>,
+    /// Content hashes of the compositions this store has run, oldest
+    /// first, as recorded in the WAL. The last is the current one.
+    pub composition_history: Vec<String>,
+    /// Running canary, if any.
+    pub canary: Option<ShimCanary>,
+    /// Runtime stats from `activation_state.json`.
+    pub activation: ActivationState,
 }
 
 /// Fourth pillar: cognitive substrate management.
@@ -152,6 +206,53 @@ impl ShimStore {
         self.stores.get(name).map(|s| s.composition_bytes.as_slice())
     }
 
+    /// SHA-256 hex of the current composition — the key its runtime
+    /// stats are recorded under.
+    pub fn composition_hash_for(&self, name: &str) -> Option<String> {
+        self.stores.get(name).map(|s| hash_bytes(&s.composition_bytes))
+    }
+
+    pub fn composition_history_for(&self, name: &str) -> Option<&[String]> {
+        self.stores.get(name).map(|s| s.composition_history.as_slice())
+    }
+
+    pub fn canary_for(&self, name: &str) -> Option<&ShimCanary> {
+        self.stores.get(name).and_then(|s| s.canary.as_ref())
+    }
+
+    pub fn activation_for(&self, name: &str) -> Option<&ActivationState> {
+        self.stores.get(name).map(|s| &s.activation)
+    }
+
+    /// Read an archived composition, verifying its hash. The default
+    /// `{}` every store starts from needs no archive entry.
+    pub fn archived_composition(&self, name: &str, content_hash: &str) -> KernelResult<Vec<u8>> {
+        validate_name("shim_store name", name)?;
+        if !content_hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(KernelError::InvalidData(format!("bad composition hash `{content_hash}`")));
+        }
+        let path = self
+            .base_dir
+            .join(name)
+            .join("compositions")
+            .join(format!("{content_hash}.json"));
+        let bytes = match fs::read(&path) {
+            Ok(b) => b,
+            Err(_) if content_hash == hash_bytes(EMPTY_COMPOSITION) => EMPTY_COMPOSITION.to_vec(),
+            Err(e) => {
+                return Err(KernelError::InvalidData(format!(
+                    "composition {content_hash} of `{name}` is not archived: {e}"
+                )))
+            }
+        };
+        if hash_bytes(&bytes) != content_hash {
+            return Err(KernelError::InvalidData(format!(
+                "archived composition {content_hash} of `{name}` fails its hash check"
+            )));
+        }
+        Ok(bytes)
+    }
+
     pub fn manifest_for(&self, name: &str) -> Option<&ShimStoreManifest> {
         self.stores.get(name).map(|s| &s.manifest)
     }
@@ -173,6 +274,9 @@ impl ShimStore {
             EntryType::ShimRetire => self.replay_retire(&entry.payload),
             EntryType::ShimStoreDelete => self.replay_delete(&entry.payload),
             EntryType::CompositionUpdate => self.replay_composition(&entry.payload),
+            EntryType::CompositionRollback => self.replay_rollback(&entry.payload),
+            EntryType::CanaryStart => self.replay_canary_start(&entry.payload),
+            EntryType::CanaryEnd => self.replay_canary_end(&entry.payload),
             _ => {}
         }
     }
@@ -195,13 +299,24 @@ impl ShimStore {
             }
         };
         // Re-load from disk if files exist (the boot scan in `open` may
-        // have already loaded this; idempotent).
+        // have already loaded this; idempotent). History only lives in
+        // the WAL, so keep what earlier entries built; a new store's
+        // history starts at the default composition.
         let dir = self.base_dir.join(&name);
-        let state = load_store_state(&dir).unwrap_or(ShimStoreState {
+        let mut state = load_store_state(&dir).unwrap_or(ShimStoreState {
             manifest: manifest.clone(),
-            composition_bytes: b"{}".to_vec(),
+            composition_bytes: EMPTY_COMPOSITION.to_vec(),
             shims: HashMap::new(),
+            composition_history: Vec::new(),
+            canary: None,
+            activation: ActivationState::default(),
         });
+        state.composition_history = self
+            .stores
+            .get(&name)
+            .map(|s| s.composition_history.clone())
+            .filter(|h| !h.is_empty())
+            .unwrap_or_else(|| vec![hash_bytes(EMPTY_COMPOSITION)]);
         self.stores.insert(name, state);
     }
 
@@ -299,23 +414,77 @@ impl ShimStore {
     }
 
     fn replay_composition(&mut self, payload: &[u8]) {
-        let mut parts = payload.splitn(2, |b| *b == 0);
-        let store_name = match parts.next().and_then(|b| std::str::from_utf8(b).ok()) {
-            Some(s) => s.to_string(),
-            None => return,
+        let Some((store_name, expected_hash)) = split_pair(payload) else {
+            return;
         };
-        let expected_hash = match parts.next().and_then(|b| std::str::from_utf8(b).ok()) {
-            Some(s) => s.to_string(),
-            None => return,
+        if let Some(state) = self.stores.get_mut(&store_name) {
+            state.composition_history.push(expected_hash.clone());
+        }
+        self.reload_composition(&store_name, &expected_hash, "CompositionUpdate");
+    }
+
+    fn replay_rollback(&mut self, payload: &[u8]) {
+        let Some((store_name, restored_hash)) = split_pair(payload) else {
+            return;
+        };
+        if let Some(state) = self.stores.get_mut(&store_name) {
+            let history = &mut state.composition_history;
+            let earlier = history.len().saturating_sub(1);
+            match history[..earlier].iter().rposition(|h| *h == restored_hash) {
+                Some(at) => history.truncate(at + 1),
+                None => tracing::warn!(
+                    store = %store_name,
+                    hash = %restored_hash,
+                    "CompositionRollback replay: target not in history"
+                ),
+            }
+        }
+        self.reload_composition(&store_name, &restored_hash, "CompositionRollback");
+    }
+
+    fn replay_canary_start(&mut self, payload: &[u8]) {
+        let mut parts = payload.splitn(3, |b| *b == 0).map(std::str::from_utf8);
+        let (Some(Ok(store_name)), Some(Ok(hash)), Some(Ok(fraction))) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return;
+        };
+        let Ok(fraction) = fraction.parse::<f32>() else {
+            return;
+        };
+        let bytes = match self.archived_composition(store_name, hash) {
+            Ok(b) => b,
+            Err(e) => {
+                tracing::warn!(store = %store_name, error = %e, "CanaryStart replay: dropping");
+                return;
+            }
         };
-        let composition_path = self.base_dir.join(&store_name).join("composition.json");
+        if let Some(state) = self.stores.get_mut(store_name) {
+            state.canary = Some(ShimCanary {
+                composition_hash: hash.to_string(),
+                composition_bytes: bytes,
+                fraction,
+            });
+        }
+    }
+
+    fn replay_canary_end(&mut self, payload: &[u8]) {
+        if let Some(state) = std::str::from_utf8(payload).ok().and_then(|n| self.stores.get_mut(n)) {
+            state.canary = None;
+        }
+    }
+
+    /// Adopt `composition.json` as the store's composition if it hashes
+    /// to what the WAL entry committed.
+    fn reload_composition(&mut self, store_name: &str, expected_hash: &str, entry: &str) {
+        let composition_path = self.base_dir.join(store_name).join("composition.json");
         let bytes = match fs::read(&composition_path) {
             Ok(b) => b,
             Err(e) => {
                 tracing::warn!(
                     store = %store_name,
                     error = %e,
-                    "CompositionUpdate replay: composition.json unreadable"
+                    "{entry} replay: composition.json unreadable"
                 );
                 return;
             }
@@ -326,11 +495,11 @@ impl ShimStore {
                 store = %store_name,
                 expected = %expected_hash,
                 actual = %actual_hash,
-                "CompositionUpdate replay: hash mismatch; dropping"
+                "{entry} replay: hash mismatch; dropping"
             );
             return;
         }
-        if let Some(state) = self.stores.get_mut(&store_name) {
+        if let Some(state) = self.stores.get_mut(store_name) {
             state.composition_bytes = bytes;
         }
     }
@@ -366,20 +535,24 @@ impl ShimStore {
 
         let composition_path = dir.join("composition.json");
         if !composition_path.exists() {
-            write_atomic(&composition_path, b"{}")?;
+            write_atomic(&composition_path, EMPTY_COMPOSITION)?;
         }
         let activation_path = dir.join("activation_state.json");
         if !activation_path.exists() {
             write_atomic(&activation_path, b"{}")?;
         }
 
-        let composition_bytes = fs::read(&composition_path).unwrap_or_else(|_| b"{}".to_vec());
+        let composition_bytes =
+            fs::read(&composition_path).unwrap_or_else(|_| EMPTY_COMPOSITION.to_vec());
         self.stores
             .entry(name.to_string())
             .or_insert_with(|| ShimStoreState {
                 manifest: manifest.clone(),
                 composition_bytes,
                 shims: HashMap::new(),
+                composition_history: vec![hash_bytes(EMPTY_COMPOSITION)],
+                canary: None,
+                activation: ActivationState::default(),
             });
 
         let mut payload = Vec::with_capacity(name.len() + 1 + manifest_bytes.len());
@@ -486,18 +659,127 @@ impl ShimStore {
                 "shim_store `{store_name}` does not exist"
             )));
         }
-        let composition_path = self.base_dir.join(store_name).join("composition.json");
+        let store_dir = self.base_dir.join(store_name);
         let content_hash = hash_bytes(&composition_bytes);
-        write_atomic(&composition_path, &composition_bytes)?;
+        archive_composition(&store_dir, &content_hash, &composition_bytes)?;
+        write_atomic(&store_dir.join("composition.json"), &composition_bytes)?;
         if let Some(state) = self.stores.get_mut(store_name) {
             state.composition_bytes = composition_bytes;
+            state.composition_history.push(content_hash.clone());
         }
 
-        let mut payload = Vec::with_capacity(store_name.len() + content_hash.len() + 1);
-        payload.extend_from_slice(store_name.as_bytes());
+        Ok(WalEntry::new(
+            EntryType::CompositionUpdate,
+            join_pair(store_name, &content_hash),
+        ))
+    }
+
+    /// Restore the composition before the current one in the store's
+    /// history. Returns the WAL entry and the restored hash; calling it
+    /// again walks further back.
+    pub fn rollback_composition(&mut self, store_name: &str) -> KernelResult<(WalEntry, String)> {
+        validate_name("shim_store name", store_name)?;
+        let history = self.composition_history_for(store_name).ok_or_else(|| {
+            KernelError::InvalidData(format!("shim_store `{store_name}` does not exist"))
+        })?;
+        let Some(target) = history.len().checked_sub(2).map(|i| history[i].clone()) else {
+            return Err(KernelError::InvalidData(format!(
+                "shim_store `{store_name}` has no earlier composition to roll back to"
+            )));
+        };
+        let bytes = self.archived_composition(store_name, &target)?;
+        write_atomic(
+            &self.base_dir.join(store_name).join("composition.json"),
+            &bytes,
+        )?;
+        if let Some(state) = self.stores.get_mut(store_name) {
+            state.composition_history.pop();
+            state.composition_bytes = bytes;
+        }
+        let entry = WalEntry::new(EntryType::CompositionRollback, join_pair(store_name, &target));
+        Ok((entry, target))
+    }
+
+    /// Start canarying `composition_bytes` on `fraction` of traffic,
+    /// replacing any running canary.
+    pub fn start_canary(
+        &mut self,
+        store_name: &str,
+        composition_bytes: Vec<u8>,
+        fraction: f32,
+    ) -> KernelResult<WalEntry> {
+        validate_name("shim_store name", store_name)?;
+        if !(fraction > 0.0 && fraction <= 1.0) {
+            return Err(KernelError::InvalidData(format!(
+                "canary fraction must be in (0, 1], got {fraction}"
+            )));
+        }
+        if !self.stores.contains_key(store_name) {
+            return Err(KernelError::InvalidData(format!(
+                "shim_store `{store_name}` does not exist"
+            )));
+        }
+        let store_dir = self.base_dir.join(store_name);
+        let content_hash = hash_bytes(&composition_bytes);
+        archive_composition(&store_dir, &content_hash, &composition_bytes)?;
+        let file = CanaryFile {
+            composition_hash: content_hash.clone(),
+            fraction,
+        };
+        let file_bytes = serde_json::to_vec_pretty(&file)
+            .map_err(|e| KernelError::InvalidData(format!("serialize canary: {e}")))?;
+        write_atomic(&store_dir.join("canary.json"), &file_bytes)?;
+        if let Some(state) = self.stores.get_mut(store_name) {
+            state.canary = Some(ShimCanary {
+                composition_hash: content_hash.clone(),
+                composition_bytes,
+                fraction,
+            });
+        }
+
+        let mut payload = join_pair(store_name, &content_hash);
         payload.push(0);
-        payload.extend_from_slice(content_hash.as_bytes());
-        Ok(WalEntry::new(EntryType::CompositionUpdate, payload))
+        payload.extend_from_slice(fraction.to_string().as_bytes());
+        Ok(WalEntry::new(EntryType::CanaryStart, payload))
+    }
+
+    /// Stop the running canary. Returns the WAL entry and the canary;
+    /// promoting it is the caller's `update_composition` with its bytes.
+    pub fn end_canary(&mut self, store_name: &str) -> KernelResult<(WalEntry, ShimCanary)> {
+        validate_name("shim_store name", store_name)?;
+        if self.canary_for(store_name).is_none() {
+            return Err(KernelError::InvalidData(format!(
+                "shim_store `{store_name}` has no running canary"
+            )));
+        }
+        let path = self.base_dir.join(store_name).join("canary.json");
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        let canary = self
+            .stores
+            .get_mut(store_name)
+            .and_then(|s| s.canary.take())
+            .expect("checked above");
+        let entry = WalEntry::new(EntryType::CanaryEnd, store_name.as_bytes().to_vec());
+        Ok((entry, canary))
+    }
+
+    /// Fold a batch of responses' shim outcomes into the store's runtime
+    /// stats and rewrite `activation_state.json` once. Not WAL-logged.
+    pub fn record_activations(&mut self, store_name: &str, batch: &[ShimObservation]) -> KernelResult<()> {
+        let state = self.stores.get_mut(store_name).ok_or_else(|| {
+            KernelError::InvalidData(format!("shim_store `{store_name}` does not exist"))
+        })?;
+        for obs in batch {
+            state.activation.record(obs);
+        }
+        let bytes = serde_json::to_vec_pretty(&state.activation)
+            .map_err(|e| KernelError::InvalidData(format!("serialize activation state: {e}")))?;
+        write_atomic(
+            &self.base_dir.join(store_name).join("activation_state.json"),
+            &bytes,
+        )
     }
 
     pub fn delete_store(&mut self, name: &str) -> KernelResult<WalEntry> {
@@ -516,6 +798,37 @@ impl ShimStore {
 
 // ── Helpers ──
 
+/// The composition every store starts with.
+const EMPTY_COMPOSITION: &[u8] = b"{}";
+
+/// `a\0b` WAL payload.
+fn join_pair(a: &str, b: &str) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(a.len() + b.len() + 1);
+    payload.extend_from_slice(a.as_bytes());
+    payload.push(0);
+    payload.extend_from_slice(b.as_bytes());
+    payload
+}
+
+/// Inverse of [`join_pair`].
+fn split_pair(payload: &[u8]) -> Option<(String, String)> {
+    let mut parts = payload.splitn(2, |b| *b == 0);
+    let a = std::str::from_utf8(parts.next()?).ok()?;
+    let b = std::str::from_utf8(parts.next()?).ok()?;
+    Some((a.to_string(), b.to_string()))
+}
+
+/// Keep a copy of a composition under `compositions/<hash>.json` so it
+/// can be rolled back to or canaried. Content-addressed, so rewriting an
+/// existing entry is skipped.
+fn archive_composition(store_dir: &Path, content_hash: &str, bytes: &[u8]) -> KernelResult<()> {
+    let path = store_dir.join("compositions").join(format!("{content_hash}.json"));
+    if !path.exists() {
+        write_atomic(&path, bytes)?;
+    }
+    Ok(())
+}
+
 /// Validate a shim_store name or shim_id. Strict ASCII allowlist so
 /// these strings are safe to splice into filesystem paths without
 /// `..` traversal, drive-letter override (Windows `C:\Users\foo`), or
@@ -578,7 +891,22 @@ fn load_store_state(dir: &Path) -> Option<ShimStoreState> {
     let manifest_bytes = fs::read(dir.join("manifest.json")).ok()?;
     let manifest: ShimStoreManifest = serde_json::from_slice(&manifest_bytes).ok()?;
     let composition_bytes =
-        fs::read(dir.join("composition.json")).unwrap_or_else(|_| b"{}".to_vec());
+        fs::read(dir.join("composition.json")).unwrap_or_else(|_| EMPTY_COMPOSITION.to_vec());
+    let activation = fs::read(dir.join("activation_state.json"))
+        .map(|b| ActivationState::from_json(&b))
+        .unwrap_or_default();
+    let canary = fs::read(dir.join("canary.json"))
+        .ok()
+        .and_then(|b| serde_json::from_slice::<CanaryFile>(&b).ok())
+        .and_then(|c| {
+            let path = dir.join("compositions").join(format!("{}.json", c.composition_hash));
+            let bytes = fs::read(path).ok().filter(|b| hash_bytes(b) == c.composition_hash)?;
+            Some(ShimCanary {
+                composition_hash: c.composition_hash,
+                composition_bytes: bytes,
+                fraction: c.fraction,
+            })
+        });
 
     let mut shims = HashMap::new();
     let shims_dir = dir.join("shims");
@@ -617,6 +945,9 @@ fn load_store_state(dir: &Path) -> Option<ShimStoreState> {
         manifest,
         composition_bytes,
         shims,
+        composition_history: Vec::new(),
+        canary,
+        activation,
     })
 }
 
@@ -937,4 +1268,116 @@ mod tests {
             s.add_shim("bob", id, b"{}".to_vec(), vec![1]).unwrap();
         }
     }
+
+    /// Reopen the same directory and replay `entries`, as `Kernel::open` does.
+    fn reopen(dir: &TempDir, entries: &[WalEntry]) -> ShimStore {
+        let mut s = ShimStore::open(dir.path().to_path_buf()).unwrap();
+        for e in entries {
+            s.apply_wal_entry(e);
+        }
+        s
+    }
+
+    #[test]
+    fn rollback_walks_back_through_history_and_replays() {
+        let (dir, mut s) = fresh();
+        let mut wal = vec![s.create_store("bob", vec![]).unwrap()];
+        wal.push(s.update_composition("bob", br#"{"v":1}"#.to_vec()).unwrap());
+        wal.push(s.update_composition("bob", br#"{"v":2}"#.to_vec()).unwrap());
+        assert_eq!(s.composition_history_for("bob").unwrap().len(), 3);
+
+        let (entry, restored) = s.rollback_composition("bob").unwrap();
+        wal.push(entry);
+        assert_eq!(restored, hash_bytes(br#"{"v":1}"#));
+        assert_eq!(s.composition_bytes_for("bob").unwrap(), br#"{"v":1}"#);
+
+        let replayed = reopen(&dir, &wal);
+        assert_eq!(replayed.composition_bytes_for("bob").unwrap(), br#"{"v":1}"#);
+        assert_eq!(
+            replayed.composition_history_for("bob"),
+            s.composition_history_for("bob")
+        );
+
+        // Back to the default, then nothing left to undo.
+        s.rollback_composition("bob").unwrap();
+        assert_eq!(s.composition_bytes_for("bob").unwrap(), b"{}");
+        assert!(matches!(
+            s.rollback_composition("bob").unwrap_err(),
+            KernelError::InvalidData(_)
+        ));
+    }
+
+    #[test]
+    fn archived_composition_rejects_tampered_bytes() {
+        let (dir, mut s) = fresh();
+        s.create_store("bob", vec![]).unwrap();
+        s.update_composition("bob", br#"{"v":1}"#.to_vec()).unwrap();
+        s.update_composition("bob", br#"{"v":2}"#.to_vec()).unwrap();
+        let hash = hash_bytes(br#"{"v":1}"#);
+        fs::write(
+            dir.path().join("bob/compositions").join(format!("{hash}.json")),
+            b"tampered",
+        )
+        .unwrap();
+        assert!(s.rollback_composition("bob").is_err());
+        assert_eq!(s.composition_bytes_for("bob").unwrap(), br#"{"v":2}"#);
+    }
+
+    #[test]
+    fn canary_survives_reopen_until_ended() {
+        let (dir, mut s) = fresh();
+        let mut wal = vec![s.create_store("bob", vec![]).unwrap()];
+        assert!(s.start_canary("bob", b"{}".to_vec(), 1.5).is_err());
+        wal.push(s.start_canary("bob", br#"{"c":1}"#.to_vec(), 0.25).unwrap());
+
+        let replayed = reopen(&dir, &wal);
+        let canary = replayed.canary_for("bob").unwrap();
+        assert_eq!(canary.composition_bytes, br#"{"c":1}"#);
+        assert_eq!(canary.fraction, 0.25);
+
+        let (entry, ended) = s.end_canary("bob").unwrap();
+        wal.push(entry);
+        assert_eq!(&ended, canary);
+        assert!(s.canary_for("bob").is_none());
+        assert!(!dir.path().join("bob/canary.json").exists());
+        assert!(reopen(&dir, &wal).canary_for("bob").is_none());
+        assert!(s.end_canary("bob").is_err());
+    }
+
+    #[test]
+    fn canary_split_is_stable_and_roughly_proportional() {
+        let canary = ShimCanary {
+            composition_hash: String::new(),
+            composition_bytes: Vec::new(),
+            fraction: 0.2,
+        };
+        let served = (0..5000)
+            .filter(|i| canary.serves_candidate(&format!("thread-{i}")))
+            .count();
+        assert!((800..1200).contains(&served), "served {served}/5000");
+        assert_eq!(
+            canary.serves_candidate("thread-7"),
+            canary.serves_candidate("thread-7")
+        );
+        let all = ShimCanary { fraction: 1.0, ..canary };
+        assert!((0..100).all(|i| all.serves_candidate(&i.to_string())));
+    }
+
+    #[test]
+    fn record_activations_persists_to_activation_state() {
+        let (dir, mut s) = fresh();
+        s.create_store("bob", vec![]).unwrap();
+        let obs = ShimObservation {
+            composition_hash: s.composition_hash_for("bob").unwrap(),
+            gate_decisions: HashMap::from([("gate".to_string(), 0.8)]),
+            ..Default::default()
+        };
+        s.record_activations("bob", &[obs.clone(), obs.clone()]).unwrap();
+        assert!(s.record_activations("nobody", std::slice::from_ref(&obs)).is_err());
+
+        let replayed = ShimStore::open(dir.path().to_path_buf()).unwrap();
+        let stats = replayed.activation_for("bob").unwrap();
+        assert_eq!(stats.shims["gate"].gate_fires, 2);
+        assert_eq!(stats.compositions[&obs.composition_hash].responses, 2);
+    }
 }
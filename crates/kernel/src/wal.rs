@@ -12,6 +12,7 @@
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 use crc32fast::Hasher;
 
@@ -45,6 +46,7 @@ pub enum EntryType {
     JournalDispatched = 20,
     JournalDelivered = 21,
     JournalFailed = 22,
+    JournalErase = 23, // payload: thread_id
 
     // Shim store ops (fourth pillar — manages cognitive substrate per
     // project_shim_store_design.md). Files for ONNX blobs and JSON
@@ -56,6 +58,9 @@ pub enum EntryType {
     ShimRetire = 32,         // payload: store_name\0shim_id
     ShimStoreDelete = 33,    // payload: store_name
     CompositionUpdate = 34,  // payload: store_name\0content_hash
+    CompositionRollback = 35, // payload: store_name\0restored content_hash
+    CanaryStart = 36,        // payload: store_name\0content_hash\0fraction
+    CanaryEnd = 37,          // payload: store_name
 
     // Compound
     AtomicBatch = 50,
@@ -81,11 +86,15 @@ impl EntryType {
             20 => Some(Self::JournalDispatched),
             21 => Some(Self::JournalDelivered),
             22 => Some(Self::JournalFailed),
+            23 => Some(Self::JournalErase),
             30 => Some(Self::ShimStoreCreate),
             31 => Some(Self::ShimAdd),
             32 => Some(Self::ShimRetire),
             33 => Some(Self::ShimStoreDelete),
             34 => Some(Self::CompositionUpdate),
+            35 => Some(Self::CompositionRollback),
+            36 => Some(Self::CanaryStart),
+            37 => Some(Self::CanaryEnd),
             50 => Some(Self::AtomicBatch),
             _ => None,
         }
@@ -159,10 +168,7 @@ impl Wal {
 
     /// Append a single entry. Writes + fsync for durability.
     pub fn append(&mut self, entry: &WalEntry) -> KernelResult<()> {
-        let bytes = entry.to_bytes();
-        self.file.write_all(&bytes)?;
-        self.file.sync_data()?;
-        Ok(())
+        self.write_durable(&entry.to_bytes())
     }
 
     /// Append multiple entries atomically as a batch.
@@ -178,9 +184,16 @@ impl Wal {
         }
 
         let batch = WalEntry::new(EntryType::AtomicBatch, batch_payload);
-        let bytes = batch.to_bytes();
-        self.file.write_all(&bytes)?;
+        self.write_durable(&batch.to_bytes())
+    }
+
+    /// Write + fsync one encoded entry, timing it for the
+    /// `agentos_wal_append_*` metrics.
+    fn write_durable(&mut self, bytes: &[u8]) -> KernelResult<()> {
+        let started = Instant::now();
+        self.file.write_all(bytes)?;
         self.file.sync_data()?;
+        agentos_telemetry::record_wal_append(started.elapsed(), bytes.len());
         Ok(())
     }
 
@@ -299,6 +312,40 @@ impl Wal {
         Ok(())
     }
 
+    /// Rewrite the WAL so it contains exactly `entries`, in order.
+    ///
+    /// Used for erasure (GDPR delete): the caller replays, filters out
+    /// every entry that mentions the erased data, and hands back what's
+    /// left. The new log is written to a sibling temp file, fsynced, and
+    /// renamed over the old one, so a crash mid-rewrite leaves either
+    /// the old WAL or the new one — never a torn mix. Batches come back
+    /// from `replay` already flattened; they're rewritten as individual
+    /// entries since every one of them was committed.
+    pub fn rewrite(&mut self, entries: &[WalEntry]) -> KernelResult<()> {
+        let tmp_path = self.path.with_extension("wal.rewrite");
+        {
+            let mut tmp = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(true)
+                .open(&tmp_path)
+                .map_err(|e| KernelError::Wal(format!("failed to open WAL rewrite file: {e}")))?;
+            for entry in entries {
+                tmp.write_all(&entry.to_bytes())?;
+            }
+            tmp.sync_all()?;
+        }
+        std::fs::rename(&tmp_path, &self.path)
+            .map_err(|e| KernelError::Wal(format!("failed to swap rewritten WAL: {e}")))?;
+
+        self.file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| KernelError::Wal(format!("failed to reopen WAL after rewrite: {e}")))?;
+        Ok(())
+    }
+
     /// Current WAL file size in bytes.
     pub fn size(&self) -> KernelResult<u64> {
         let mut file = self.file.try_clone()?;
@@ -487,6 +534,38 @@ mod tests {
         assert_eq!(entries[2].entry_type, EntryType::JournalDelivered);
     }
 
+    #[test]
+    fn rewrite_replaces_contents() {
+        let dir = TempDir::new().unwrap();
+        let wal_path = dir.path().join("test.wal");
+
+        let mut wal = Wal::open(&wal_path).unwrap();
+        wal.append(&WalEntry::new(EntryType::ThreadCreate, b"keep".to_vec()))
+            .unwrap();
+        wal.append_batch(&[
+            WalEntry::new(EntryType::ContextAllocate, b"drop".to_vec()),
+            WalEntry::new(EntryType::JournalDispatched, b"keep-too".to_vec()),
+        ])
+        .unwrap();
+
+        let kept: Vec<WalEntry> = wal
+            .replay()
+            .unwrap()
+            .into_iter()
+            .filter(|e| e.payload != b"drop")
+            .collect();
+        wal.rewrite(&kept).unwrap();
+
+        // Appends still land after the rewrite.
+        wal.append(&WalEntry::new(EntryType::ThreadExtend, b"after".to_vec()))
+            .unwrap();
+
+        let entries = Wal::open(&wal_path).unwrap().replay().unwrap();
+        let payloads: Vec<&[u8]> = entries.iter().map(|e| e.payload.as_slice()).collect();
+        assert_eq!(payloads, vec![&b"keep"[..], &b"keep-too"[..], &b"after"[..]]);
+        assert!(!dir.path().join("test.wal.rewrite").exists());
+    }
+
     #[test]
     fn large_payload() {
         let dir = TempDir::new().unwrap();
@@ -31,4 +31,4 @@
 
 mod store;
 
-pub use store::{KvCacheStore, KvCacheEntry, KvCacheStats, StoreError};
+pub use store::{CacheFormat, KvCacheStore, KvCacheEntry, KvCacheStats, StoreError};
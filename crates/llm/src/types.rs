@@ -110,6 +110,30 @@ impl ShimAttachment {
             && self.inject_shims.is_empty()
             && self.shim_rules.is_empty()
     }
+
+    /// Evaluate `shim_rules` against gate decisions the way cortex does:
+    /// in declared order, first match wins, `else` matches anything.
+    /// `None` when no rule matches.
+    pub fn resolve_rules(
+        &self,
+        gate_decisions: &std::collections::HashMap<String, f32>,
+    ) -> Option<&ShimAction> {
+        self.shim_rules.iter().find_map(|rule| match rule {
+            ShimRule::If { condition, action } => {
+                condition.matches(gate_decisions).then_some(action)
+            }
+            ShimRule::Else { action } => Some(action),
+        })
+    }
+
+    /// Steers active during decode once `action` (if any) has been
+    /// applied: its `activate` list replaces the default set.
+    pub fn active_steers(&self, action: Option<&ShimAction>) -> Vec<String> {
+        match action {
+            Some(a) if !a.activate.is_empty() => a.activate.clone(),
+            _ => self.steer_shims.clone(),
+        }
+    }
 }
 
 /// One rule in the cortex shim dispatch table.
@@ -144,6 +168,14 @@ pub struct ShimCondition {
     pub gt: f32,
 }
 
+impl ShimCondition {
+    /// True when the gate fired and its decision exceeds `gt`. A gate
+    /// with no decision never matches.
+    pub fn matches(&self, gate_decisions: &std::collections::HashMap<String, f32>) -> bool {
+        gate_decisions.get(&self.gate).is_some_and(|v| *v > self.gt)
+    }
+}
+
 /// Action under `"then"` / `"else"`. Vocabulary is intentionally bounded:
 /// activate a steer set, route to silence, attach a free-form signal.
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
@@ -512,6 +544,38 @@ mod tests {
         assert_eq!(back.input_schema["type"], "object");
     }
 
+    #[test]
+    fn shim_rules_first_match_wins() {
+        let rule = |gate: &str, gt: f32, signal: &str| ShimRule::If {
+            condition: ShimCondition { gate: gate.into(), gt },
+            action: ShimAction { signal: Some(signal.into()), ..Default::default() },
+        };
+        let attachment = ShimAttachment {
+            steer_shims: vec!["default_tone".into()],
+            shim_rules: vec![
+                rule("should_respond", 0.9, "eager"),
+                rule("should_respond", 0.5, "respond"),
+                ShimRule::Else {
+                    action: ShimAction { silent: true, activate: vec!["quiet".into()], ..Default::default() },
+                },
+            ],
+            ..Default::default()
+        };
+
+        let decisions = |v: f32| std::collections::HashMap::from([("should_respond".to_string(), v)]);
+        let signal = |v: f32| attachment.resolve_rules(&decisions(v)).and_then(|a| a.signal.clone());
+        assert_eq!(signal(0.95).as_deref(), Some("eager"));
+        assert_eq!(signal(0.7).as_deref(), Some("respond"));
+        // Strictly greater than: 0.5 falls through to `else`.
+        let fallback = attachment.resolve_rules(&decisions(0.5)).unwrap();
+        assert!(fallback.silent);
+        assert_eq!(attachment.active_steers(Some(fallback)), vec!["quiet".to_string()]);
+        assert_eq!(attachment.active_steers(None), vec!["default_tone".to_string()]);
+
+        let no_else = ShimAttachment { shim_rules: vec![rule("other", 0.0, "x")], ..Default::default() };
+        assert!(no_else.resolve_rules(&decisions(1.0)).is_none());
+    }
+
     #[test]
     fn content_block_text_roundtrip() {
         let block = ContentBlock::Text { text: "Hello world".into() };
@@ -7,8 +7,9 @@ pub mod parser;
 pub mod profile;
 
 use std::collections::HashMap;
+use std::path::PathBuf;
 
-use agentos_events::{PermissionMap, ToolDefinition, WasmCapabilities};
+use agentos_events::{MountPolicies, MountPolicy, PermissionMap, ToolDefinition, WasmCapabilities};
 use profile::{DispatchTable, SecurityProfile};
 
 /// WASM tool configuration on a listener.
@@ -25,6 +26,19 @@ pub struct PythonToolConfig {
     pub source: String,
 }
 
+/// Trainer backend for the `shim-train` tool's listener (`trainer:` block).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShimTrainerConfig {
+    /// In-process Rust trainer.
+    Native,
+    /// The Python trainer script, run as a subprocess.
+    Python {
+        python: String,
+        /// Resolved against the organism base dir.
+        script_path: PathBuf,
+    },
+}
+
 /// Agent configuration block on a listener.
 #[derive(Debug, Clone)]
 pub struct AgentConfig {
@@ -48,6 +62,8 @@ pub struct AgentConfig {
     pub shim_store: Option<String>,
     /// Per-tool permission tiers. Unlisted tools default to Prompt.
     pub permissions: PermissionMap,
+    /// Drive policy for this agent, on top of its profile's.
+    pub mount: MountPolicy,
 }
 
 impl Default for AgentConfig {
@@ -60,6 +76,7 @@ impl Default for AgentConfig {
             model: None,
             shim_store: None,
             permissions: PermissionMap::new(),
+            mount: MountPolicy::default(),
         }
     }
 }
@@ -265,6 +282,8 @@ pub struct ListenerDef {
     /// Trigger config (present when handler == "trigger"). Makes this listener
     /// fire messages rather than handle them.
     pub trigger: Option<TriggerConfig>,
+    /// Shim trainer backend (on the `shim-train` listener). None = native.
+    pub shim_trainer: Option<ShimTrainerConfig>,
 }
 
 /// Result of a hot-reload diff.
@@ -312,6 +331,23 @@ pub enum KvStoreConfig {
     Disk(String),
 }
 
+/// A tree-sitter grammar the code index loads at runtime.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrammarDef {
+    /// Language name, as reported in codebase maps.
+    pub name: String,
+    /// File extensions (without the dot).
+    pub extensions: Vec<String>,
+    /// Compiled parser library.
+    pub library: PathBuf,
+    /// Exported language function; `None` means `tree_sitter_<name>`.
+    pub symbol: Option<String>,
+    /// Symbol extraction query.
+    pub symbol_query: String,
+    /// Reference extraction query, if any.
+    pub reference_query: Option<String>,
+}
+
 /// The organism: single source of truth for configuration.
 #[derive(Debug, Clone)]
 pub struct Organism {
@@ -323,6 +359,10 @@ pub struct Organism {
     pub onboarding: Vec<OnboardingStep>,
     /// KV store configuration.
     pub kv_store: KvStoreConfig,
+    /// Runtime grammars for the code index.
+    grammars: Vec<GrammarDef>,
+    /// Directory runtime grammar libraries must live in.
+    pub grammar_dir: PathBuf,
 }
 
 impl Organism {
@@ -335,6 +375,8 @@ impl Organism {
             prompts: HashMap::new(),
             onboarding: Vec::new(),
             kv_store: KvStoreConfig::None,
+            grammars: Vec::new(),
+            grammar_dir: PathBuf::from("grammars"),
         }
     }
 
@@ -425,6 +467,52 @@ impl Organism {
         self.profiles.keys().map(|s| s.as_str()).collect()
     }
 
+    // ── Mount policies ──
+
+    /// Drive policies for threads under `profile`: the profile's policy
+    /// for every caller, plus each agent's own `mount:` block.
+    pub fn mount_policies(&self, profile: &str) -> MountPolicies {
+        let base = self
+            .profiles
+            .get(profile)
+            .map(|p| p.mount.clone())
+            .unwrap_or_default();
+        let agents = self
+            .listeners
+            .values()
+            .filter_map(|l| {
+                let cfg = l.agent_config.as_ref()?;
+                (!cfg.mount.is_unrestricted()).then(|| (l.name.clone(), cfg.mount.clone()))
+            })
+            .collect();
+        MountPolicies { base, agents }
+    }
+
+    /// The tightest policy among the profiles that can reach `listener`.
+    pub fn listener_mount_policy(&self, listener: &str) -> MountPolicy {
+        self.profiles
+            .values()
+            .filter(|p| p.allows(listener))
+            .fold(MountPolicy::default(), |acc, p| acc.merge(&p.mount))
+    }
+
+    /// Stamp every WASM filesystem grant with its listener's profile
+    /// policy, so preopens are no looser than the VDrive tools.
+    pub fn apply_mount_policies(&mut self) {
+        let policies: HashMap<String, MountPolicy> = self
+            .listeners
+            .keys()
+            .map(|name| (name.clone(), self.listener_mount_policy(name)))
+            .collect();
+        for (name, def) in self.listeners.iter_mut() {
+            if let Some(wasm) = def.wasm.as_mut() {
+                for grant in &mut wasm.capabilities.filesystem {
+                    grant.mount = policies[name].clone();
+                }
+            }
+        }
+    }
+
     // ── Prompt management ──
 
     /// Register a named prompt.
@@ -475,7 +563,8 @@ impl Organism {
     }
 
     /// Merge all listeners and prompts from another organism.
-    /// Profiles, onboarding, and kv_store are NOT imported — they belong to the root organism.
+    /// Profiles, onboarding, kv_store and grammar_dir are NOT imported — they belong to the
+    /// root organism.
     pub fn merge_from(&mut self, other: Organism) -> Result<(), String> {
         for (_, listener) in other.listeners {
             self.merge_listener(listener)?;
@@ -483,9 +572,28 @@ impl Organism {
         for (name, content) in other.prompts {
             self.merge_prompt(name, content)?;
         }
+        for grammar in other.grammars {
+            self.add_grammar(grammar)?;
+        }
         Ok(())
     }
 
+    // ── Grammars ──
+
+    /// Add a runtime grammar. Names must be unique.
+    pub fn add_grammar(&mut self, grammar: GrammarDef) -> Result<(), String> {
+        if self.grammars.iter().any(|g| g.name == grammar.name) {
+            return Err(format!("grammar '{}' already defined", grammar.name));
+        }
+        self.grammars.push(grammar);
+        Ok(())
+    }
+
+    /// Runtime grammars for the code index.
+    pub fn grammars(&self) -> &[GrammarDef] {
+        &self.grammars
+    }
+
     /// Get all listeners that are agents.
     pub fn agent_listeners(&self) -> Vec<&ListenerDef> {
         self.listeners.values().filter(|l| l.is_agent).collect()
@@ -589,6 +697,7 @@ mod tests {
             buffer: None,
             python: None,
             trigger: None,
+            shim_trainer: None,
         }
     }
 
@@ -600,6 +709,7 @@ mod tests {
             allow_all: false,
             journal_retention: RetentionPolicy::Forever,
             network: vec![],
+            mount: MountPolicy::default(),
         }
     }
 
@@ -656,6 +766,7 @@ mod tests {
             allow_all: true,
             journal_retention: RetentionPolicy::Forever,
             network: vec![],
+            mount: MountPolicy::default(),
         };
         org.add_profile(profile).unwrap();
 
@@ -721,6 +832,7 @@ mod tests {
             buffer: None,
             python: None,
             trigger: None,
+            shim_trainer: None,
         })
         .unwrap();
 
@@ -754,6 +866,7 @@ mod tests {
             buffer: None,
             python: None,
             trigger: None,
+            shim_trainer: None,
         })
         .unwrap();
 
@@ -822,6 +935,7 @@ mod tests {
             model: Some("haiku".into()),
             shim_store: None,
             permissions: agentos_events::PermissionMap::new(),
+            mount: MountPolicy::default(),
         });
 
         let cfg = def.agent_config.as_ref().unwrap();
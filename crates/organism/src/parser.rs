@@ -12,9 +12,12 @@ use serde::Deserialize;
 use super::profile::{RetentionPolicy, SecurityProfile};
 use super::{
     AgentConfig, BufferConfig, CallableParam, ListenerDef, Organism, PortDef, PythonToolConfig,
-    TriggerConfig, TriggerSource, WasmToolConfig,
+    ShimTrainerConfig, TriggerConfig, TriggerSource, WasmToolConfig,
+};
+use agentos_events::{
+    EnvGrant, FsGrant, GlobRules, KvGrant, MountPolicy, PermissionMap, PermissionTier,
+    WasmCapabilities,
 };
-use agentos_events::{EnvGrant, FsGrant, KvGrant, PermissionMap, PermissionTier, WasmCapabilities};
 
 /// Top-level organism YAML configuration.
 ///
@@ -40,8 +43,16 @@ struct OrganismYaml {
     /// a path string = on-disk. Omit or `false`/`"no"` = no KV store.
     #[serde(default, rename = "kv-store")]
     kv_store: Option<KvStoreYaml>,
+    /// Extra tree-sitter grammars for the code index, loaded at startup
+    /// from compiled parser libraries.
+    #[serde(default)]
+    grammars: Vec<GrammarYaml>,
+    /// Directory grammar libraries must live in, relative to this file's
+    /// directory. Default: `grammars`.
+    #[serde(default, rename = "grammar-dir")]
+    grammar_dir: Option<String>,
     /// Organism files to import (paths relative to this file's directory).
-    /// Listeners and prompts are merged; profiles/onboarding/kv-store are root-only.
+    /// Listeners and prompts are merged; profiles/onboarding/kv-store/grammar-dir are root-only.
     #[serde(default)]
     imports: Vec<String>,
 }
@@ -144,6 +155,9 @@ struct ListenerYaml {
     /// Trigger configuration (handler == "trigger").
     #[serde(default)]
     trigger: Option<TriggerYaml>,
+    /// Shim trainer backend (handler == "tools.shim_train.handle"). Default: native.
+    #[serde(default)]
+    trainer: Option<TrainerYaml>,
 }
 
 /// Agent field: `true` for defaults, or a configuration block. Untagged for YAML flexibility.
@@ -185,6 +199,9 @@ struct AgentConfigYaml {
     /// Per-tool permission tiers: `auto` (no approval), `prompt` (ask user), `deny` (never).
     #[serde(default)]
     permissions: std::collections::HashMap<String, String>,
+    /// Drive policy for this agent, on top of its profile's `mount:`.
+    #[serde(default)]
+    mount: Option<MountPolicyYaml>,
 }
 
 /// Two-shape model declaration. Backwards-compat: `model: haiku` continues
@@ -335,6 +352,62 @@ struct PythonYaml {
     source: String,
 }
 
+/// Shim trainer backend for the `shim-train` tool.
+#[derive(Debug, Deserialize, JsonSchema)]
+struct TrainerYaml {
+    /// `native` (in-process Rust trainer) or `python` (subprocess).
+    backend: String,
+    /// Python interpreter (for `python`). Default: `python`.
+    #[serde(default)]
+    python: Option<String>,
+    /// Trainer script, relative to organism base dir (required for `python`).
+    #[serde(default)]
+    script_path: Option<String>,
+}
+
+impl TrainerYaml {
+    fn to_config(&self, owner: &str, base_dir: Option<&Path>) -> Result<ShimTrainerConfig, String> {
+        match self.backend.as_str() {
+            "native" => Ok(ShimTrainerConfig::Native),
+            "python" => {
+                let script_path = self
+                    .script_path
+                    .as_deref()
+                    .ok_or_else(|| format!("{owner}: trainer: python backend needs script_path"))?;
+                Ok(ShimTrainerConfig::Python {
+                    python: self.python.clone().unwrap_or_else(|| "python".to_string()),
+                    script_path: resolve_path(script_path, base_dir),
+                })
+            }
+            other => Err(format!(
+                "{owner}: trainer: unknown backend '{other}' (expected native or python)"
+            )),
+        }
+    }
+}
+
+/// A tree-sitter grammar loaded at runtime for the code index.
+#[derive(Debug, Deserialize, JsonSchema)]
+struct GrammarYaml {
+    /// Language name, as reported in codebase maps.
+    name: String,
+    /// File extensions (without the dot) parsed with this grammar.
+    extensions: Vec<String>,
+    /// Compiled parser library (`.so`, `.dylib` or `.dll`), relative to organism base dir.
+    /// Must be inside the root organism's `grammar-dir`.
+    library: String,
+    /// Exported language function. Default: `tree_sitter_<name>`.
+    #[serde(default)]
+    symbol: Option<String>,
+    /// Symbol query: inline text or `file:path`. Each pattern captures `@name`
+    /// and ends with `) @<kind>` on its own line.
+    symbols: String,
+    /// Reference query: inline text or `file:path`. Captures `@call`, `@type`,
+    /// `@import`, or `@trait` with `@implementor`.
+    #[serde(default)]
+    references: Option<String>,
+}
+
 /// Trigger configuration — makes a listener fire messages rather than handle them.
 #[derive(Debug, Deserialize, JsonSchema)]
 struct TriggerYaml {
@@ -409,6 +482,47 @@ struct ProfileYaml {
     /// Listeners whose network ports this profile may use.
     #[serde(default)]
     network: Vec<String>,
+    /// Policy enforced on mounted drives (and WASM filesystem grants).
+    #[serde(default)]
+    mount: Option<MountPolicyYaml>,
+}
+
+/// Mount policy for VDrive workspaces. Globs are relative to the drive root.
+#[derive(Debug, Deserialize, JsonSchema)]
+struct MountPolicyYaml {
+    /// Reject every write, edit, mkdir and delete. Default: `false`.
+    #[serde(default)]
+    read_only: bool,
+    /// Total bytes that may be written while the drive is mounted.
+    #[serde(default)]
+    max_bytes_written: Option<u64>,
+    /// Largest file that may be written, in bytes.
+    #[serde(default)]
+    max_file_size: Option<u64>,
+    /// How many new files may be created while the drive is mounted.
+    #[serde(default)]
+    max_files: Option<u64>,
+    /// Globs hidden from every tool (e.g. `secrets/**`).
+    #[serde(default)]
+    deny: Vec<String>,
+    /// Globs that may be read but not changed (e.g. `.git/**`).
+    #[serde(default)]
+    readonly: Vec<String>,
+}
+
+impl MountPolicyYaml {
+    fn to_policy(&self, owner: &str) -> Result<MountPolicy, String> {
+        let rules =
+            |globs: &[String]| GlobRules::new(globs).map_err(|e| format!("{owner}: mount: {e}"));
+        Ok(MountPolicy {
+            read_only: self.read_only,
+            max_bytes_written: self.max_bytes_written,
+            max_file_size: self.max_file_size,
+            max_files: self.max_files,
+            deny: rules(&self.deny)?,
+            readonly: rules(&self.readonly)?,
+        })
+    }
 }
 
 /// Tools spec: `"auto"` for auto-discovery, or a list of listener names.
@@ -571,6 +685,7 @@ fn load_organism_recursive(
 
     // Validate profiles now that all listeners (local + imported) are registered
     org.validate_profiles()?;
+    org.apply_mount_policies();
 
     loading.pop();
     loaded.insert(canonical);
@@ -584,11 +699,32 @@ fn load_organism_recursive(
 pub fn parse_organism(yaml: &str) -> Result<Organism, String> {
     let raw: OrganismYaml =
         serde_yaml::from_str(yaml).map_err(|e| format!("YAML parse error: {e}"))?;
-    let org = build_organism(raw, None)?;
+    let mut org = build_organism(raw, None)?;
     org.validate_profiles()?;
+    org.apply_mount_policies();
     Ok(org)
 }
 
+/// Resolve `path` against `base_dir` (or the working directory).
+fn resolve_path(path: &str, base_dir: Option<&Path>) -> PathBuf {
+    match base_dir {
+        Some(dir) => dir.join(path.trim()),
+        None => PathBuf::from(path.trim()),
+    }
+}
+
+/// Return `value`, or the contents of the file it names with a `file:` prefix.
+fn resolve_file_value(value: &str, base_dir: Option<&Path>, what: &str) -> Result<String, String> {
+    match value.strip_prefix("file:") {
+        Some(file_path) => {
+            let resolved = resolve_path(file_path, base_dir);
+            std::fs::read_to_string(&resolved)
+                .map_err(|e| format!("failed to load {what} file '{}': {e}", resolved.display()))
+        }
+        None => Ok(value.to_string()),
+    }
+}
+
 /// Build an Organism from a parsed YAML struct.
 ///
 /// `base_dir` is used to resolve `file:` prompt references. If `None`,
@@ -598,18 +734,25 @@ fn build_organism(raw: OrganismYaml, base_dir: Option<&Path>) -> Result<Organism
 
     // Register prompts (resolve file: prefixes)
     for (name, value) in raw.prompts {
-        if let Some(file_path) = value.strip_prefix("file:") {
-            let resolved = if let Some(dir) = base_dir {
-                dir.join(file_path.trim())
-            } else {
-                PathBuf::from(file_path.trim())
-            };
-            let content = std::fs::read_to_string(&resolved)
-                .map_err(|e| format!("failed to load prompt file '{}': {e}", resolved.display()))?;
-            org.register_prompt(name, content);
-        } else {
-            org.register_prompt(name, value);
-        }
+        let content = resolve_file_value(&value, base_dir, "prompt")?;
+        org.register_prompt(name, content);
+    }
+
+    // Runtime grammars (resolve library paths and file: queries)
+    for g in raw.grammars {
+        let context = format!("grammar '{}'", g.name);
+        let grammar = super::GrammarDef {
+            library: resolve_path(&g.library, base_dir),
+            symbol_query: resolve_file_value(&g.symbols, base_dir, &context)?,
+            reference_query: g
+                .references
+                .map(|r| resolve_file_value(&r, base_dir, &context))
+                .transpose()?,
+            name: g.name,
+            extensions: g.extensions,
+            symbol: g.symbol,
+        };
+        org.add_grammar(grammar)?;
     }
 
     // Register listeners
@@ -646,6 +789,10 @@ fn build_organism(raw: OrganismYaml, base_dir: Option<&Path>) -> Result<Organism
                     Some(ModelYaml::Layered(l)) => (Some(l.base), l.shim_store),
                     None => (None, None),
                 };
+                let mount = match &cfg.mount {
+                    Some(m) => m.to_policy(&format!("listener '{}'", l.name))?,
+                    None => MountPolicy::default(),
+                };
                 let config = AgentConfig {
                     prompt: cfg.prompt,
                     max_tokens: cfg.max_tokens.unwrap_or(4096),
@@ -654,6 +801,7 @@ fn build_organism(raw: OrganismYaml, base_dir: Option<&Path>) -> Result<Organism
                     model,
                     shim_store,
                     permissions,
+                    mount,
                 };
                 (true, Some(config))
             }
@@ -666,6 +814,12 @@ fn build_organism(raw: OrganismYaml, base_dir: Option<&Path>) -> Result<Organism
             }
         };
 
+        let shim_trainer = l
+            .trainer
+            .as_ref()
+            .map(|t| t.to_config(&format!("listener '{}'", l.name), base_dir))
+            .transpose()?;
+
         // Resolve buffer: use explicit declaration, or auto-generate default for agents
         let buffer = l.buffer.map(|b| {
             let parameters = b
@@ -738,6 +892,8 @@ fn build_organism(raw: OrganismYaml, base_dir: Option<&Path>) -> Result<Organism
                                 host_path: f.host_path,
                                 guest_path: f.guest_path,
                                 read_only: f.read_only,
+                                // Filled from profiles by apply_mount_policies.
+                                mount: MountPolicy::default(),
                             })
                             .collect(),
                         env_vars: c
@@ -812,6 +968,7 @@ fn build_organism(raw: OrganismYaml, base_dir: Option<&Path>) -> Result<Organism
                     source_namespace: None, // set at runtime registration, not in YAML
                 }
             }),
+            shim_trainer,
         })?;
     }
 
@@ -839,6 +996,11 @@ fn build_organism(raw: OrganismYaml, base_dir: Option<&Path>) -> Result<Organism
             JournalSpec::WithDays(spec) => RetentionPolicy::RetainDays(spec.retain_days),
         };
 
+        let mount = match &p.mount {
+            Some(m) => m.to_policy(&format!("profile '{name}'"))?,
+            None => MountPolicy::default(),
+        };
+
         org.add_profile_deferred(SecurityProfile {
             name,
             linux_user: p.linux_user,
@@ -846,6 +1008,7 @@ fn build_organism(raw: OrganismYaml, base_dir: Option<&Path>) -> Result<Organism
             allow_all,
             journal_retention,
             network: p.network,
+            mount,
         });
     }
 
@@ -858,6 +1021,9 @@ fn build_organism(raw: OrganismYaml, base_dir: Option<&Path>) -> Result<Organism
         .map(|k| k.to_config())
         .unwrap_or(super::KvStoreConfig::None);
 
+    // Grammar libraries are only loaded from here
+    org.grammar_dir = resolve_path(raw.grammar_dir.as_deref().unwrap_or("grammars"), base_dir);
+
     Ok(org)
 }
 
@@ -1135,6 +1301,77 @@ profiles:
         assert!(wasm.capabilities.stdio);
     }
 
+    #[test]
+    fn parse_mount_policies() {
+        let yaml = r#"
+organism:
+  name: test-mount
+
+listeners:
+  - name: coder
+    payload_class: agent.CoderTask
+    handler: agent.handle
+    description: "Coder"
+    agent:
+      mount:
+        max_file_size: 1000
+        readonly: ["Cargo.lock"]
+  - name: my-tool
+    payload_class: tools.MyToolRequest
+    handler: wasm
+    description: "My custom tool"
+    wasm:
+      path: tools/my_tool.wasm
+      capabilities:
+        filesystem:
+          - host_path: /data
+            guest_path: /data
+
+profiles:
+  admin:
+    linux_user: agentos-admin
+    listeners: [coder, my-tool]
+    mount:
+      max_bytes_written: 5000
+      deny: ["secrets/**"]
+      readonly: [".git/**"]
+"#;
+        let org = parse_organism(yaml).unwrap();
+        let policies = org.mount_policies("admin");
+        assert_eq!(policies.base.max_bytes_written, Some(5000));
+        let coder = policies.effective(Some("coder"));
+        assert_eq!(coder.max_file_size, Some(1000));
+        assert_eq!(coder.readonly.iter().collect::<Vec<_>>(), [".git/**", "Cargo.lock"]);
+        assert!(coder.is_denied("secrets/key"));
+
+        let grant = &org.get_listener("my-tool").unwrap().wasm.as_ref().unwrap()
+            .capabilities.filesystem[0];
+        assert_eq!(grant.mount.deny.iter().collect::<Vec<_>>(), ["secrets/**"]);
+    }
+
+    #[test]
+    fn parse_mount_policy_bad_glob() {
+        let yaml = r#"
+organism:
+  name: test-mount
+
+listeners:
+  - name: faq
+    payload_class: tools.FaqRequest
+    handler: tools.faq.handle
+    description: "FAQ"
+
+profiles:
+  public:
+    linux_user: agentos-public
+    listeners: [faq]
+    mount:
+      deny: ["[oops"]
+"#;
+        let err = parse_organism(yaml).unwrap_err();
+        assert!(err.contains("profile 'public': mount"), "unexpected error: {err}");
+    }
+
     #[test]
     fn parse_listener_without_wasm() {
         let yaml = r#"
@@ -1158,6 +1395,50 @@ profiles:
         assert!(fops.wasm.is_none());
     }
 
+    #[test]
+    fn parse_shim_trainer() {
+        let yaml = |trainer: &str| {
+            format!(
+                r#"
+organism:
+  name: test-trainer
+
+listeners:
+  - name: shim-train
+    payload_class: tools.ShimTrainRequest
+    handler: tools.shim_train.handle
+    description: "Train shims"
+{trainer}
+
+profiles:
+  admin:
+    linux_user: agentos-admin
+    listeners: [shim-train]
+    journal: retain_forever
+"#
+            )
+        };
+        let trainer = |trainer: &str| {
+            parse_organism(&yaml(trainer)).map(|org| org.get_listener("shim-train").unwrap().shim_trainer.clone())
+        };
+        assert_eq!(trainer("").unwrap(), None);
+        assert_eq!(
+            trainer("    trainer:\n      backend: native").unwrap(),
+            Some(ShimTrainerConfig::Native)
+        );
+        assert_eq!(
+            trainer("    trainer:\n      backend: python\n      script_path: scripts/train_shim.py").unwrap(),
+            Some(ShimTrainerConfig::Python {
+                python: "python".into(),
+                script_path: PathBuf::from("scripts/train_shim.py"),
+            })
+        );
+        let err = trainer("    trainer:\n      backend: python").unwrap_err();
+        assert!(err.contains("needs script_path"), "unexpected error: {err}");
+        let err = trainer("    trainer:\n      backend: torch").unwrap_err();
+        assert!(err.contains("unknown backend 'torch'"), "unexpected error: {err}");
+    }
+
     #[test]
     fn parse_wasm_empty_capabilities() {
         let yaml = r#"
@@ -1996,7 +2277,7 @@ profiles:
     #[test]
     fn existing_organisms_parse() {
         let root = workspace_root();
-        for name in &["default.yaml", "infrastructure.yaml", "coder.yaml", "coder-v2.yaml", "organism-builder.yaml", "agent-expert.yaml", "wiki-expert.yaml", "plan-expert.yaml"] {
+        for name in &["default.yaml", "infrastructure.yaml", "coder.yaml", "coder-v2.yaml", "organism-builder.yaml", "agent-expert.yaml", "wiki-expert.yaml", "plan-expert.yaml", "shim-expert.yaml"] {
             let path = root.join("organisms").join(name);
             let content = std::fs::read_to_string(&path)
                 .unwrap_or_else(|e| panic!("failed to read {}: {e}", path.display()));
@@ -2085,6 +2366,56 @@ profiles:
         assert_eq!(org.kv_store, super::super::KvStoreConfig::None);
     }
 
+    // ── Grammar config parsing ──
+
+    #[test]
+    fn grammars_resolve_library_and_query_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("kotlin.scm"), "(class_declaration) @class").unwrap();
+        let path = write_yaml(dir.path(), "org.yaml", r#"
+organism:
+  name: test
+grammars:
+  - name: kotlin
+    extensions: [kt, kts]
+    library: grammars/libtree-sitter-kotlin.so
+    symbols: "file:kotlin.scm"
+"#);
+        let org = load_organism(&path).unwrap();
+        let g = &org.grammars()[0];
+        assert_eq!(g.extensions, vec!["kt", "kts"]);
+        assert_eq!(g.library, dir.path().join("grammars/libtree-sitter-kotlin.so"));
+        assert_eq!(org.grammar_dir, dir.path().join("grammars"));
+        assert_eq!(g.symbol_query, "(class_declaration) @class");
+        assert_eq!(g.symbol, None);
+        assert_eq!(g.reference_query, None);
+    }
+
+    #[test]
+    fn grammar_dir_resolves_against_organism_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = write_yaml(dir.path(), "org.yaml", r#"
+organism:
+  name: test
+grammar-dir: vendor/parsers
+"#);
+        let org = load_organism(&path).unwrap();
+        assert_eq!(org.grammar_dir, dir.path().join("vendor/parsers"));
+    }
+
+    #[test]
+    fn duplicate_grammar_rejected() {
+        let yaml = r#"
+organism:
+  name: test
+grammars:
+  - { name: zig, extensions: [zig], library: a.so, symbols: "" }
+  - { name: zig, extensions: [zig], library: b.so, symbols: "" }
+"#;
+        let err = parse_organism(yaml).unwrap_err();
+        assert!(err.contains("grammar 'zig' already defined"), "{err}");
+    }
+
     // ── Import resolution tests ──
 
     /// Helper: write a YAML file and return its path.
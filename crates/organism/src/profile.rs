@@ -4,6 +4,8 @@
 
 use std::collections::{HashMap, HashSet};
 
+use agentos_events::MountPolicy;
+
 use super::ListenerDef;
 
 /// Retention policy for journal entries under a profile.
@@ -33,6 +35,15 @@ pub struct SecurityProfile {
     /// Which listeners' ports this profile can use (for network access).
     /// Empty means no network restrictions beyond listener access.
     pub network: Vec<String>,
+    /// Policy enforced on mounted drives for threads under this profile.
+    pub mount: MountPolicy,
+}
+
+impl SecurityProfile {
+    /// Whether this profile may reach listener `name`.
+    pub fn allows(&self, name: &str) -> bool {
+        self.allow_all || self.allowed_listeners.contains(name)
+    }
 }
 
 /// A materialized dispatch table for a specific profile.
@@ -86,6 +97,7 @@ mod tests {
                 buffer: None,
                 python: None,
                 trigger: None,
+                shim_trainer: None,
             },
         );
 
@@ -13,6 +13,9 @@
 //! 5. Await PipelineEvent::AgentResponse from child broadcast channel
 //! 6. Shutdown child, drop tempdir
 //! 7. Return ToolResponse::ok(result) or ToolResponse::err(e) to host
+//!
+//! The child run is traced as a `buffer.child` span, and the child's
+//! root thread continues the host request's trace.
 
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -20,6 +23,7 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use rust_pipeline::prelude::*;
 use tokio::sync::{broadcast, Mutex, Semaphore};
+use tracing::Instrument;
 
 use agentos_llm::LlmPool;
 use agentos_organism::parser::load_organism;
@@ -28,7 +32,7 @@ use crate::events::PipelineEvent;
 use crate::AgentPipelineBuilder;
 use agentos_tools::vdrive_tools::{
     DriveSlot, VDriveFileRead, VDriveFileWrite, VDriveFileEdit,
-    VDriveGlob, VDriveGrep, VDriveListDir, VDriveCommandExec,
+    VDriveFileMultiEdit, VDriveFilePatch, VDriveFileMove, VDriveFileCopy, VDriveGlob, VDriveGrep, VDriveListDir, VDriveCommandExec, VDriveUndo,
 };
 use agentos_tools::user_channel::{UserChannelHandler, UserQueryRequest};
 use agentos_tools::{self as tools, ToolResponse};
@@ -134,6 +138,15 @@ impl BufferHandler {
             .map(|a| a.name.clone())
             .unwrap_or_else(|| "child".to_string());
 
+        // The child kernel mints its own thread ids; link the root one
+        // into this handler's trace so the child run nests under it.
+        agentos_telemetry::trace::continue_thread(
+            &root_uuid,
+            &child_agent_name,
+            profile,
+            &tracing::Span::current(),
+        );
+
         // If interactive, emit FocusAcquire so TUI switches to child agent tab
         if self.config.interactive {
             if let Some(parent_tx) = &self.event_tx {
@@ -243,6 +256,10 @@ impl BufferHandler {
         child_pipeline.shutdown().await;
         // tempdir drops here
 
+        // Normally released when the child agent replies; a timed-out
+        // child never does.
+        agentos_telemetry::trace::finish(&root_uuid, &child_agent_name);
+
         // If interactive, emit FocusRelease so TUI switches back to parent
         if self.config.interactive {
             if let Some(parent_tx) = &self.event_tx {
@@ -277,7 +294,12 @@ impl Handler for BufferHandler {
         let task_text = task_parts.join("\n");
 
         // Run the child pipeline (ctx.from = the calling agent's name)
-        match self.run_child(&task_text, &ctx.from).await {
+        let span = tracing::info_span!(
+            "buffer.child",
+            parent_agent = %ctx.from,
+            thread_id = %ctx.thread_id,
+        );
+        match self.run_child(&task_text, &ctx.from).instrument(span).await {
             Ok(result) => Ok(HandlerResponse::Reply {
                 payload_xml: ToolResponse::ok(&result),
             }),
@@ -317,14 +339,19 @@ fn register_required_tools(
             "file-read" => builder.register_tool(name, VDriveFileRead::new(drive_slot.clone()))?,
             "file-write" => builder.register_tool(name, VDriveFileWrite::new(drive_slot.clone()))?,
             "file-edit" => builder.register_tool(name, VDriveFileEdit::new(drive_slot.clone()))?,
+            "file-multi-edit" => builder.register_tool(name, VDriveFileMultiEdit::new(drive_slot.clone()))?,
+            "file-patch" => builder.register_tool(name, VDriveFilePatch::new(drive_slot.clone()))?,
+            "file-move" => builder.register_tool(name, VDriveFileMove::new(drive_slot.clone()))?,
+            "file-copy" => builder.register_tool(name, VDriveFileCopy::new(drive_slot.clone()))?,
             "glob" => builder.register_tool(name, VDriveGlob::new(drive_slot.clone()))?,
             "grep" => builder.register_tool(name, VDriveGrep::new(drive_slot.clone()))?,
             "list-dir" => builder.register_tool(name, VDriveListDir::new(drive_slot.clone()))?,
+            "vdrive-undo" => builder.register_tool(name, VDriveUndo::new(drive_slot.clone()))?,
             "bash" => builder.register_tool(name, VDriveCommandExec::new(drive_slot.clone()))?,
             "validate-organism" => builder.register_tool(name, agentos_tools::validate_organism::ValidateOrganismTool::new(drive_slot.clone()))?,
             "test-organism" => builder.register_tool(name, crate::test_organism::TestOrganismTool::new(drive_slot.clone(), None))?,
             "package-organism" => builder.register_tool(name, agentos_tools::package_organism::PackageOrganismTool::new(drive_slot.clone()))?,
-            "codebase-index" => {
+            "codebase-index" | "semantic-search" => {
                 builder = builder.with_code_index()?;
                 continue;
             }
@@ -13,8 +13,10 @@
 pub mod buffer;
 pub mod events;
 pub mod llm_handler;
+pub mod metered;
 pub mod runtime_impl;
 pub mod test_organism;
+pub mod traced;
 
 use std::path::Path;
 use std::sync::Arc;
@@ -36,12 +38,17 @@ use agentos_kernel::Kernel;
 use agentos_librarian::handler::LibrarianHandler;
 use agentos_librarian::Librarian;
 use agentos_llm::LlmPool;
+use agentos_tools::shim_train::{ShimTrainTool, ShimTrainer};
 use crate::llm_handler::LlmHandler;
-use agentos_organism::Organism;
+use crate::metered::Metered;
+use crate::traced::Traced;
+use agentos_organism::{Organism, ShimTrainerConfig};
 use agentos_ports::{Direction, PortDeclaration, PortManager, Protocol};
 use agentos_routing::{self as routing, form_filler::CloudFormFiller, SemanticRouter, ToolMetadata};
 use agentos_security::SecurityResolver;
-use agentos_treesitter::handler::CodeIndexHandler;
+use agentos_treesitter::handler::{CodeIndexHandler, SemanticSearchHandler};
+use agentos_treesitter::semantic::SemanticIndex;
+use agentos_treesitter::watch::{DriveIndex, IndexWatcher};
 use agentos_treesitter::CodeIndex;
 use agentos_wasm::definitions::WasmToolRegistry;
 use agentos_wasm::peer::WasmToolPeer;
@@ -68,10 +75,18 @@ pub struct AgentPipeline {
     query_rx: Option<tokio::sync::mpsc::Receiver<agentos_tools::user_channel::UserQueryRequest>>,
     /// Trigger runtime — spawns background tasks for file watchers, timers, crons, etc.
     trigger_runtime: Option<agentos_trigger::TriggerRuntime>,
+    /// Code index shared with the `codebase-index` handler.
+    code_index: Option<Arc<Mutex<CodeIndex>>>,
+    /// Semantic index shared with the `semantic-search` handler.
+    semantic_index: Option<Arc<Mutex<SemanticIndex>>>,
     /// Kernel data directory — exposed so frontends can derive sibling
     /// paths (e.g. the platform registry snapshot) without locking the
     /// kernel mutex.
     data_dir: std::path::PathBuf,
+    /// Agents' shim routing, flushed periodically and on shutdown.
+    shim_routings: Vec<agentos_agent::shims::ShimRouting>,
+    /// Periodic shim stats flush, started by `run()`.
+    shim_flusher: Option<tokio::task::JoinHandle<()>>,
 }
 
 impl AgentPipeline {
@@ -108,7 +123,11 @@ impl AgentPipeline {
             approval_rx: None,
             query_rx: None,
             trigger_runtime: None,
+            code_index: None,
+            semantic_index: None,
             data_dir: data_dir.to_path_buf(),
+            shim_routings: Vec::new(),
+            shim_flusher: None,
         })
     }
 
@@ -155,6 +174,10 @@ impl AgentPipeline {
     /// Before the message enters the pipeline, we check:
     /// 1. The thread's profile allows messaging the target
     /// 2. The dispatch is logged in the kernel
+    ///
+    /// An admitted message opens the request's trace (see
+    /// [`agentos_telemetry::trace`]); every dispatch on `thread_id` until
+    /// `target` replies lands in it.
     pub async fn inject_checked(
         &self,
         raw: Vec<u8>,
@@ -173,11 +196,15 @@ impl AgentPipeline {
             ));
         }
 
+        // Registered before injection so the first dispatch finds it;
+        // dropped again if the injection fails.
+        agentos_telemetry::trace::start_request(thread_id, target, profile);
+
         // Inject into the inner pipeline
-        self.pipeline
-            .inject(raw)
-            .await
-            .map_err(|e| format!("inject failed: {e}"))?;
+        if let Err(e) = self.pipeline.inject(raw).await {
+            agentos_telemetry::trace::finish(thread_id, target);
+            return Err(format!("inject failed: {e}"));
+        }
 
         let _ = self.event_tx.send(PipelineEvent::MessageInjected {
             thread_id: thread_id.to_string(),
@@ -196,14 +223,27 @@ impl AgentPipeline {
             .map_err(|e| format!("inject failed: {e}"))
     }
 
-    /// Start the pipeline.
+    /// Start the pipeline, and the periodic flush of agents' shim stats.
     pub fn run(&mut self) {
         self.pipeline.run();
+        if !self.shim_routings.is_empty() && self.shim_flusher.is_none() {
+            self.shim_flusher = Some(tokio::spawn(agentos_agent::shims::flush_periodically(
+                self.shim_routings.clone(),
+                agentos_agent::shims::FLUSH_INTERVAL,
+            )));
+        }
     }
 
-    /// Shutdown the pipeline.
+    /// Shutdown the pipeline, then hand any pending shim stats to the
+    /// kernel.
     pub async fn shutdown(self) {
+        if let Some(flusher) = &self.shim_flusher {
+            flusher.abort();
+        }
         self.pipeline.shutdown().await;
+        for routing in &self.shim_routings {
+            routing.flush().await;
+        }
     }
 
     /// Get a reference to the organism.
@@ -236,6 +276,79 @@ impl AgentPipeline {
         self.kernel.clone()
     }
 
+    /// Periodically publish the kernel's context-store occupancy to the
+    /// `agentos_context_bytes` gauges. Counters and histograms update as
+    /// events happen; this gauge is a scan over every segment, so it is
+    /// sampled instead. Abort the handle to stop it.
+    pub fn spawn_gauge_sampler(
+        &self,
+        interval: std::time::Duration,
+    ) -> tokio::task::JoinHandle<()> {
+        let kernel = self.kernel.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let (active, shelved, folded) = kernel.lock().await.contexts().bytes_by_status();
+                agentos_telemetry::set_context_bytes(active, shelved, folded);
+            }
+        })
+    }
+
+    /// Keep the code and semantic indexes in step with whatever drive is
+    /// mounted in `slot`, checking for mount changes every `interval`. For
+    /// each newly mounted drive, the persisted indexes for its root are
+    /// loaded from the data dir, caught up, and then watched for changes.
+    /// Returns `None` without a code index. Abort the handle to stop it.
+    pub fn spawn_code_index_sync(
+        &self,
+        slot: agentos_tools::vdrive_tools::DriveSlot,
+        interval: std::time::Duration,
+    ) -> Option<tokio::task::JoinHandle<()>> {
+        let index = self.code_index.clone()?;
+        let semantic = self.semantic_index.clone();
+        let data_dir = self.data_dir.clone();
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let mut watched: Option<(Arc<agentos_vdrive::VDrive>, Vec<IndexWatcher>)> = None;
+            loop {
+                ticker.tick().await;
+                let mounted = slot.read().await.clone();
+                let unchanged = match (&mounted, &watched) {
+                    (Some(drive), Some((current, _))) => Arc::ptr_eq(drive, current),
+                    (None, None) => true,
+                    _ => false,
+                };
+                if unchanged {
+                    continue;
+                }
+
+                // Stop the old watchers before swapping out the indexes they feed.
+                drop(watched.take());
+                let root = mounted.as_ref().map(|drive| drive.root().to_path_buf());
+                remount(&index, |_| match &root {
+                    Some(root) => CodeIndex::open(&data_dir, root),
+                    None => CodeIndex::new(),
+                })
+                .await;
+                if let Some(semantic) = &semantic {
+                    remount(semantic, |old: &SemanticIndex| match &root {
+                        Some(root) => SemanticIndex::open(&data_dir, root, old.provider()),
+                        None => SemanticIndex::new(old.provider()),
+                    })
+                    .await;
+                }
+                watched = mounted.map(|drive| {
+                    let mut watchers = vec![IndexWatcher::start(index.clone(), drive.clone())];
+                    if let Some(semantic) = &semantic {
+                        watchers.push(IndexWatcher::start(semantic.clone(), drive.clone()));
+                    }
+                    (drive, watchers)
+                });
+            }
+        }))
+    }
+
     /// Get the LLM pool (for TUI `/model` command).
     pub fn llm_pool(&self) -> Option<Arc<Mutex<LlmPool>>> {
         self.llm_pool.clone()
@@ -369,6 +482,15 @@ fn load_shim_config_from_kernel(
     Ok(parsed)
 }
 
+/// Save `index` and replace it with `reopen(&old)` for a new mount.
+async fn remount<I: DriveIndex>(index: &Arc<Mutex<I>>, reopen: impl FnOnce(&I) -> I) {
+    let mut idx = index.lock().await;
+    if let Err(e) = idx.save() {
+        tracing::warn!("{}: {e}", I::NAME);
+    }
+    *idx = reopen(&*idx);
+}
+
 /// Builder for AgentPipeline — register handlers before building.
 pub struct AgentPipelineBuilder {
     organism: Organism,
@@ -378,6 +500,7 @@ pub struct AgentPipelineBuilder {
     port_manager: Option<PortManager>,
     pub librarian: Option<Arc<Mutex<Librarian>>>,
     pub code_index: Option<Arc<Mutex<CodeIndex>>>,
+    pub semantic_index: Option<Arc<Mutex<SemanticIndex>>>,
     wasm_runtime: Option<Arc<WasmRuntime>>,
     wasm_registry: Option<WasmToolRegistry>,
     semantic_router: Option<SemanticRouter>,
@@ -403,6 +526,10 @@ pub struct AgentPipelineBuilder {
     query_rx: Option<tokio::sync::mpsc::Receiver<agentos_tools::user_channel::UserQueryRequest>>,
     /// Debug mode: enables DebugGate middleware and PermissionGate override.
     debug: bool,
+    /// Kernel handle for agents' shim routing, set in `build()`.
+    shim_kernel: agentos_agent::shims::KernelCell,
+    /// Every agent's shim routing, handed to the pipeline for flushing.
+    shim_routings: Vec<agentos_agent::shims::ShimRouting>,
 }
 
 impl AgentPipelineBuilder {
@@ -419,6 +546,7 @@ impl AgentPipelineBuilder {
             port_manager: None,
             librarian: None,
             code_index: None,
+            semantic_index: None,
             wasm_runtime: None,
             wasm_registry: None,
             semantic_router: None,
@@ -432,6 +560,8 @@ impl AgentPipelineBuilder {
             query_tx,
             query_rx: Some(query_rx),
             debug: false,
+            shim_kernel: Default::default(),
+            shim_routings: Vec::new(),
         }
     }
 
@@ -458,11 +588,15 @@ impl AgentPipelineBuilder {
     /// - ToolInterface stored for later ToolDefinition generation by `with_agents()`
     ///
     /// For WASM tools with empty WIT, falls back to schema-free registration.
+    ///
+    /// The handler is wrapped in [`Metered`] so its calls show up in the
+    /// per-tool latency / failure metrics, and in [`Traced`] like every
+    /// other listener.
     pub fn register_tool<T: ToolPeer>(mut self, listener_name: &str, tool: T) -> Result<Self, String> {
         let wit_str = tool.wit();
         if wit_str.is_empty() {
             // WASM tools — no WIT text, fall back to regular register
-            return self.register(listener_name, tool);
+            return self.register(listener_name, Metered::new(listener_name, tool));
         }
 
         let iface = agentos_wit::parser::parse_wit(wit_str)
@@ -483,7 +617,7 @@ impl AgentPipelineBuilder {
         self.registry.register(
             &def.name,
             &def.payload_tag,
-            tool,
+            Traced::new(&def.name, Metered::new(listener_name, tool)),
             def.is_agent,
             def.tools.clone(),
             &def.description,
@@ -494,6 +628,9 @@ impl AgentPipelineBuilder {
     }
 
     /// Register a handler for a listener defined in the organism.
+    ///
+    /// The handler is wrapped in [`Traced`] so each envelope it receives
+    /// opens a span in the thread's request trace.
     pub fn register<H: Handler>(mut self, listener_name: &str, handler: H) -> Result<Self, String> {
         let def = self
             .organism
@@ -504,7 +641,7 @@ impl AgentPipelineBuilder {
         self.registry.register(
             &def.name,
             &def.payload_tag,
-            handler,
+            Traced::new(&def.name, handler),
             def.is_agent,
             def.tools.clone(),
             &def.description,
@@ -634,12 +771,17 @@ impl AgentPipelineBuilder {
                 "file-read",
                 "file-write",
                 "file-edit",
+                "file-multi-edit",
+                "file-patch",
+                "file-move",
+                "file-copy",
                 "glob",
                 "grep",
                 "list-dir",
                 "bash",
                 "validate-organism",
                 "codebase-index",
+                "semantic-search",
                 "user",
             ];
             // Safe command tools are also known
@@ -726,10 +868,27 @@ impl AgentPipelineBuilder {
         Ok(self)
     }
 
-    /// Attach a CodeIndex service and register the `codebase-index` handler.
-    ///
-    /// The organism config must have a listener named `codebase-index`.
+    /// Attach the CodeIndex and SemanticIndex services and register the
+    /// `codebase-index` and `semantic-search` handlers for whichever of
+    /// those listeners the organism config defines. Grammars declared
+    /// under `grammars:` are loaded first. Calling this again is a no-op.
     pub fn with_code_index(mut self) -> Result<Self, String> {
+        if self.code_index.is_some() {
+            return Ok(self);
+        }
+        for g in self.organism.grammars() {
+            let grammar = agentos_treesitter::external::ExternalGrammar {
+                name: g.name.clone(),
+                extensions: g.extensions.clone(),
+                library: g.library.clone(),
+                symbol: g.symbol.clone(),
+                symbol_query: g.symbol_query.clone(),
+                reference_query: g.reference_query.clone(),
+            };
+            agentos_treesitter::languages::Lang::register(grammar, &self.organism.grammar_dir)
+                .map_err(|e| format!("grammar '{}': {e}", g.name))?;
+        }
+
         let index = CodeIndex::new();
         let arc = Arc::new(Mutex::new(index));
         self.code_index = Some(arc.clone());
@@ -740,6 +899,16 @@ impl AgentPipelineBuilder {
             self = self.register("codebase-index", handler)?;
         }
 
+        let provider = Arc::new(TfIdfProvider::hashed(
+            &[],
+            agentos_treesitter::semantic::DEFAULT_DIMENSIONS,
+        ));
+        let semantic = Arc::new(Mutex::new(SemanticIndex::new(provider)));
+        self.semantic_index = Some(semantic.clone());
+        if self.organism.get_listener("semantic-search").is_some() {
+            self = self.register_tool("semantic-search", SemanticSearchHandler::new(semantic))?;
+        }
+
         Ok(self)
     }
 
@@ -823,7 +992,7 @@ impl AgentPipelineBuilder {
                 Arc::new(component),
                 caps.clone(),
             );
-            self = self.register(name, peer)?;
+            self = self.register(name, Metered::new(name.as_str(), peer))?;
         }
 
         self.wasm_runtime = Some(runtime);
@@ -885,7 +1054,7 @@ impl AgentPipelineBuilder {
             registry.register(peer.metadata())
                 .map_err(|e| format!("Python tool '{}' registry failed: {e}", name))?;
 
-            self = self.register(name, peer)?;
+            self = self.register(name, Metered::new(name.as_str(), peer))?;
         }
 
         self.wasm_runtime = Some(runtime);
@@ -893,6 +1062,30 @@ impl AgentPipelineBuilder {
         Ok(self)
     }
 
+    /// Register the shim trainer on every listener whose handler is
+    /// `tools.shim_train.handle`, with the backend its `trainer:` block
+    /// selects (native when there is none).
+    pub fn with_shim_train_tools(mut self) -> Result<Self, String> {
+        let train_listeners: Vec<_> = self
+            .organism
+            .listeners()
+            .values()
+            .filter(|l| l.handler == "tools.shim_train.handle")
+            .map(|l| (l.name.clone(), l.shim_trainer.clone()))
+            .collect();
+
+        for (name, config) in train_listeners {
+            let trainer = match config {
+                None | Some(ShimTrainerConfig::Native) => ShimTrainer::Native,
+                Some(ShimTrainerConfig::Python { python, script_path }) => {
+                    ShimTrainer::Python { python, script_path }
+                }
+            };
+            self = self.register_tool(&name, ShimTrainTool::with_trainer(trainer))?;
+        }
+        Ok(self)
+    }
+
     /// Register triggers from the organism config.
     ///
     /// Collects all listeners with `handler: "trigger"` and creates a
@@ -947,8 +1140,9 @@ impl AgentPipelineBuilder {
     ///
     /// Requires an LLM pool to be attached first (form-filler calls Haiku).
     /// Creates a TF-IDF provider from all semantic descriptions, builds
-    /// an embedding index, and stores the router for later injection
-    /// into CodingAgentHandler.
+    /// an embedding index and a BM25 index over each tool's name,
+    /// description and semantic description, and stores the hybrid router
+    /// for later injection into CodingAgentHandler.
     pub fn with_semantic_router(mut self) -> Result<Self, String> {
         let pool = self.llm_pool.clone().ok_or_else(|| {
             "with_semantic_router() requires LLM pool — call with_llm_pool() first".to_string()
@@ -980,6 +1174,8 @@ impl AgentPipelineBuilder {
         // Build embedding index and register all tools
         let mut index = EmbeddingIndex::new(0.3); // default threshold
         routing::register_tools(&mut index, &provider, &self.organism);
+        let mut lexical = routing::lexical_index(0.3);
+        routing::register_tools_lexical(&mut lexical, &self.organism);
 
         // Build tool metadata from listener definitions
         let mut metadata = std::collections::HashMap::new();
@@ -1027,7 +1223,8 @@ impl AgentPipelineBuilder {
             };
 
         // Build router
-        let router = SemanticRouter::new(Box::new(provider), index, form_filler, metadata);
+        let router = SemanticRouter::new(Box::new(provider), index, form_filler, metadata)
+            .with_lexical(lexical, agentos_embedding::hybrid::HybridRanker::default());
         self.semantic_router = Some(router);
 
         Ok(self)
@@ -1180,7 +1377,9 @@ impl AgentPipelineBuilder {
             // it on the handler. Build fails loud when the named store
             // doesn't exist (typo defense — matches the design pin's
             // "fork the cognition by changing the name" pattern only
-            // in the shim-expert agent's create-store path).
+            // in the shim-expert agent's create-store path). Once
+            // `build()` connects the kernel, the routing handle takes
+            // over: live composition, canary split, runtime stats.
             if let Some(store_name) = def
                 .agent_config
                 .as_ref()
@@ -1188,6 +1387,9 @@ impl AgentPipelineBuilder {
             {
                 let parsed = load_shim_config_from_kernel(&self.data_dir, &def.name, &store_name)?;
                 handler.set_shim_config(Some(parsed));
+                let routing = agentos_agent::shims::ShimRouting::new(store_name, self.shim_kernel.clone());
+                self.shim_routings.push(routing.clone());
+                handler.set_shim_routing(Some(routing));
             }
 
             self = self.register(&def.name, handler)?;
@@ -1316,9 +1518,13 @@ impl AgentPipelineBuilder {
             );
         }
 
+        let kernel = Arc::new(Mutex::new(kernel));
+        // Agents' shim routing reads compositions and records stats here.
+        let _ = self.shim_kernel.set(kernel.clone());
+
         Ok(AgentPipeline {
             pipeline,
-            kernel: Arc::new(Mutex::new(kernel)),
+            kernel,
             organism: self.organism,
             security,
             event_tx: self.event_tx,
@@ -1326,7 +1532,11 @@ impl AgentPipelineBuilder {
             approval_rx: self.approval_rx,
             query_rx: self.query_rx,
             trigger_runtime: self.trigger_runtime,
+            code_index: self.code_index,
+            semantic_index: self.semantic_index,
             data_dir: self.data_dir,
+            shim_routings: self.shim_routings,
+            shim_flusher: None,
         })
     }
 }
@@ -2676,6 +2886,59 @@ profiles:
         assert!(echo.wasm.is_some());
     }
 
+    #[tokio::test]
+    async fn shim_train_tool_registered_from_listener_config() {
+        let dir = TempDir::new().unwrap();
+        let org = parse_organism(
+            r#"
+organism:
+  name: test-shim-train
+
+listeners:
+  - name: shim-train
+    payload_class: tools.ShimTrainRequest
+    handler: tools.shim_train.handle
+    description: "Train shims"
+    trainer:
+      backend: python
+      python: python3
+      script_path: scripts/train_shim.py
+
+profiles:
+  admin:
+    linux_user: agentos-admin
+    listeners: [shim-train]
+    journal: retain_forever
+"#,
+        )
+        .unwrap();
+
+        let builder = AgentPipelineBuilder::new(org, &dir.path().join("data"))
+            .with_shim_train_tools()
+            .unwrap();
+        assert!(builder.tool_interfaces.contains_key("shim-train"));
+        builder.build().unwrap();
+    }
+
+    #[tokio::test]
+    async fn shipped_shim_expert_organism_registers_shim_train() {
+        let dir = TempDir::new().unwrap();
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../organisms/shim-expert.yaml");
+        let org = agentos_organism::parser::load_organism(&path).unwrap();
+        assert_eq!(
+            org.get_listener("shim-train").unwrap().shim_trainer,
+            Some(ShimTrainerConfig::Native)
+        );
+
+        let builder = AgentPipelineBuilder::new(org, &dir.path().join("data"))
+            .with_shim_train_tools()
+            .unwrap();
+        assert!(builder.tool_interfaces.contains_key("shim-train"));
+        let pipeline = builder.build().unwrap();
+        let listener = pipeline.organism().get_listener("shim-train").unwrap();
+        assert_eq!(listener.handler, "tools.shim_train.handle");
+    }
+
     #[tokio::test]
     async fn wasm_tool_security_scoping() {
         let dir = TempDir::new().unwrap();
@@ -3061,6 +3324,7 @@ profiles:
 
         let token = PipelineEvent::TokenUsage {
             thread_id: "t1".into(),
+            model: "claude-sonnet-4-6".into(),
             input_tokens: 100,
             output_tokens: 50,
         };
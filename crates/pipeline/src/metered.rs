@@ -0,0 +1,53 @@
+//! Metered — wraps a tool handler to record call latency and outcome.
+//!
+//! Applied to every tool registered through the builder (native,
+//! WASM, Python), so `agentos_tool_call_*` covers them uniformly
+//! without each tool timing itself. A call counts as an error when the
+//! handler returns `Err` or replies with `<success>false</success>`.
+//! The same outcome marks the call's `tool` span.
+
+use async_trait::async_trait;
+use rust_pipeline::prelude::*;
+use tracing::Instrument;
+
+use agentos_events::extract_tag;
+
+/// A tool handler plus the listener name its metrics are labelled with.
+pub struct Metered<H> {
+    tool: String,
+    inner: H,
+}
+
+impl<H: Handler> Metered<H> {
+    pub fn new(tool: impl Into<String>, inner: H) -> Self {
+        Self {
+            tool: tool.into(),
+            inner,
+        }
+    }
+}
+
+#[async_trait]
+impl<H: Handler> Handler for Metered<H> {
+    async fn handle(&self, payload: ValidatedPayload, ctx: HandlerContext) -> HandlerResult {
+        let span = tracing::info_span!(
+            "tool",
+            tool = %self.tool,
+            thread_id = %ctx.thread_id,
+            otel.status_code = tracing::field::Empty,
+        );
+        let started = std::time::Instant::now();
+        let result = self.inner.handle(payload, ctx).instrument(span.clone()).await;
+        let ok = match &result {
+            Ok(HandlerResponse::Reply { payload_xml }) => {
+                extract_tag(&String::from_utf8_lossy(payload_xml), "success").as_deref()
+                    != Some("false")
+            }
+            Ok(_) => true,
+            Err(_) => false,
+        };
+        agentos_telemetry::record_tool_call(&self.tool, started.elapsed(), ok);
+        agentos_telemetry::trace::record_outcome(&span, ok);
+        result
+    }
+}
@@ -41,6 +41,17 @@ impl PipelineRuntime {
             ingress_tx,
         }
     }
+
+    /// The organism's first declared profile — what platform instances
+    /// run under until per-namespace profile resolution lands.
+    fn default_profile(&self) -> String {
+        self.organism
+            .profile_names()
+            .into_iter()
+            .next()
+            .unwrap_or("default")
+            .to_string()
+    }
 }
 
 #[async_trait::async_trait]
@@ -100,13 +111,7 @@ impl Runtime for PipelineRuntime {
         address: &Address,
         organism: &str,
     ) -> Result<(), String> {
-        let profile = self
-            .organism
-            .profile_names()
-            .into_iter()
-            .next()
-            .unwrap_or("default")
-            .to_string();
+        let profile = self.default_profile();
 
         let mut kernel = self.kernel.lock().await;
         kernel
@@ -136,6 +141,9 @@ impl Runtime for PipelineRuntime {
     /// hierarchy (namespaces, instance keys, buffers) is collapsed at
     /// this seam to a single listener name; the registry preserves the
     /// rest of the addressing structure for materialization.
+    ///
+    /// Each delivery opens a request trace on the instance's thread,
+    /// under whatever span the caller is in (HTTP request, trigger fire).
     async fn deliver(
         &self,
         thread_id: &str,
@@ -151,10 +159,14 @@ impl Runtime for PipelineRuntime {
         let raw = rust_pipeline::prelude::build_envelope(from, to, thread_id, &envelope.body)
             .map_err(|e| format!("envelope build failed: {e}"))?;
 
-        self.ingress_tx
-            .send(raw)
-            .await
-            .map_err(|e| format!("ingress send failed: {e}"))?;
+        // Registered before the send so the first dispatch finds it;
+        // dropped again if the message never makes it in.
+        agentos_telemetry::trace::start_request(thread_id, to, &self.default_profile());
+
+        if let Err(e) = self.ingress_tx.send(raw).await {
+            agentos_telemetry::trace::finish(thread_id, to);
+            return Err(format!("ingress send failed: {e}"));
+        }
 
         Ok(())
     }
@@ -184,6 +196,7 @@ use agentos_platform::router::Envelope;
 use agentos_platform::template;
 use agentos_trigger::TriggerEvent;
 use agentos_trigger::runtime::TriggerPayload;
+use tracing::Instrument;
 
 /// Build a variable map from a TriggerEvent for template expansion.
 fn trigger_vars(event: &TriggerEvent) -> HashMap<String, String> {
@@ -278,59 +291,76 @@ pub async fn process_trigger_events<R: Runtime + 'static>(
     shared_router: Arc<SharedRouter<R>>,
 ) {
     while let Some(event) = trigger_rx.recv().await {
-        // Look up the trigger's config from the organism to get send_to/message.
-        let config = organism
-            .get_listener(&event.trigger_name)
-            .and_then(|l| l.trigger.as_ref());
-
-        let (send_to, message, source_ns) = match config {
-            Some(cfg) => (
-                cfg.send_to.as_deref(),
-                cfg.message.as_deref(),
-                cfg.source_namespace.as_deref(),
-            ),
-            None => {
-                tracing::warn!(
-                    trigger = &event.trigger_name,
-                    "Trigger fired but no config found in organism"
-                );
-                continue;
-            }
-        };
+        agentos_telemetry::record_trigger_fire(&event.trigger_name);
+        process_trigger_event(&event, &organism, &shared_router)
+            .instrument(tracing::info_span!(
+                "trigger.fire",
+                trigger = %event.trigger_name,
+                target = %event.target,
+            ))
+            .await;
+    }
 
-        // If send_to is present, route through the platform router.
-        if let Some(send_to_tmpl) = send_to {
-            if let Some(envelope) = trigger_to_envelope(&event, send_to_tmpl, message, source_ns) {
-                match shared_router.send_to(&envelope).await {
-                    Ok(()) => {
-                        tracing::info!(
-                            trigger = &event.trigger_name,
-                            address = envelope.to.raw(),
-                            "Trigger routed through platform"
-                        );
-                    }
-                    Err(e) => {
-                        tracing::error!(
-                            trigger = &event.trigger_name,
-                            address = envelope.to.raw(),
-                            error = %e,
-                            "Trigger routing failed"
-                        );
-                    }
-                }
-            }
-        } else {
-            // Old-style trigger: no send_to, uses raw pipeline dispatch.
-            // TODO: inject into pipeline ingress channel as before.
-            tracing::debug!(
+    tracing::info!("Trigger event processing loop ended");
+}
+
+/// Route one trigger event. Runs inside the fire's span, so the
+/// request it delivers becomes part of the trigger's trace.
+async fn process_trigger_event<R: Runtime + 'static>(
+    event: &TriggerEvent,
+    organism: &Organism,
+    shared_router: &SharedRouter<R>,
+) {
+    // Look up the trigger's config from the organism to get send_to/message.
+    let config = organism
+        .get_listener(&event.trigger_name)
+        .and_then(|l| l.trigger.as_ref());
+
+    let (send_to, message, source_ns) = match config {
+        Some(cfg) => (
+            cfg.send_to.as_deref(),
+            cfg.message.as_deref(),
+            cfg.source_namespace.as_deref(),
+        ),
+        None => {
+            tracing::warn!(
                 trigger = &event.trigger_name,
-                target = &event.target,
-                "Trigger fired (legacy dispatch, not routed through platform)"
+                "Trigger fired but no config found in organism"
             );
+            return;
         }
-    }
+    };
 
-    tracing::info!("Trigger event processing loop ended");
+    // If send_to is present, route through the platform router.
+    if let Some(send_to_tmpl) = send_to {
+        if let Some(envelope) = trigger_to_envelope(event, send_to_tmpl, message, source_ns) {
+            match shared_router.send_to(&envelope).await {
+                Ok(()) => {
+                    tracing::info!(
+                        trigger = &event.trigger_name,
+                        address = envelope.to.raw(),
+                        "Trigger routed through platform"
+                    );
+                }
+                Err(e) => {
+                    tracing::error!(
+                        trigger = &event.trigger_name,
+                        address = envelope.to.raw(),
+                        error = %e,
+                        "Trigger routing failed"
+                    );
+                }
+            }
+        }
+    } else {
+        // Old-style trigger: no send_to, uses raw pipeline dispatch.
+        // TODO: inject into pipeline ingress channel as before.
+        tracing::debug!(
+            trigger = &event.trigger_name,
+            target = &event.target,
+            "Trigger fired (legacy dispatch, not routed through platform)"
+        );
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,46 @@
+//! Traced — wraps every listener handler in a per-envelope span.
+//!
+//! rust-pipeline runs each dispatch on its own task, so a handler never
+//! inherits the span of the handler that sent it the envelope. The
+//! wrapper looks the thread up in the telemetry registry instead and
+//! opens a `dispatch` span under that thread's request; everything the
+//! handler does (agent turn, LLM call, tool execution) nests inside.
+//! When the listener the request was addressed to replies, the request
+//! span is released.
+
+use async_trait::async_trait;
+use rust_pipeline::prelude::*;
+use tracing::Instrument;
+
+use agentos_telemetry::trace;
+
+/// A handler plus the listener name its spans are labelled with.
+pub struct Traced<H> {
+    listener: String,
+    inner: H,
+}
+
+impl<H: Handler> Traced<H> {
+    pub fn new(listener: impl Into<String>, inner: H) -> Self {
+        Self {
+            listener: listener.into(),
+            inner,
+        }
+    }
+}
+
+#[async_trait]
+impl<H: Handler> Handler for Traced<H> {
+    async fn handle(&self, payload: ValidatedPayload, ctx: HandlerContext) -> HandlerResult {
+        let thread_id = ctx.thread_id.clone();
+        let span = trace::dispatch_span(&thread_id, &self.listener, &ctx.from);
+        let result = self.inner.handle(payload, ctx).instrument(span.clone()).await;
+        trace::record_outcome(&span, result.is_ok());
+        // A `Send` means the turn continues on this thread (tool call,
+        // agent-to-agent); anything else ends this listener's part.
+        if !matches!(result, Ok(HandlerResponse::Send { .. })) {
+            trace::finish(&thread_id, &self.listener);
+        }
+        result
+    }
+}
@@ -242,7 +242,13 @@ impl BufferStore {
 /// `[`/`]` from keyed buffer IDs are replaced with `-` so the thread_id
 /// stays safe in trace logs and any path-derived contexts.
 fn derive_buffer_thread_id(instance_thread_id: &str, id: &BufferId) -> String {
-    let canonical = id.canonical();
+    buffer_thread_id(instance_thread_id, &id.canonical())
+}
+
+/// The thread_id a buffer with canonical name `canonical` gets under
+/// `instance_thread_id`. Lets callers find a buffer's kernel state
+/// after its instance (and buffer store) is gone.
+pub fn buffer_thread_id(instance_thread_id: &str, canonical: &str) -> String {
     let label: String = canonical
         .chars()
         .map(|c| if matches!(c, '[' | ']') { '-' } else { c })
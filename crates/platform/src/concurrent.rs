@@ -21,6 +21,7 @@ use std::time::Duration;
 use tokio::sync::Mutex;
 
 use crate::address::Address;
+use crate::buffers::{BufferId, BufferInfo};
 use crate::registry::{InstanceInfo, InstanceRegistry, RegistryError};
 use crate::router::{Envelope, Router, RouterError, Runtime};
 
@@ -111,6 +112,43 @@ impl<R: Runtime + 'static> SharedRouter<R> {
         router.registry().list().into_iter().cloned().collect()
     }
 
+    /// Look up a single live instance by address.
+    pub async fn lookup(&self, address: &Address) -> Option<InstanceInfo> {
+        let router = self.inner.lock().await;
+        router.registry().lookup(address).cloned()
+    }
+
+    /// Drop one buffer from a live instance's buffer store. Returns the
+    /// removed buffer, or `None` if the instance or buffer isn't live.
+    /// Kernel state for the buffer's thread is the caller's to clean up.
+    pub async fn remove_buffer(&self, address: &Address, id: &BufferId) -> Option<BufferInfo> {
+        let mut router = self.inner.lock().await;
+        router
+            .registry_mut()
+            .lookup_mut(address)
+            .and_then(|info| info.buffers.remove(id))
+    }
+
+    /// Instance thread_ids evicted or killed at `address` whose kernel
+    /// state hasn't been erased.
+    pub async fn retired_threads(&self, address: &Address) -> Vec<String> {
+        let router = self.inner.lock().await;
+        router.registry().retired_threads(address).to_vec()
+    }
+
+    /// The id a retired instance at `address` had open on buffer
+    /// thread `thread_id`, if the registry recorded one.
+    pub async fn retired_buffer(&self, address: &Address, thread_id: &str) -> Option<BufferId> {
+        let router = self.inner.lock().await;
+        router.registry().retired_buffer(address, thread_id).cloned()
+    }
+
+    /// Forget `address`'s retired thread_ids after erasing them.
+    pub async fn forget_retired(&self, address: &Address) -> Vec<String> {
+        let mut router = self.inner.lock().await;
+        router.registry_mut().forget_retired(address)
+    }
+
     /// Get instance count.
     pub async fn count(&self) -> usize {
         let router = self.inner.lock().await;
@@ -338,4 +376,28 @@ mod tests {
         let list = shared.list().await;
         assert_eq!(list.len(), 2);
     }
+
+    #[tokio::test]
+    async fn lookup_and_remove_buffer() {
+        let runtime = TestRuntime::new();
+        let shared = SharedRouter::new(0, runtime, Duration::from_secs(60));
+        let alice = Address::parse("concierge[alice]").unwrap();
+
+        assert!(shared.lookup(&alice).await.is_none());
+        shared.send_to(&envelope("concierge[alice]")).await.unwrap();
+
+        let info = shared.lookup(&alice).await.unwrap();
+        assert_eq!(info.buffers.count(), 1);
+
+        let removed = shared
+            .remove_buffer(&alice, &BufferId::default_buffer())
+            .await
+            .unwrap();
+        assert_eq!(removed.id, BufferId::default_buffer());
+        assert_eq!(shared.lookup(&alice).await.unwrap().buffers.count(), 0);
+        assert!(shared
+            .remove_buffer(&alice, &BufferId::default_buffer())
+            .await
+            .is_none());
+    }
 }
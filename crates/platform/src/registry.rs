@@ -25,8 +25,8 @@ use std::path::PathBuf;
 use std::time::{Duration, Instant};
 
 use crate::address::Address;
-use crate::buffers::BufferStore;
-use crate::snapshot::{self, InstanceRecord, RegistrySnapshot};
+use crate::buffers::{BufferId, BufferStore};
+use crate::snapshot::{self, InstanceRecord, RegistrySnapshot, RetiredBufferRecord, RetiredRecord};
 
 /// Lifecycle policy for an agent instance.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -123,6 +123,10 @@ pub struct InstanceRegistry {
     instances: HashMap<String, InstanceInfo>,
     /// Maximum number of concurrent instances (0 = unlimited).
     max_instances: usize,
+    /// Address (as raw string) → instances evicted or killed there.
+    /// Their kernel threads outlive the registry entry until something
+    /// erases them; see [`Self::forget_retired`].
+    retired: HashMap<String, Retired>,
     /// Counter for generating unique thread_ids.
     next_thread_id: u64,
     /// Optional snapshot path. When `Some`, materialize / kill / evict
@@ -130,6 +134,16 @@ pub struct InstanceRegistry {
     snapshot_path: Option<PathBuf>,
 }
 
+/// What an address left behind when its instances were evicted or
+/// killed.
+#[derive(Debug, Default)]
+struct Retired {
+    /// Instance thread_ids, oldest first.
+    thread_ids: Vec<String>,
+    /// Buffer thread_id → the buffer id it was opened under.
+    buffers: HashMap<String, BufferId>,
+}
+
 impl InstanceRegistry {
     /// Create a new empty in-memory registry. Use [`Self::open`] for
     /// the persistent variant.
@@ -137,6 +151,7 @@ impl InstanceRegistry {
         Self {
             instances: HashMap::new(),
             max_instances,
+            retired: HashMap::new(),
             next_thread_id: 1,
             snapshot_path: None,
         }
@@ -151,9 +166,21 @@ impl InstanceRegistry {
     /// with replayed ones.
     pub fn open(snapshot_path: PathBuf, max_instances: usize) -> Self {
         let mut instances: HashMap<String, InstanceInfo> = HashMap::new();
+        let mut retired: HashMap<String, Retired> = HashMap::new();
         let mut max_seen: u64 = 0;
 
         if let Ok(Some(snap)) = snapshot::read(&snapshot_path) {
+            for rec in snap.retired {
+                for thread_id in &rec.thread_ids {
+                    max_seen = max_seen.max(thread_id_suffix(thread_id).unwrap_or(0));
+                }
+                let buffers = rec
+                    .buffers
+                    .into_iter()
+                    .map(|b| (b.thread_id, BufferId { name: b.name, key: b.key }))
+                    .collect();
+                retired.insert(rec.address_raw, Retired { thread_ids: rec.thread_ids, buffers });
+            }
             for rec in snap.instances {
                 let address = match Address::parse(&rec.address_raw) {
                     Ok(a) => a,
@@ -192,12 +219,15 @@ impl InstanceRegistry {
             }
         }
 
-        Self {
+        let registry = Self {
             instances,
             max_instances,
+            retired,
             next_thread_id: max_seen + 1,
             snapshot_path: Some(snapshot_path),
-        }
+        };
+        registry.publish_tier_gauges();
+        registry
     }
 
     /// Force a snapshot write. Returns `Ok(())` when no snapshot path
@@ -219,7 +249,24 @@ impl InstanceRegistry {
                 cache_shards: info.cache_shards.clone(),
             })
             .collect();
-        snapshot::write_atomic(path, &RegistrySnapshot::new(records))
+        let retired = self
+            .retired
+            .iter()
+            .map(|(address_raw, r)| RetiredRecord {
+                address_raw: address_raw.clone(),
+                thread_ids: r.thread_ids.clone(),
+                buffers: r
+                    .buffers
+                    .iter()
+                    .map(|(thread_id, id)| RetiredBufferRecord {
+                        thread_id: thread_id.clone(),
+                        name: id.name.clone(),
+                        key: id.key.clone(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        snapshot::write_atomic(path, &RegistrySnapshot { retired, ..RegistrySnapshot::new(records) })
     }
 
     /// Best-effort flush — logs but doesn't propagate IO errors.
@@ -235,6 +282,14 @@ impl InstanceRegistry {
         }
     }
 
+    /// Refresh the `agentos_platform_instances` gauges. Called after
+    /// every tier change — O(instances), but tier changes are rare next
+    /// to message traffic (a plain `touch` doesn't publish).
+    fn publish_tier_gauges(&self) {
+        let (active, shelved, folded) = self.count_by_tier();
+        agentos_telemetry::set_platform_instances(active, shelved, folded);
+    }
+
     /// Look up an instance by address. Returns None if not materialized.
     pub fn lookup(&self, address: &Address) -> Option<&InstanceInfo> {
         self.instances.get(address.raw())
@@ -288,6 +343,7 @@ impl InstanceRegistry {
 
         self.instances.insert(key, info);
         self.flush_quiet();
+        self.publish_tier_gauges();
         Ok(thread_id)
     }
 
@@ -302,6 +358,7 @@ impl InstanceRegistry {
         info.last_accessed = Instant::now();
         if info.tier == Tier::Shelved {
             info.tier = Tier::Active;
+            self.publish_tier_gauges();
         }
         Ok(())
     }
@@ -315,6 +372,7 @@ impl InstanceRegistry {
 
         if info.tier == Tier::Active {
             info.tier = Tier::Shelved;
+            self.publish_tier_gauges();
         }
         Ok(())
     }
@@ -327,6 +385,7 @@ impl InstanceRegistry {
             .ok_or_else(|| RegistryError::NotFound(address.raw().to_string()))?;
 
         info.tier = Tier::Folded;
+        self.publish_tier_gauges();
         Ok(())
     }
 
@@ -337,7 +396,9 @@ impl InstanceRegistry {
             .instances
             .remove(address.raw())
             .ok_or_else(|| RegistryError::NotFound(address.raw().to_string()))?;
+        self.retire(&info);
         self.flush_quiet();
+        self.publish_tier_gauges();
         Ok(info)
     }
 
@@ -385,20 +446,54 @@ impl InstanceRegistry {
             }
         }
 
-        let evicted: Vec<Address> = to_evict
-            .iter()
-            .filter_map(|key| {
-                self.instances
-                    .remove(key)
-                    .map(|info| info.address)
-            })
-            .collect();
+        let mut evicted = Vec::with_capacity(to_evict.len());
+        for key in &to_evict {
+            if let Some(info) = self.instances.remove(key) {
+                self.retire(&info);
+                evicted.push(info.address);
+            }
+        }
         if !evicted.is_empty() {
             self.flush_quiet();
+            self.publish_tier_gauges();
         }
         evicted
     }
 
+    /// Remember `info`'s thread_id, and the ids of the buffers it had
+    /// open, under its address once it leaves the live set.
+    fn retire(&mut self, info: &InstanceInfo) {
+        let retired = self.retired.entry(info.address.raw().to_string()).or_default();
+        if !retired.thread_ids.contains(&info.thread_id) {
+            retired.thread_ids.push(info.thread_id.clone());
+        }
+        for b in info.buffers.list() {
+            retired.buffers.insert(b.thread_id.clone(), b.id.clone());
+        }
+    }
+
+    /// Instance thread_ids evicted or killed at `address`, oldest first.
+    pub fn retired_threads(&self, address: &Address) -> &[String] {
+        self.retired.get(address.raw()).map_or(&[], |r| r.thread_ids.as_slice())
+    }
+
+    /// The id of the buffer that held kernel thread `thread_id` when an
+    /// instance at `address` was retired. `None` for buffers the
+    /// registry never saw, e.g. ones only replayed by the kernel.
+    pub fn retired_buffer(&self, address: &Address, thread_id: &str) -> Option<&BufferId> {
+        self.retired.get(address.raw())?.buffers.get(thread_id)
+    }
+
+    /// Drop the retired thread_ids of `address` once their kernel state
+    /// has been erased. Returns what was dropped.
+    pub fn forget_retired(&mut self, address: &Address) -> Vec<String> {
+        let forgotten = self.retired.remove(address.raw()).unwrap_or_default().thread_ids;
+        if !forgotten.is_empty() {
+            self.flush_quiet();
+        }
+        forgotten
+    }
+
     /// Total number of materialized instances.
     pub fn count(&self) -> usize {
         self.instances.len()
@@ -747,4 +842,51 @@ mod tests {
         assert!(reg2.lookup(&addr("bob[alice]")).is_none());
         assert!(reg2.lookup(&addr("bob[bob]")).is_some());
     }
+
+    #[test]
+    fn evicted_and_killed_threads_are_retired_until_forgotten() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("registry.json");
+
+        let mut reg = InstanceRegistry::open(path.clone(), 0);
+        let idle = MaterializeOpts {
+            lifetime: Lifetime::UntilIdle(Duration::from_secs(0)),
+            ..default_opts("bob")
+        };
+        let first = reg.materialize(addr("bob[alice]"), idle).unwrap();
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(reg.evict_idle().len(), 1);
+        let second = reg.materialize(addr("bob[alice]"), default_opts("bob")).unwrap();
+        reg.kill(&addr("bob[alice]")).unwrap();
+        assert_eq!(reg.retired_threads(&addr("bob[alice]")), [first.clone(), second.clone()]);
+
+        // Retired ids survive a restart and keep the counter ahead of them.
+        let mut reg = InstanceRegistry::open(path.clone(), 0);
+        assert_eq!(reg.retired_threads(&addr("bob[alice]")), [first.clone(), second.clone()]);
+        let third = reg.materialize(addr("bob[carol]"), default_opts("bob")).unwrap();
+        assert!(third != first && third != second);
+
+        assert_eq!(reg.forget_retired(&addr("bob[alice]")), vec![first, second]);
+        let reg = InstanceRegistry::open(path, 0);
+        assert!(reg.retired_threads(&addr("bob[alice]")).is_empty());
+    }
+
+    #[test]
+    fn retired_buffers_keep_their_ids_across_restart() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("registry.json");
+
+        let mut reg = InstanceRegistry::open(path.clone(), 0);
+        let root = reg.materialize(addr("bob[alice]"), default_opts("bob")).unwrap();
+        let id = BufferId { name: "help-desk".to_string(), key: Some("email-issue".to_string()) };
+        let info = reg.lookup_mut(&addr("bob[alice]")).unwrap();
+        let thread_id = info.buffers.get_or_create(id.clone(), &root).0.thread_id.clone();
+        // The flattened label no longer says where the name ends.
+        assert_eq!(thread_id, format!("{root}/buf-help-desk-email-issue"));
+        reg.kill(&addr("bob[alice]")).unwrap();
+
+        let reg = InstanceRegistry::open(path, 0);
+        assert_eq!(reg.retired_buffer(&addr("bob[alice]"), &thread_id), Some(&id));
+        assert!(reg.retired_buffer(&addr("bob[alice]"), &format!("{root}/buf-dm")).is_none());
+    }
 }
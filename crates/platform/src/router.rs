@@ -316,9 +316,11 @@ impl Router {
 
         // Best-effort cleanup — if evict_instance fails, the instance is still
         // removed from the registry (it timed out, we're not going to keep it).
+        // It's gone from the live set by now; its thread_id is the one the
+        // registry just retired.
         for addr in &evicted {
-            if let Some(info) = self.registry.lookup(addr) {
-                let _ = runtime.evict_instance(&info.thread_id).await;
+            if let Some(thread_id) = self.registry.retired_threads(addr).last() {
+                let _ = runtime.evict_instance(thread_id).await;
             }
         }
 
@@ -683,4 +685,28 @@ mod tests {
         assert!(!router.registry().is_materialized(&addr));
         assert_eq!(runtime.evicted.lock().unwrap().len(), 1);
     }
+
+    #[tokio::test]
+    async fn evict_idle_releases_the_evicted_thread() {
+        let reg = InstanceRegistry::new(0);
+        let mut router = Router::new(reg);
+        let mut runtime = MockRuntime::new();
+        runtime.organisms.insert(
+            "flash".to_string(),
+            OrganismMeta {
+                default_lifetime: Lifetime::UntilIdle(std::time::Duration::ZERO),
+                shard_pattern: vec![],
+                ephemeral: false,
+            },
+        );
+
+        router.send_to(&envelope("flash[alice]"), &runtime).await.unwrap();
+        let thread_id = runtime.allocated.lock().unwrap()[0].clone();
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+
+        let evicted = router.evict_idle(&runtime).await;
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(*runtime.evicted.lock().unwrap(), vec![thread_id.clone()]);
+        assert_eq!(router.registry().retired_threads(&evicted[0]), [thread_id]);
+    }
 }
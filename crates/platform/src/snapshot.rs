@@ -4,7 +4,9 @@
 //! address↔thread_id bindings, organism templates, lifecycle policies,
 //! cache shard names. To make instances survive process restart, the
 //! registry writes a snapshot to disk on every materialize / kill /
-//! idle-eviction; on boot, it replays the snapshot back.
+//! idle-eviction; on boot, it replays the snapshot back. Evicted and
+//! killed instances leave their thread_ids behind under `retired`, so
+//! an erase can still find kernel state the live registry has dropped.
 //!
 //! # Format
 //!
@@ -44,6 +46,10 @@ use crate::registry::Lifetime;
 pub struct RegistrySnapshot {
     pub version: u32,
     pub instances: Vec<InstanceRecord>,
+    /// Instances that were evicted or killed but whose kernel threads
+    /// were never erased. Absent in snapshots written before it existed.
+    #[serde(default)]
+    pub retired: Vec<RetiredRecord>,
 }
 
 impl RegistrySnapshot {
@@ -54,6 +60,7 @@ impl RegistrySnapshot {
         Self {
             version: Self::CURRENT_VERSION,
             instances,
+            retired: Vec::new(),
         }
     }
 }
@@ -79,6 +86,28 @@ pub struct InstanceRecord {
     pub cache_shards: Vec<String>,
 }
 
+/// Instance thread_ids an address held before it was evicted or killed.
+/// Buffer threads live under `<thread_id>/buf-*`; `buffers` names the
+/// ones the instance had open when it left, since the thread_id alone
+/// can't tell `help[email-issue]` from a buffer called `help-email-issue`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RetiredRecord {
+    pub address_raw: String,
+    pub thread_ids: Vec<String>,
+    /// Absent in snapshots written before it existed.
+    #[serde(default)]
+    pub buffers: Vec<RetiredBufferRecord>,
+}
+
+/// A buffer of a retired instance: its kernel thread and the id it
+/// was opened under.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RetiredBufferRecord {
+    pub thread_id: String,
+    pub name: String,
+    pub key: Option<String>,
+}
+
 /// Wire form of [`Lifetime`] — `Duration` round-trips through serde
 /// natively so this is a thin tag-on-discriminant mirror. Kept separate
 /// from `Lifetime` itself to avoid dragging serde into the in-memory
@@ -230,6 +259,28 @@ mod tests {
         assert_eq!(loaded, snap);
     }
 
+    #[test]
+    fn retired_round_trips_and_defaults_when_absent() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("registry.json");
+
+        let mut snap = sample_snapshot();
+        snap.retired.push(RetiredRecord {
+            address_raw: "bob[carol]".to_string(),
+            thread_ids: vec!["inst-000003".to_string()],
+            buffers: vec![RetiredBufferRecord {
+                thread_id: "inst-000003/buf-help-email-issue".to_string(),
+                name: "help".to_string(),
+                key: Some("email-issue".to_string()),
+            }],
+        });
+        write_atomic(&path, &snap).unwrap();
+        assert_eq!(read(&path).unwrap().unwrap(), snap);
+
+        fs::write(&path, b"{\"version\": 1, \"instances\": []}").unwrap();
+        assert!(read(&path).unwrap().unwrap().retired.is_empty());
+    }
+
     #[test]
     fn read_missing_file_returns_none() {
         let dir = TempDir::new().unwrap();
@@ -155,6 +155,7 @@ mod tests {
             buffer: None,
             python: None,
             trigger: None,
+            shim_trainer: None,
         })
         .unwrap();
         org.register_listener(ListenerDef {
@@ -174,6 +175,7 @@ mod tests {
             buffer: None,
             python: None,
             trigger: None,
+            shim_trainer: None,
         })
         .unwrap();
 
@@ -187,6 +189,7 @@ mod tests {
             allow_all: false,
             journal_retention: RetentionPolicy::Forever,
             network: vec!["llm-pool".into()],
+            mount: Default::default(),
         })
         .unwrap();
 
@@ -197,6 +200,7 @@ mod tests {
             allow_all: false,
             journal_retention: RetentionPolicy::PruneOnDelivery,
             network: vec![],
+            mount: Default::default(),
         })
         .unwrap();
 
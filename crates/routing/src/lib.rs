@@ -3,14 +3,21 @@
 //! The router intercepts Opus's natural language output, matches it against
 //! tool descriptions via embedding similarity, and dispatches invisibly.
 //! No tool call ceremony. Just thought, and result.
+//!
+//! With a lexical index attached, matching is hybrid: BM25 over each tool's
+//! name, description and semantic description, fused with the embedding
+//! ranking by reciprocal rank fusion.
 
 pub mod form_filler;
 pub mod local_engine;
 
 use std::collections::HashMap;
 
+use agentos_embedding::bm25::Bm25Index;
+use agentos_embedding::hybrid::HybridRanker;
+use agentos_embedding::tokenize::Tokenizer;
 use agentos_embedding::{EmbeddingIndex, EmbeddingProvider};
-use agentos_organism::Organism;
+use agentos_organism::{ListenerDef, Organism};
 
 use form_filler::{FormFillResult, FormFillStrategy};
 
@@ -31,6 +38,45 @@ pub fn register_tools(
     }
 }
 
+/// BM25 field weights for tool matching: a hit on the tool's name counts
+/// double, its one-line description half again.
+pub const FIELD_WEIGHTS: &[(&str, f32)] = &[
+    ("name", 2.0),
+    ("description", 1.5),
+    ("semantic_description", 1.0),
+];
+
+/// The fields of a listener matched lexically.
+pub fn tool_fields(listener: &ListenerDef) -> Vec<(&str, &str)> {
+    let mut fields = vec![
+        ("name", listener.name.as_str()),
+        ("description", listener.description.as_str()),
+    ];
+    if let Some(ref desc) = listener.semantic_description {
+        fields.push(("semantic_description", desc.as_str()));
+    }
+    fields
+}
+
+/// An empty BM25 index set up for tool matching: stemmed, identifier-aware
+/// tokens and [`FIELD_WEIGHTS`].
+pub fn lexical_index(threshold: f32) -> Bm25Index {
+    FIELD_WEIGHTS.iter().fold(
+        Bm25Index::new(threshold).with_tokenizer(Tokenizer::new().with_stemming(true)),
+        |index, (field, weight)| index.with_field_weight(field, *weight),
+    )
+}
+
+/// Register all tools with semantic descriptions into a lexical index,
+/// the counterpart of [`register_tools`].
+pub fn register_tools_lexical(index: &mut Bm25Index, organism: &Organism) {
+    for listener in organism.listeners().values() {
+        if listener.semantic_description.is_some() {
+            index.register(&listener.name, &tool_fields(listener));
+        }
+    }
+}
+
 /// Metadata for a registered tool (used by the router for form-filling).
 #[derive(Debug, Clone)]
 pub struct ToolMetadata {
@@ -59,6 +105,8 @@ pub enum RouteDecision {
 pub struct SemanticRouter {
     provider: Box<dyn EmbeddingProvider>,
     index: EmbeddingIndex,
+    /// Lexical index and fusion settings, for hybrid matching.
+    lexical: Option<(Bm25Index, HybridRanker)>,
     form_filler: Box<dyn FormFillStrategy>,
     /// Tool metadata: name → (description, XML template, payload tag)
     tool_metadata: HashMap<String, ToolMetadata>,
@@ -75,11 +123,19 @@ impl SemanticRouter {
         Self {
             provider,
             index,
+            lexical: None,
             form_filler,
             tool_metadata,
         }
     }
 
+    /// Match hybridly: fuse `lexical` rankings with the embedding ones.
+    /// A tool passing either index's threshold can match.
+    pub fn with_lexical(mut self, lexical: Bm25Index, ranker: HybridRanker) -> Self {
+        self.lexical = Some((lexical, ranker));
+        self
+    }
+
     /// Route LLM output: tool call or response?
     ///
     /// `allowed_tools` pre-filters candidates by security profile.
@@ -97,7 +153,14 @@ impl SemanticRouter {
         let query = self.provider.embed(text);
 
         // Search filtered by security profile
-        let match_result = self.index.search_filtered(&query, allowed_tools);
+        let match_result = match &self.lexical {
+            None => self.index.search_filtered(&query, allowed_tools),
+            Some((lexical, ranker)) => {
+                let dense = self.index.search_top_k_filtered(&query, allowed_tools, ranker.depth);
+                let lexical = lexical.search_top_k_filtered(text, allowed_tools, ranker.depth);
+                ranker.fuse(&lexical, &dense).into_iter().next()
+            }
+        };
 
         match match_result {
             Some(m) => {
@@ -149,6 +212,11 @@ impl SemanticRouter {
     pub fn index(&self) -> &EmbeddingIndex {
         &self.index
     }
+
+    /// Get a reference to the lexical index, if matching is hybrid.
+    pub fn lexical_index(&self) -> Option<&Bm25Index> {
+        self.lexical.as_ref().map(|(index, _)| index)
+    }
 }
 
 #[cfg(test)]
@@ -477,4 +545,25 @@ profiles:
             assert!(!note.contains("panic"));
         }
     }
+
+    #[tokio::test]
+    async fn hybrid_route_matches_on_tool_name() {
+        // Dense matching alone can never pass this threshold.
+        let (router, _) = build_test_router(0.99);
+        let org = routing_organism();
+        let mut lexical = lexical_index(0.1);
+        register_tools_lexical(&mut lexical, &org);
+        assert_eq!(lexical.len(), 2);
+        let router = router.with_lexical(lexical, HybridRanker::default());
+        let allowed = vec!["file-ops".to_string(), "shell".to_string()];
+
+        let decision = router.route("drop into the shell and compile it", &allowed).await;
+        assert!(
+            matches!(decision, RouteDecision::ToolFailed { ref note } if note.contains("shell")),
+            "expected shell match, got {decision:?}"
+        );
+
+        let decision = router.route("The weather today is sunny and warm", &allowed).await;
+        assert!(matches!(decision, RouteDecision::Response));
+    }
 }
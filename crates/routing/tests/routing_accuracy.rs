@@ -0,0 +1,169 @@
+//! Routing accuracy over the bundled organisms.
+//!
+//! Indexes every tool listener the bundled organisms define and routes a
+//! labelled set of natural-language requests through three rankers: dense
+//! (TF-IDF), lexical (BM25) and hybrid (both, fused). Prints top-1
+//! accuracy for each and checks that fusion never does worse than the
+//! embedding ranking alone.
+//!
+//! Run with `cargo test -p agentos-routing --test routing_accuracy -- --nocapture`
+//! to see the numbers.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use agentos_embedding::hybrid::HybridRanker;
+use agentos_embedding::tfidf::TfIdfProvider;
+use agentos_embedding::{EmbeddingIndex, EmbeddingProvider, MatchResult};
+use agentos_organism::parser::load_organism;
+use agentos_organism::ListenerDef;
+use agentos_routing::{lexical_index, tool_fields};
+
+const ORGANISMS: &[&str] = &[
+    "default.yaml",
+    "infrastructure.yaml",
+    "coder.yaml",
+    "coder-v2.yaml",
+    "organism-builder.yaml",
+    "agent-expert.yaml",
+    "wiki-expert.yaml",
+    "plan-expert.yaml",
+];
+
+/// Thresholds the pipeline routes with.
+const THRESHOLD: f32 = 0.3;
+
+/// (request, tool it should route to). The first half mostly names the
+/// tool's own words; the second half paraphrases.
+const QUERIES: &[(&str, &str)] = &[
+    ("Let me read the file src/main.rs to see what it does", "file-read"),
+    ("I'll open and read Cargo.toml", "file-read"),
+    ("Write a new file docs/intro.md with this content", "file-write"),
+    ("I need to write the generated module out to disk", "file-write"),
+    ("Edit parser.rs to rename the function", "file-edit"),
+    ("I'll edit the existing config file in place", "file-edit"),
+    ("Find all files matching the glob pattern src/**/*.rs", "glob"),
+    ("grep the codebase for TODO comments", "grep"),
+    ("Search file contents for calls to unwrap with grep", "grep"),
+    ("List the contents of the crates directory", "list-dir"),
+    ("What files are in this directory? Let me list it", "list-dir"),
+    ("Run this shell command in bash", "bash"),
+    ("Run the test suite with cargo test", "cargo-test"),
+    ("Let me run the tests to make sure nothing broke", "cargo-test"),
+    ("Build the project with cargo build", "cargo-build"),
+    ("Type-check everything quickly with cargo check", "cargo-check"),
+    ("Lint the code with clippy", "cargo-clippy"),
+    ("Show the git status of the working tree", "git-status"),
+    ("Show me the diff of my uncommitted changes", "git-diff"),
+    ("Look at the recent commit history in the git log", "git-log"),
+    ("Stage the modified files for the next commit", "git-add"),
+    ("Create a commit with the message 'fix parser'", "git-commit"),
+    ("Push the branch to the remote", "git-push"),
+    ("Index the code with tree-sitter to get a symbol map", "codebase-index"),
+    ("Validate this organism YAML configuration", "validate-organism"),
+    ("Smoke-test the organism with dummy tools", "test-organism"),
+    ("Bundle the organism folder into an .agent package", "package-organism"),
+    ("Ask the user which option they prefer", "user"),
+    ("Search the code by meaning for where retries happen", "semantic-search"),
+    ("Compile my Python tool into a WASM component", "compile-wasm"),
+    ("Open src/lib.rs so I can look at its contents", "file-read"),
+    ("Save this generated output as a new document on disk", "file-write"),
+    ("Change the timeout constant in config.rs from 30 to 60", "file-edit"),
+    ("Which paths look like tests/**/*.py?", "glob"),
+    ("Look for every occurrence of the string deprecated_api", "grep"),
+    ("Show what is inside the crates folder", "list-dir"),
+    ("Make sure nothing broke by running the unit tests", "cargo-test"),
+    ("Compile the Rust crate", "cargo-build"),
+    ("Are there any lint warnings?", "cargo-clippy"),
+    ("Which files have I modified?", "git-status"),
+    ("What changed since the last commit?", "git-diff"),
+    ("Who committed recently and what were the messages?", "git-log"),
+    ("Upload my commits to origin", "git-push"),
+    ("Get a map of the functions and structs in this crate", "codebase-index"),
+    ("Is this agent configuration valid?", "validate-organism"),
+    ("Find where we handle retries, even if it is not called retry", "semantic-search"),
+    ("Turn this Python script into a WebAssembly module", "compile-wasm"),
+    ("Confirm with the person before deleting anything", "user"),
+];
+
+fn workspace_root() -> PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent() // crates/
+        .and_then(|p| p.parent()) // workspace root
+        .expect("can't find workspace root")
+        .to_path_buf()
+}
+
+/// Every non-agent listener across the bundled organisms, by name.
+fn bundled_tools() -> BTreeMap<String, ListenerDef> {
+    let root = workspace_root();
+    let mut tools = BTreeMap::new();
+    for name in ORGANISMS {
+        let path = root.join("organisms").join(name);
+        let organism = load_organism(&path).unwrap_or_else(|e| panic!("{name} failed to load: {e}"));
+        for listener in organism.listeners().values() {
+            if !listener.is_agent {
+                tools.entry(listener.name.clone()).or_insert_with(|| listener.clone());
+            }
+        }
+    }
+    tools
+}
+
+/// Text the dense ranker embeds for a tool: every field BM25 sees.
+fn tool_text(listener: &ListenerDef) -> String {
+    tool_fields(listener)
+        .into_iter()
+        .map(|(_, text)| text)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn top(ranking: &[MatchResult]) -> Option<&str> {
+    ranking.first().map(|r| r.name.as_str())
+}
+
+#[test]
+fn hybrid_routing_accuracy() {
+    let tools = bundled_tools();
+    let texts: Vec<String> = tools.values().map(tool_text).collect();
+    let corpus: Vec<&str> = texts.iter().map(String::as_str).collect();
+    let provider = TfIdfProvider::from_corpus(&corpus);
+
+    let mut dense = EmbeddingIndex::new(THRESHOLD);
+    let mut lexical = lexical_index(THRESHOLD);
+    for (listener, text) in tools.values().zip(&texts) {
+        dense.register(&listener.name, provider.embed(text));
+        lexical.register(&listener.name, &tool_fields(listener));
+    }
+    let ranker = HybridRanker::default();
+
+    let cases: Vec<&(&str, &str)> = QUERIES.iter().filter(|(_, tool)| tools.contains_key(*tool)).collect();
+    assert!(cases.len() >= QUERIES.len() / 2, "bundled organisms lost most of their tools");
+
+    let (mut dense_hits, mut lexical_hits, mut hybrid_hits) = (0, 0, 0);
+    for (query, expected) in &cases {
+        let d = dense.search_top_k(&provider.embed(query), ranker.depth);
+        let l = lexical.search_top_k(query, ranker.depth);
+        let h = ranker.fuse(&l, &d);
+        dense_hits += usize::from(top(&d) == Some(*expected));
+        lexical_hits += usize::from(top(&l) == Some(*expected));
+        if top(&h) == Some(*expected) {
+            hybrid_hits += 1;
+        } else {
+            println!("miss: {query:?} → {:?}, expected {expected}", top(&h));
+        }
+    }
+
+    let n = cases.len() as f32;
+    println!(
+        "top-1 over {} requests, {} tools: dense {:.2}, lexical {:.2}, hybrid {:.2}",
+        cases.len(),
+        tools.len(),
+        dense_hits as f32 / n,
+        lexical_hits as f32 / n,
+        hybrid_hits as f32 / n,
+    );
+    assert!(hybrid_hits >= dense_hits, "hybrid {hybrid_hits} < dense {dense_hits}");
+    assert!(hybrid_hits as f32 / n >= 0.6, "hybrid top-1 {hybrid_hits}/{}", cases.len());
+}
@@ -106,6 +106,7 @@ mod tests {
                 buffer: None,
                 python: None,
                 trigger: None,
+                shim_trainer: None,
             })
             .unwrap();
         }
@@ -121,6 +122,7 @@ mod tests {
             allow_all: false,
             journal_retention: RetentionPolicy::RetainDays(90),
             network: vec![],
+            mount: Default::default(),
         })
         .unwrap();
 
@@ -135,6 +137,7 @@ mod tests {
             allow_all: false,
             journal_retention: RetentionPolicy::PruneOnDelivery,
             network: vec![],
+            mount: Default::default(),
         })
         .unwrap();
 
@@ -146,6 +149,7 @@ mod tests {
             allow_all: true,
             journal_retention: RetentionPolicy::Forever,
             network: vec![],
+            mount: Default::default(),
         })
         .unwrap();
 
@@ -238,6 +242,7 @@ mod tests {
             allow_all: false,
             journal_retention: RetentionPolicy::PruneOnDelivery,
             network: vec![],
+            mount: Default::default(),
         })
         .unwrap();
 
@@ -0,0 +1,763 @@
+//! Conversation history and instance admin endpoints.
+//!
+//! Everything here sits behind the same bearer tokens as
+//! `POST /v1/messages` and answers with the same error envelope
+//! (`{ "error": { code, message, request_id } }`). Token scope applies:
+//! user routes resolve inside the token's namespace, and instances the
+//! scope doesn't cover are invisible (filtered from lists, 404 on
+//! lookup).
+//!
+//! Conversation routes are scoped to the chat agent
+//! (`[<ns>.]<agent_name>[user_id]`); a "conversation" is one buffer of that
+//! instance, and its messages are the context segments the kernel
+//! holds under the buffer's thread_id:
+//!
+//! - `GET    /v1/users/:user_id/conversations`
+//! - `GET    /v1/users/:user_id/conversations/:buffer/messages?cursor=&limit=`
+//! - `DELETE /v1/users/:user_id/conversations/:buffer`
+//! - `DELETE /v1/users/:user_id` — kill the instance and erase every thread
+//!
+//! Instance routes are operator tooling over `SharedRouter::list` / `kill`:
+//!
+//! - `GET    /v1/instances`
+//! - `GET    /v1/instances/:address`
+//! - `DELETE /v1/instances/:address`
+//!
+//! Deletes go through `Kernel::erase_threads`, which drops the context
+//! and journal entries and compacts the WAL once for the whole batch so
+//! erased content can't come back on replay. A user doesn't need a live
+//! instance to be listed or erased: the registry keeps the thread_ids of
+//! evicted and killed instances (`SharedRouter::retired_threads`), and
+//! the kernel still holds their buffer threads under `<thread_id>/buf-*`.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use agentos_platform::address::Address;
+use agentos_platform::buffers::{buffer_thread_id, BufferId, BufferInfo, ChannelType};
+use agentos_platform::registry::{InstanceInfo, Lifetime, RegistryError, Tier};
+use agentos_platform::router::RouterError;
+
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::{check_auth, Principal};
+use crate::handler::{is_valid_user_id, request_id_from_headers, ErrorBody, ErrorPayload};
+use crate::metrics;
+use crate::state::ServerState;
+
+/// Default page size for `.../messages`.
+const DEFAULT_PAGE_LIMIT: usize = 50;
+/// Upper bound on a single page. Segments can be large (tool output,
+/// code), so a page is capped well below anything that would stress
+/// the response path.
+const MAX_PAGE_LIMIT: usize = 200;
+
+/// Error response for the admin routes. Same envelope as
+/// `PreStreamError`; records under the admin metric family with the
+/// route label instead of the `/v1/messages` request counter.
+pub struct AdminError {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+    request_id: String,
+}
+
+impl AdminError {
+    fn record(
+        route: &'static str,
+        started: Instant,
+        status: StatusCode,
+        code: &'static str,
+        message: impl Into<String>,
+        request_id: &str,
+    ) -> Self {
+        metrics::record_admin_request(route, status_class(status), started.elapsed());
+        Self {
+            status,
+            code,
+            message: message.into(),
+            request_id: request_id.to_string(),
+        }
+    }
+
+    /// Like `record`, but the detail goes to the server log and the
+    /// client sees a generic message (kernel / router errors carry
+    /// internal structure the caller has no use for).
+    fn record_internal(
+        route: &'static str,
+        started: Instant,
+        detail: impl Into<String>,
+        request_id: &str,
+    ) -> Self {
+        let detail = detail.into();
+        tracing::error!(
+            request_id = %request_id,
+            route,
+            detail = %detail,
+            "admin request failed (detail withheld from client)"
+        );
+        Self::record(
+            route,
+            started,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "internal_error",
+            "internal_error (see server logs)",
+            request_id,
+        )
+    }
+}
+
+impl IntoResponse for AdminError {
+    fn into_response(self) -> axum::response::Response {
+        let body = ErrorBody {
+            error: ErrorPayload {
+                code: self.code,
+                message: self.message,
+                request_id: self.request_id,
+                retry_after: None,
+            },
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+fn status_class(status: StatusCode) -> &'static str {
+    if status.is_success() {
+        metrics::STATUS_OK
+    } else if status.is_client_error() {
+        metrics::STATUS_CLIENT_ERROR
+    } else {
+        metrics::STATUS_SERVER_ERROR
+    }
+}
+
+/// Per-request bookkeeping shared by every admin handler: request id,
+/// time origin, route label, caller. Built after the auth check passes.
+struct AdminCtx {
+    route: &'static str,
+    started: Instant,
+    request_id: String,
+    principal: Principal,
+}
+
+impl AdminCtx {
+    fn authorize(
+        route: &'static str,
+        state: &ServerState,
+        headers: &HeaderMap,
+    ) -> Result<Self, AdminError> {
+        let started = Instant::now();
+        let request_id = request_id_from_headers(headers);
+        match check_auth(headers, &state.auth) {
+            Ok(principal) => Ok(Self {
+                route,
+                started,
+                request_id,
+                principal,
+            }),
+            Err((status, code, message)) => Err(AdminError::record(
+                route,
+                started,
+                status,
+                code,
+                message,
+                &request_id,
+            )),
+        }
+    }
+
+    fn err(&self, status: StatusCode, code: &'static str, message: impl Into<String>) -> AdminError {
+        AdminError::record(self.route, self.started, status, code, message, &self.request_id)
+    }
+
+    fn internal(&self, detail: impl Into<String>) -> AdminError {
+        AdminError::record_internal(self.route, self.started, detail, &self.request_id)
+    }
+
+    fn not_found(&self, what: &str) -> AdminError {
+        self.err(StatusCode::NOT_FOUND, "not_found", format!("{what} not found"))
+    }
+
+    /// Record the success metric and hand back the body.
+    fn ok<T: Serialize>(&self, body: T) -> Json<T> {
+        metrics::record_admin_request(self.route, metrics::STATUS_OK, self.started.elapsed());
+        Json(body)
+    }
+
+    /// Resolve the chat agent's instance address for a user, inside the
+    /// caller's namespace. Applies the same `user_id` allowlist as
+    /// `/v1/messages` so path parameters can't smuggle address grammar.
+    fn user_address(&self, state: &ServerState, user_id: &str) -> Result<Address, AdminError> {
+        if !is_valid_user_id(user_id) {
+            return Err(self.err(
+                StatusCode::BAD_REQUEST,
+                "invalid_request",
+                "user_id must be 1-128 chars of [A-Za-z0-9_-:]",
+            ));
+        }
+        let address = Address::parse(&self.principal.target_address_str(&state.agent_name, user_id))
+            .map_err(|e| self.internal(format!("Address::parse rejected user address: {e}")))?;
+        if !self.principal.allows(&address) {
+            return Err(self.err(
+                StatusCode::FORBIDDEN,
+                "forbidden",
+                "token scope does not cover this user",
+            ));
+        }
+        Ok(address)
+    }
+
+    fn parse_address(&self, raw: &str) -> Result<Address, AdminError> {
+        Address::parse(raw).map_err(|_| {
+            self.err(
+                StatusCode::BAD_REQUEST,
+                "invalid_request",
+                "address is not a valid instance address",
+            )
+        })
+    }
+
+    /// Look up an instance the caller's scope covers. Out-of-scope
+    /// instances answer 404, same as absent ones, so a tenant can't
+    /// probe for another tenant's users.
+    async fn scoped_instance(
+        &self,
+        state: &ServerState,
+        address: &Address,
+    ) -> Result<InstanceInfo, AdminError> {
+        if !self.principal.allows(address) {
+            return Err(self.not_found("instance"));
+        }
+        state
+            .router
+            .lookup(address)
+            .await
+            .ok_or_else(|| self.not_found("instance"))
+    }
+}
+
+// ── wire types ──────────────────────────────────────────────────────
+
+/// One buffer of the user's chat instance.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConversationSummary {
+    /// Canonical buffer id (`default`, `dm`, `help[email-issue]`).
+    pub buffer: String,
+    pub channel: String,
+    pub thread_id: String,
+    pub message_count: u64,
+    pub age_secs: u64,
+    pub idle_secs: u64,
+    /// False when the buffer isn't in a live instance (evicted, killed,
+    /// or not touched since a restart). `buffer` is then the name its
+    /// thread_id was derived from, `message_count` counts the kernel's
+    /// segments, and the age fields read 0.
+    pub live: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConversationList {
+    pub user_id: String,
+    pub address: String,
+    pub conversations: Vec<ConversationSummary>,
+}
+
+/// Query string for `.../messages`. `cursor` is the opaque value from
+/// the previous page's `next_cursor` (an offset today).
+#[derive(Debug, Default, Deserialize)]
+pub struct PageQuery {
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// One context segment, rendered for the frontend.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MessageRecord {
+    pub id: String,
+    pub tag: String,
+    pub status: String,
+    pub created_at: u64,
+    /// Segment content as UTF-8 (lossy). Folded segments carry their
+    /// summary, not the original.
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MessagePage {
+    pub thread_id: String,
+    pub messages: Vec<MessageRecord>,
+    /// Pass back as `cursor` to fetch the next page; absent on the last page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Result of a delete: which kernel threads were erased, and how many
+/// WAL entries the erase scrubbed in total.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EraseReport {
+    pub address: String,
+    pub erased_threads: Vec<String>,
+    pub wal_entries_scrubbed: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InstanceSummary {
+    pub address: String,
+    pub organism: String,
+    pub thread_id: String,
+    pub tier: String,
+    pub lifetime: String,
+    pub parent: Option<String>,
+    pub buffer_count: usize,
+    pub age_secs: u64,
+    pub idle_secs: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InstanceList {
+    pub instances: Vec<InstanceSummary>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InstanceDetail {
+    #[serde(flatten)]
+    pub summary: InstanceSummary,
+    pub cache_shards: Vec<String>,
+    pub buffers: Vec<ConversationSummary>,
+}
+
+fn tier_name(tier: Tier) -> &'static str {
+    match tier {
+        Tier::Active => "active",
+        Tier::Shelved => "shelved",
+        Tier::Folded => "folded",
+    }
+}
+
+fn lifetime_name(lifetime: &Lifetime) -> String {
+    match lifetime {
+        Lifetime::UntilIdle(d) => format!("until_idle({}s)", d.as_secs()),
+        Lifetime::UntilTaskComplete => "until_task_complete".into(),
+        Lifetime::Pinned => "pinned".into(),
+        Lifetime::Ephemeral => "ephemeral".into(),
+    }
+}
+
+fn conversation_summary(b: &BufferInfo) -> ConversationSummary {
+    ConversationSummary {
+        buffer: b.id.canonical(),
+        channel: format!("{:?}", b.channel).to_lowercase(),
+        thread_id: b.thread_id.clone(),
+        message_count: b.message_count,
+        age_secs: b.created_at.elapsed().as_secs(),
+        idle_secs: b.last_accessed.elapsed().as_secs(),
+        live: true,
+    }
+}
+
+/// A buffer thread the kernel holds with no live buffer behind it,
+/// named by the id the registry recorded when its instance retired.
+/// Without one, `label` (the thread_id past `/buf-`) stands in: it
+/// resolves to the same thread_id, but the channel is only right for
+/// unkeyed buffers.
+fn retired_conversation(
+    id: Option<BufferId>,
+    label: String,
+    thread_id: String,
+    message_count: u64,
+) -> ConversationSummary {
+    let (name, buffer) = match id {
+        Some(id) => (id.name.clone(), id.canonical()),
+        None => (label.clone(), label),
+    };
+    ConversationSummary {
+        channel: format!("{:?}", ChannelType::from_name(&name)).to_lowercase(),
+        buffer,
+        thread_id,
+        message_count,
+        age_secs: 0,
+        idle_secs: 0,
+        live: false,
+    }
+}
+
+/// Buffers in canonical-name order so listings are stable.
+fn sorted_buffers(info: &InstanceInfo) -> Vec<&BufferInfo> {
+    let mut buffers = info.buffers.list();
+    buffers.sort_by_key(|b| b.id.canonical());
+    buffers
+}
+
+fn instance_summary(info: &InstanceInfo) -> InstanceSummary {
+    InstanceSummary {
+        address: info.address.raw().to_string(),
+        organism: info.organism.clone(),
+        thread_id: info.thread_id.clone(),
+        tier: tier_name(info.tier).to_string(),
+        lifetime: lifetime_name(&info.lifetime),
+        parent: info.parent.as_ref().map(|p| p.raw().to_string()),
+        buffer_count: info.buffers.count(),
+        age_secs: info.created_at.elapsed().as_secs(),
+        idle_secs: info.last_accessed.elapsed().as_secs(),
+    }
+}
+
+fn find_buffer<'a>(info: &'a InstanceInfo, canonical: &str) -> Option<&'a BufferInfo> {
+    info.buffers.list().into_iter().find(|b| b.id.canonical() == canonical)
+}
+
+/// The live instance at `address`, if any, and every instance thread
+/// the address has held, oldest first: those the registry retired on
+/// eviction or kill, then the live one.
+async fn instance_threads(state: &ServerState, address: &Address) -> (Option<InstanceInfo>, Vec<String>) {
+    let live = state.router.lookup(address).await;
+    let mut roots = state.router.retired_threads(address).await;
+    if let Some(info) = &live {
+        if !roots.contains(&info.thread_id) {
+            roots.push(info.thread_id.clone());
+        }
+    }
+    (live, roots)
+}
+
+/// Threads holding conversation `buffer` of `address`, newest first:
+/// the live buffer's, then the derived buffer thread under each older
+/// instance thread the kernel still has state for. Also returns the
+/// live buffer's id so a delete can drop it from the instance.
+async fn conversation_threads(
+    state: &ServerState,
+    address: &Address,
+    buffer: &str,
+) -> (Option<BufferId>, Vec<String>) {
+    let (live, roots) = instance_threads(state, address).await;
+    let live_buffer = live.as_ref().and_then(|info| find_buffer(info, buffer)).cloned();
+    let mut threads: Vec<String> = live_buffer.iter().map(|b| b.thread_id.clone()).collect();
+    let kernel = state.kernel.lock().await;
+    for root in roots.iter().rev() {
+        let thread_id = buffer_thread_id(root, buffer);
+        if !threads.contains(&thread_id) && kernel.thread_ids_under(root).contains(&thread_id) {
+            threads.push(thread_id);
+        }
+    }
+    (live_buffer.map(|b| b.id), threads)
+}
+
+// ── conversation routes ─────────────────────────────────────────────
+
+/// `GET /v1/users/:user_id/conversations`
+///
+/// Live buffers plus any buffer thread the kernel still holds under an
+/// instance thread the user had before. A user with neither has no
+/// conversations; that's an empty list, not a 404, so the frontend can
+/// render it directly.
+pub async fn list_conversations(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Path(user_id): Path<String>,
+) -> Result<Json<ConversationList>, AdminError> {
+    let ctx = AdminCtx::authorize(metrics::ROUTE_LIST_CONVERSATIONS, &state, &headers)?;
+    let address = ctx.user_address(&state, &user_id)?;
+
+    let (live, roots) = instance_threads(&state, &address).await;
+    let mut conversations: Vec<ConversationSummary> = live
+        .as_ref()
+        .map(|info| sorted_buffers(info).into_iter().map(conversation_summary).collect())
+        .unwrap_or_default();
+    let mut retired = Vec::new();
+    {
+        let kernel = state.kernel.lock().await;
+        for root in &roots {
+            let prefix = format!("{root}/buf-");
+            for thread_id in kernel.thread_ids_under(root) {
+                let Some(label) = thread_id.strip_prefix(&prefix).map(str::to_string) else {
+                    continue;
+                };
+                if conversations.iter().any(|c| c.thread_id == thread_id) {
+                    continue;
+                }
+                let message_count = kernel
+                    .contexts()
+                    .get(&thread_id)
+                    .map_or(0, |c| c.segments.len() as u64);
+                retired.push((label, thread_id, message_count));
+            }
+        }
+    }
+    for (label, thread_id, message_count) in retired {
+        let id = state.router.retired_buffer(&address, &thread_id).await;
+        conversations.push(retired_conversation(id, label, thread_id, message_count));
+    }
+    conversations.sort_by(|a, b| a.buffer.cmp(&b.buffer).then_with(|| a.thread_id.cmp(&b.thread_id)));
+
+    Ok(ctx.ok(ConversationList {
+        user_id,
+        address: address.raw().to_string(),
+        conversations,
+    }))
+}
+
+/// `GET /v1/users/:user_id/conversations/:buffer/messages`
+///
+/// Pages through the buffer thread's context segments in creation
+/// order (ties broken by segment id so paging is deterministic). When
+/// the buffer has threads under several instance threads, the newest
+/// one is paged.
+pub async fn list_messages(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Path((user_id, buffer)): Path<(String, String)>,
+    Query(page): Query<PageQuery>,
+) -> Result<Json<MessagePage>, AdminError> {
+    let ctx = AdminCtx::authorize(metrics::ROUTE_LIST_MESSAGES, &state, &headers)?;
+    let address = ctx.user_address(&state, &user_id)?;
+
+    let offset = match page.cursor.as_deref() {
+        None => 0,
+        Some(c) => c.parse::<usize>().map_err(|_| {
+            ctx.err(StatusCode::BAD_REQUEST, "invalid_request", "cursor is not valid")
+        })?,
+    };
+    let limit = page.limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+    if limit == 0 || limit > MAX_PAGE_LIMIT {
+        return Err(ctx.err(
+            StatusCode::BAD_REQUEST,
+            "invalid_request",
+            format!("limit must be 1-{MAX_PAGE_LIMIT}"),
+        ));
+    }
+
+    let (_, threads) = conversation_threads(&state, &address, &buffer).await;
+    let thread_id = threads
+        .into_iter()
+        .next()
+        .ok_or_else(|| ctx.not_found("conversation"))?;
+
+    let kernel = state.kernel.lock().await;
+    let Some(thread_ctx) = kernel.contexts().get(&thread_id) else {
+        drop(kernel);
+        return Ok(ctx.ok(MessagePage {
+            thread_id,
+            messages: vec![],
+            next_cursor: None,
+        }));
+    };
+    let mut segments: Vec<_> = thread_ctx.segments.values().collect();
+    segments.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+    let total = segments.len();
+    let messages: Vec<MessageRecord> = segments
+        .into_iter()
+        .skip(offset)
+        .take(limit)
+        .map(|s| MessageRecord {
+            id: s.id.clone(),
+            tag: s.tag.clone(),
+            status: format!("{:?}", s.status).to_lowercase(),
+            created_at: s.created_at,
+            content: String::from_utf8_lossy(&s.content).into_owned(),
+        })
+        .collect();
+    drop(kernel);
+
+    let end = offset.saturating_add(messages.len());
+    let next_cursor = (end < total).then(|| end.to_string());
+    Ok(ctx.ok(MessagePage {
+        thread_id,
+        messages,
+        next_cursor,
+    }))
+}
+
+/// `DELETE /v1/users/:user_id/conversations/:buffer`
+///
+/// GDPR erase of one conversation: the buffer leaves the live instance,
+/// if there is one, and its threads under every instance thread the
+/// user has held are erased from the kernel (context, journal, WAL).
+pub async fn delete_conversation(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Path((user_id, buffer)): Path<(String, String)>,
+) -> Result<Json<EraseReport>, AdminError> {
+    let ctx = AdminCtx::authorize(metrics::ROUTE_DELETE_CONVERSATION, &state, &headers)?;
+    let address = ctx.user_address(&state, &user_id)?;
+
+    let (live_buffer, threads) = conversation_threads(&state, &address, &buffer).await;
+    if threads.is_empty() {
+        return Err(ctx.not_found("conversation"));
+    }
+    if let Some(buffer_id) = &live_buffer {
+        state.router.remove_buffer(&address, buffer_id).await;
+    }
+
+    let scrubbed = {
+        let mut kernel = state.kernel.lock().await;
+        kernel
+            .erase_threads(&threads)
+            .map_err(|e| ctx.internal(format!("kernel erase failed: {e}")))?
+    };
+    state.budgets.unbind_threads(&threads);
+
+    tracing::info!(
+        request_id = %ctx.request_id,
+        address = address.raw(),
+        buffer = %buffer,
+        "Conversation erased"
+    );
+    Ok(ctx.ok(EraseReport {
+        address: address.raw().to_string(),
+        erased_threads: threads,
+        wal_entries_scrubbed: scrubbed,
+    }))
+}
+
+/// `DELETE /v1/users/:user_id`
+///
+/// Full GDPR erase for a user: kill the chat instance if it's live,
+/// then erase every instance thread the user has held, live or
+/// retired, and every buffer thread under them. 404 when there is
+/// nothing left to erase.
+pub async fn delete_user(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Path(user_id): Path<String>,
+) -> Result<Json<EraseReport>, AdminError> {
+    let ctx = AdminCtx::authorize(metrics::ROUTE_DELETE_USER, &state, &headers)?;
+    let address = ctx.user_address(&state, &user_id)?;
+    let report = kill_and_erase(&state, &ctx, &address).await?;
+    if report.erased_threads.is_empty() {
+        return Err(ctx.not_found("user instance"));
+    }
+    tracing::info!(request_id = %ctx.request_id, address = address.raw(), "User erased");
+    Ok(ctx.ok(report))
+}
+
+/// Kill the live instance, if any, then erase its thread, the threads
+/// the registry retired for `address`, and the buffer threads under all
+/// of them in one kernel pass. The retired ids are forgotten only once
+/// the erase succeeded, so a failed erase can be retried.
+async fn kill_and_erase(
+    state: &ServerState,
+    ctx: &AdminCtx,
+    address: &Address,
+) -> Result<EraseReport, AdminError> {
+    let mut threads = Vec::new();
+    match state.router.kill(address).await {
+        // The kill retires the instance thread, so it's picked up below.
+        Ok(info) => threads.extend(sorted_buffers(&info).into_iter().map(|b| b.thread_id.clone())),
+        Err(RouterError::Registry(RegistryError::NotFound(_))) => {}
+        Err(e) => return Err(ctx.internal(format!("router kill failed: {e}"))),
+    }
+    let roots = state.router.retired_threads(address).await;
+
+    let scrubbed = {
+        let mut kernel = state.kernel.lock().await;
+        for root in &roots {
+            threads.extend(kernel.thread_ids_under(root));
+        }
+        threads.sort();
+        threads.dedup();
+        kernel
+            .erase_threads(&threads)
+            .map_err(|e| ctx.internal(format!("kernel erase failed: {e}")))?
+    };
+    state.router.forget_retired(address).await;
+    state.budgets.unbind_threads(&threads);
+
+    Ok(EraseReport {
+        address: address.raw().to_string(),
+        erased_threads: threads,
+        wal_entries_scrubbed: scrubbed,
+    })
+}
+
+// ── instance routes ─────────────────────────────────────────────────
+
+/// `GET /v1/instances`
+pub async fn list_instances(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+) -> Result<Json<InstanceList>, AdminError> {
+    let ctx = AdminCtx::authorize(metrics::ROUTE_LIST_INSTANCES, &state, &headers)?;
+    let mut instances: Vec<InstanceSummary> = state
+        .router
+        .list()
+        .await
+        .iter()
+        .filter(|info| ctx.principal.allows(&info.address))
+        .map(instance_summary)
+        .collect();
+    instances.sort_by(|a, b| a.address.cmp(&b.address));
+    Ok(ctx.ok(InstanceList { instances }))
+}
+
+/// `GET /v1/instances/:address` — tier, lifetime, shards and buffers.
+pub async fn get_instance(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Path(raw): Path<String>,
+) -> Result<Json<InstanceDetail>, AdminError> {
+    let ctx = AdminCtx::authorize(metrics::ROUTE_GET_INSTANCE, &state, &headers)?;
+    let address = ctx.parse_address(&raw)?;
+    let info = ctx.scoped_instance(&state, &address).await?;
+    Ok(ctx.ok(InstanceDetail {
+        summary: instance_summary(&info),
+        cache_shards: info.cache_shards.clone(),
+        buffers: sorted_buffers(&info).into_iter().map(conversation_summary).collect(),
+    }))
+}
+
+/// `DELETE /v1/instances/:address` — kill without erasing history.
+/// Kernel contexts are released the same way idle eviction releases
+/// them; use `DELETE /v1/users/:user_id` for an erase.
+pub async fn kill_instance(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    Path(raw): Path<String>,
+) -> Result<Json<InstanceSummary>, AdminError> {
+    let ctx = AdminCtx::authorize(metrics::ROUTE_KILL_INSTANCE, &state, &headers)?;
+    let address = ctx.parse_address(&raw)?;
+    ctx.scoped_instance(&state, &address).await?;
+    let info = state
+        .router
+        .kill(&address)
+        .await
+        .map_err(|e| ctx.internal(format!("router kill failed: {e}")))?;
+    tracing::info!(request_id = %ctx.request_id, address = address.raw(), "Instance killed via admin API");
+    Ok(ctx.ok(instance_summary(&info)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tier_names_are_stable() {
+        assert_eq!(tier_name(Tier::Active), "active");
+        assert_eq!(tier_name(Tier::Shelved), "shelved");
+        assert_eq!(tier_name(Tier::Folded), "folded");
+    }
+
+    #[test]
+    fn lifetime_names_are_readable() {
+        assert_eq!(
+            lifetime_name(&Lifetime::UntilIdle(std::time::Duration::from_secs(300))),
+            "until_idle(300s)"
+        );
+        assert_eq!(lifetime_name(&Lifetime::Pinned), "pinned");
+    }
+
+    #[test]
+    fn status_class_buckets() {
+        assert_eq!(status_class(StatusCode::OK), metrics::STATUS_OK);
+        assert_eq!(status_class(StatusCode::NOT_FOUND), metrics::STATUS_CLIENT_ERROR);
+        assert_eq!(
+            status_class(StatusCode::INTERNAL_SERVER_ERROR),
+            metrics::STATUS_SERVER_ERROR
+        );
+    }
+}
@@ -0,0 +1,988 @@
+//! Bearer-token authentication — named, scoped tokens.
+//!
+//! Every authenticated request resolves to a [`Principal`]: who is
+//! calling, which addresses it may reach, which `user_tier`s it may
+//! claim, and its request-rate ceiling. Three sources can mint one:
+//!
+//! - **Token store** ([`TokenStore`]) — a JSON file of named tokens
+//!   under the data dir. Only SHA-256 digests of the secrets are kept
+//!   on disk; the secret is shown once, at `tokens create`. The server
+//!   re-reads the file when its mtime changes, so create / revoke take
+//!   effect without a restart.
+//! - **HS256 JWTs** ([`JwtVerifier`]) — optional; keys come from a
+//!   local JWKS file (`kty: "oct"`). Scope, tiers, rate limit and
+//!   budget ride in the claims.
+//! - **Static token** — the legacy `--auth-token` / `AGENTOS_SERVER_TOKEN`
+//!   path. Resolves to a root-scoped principal named `static`.
+//!
+//! # Scope and namespaces
+//!
+//! A token's scope is an address pattern ([`ScopePattern`]), e.g.
+//! `ringhub.concierge[*]`. The handler builds target addresses inside
+//! the principal's namespace and rejects anything the pattern doesn't
+//! cover; the envelope's `from` is then set to `<ns>.gateway[<name>]`
+//! so the platform router's namespace check enforces the same boundary
+//! a second time, independently of the pattern match.
+//!
+//! All secret comparisons go through SHA-256 + `subtle::ConstantTimeEq`
+//! (see [`ct_eq_token`]).
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use agentos_platform::address::Address;
+use axum::http::{HeaderMap, StatusCode};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+use crate::budget::BudgetLimits;
+
+/// Tiers the v1 contract admits on `/v1/messages`. A token with an
+/// empty tier list may claim any of these.
+pub const KNOWN_TIERS: &[&str] = &["warm", "member"];
+
+/// Name of the principal minted from the legacy static token.
+pub const STATIC_PRINCIPAL: &str = "static";
+
+/// Errors from token-store management (CLI paths). Request-path
+/// failures never surface these — they map to 401/403 instead.
+#[derive(Debug, thiserror::Error)]
+pub enum TokenError {
+    #[error("token name must be 1-64 chars of [A-Za-z0-9_-]: {0:?}")]
+    InvalidName(String),
+    #[error("token {0:?} already exists")]
+    AlreadyExists(String),
+    #[error("token {0:?} not found")]
+    NotFound(String),
+    #[error("invalid scope pattern {0:?}")]
+    InvalidScope(String),
+    #[error("scope {0:?} has no leading namespace (only root `**` may be un-namespaced)")]
+    UnNamespacedScope(String),
+    #[error("unknown user_tier {0:?} (expected one of: warm, member)")]
+    InvalidTier(String),
+    #[error("token store I/O: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("token store is corrupt: {0}")]
+    Corrupt(#[from] serde_json::Error),
+}
+
+// ── scope patterns ──────────────────────────────────────────────────
+
+/// Address glob. `*` matches one run of characters that contains no
+/// address grammar (`.`, `[`, `]`); `**` matches anything, including
+/// further segments. `ringhub.concierge[*]` covers every user of
+/// `ringhub.concierge`; `ringhub.**` covers the whole namespace; `**`
+/// is root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ScopePattern(String);
+
+impl ScopePattern {
+    pub fn parse(raw: &str) -> Result<Self, TokenError> {
+        let raw = raw.trim();
+        let probe = raw.replace("**", "x").replace('*', "x");
+        if raw.is_empty() || Address::parse(&probe).is_err() {
+            return Err(TokenError::InvalidScope(raw.to_string()));
+        }
+        Ok(Self(raw.to_string()))
+    }
+
+    /// Root scope — reaches every address.
+    pub fn root() -> Self {
+        Self("**".to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.0 == "**"
+    }
+
+    /// Can a principal hold this scope? Anything short of root must
+    /// name a literal namespace: the router treats an un-namespaced
+    /// sender as admin, so `bob[*]` or `*.bob[*]` would otherwise
+    /// reach every listener.
+    pub fn check_grantable(&self) -> Result<(), TokenError> {
+        if self.is_root() || self.namespace().is_some() {
+            Ok(())
+        } else {
+            Err(TokenError::UnNamespacedScope(self.0.clone()))
+        }
+    }
+
+    /// The namespace this scope lives in: the leading literal segment,
+    /// when the pattern has more than one segment. `None` for root and
+    /// for un-namespaced patterns like `bob[*]`.
+    pub fn namespace(&self) -> Option<&str> {
+        let (first, _) = self.0.split_once('.')?;
+        if first.is_empty() || first.contains(['*', '[', ']']) {
+            return None;
+        }
+        Some(first)
+    }
+
+    /// Does this pattern cover `address`?
+    pub fn matches(&self, address: &Address) -> bool {
+        glob_match(self.0.as_bytes(), address.raw().as_bytes())
+    }
+}
+
+impl TryFrom<String> for ScopePattern {
+    type Error = TokenError;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::parse(&s)
+    }
+}
+
+impl From<ScopePattern> for String {
+    fn from(p: ScopePattern) -> Self {
+        p.0
+    }
+}
+
+impl std::fmt::Display for ScopePattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+fn is_grammar(b: u8) -> bool {
+    matches!(b, b'.' | b'[' | b']')
+}
+
+/// Backtracking glob over bytes. Patterns are operator-written and
+/// short; addresses are bounded by the user_id cap, so the worst case
+/// stays trivial.
+fn glob_match(pat: &[u8], text: &[u8]) -> bool {
+    match pat {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => (0..=text.len()).any(|i| glob_match(rest, &text[i..])),
+        [b'*', rest @ ..] => {
+            let run = text.iter().take_while(|b| !is_grammar(**b)).count();
+            (0..=run).any(|i| glob_match(rest, &text[i..]))
+        }
+        [p, rest @ ..] => text.first() == Some(p) && glob_match(rest, &text[1..]),
+    }
+}
+
+// ── principals ──────────────────────────────────────────────────────
+
+/// An authenticated caller.
+#[derive(Debug, Clone)]
+pub struct Principal {
+    /// Stable identity: the token name, `jwt:<sub>` for JWTs, or
+    /// [`STATIC_PRINCIPAL`]. Keys idempotency and rate-limit state.
+    pub id: String,
+    pub scope: ScopePattern,
+    /// Allowed `user_tier`s. Empty = any known tier.
+    pub tiers: Vec<String>,
+    /// Requests per minute across all users of this token. `None` =
+    /// unlimited.
+    pub rate_limit_per_minute: Option<u32>,
+    /// Rolling LLM token / dollar caps across all users of this token.
+    pub budget: BudgetLimits,
+}
+
+impl Principal {
+    /// Root principal for the legacy static token.
+    pub fn root(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            scope: ScopePattern::root(),
+            tiers: vec![],
+            rate_limit_per_minute: None,
+            budget: BudgetLimits::default(),
+        }
+    }
+
+    pub fn allows_tier(&self, tier: &str) -> bool {
+        self.tiers.is_empty() || self.tiers.iter().any(|t| t == tier)
+    }
+
+    pub fn allows(&self, address: &Address) -> bool {
+        self.scope.matches(address)
+    }
+
+    /// Build `<agent>[<key>]` inside this principal's namespace.
+    pub fn target_address_str(&self, agent: &str, key: &str) -> String {
+        match self.scope.namespace() {
+            Some(ns) => format!("{ns}.{agent}[{key}]"),
+            None => format!("{agent}[{key}]"),
+        }
+    }
+
+    /// Sender address for envelopes this principal originates. `None`
+    /// for root, which the router treats as admin (store and JWT
+    /// principals are always namespaced or root). Namespaced principals send as `<ns>.gateway[<id>]`, so
+    /// `Router::check_namespace` blocks anything outside `<ns>`.
+    pub fn sender_address(&self) -> Option<Address> {
+        let ns = self.scope.namespace()?;
+        let key: String = self
+            .id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        Address::parse(&format!("{ns}.gateway[{key}]")).ok()
+    }
+}
+
+// ── token store ─────────────────────────────────────────────────────
+
+/// One named token as persisted. The secret itself is never stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenRecord {
+    pub name: String,
+    /// Hex SHA-256 of the secret.
+    pub secret_sha256: String,
+    pub scope: ScopePattern,
+    #[serde(default)]
+    pub tiers: Vec<String>,
+    #[serde(default)]
+    pub rate_limit_per_minute: Option<u32>,
+    #[serde(default, skip_serializing_if = "BudgetLimits::is_unlimited")]
+    pub budget: BudgetLimits,
+    /// Unix seconds.
+    pub created_at: u64,
+    #[serde(default)]
+    pub revoked_at: Option<u64>,
+}
+
+impl TokenRecord {
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none()
+    }
+
+    /// `None` for records whose scope is not grantable (hand-edited or
+    /// written before that check existed) — they authenticate nobody.
+    fn principal(&self) -> Option<Principal> {
+        self.scope.check_grantable().ok()?;
+        Some(Principal {
+            id: self.name.clone(),
+            scope: self.scope.clone(),
+            tiers: self.tiers.clone(),
+            rate_limit_per_minute: self.rate_limit_per_minute,
+            budget: self.budget,
+        })
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TokenFile {
+    version: u32,
+    tokens: Vec<TokenRecord>,
+}
+
+impl TokenFile {
+    const CURRENT_VERSION: u32 = 1;
+}
+
+/// File-backed token store. Writes are atomic (tmp + rename), matching
+/// the platform registry snapshot discipline.
+#[derive(Debug)]
+pub struct TokenStore {
+    path: PathBuf,
+    tokens: Vec<TokenRecord>,
+}
+
+impl TokenStore {
+    /// Conventional location under the server data dir.
+    pub fn default_path(data_dir: &Path) -> PathBuf {
+        data_dir.join("server_tokens.json")
+    }
+
+    /// Open a store. A missing file is an empty store; a malformed one
+    /// is an error (unlike the registry snapshot, silently dropping
+    /// tokens would lock every tenant out).
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, TokenError> {
+        let path = path.into();
+        let tokens = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice::<TokenFile>(&bytes)?.tokens,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { path, tokens })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// All tokens, revoked included.
+    pub fn list(&self) -> &[TokenRecord] {
+        &self.tokens
+    }
+
+    /// Mint a new token and persist it. Returns the secret — the only
+    /// time it is ever available.
+    pub fn create(
+        &mut self,
+        name: &str,
+        scope: ScopePattern,
+        tiers: Vec<String>,
+        rate_limit_per_minute: Option<u32>,
+        budget: BudgetLimits,
+    ) -> Result<String, TokenError> {
+        if !is_valid_token_name(name) {
+            return Err(TokenError::InvalidName(name.to_string()));
+        }
+        if self.tokens.iter().any(|t| t.name == name) {
+            return Err(TokenError::AlreadyExists(name.to_string()));
+        }
+        if let Some(bad) = tiers.iter().find(|t| !KNOWN_TIERS.contains(&t.as_str())) {
+            return Err(TokenError::InvalidTier(bad.clone()));
+        }
+        scope.check_grantable()?;
+        let secret = format!(
+            "aos_{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        );
+        self.tokens.push(TokenRecord {
+            name: name.to_string(),
+            secret_sha256: sha256_hex(secret.as_bytes()),
+            scope,
+            tiers,
+            rate_limit_per_minute,
+            budget,
+            created_at: now_secs(),
+            revoked_at: None,
+        });
+        self.save()?;
+        Ok(secret)
+    }
+
+    /// Revoke a token. Idempotent for already-revoked tokens; the
+    /// record stays in the file for audit.
+    pub fn revoke(&mut self, name: &str) -> Result<(), TokenError> {
+        let rec = self
+            .tokens
+            .iter_mut()
+            .find(|t| t.name == name)
+            .ok_or_else(|| TokenError::NotFound(name.to_string()))?;
+        if rec.revoked_at.is_none() {
+            rec.revoked_at = Some(now_secs());
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Resolve a presented secret. Every active record is compared
+    /// (no early exit) so timing doesn't reveal which one matched.
+    pub fn verify(&self, secret: &str) -> Option<Principal> {
+        let digest = Sha256::digest(secret.as_bytes());
+        let mut found = None;
+        for rec in self.tokens.iter().filter(|t| t.is_active()) {
+            let Some(stored) = hex_decode_32(&rec.secret_sha256) else {
+                continue;
+            };
+            if bool::from(digest.as_slice().ct_eq(&stored)) {
+                found = rec.principal();
+            }
+        }
+        found
+    }
+
+    fn save(&self) -> Result<(), TokenError> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = TokenFile {
+            version: TokenFile::CURRENT_VERSION,
+            tokens: self.tokens.clone(),
+        };
+        let bytes = serde_json::to_vec_pretty(&file)?;
+        let tmp = self.path.with_extension("json.tmp");
+        {
+            use std::io::Write;
+            let mut f = std::fs::File::create(&tmp)?;
+            f.write_all(&bytes)?;
+            f.sync_all()?;
+        }
+        std::fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+}
+
+fn is_valid_token_name(s: &str) -> bool {
+    (1..=64).contains(&s.len())
+        && s.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+// ── JWT ─────────────────────────────────────────────────────────────
+
+/// One symmetric key from a JWKS file.
+#[derive(Debug, Deserialize)]
+struct Jwk {
+    kty: String,
+    #[serde(default)]
+    kid: Option<String>,
+    #[serde(default)]
+    alg: Option<String>,
+    k: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Jwks {
+    keys: Vec<Jwk>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JwtHeader {
+    alg: String,
+    #[serde(default)]
+    kid: Option<String>,
+}
+
+/// Claims AgentOS reads. `scope` is required — a JWT without one
+/// would otherwise be an implicit root token.
+#[derive(Debug, Deserialize)]
+struct JwtClaims {
+    sub: String,
+    exp: u64,
+    #[serde(default)]
+    nbf: Option<u64>,
+    #[serde(default)]
+    iss: Option<String>,
+    #[serde(default)]
+    aud: Option<serde_json::Value>,
+    scope: String,
+    #[serde(default)]
+    tiers: Vec<String>,
+    #[serde(default)]
+    rpm: Option<u32>,
+    #[serde(default)]
+    budget_tokens: Option<u64>,
+    #[serde(default)]
+    budget_usd: Option<f64>,
+}
+
+/// HS256 JWT verifier over a local JWKS file. Only `kty: "oct"` keys
+/// are loaded; asymmetric algorithms are out of scope.
+#[derive(Debug)]
+pub struct JwtVerifier {
+    /// kid → key bytes. Keys without a kid are stored under "".
+    keys: HashMap<String, Vec<u8>>,
+    issuer: Option<String>,
+    audience: Option<String>,
+}
+
+/// Allowed clock skew for `exp` / `nbf`, in seconds.
+const JWT_LEEWAY_SECS: u64 = 30;
+
+impl JwtVerifier {
+    pub fn from_jwks_file(
+        path: &Path,
+        issuer: Option<String>,
+        audience: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::from_jwks(&bytes, issuer, audience)
+    }
+
+    pub fn from_jwks(
+        jwks: &[u8],
+        issuer: Option<String>,
+        audience: Option<String>,
+    ) -> anyhow::Result<Self> {
+        let jwks: Jwks = serde_json::from_slice(jwks)?;
+        let mut keys = HashMap::new();
+        for jwk in jwks.keys {
+            if jwk.kty != "oct" || jwk.alg.as_deref().is_some_and(|a| a != "HS256") {
+                continue;
+            }
+            let key = URL_SAFE_NO_PAD.decode(jwk.k.trim_end_matches('='))?;
+            keys.insert(jwk.kid.unwrap_or_default(), key);
+        }
+        if keys.is_empty() {
+            anyhow::bail!("JWKS contains no HS256 (kty=oct) keys");
+        }
+        Ok(Self {
+            keys,
+            issuer,
+            audience,
+        })
+    }
+
+    /// Validate a compact JWT and map its claims to a principal.
+    /// Any failure — bad signature, expired, wrong issuer/audience,
+    /// malformed scope — is `None`; the caller answers 403 without
+    /// saying which check tripped.
+    pub fn verify(&self, token: &str, now: u64) -> Option<Principal> {
+        let mut parts = token.split('.');
+        let (h, c, s) = (parts.next()?, parts.next()?, parts.next()?);
+        if parts.next().is_some() {
+            return None;
+        }
+        let header: JwtHeader = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(h).ok()?).ok()?;
+        if header.alg != "HS256" {
+            return None;
+        }
+        let key = self.keys.get(header.kid.as_deref().unwrap_or(""))?;
+        let sig = URL_SAFE_NO_PAD.decode(s).ok()?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).ok()?;
+        mac.update(h.as_bytes());
+        mac.update(b".");
+        mac.update(c.as_bytes());
+        mac.verify_slice(&sig).ok()?;
+
+        let claims: JwtClaims = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(c).ok()?).ok()?;
+        if now > claims.exp.saturating_add(JWT_LEEWAY_SECS) {
+            return None;
+        }
+        if claims.nbf.is_some_and(|nbf| now.saturating_add(JWT_LEEWAY_SECS) < nbf) {
+            return None;
+        }
+        if let Some(ref iss) = self.issuer {
+            if claims.iss.as_deref() != Some(iss.as_str()) {
+                return None;
+            }
+        }
+        if let Some(ref aud) = self.audience {
+            let ok = match claims.aud {
+                Some(serde_json::Value::String(ref a)) => a == aud,
+                Some(serde_json::Value::Array(ref v)) => v.iter().any(|a| a.as_str() == Some(aud)),
+                _ => false,
+            };
+            if !ok {
+                return None;
+            }
+        }
+        if claims.tiers.iter().any(|t| !KNOWN_TIERS.contains(&t.as_str())) {
+            return None;
+        }
+        let scope = ScopePattern::parse(&claims.scope).ok()?;
+        scope.check_grantable().ok()?;
+        Some(Principal {
+            id: format!("jwt:{}", claims.sub),
+            scope,
+            tiers: claims.tiers,
+            rate_limit_per_minute: claims.rpm,
+            budget: BudgetLimits {
+                tokens: claims.budget_tokens,
+                usd: claims.budget_usd,
+            },
+        })
+    }
+}
+
+// ── authenticator ───────────────────────────────────────────────────
+
+/// Token store plus the mtime it was loaded at, for hot reload.
+#[derive(Debug)]
+struct LoadedStore {
+    store: TokenStore,
+    mtime: Option<SystemTime>,
+}
+
+/// Resolves bearer credentials to principals. Held by `ServerState`.
+#[derive(Debug, Default)]
+pub struct Authenticator {
+    static_token: Option<String>,
+    store: Option<RwLock<LoadedStore>>,
+    jwt: Option<JwtVerifier>,
+}
+
+impl Authenticator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Single root-scoped token — the pre-token-store behavior.
+    pub fn static_token(token: impl Into<String>) -> Self {
+        Self::new().with_static_token(token)
+    }
+
+    pub fn with_static_token(mut self, token: impl Into<String>) -> Self {
+        self.static_token = Some(token.into());
+        self
+    }
+
+    pub fn with_store(mut self, store: TokenStore) -> Self {
+        let mtime = file_mtime(store.path());
+        self.store = Some(RwLock::new(LoadedStore { store, mtime }));
+        self
+    }
+
+    pub fn with_jwt(mut self, verifier: JwtVerifier) -> Self {
+        self.jwt = Some(verifier);
+        self
+    }
+
+    /// Resolve a bearer credential. Compact JWTs (three dot-separated
+    /// parts) go to the JWT verifier; everything else is checked
+    /// against the static token and the token store.
+    pub fn authenticate(&self, bearer: &str) -> Option<Principal> {
+        if bearer.split('.').count() == 3 {
+            if let Some(ref jwt) = self.jwt {
+                return jwt.verify(bearer, now_secs());
+            }
+        }
+        if let Some(ref expected) = self.static_token {
+            if ct_eq_token(bearer, expected) {
+                return Some(Principal::root(STATIC_PRINCIPAL));
+            }
+        }
+        let lock = self.store.as_ref()?;
+        self.reload_if_changed(lock);
+        let loaded = lock.read().unwrap_or_else(|e| e.into_inner());
+        loaded.store.verify(bearer)
+    }
+
+    /// Pick up `tokens create` / `tokens revoke` from another process.
+    /// A reload that fails to parse keeps the last good store.
+    fn reload_if_changed(&self, lock: &RwLock<LoadedStore>) {
+        let (path, seen) = {
+            let loaded = lock.read().unwrap_or_else(|e| e.into_inner());
+            (loaded.store.path().to_path_buf(), loaded.mtime)
+        };
+        let current = file_mtime(&path);
+        if current == seen {
+            return;
+        }
+        match TokenStore::open(&path) {
+            Ok(store) => {
+                let mut loaded = lock.write().unwrap_or_else(|e| e.into_inner());
+                *loaded = LoadedStore {
+                    store,
+                    mtime: current,
+                };
+                tracing::info!(path = %path.display(), "token store reloaded");
+            }
+            Err(e) => {
+                tracing::error!(
+                    path = %path.display(),
+                    error = %e,
+                    "token store reload failed; keeping previous tokens"
+                );
+            }
+        }
+    }
+}
+
+/// Resolve the request's principal or the status / code / message
+/// triple the caller wraps in its own error type (so each route records
+/// metrics under its own label).
+pub(crate) fn check_auth(
+    headers: &HeaderMap,
+    auth: &Authenticator,
+) -> Result<Principal, (StatusCode, &'static str, &'static str)> {
+    match bearer_token(headers) {
+        Some(t) => auth.authenticate(t).ok_or((
+            StatusCode::FORBIDDEN,
+            "unauthorized",
+            "bearer token did not match",
+        )),
+        None => Err((
+            StatusCode::UNAUTHORIZED,
+            "unauthenticated",
+            "missing or malformed Authorization header",
+        )),
+    }
+}
+
+/// Pull the bearer token out of the `Authorization` header.
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer "))
+}
+
+/// Constant-time bearer-token check.
+///
+/// `PartialEq` on `str` / `String` short-circuits at the first
+/// mismatching byte → enables remote byte-by-byte timing oracle. Both
+/// tokens go through SHA-256 (fixed 32-byte output) and the digests
+/// are compared with `subtle::ConstantTimeEq`. Hashing makes the
+/// comparison length-independent — supplying a 5-byte vs 500-byte
+/// token both pay the same SHA-256 + 32-byte compare cost.
+fn ct_eq_token(supplied: &str, expected: &str) -> bool {
+    let s = Sha256::digest(supplied.as_bytes());
+    let e = Sha256::digest(expected.as_bytes());
+    s.ct_eq(&e).into()
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn hex_decode_32(s: &str) -> Option<[u8; 32]> {
+    if s.len() != 64 {
+        return None;
+    }
+    let mut out = [0u8; 32];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(s.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(out)
+}
+
+fn file_mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn addr(s: &str) -> Address {
+        Address::parse(s).unwrap()
+    }
+
+    #[test]
+    fn scope_star_stays_inside_one_key() {
+        let p = ScopePattern::parse("ringhub.concierge[*]").unwrap();
+        assert!(p.matches(&addr("ringhub.concierge[alice]")));
+        assert!(!p.matches(&addr("ringhub.concierge[alice].dm")));
+        assert!(!p.matches(&addr("ringhub.bob[alice]")));
+        assert!(!p.matches(&addr("other.concierge[alice]")));
+        assert!(!p.matches(&addr("concierge[alice]")));
+        assert_eq!(p.namespace(), Some("ringhub"));
+    }
+
+    #[test]
+    fn scope_double_star_spans_segments() {
+        let ns = ScopePattern::parse("ringhub.**").unwrap();
+        assert!(ns.matches(&addr("ringhub.concierge[alice].help[x]")));
+        assert!(!ns.matches(&addr("bob[alice]")));
+        assert_eq!(ns.namespace(), Some("ringhub"));
+
+        let root = ScopePattern::root();
+        assert!(root.matches(&addr("anything.at[all]")));
+        assert_eq!(root.namespace(), None);
+
+        let bare = ScopePattern::parse("bob[*]").unwrap();
+        assert!(bare.matches(&addr("bob[alice]")));
+        assert_eq!(bare.namespace(), None);
+    }
+
+    #[test]
+    fn scope_rejects_malformed_patterns() {
+        assert!(ScopePattern::parse("").is_err());
+        assert!(ScopePattern::parse("bob[*").is_err());
+        assert!(ScopePattern::parse("bob]*[").is_err());
+    }
+
+    #[test]
+    fn principal_builds_namespaced_addresses() {
+        let p = Principal {
+            id: "ringhub".into(),
+            scope: ScopePattern::parse("ringhub.concierge[*]").unwrap(),
+            tiers: vec!["warm".into()],
+            rate_limit_per_minute: None,
+            budget: BudgetLimits::default(),
+        };
+        assert_eq!(p.target_address_str("concierge", "alice"), "ringhub.concierge[alice]");
+        assert_eq!(p.sender_address().unwrap().raw(), "ringhub.gateway[ringhub]");
+        assert_eq!(p.sender_address().unwrap().namespace(), Some("ringhub"));
+        assert!(p.allows_tier("warm"));
+        assert!(!p.allows_tier("member"));
+
+        let root = Principal::root(STATIC_PRINCIPAL);
+        assert_eq!(root.target_address_str("bob", "alice"), "bob[alice]");
+        assert!(root.sender_address().is_none());
+        assert!(root.allows_tier("member"));
+    }
+
+    #[test]
+    fn store_create_verify_revoke_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let path = TokenStore::default_path(dir.path());
+        let mut store = TokenStore::open(&path).unwrap();
+        let secret = store
+            .create(
+                "ringhub",
+                ScopePattern::parse("ringhub.concierge[*]").unwrap(),
+                vec!["warm".into()],
+                Some(120),
+                BudgetLimits {
+                    tokens: None,
+                    usd: Some(25.0),
+                },
+            )
+            .unwrap();
+
+        // Secret never hits the disk.
+        let on_disk = std::fs::read_to_string(&path).unwrap();
+        assert!(!on_disk.contains(&secret));
+
+        let reopened = TokenStore::open(&path).unwrap();
+        let p = reopened.verify(&secret).unwrap();
+        assert_eq!(p.id, "ringhub");
+        assert_eq!(p.rate_limit_per_minute, Some(120));
+        assert_eq!(p.budget.usd, Some(25.0));
+        assert!(reopened.verify("aos_wrong").is_none());
+
+        let mut store = reopened;
+        store.revoke("ringhub").unwrap();
+        assert!(store.verify(&secret).is_none());
+        assert_eq!(store.list().len(), 1, "revoked tokens stay listed");
+        assert!(matches!(store.revoke("nope"), Err(TokenError::NotFound(_))));
+    }
+
+    #[test]
+    fn store_rejects_duplicates_bad_names_and_tiers() {
+        let dir = TempDir::new().unwrap();
+        let mut store = TokenStore::open(TokenStore::default_path(dir.path())).unwrap();
+        store.create("a", ScopePattern::root(), vec![], None, BudgetLimits::default()).unwrap();
+        assert!(matches!(
+            store.create("a", ScopePattern::root(), vec![], None, BudgetLimits::default()),
+            Err(TokenError::AlreadyExists(_))
+        ));
+        assert!(matches!(
+            store.create("bad name", ScopePattern::root(), vec![], None, BudgetLimits::default()),
+            Err(TokenError::InvalidName(_))
+        ));
+        assert!(matches!(
+            store.create("b", ScopePattern::root(), vec!["anon".into()], None, BudgetLimits::default()),
+            Err(TokenError::InvalidTier(_))
+        ));
+    }
+
+    #[test]
+    fn store_rejects_un_namespaced_scopes() {
+        let dir = TempDir::new().unwrap();
+        let path = TokenStore::default_path(dir.path());
+        let mut store = TokenStore::open(&path).unwrap();
+        for raw in ["bob[*]", "*.bob[*]", "*[*]"] {
+            let scope = ScopePattern::parse(raw).unwrap();
+            assert!(matches!(
+                store.create("t", scope, vec![], None, BudgetLimits::default()),
+                Err(TokenError::UnNamespacedScope(_))
+            ));
+        }
+        assert!(store.list().is_empty());
+
+        // A record that slipped in some other way authenticates nobody.
+        let secret = store
+            .create("t", ScopePattern::parse("ringhub.bob[*]").unwrap(), vec![], None, BudgetLimits::default())
+            .unwrap();
+        let edited = std::fs::read_to_string(&path).unwrap().replace("ringhub.bob[*]", "bob[*]");
+        std::fs::write(&path, edited).unwrap();
+        assert!(TokenStore::open(&path).unwrap().verify(&secret).is_none());
+    }
+
+    #[test]
+    fn authenticator_hot_reloads_store() {
+        let dir = TempDir::new().unwrap();
+        let path = TokenStore::default_path(dir.path());
+        let auth = Authenticator::new().with_store(TokenStore::open(&path).unwrap());
+        assert!(auth.authenticate("aos_anything").is_none());
+
+        // Another process (the CLI) mints a token.
+        let mut cli_view = TokenStore::open(&path).unwrap();
+        let secret = cli_view.create("late", ScopePattern::root(), vec![], None, BudgetLimits::default()).unwrap();
+        assert_eq!(auth.authenticate(&secret).unwrap().id, "late");
+    }
+
+    #[test]
+    fn static_token_is_root() {
+        let auth = Authenticator::static_token("tok");
+        let p = auth.authenticate("tok").unwrap();
+        assert_eq!(p.id, STATIC_PRINCIPAL);
+        assert_eq!(p.scope, ScopePattern::root());
+        assert!(auth.authenticate("nope").is_none());
+    }
+
+    // ── JWT ──
+
+    const KEY: &[u8] = b"super-secret-hmac-key-for-tests!";
+
+    fn jwks() -> Vec<u8> {
+        serde_json::to_vec(&serde_json::json!({
+            "keys": [{ "kty": "oct", "kid": "k1", "alg": "HS256", "k": URL_SAFE_NO_PAD.encode(KEY) }]
+        }))
+        .unwrap()
+    }
+
+    fn sign(header: serde_json::Value, claims: serde_json::Value, key: &[u8]) -> String {
+        let h = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&header).unwrap());
+        let c = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap());
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+        mac.update(format!("{h}.{c}").as_bytes());
+        let s = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+        format!("{h}.{c}.{s}")
+    }
+
+    fn claims(exp: u64) -> serde_json::Value {
+        serde_json::json!({
+            "sub": "svc-a", "exp": exp, "iss": "ringhub", "aud": ["agentos"],
+            "scope": "ringhub.concierge[*]", "tiers": ["member"], "rpm": 30,
+            "budget_usd": 5.0
+        })
+    }
+
+    #[test]
+    fn jwt_valid_token_maps_to_principal() {
+        let v = JwtVerifier::from_jwks(&jwks(), Some("ringhub".into()), Some("agentos".into()))
+            .unwrap();
+        let now = 1_700_000_000;
+        let tok = sign(serde_json::json!({"alg": "HS256", "kid": "k1"}), claims(now + 60), KEY);
+        let p = v.verify(&tok, now).unwrap();
+        assert_eq!(p.id, "jwt:svc-a");
+        assert_eq!(p.scope.as_str(), "ringhub.concierge[*]");
+        assert_eq!(p.tiers, vec!["member".to_string()]);
+        assert_eq!(p.rate_limit_per_minute, Some(30));
+        assert_eq!(p.budget.usd, Some(5.0));
+    }
+
+    #[test]
+    fn jwt_rejects_bad_signature_expiry_alg_and_audience() {
+        let v = JwtVerifier::from_jwks(&jwks(), None, Some("agentos".into())).unwrap();
+        let now = 1_700_000_000;
+        let hdr = serde_json::json!({"alg": "HS256", "kid": "k1"});
+
+        let forged = sign(hdr.clone(), claims(now + 60), b"wrong-key");
+        assert!(v.verify(&forged, now).is_none());
+
+        let expired = sign(hdr.clone(), claims(now - 3600), KEY);
+        assert!(v.verify(&expired, now).is_none());
+
+        let none_alg = sign(serde_json::json!({"alg": "none", "kid": "k1"}), claims(now + 60), KEY);
+        assert!(v.verify(&none_alg, now).is_none());
+
+        let mut wrong_aud = claims(now + 60);
+        wrong_aud["aud"] = serde_json::json!("someone-else");
+        assert!(v.verify(&sign(hdr.clone(), wrong_aud, KEY), now).is_none());
+
+        let mut no_scope = claims(now + 60);
+        no_scope.as_object_mut().unwrap().remove("scope");
+        assert!(v.verify(&sign(hdr.clone(), no_scope, KEY), now).is_none());
+
+        let mut bare_scope = claims(now + 60);
+        bare_scope["scope"] = serde_json::json!("bob[*]");
+        assert!(v.verify(&sign(hdr, bare_scope, KEY), now).is_none());
+    }
+
+    #[test]
+    fn authenticator_routes_jwts_to_verifier() {
+        let v = JwtVerifier::from_jwks(&jwks(), None, None).unwrap();
+        let auth = Authenticator::static_token("tok").with_jwt(v);
+        let tok = sign(
+            serde_json::json!({"alg": "HS256", "kid": "k1"}),
+            claims(now_secs() + 60),
+            KEY,
+        );
+        assert_eq!(auth.authenticate(&tok).unwrap().id, "jwt:svc-a");
+        assert_eq!(auth.authenticate("tok").unwrap().id, STATIC_PRINCIPAL);
+    }
+}
@@ -2,8 +2,13 @@
 //!
 //! Loads an organism from `--organism <path>` (or a stub if omitted),
 //! constructs the pipeline, mounts the platform router, starts the
-//! HTTP server. Static bearer token from `--auth-token` or
-//! `AGENTOS_SERVER_TOKEN` env var.
+//! HTTP server. Callers authenticate with any combination of:
+//!
+//! - named, scoped tokens from `<data>/server_tokens.json`, managed with
+//!   `agentos-server tokens create|revoke|list` (picked up live);
+//! - HS256 JWTs verified against `--jwks <file>`;
+//! - the legacy static token from `--auth-token-file`, `--auth-token`
+//!   or `AGENTOS_SERVER_TOKEN` (root scope).
 //!
 //! Note on conversation persistence: the registry is in-memory only.
 //! A restart drops all materialized instances. See Step 3.5 in the
@@ -14,12 +19,19 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use tokio::net::TcpListener;
 use tracing::info;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
 
 use agentos_organism::parser::parse_organism;
 use agentos_pipeline::AgentPipelineBuilder;
+use agentos_config::ModelsConfig;
+use agentos_server::auth::{Authenticator, JwtVerifier, ScopePattern, TokenStore};
+use agentos_server::budget::{BudgetLimits, Budgets};
+use agentos_server::rate_limit::RateLimiter;
 use agentos_server::{build_router, ServerState};
 
 /// Embedded fallback organism so the bin can boot without an explicit
@@ -74,40 +86,157 @@ struct Cli {
     /// Listener name to handle /v1/messages traffic. Defaults to "bob".
     #[arg(long, default_value = "bob")]
     agent: String,
+
+    /// Local JWKS file with HS256 (`kty: "oct"`) keys. Enables JWT
+    /// bearer tokens carrying `scope` / `tiers` / `rpm` claims.
+    #[arg(long)]
+    jwks: Option<PathBuf>,
+
+    /// Required `iss` claim for JWTs.
+    #[arg(long, requires = "jwks")]
+    jwt_issuer: Option<String>,
+
+    /// Required `aud` claim for JWTs.
+    #[arg(long, requires = "jwks")]
+    jwt_audience: Option<String>,
+
+    /// Requests per minute for any single user (per token).
+    #[arg(long)]
+    user_rate_limit: Option<u32>,
+
+    /// LLM tokens (input + output) any single user may consume per
+    /// budget window.
+    #[arg(long)]
+    user_token_budget: Option<u64>,
+
+    /// LLM spend in USD any single user may incur per budget window.
+    /// Priced from the `prices` table in `--models`.
+    #[arg(long)]
+    user_usd_budget: Option<f64>,
+
+    /// Rolling budget window, in hours.
+    #[arg(long, default_value_t = 24)]
+    budget_window_hours: u64,
+
+    /// models.yaml carrying the `prices` table. Defaults to the user
+    /// config (`~/.agentos/models.yaml`).
+    #[arg(long)]
+    models: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage named API tokens in `<data>/server_tokens.json`.
+    Tokens {
+        #[command(subcommand)]
+        action: TokensAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum TokensAction {
+    /// Mint a token. The secret is printed once and never stored.
+    Create {
+        /// Token name (`[A-Za-z0-9_-]`, unique).
+        name: String,
+        /// Address pattern the token may reach, e.g. `ringhub.concierge[*]`.
+        /// `*` matches within one key/segment, `**` matches anything.
+        #[arg(long)]
+        scope: String,
+        /// Allowed `user_tier` (repeatable). Omit to allow every tier.
+        #[arg(long = "tier")]
+        tiers: Vec<String>,
+        /// Requests per minute across all users of this token.
+        #[arg(long)]
+        rate_limit: Option<u32>,
+        /// LLM tokens per budget window across all users of this token.
+        #[arg(long)]
+        token_budget: Option<u64>,
+        /// LLM spend (USD) per budget window across all users of this token.
+        #[arg(long)]
+        usd_budget: Option<f64>,
+    },
+    /// Revoke a token. It stays listed with its revocation time.
+    Revoke { name: String },
+    /// List tokens (never their secrets).
+    List,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info,agentos=debug")),
+    // Request traces: OTLP when OTEL_EXPORTER_OTLP_ENDPOINT is set, a
+    // JSON-lines file when AGENTOS_TRACE_FILE is. The guard flushes on exit.
+    let (trace_layer, _trace_guard) = match agentos_telemetry::trace::TraceExport::from_env() {
+        Some(export) => {
+            let (layer, guard) = agentos_telemetry::trace::layer(&export, "agentos-server")
+                .map_err(anyhow::Error::msg)?;
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer().with_filter(
+                tracing_subscriber::EnvFilter::try_from_default_env()
+                    .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info,agentos=debug")),
+            ),
         )
+        .with(trace_layer)
         .init();
 
     let cli = Cli::parse();
 
-    // Token sourcing precedence: --auth-token-file > --auth-token >
+    let tokens_path = TokenStore::default_path(&cli.data);
+    if let Some(Command::Tokens { action }) = cli.command {
+        return run_tokens(&tokens_path, action);
+    }
+
+    // Static token sourcing precedence: --auth-token-file > --auth-token >
     // AGENTOS_SERVER_TOKEN env var. The file is the recommended path
     // for production; the inline flag and env var are dev conveniences
     // that leak to local-attacker recon (ps, /proc/<pid>/environ).
-    let token = if let Some(ref path) = cli.auth_token_file {
+    let static_token = if let Some(ref path) = cli.auth_token_file {
         let raw = std::fs::read_to_string(path)
             .with_context(|| format!("reading auth-token-file {}", path.display()))?;
         let trimmed = raw.trim();
         if trimmed.is_empty() {
             anyhow::bail!("auth-token-file {} is empty", path.display());
         }
-        trimmed.to_string()
+        Some(trimmed.to_string())
     } else {
         cli.auth_token
+            .clone()
             .or_else(|| std::env::var("AGENTOS_SERVER_TOKEN").ok())
-            .context(
-                "no auth token: pass --auth-token-file (preferred), --auth-token, \
-                 or set AGENTOS_SERVER_TOKEN env var",
-            )?
     };
 
+    // The token store is always attached, even when empty, so tokens
+    // created while the server runs are picked up without a restart.
+    let store = TokenStore::open(&tokens_path)
+        .with_context(|| format!("loading token store {}", tokens_path.display()))?;
+    let active_tokens = store.list().iter().filter(|t| t.is_active()).count();
+    info!(tokens = active_tokens, path = %tokens_path.display(), "token store loaded");
+    let mut auth = Authenticator::new().with_store(store);
+    let has_static = static_token.is_some();
+    if let Some(token) = static_token {
+        auth = auth.with_static_token(token);
+    }
+    if let Some(ref path) = cli.jwks {
+        let verifier =
+            JwtVerifier::from_jwks_file(path, cli.jwt_issuer.clone(), cli.jwt_audience.clone())
+                .with_context(|| format!("loading JWKS {}", path.display()))?;
+        auth = auth.with_jwt(verifier);
+    }
+    if active_tokens == 0 && !has_static && cli.jwks.is_none() {
+        anyhow::bail!(
+            "no auth configured: create a token with `agentos-server tokens create`, \
+             pass --jwks, or pass --auth-token-file (preferred) / --auth-token / \
+             AGENTOS_SERVER_TOKEN"
+        );
+    }
+
     let yaml = match cli.organism {
         Some(ref p) => std::fs::read_to_string(p)
             .with_context(|| format!("reading organism from {}", p.display()))?,
@@ -118,7 +247,10 @@ async fn main() -> Result<()> {
     };
     let org = parse_organism(&yaml).map_err(|e| anyhow::anyhow!("organism parse: {e}"))?;
 
-    let builder = AgentPipelineBuilder::new(org, &cli.data);
+    // shim-train listeners get the trainer their `trainer:` block selects.
+    let builder = AgentPipelineBuilder::new(org, &cli.data)
+        .with_shim_train_tools()
+        .map_err(|e| anyhow::anyhow!("shim-train registration: {e}"))?;
     let event_tx = builder.event_sender();
     let mut pipeline = builder
         .build()
@@ -139,6 +271,8 @@ async fn main() -> Result<()> {
 
     let shared_router = Arc::new(pipeline.shared_router(0, Duration::from_secs(60)));
     let _eviction = shared_router.start_eviction_timer();
+    // Context-store occupancy for /metrics (agentos_context_bytes).
+    let _context_gauges = pipeline.spawn_gauge_sampler(Duration::from_secs(10));
 
     let idempotency = agentos_server::idempotency::IdempotencyCache::new();
     // Periodic TTL sweep; the handle is dropped on shutdown which
@@ -152,14 +286,40 @@ async fn main() -> Result<()> {
     // a gauge over a DashMap needs an active sampler — every 10s is
     // plenty for capacity monitoring (the cache changes on human-paced
     // request rates, not microseconds).
+    let prices = match cli.models {
+        Some(ref path) => ModelsConfig::load_file(path).map_err(|e| anyhow::anyhow!(e))?,
+        None => ModelsConfig::load(),
+    };
+    if cli.user_usd_budget.is_some() && prices.prices.is_empty() {
+        tracing::warn!(
+            "--user-usd-budget is set but models.yaml has no `prices` table; \
+             dollar budgets will never trip"
+        );
+    }
+    let budgets = Budgets::new(
+        Duration::from_secs(cli.budget_window_hours.max(1) * 60 * 60),
+        BudgetLimits {
+            tokens: cli.user_token_budget,
+            usd: cli.user_usd_budget,
+        },
+        prices,
+    );
+    let _budget_listener = budgets.clone().spawn_listener(&event_tx);
+    let rate_limiter = RateLimiter::with_user_limit(cli.user_rate_limit);
+
+    // The budget gauges and limiter sweep ride the same ticker.
     let _gauge_updater = {
         let cache = idempotency.clone();
+        let budgets = budgets.clone();
+        let rate_limiter = rate_limiter.clone();
         tokio::spawn(async move {
             let mut ticker = tokio::time::interval(Duration::from_secs(10));
             ticker.tick().await;
             loop {
                 ticker.tick().await;
                 agentos_server::metrics::set_idempotency_cache_entries(cache.len());
+                budgets.publish_gauges();
+                rate_limiter.sweep();
             }
         })
     };
@@ -167,10 +327,13 @@ async fn main() -> Result<()> {
     let state = Arc::new(ServerState {
         router: shared_router,
         events: event_tx,
+        kernel: pipeline.kernel(),
         organism: Arc::new(pipeline.organism().clone()),
         agent_name: cli.agent,
-        auth_token: token,
+        auth: Arc::new(auth),
         idempotency,
+        rate_limiter,
+        budgets,
     });
 
     let app = build_router(state);
@@ -206,3 +369,61 @@ async fn main() -> Result<()> {
     axum::serve(listener, app).await.context("axum serve")?;
     Ok(())
 }
+
+/// `agentos-server tokens ...` — offline token-store management. A
+/// running server picks the changes up on its next authenticated
+/// request (the store is reloaded when its mtime moves).
+fn run_tokens(path: &std::path::Path, action: TokensAction) -> Result<()> {
+    let mut store = TokenStore::open(path)
+        .with_context(|| format!("loading token store {}", path.display()))?;
+    match action {
+        TokensAction::Create {
+            name,
+            scope,
+            tiers,
+            rate_limit,
+            token_budget,
+            usd_budget,
+        } => {
+            let scope = ScopePattern::parse(&scope)?;
+            let budget = BudgetLimits {
+                tokens: token_budget,
+                usd: usd_budget,
+            };
+            let secret = store.create(&name, scope, tiers, rate_limit, budget)?;
+            eprintln!("created token {name:?}; the secret is shown once:");
+            println!("{secret}");
+        }
+        TokensAction::Revoke { name } => {
+            store.revoke(&name)?;
+            eprintln!("revoked token {name:?}");
+        }
+        TokensAction::List => {
+            for t in store.list() {
+                let tiers = if t.tiers.is_empty() {
+                    "*".to_string()
+                } else {
+                    t.tiers.join(",")
+                };
+                let rpm = t
+                    .rate_limit_per_minute
+                    .map(|n| format!("{n}/min"))
+                    .unwrap_or_else(|| "unlimited".into());
+                let budget = match (t.budget.tokens, t.budget.usd) {
+                    (None, None) => "no-budget".to_string(),
+                    (tok, usd) => format!(
+                        "budget={}tok/${}",
+                        tok.map_or("-".into(), |n| n.to_string()),
+                        usd.map_or("-".into(), |n| format!("{n:.2}")),
+                    ),
+                };
+                let status = if t.is_active() { "active" } else { "revoked" };
+                println!(
+                    "{}\t{}\ttiers={}\t{}\t{}\t{}",
+                    t.name, t.scope, tiers, rpm, budget, status
+                );
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,479 @@
+//! Rolling LLM token and dollar budgets.
+//!
+//! The agent emits `PipelineEvent::TokenUsage` after every LLM call
+//! (model id + `MessagesResponse.usage`). A listener task prices each
+//! event against the `prices` table in `models.yaml` and charges it to
+//! two ledger entries: the calling principal (token) and the user
+//! (the chat instance address, so `alice` under one tenant is not
+//! `alice` under another).
+//!
+//! Usage events carry only a thread_id; `post_messages` binds each
+//! buffer thread to its (principal, user) owner after delivery. The
+//! first LLM call of a brand-new buffer always lands after that bind
+//! (the handler binds as soon as the router returns, long before the
+//! model answers). Bindings go when the admin API erases their threads,
+//! and any left idle for a whole window (idle-evicted instances, say)
+//! are dropped by the gauge sampler; the next message binds afresh.
+//!
+//! `post_messages` checks both entries before dispatch; an exhausted
+//! budget answers 429 with `retry_after` set to when enough usage ages
+//! out of the window to get back under the cap. Budgets are soft by
+//! one turn: the turn that crosses the line completes, the next one is
+//! refused.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use agentos_config::ModelsConfig;
+use agentos_events::PipelineEvent;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::auth::Principal;
+use crate::metrics;
+use crate::rate_limit::RateDecision;
+
+/// Default rolling window.
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Buckets per window. Usage ages out in window/24 steps.
+const BUCKETS: u32 = 24;
+
+/// Caps over one rolling window. `None` = uncapped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct BudgetLimits {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usd: Option<f64>,
+}
+
+impl BudgetLimits {
+    pub fn is_unlimited(&self) -> bool {
+        self.tokens.is_none() && self.usd.is_none()
+    }
+}
+
+/// Usage inside the current window.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Spend {
+    pub tokens: u64,
+    pub usd: f64,
+}
+
+impl Spend {
+    fn exceeds(&self, limits: &BudgetLimits) -> bool {
+        limits.tokens.is_some_and(|cap| self.tokens >= cap)
+            || limits.usd.is_some_and(|cap| self.usd >= cap)
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    start: Instant,
+    spend: Spend,
+}
+
+/// Time-bucketed usage for one ledger key.
+#[derive(Debug, Default)]
+struct Rolling {
+    buckets: VecDeque<Bucket>,
+}
+
+impl Rolling {
+    fn prune(&mut self, window: Duration, now: Instant) {
+        while self
+            .buckets
+            .front()
+            .is_some_and(|b| now.duration_since(b.start) >= window)
+        {
+            self.buckets.pop_front();
+        }
+    }
+
+    fn charge(&mut self, spend: Spend, window: Duration, now: Instant) {
+        self.prune(window, now);
+        let width = window / BUCKETS;
+        match self.buckets.back_mut() {
+            Some(b) if now.duration_since(b.start) < width => {
+                b.spend.tokens += spend.tokens;
+                b.spend.usd += spend.usd;
+            }
+            _ => self.buckets.push_back(Bucket { start: now, spend }),
+        }
+    }
+
+    fn total(&self) -> Spend {
+        self.buckets.iter().fold(Spend::default(), |acc, b| Spend {
+            tokens: acc.tokens + b.spend.tokens,
+            usd: acc.usd + b.spend.usd,
+        })
+    }
+
+    /// How long until the total drops back under `limits`, if it is
+    /// over them now.
+    fn retry_after(&self, limits: &BudgetLimits, window: Duration, now: Instant) -> Option<Duration> {
+        let mut remaining = self.total();
+        if !remaining.exceeds(limits) {
+            return None;
+        }
+        for b in &self.buckets {
+            remaining.tokens -= b.spend.tokens;
+            remaining.usd -= b.spend.usd;
+            if !remaining.exceeds(limits) {
+                return Some((b.start + window).saturating_duration_since(now));
+            }
+        }
+        Some(window)
+    }
+}
+
+/// Who a buffer thread's usage is charged to.
+#[derive(Debug, Clone)]
+struct Owner {
+    principal: String,
+    principal_limits: BudgetLimits,
+    user: String,
+    /// Last bind or charge, for expiry.
+    last_seen: Instant,
+}
+
+/// Server-wide budget state. Held by `ServerState`.
+#[derive(Debug)]
+pub struct Budgets {
+    window: Duration,
+    per_user: BudgetLimits,
+    prices: ModelsConfig,
+    ledger: DashMap<String, Rolling>,
+    owners: DashMap<String, Owner>,
+    /// Models already warned about as unpriced (log once each).
+    unpriced: DashMap<String, ()>,
+}
+
+impl Budgets {
+    pub fn new(window: Duration, per_user: BudgetLimits, prices: ModelsConfig) -> Arc<Self> {
+        Arc::new(Self {
+            window,
+            per_user,
+            prices,
+            ledger: DashMap::new(),
+            owners: DashMap::new(),
+            unpriced: DashMap::new(),
+        })
+    }
+
+    /// No per-user caps and no price table. Principal caps still apply.
+    pub fn unlimited() -> Arc<Self> {
+        Self::new(DEFAULT_WINDOW, BudgetLimits::default(), ModelsConfig::default())
+    }
+
+    fn principal_key(id: &str) -> String {
+        format!("p:{id}")
+    }
+
+    fn user_key(principal: &str, user: &str) -> String {
+        format!("u:{principal}/{user}")
+    }
+
+    /// Pre-dispatch check for `user` (the instance address) under
+    /// `principal`.
+    pub fn check(&self, principal: &Principal, user: &str) -> RateDecision {
+        self.check_at(principal, user, Instant::now())
+    }
+
+    pub fn check_at(&self, principal: &Principal, user: &str, now: Instant) -> RateDecision {
+        let probes = [
+            (Self::principal_key(&principal.id), principal.budget),
+            (Self::user_key(&principal.id, user), self.per_user),
+        ];
+        let worst = probes
+            .iter()
+            .filter(|(_, limits)| !limits.is_unlimited())
+            .filter_map(|(key, limits)| {
+                let mut entry = self.ledger.get_mut(key)?;
+                entry.prune(self.window, now);
+                entry.retry_after(limits, self.window, now)
+            })
+            .max();
+        match worst {
+            Some(retry_after) => RateDecision::Limited { retry_after },
+            None => RateDecision::Allowed,
+        }
+    }
+
+    /// Attribute future usage on `thread_id` to (principal, user).
+    pub fn bind_thread(&self, thread_id: &str, principal: &Principal, user: &str) {
+        self.owners.insert(
+            thread_id.to_string(),
+            Owner {
+                principal: principal.id.clone(),
+                principal_limits: principal.budget,
+                user: user.to_string(),
+                last_seen: Instant::now(),
+            },
+        );
+    }
+
+    /// Forget the owners of erased threads.
+    pub fn unbind_threads(&self, thread_ids: &[String]) {
+        for thread_id in thread_ids {
+            self.owners.remove(thread_id);
+        }
+    }
+
+    /// Drop owners neither bound nor charged within the last window.
+    fn expire_owners(&self, now: Instant) {
+        self.owners
+            .retain(|_, owner| now.saturating_duration_since(owner.last_seen) < self.window);
+    }
+
+    /// Price and charge one LLM call. Threads nobody bound (TUI,
+    /// internal agents) are ignored.
+    pub fn record_usage(&self, thread_id: &str, model: &str, input_tokens: u32, output_tokens: u32) {
+        self.record_usage_at(thread_id, model, input_tokens, output_tokens, Instant::now());
+    }
+
+    pub fn record_usage_at(
+        &self,
+        thread_id: &str,
+        model: &str,
+        input_tokens: u32,
+        output_tokens: u32,
+        now: Instant,
+    ) {
+        let Some(owner) = self.owners.get_mut(thread_id).map(|mut o| {
+            o.last_seen = now;
+            o.clone()
+        }) else {
+            return;
+        };
+        let usd = match self.prices.price_for(model) {
+            Some(price) => price.cost_usd(input_tokens, output_tokens),
+            None => {
+                if self.unpriced.insert(model.to_string(), ()).is_none() {
+                    tracing::warn!(
+                        model,
+                        "no price for model in models.yaml; dollar budgets won't count it"
+                    );
+                }
+                0.0
+            }
+        };
+        let spend = Spend {
+            tokens: input_tokens as u64 + output_tokens as u64,
+            usd,
+        };
+        metrics::record_llm_spend(spend.tokens, usd);
+        for key in [
+            Self::principal_key(&owner.principal),
+            Self::user_key(&owner.principal, &owner.user),
+        ] {
+            self.ledger
+                .entry(key)
+                .or_default()
+                .charge(spend, self.window, now);
+        }
+    }
+
+    /// Current window spend for a principal.
+    pub fn principal_spend(&self, principal: &str) -> Spend {
+        self.spend(&Self::principal_key(principal))
+    }
+
+    /// Current window spend for a user under a principal.
+    pub fn user_spend(&self, principal: &str, user: &str) -> Spend {
+        self.spend(&Self::user_key(principal, user))
+    }
+
+    fn spend(&self, key: &str) -> Spend {
+        self.ledger
+            .get_mut(key)
+            .map(|mut r| {
+                r.prune(self.window, Instant::now());
+                r.total()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Refresh the budget gauges and drop fully aged-out ledger entries
+    /// and idle thread owners.
+    /// Called from the bin's periodic sampler (per-principal gauges are
+    /// labelled by token name, which operators mint — bounded
+    /// cardinality; users are only ever counted, never labelled).
+    pub fn publish_gauges(&self) {
+        let now = Instant::now();
+        let mut exhausted_users = 0usize;
+        self.ledger.retain(|key, rolling| {
+            rolling.prune(self.window, now);
+            let total = rolling.total();
+            if let Some(principal) = key.strip_prefix("p:") {
+                metrics::set_budget_spend(principal, total.tokens, total.usd);
+            } else if total.exceeds(&self.per_user) {
+                exhausted_users += 1;
+            }
+            !rolling.buckets.is_empty()
+        });
+        self.expire_owners(now);
+        let mut usd_limits = HashMap::new();
+        for owner in self.owners.iter() {
+            if let Some(cap) = owner.principal_limits.usd {
+                usd_limits.insert(owner.principal.clone(), cap);
+            }
+        }
+        for (principal, cap) in usd_limits {
+            metrics::set_budget_usd_limit(&principal, cap);
+        }
+        metrics::set_budget_users_exhausted(exhausted_users);
+    }
+
+    /// Charge `TokenUsage` events off the pipeline broadcast until the
+    /// sender closes.
+    pub fn spawn_listener(
+        self: Arc<Self>,
+        events: &broadcast::Sender<PipelineEvent>,
+    ) -> tokio::task::JoinHandle<()> {
+        let mut rx = events.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(PipelineEvent::TokenUsage {
+                        thread_id,
+                        model,
+                        input_tokens,
+                        output_tokens,
+                    }) => self.record_usage(&thread_id, &model, input_tokens, output_tokens),
+                    Ok(_) => {}
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        // Dropped usage is unbilled usage; make it visible.
+                        tracing::warn!(skipped = n, "budget listener lagged; usage events lost");
+                        metrics::record_broadcast_lag();
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::ScopePattern;
+    use agentos_config::ModelPrice;
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    fn prices() -> ModelsConfig {
+        let mut config = ModelsConfig::default();
+        config.prices.insert(
+            "claude-sonnet-4-6".into(),
+            ModelPrice {
+                input_per_mtok: 3.0,
+                output_per_mtok: 15.0,
+            },
+        );
+        config
+    }
+
+    fn principal(budget: BudgetLimits) -> Principal {
+        Principal {
+            id: "ringhub".into(),
+            scope: ScopePattern::root(),
+            tiers: vec![],
+            rate_limit_per_minute: None,
+            budget,
+        }
+    }
+
+    #[test]
+    fn user_token_budget_blocks_until_usage_ages_out() {
+        let b = Budgets::new(
+            24 * HOUR,
+            BudgetLimits {
+                tokens: Some(1_000),
+                usd: None,
+            },
+            prices(),
+        );
+        let p = principal(BudgetLimits::default());
+        let t0 = Instant::now();
+        b.bind_thread("t1", &p, "bob[alice]");
+
+        b.record_usage_at("t1", "claude-sonnet-4-6", 400, 200, t0);
+        assert_eq!(b.check_at(&p, "bob[alice]", t0), RateDecision::Allowed);
+
+        b.record_usage_at("t1", "claude-sonnet-4-6", 300, 200, t0 + 2 * HOUR);
+        let d = b.check_at(&p, "bob[alice]", t0 + 3 * HOUR);
+        // Dropping the first bucket (600 tokens) gets back under the cap.
+        assert_eq!(
+            d,
+            RateDecision::Limited {
+                retry_after: 21 * HOUR
+            }
+        );
+        // Another user of the same token is unaffected.
+        assert_eq!(b.check_at(&p, "bob[carol]", t0), RateDecision::Allowed);
+        // After the first bucket ages out, alice is back under.
+        assert_eq!(b.check_at(&p, "bob[alice]", t0 + 24 * HOUR), RateDecision::Allowed);
+    }
+
+    #[test]
+    fn principal_dollar_budget_spans_users() {
+        let b = Budgets::new(24 * HOUR, BudgetLimits::default(), prices());
+        let p = principal(BudgetLimits {
+            tokens: None,
+            usd: Some(1.0),
+        });
+        let t0 = Instant::now();
+        b.bind_thread("t1", &p, "bob[alice]");
+        b.bind_thread("t2", &p, "bob[carol]");
+
+        // $0.60 each: 100k input @ $3/M + 20k output @ $15/M.
+        b.record_usage_at("t1", "claude-sonnet-4-6", 100_000, 20_000, t0);
+        assert_eq!(b.check_at(&p, "bob[dave]", t0), RateDecision::Allowed);
+        b.record_usage_at("t2", "claude-sonnet-4-6", 100_000, 20_000, t0);
+        assert!(matches!(
+            b.check_at(&p, "bob[dave]", t0),
+            RateDecision::Limited { .. }
+        ));
+        assert!((b.principal_spend("ringhub").usd - 1.2).abs() < 1e-9);
+        assert_eq!(b.user_spend("ringhub", "bob[alice]").tokens, 120_000);
+    }
+
+    #[test]
+    fn unbound_threads_and_unpriced_models() {
+        let b = Budgets::new(24 * HOUR, BudgetLimits::default(), prices());
+        b.record_usage("nobody", "claude-sonnet-4-6", 10, 10);
+        assert_eq!(b.principal_spend("ringhub"), Spend::default());
+
+        let p = principal(BudgetLimits::default());
+        b.bind_thread("t1", &p, "bob[alice]");
+        b.record_usage("t1", "mystery-model", 10, 5);
+        let s = b.principal_spend("ringhub");
+        assert_eq!(s.tokens, 15);
+        assert_eq!(s.usd, 0.0);
+    }
+
+    #[test]
+    fn owners_go_on_unbind_and_after_an_idle_window() {
+        let b = Budgets::new(24 * HOUR, BudgetLimits::default(), prices());
+        let p = principal(BudgetLimits::default());
+        let t0 = Instant::now();
+        b.bind_thread("t1", &p, "bob[alice]");
+        b.bind_thread("t2", &p, "bob[carol]");
+        b.bind_thread("t3", &p, "bob[dave]");
+
+        b.unbind_threads(&["t1".to_string()]);
+        b.record_usage_at("t2", "claude-sonnet-4-6", 10, 0, t0 + 20 * HOUR);
+        b.expire_owners(t0 + 30 * HOUR);
+        assert_eq!(b.owners.len(), 1);
+        assert!(b.owners.contains_key("t2"));
+
+        // Unbound threads are no longer charged.
+        b.record_usage_at("t1", "claude-sonnet-4-6", 10, 0, t0 + 30 * HOUR);
+        b.record_usage_at("t3", "claude-sonnet-4-6", 10, 0, t0 + 30 * HOUR);
+        assert_eq!(b.principal_spend("ringhub").tokens, 10);
+    }
+}
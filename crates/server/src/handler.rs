@@ -1,8 +1,11 @@
 //! `POST /v1/messages` — chat-bubble Bob endpoint.
 //!
 //! Wires:
-//!  1. Bearer token check
-//!  2. Request validation (reject `user_tier="anon"`)
+//!  1. Bearer token check → resolve the calling [`crate::auth::Principal`]
+//!  2. Request validation (reject `user_tier="anon"`, tiers and
+//!     addresses outside the principal's scope), then admission:
+//!     per-token and per-user rate limits and rolling LLM budgets
+//!     (429 `rate_limited` / `budget_exceeded` with `retry_after`)
 //!  3. Subscribe to pipeline events *before* sending so we don't miss
 //!     a fast-returning AgentResponse
 //!  4. Materialize-and-deliver via the platform router
@@ -20,11 +23,12 @@ use agentos_platform::address::Address;
 use agentos_platform::buffers::BufferId;
 use agentos_platform::router::Envelope;
 
+use crate::auth::check_auth;
 use crate::idempotency::{IdempotencyCache, InFlightGuard, LookupResult};
 use crate::metrics;
+use crate::rate_limit::RateDecision;
 
 use sha2::{Digest, Sha256};
-use subtle::ConstantTimeEq;
 
 /// Per-turn `text` cap (security audit H3). Natural-language chat
 /// turns fit easily; pathological payloads that would otherwise
@@ -44,6 +48,7 @@ use axum::Json;
 use futures_core::Stream;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use tracing::Instrument;
 
 use crate::sse::{ack_event, done_event, text_event, AckPayload, DoneMetadata, DonePayload};
 use crate::state::ServerState;
@@ -64,15 +69,19 @@ pub struct PostMessagesRequest {
 
 /// Error envelope per the contract: `{ "error": { code, message, request_id } }`.
 #[derive(Serialize)]
-struct ErrorBody {
-    error: ErrorPayload,
+pub(crate) struct ErrorBody {
+    pub(crate) error: ErrorPayload,
 }
 
 #[derive(Serialize)]
-struct ErrorPayload {
-    code: &'static str,
-    message: String,
-    request_id: String,
+pub(crate) struct ErrorPayload {
+    pub(crate) code: &'static str,
+    pub(crate) message: String,
+    pub(crate) request_id: String,
+    /// Seconds until a 429'd request may be retried. Mirrors the
+    /// `Retry-After` header for clients that only read the body.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) retry_after: Option<u64>,
 }
 
 /// Errors that prevent the SSE stream from starting. Map to HTTP error
@@ -82,6 +91,8 @@ pub struct PreStreamError {
     code: &'static str,
     message: String,
     request_id: String,
+    /// Seconds for the `Retry-After` header on 429s.
+    retry_after: Option<u64>,
 }
 
 impl PreStreamError {
@@ -114,9 +125,16 @@ impl PreStreamError {
             code,
             message: message.into(),
             request_id: request_id.to_string(),
+            retry_after: None,
         }
     }
 
+    /// Attach a `Retry-After` (rounded up to whole seconds, minimum 1).
+    fn with_retry_after(mut self, after: Duration) -> Self {
+        self.retry_after = Some(after.as_secs_f64().ceil().max(1.0) as u64);
+        self
+    }
+
     /// Build an internal-error response where the detail goes to the
     /// server log via `tracing::error!` but the client sees only a
     /// generic message + the request_id (for correlation). Closes
@@ -162,6 +180,7 @@ impl PreStreamError {
             code,
             message: public_message,
             request_id: request_id.to_string(),
+            retry_after: None,
         }
     }
 }
@@ -173,22 +192,20 @@ impl IntoResponse for PreStreamError {
                 code: self.code,
                 message: self.message,
                 request_id: self.request_id,
+                retry_after: self.retry_after,
             },
         };
-        (self.status, Json(body)).into_response()
+        let mut resp = (self.status, Json(body)).into_response();
+        if let Some(secs) = self.retry_after {
+            resp.headers_mut()
+                .insert(axum::http::header::RETRY_AFTER, secs.into());
+        }
+        resp
     }
 }
 
-/// Pull the bearer token out of the `Authorization` header.
-fn bearer_token(headers: &HeaderMap) -> Option<&str> {
-    headers
-        .get(axum::http::header::AUTHORIZATION)
-        .and_then(|v| v.to_str().ok())
-        .and_then(|s| s.strip_prefix("Bearer "))
-}
-
 /// Echo `X-Request-Id` if the client supplied one, otherwise mint a fresh UUID.
-fn request_id_from_headers(headers: &HeaderMap) -> String {
+pub(crate) fn request_id_from_headers(headers: &HeaderMap) -> String {
     headers
         .get("x-request-id")
         .and_then(|v| v.to_str().ok())
@@ -215,31 +232,15 @@ pub async fn post_messages(
     // Records into `agentos_request_duration_seconds` histogram on completion.
     let started = Instant::now();
 
-    // 1. Auth. Constant-time comparison via SHA-256 of both sides
-    //    (defeats remote byte-by-byte timing oracles; the hash also
-    //    erases length-difference leakage that a raw ct_eq on the raw
-    //    bytes would still have via its short-circuit length check).
-    match bearer_token(&headers) {
-        Some(t) if ct_eq_token(t, &state.auth_token) => {}
-        Some(_) => {
-            return Err(PreStreamError::record(
-                started,
-                StatusCode::FORBIDDEN,
-                "unauthorized",
-                "bearer token did not match",
-                &request_id,
-            ));
+    // 1. Auth. Resolves the bearer to a principal (named token, JWT,
+    //    or the legacy static token). All secret comparisons are
+    //    constant-time; see `crate::auth`.
+    let principal = match check_auth(&headers, &state.auth) {
+        Ok(p) => p,
+        Err((status, code, message)) => {
+            return Err(PreStreamError::record(started, status, code, message, &request_id));
         }
-        None => {
-            return Err(PreStreamError::record(
-                started,
-                StatusCode::UNAUTHORIZED,
-                "unauthenticated",
-                "missing or malformed Authorization header",
-                &request_id,
-            ));
-        }
-    }
+    };
 
     // 2. Validate. The contract says "anon" never reaches this endpoint —
     //    AgentOS MUST 400 it.
@@ -261,6 +262,15 @@ pub async fn post_messages(
             &request_id,
         ));
     }
+    if !principal.allows_tier(&req.user_tier) {
+        return Err(PreStreamError::record(
+            started,
+            StatusCode::FORBIDDEN,
+            "forbidden",
+            format!("token is not permitted to use user_tier={}", req.user_tier),
+            &request_id,
+        ));
+    }
     if req.text.trim().is_empty() {
         return Err(PreStreamError::record(
             started,
@@ -338,7 +348,52 @@ pub async fn post_messages(
         ));
     }
 
-    // 2.5. Idempotency. Probe the cache; if the same (token, key) was
+    // The user's chat instance address. Built here (not at step 5) so
+    // admission can key per-user limits on it: the same user_id under
+    // two tenants is two users.
+    let address_str = principal.target_address_str(&state.agent_name, &req.user_id);
+
+    // 2.25. Admission: per-token rate limit, per-user rate limit, then
+    //       rolling token / dollar budgets. Checked after validation so
+    //       malformed requests don't eat quota, and before the
+    //       idempotency probe so replays count too (they still cost a
+    //       stream slot).
+    // Each check runs only if the previous one admitted, so a rejected
+    // request never consumes the later limiters' quota.
+    let admission = if let RateDecision::Limited { retry_after } = state
+        .rate_limiter
+        .check(&principal.id, principal.rate_limit_per_minute)
+    {
+        Some((retry_after, metrics::LIMIT_TOKEN, "rate_limited", "token request rate limit exceeded"))
+    } else if let RateDecision::Limited { retry_after } =
+        state.rate_limiter.check_user(&principal.id, &address_str)
+    {
+        Some((retry_after, metrics::LIMIT_USER, "rate_limited", "user request rate limit exceeded"))
+    } else if let RateDecision::Limited { retry_after } =
+        state.budgets.check(&principal, &address_str)
+    {
+        Some((
+            retry_after,
+            metrics::LIMIT_BUDGET,
+            "budget_exceeded",
+            "LLM usage budget exhausted for this window",
+        ))
+    } else {
+        None
+    };
+    if let Some((retry_after, limit, code, message)) = admission {
+        metrics::record_rate_limited(limit);
+        return Err(PreStreamError::record(
+            started,
+            StatusCode::TOO_MANY_REQUESTS,
+            code,
+            message,
+            &request_id,
+        )
+        .with_retry_after(retry_after));
+    }
+
+    // 2.5. Idempotency. Probe the cache; if the same (principal, key) was
     //      seen before, either replay the cached SSE stream (same body)
     //      or 409 (different body / in-flight). Per the v1 API contract.
     //
@@ -353,7 +408,7 @@ pub async fn post_messages(
     // bookkeeping that the client (e.g., RingHub) may legitimately
     // vary across retries.
     let body_hash = idempotency_body_hash(&req.user_id, &req.user_tier, &req.text);
-    let cache_key = IdempotencyCache::key(&state.auth_token, &req.idempotency_key);
+    let cache_key = IdempotencyCache::key(&principal.id, &req.idempotency_key);
     let replay_data = match state.idempotency.lookup_or_claim(cache_key.clone(), body_hash) {
         LookupResult::Miss => {
             metrics::record_idempotency_lookup(metrics::RESULT_MISS);
@@ -418,10 +473,10 @@ pub async fn post_messages(
     // 4. Subscribe BEFORE send so we never miss a fast AgentResponse.
     let mut events = state.events.subscribe();
 
-    // 5. Build address. Chat-bubble Bob lives at `<agent_name>[user_id]`;
+    // 5. Build address. Chat-bubble Bob lives at `<agent_name>[user_id]`,
+    //    inside the principal's namespace when its scope has one;
     //    address parsing rejects keys with characters that break the
     //    grammar so this also doubles as a sanity check on user_id shape.
-    let address_str = format!("{}[{}]", state.agent_name, req.user_id);
     let address = Address::parse(&address_str).map_err(|e| {
         // Generic 4xx message to the client; full grammar error to
         // the log. Post-B2 validation, this path is largely
@@ -436,6 +491,15 @@ pub async fn post_messages(
             &request_id,
         )
     })?;
+    if !principal.allows(&address) {
+        return Err(PreStreamError::record(
+            started,
+            StatusCode::FORBIDDEN,
+            "forbidden",
+            "token scope does not cover this user",
+            &request_id,
+        ));
+    }
 
     // 6. Wrap the user text in the listener's payload XML. Bob's
     //    payload_class produces a tag like `AgentTask`; we write the
@@ -461,17 +525,28 @@ pub async fn post_messages(
         text = xml_escape(&req.text),
     );
 
+    // `from` is the principal's gateway address, so the platform
+    // router's namespace check holds the same boundary as the scope
+    // match above. Root principals send as `None` (un-namespaced).
     let envelope = Envelope {
         to: address,
-        from: None,
+        from: principal.sender_address(),
         body: body_xml.into_bytes(),
         buffer: None,
     };
 
     // 7. Send. After this, the instance is materialized and the message
     //    is in flight. Errors here haven't reached "ack sent" yet so we
-    //    surface as HTTP errors per the contract.
-    state.router.send_to(&envelope).await.map_err(|e| {
+    //    surface as HTTP errors per the contract. The span ties the
+    //    request trace the delivery opens to this HTTP turn.
+    let send_span = tracing::info_span!(
+        "server.turn",
+        request_id = %request_id,
+        conversation_id = %conversation_id,
+        turn_id = %turn_id,
+        address = %address_str,
+    );
+    state.router.send_to(&envelope).instrument(send_span).await.map_err(|e| {
         PreStreamError::record_internal(
             started,
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -506,6 +581,11 @@ pub async fn post_messages(
             )
         })?;
 
+    // 8.5. Charge this thread's LLM usage to the caller and the user.
+    state
+        .budgets
+        .bind_thread(&buffer_thread_id, &principal, &address_str);
+
     // 9. Build the SSE stream. ack first, then filter events for our
     //    buffer thread, emit text per AgentResponse, terminate on done.
     //    Along the way, accumulate the emitted payloads into the
@@ -676,7 +756,7 @@ fn xml_escape(s: &str) -> String {
 /// formatter or collide with reserved grammar (`+` for the cache-
 /// composition operator). 128-char cap bounds key length in the
 /// idempotency cache and any downstream identifier surfaces.
-fn is_valid_user_id(s: &str) -> bool {
+pub(crate) fn is_valid_user_id(s: &str) -> bool {
     let len = s.len();
     if !(1..=128).contains(&len) {
         return false;
@@ -705,20 +785,6 @@ fn idempotency_body_hash(user_id: &str, user_tier: &str, text: &str) -> [u8; 32]
     h.finalize().into()
 }
 
-/// Constant-time bearer-token check.
-///
-/// `PartialEq` on `str` / `String` short-circuits at the first
-/// mismatching byte → enables remote byte-by-byte timing oracle. Both
-/// tokens go through SHA-256 (fixed 32-byte output) and the digests
-/// are compared with `subtle::ConstantTimeEq`. Hashing makes the
-/// comparison length-independent — supplying a 5-byte vs 500-byte
-/// token both pay the same SHA-256 + 32-byte compare cost.
-fn ct_eq_token(supplied: &str, expected: &str) -> bool {
-    let s = Sha256::digest(supplied.as_bytes());
-    let e = Sha256::digest(expected.as_bytes());
-    s.ct_eq(&e).into()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -162,10 +162,12 @@ impl IdempotencyCache {
         self.max_entries
     }
 
-    /// Build a `Key` from a service token + idempotency_key.
-    pub fn key(auth_token: &str, idempotency_key: &str) -> Key {
+    /// Build a `Key` from the caller's principal id + idempotency_key.
+    /// Keys never collide across tenants, and rotating a token's secret
+    /// (same name) keeps its retries replayable.
+    pub fn key(principal: &str, idempotency_key: &str) -> Key {
         Key {
-            token_hash: hash_token(auth_token),
+            token_hash: hash_token(principal),
             idempotency_key: idempotency_key.to_string(),
         }
     }
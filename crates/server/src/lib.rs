@@ -1,13 +1,18 @@
 //! agentos-server — HTTP+SSE frontend per the AgentOS API contract (v1).
 //!
 //! See `project_agentos_api_contract.md` for the wire spec. This crate
-//! exposes [`build_router`] which mounts `POST /v1/messages` over an
-//! axum [`Router`], plus a [`ServerState`] handle that the bin (or
+//! exposes [`build_router`] which mounts `POST /v1/messages` (plus the
+//! conversation-history and instance admin routes in [`admin`]) over an
+//! axum [`Router`], and a [`ServerState`] handle that the bin (or
 //! integration tests) wire up to an [`AgentPipeline`] + platform router.
 
+pub mod admin;
+pub mod auth;
+pub mod budget;
 pub mod handler;
 pub mod idempotency;
 pub mod metrics;
+pub mod rate_limit;
 pub mod sse;
 pub mod state;
 
@@ -40,7 +45,7 @@ const MAX_CONCURRENT_REQUESTS: usize = 256;
 ///
 /// All routes mount under `/v1/*` except `GET /metrics` which lives at
 /// the root (standard Prometheus convention). The shared state carries
-/// the platform router (for materializing instances) and the auth token.
+/// the platform router (for materializing instances) and the authenticator.
 ///
 /// Side effect: installs the global Prometheus recorder on first call
 /// (subsequent calls are no-ops). Tests don't need to do this manually.
@@ -49,8 +54,9 @@ const MAX_CONCURRENT_REQUESTS: usize = 256;
 /// - body-size cap (via `DefaultBodyLimit`) at the router level
 /// - concurrency cap on the whole router so an SSE flood can't
 ///   stack up unbounded
-/// - per-IP rate-limiting is queued as a follow-up (needs
-///   tower-governor or equivalent)
+/// - per-token / per-user rate limits and rolling LLM budgets are
+///   admission checks inside `post_messages` (see [`rate_limit`],
+///   [`budget`]); per-IP limiting is left to the reverse proxy
 pub fn build_router(state: Arc<ServerState>) -> Router {
     metrics::init();
     Router::new()
@@ -58,6 +64,27 @@ pub fn build_router(state: Arc<ServerState>) -> Router {
             "/v1/messages",
             axum::routing::post(handler::post_messages),
         )
+        .route(
+            "/v1/users/:user_id",
+            axum::routing::delete(admin::delete_user),
+        )
+        .route(
+            "/v1/users/:user_id/conversations",
+            axum::routing::get(admin::list_conversations),
+        )
+        .route(
+            "/v1/users/:user_id/conversations/:buffer",
+            axum::routing::delete(admin::delete_conversation),
+        )
+        .route(
+            "/v1/users/:user_id/conversations/:buffer/messages",
+            axum::routing::get(admin::list_messages),
+        )
+        .route("/v1/instances", axum::routing::get(admin::list_instances))
+        .route(
+            "/v1/instances/:address",
+            axum::routing::get(admin::get_instance).delete(admin::kill_instance),
+        )
         .route("/metrics", axum::routing::get(metrics_handler))
         .with_state(state)
         .layer(DefaultBodyLimit::max(MAX_REQUEST_BODY_BYTES))
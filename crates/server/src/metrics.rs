@@ -6,19 +6,22 @@
 //!
 //! Initialization is idempotent — `init()` is safe to call from any
 //! number of `build_router` invocations (one bin, many tests). The
-//! recorder is installed globally on first call; subsequent calls
-//! return the same handle.
+//! recorder itself belongs to `agentos-telemetry`, which also carries
+//! the pipeline-level metrics (LLM and tool calls, permissions, WAL,
+//! context and instance tiers, triggers); this module only adds the
+//! HTTP-layer series on top, so `/metrics` renders both.
 //!
 //! Metric naming follows Prometheus conventions: namespace prefix
 //! (`agentos_`), snake_case, units in the suffix (`_seconds`,
 //! `_total`). Labels are low-cardinality strings: HTTP status class,
-//! idempotency result kind. No user IDs, no addresses — those would
-//! blow up cardinality and leak privacy.
+//! idempotency result kind, and for budget gauges the token name
+//! (operator-minted, so bounded). No user IDs, no addresses — those
+//! would blow up cardinality and leak privacy.
 
-use std::sync::OnceLock;
+use std::sync::Once;
 use std::time::Duration;
 
-use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use metrics_exporter_prometheus::PrometheusHandle;
 
 /// Metric names. Constants rather than inline strings so the test
 /// suite can assert on them without typo risk.
@@ -29,6 +32,15 @@ pub const IDEMPOTENCY_CACHE_ENTRIES: &str = "agentos_idempotency_cache_entries";
 pub const IDEMPOTENCY_LRU_EVICTIONS_TOTAL: &str = "agentos_idempotency_lru_evictions_total";
 pub const BROADCAST_LAG_TOTAL: &str = "agentos_broadcast_lag_total";
 pub const ACTIVE_SSE_STREAMS: &str = "agentos_active_sse_streams";
+pub const ADMIN_REQUEST_DURATION_SECONDS: &str = "agentos_admin_request_duration_seconds";
+pub const ADMIN_REQUESTS_TOTAL: &str = "agentos_admin_requests_total";
+pub const RATE_LIMITED_TOTAL: &str = "agentos_rate_limited_total";
+pub const LLM_TOKENS_TOTAL: &str = "agentos_llm_tokens_total";
+pub const LLM_SPEND_USD: &str = "agentos_llm_spend_usd";
+pub const BUDGET_TOKENS_USED: &str = "agentos_budget_tokens_used";
+pub const BUDGET_USD_USED: &str = "agentos_budget_usd_used";
+pub const BUDGET_USD_LIMIT: &str = "agentos_budget_usd_limit";
+pub const BUDGET_USERS_EXHAUSTED: &str = "agentos_budget_users_exhausted";
 
 /// Idempotency lookup outcome labels. Match `LookupResult` variants.
 pub const RESULT_MISS: &str = "miss";
@@ -43,51 +55,43 @@ pub const STATUS_OK: &str = "ok";
 pub const STATUS_CLIENT_ERROR: &str = "client_error";
 pub const STATUS_SERVER_ERROR: &str = "server_error";
 
-static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+/// Route labels for the history / instance admin endpoints. One per
+/// route template (never the raw path — that would carry user IDs).
+pub const ROUTE_LIST_CONVERSATIONS: &str = "list_conversations";
+pub const ROUTE_LIST_MESSAGES: &str = "list_messages";
+pub const ROUTE_DELETE_CONVERSATION: &str = "delete_conversation";
+pub const ROUTE_DELETE_USER: &str = "delete_user";
+pub const ROUTE_LIST_INSTANCES: &str = "list_instances";
+pub const ROUTE_GET_INSTANCE: &str = "get_instance";
+pub const ROUTE_KILL_INSTANCE: &str = "kill_instance";
 
-/// Install the Prometheus recorder if it hasn't been already, and
-/// return a static reference to the handle. Safe to call concurrently
-/// and repeatedly — the first call wins, subsequent calls return the
-/// same handle.
+/// Rate-limit rejection labels: which limiter tripped.
+pub const LIMIT_TOKEN: &str = "token";
+pub const LIMIT_USER: &str = "user";
+pub const LIMIT_BUDGET: &str = "budget";
+
+static DESCRIBED: Once = Once::new();
+
+/// Install the shared Prometheus recorder if it hasn't been already,
+/// and return a static reference to the handle. Safe to call
+/// concurrently and repeatedly — the first call wins, subsequent calls
+/// return the same handle.
 pub fn init() -> &'static PrometheusHandle {
-    HANDLE.get_or_init(|| {
-        let recorder = PrometheusBuilder::new()
-            .build_recorder();
-        let handle = recorder.handle();
-        // `set_global_recorder` errors if a recorder was already installed
-        // by some other code path (rare; effectively impossible in the
-        // bin, possible in mixed test setups). If it fails we still return
-        // a handle — it just won't see any new recordings, and /metrics
-        // will render empty.
-        //
-        // Log loudly when this happens (security audit Tier 3): silent
-        // observability loss removes detection capability for every
-        // capacity / DoS class. The operator needs to know that the
-        // dashboard they're staring at is dead.
-        if let Err(e) = metrics::set_global_recorder(recorder) {
-            tracing::error!(
-                error = ?e,
-                "metrics::set_global_recorder failed — /metrics will be empty. \
-                 This usually means another library installed a recorder first. \
-                 Verify no other crate initializes the metrics global before \
-                 build_router(); your dashboard is currently flying blind."
-            );
-        }
-        describe_all();
-        handle
-    })
+    let handle = agentos_telemetry::install();
+    DESCRIBED.call_once(describe_all);
+    handle
 }
 
 /// Read-side handle to the recorder. Returns `None` if `init` was
 /// never called (which can't actually happen since `build_router`
 /// always calls it, but the type makes the dependency explicit).
 pub fn handle() -> Option<&'static PrometheusHandle> {
-    HANDLE.get()
+    agentos_telemetry::handle()
 }
 
 /// Render the current metrics in Prometheus exposition format.
 pub fn render() -> String {
-    HANDLE.get().map(|h| h.render()).unwrap_or_default()
+    agentos_telemetry::render()
 }
 
 fn describe_all() {
@@ -124,6 +128,43 @@ fn describe_all() {
         ACTIVE_SSE_STREAMS,
         "Currently active SSE response streams. Tracks both live and replay paths."
     );
+    metrics::describe_histogram!(
+        ADMIN_REQUEST_DURATION_SECONDS,
+        metrics::Unit::Seconds,
+        "Wall-clock time of history / instance admin requests, by route and outcome class."
+    );
+    metrics::describe_counter!(
+        ADMIN_REQUESTS_TOTAL,
+        "History / instance admin requests by route and outcome class."
+    );
+    metrics::describe_counter!(
+        RATE_LIMITED_TOTAL,
+        "POST /v1/messages requests rejected with 429, by limiter (token / user / budget)."
+    );
+    metrics::describe_counter!(
+        LLM_TOKENS_TOTAL,
+        "LLM tokens (input + output) consumed by server-attributed threads."
+    );
+    metrics::describe_gauge!(
+        LLM_SPEND_USD,
+        "Running LLM spend in USD for server-attributed threads, priced from models.yaml."
+    );
+    metrics::describe_gauge!(
+        BUDGET_TOKENS_USED,
+        "LLM tokens used in the current budget window, by token (principal)."
+    );
+    metrics::describe_gauge!(
+        BUDGET_USD_USED,
+        "LLM spend in USD in the current budget window, by token (principal)."
+    );
+    metrics::describe_gauge!(
+        BUDGET_USD_LIMIT,
+        "Configured USD budget per window, by token (principal). Absent when uncapped."
+    );
+    metrics::describe_gauge!(
+        BUDGET_USERS_EXHAUSTED,
+        "Users currently over their per-user budget. Counted, never labelled."
+    );
 }
 
 // ── recording helpers (called from handler.rs) ────────────────────────
@@ -134,6 +175,37 @@ pub fn record_request(status: &'static str, duration: Duration) {
         .record(duration.as_secs_f64());
 }
 
+pub fn record_admin_request(route: &'static str, status: &'static str, duration: Duration) {
+    metrics::counter!(ADMIN_REQUESTS_TOTAL, "route" => route, "status" => status).increment(1);
+    metrics::histogram!(ADMIN_REQUEST_DURATION_SECONDS, "route" => route, "status" => status)
+        .record(duration.as_secs_f64());
+}
+
+pub fn record_rate_limited(limit: &'static str) {
+    metrics::counter!(RATE_LIMITED_TOTAL, "limit" => limit).increment(1);
+}
+
+/// One priced LLM call. USD is a gauge incremented by fractional
+/// amounts — Prometheus counters here are integer-valued.
+pub fn record_llm_spend(tokens: u64, usd: f64) {
+    metrics::counter!(LLM_TOKENS_TOTAL).increment(tokens);
+    metrics::gauge!(LLM_SPEND_USD).increment(usd);
+}
+
+pub fn set_budget_spend(principal: &str, tokens: u64, usd: f64) {
+    let principal = principal.to_string();
+    metrics::gauge!(BUDGET_TOKENS_USED, "principal" => principal.clone()).set(tokens as f64);
+    metrics::gauge!(BUDGET_USD_USED, "principal" => principal).set(usd);
+}
+
+pub fn set_budget_usd_limit(principal: &str, usd: f64) {
+    metrics::gauge!(BUDGET_USD_LIMIT, "principal" => principal.to_string()).set(usd);
+}
+
+pub fn set_budget_users_exhausted(n: usize) {
+    metrics::gauge!(BUDGET_USERS_EXHAUSTED).set(n as f64);
+}
+
 pub fn record_idempotency_lookup(result: &'static str) {
     metrics::counter!(IDEMPOTENCY_LOOKUPS_TOTAL, "result" => result).increment(1);
 }
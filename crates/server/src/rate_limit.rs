@@ -0,0 +1,143 @@
+//! Fixed-window request rate limiter.
+//!
+//! One counter per key — a token's principal id, or a (principal,
+//! user) pair for the server-wide per-user limit — reset at the start
+//! of each 60-second window. Fixed windows admit up to 2× the limit
+//! across a window boundary; for ceilings measured in requests per
+//! minute that slack is acceptable, and it keeps the hot path to one
+//! `DashMap` entry lock with no timestamp queues.
+//!
+//! Rejections carry the time until the window rolls over so the handler
+//! can send `Retry-After`.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+
+/// Window length for `rate_limit_per_minute`.
+pub const WINDOW: Duration = Duration::from_secs(60);
+
+/// Outcome of a [`RateLimiter::check`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateDecision {
+    Allowed,
+    /// Over the limit; the window resets in `retry_after`.
+    Limited { retry_after: Duration },
+}
+
+#[derive(Debug)]
+struct Window {
+    started: Instant,
+    count: u32,
+}
+
+/// Per-key fixed-window counters.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    windows: DashMap<String, Window>,
+    /// Requests per minute for any single user. `None` = unlimited.
+    per_user: Option<u32>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Limiter that also caps each user at `per_user` requests/minute.
+    pub fn with_user_limit(per_user: Option<u32>) -> Arc<Self> {
+        Arc::new(Self {
+            windows: DashMap::new(),
+            per_user,
+        })
+    }
+
+    /// Count one request against `user` (the instance address) under
+    /// `principal`, at the server-wide per-user limit.
+    pub fn check_user(&self, principal: &str, user: &str) -> RateDecision {
+        // `user:` can't collide with principal ids (token names have no
+        // `:`, JWT principals are `jwt:<sub>`).
+        self.check(&format!("user:{principal}/{user}"), self.per_user)
+    }
+
+    /// Drop windows that have fully elapsed. Called from the bin's
+    /// periodic sampler so one-off users don't accumulate forever.
+    pub fn sweep(&self) {
+        let now = Instant::now();
+        self.windows
+            .retain(|_, w| now.duration_since(w.started) < WINDOW);
+    }
+
+    /// Count one request against `key`. `limit` of `None` is unlimited
+    /// and leaves no state behind.
+    pub fn check(&self, key: &str, limit: Option<u32>) -> RateDecision {
+        self.check_at(key, limit, Instant::now())
+    }
+
+    /// [`check`](Self::check) with an explicit clock, for tests.
+    pub fn check_at(&self, key: &str, limit: Option<u32>, now: Instant) -> RateDecision {
+        let Some(limit) = limit else {
+            return RateDecision::Allowed;
+        };
+        let mut w = self.windows.entry(key.to_string()).or_insert(Window {
+            started: now,
+            count: 0,
+        });
+        if now.duration_since(w.started) >= WINDOW {
+            w.started = now;
+            w.count = 0;
+        }
+        if w.count >= limit {
+            let retry_after = WINDOW.saturating_sub(now.duration_since(w.started));
+            return RateDecision::Limited { retry_after };
+        }
+        w.count += 1;
+        RateDecision::Allowed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_within_window_and_resets_after() {
+        let rl = RateLimiter::default();
+        let t0 = Instant::now();
+        assert_eq!(rl.check_at("a", Some(2), t0), RateDecision::Allowed);
+        assert_eq!(rl.check_at("a", Some(2), t0), RateDecision::Allowed);
+        let d = rl.check_at("a", Some(2), t0 + Duration::from_secs(15));
+        assert_eq!(
+            d,
+            RateDecision::Limited {
+                retry_after: Duration::from_secs(45)
+            }
+        );
+        // Other keys are independent.
+        assert_eq!(rl.check_at("b", Some(2), t0), RateDecision::Allowed);
+        // Next window.
+        assert_eq!(rl.check_at("a", Some(2), t0 + WINDOW), RateDecision::Allowed);
+    }
+
+    #[test]
+    fn user_limit_is_per_principal_and_user() {
+        let rl = RateLimiter::with_user_limit(Some(1));
+        assert_eq!(rl.check_user("ringhub", "bob[alice]"), RateDecision::Allowed);
+        assert!(matches!(
+            rl.check_user("ringhub", "bob[alice]"),
+            RateDecision::Limited { .. }
+        ));
+        assert_eq!(rl.check_user("chorus", "bob[alice]"), RateDecision::Allowed);
+        assert_eq!(rl.check_user("ringhub", "bob[carol]"), RateDecision::Allowed);
+    }
+
+    #[test]
+    fn no_limit_is_stateless() {
+        let rl = RateLimiter::default();
+        for _ in 0..1000 {
+            assert_eq!(rl.check("a", None), RateDecision::Allowed);
+        }
+        assert!(rl.windows.is_empty());
+    }
+}
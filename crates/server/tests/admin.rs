@@ -0,0 +1,448 @@
+//! Conversation history + instance admin endpoints, end to end.
+//!
+//! Materializes `bob[alice]` through the platform router, seeds the
+//! buffer thread's context with message segments directly in the
+//! kernel, then drives the admin routes over HTTP: list, page, inspect,
+//! erase, kill. The erase tests also check the kernel WAL no longer
+//! carries the erased content, including for a user whose instance was
+//! already gone.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use agentos_kernel::context_store::{ContextSegment, ContextStore, SegmentStatus};
+use agentos_kernel::Kernel;
+use agentos_organism::parser::parse_organism;
+use agentos_pipeline::AgentPipelineBuilder;
+use agentos_platform::address::Address;
+use agentos_platform::router::Envelope;
+use agentos_server::{build_router, ServerState};
+
+use rust_pipeline::prelude::{FnHandler, HandlerContext, HandlerResponse, ValidatedPayload};
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+use tokio::sync::Mutex;
+
+const ORGANISM: &str = r#"
+organism:
+  name: server-admin-test
+
+listeners:
+  - name: bob
+    payload_class: agent.AgentTask
+    handler: agent.handle
+    description: "Stub Bob"
+    agent:
+      prompt: "stub"
+
+profiles:
+  default:
+    linux_user: agentos
+    listeners: [bob]
+    journal: retain_forever
+"#;
+
+struct Harness {
+    addr: SocketAddr,
+    kernel: Arc<Mutex<Kernel>>,
+    state: Arc<ServerState>,
+    data_dir: std::path::PathBuf,
+    _dir: TempDir,
+}
+
+async fn start() -> Harness {
+    let org = parse_organism(ORGANISM).unwrap();
+    let dir = TempDir::new().unwrap();
+    let data_dir = dir.path().join("data");
+    let builder = AgentPipelineBuilder::new(org, &data_dir);
+    let event_tx = builder.event_sender();
+    let bob = FnHandler(|p: ValidatedPayload, _ctx: HandlerContext| {
+        Box::pin(async move { Ok(HandlerResponse::Reply { payload_xml: p.xml }) })
+    });
+    let mut pipeline = builder.register("bob", bob).unwrap().build().unwrap();
+    pipeline
+        .initialize_root("server-admin-test", "default")
+        .await
+        .unwrap();
+    pipeline.run();
+
+    let state = Arc::new(ServerState {
+        router: Arc::new(pipeline.shared_router(0, Duration::from_secs(60))),
+        events: event_tx,
+        kernel: pipeline.kernel(),
+        organism: Arc::new(pipeline.organism().clone()),
+        agent_name: "bob".to_string(),
+        auth: Arc::new(agentos_server::auth::Authenticator::static_token("test-token")),
+        idempotency: agentos_server::idempotency::IdempotencyCache::new(),
+        rate_limiter: agentos_server::rate_limit::RateLimiter::new(),
+        budgets: agentos_server::budget::Budgets::unlimited(),
+    });
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = build_router(state.clone());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    Harness {
+        addr,
+        kernel: pipeline.kernel(),
+        state,
+        data_dir,
+        _dir: dir,
+    }
+}
+
+/// Materialize `bob[user]` and write `n` message segments into its
+/// default buffer thread. Returns the buffer thread_id.
+async fn seed(h: &Harness, user: &str, n: usize, secret: &str) -> String {
+    let address = Address::parse(&format!("bob[{user}]")).unwrap();
+    h.state
+        .router
+        .send_to(&Envelope {
+            to: address.clone(),
+            from: None,
+            body: b"<AgentTask><task>hi</task></AgentTask>".to_vec(),
+            buffer: None,
+        })
+        .await
+        .unwrap();
+    let info = h.state.router.lookup(&address).await.unwrap();
+    let thread_id = info.buffers.list()[0].thread_id.clone();
+
+    let mut kernel = h.kernel.lock().await;
+    for i in 0..n {
+        let seg = ContextSegment {
+            id: format!("msg-{i:03}"),
+            tag: "message".into(),
+            content: format!("{secret} #{i}").into_bytes(),
+            status: SegmentStatus::Active,
+            relevance: 0.5,
+            created_at: 1_000 + i as u64,
+            fold_ref: None,
+        };
+        let entry = ContextStore::wal_entry_segment_add(&thread_id, &seg);
+        kernel.wal.append(&entry).unwrap();
+        kernel.contexts_mut().add_segment(&thread_id, seg).unwrap();
+    }
+    thread_id
+}
+
+fn get(h: &Harness, path: &str) -> reqwest::RequestBuilder {
+    reqwest::Client::new()
+        .get(format!("http://{}{path}", h.addr))
+        .bearer_auth("test-token")
+}
+
+fn delete(h: &Harness, path: &str) -> reqwest::RequestBuilder {
+    reqwest::Client::new()
+        .delete(format!("http://{}{path}", h.addr))
+        .bearer_auth("test-token")
+}
+
+#[tokio::test]
+async fn admin_routes_require_bearer() {
+    let h = start().await;
+    let resp = reqwest::Client::new()
+        .get(format!("http://{}/v1/instances", h.addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 401);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "unauthenticated");
+
+    let resp = reqwest::Client::new()
+        .get(format!("http://{}/v1/users/alice/conversations", h.addr))
+        .bearer_auth("wrong")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 403);
+}
+
+#[tokio::test]
+async fn list_and_page_conversation_messages() {
+    let h = start().await;
+    let thread_id = seed(&h, "alice", 5, "hello").await;
+
+    let body: serde_json::Value = get(&h, "/v1/users/alice/conversations")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["address"], "bob[alice]");
+    let convs = body["conversations"].as_array().unwrap();
+    assert_eq!(convs.len(), 1);
+    assert_eq!(convs[0]["buffer"], "default");
+    assert_eq!(convs[0]["thread_id"], thread_id.as_str());
+
+    // Unknown user: empty list, not an error.
+    let body: serde_json::Value = get(&h, "/v1/users/nobody/conversations")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(body["conversations"].as_array().unwrap().is_empty());
+
+    // Page 1 of 2.
+    let page: serde_json::Value = get(&h, "/v1/users/alice/conversations/default/messages?limit=3")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let msgs = page["messages"].as_array().unwrap();
+    assert_eq!(msgs.len(), 3);
+    assert_eq!(msgs[0]["id"], "msg-000");
+    assert_eq!(msgs[0]["content"], "hello #0");
+    let cursor = page["next_cursor"].as_str().unwrap().to_string();
+
+    // Page 2 is the tail, with no further cursor.
+    let page: serde_json::Value = get(
+        &h,
+        &format!("/v1/users/alice/conversations/default/messages?limit=3&cursor={cursor}"),
+    )
+    .send()
+    .await
+    .unwrap()
+    .json()
+    .await
+    .unwrap();
+    let msgs = page["messages"].as_array().unwrap();
+    assert_eq!(msgs.len(), 2);
+    assert_eq!(msgs[1]["id"], "msg-004");
+    assert!(page.get("next_cursor").is_none());
+
+    // Bad limit and unknown buffer map onto the error envelope.
+    let resp = get(&h, "/v1/users/alice/conversations/default/messages?limit=0")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+    let resp = get(&h, "/v1/users/alice/conversations/dm/messages")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "not_found");
+}
+
+#[tokio::test]
+async fn rejects_address_grammar_in_user_id() {
+    let h = start().await;
+    let resp = get(&h, "/v1/users/alice%5D.dm%5Bevil/conversations")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+async fn delete_conversation_erases_kernel_state_and_wal() {
+    let h = start().await;
+    let thread_id = seed(&h, "alice", 2, "TOP-SECRET-PAYLOAD").await;
+    let _other = seed(&h, "carol", 1, "keep-me").await;
+
+    let resp = delete(&h, "/v1/users/alice/conversations/default")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["erased_threads"][0], thread_id.as_str());
+    assert!(body["wal_entries_scrubbed"].as_u64().unwrap() >= 2);
+
+    {
+        let kernel = h.kernel.lock().await;
+        assert!(!kernel.contexts().exists(&thread_id));
+        assert!(kernel.journal().all_entries().all(|e| e.thread_id != thread_id));
+    }
+    let wal = std::fs::read(h.data_dir.join("kernel.wal")).unwrap();
+    let needle = b"TOP-SECRET-PAYLOAD";
+    assert!(!wal.windows(needle.len()).any(|w| w == needle));
+    assert!(wal.windows(7).any(|w| w == b"keep-me"));
+
+    // The buffer is gone from the listing; a second delete is a 404.
+    let body: serde_json::Value = get(&h, "/v1/users/alice/conversations")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(body["conversations"].as_array().unwrap().is_empty());
+    let resp = delete(&h, "/v1/users/alice/conversations/default")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+async fn delete_user_kills_instance_and_erases_all_threads() {
+    let h = start().await;
+    let buffer_thread = seed(&h, "alice", 1, "bye").await;
+
+    let resp = delete(&h, "/v1/users/alice").send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    let erased: Vec<&str> = body["erased_threads"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v.as_str().unwrap())
+        .collect();
+    assert!(erased.contains(&buffer_thread.as_str()));
+    assert_eq!(erased.len(), 2, "instance thread + default buffer thread");
+
+    assert!(h
+        .state
+        .router
+        .lookup(&Address::parse("bob[alice]").unwrap())
+        .await
+        .is_none());
+    let resp = delete(&h, "/v1/users/alice").send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+async fn evicted_user_is_listed_and_erased_without_a_live_instance() {
+    let h = start().await;
+    let buffer_thread = seed(&h, "alice", 3, "EVICTED-SECRET").await;
+    let _other = seed(&h, "carol", 1, "keep-me").await;
+
+    // Router kill takes the idle-eviction path: the registry entry goes,
+    // its thread_id is retired, and the kernel releases the instance
+    // thread. The buffer thread's context stays behind in the kernel.
+    let address = Address::parse("bob[alice]").unwrap();
+    let instance_thread = h.state.router.kill(&address).await.unwrap().thread_id;
+    assert!(h.state.router.lookup(&address).await.is_none());
+
+    let body: serde_json::Value = get(&h, "/v1/users/alice/conversations")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let convs = body["conversations"].as_array().unwrap();
+    assert_eq!(convs.len(), 1);
+    assert_eq!(convs[0]["buffer"], "default");
+    assert_eq!(convs[0]["thread_id"], buffer_thread.as_str());
+    assert_eq!(convs[0]["live"], false);
+    assert_eq!(convs[0]["message_count"], 3);
+
+    let page: serde_json::Value = get(&h, "/v1/users/alice/conversations/default/messages")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(page["messages"].as_array().unwrap().len(), 3);
+
+    let resp = delete(&h, "/v1/users/alice").send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(
+        body["erased_threads"],
+        serde_json::json!([&instance_thread, &buffer_thread])
+    );
+    {
+        let kernel = h.kernel.lock().await;
+        assert!(!kernel.contexts().exists(&buffer_thread));
+    }
+    let wal = std::fs::read(h.data_dir.join("kernel.wal")).unwrap();
+    let needle = b"EVICTED-SECRET";
+    assert!(!wal.windows(needle.len()).any(|w| w == needle));
+    assert!(wal.windows(7).any(|w| w == b"keep-me"));
+
+    assert!(h.state.router.retired_threads(&address).await.is_empty());
+    let resp = delete(&h, "/v1/users/alice").send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+async fn evicted_keyed_buffer_is_listed_under_its_recorded_id() {
+    let h = start().await;
+    h.state
+        .router
+        .send_to(&Envelope {
+            to: Address::parse("bob[alice].help-desk[email-issue]").unwrap(),
+            from: None,
+            body: b"<AgentTask><task>hi</task></AgentTask>".to_vec(),
+            buffer: None,
+        })
+        .await
+        .unwrap();
+    h.state.router.kill(&Address::parse("bob[alice]").unwrap()).await.unwrap();
+
+    let body: serde_json::Value = get(&h, "/v1/users/alice/conversations")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let convs = body["conversations"].as_array().unwrap();
+    assert_eq!(convs.len(), 1);
+    // The thread_id flattens to `help-desk-email-issue`; the name and
+    // channel come from the registry, not from splitting that label.
+    assert_eq!(convs[0]["buffer"], "help-desk[email-issue]");
+    assert_eq!(convs[0]["channel"], "default");
+    assert_eq!(convs[0]["live"], false);
+}
+
+#[tokio::test]
+async fn list_inspect_and_kill_instances() {
+    let h = start().await;
+    seed(&h, "alice", 0, "").await;
+    seed(&h, "bob-user", 0, "").await;
+
+    let body: serde_json::Value = get(&h, "/v1/instances")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let instances = body["instances"].as_array().unwrap();
+    assert_eq!(instances.len(), 2);
+    assert_eq!(instances[0]["address"], "bob[alice]");
+    assert_eq!(instances[0]["tier"], "active");
+
+    let detail: serde_json::Value = get(&h, "/v1/instances/bob%5Balice%5D")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(detail["organism"], "bob");
+    assert_eq!(detail["tier"], "active");
+    assert_eq!(detail["buffers"].as_array().unwrap().len(), 1);
+
+    let resp = delete(&h, "/v1/instances/bob%5Balice%5D").send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let resp = get(&h, "/v1/instances/bob%5Balice%5D").send().await.unwrap();
+    assert_eq!(resp.status(), 404);
+    let resp = get(&h, "/v1/instances/bob%5Balice").send().await.unwrap();
+    assert_eq!(resp.status(), 400);
+
+    let metrics = reqwest::get(format!("http://{}/metrics", h.addr))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(metrics.contains(agentos_server::metrics::ADMIN_REQUESTS_TOTAL));
+}
@@ -0,0 +1,211 @@
+//! Per-user rate limits and rolling LLM budgets, end to end.
+//!
+//! The stub `bob` emits a `TokenUsage` event (as the real agent does
+//! after each LLM call) before replying, so the budget listener charges
+//! the turn to the calling user. Once the user is over the cap, the
+//! next `POST /v1/messages` is a 429 `budget_exceeded` carrying
+//! `retry_after` in both the header and the error envelope.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use agentos_config::{ModelPrice, ModelsConfig};
+use agentos_events::PipelineEvent;
+use agentos_organism::parser::parse_organism;
+use agentos_pipeline::AgentPipelineBuilder;
+use agentos_server::auth::Authenticator;
+use agentos_server::budget::{BudgetLimits, Budgets};
+use agentos_server::rate_limit::RateLimiter;
+use agentos_server::{build_router, metrics, ServerState};
+
+use rust_pipeline::prelude::{FnHandler, HandlerContext, HandlerResponse, ValidatedPayload};
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+
+const ORGANISM: &str = r#"
+organism:
+  name: server-budget-test
+
+listeners:
+  - name: bob
+    payload_class: agent.AgentTask
+    handler: agent.handle
+    description: "Stub Bob"
+    agent:
+      prompt: "stub"
+
+profiles:
+  default:
+    linux_user: agentos
+    listeners: [bob]
+    journal: retain_forever
+"#;
+
+/// Each stub turn "costs" 1000 input + 500 output tokens.
+const TURN_TOKENS: u64 = 1_500;
+
+struct Harness {
+    addr: SocketAddr,
+    state: Arc<ServerState>,
+    _dir: TempDir,
+}
+
+async fn start(per_user: BudgetLimits, user_rpm: Option<u32>) -> Harness {
+    let org = parse_organism(ORGANISM).unwrap();
+    let dir = TempDir::new().unwrap();
+    let builder = AgentPipelineBuilder::new(org, &dir.path().join("data"));
+    let event_tx = builder.event_sender();
+    let tx = event_tx.clone();
+    let bob = FnHandler(move |p: ValidatedPayload, ctx: HandlerContext| {
+        let tx = tx.clone();
+        Box::pin(async move {
+            let _ = tx.send(PipelineEvent::TokenUsage {
+                thread_id: ctx.thread_id.clone(),
+                model: "claude-sonnet-4-6".to_string(),
+                input_tokens: 1_000,
+                output_tokens: 500,
+            });
+            let _ = tx.send(PipelineEvent::AgentResponse {
+                thread_id: ctx.thread_id.clone(),
+                agent_name: "bob".to_string(),
+                text: "ok".to_string(),
+                shim_report: None,
+            });
+            Ok(HandlerResponse::Reply { payload_xml: p.xml })
+        })
+    });
+    let mut pipeline = builder.register("bob", bob).unwrap().build().unwrap();
+    pipeline
+        .initialize_root("server-budget-test", "default")
+        .await
+        .unwrap();
+    pipeline.run();
+
+    let mut prices = ModelsConfig::default();
+    prices.prices.insert(
+        "claude-sonnet-4-6".into(),
+        ModelPrice {
+            input_per_mtok: 3.0,
+            output_per_mtok: 15.0,
+        },
+    );
+    let budgets = Budgets::new(Duration::from_secs(3600), per_user, prices);
+    let _listener = budgets.clone().spawn_listener(&event_tx);
+
+    let state = Arc::new(ServerState {
+        router: Arc::new(pipeline.shared_router(0, Duration::from_secs(60))),
+        events: event_tx,
+        kernel: pipeline.kernel(),
+        organism: Arc::new(pipeline.organism().clone()),
+        agent_name: "bob".to_string(),
+        auth: Arc::new(Authenticator::static_token("test-token")),
+        idempotency: agentos_server::idempotency::IdempotencyCache::new(),
+        rate_limiter: RateLimiter::with_user_limit(user_rpm),
+        budgets,
+    });
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = build_router(state.clone());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    Harness {
+        addr,
+        state,
+        _dir: dir,
+    }
+}
+
+async fn post(h: &Harness, user: &str, key: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("http://{}/v1/messages", h.addr))
+        .bearer_auth("test-token")
+        .json(&serde_json::json!({
+            "user_id": user,
+            "user_tier": "warm",
+            "text": "hello",
+            "idempotency_key": key
+        }))
+        .send()
+        .await
+        .unwrap()
+}
+
+/// The listener charges asynchronously; wait for it to catch up.
+async fn wait_for_spend(h: &Harness, user: &str, tokens: u64) {
+    for _ in 0..100 {
+        if h.state.budgets.user_spend("static", user).tokens >= tokens {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("usage for {user} never reached {tokens} tokens");
+}
+
+#[tokio::test]
+async fn user_token_budget_rejects_with_retry_after() {
+    let h = start(
+        BudgetLimits {
+            tokens: Some(2 * TURN_TOKENS),
+            usd: None,
+        },
+        None,
+    )
+    .await;
+
+    for key in ["k1", "k2"] {
+        let resp = post(&h, "alice", key).await;
+        assert_eq!(resp.status(), 200);
+        let _ = resp.text().await.unwrap();
+    }
+    wait_for_spend(&h, "bob[alice]", 2 * TURN_TOKENS).await;
+
+    let resp = post(&h, "alice", "k3").await;
+    assert_eq!(resp.status(), 429);
+    let header: u64 = resp.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "budget_exceeded");
+    assert_eq!(body["error"]["retry_after"].as_u64(), Some(header));
+    assert!(header > 0 && header <= 3600);
+
+    // Another user is unaffected.
+    let resp = post(&h, "carol", "k4").await;
+    assert_eq!(resp.status(), 200);
+    let _ = resp.text().await.unwrap();
+
+    // Dollar spend: 2 turns × (1000 × $3 + 500 × $15) / 1M.
+    let spend = h.state.budgets.user_spend("static", "bob[alice]");
+    assert!((spend.usd - 0.021).abs() < 1e-9);
+
+    h.state.budgets.publish_gauges();
+    let text = reqwest::get(format!("http://{}/metrics", h.addr))
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(text.contains(metrics::BUDGET_USD_USED));
+    assert!(text.contains(metrics::BUDGET_USERS_EXHAUSTED));
+    assert!(text.contains(metrics::RATE_LIMITED_TOTAL));
+}
+
+#[tokio::test]
+async fn per_user_rate_limit() {
+    let h = start(BudgetLimits::default(), Some(1)).await;
+
+    let resp = post(&h, "alice", "k1").await;
+    assert_eq!(resp.status(), 200);
+    let _ = resp.text().await.unwrap();
+
+    let resp = post(&h, "alice", "k2").await;
+    assert_eq!(resp.status(), 429);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "rate_limited");
+    assert!(body["error"]["retry_after"].as_u64().unwrap() >= 1);
+
+    let resp = post(&h, "carol", "k3").await;
+    assert_eq!(resp.status(), 200);
+}
@@ -97,10 +97,13 @@ async fn boot_server() -> (
     let state = Arc::new(ServerState {
         router: shared_router,
         events: event_tx,
+        kernel: pipeline.kernel(),
         organism: Arc::new(pipeline.organism().clone()),
         agent_name: "bob".to_string(),
-        auth_token: "test-token".to_string(),
+        auth: Arc::new(agentos_server::auth::Authenticator::static_token("test-token")),
         idempotency: agentos_server::idempotency::IdempotencyCache::new(),
+        rate_limiter: agentos_server::rate_limit::RateLimiter::new(),
+        budgets: agentos_server::budget::Budgets::unlimited(),
     });
 
     let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
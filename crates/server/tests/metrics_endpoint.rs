@@ -7,6 +7,8 @@
 //! - Idempotency lookup counters increment on miss + replay.
 //! - Active-stream gauge appears in output (we don't assert exact value
 //!   since timing makes it racy, but the metric must be declared).
+//! - Pipeline-level series from `agentos-telemetry` (WAL, context and
+//!   instance tiers) render from the same endpoint.
 
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
@@ -91,10 +93,13 @@ async fn boot_server() -> (
     let state = Arc::new(ServerState {
         router: shared_router,
         events: event_tx,
+        kernel: pipeline.kernel(),
         organism: Arc::new(pipeline.organism().clone()),
         agent_name: "bob".to_string(),
-        auth_token: "test-token".to_string(),
+        auth: Arc::new(agentos_server::auth::Authenticator::static_token("test-token")),
         idempotency: agentos_server::idempotency::IdempotencyCache::new(),
+        rate_limiter: agentos_server::rate_limit::RateLimiter::new(),
+        budgets: agentos_server::budget::Budgets::unlimited(),
     });
 
     let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
@@ -259,3 +264,53 @@ async fn client_error_status_recorded_for_invalid_request() {
         "expected status=client_error label after 401; got:\n{body}"
     );
 }
+
+#[tokio::test]
+async fn pipeline_metrics_render_alongside_http_metrics() {
+    let (addr, _counter, _dir, pipeline) = boot_server().await;
+    let _sampler = pipeline.spawn_gauge_sampler(Duration::from_millis(10));
+    let client = reqwest::Client::new();
+
+    // Materializes `bob[alice]` (platform tier gauge) and writes the
+    // platform thread registration through the kernel WAL.
+    let resp = client
+        .post(format!("http://{addr}/v1/messages"))
+        .bearer_auth("test-token")
+        .json(&post_body("hello", "metrics-idem-3"))
+        .send()
+        .await
+        .unwrap();
+    assert!(resp.status().is_success());
+    let _ = resp.text().await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let body = client
+        .get(format!("http://{addr}/metrics"))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    for required in [
+        agentos_telemetry::WAL_APPEND_DURATION_SECONDS,
+        agentos_telemetry::WAL_APPEND_BYTES,
+        agentos_telemetry::PLATFORM_INSTANCES,
+        agentos_telemetry::CONTEXT_BYTES,
+    ] {
+        assert!(
+            body.contains(required),
+            "expected /metrics body to contain {required}; got:\n{body}"
+        );
+    }
+
+    // The TUI reads the same recorder through a snapshot. Values of the
+    // instance gauge race with the other tests' registries (one global
+    // recorder per test binary), so only assert on the shape.
+    let snap = agentos_telemetry::snapshot();
+    assert!(snap.count(agentos_telemetry::WAL_APPEND_DURATION_SECONDS, &[]) > 0);
+    assert_eq!(
+        snap.label_values(agentos_telemetry::PLATFORM_INSTANCES, "tier"),
+        vec!["active", "folded", "shelved"]
+    );
+}
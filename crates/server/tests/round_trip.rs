@@ -82,10 +82,13 @@ async fn post_messages_round_trip() {
     let state = Arc::new(ServerState {
         router: shared_router,
         events: event_tx,
+        kernel: pipeline.kernel(),
         organism: Arc::new(pipeline.organism().clone()),
         agent_name: "bob".to_string(),
-        auth_token: "test-token".to_string(),
+        auth: Arc::new(agentos_server::auth::Authenticator::static_token("test-token")),
         idempotency: agentos_server::idempotency::IdempotencyCache::new(),
+        rate_limiter: agentos_server::rate_limit::RateLimiter::new(),
+        budgets: agentos_server::budget::Budgets::unlimited(),
     });
 
     // Bind the server on an ephemeral port so the test never collides.
@@ -180,10 +183,13 @@ async fn post_messages_rejects_anon_tier() {
     let state = Arc::new(ServerState {
         router: shared_router,
         events: event_tx,
+        kernel: pipeline.kernel(),
         organism: Arc::new(pipeline.organism().clone()),
         agent_name: "bob".to_string(),
-        auth_token: "test-token".to_string(),
+        auth: Arc::new(agentos_server::auth::Authenticator::static_token("test-token")),
         idempotency: agentos_server::idempotency::IdempotencyCache::new(),
+        rate_limiter: agentos_server::rate_limit::RateLimiter::new(),
+        budgets: agentos_server::budget::Budgets::unlimited(),
     });
     let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
     let addr = listener.local_addr().unwrap();
@@ -232,10 +238,13 @@ async fn post_messages_rejects_missing_bearer() {
     let state = Arc::new(ServerState {
         router: shared_router,
         events: event_tx,
+        kernel: pipeline.kernel(),
         organism: Arc::new(pipeline.organism().clone()),
         agent_name: "bob".to_string(),
-        auth_token: "test-token".to_string(),
+        auth: Arc::new(agentos_server::auth::Authenticator::static_token("test-token")),
         idempotency: agentos_server::idempotency::IdempotencyCache::new(),
+        rate_limiter: agentos_server::rate_limit::RateLimiter::new(),
+        budgets: agentos_server::budget::Budgets::unlimited(),
     });
     let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
     let addr = listener.local_addr().unwrap();
@@ -307,10 +316,13 @@ async fn shim_silent_emits_no_text_events() {
     let state = Arc::new(ServerState {
         router: shared_router,
         events: event_tx,
+        kernel: pipeline.kernel(),
         organism: Arc::new(pipeline.organism().clone()),
         agent_name: "bob".to_string(),
-        auth_token: "test-token".to_string(),
+        auth: Arc::new(agentos_server::auth::Authenticator::static_token("test-token")),
         idempotency: agentos_server::idempotency::IdempotencyCache::new(),
+        rate_limiter: agentos_server::rate_limit::RateLimiter::new(),
+        budgets: agentos_server::budget::Budgets::unlimited(),
     });
     let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
     let addr = listener.local_addr().unwrap();
@@ -400,10 +412,13 @@ async fn shim_non_silent_populates_done_metadata() {
     let state = Arc::new(ServerState {
         router: shared_router,
         events: event_tx,
+        kernel: pipeline.kernel(),
         organism: Arc::new(pipeline.organism().clone()),
         agent_name: "bob".to_string(),
-        auth_token: "test-token".to_string(),
+        auth: Arc::new(agentos_server::auth::Authenticator::static_token("test-token")),
         idempotency: agentos_server::idempotency::IdempotencyCache::new(),
+        rate_limiter: agentos_server::rate_limit::RateLimiter::new(),
+        budgets: agentos_server::budget::Budgets::unlimited(),
     });
     let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
     let addr = listener.local_addr().unwrap();
@@ -0,0 +1,261 @@
+//! Scoped API tokens, end to end.
+//!
+//! A named token scoped to `ringhub.bob[*]` must land its users in the
+//! `ringhub` namespace, be held to its tier list and rate limit, and
+//! see only its own instances on the admin routes. The legacy static
+//! token keeps root scope alongside it.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use agentos_events::PipelineEvent;
+use agentos_organism::parser::parse_organism;
+use agentos_pipeline::AgentPipelineBuilder;
+use agentos_platform::address::Address;
+use agentos_server::auth::{Authenticator, ScopePattern, TokenStore};
+use agentos_server::budget::BudgetLimits;
+use agentos_server::{build_router, ServerState};
+
+use rust_pipeline::prelude::{FnHandler, HandlerContext, HandlerResponse, ValidatedPayload};
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+
+const ORGANISM: &str = r#"
+organism:
+  name: server-scoped-token-test
+
+listeners:
+  - name: bob
+    payload_class: agent.AgentTask
+    handler: agent.handle
+    description: "Stub Bob"
+    agent:
+      prompt: "stub"
+
+profiles:
+  default:
+    linux_user: agentos
+    listeners: [bob]
+    journal: retain_forever
+"#;
+
+struct Harness {
+    addr: SocketAddr,
+    state: Arc<ServerState>,
+    tokens_path: std::path::PathBuf,
+    ringhub: String,
+    _dir: TempDir,
+}
+
+async fn start() -> Harness {
+    let org = parse_organism(ORGANISM).unwrap();
+    let dir = TempDir::new().unwrap();
+    let data_dir = dir.path().join("data");
+    let builder = AgentPipelineBuilder::new(org, &data_dir);
+    let event_tx = builder.event_sender();
+    let tx = event_tx.clone();
+    let bob = FnHandler(move |p: ValidatedPayload, ctx: HandlerContext| {
+        let tx = tx.clone();
+        Box::pin(async move {
+            let _ = tx.send(PipelineEvent::AgentResponse {
+                thread_id: ctx.thread_id.clone(),
+                agent_name: "bob".to_string(),
+                text: "ok".to_string(),
+                shim_report: None,
+            });
+            Ok(HandlerResponse::Reply { payload_xml: p.xml })
+        })
+    });
+    let mut pipeline = builder.register("bob", bob).unwrap().build().unwrap();
+    pipeline
+        .initialize_root("server-scoped-token-test", "default")
+        .await
+        .unwrap();
+    pipeline.run();
+
+    let tokens_path = TokenStore::default_path(&data_dir);
+    let mut store = TokenStore::open(&tokens_path).unwrap();
+    let ringhub = store
+        .create(
+            "ringhub",
+            ScopePattern::parse("ringhub.bob[*]").unwrap(),
+            vec!["warm".into()],
+            Some(2),
+            BudgetLimits::default(),
+        )
+        .unwrap();
+
+    let auth = Authenticator::static_token("test-token").with_store(store);
+    let state = Arc::new(ServerState {
+        router: Arc::new(pipeline.shared_router(0, Duration::from_secs(60))),
+        events: event_tx,
+        kernel: pipeline.kernel(),
+        organism: Arc::new(pipeline.organism().clone()),
+        agent_name: "bob".to_string(),
+        auth: Arc::new(auth),
+        idempotency: agentos_server::idempotency::IdempotencyCache::new(),
+        rate_limiter: agentos_server::rate_limit::RateLimiter::new(),
+        budgets: agentos_server::budget::Budgets::unlimited(),
+    });
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = build_router(state.clone());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    Harness {
+        addr,
+        state,
+        tokens_path,
+        ringhub,
+        _dir: dir,
+    }
+}
+
+async fn post(h: &Harness, token: &str, user: &str, tier: &str, key: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("http://{}/v1/messages", h.addr))
+        .bearer_auth(token)
+        .json(&serde_json::json!({
+            "user_id": user,
+            "user_tier": tier,
+            "text": "hello",
+            "idempotency_key": key
+        }))
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn scoped_token_lands_in_its_namespace() {
+    let h = start().await;
+    let resp = post(&h, &h.ringhub, "alice", "warm", "k1").await;
+    assert_eq!(resp.status(), 200);
+    let _ = resp.text().await.unwrap();
+
+    let router = &h.state.router;
+    assert!(router
+        .lookup(&Address::parse("ringhub.bob[alice]").unwrap())
+        .await
+        .is_some());
+    assert!(router
+        .lookup(&Address::parse("bob[alice]").unwrap())
+        .await
+        .is_none());
+}
+
+#[tokio::test]
+async fn scoped_token_enforces_tiers_and_rate_limit() {
+    let h = start().await;
+
+    let resp = post(&h, &h.ringhub, "alice", "member", "k0").await;
+    assert_eq!(resp.status(), 403);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "forbidden");
+
+    // The forbidden request didn't count: two succeed, the third trips.
+    for key in ["k1", "k2"] {
+        let resp = post(&h, &h.ringhub, "alice", "warm", key).await;
+        assert_eq!(resp.status(), 200);
+        let _ = resp.text().await.unwrap();
+    }
+    let resp = post(&h, &h.ringhub, "alice", "warm", "k3").await;
+    assert_eq!(resp.status(), 429);
+    let retry: u64 = resp.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+    assert!((1..=60).contains(&retry));
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "rate_limited");
+
+    // The static token has its own (unlimited) budget.
+    let resp = post(&h, "test-token", "alice", "member", "k4").await;
+    assert_eq!(resp.status(), 200);
+}
+
+#[tokio::test]
+async fn admin_routes_respect_scope() {
+    let h = start().await;
+    let _ = post(&h, &h.ringhub, "alice", "warm", "k1").await.text().await;
+    let _ = post(&h, "test-token", "carol", "warm", "k2").await.text().await;
+
+    let list = |token: String| {
+        let addr = h.addr;
+        async move {
+            let body: serde_json::Value = reqwest::Client::new()
+                .get(format!("http://{addr}/v1/instances"))
+                .bearer_auth(token)
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            body["instances"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|i| i["address"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        }
+    };
+    assert_eq!(list(h.ringhub.clone()).await, vec!["ringhub.bob[alice]"]);
+    assert_eq!(list("test-token".into()).await.len(), 2);
+
+    // Another tenant's instance is invisible, not forbidden.
+    let resp = reqwest::Client::new()
+        .get(format!("http://{}/v1/instances/bob%5Bcarol%5D", h.addr))
+        .bearer_auth(&h.ringhub)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 404);
+
+    // User routes resolve inside the namespace.
+    let body: serde_json::Value = reqwest::Client::new()
+        .get(format!("http://{}/v1/users/alice/conversations", h.addr))
+        .bearer_auth(&h.ringhub)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["address"], "ringhub.bob[alice]");
+}
+
+#[tokio::test]
+async fn revoked_token_is_rejected_without_restart() {
+    let h = start().await;
+    let mut cli_view = TokenStore::open(&h.tokens_path).unwrap();
+    cli_view.revoke("ringhub").unwrap();
+
+    let resp = post(&h, &h.ringhub, "alice", "warm", "k1").await;
+    assert_eq!(resp.status(), 403);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "unauthorized");
+}
+
+#[tokio::test]
+async fn un_namespaced_record_cannot_reach_unscoped_listener() {
+    let h = start().await;
+    // Hand-edit the record to a bare scope that matches the
+    // un-namespaced `bob` listener; `create` would refuse it.
+    let edited = std::fs::read_to_string(&h.tokens_path)
+        .unwrap()
+        .replace("ringhub.bob[*]", "bob[*]");
+    std::fs::write(&h.tokens_path, edited).unwrap();
+
+    let resp = post(&h, &h.ringhub, "alice", "warm", "k1").await;
+    assert_eq!(resp.status(), 403);
+    let body: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(body["error"]["code"], "unauthorized");
+    assert!(h
+        .state
+        .router
+        .lookup(&Address::parse("bob[alice]").unwrap())
+        .await
+        .is_none());
+}
@@ -0,0 +1,94 @@
+//! JSON-lines span exporter — the no-collector option.
+//!
+//! One object per finished span, appended to a file:
+//! `{"trace_id","span_id","parent_span_id","name","start_unix_nanos",
+//! "end_unix_nanos","status","attributes":{...}}`. Good enough to grep,
+//! `jq`, or load into a notebook when there is no collector to hand.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use opentelemetry::trace::{SpanId, Status};
+use opentelemetry::Value;
+use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use serde_json::{json, Map};
+
+#[derive(Debug)]
+pub(crate) struct JsonLinesExporter {
+    out: Mutex<BufWriter<File>>,
+}
+
+impl JsonLinesExporter {
+    /// Open `path` for appending, creating parent directories.
+    pub(crate) fn open(path: &Path) -> Result<Self, String> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("creating {}: {e}", parent.display()))?;
+        }
+        let file = File::options()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("opening trace file {}: {e}", path.display()))?;
+        Ok(Self {
+            out: Mutex::new(BufWriter::new(file)),
+        })
+    }
+
+    fn write_batch(&self, batch: &[SpanData]) -> std::io::Result<()> {
+        let mut out = self.out.lock().unwrap_or_else(|e| e.into_inner());
+        for span in batch {
+            serde_json::to_writer(&mut *out, &to_json(span))?;
+            out.write_all(b"\n")?;
+        }
+        out.flush()
+    }
+}
+
+impl SpanExporter for JsonLinesExporter {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        self.write_batch(&batch)
+            .map_err(|e| OTelSdkError::InternalFailure(format!("trace file write: {e}")))
+    }
+}
+
+fn to_json(span: &SpanData) -> serde_json::Value {
+    let parent = (span.parent_span_id != SpanId::INVALID).then(|| span.parent_span_id.to_string());
+    let status = match &span.status {
+        Status::Unset => "unset",
+        Status::Ok => "ok",
+        Status::Error { .. } => "error",
+    };
+    let attributes: Map<String, serde_json::Value> = span
+        .attributes
+        .iter()
+        .map(|kv| (kv.key.to_string(), value_to_json(&kv.value)))
+        .collect();
+    json!({
+        "trace_id": span.span_context.trace_id().to_string(),
+        "span_id": span.span_context.span_id().to_string(),
+        "parent_span_id": parent,
+        "name": span.name,
+        "start_unix_nanos": unix_nanos(span.start_time),
+        "end_unix_nanos": unix_nanos(span.end_time),
+        "status": status,
+        "attributes": attributes,
+    })
+}
+
+fn value_to_json(value: &Value) -> serde_json::Value {
+    match value {
+        Value::Bool(b) => json!(b),
+        Value::I64(i) => json!(i),
+        Value::F64(f) => json!(f),
+        other => json!(other.as_str()),
+    }
+}
+
+fn unix_nanos(t: SystemTime) -> u128 {
+    t.duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0)
+}
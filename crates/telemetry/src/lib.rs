@@ -0,0 +1,291 @@
+//! Pipeline telemetry — Prometheus metrics recorded from inside AgentOS.
+//!
+//! The HTTP layer (`agentos-server`) has its own request metrics; this
+//! crate covers what happens *behind* it: LLM calls, tool calls,
+//! permission prompts, injection detections, WAL appends, context store
+//! occupancy, platform instance tiers, and trigger fires.
+//!
+//! Instrumented crates call the `record_*` / `set_*` helpers below,
+//! which go through the `metrics` facade. Nothing is kept unless a
+//! frontend calls [`install`]: the server does so from `build_router`
+//! and renders [`render`] at `GET /metrics`; the TUI does so at startup
+//! and reads [`snapshot`] for its dashboard. One recorder, one set of
+//! numbers — the dashboard and a Prometheus scrape never disagree.
+//!
+//! Labels stay low-cardinality: model ids, agent / tool / trigger
+//! listener names (all bounded by the organism), and fixed enums. No
+//! thread ids, no addresses, no user ids.
+//!
+//! Per-request detail — which thread, which profile — lives in the
+//! tracing spans of [`trace`] instead. Exporting those spans needs the
+//! `export` feature; without it the spans stay in-process.
+
+#[cfg(feature = "export")]
+mod jsonl;
+pub mod snapshot;
+pub mod trace;
+
+pub use snapshot::{MetricsSnapshot, Sample};
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+
+/// Metric names. Constants rather than inline strings so frontends and
+/// tests can query them without typo risk.
+pub const LLM_CALL_DURATION_SECONDS: &str = "agentos_llm_call_duration_seconds";
+pub const LLM_CALLS_TOTAL: &str = "agentos_llm_calls_total";
+pub const LLM_CALL_TOKENS_TOTAL: &str = "agentos_llm_call_tokens_total";
+pub const TOOL_CALL_DURATION_SECONDS: &str = "agentos_tool_call_duration_seconds";
+pub const TOOL_CALLS_TOTAL: &str = "agentos_tool_calls_total";
+pub const PERMISSION_PROMPTS_TOTAL: &str = "agentos_permission_prompts_total";
+pub const PERMISSION_DENIALS_TOTAL: &str = "agentos_permission_denials_total";
+pub const INJECTIONS_DETECTED_TOTAL: &str = "agentos_injections_detected_total";
+pub const WAL_APPEND_DURATION_SECONDS: &str = "agentos_wal_append_duration_seconds";
+pub const WAL_APPEND_BYTES: &str = "agentos_wal_append_bytes";
+pub const CONTEXT_BYTES: &str = "agentos_context_bytes";
+pub const PLATFORM_INSTANCES: &str = "agentos_platform_instances";
+pub const TRIGGER_FIRES_TOTAL: &str = "agentos_trigger_fires_total";
+
+/// Call outcome labels for LLM and tool calls.
+pub const STATUS_OK: &str = "ok";
+pub const STATUS_ERROR: &str = "error";
+
+/// Token direction labels.
+pub const DIRECTION_INPUT: &str = "input";
+pub const DIRECTION_OUTPUT: &str = "output";
+
+/// Permission denial reasons: the agent's policy, or the user at the prompt.
+pub const DENIED_BY_POLICY: &str = "policy";
+pub const DENIED_BY_USER: &str = "user";
+
+/// VMM tier labels, shared by the context store and the platform
+/// registry (both use the same Active / Shelved / Folded hierarchy).
+pub const TIER_ACTIVE: &str = "active";
+pub const TIER_SHELVED: &str = "shelved";
+pub const TIER_FOLDED: &str = "folded";
+
+static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Install the process-wide Prometheus recorder if it hasn't been
+/// already, and return its handle. Safe to call concurrently and
+/// repeatedly — the first call wins.
+pub fn install() -> &'static PrometheusHandle {
+    HANDLE.get_or_init(|| {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        // Fails only if some other code installed a recorder first. We
+        // still hand back a handle so callers don't have to branch; it
+        // just never sees a recording. Say so loudly — a dashboard that
+        // silently reads zero is worse than no dashboard.
+        if let Err(e) = metrics::set_global_recorder(recorder) {
+            tracing::error!(
+                error = ?e,
+                "metrics::set_global_recorder failed — pipeline metrics will be empty. \
+                 Another library installed a recorder before agentos_telemetry::install()."
+            );
+        }
+        describe_all();
+        handle
+    })
+}
+
+/// The installed recorder's handle, if [`install`] has run.
+pub fn handle() -> Option<&'static PrometheusHandle> {
+    HANDLE.get()
+}
+
+/// Render everything recorded so far in Prometheus exposition format.
+/// Empty if [`install`] was never called.
+pub fn render() -> String {
+    HANDLE.get().map(|h| h.render()).unwrap_or_default()
+}
+
+/// Structured view of [`render`] for in-process readers (the TUI).
+pub fn snapshot() -> MetricsSnapshot {
+    MetricsSnapshot::parse(&render())
+}
+
+fn describe_all() {
+    metrics::describe_histogram!(
+        LLM_CALL_DURATION_SECONDS,
+        metrics::Unit::Seconds,
+        "Wall-clock time of agent LLM calls, by model, agent and outcome."
+    );
+    metrics::describe_counter!(
+        LLM_CALLS_TOTAL,
+        "Agent LLM calls by model, agent and outcome (ok / error)."
+    );
+    metrics::describe_counter!(
+        LLM_CALL_TOKENS_TOTAL,
+        "LLM tokens consumed by agent calls, by model, agent and direction (input / output)."
+    );
+    metrics::describe_histogram!(
+        TOOL_CALL_DURATION_SECONDS,
+        metrics::Unit::Seconds,
+        "Wall-clock time of tool handler invocations, by tool and outcome."
+    );
+    metrics::describe_counter!(
+        TOOL_CALLS_TOTAL,
+        "Tool handler invocations by tool and outcome. `error` covers both \
+         handler errors and ToolResponses with success=false."
+    );
+    metrics::describe_counter!(
+        PERMISSION_PROMPTS_TOTAL,
+        "Tool calls that stopped to ask the user for approval, by tool."
+    );
+    metrics::describe_counter!(
+        PERMISSION_DENIALS_TOTAL,
+        "Tool calls refused by the permission gate, by tool and reason (policy / user)."
+    );
+    metrics::describe_counter!(
+        INJECTIONS_DETECTED_TOTAL,
+        "Tool outputs flagged by the injection scanner before reaching an agent, by tool."
+    );
+    metrics::describe_histogram!(
+        WAL_APPEND_DURATION_SECONDS,
+        metrics::Unit::Seconds,
+        "Kernel WAL append latency, write plus fsync."
+    );
+    metrics::describe_histogram!(
+        WAL_APPEND_BYTES,
+        metrics::Unit::Bytes,
+        "Bytes written per kernel WAL append (single entry or atomic batch)."
+    );
+    metrics::describe_gauge!(
+        CONTEXT_BYTES,
+        metrics::Unit::Bytes,
+        "Kernel context store segment bytes by tier (active / shelved / folded)."
+    );
+    metrics::describe_gauge!(
+        PLATFORM_INSTANCES,
+        "Materialized platform instances by tier (active / shelved / folded)."
+    );
+    metrics::describe_counter!(
+        TRIGGER_FIRES_TOTAL,
+        "Trigger events delivered to the pipeline, by trigger listener."
+    );
+}
+
+// ── recording helpers ─────────────────────────────────────────────────
+
+fn status(ok: bool) -> &'static str {
+    if ok {
+        STATUS_OK
+    } else {
+        STATUS_ERROR
+    }
+}
+
+/// One agent LLM call, successful or not.
+pub fn record_llm_call(model: &str, agent: &str, duration: Duration, ok: bool) {
+    let labels = [
+        ("model", model.to_string()),
+        ("agent", agent.to_string()),
+        ("status", status(ok).to_string()),
+    ];
+    metrics::counter!(LLM_CALLS_TOTAL, &labels).increment(1);
+    metrics::histogram!(LLM_CALL_DURATION_SECONDS, &labels).record(duration.as_secs_f64());
+}
+
+/// Token usage reported by a successful LLM call.
+pub fn record_llm_tokens(model: &str, agent: &str, input: u64, output: u64) {
+    let model = model.to_string();
+    let agent = agent.to_string();
+    metrics::counter!(
+        LLM_CALL_TOKENS_TOTAL,
+        "model" => model.clone(), "agent" => agent.clone(), "direction" => DIRECTION_INPUT
+    )
+    .increment(input);
+    metrics::counter!(
+        LLM_CALL_TOKENS_TOTAL,
+        "model" => model, "agent" => agent, "direction" => DIRECTION_OUTPUT
+    )
+    .increment(output);
+}
+
+/// One tool handler invocation.
+pub fn record_tool_call(tool: &str, duration: Duration, ok: bool) {
+    let labels = [("tool", tool.to_string()), ("status", status(ok).to_string())];
+    metrics::counter!(TOOL_CALLS_TOTAL, &labels).increment(1);
+    metrics::histogram!(TOOL_CALL_DURATION_SECONDS, &labels).record(duration.as_secs_f64());
+}
+
+pub fn record_permission_prompt(tool: &str) {
+    metrics::counter!(PERMISSION_PROMPTS_TOTAL, "tool" => tool.to_string()).increment(1);
+}
+
+/// `reason` is [`DENIED_BY_POLICY`] or [`DENIED_BY_USER`].
+pub fn record_permission_denial(tool: &str, reason: &'static str) {
+    metrics::counter!(PERMISSION_DENIALS_TOTAL, "tool" => tool.to_string(), "reason" => reason)
+        .increment(1);
+}
+
+pub fn record_injection_detected(tool: &str) {
+    metrics::counter!(INJECTIONS_DETECTED_TOTAL, "tool" => tool.to_string()).increment(1);
+}
+
+pub fn record_wal_append(duration: Duration, bytes: usize) {
+    metrics::histogram!(WAL_APPEND_DURATION_SECONDS).record(duration.as_secs_f64());
+    metrics::histogram!(WAL_APPEND_BYTES).record(bytes as f64);
+}
+
+/// Context store occupancy, sampled periodically.
+pub fn set_context_bytes(active: usize, shelved: usize, folded: usize) {
+    metrics::gauge!(CONTEXT_BYTES, "tier" => TIER_ACTIVE).set(active as f64);
+    metrics::gauge!(CONTEXT_BYTES, "tier" => TIER_SHELVED).set(shelved as f64);
+    metrics::gauge!(CONTEXT_BYTES, "tier" => TIER_FOLDED).set(folded as f64);
+}
+
+/// Platform instance counts, published whenever a tier changes.
+pub fn set_platform_instances(active: usize, shelved: usize, folded: usize) {
+    metrics::gauge!(PLATFORM_INSTANCES, "tier" => TIER_ACTIVE).set(active as f64);
+    metrics::gauge!(PLATFORM_INSTANCES, "tier" => TIER_SHELVED).set(shelved as f64);
+    metrics::gauge!(PLATFORM_INSTANCES, "tier" => TIER_FOLDED).set(folded as f64);
+}
+
+pub fn record_trigger_fire(trigger: &str) {
+    metrics::counter!(TRIGGER_FIRES_TOTAL, "trigger" => trigger.to_string()).increment(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn install_is_idempotent() {
+        assert!(std::ptr::eq(install(), install()));
+    }
+
+    #[test]
+    fn recordings_show_up_in_render_and_snapshot() {
+        install();
+        record_llm_call("test-model", "bob", Duration::from_millis(1500), true);
+        record_llm_call("test-model", "bob", Duration::from_millis(500), false);
+        record_llm_tokens("test-model", "bob", 1000, 250);
+        record_tool_call("file-read", Duration::from_millis(4), true);
+        record_permission_denial("bash", DENIED_BY_USER);
+        set_context_bytes(10, 20, 30);
+
+        let text = render();
+        assert!(text.contains(LLM_CALL_DURATION_SECONDS), "{text}");
+        assert!(text.contains(TOOL_CALLS_TOTAL), "{text}");
+
+        let snap = snapshot();
+        let bob = [("model", "test-model"), ("agent", "bob")];
+        assert_eq!(snap.sum(LLM_CALLS_TOTAL, &bob), 2.0);
+        assert_eq!(
+            snap.sum(LLM_CALLS_TOTAL, &[("agent", "bob"), ("status", STATUS_ERROR)]),
+            1.0
+        );
+        assert_eq!(
+            snap.sum(LLM_CALL_TOKENS_TOTAL, &[("agent", "bob"), ("direction", DIRECTION_INPUT)]),
+            1000.0
+        );
+        let mean = snap.mean(LLM_CALL_DURATION_SECONDS, &bob).unwrap();
+        assert!((mean - 1.0).abs() < 1e-9);
+        assert_eq!(snap.sum(PERMISSION_DENIALS_TOTAL, &[("tool", "bash")]), 1.0);
+        assert_eq!(snap.sum(CONTEXT_BYTES, &[("tier", TIER_SHELVED)]), 20.0);
+    }
+}
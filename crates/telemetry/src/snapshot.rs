@@ -0,0 +1,202 @@
+//! In-process reader for the rendered exposition text.
+//!
+//! The Prometheus recorder only exposes its state as rendered text, so
+//! the TUI reads the same bytes a scraper would and parses them here.
+//! The parser covers exactly what `metrics-exporter-prometheus` emits:
+//! `# HELP` / `# TYPE` comments and `name{k="v",...} value` lines, with
+//! histograms rendered as summaries (`quantile` label plus `_sum` /
+//! `_count` series).
+
+use std::collections::BTreeMap;
+
+/// One exposition line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+    pub value: f64,
+}
+
+impl Sample {
+    fn matches(&self, name: &str, filter: &[(&str, &str)]) -> bool {
+        self.name == name
+            && filter
+                .iter()
+                .all(|(k, v)| self.labels.get(*k).map(String::as_str) == Some(*v))
+    }
+}
+
+/// Parsed metrics at one point in time.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsSnapshot {
+    samples: Vec<Sample>,
+}
+
+impl MetricsSnapshot {
+    /// Parse exposition text. Malformed lines are skipped rather than
+    /// failing the whole snapshot — a dashboard should degrade, not die.
+    pub fn parse(text: &str) -> Self {
+        let samples = text
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .filter_map(parse_line)
+            .collect();
+        Self { samples }
+    }
+
+    pub fn samples(&self) -> &[Sample] {
+        &self.samples
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Sum of every `name` sample whose labels include all of `filter`.
+    /// Zero when nothing matches.
+    pub fn sum(&self, name: &str, filter: &[(&str, &str)]) -> f64 {
+        self.samples
+            .iter()
+            .filter(|s| s.matches(name, filter))
+            .map(|s| s.value)
+            .sum()
+    }
+
+    /// Mean observation of a histogram (`_sum / _count`) across every
+    /// series matching `filter`. `None` before the first observation.
+    pub fn mean(&self, histogram: &str, filter: &[(&str, &str)]) -> Option<f64> {
+        let count = self.sum(&format!("{histogram}_count"), filter);
+        if count == 0.0 {
+            return None;
+        }
+        Some(self.sum(&format!("{histogram}_sum"), filter) / count)
+    }
+
+    /// Observation count of a histogram across matching series.
+    pub fn count(&self, histogram: &str, filter: &[(&str, &str)]) -> u64 {
+        self.sum(&format!("{histogram}_count"), filter) as u64
+    }
+
+    /// Distinct values of `label` on `name` samples, sorted.
+    pub fn label_values(&self, name: &str, label: &str) -> Vec<String> {
+        let mut values: Vec<String> = self
+            .samples
+            .iter()
+            .filter(|s| s.name == name)
+            .filter_map(|s| s.labels.get(label).cloned())
+            .collect();
+        values.sort();
+        values.dedup();
+        values
+    }
+}
+
+fn parse_line(line: &str) -> Option<Sample> {
+    let (series, value) = line.rsplit_once(' ')?;
+    let value = match value {
+        "+Inf" => f64::INFINITY,
+        "-Inf" => f64::NEG_INFINITY,
+        v => v.parse().ok()?,
+    };
+    let (name, labels) = match series.find('{') {
+        Some(open) => {
+            let body = series[open + 1..].strip_suffix('}')?;
+            (&series[..open], parse_labels(body)?)
+        }
+        None => (series, BTreeMap::new()),
+    };
+    Some(Sample {
+        name: name.to_string(),
+        labels,
+        value,
+    })
+}
+
+/// `k="v",k2="v2"` with `\\`, `\"` and `\n` escapes in values.
+fn parse_labels(body: &str) -> Option<BTreeMap<String, String>> {
+    let mut labels = BTreeMap::new();
+    let mut rest = body;
+    while !rest.is_empty() {
+        let (key, after) = rest.split_once("=\"")?;
+        let mut value = String::new();
+        let mut chars = after.char_indices();
+        let end = loop {
+            match chars.next()? {
+                (i, '"') => break i,
+                (_, '\\') => match chars.next()?.1 {
+                    'n' => value.push('\n'),
+                    c => value.push(c),
+                },
+                (_, c) => value.push(c),
+            }
+        };
+        labels.insert(key.trim().to_string(), value);
+        rest = after[end + 1..].trim_start_matches(',');
+    }
+    Some(labels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = r#"# HELP agentos_tool_calls_total Tool calls.
+# TYPE agentos_tool_calls_total counter
+agentos_tool_calls_total{tool="grep",status="ok"} 3
+agentos_tool_calls_total{tool="grep",status="error"} 1
+agentos_tool_calls_total{tool="bash",status="ok"} 2
+# TYPE agentos_tool_call_duration_seconds summary
+agentos_tool_call_duration_seconds{tool="grep",status="ok",quantile="0.5"} 0.01
+agentos_tool_call_duration_seconds_sum{tool="grep",status="ok"} 0.3
+agentos_tool_call_duration_seconds_count{tool="grep",status="ok"} 3
+agentos_tool_call_duration_seconds_sum{tool="grep",status="error"} 0.1
+agentos_tool_call_duration_seconds_count{tool="grep",status="error"} 1
+agentos_odd{path="a \"quoted\", comma\\slash"} 7
+agentos_plain 1.5
+garbage line without value{
+"#;
+
+    #[test]
+    fn parses_labels_and_values() {
+        let snap = MetricsSnapshot::parse(TEXT);
+        assert_eq!(snap.sum("agentos_tool_calls_total", &[]), 6.0);
+        assert_eq!(snap.sum("agentos_tool_calls_total", &[("tool", "grep")]), 4.0);
+        assert_eq!(
+            snap.sum("agentos_tool_calls_total", &[("tool", "grep"), ("status", "error")]),
+            1.0
+        );
+        assert_eq!(snap.sum("agentos_plain", &[]), 1.5);
+        assert_eq!(
+            snap.label_values("agentos_tool_calls_total", "tool"),
+            vec!["bash", "grep"]
+        );
+    }
+
+    #[test]
+    fn unescapes_label_values() {
+        let snap = MetricsSnapshot::parse(TEXT);
+        assert_eq!(
+            snap.sum("agentos_odd", &[("path", "a \"quoted\", comma\\slash")]),
+            7.0
+        );
+    }
+
+    #[test]
+    fn histogram_mean_and_count_span_series() {
+        let snap = MetricsSnapshot::parse(TEXT);
+        let grep = [("tool", "grep")];
+        assert_eq!(snap.count("agentos_tool_call_duration_seconds", &grep), 4);
+        let mean = snap.mean("agentos_tool_call_duration_seconds", &grep).unwrap();
+        assert!((mean - 0.1).abs() < 1e-9);
+        assert_eq!(
+            snap.mean("agentos_tool_call_duration_seconds", &[("tool", "bash")]),
+            None
+        );
+    }
+
+    #[test]
+    fn empty_text_is_empty_snapshot() {
+        assert!(MetricsSnapshot::parse("").is_empty());
+    }
+}
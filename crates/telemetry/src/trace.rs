@@ -0,0 +1,370 @@
+//! Distributed tracing — one trace per user request, end to end.
+//!
+//! The pipeline hands envelopes between handlers over channels, so the
+//! ambient `tracing` span does not follow a message from one listener to
+//! the next. Instead each request's span is registered against its
+//! thread id, and whichever handler picks up an envelope on that thread
+//! parents its span under it:
+//!
+//! - [`start_request`] at the ingress (TUI injection, platform delivery)
+//! - [`continue_thread`] when work forks onto a new thread (the
+//!   `dispatch` tool, buffer child pipelines)
+//! - [`dispatch_span`] per envelope handed to a listener
+//! - [`finish`] when the listener the thread was opened for replies
+//!
+//! Spans below a dispatch (agent turn, LLM call, tool execution) are
+//! ordinary contextual `tracing` spans. None of it leaves the process
+//! unless a frontend adds `layer` (behind the `export` feature) to its
+//! subscriber: OTLP/HTTP to a collector, or one JSON object per span to
+//! a local file.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+#[cfg(feature = "export")]
+use opentelemetry::trace::TracerProvider as _;
+#[cfg(feature = "export")]
+use opentelemetry_otlp::WithExportConfig;
+#[cfg(feature = "export")]
+use opentelemetry_sdk::trace::SdkTracerProvider;
+#[cfg(feature = "export")]
+use opentelemetry_sdk::Resource;
+use tracing::Span;
+#[cfg(feature = "export")]
+use tracing_subscriber::filter::filter_fn;
+#[cfg(feature = "export")]
+use tracing_subscriber::registry::LookupSpan;
+#[cfg(feature = "export")]
+use tracing_subscriber::Layer;
+
+#[cfg(feature = "export")]
+use crate::jsonl::JsonLinesExporter;
+
+/// Env var naming a JSON-lines trace file. Takes precedence over OTLP.
+pub const TRACE_FILE_ENV: &str = "AGENTOS_TRACE_FILE";
+/// The standard OTLP collector base URL (`http://host:4318`).
+pub const OTLP_ENDPOINT_ENV: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+/// Where finished spans go.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceExport {
+    /// OTLP over HTTP/protobuf. `endpoint` is the collector base URL;
+    /// `/v1/traces` is appended.
+    Otlp { endpoint: String },
+    /// One JSON object per finished span, appended to `path`.
+    JsonLines { path: PathBuf },
+}
+
+impl TraceExport {
+    /// Read the export target from the environment. `None` when neither
+    /// [`TRACE_FILE_ENV`] nor [`OTLP_ENDPOINT_ENV`] is set — tracing
+    /// then stays in-process.
+    pub fn from_env() -> Option<Self> {
+        let non_empty = |key| std::env::var(key).ok().filter(|v| !v.trim().is_empty());
+        if let Some(path) = non_empty(TRACE_FILE_ENV) {
+            return Some(Self::JsonLines { path: path.into() });
+        }
+        non_empty(OTLP_ENDPOINT_ENV).map(|endpoint| Self::Otlp { endpoint })
+    }
+}
+
+/// Keeps the tracer provider alive. Dropping it flushes buffered spans
+/// and shuts the exporter down, so hold it until the process exits.
+#[cfg(feature = "export")]
+pub struct TraceGuard {
+    provider: SdkTracerProvider,
+}
+
+#[cfg(feature = "export")]
+impl Drop for TraceGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.provider.shutdown() {
+            tracing::warn!(error = %e, "trace exporter shutdown failed");
+        }
+    }
+}
+
+/// Build the OpenTelemetry layer for `export`.
+///
+/// Only spans from AgentOS crates are exported — dependency spans
+/// (HTTP clients, the exporter's own transport) would otherwise feed
+/// back into the exporter. Log events stay with the fmt layer.
+#[cfg(feature = "export")]
+pub fn layer<S>(
+    export: &TraceExport,
+    service_name: &'static str,
+) -> Result<(impl Layer<S>, TraceGuard), String>
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+{
+    let resource = Resource::builder().with_service_name(service_name).build();
+    let builder = SdkTracerProvider::builder().with_resource(resource);
+    let provider = match export {
+        TraceExport::Otlp { endpoint } => {
+            let exporter = opentelemetry_otlp::SpanExporter::builder()
+                .with_http()
+                .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+                .build()
+                .map_err(|e| format!("OTLP exporter for {endpoint}: {e}"))?;
+            builder.with_batch_exporter(exporter).build()
+        }
+        // Simple (synchronous) export: a line lands as soon as its span
+        // closes, so `tail -f` follows a run live.
+        TraceExport::JsonLines { path } => builder
+            .with_simple_exporter(JsonLinesExporter::open(path)?)
+            .build(),
+    };
+    let tracer = provider.tracer("agentos");
+    let layer = tracing_opentelemetry::layer()
+        .with_tracer(tracer)
+        .with_filter(filter_fn(|meta| {
+            meta.is_span() && meta.target().starts_with("agentos")
+        }));
+    Ok((layer, TraceGuard { provider }))
+}
+
+// ── Thread → span registry ──
+
+struct ThreadTrace {
+    listener: String,
+    profile: String,
+    span: Span,
+}
+
+fn threads() -> &'static Mutex<HashMap<String, ThreadTrace>> {
+    static THREADS: OnceLock<Mutex<HashMap<String, ThreadTrace>>> = OnceLock::new();
+    THREADS.get_or_init(Default::default)
+}
+
+fn register(thread_id: &str, listener: &str, profile: &str, span: Span) {
+    threads()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(
+            thread_id.to_string(),
+            ThreadTrace {
+                listener: listener.to_string(),
+                profile: profile.to_string(),
+                span,
+            },
+        );
+}
+
+/// Open the root span for a request entering on `thread_id`, addressed
+/// to `listener`. Parents under the caller's current span if there is
+/// one (an HTTP request, a trigger fire), otherwise starts a new trace.
+/// A later request on the same thread replaces this one.
+pub fn start_request(thread_id: &str, listener: &str, profile: &str) -> Span {
+    let span = tracing::info_span!("request", thread_id, listener, profile);
+    register(thread_id, listener, profile, span.clone());
+    span
+}
+
+/// Register `thread_id` as a continuation of `parent`'s trace — used
+/// when a handler forks work onto a thread of its own.
+pub fn continue_thread(thread_id: &str, listener: &str, profile: &str, parent: &Span) -> Span {
+    let span = tracing::info_span!(parent: parent, "thread", thread_id, listener, profile);
+    register(thread_id, listener, profile, span.clone());
+    span
+}
+
+/// Span for one envelope handed to `listener` on `thread_id`. Child of
+/// the thread's request span; a fresh root if the thread was never
+/// registered (raw injections).
+pub fn dispatch_span(thread_id: &str, listener: &str, from: &str) -> Span {
+    let threads = threads().lock().unwrap_or_else(|e| e.into_inner());
+    match threads.get(thread_id) {
+        Some(t) => tracing::info_span!(
+            parent: &t.span,
+            "dispatch",
+            thread_id,
+            listener,
+            from,
+            profile = %t.profile,
+            otel.status_code = tracing::field::Empty,
+        ),
+        None => tracing::info_span!(
+            parent: None,
+            "dispatch",
+            thread_id,
+            listener,
+            from,
+            profile = tracing::field::Empty,
+            otel.status_code = tracing::field::Empty,
+        ),
+    }
+}
+
+/// Close the thread's request span if `listener` is the one it was
+/// opened for. Replies from anyone else on the thread (tools, agents
+/// called along the way) leave it open.
+pub fn finish(thread_id: &str, listener: &str) {
+    let mut threads = threads().lock().unwrap_or_else(|e| e.into_inner());
+    if threads.get(thread_id).is_some_and(|t| t.listener == listener) {
+        threads.remove(thread_id);
+    }
+}
+
+/// Set the OpenTelemetry status on a span that declared
+/// `otel.status_code = Empty`.
+pub fn record_outcome(span: &Span, ok: bool) {
+    span.record("otel.status_code", if ok { "ok" } else { "error" });
+}
+
+#[cfg(all(test, feature = "export"))]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    fn run_request(export: &TraceExport, thread_id: &str) {
+        let registry = tracing_subscriber::registry();
+        let (layer, guard) = layer(export, "agentos-test").unwrap();
+        tracing::subscriber::with_default(registry.with(layer), || {
+            let request = start_request(thread_id, "coding-agent", "admin");
+            let turn = dispatch_span(thread_id, "coding-agent", "user");
+            turn.in_scope(|| {
+                let _llm = tracing::info_span!("llm.call", model = "opus").entered();
+            });
+            drop(turn);
+
+            // The agent forks a child thread; its work joins the same trace.
+            let child_id = format!("{thread_id}-child");
+            continue_thread(&child_id, "helper", "admin", &request);
+            let tool = dispatch_span(&child_id, "file-read", "helper");
+            record_outcome(&tool, false);
+            drop(tool);
+            finish(&child_id, "helper");
+
+            finish(thread_id, "file-read"); // not the request's listener
+            assert!(threads().lock().unwrap().contains_key(thread_id));
+            finish(thread_id, "coding-agent");
+            drop(request);
+        });
+        drop(guard);
+    }
+
+    #[test]
+    fn json_lines_export_is_one_trace_per_request() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("traces.jsonl");
+        run_request(&TraceExport::JsonLines { path: path.clone() }, "jsonl-thread");
+
+        let spans: Vec<serde_json::Value> = BufReader::new(std::fs::File::open(&path).unwrap())
+            .lines()
+            .map(|l| serde_json::from_str(&l.unwrap()).unwrap())
+            .collect();
+        let names: Vec<&str> = spans.iter().map(|s| s["name"].as_str().unwrap()).collect();
+        for expected in ["request", "dispatch", "llm.call", "thread"] {
+            assert!(names.contains(&expected), "missing {expected} in {names:?}");
+        }
+
+        let trace_ids: std::collections::HashSet<&str> =
+            spans.iter().map(|s| s["trace_id"].as_str().unwrap()).collect();
+        assert_eq!(trace_ids.len(), 1, "spans split across traces: {spans:?}");
+
+        let by_name = |name: &str, listener: &str| {
+            spans
+                .iter()
+                .find(|s| s["name"] == name && s["attributes"]["listener"] == listener)
+                .unwrap()
+        };
+        let request = by_name("request", "coding-agent");
+        assert_eq!(request["parent_span_id"], serde_json::Value::Null);
+        assert_eq!(request["attributes"]["thread_id"], "jsonl-thread");
+        assert_eq!(request["attributes"]["profile"], "admin");
+
+        let thread = by_name("thread", "helper");
+        assert_eq!(thread["parent_span_id"], request["span_id"]);
+        let tool = by_name("dispatch", "file-read");
+        assert_eq!(tool["parent_span_id"], thread["span_id"]);
+        assert_eq!(tool["status"], "error");
+    }
+
+    #[test]
+    fn unregistered_thread_dispatch_is_a_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("traces.jsonl");
+        let export = TraceExport::JsonLines { path: path.clone() };
+        let (layer, guard) = layer(&export, "agentos-test").unwrap();
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            drop(dispatch_span("never-registered", "echo", "test"));
+        });
+        drop(guard);
+
+        let line = std::fs::read_to_string(&path).unwrap();
+        let span: serde_json::Value = serde_json::from_str(line.trim()).unwrap();
+        assert_eq!(span["name"], "dispatch");
+        assert_eq!(span["parent_span_id"], serde_json::Value::Null);
+    }
+
+    /// Minimal OTLP/HTTP collector: accepts one POST, answers 200, and
+    /// hands back the request line and body.
+    fn collector_stub() -> (String, std::sync::mpsc::Receiver<(String, Vec<u8>)>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((k, v)) = header.split_once(':') {
+                    if k.eq_ignore_ascii_case("content-length") {
+                        content_length = v.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .unwrap();
+            tx.send((request_line, body)).unwrap();
+        });
+        (endpoint, rx)
+    }
+
+    #[test]
+    fn otlp_export_reaches_collector() {
+        let (endpoint, rx) = collector_stub();
+        run_request(&TraceExport::Otlp { endpoint }, "otlp-thread");
+
+        let (request_line, body) = rx
+            .recv_timeout(std::time::Duration::from_secs(10))
+            .expect("collector stub received nothing");
+        assert!(request_line.starts_with("POST /v1/traces "), "{request_line}");
+        // Protobuf carries strings verbatim — enough to see the spans arrived.
+        let body = String::from_utf8_lossy(&body);
+        for needle in ["request", "dispatch", "llm.call", "otlp-thread", "agentos-test"] {
+            assert!(body.contains(needle), "missing {needle}");
+        }
+    }
+
+    #[test]
+    fn from_env_prefers_trace_file() {
+        // Env is process-global; this is the only test that touches it.
+        std::env::set_var(OTLP_ENDPOINT_ENV, "http://collector:4318");
+        std::env::set_var(TRACE_FILE_ENV, "/tmp/t.jsonl");
+        assert_eq!(
+            TraceExport::from_env(),
+            Some(TraceExport::JsonLines { path: "/tmp/t.jsonl".into() })
+        );
+        std::env::remove_var(TRACE_FILE_ENV);
+        assert_eq!(
+            TraceExport::from_env(),
+            Some(TraceExport::Otlp { endpoint: "http://collector:4318".into() })
+        );
+        std::env::remove_var(OTLP_ENDPOINT_ENV);
+        assert_eq!(TraceExport::from_env(), None);
+    }
+}
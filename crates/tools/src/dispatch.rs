@@ -195,6 +195,14 @@ impl Handler for DispatchTool {
         // this thread sees the chain depth.
         self.depths.lock().await.insert(thread_id.clone(), new_depth);
 
+        // The new thread's work stays in the caller's trace.
+        agentos_telemetry::trace::continue_thread(
+            &thread_id,
+            &target,
+            &profile,
+            &tracing::Span::current(),
+        );
+
         // Build the task envelope
         let escaped_task = super::xml_escape(&task);
         let xml = format!(
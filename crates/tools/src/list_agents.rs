@@ -121,6 +121,7 @@ mod tests {
             wasm: None,
             python: None,
             trigger: None,
+            shim_trainer: None,
             semantic_description: None,
             agent_config: None,
             buffer: None,
@@ -139,6 +140,7 @@ mod tests {
             wasm: None,
             python: None,
             trigger: None,
+            shim_trainer: None,
             semantic_description: None,
             agent_config: None,
             buffer: Some(BufferConfig {
@@ -1,8 +1,8 @@
 //! Model verification tool — checks if a model is compatible with the system.
 //!
 //! Given a HuggingFace model ID and filename, checks:
-//! - Architecture support (llama, bitnet, qwen2, phi, etc.)
-//! - Quantization type support (TQ1_0, TQ2_0, Q4_K_M, Q8_0, F16, F32, etc.)
+//! - Architecture support (llama, bitnet, qwen2)
+//! - Quantization type support (TQ1_0, TQ2_0, Q4_K_M, Q8_0, Q4_0, F16, F32, etc.)
 //! - Size vs available RAM
 //! - Required disk space
 //!
@@ -65,16 +65,17 @@ fn detect_available_ram() -> u64 {
     }
 }
 
-/// Architectures our engine currently supports.
+/// Architectures our engine currently supports: the LLaMA-layout
+/// transformers (separate Q/K/V, SwiGLU, RMSNorm) the loader can build.
 const SUPPORTED_ARCHITECTURES: &[&str] = &[
     "llama",
     "bitnet",
     "qwen2",
-    "phi",
-    "falcon",
 ];
 
-/// Quantization types our engine supports.
+/// Quantization types our engine can load — the ones `agentos-bitnet`'s
+/// GGUF loader has dequantization kernels for (Q4_K_M mixes Q4_K and Q6_K
+/// tensors).
 const SUPPORTED_QUANT_TYPES: &[&str] = &[
     "TQ1_0",  // base-3 packed ternary
     "TQ2_0",  // 2-bit packed ternary
@@ -82,15 +83,24 @@ const SUPPORTED_QUANT_TYPES: &[&str] = &[
     "F16",
     "BF16",
     "Q4_K_M",
-    "Q4_K_S",
+    "Q8_0",
+    "Q6_K",
+    "Q4_0",
+];
+
+/// Quantization types we recognize in filenames but can't load yet.
+/// Listed most-specific first so e.g. "Q4_K_S" isn't matched as a prefix.
+const UNSUPPORTED_QUANT_TYPES: &[&str] = &[
+    "Q4_K_S", // uses Q5_K for some attention tensors
     "Q5_K_M",
     "Q5_K_S",
-    "Q8_0",
-    "Q2_K",
     "Q3_K_M",
     "Q3_K_S",
     "Q3_K_L",
-    "Q6_K",
+    "Q2_K",
+    "Q5_0",
+    "Q4_1",
+    "Q5_1",
 ];
 
 /// Guess architecture from model ID or filename.
@@ -105,9 +115,12 @@ fn guess_architecture(model_id: &str, filename: &str) -> Option<String> {
     if combined.contains("codellama") || combined.contains("llama") || combined.contains("tinyllama") {
         return Some("llama".into());
     }
-    if combined.contains("mistral") || combined.contains("mixtral") {
+    if combined.contains("mistral") {
         return Some("llama".into()); // Mistral uses llama architecture
     }
+    if combined.contains("falcon3") {
+        return Some("llama".into()); // Falcon3 GGUFs use llama architecture
+    }
     None
 }
 
@@ -119,14 +132,10 @@ fn guess_quant_type(filename: &str) -> Option<String> {
             return Some(qt.to_string());
         }
     }
-    // Try common patterns
-    if upper.contains("Q4_0") {
-        return Some("Q4_0".into());
-    }
-    if upper.contains("Q5_0") {
-        return Some("Q5_0".into());
-    }
-    None
+    UNSUPPORTED_QUANT_TYPES
+        .iter()
+        .find(|qt| upper.contains(*qt))
+        .map(|qt| qt.to_string())
 }
 
 /// Format bytes as human-readable.
@@ -373,6 +382,14 @@ mod tests {
         assert_eq!(guess_quant_type("model-Q8_0.gguf"), Some("Q8_0".into()));
     }
 
+    #[test]
+    fn guess_quant_unsupported() {
+        assert_eq!(guess_quant_type("model-Q5_K_M.gguf"), Some("Q5_K_M".into()));
+        assert_eq!(guess_quant_type("model-Q4_K_S.gguf"), Some("Q4_K_S".into()));
+        assert!(!SUPPORTED_QUANT_TYPES.contains(&"Q5_K_M"));
+        assert!(SUPPORTED_QUANT_TYPES.contains(&"Q4_0"));
+    }
+
     #[test]
     fn guess_quant_none() {
         assert_eq!(guess_quant_type("model.gguf"), None);
@@ -15,7 +15,7 @@ use std::time::Duration;
 use async_trait::async_trait;
 use rust_pipeline::prelude::*;
 
-use super::vdrive_tools::DriveSlot;
+use super::vdrive_tools::{exec_refusal, DriveSlot};
 use super::{extract_tag, ToolPeer, ToolResponse};
 
 /// Maximum output size before truncation.
@@ -69,8 +69,13 @@ macro_rules! require_drive {
 
 #[async_trait]
 impl Handler for SafeCommandTool {
-    async fn handle(&self, payload: ValidatedPayload, _ctx: HandlerContext) -> HandlerResult {
-        let drive = require_drive!(self.slot);
+    async fn handle(&self, payload: ValidatedPayload, ctx: HandlerContext) -> HandlerResult {
+        let drive = require_drive!(self.slot).for_agent(&ctx.from);
+        if let Some(reason) = exec_refusal(&drive) {
+            return Ok(HandlerResponse::Reply {
+                payload_xml: ToolResponse::err(reason),
+            });
+        }
         let xml_str = String::from_utf8_lossy(&payload.xml);
 
         // Build the command: executable + fixed_args + optional user args
@@ -370,6 +375,32 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn overlay_drive_refuses_commands() {
+        let lower = tempfile::tempdir().unwrap();
+        let upper = tempfile::tempdir().unwrap();
+        let drive = agentos_vdrive::VDrive::open_overlay(lower.path(), upper.path()).unwrap();
+        let slot = crate::vdrive_tools::empty_slot();
+        *slot.write().await = Some(std::sync::Arc::new(drive));
+        let tool = SafeCommandTool::new(&CARGO_CHECK, slot);
+        let payload = ValidatedPayload {
+            xml: b"<CargoCheckRequest></CargoCheckRequest>".to_vec(),
+            tag: "CargoCheckRequest".into(),
+        };
+        let ctx = HandlerContext {
+            thread_id: "t1".into(),
+            from: "agent".into(),
+            own_name: "cargo-check".into(),
+        };
+        match tool.handle(payload, ctx).await.unwrap() {
+            HandlerResponse::Reply { payload_xml } => {
+                let xml = String::from_utf8(payload_xml).unwrap();
+                assert!(xml.contains("disabled on overlay mounts"), "got: {xml}");
+            }
+            _ => panic!("expected Reply"),
+        }
+    }
+
     #[test]
     fn truncate_output_short() {
         let s = "hello";
@@ -16,12 +16,23 @@
 //! | `add-shim`          | Register a trained shim (manifest + ONNX path) in a store |
 //! | `retire-shim`       | Soft-retire a shim (move to `<store>/retired/`)      |
 //! | `list-shims`        | List shims in one store                              |
+//! | `stats`             | Runtime stats, composition history, running canary   |
+//! | `eval`              | Precision/recall of a candidate vs. the current composition on labeled JSONL |
+//! | `start-canary`      | Serve a candidate composition to a share of threads  |
+//! | `promote-canary`    | Make the running canary the composition              |
+//! | `abort-canary`      | Stop the running canary                              |
+//! | `rollback`          | Restore the previous composition                     |
+//!
+//! Agents naming a store read its composition (and canary) from the
+//! kernel on every call, so composition changes apply without restart.
 //!
 //! The tool follows the dispatch.rs pattern for kernel access: holds a
 //! `ShimStoreHandles` populated post-pipeline-build via `connect()`.
 
 use std::sync::Arc;
 
+use agentos_cortex_shim::eval::{self, EvalTarget};
+use agentos_cortex_shim::train::Dataset;
 use agentos_kernel::Kernel;
 use agentos_llm::types::ShimAttachment;
 use async_trait::async_trait;
@@ -153,12 +164,7 @@ impl ShimStoreTool {
     async fn handle_update_composition(&self, xml_str: &str) -> Result<String, String> {
         let name = required_tag(xml_str, "name")?;
         let composition_raw = required_tag(xml_str, "composition")?;
-        // Validate against the live ShimAttachment schema before writing.
-        // A typo'd update should not silently brick the agent on next
-        // restart; we catch it here while the agent is still running.
-        let _validated: ShimAttachment = serde_json::from_str(&composition_raw).map_err(|e| {
-            format!("composition does not match ShimAttachment schema: {e}")
-        })?;
+        validate_composition(&composition_raw)?;
         let kernel = self.kernel().await?;
         let mut k = kernel.lock().await;
         if !k.shim_store().exists(&name) {
@@ -168,7 +174,7 @@ impl ShimStoreTool {
             .map_err(|e| format!("update_composition: {e}"))?;
         Ok(json!({
             "store": name,
-            "note": "agents using this store must restart for changes to take effect"
+            "composition_hash": k.shim_store().composition_hash_for(&name),
         })
         .to_string())
     }
@@ -233,6 +239,147 @@ impl ShimStoreTool {
         Ok(serde_json::to_string(&ids)
             .map_err(|e| format!("serialize: {e}"))?)
     }
+
+    async fn handle_stats(&self, xml_str: &str) -> Result<String, String> {
+        let name = required_tag(xml_str, "name")?;
+        let kernel = self.kernel().await?;
+        let k = kernel.lock().await;
+        let store = k.shim_store();
+        let activation = store
+            .activation_for(&name)
+            .ok_or_else(|| format!("shim_store `{name}` does not exist"))?;
+        // Rates alongside the raw totals so the caller needn't divide.
+        let shims: serde_json::Map<String, serde_json::Value> = activation
+            .shims
+            .iter()
+            .map(|(id, s)| {
+                let entry = json!({
+                    "totals": s,
+                    "fire_rate": s.fire_rate(),
+                    "mean_decision": s.mean_decision(),
+                    "mean_generation_ms": s.latency.mean_generation_ms(),
+                });
+                (id.clone(), entry)
+            })
+            .collect();
+        let compositions: serde_json::Map<String, serde_json::Value> = activation
+            .compositions
+            .iter()
+            .map(|(hash, c)| {
+                let entry = json!({
+                    "totals": c,
+                    "silence_rate": c.silence_rate(),
+                    "mean_prefill_ms": c.latency.mean_prefill_ms(),
+                    "mean_generation_ms": c.latency.mean_generation_ms(),
+                });
+                (hash.clone(), entry)
+            })
+            .collect();
+        let canary = store.canary_for(&name).map(|c| {
+            json!({"composition_hash": c.composition_hash, "fraction": c.fraction})
+        });
+        Ok(json!({
+            "store": name,
+            "composition_hash": store.composition_hash_for(&name),
+            "history": store.composition_history_for(&name),
+            "canary": canary,
+            "shims": shims,
+            "compositions": compositions,
+        })
+        .to_string())
+    }
+
+    async fn handle_eval(&self, xml_str: &str) -> Result<String, String> {
+        let name = required_tag(xml_str, "name")?;
+        let data_path = required_tag(xml_str, "data")?;
+        let target: EvalTarget = extract_tag(xml_str, "target")
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "respond".into())
+            .parse()
+            .map_err(|e| format!("{e}"))?;
+        // Labeled data comes through the workspace sandbox, like add-shim's ONNX.
+        let drive = {
+            let guard = self.slot.read().await;
+            guard
+                .clone()
+                .ok_or_else(|| "no workspace mounted; mount before eval".to_string())?
+        };
+        let text = drive
+            .read_bytes(&data_path)
+            .map_err(|e| format!("read eval data `{data_path}`: {e}"))?;
+        let data = Dataset::parse_jsonl(&String::from_utf8_lossy(&text)).map_err(|e| format!("{e}"))?;
+
+        let kernel = self.kernel().await?;
+        let k = kernel.lock().await;
+        let store = k.shim_store();
+        // Candidate defaults to the running canary's composition.
+        let candidate = match extract_tag(xml_str, "composition").filter(|s| !s.is_empty()) {
+            Some(raw) => {
+                validate_composition(&raw)?;
+                raw.into_bytes()
+            }
+            None => store
+                .canary_for(&name)
+                .map(|c| c.composition_bytes.clone())
+                .ok_or_else(|| "missing required <composition> (no canary is running)".to_string())?,
+        };
+        let comparison = eval::compare(store, &name, &candidate, &data, &target).map_err(|e| format!("eval: {e}"))?;
+        serde_json::to_string(&comparison).map_err(|e| format!("serialize: {e}"))
+    }
+
+    async fn handle_start_canary(&self, xml_str: &str) -> Result<String, String> {
+        let name = required_tag(xml_str, "name")?;
+        let composition_raw = required_tag(xml_str, "composition")?;
+        let fraction: f32 = required_tag(xml_str, "fraction")?
+            .trim()
+            .parse()
+            .map_err(|e| format!("<fraction> must be a number in (0, 1]: {e}"))?;
+        validate_composition(&composition_raw)?;
+        let kernel = self.kernel().await?;
+        let mut k = kernel.lock().await;
+        k.start_shim_canary(&name, composition_raw.into_bytes(), fraction)
+            .map_err(|e| format!("start_shim_canary: {e}"))?;
+        let hash = k.shim_store().canary_for(&name).map(|c| c.composition_hash.clone());
+        Ok(json!({"store": name, "canary_hash": hash, "fraction": fraction}).to_string())
+    }
+
+    async fn handle_promote_canary(&self, xml_str: &str) -> Result<String, String> {
+        let name = required_tag(xml_str, "name")?;
+        let kernel = self.kernel().await?;
+        let mut k = kernel.lock().await;
+        let hash = k
+            .promote_shim_canary(&name)
+            .map_err(|e| format!("promote_shim_canary: {e}"))?;
+        Ok(json!({"store": name, "composition_hash": hash}).to_string())
+    }
+
+    async fn handle_abort_canary(&self, xml_str: &str) -> Result<String, String> {
+        let name = required_tag(xml_str, "name")?;
+        let kernel = self.kernel().await?;
+        let mut k = kernel.lock().await;
+        k.abort_shim_canary(&name)
+            .map_err(|e| format!("abort_shim_canary: {e}"))?;
+        Ok(json!({"store": name, "aborted": true}).to_string())
+    }
+
+    async fn handle_rollback(&self, xml_str: &str) -> Result<String, String> {
+        let name = required_tag(xml_str, "name")?;
+        let kernel = self.kernel().await?;
+        let mut k = kernel.lock().await;
+        let hash = k
+            .rollback_composition(&name)
+            .map_err(|e| format!("rollback_composition: {e}"))?;
+        Ok(json!({"store": name, "composition_hash": hash}).to_string())
+    }
+}
+
+/// Validate against the live ShimAttachment schema before writing. A
+/// typo'd composition should not silently brick the agent; we catch it
+/// here while the agent is still running.
+fn validate_composition(raw: &str) -> Result<(), String> {
+    serde_json::from_str::<ShimAttachment>(raw)
+        .map(|_| ())
+        .map_err(|e| format!("composition does not match ShimAttachment schema: {e}"))
 }
 
 fn required_tag(xml_str: &str, name: &str) -> Result<String, String> {
@@ -256,11 +403,18 @@ impl Handler for ShimStoreTool {
             "add-shim" => self.handle_add_shim(&xml_str).await,
             "retire-shim" => self.handle_retire_shim(&xml_str).await,
             "list-shims" => self.handle_list_shims(&xml_str).await,
+            "stats" => self.handle_stats(&xml_str).await,
+            "eval" => self.handle_eval(&xml_str).await,
+            "start-canary" => self.handle_start_canary(&xml_str).await,
+            "promote-canary" => self.handle_promote_canary(&xml_str).await,
+            "abort-canary" => self.handle_abort_canary(&xml_str).await,
+            "rollback" => self.handle_rollback(&xml_str).await,
             "" => Err("missing required <action>".into()),
             other => Err(format!(
                 "unknown action: {other} (allowed: create-store|delete-store|\
                  list-stores|read-composition|update-composition|add-shim|\
-                 retire-shim|list-shims)"
+                 retire-shim|list-shims|stats|eval|start-canary|\
+                 promote-canary|abort-canary|rollback)"
             )),
         };
 
@@ -282,20 +436,31 @@ impl ToolPeer for ShimStoreTool {
         r#"
 /// Manage cortex shim_stores via the kernel's fourth pillar. Each
 /// store is a named directory of ONNX shim weights + composition rules
-/// + per-shim metadata. Updates require an agent restart to take
-/// effect (v1).
+/// + per-shim metadata. Composition changes reach agents using the
+/// store on their next LLM call.
 interface shim-store {
     record request {
         /// create-store | delete-store | list-stores |
         /// read-composition | update-composition |
-        /// add-shim | retire-shim | list-shims
+        /// add-shim | retire-shim | list-shims |
+        /// stats | eval | start-canary | promote-canary |
+        /// abort-canary | rollback
         action: string,
         /// Store name (required for most actions).
         name: option<string>,
         /// Comma-separated base-model names (create-store only).
         base-compat: option<string>,
-        /// JSON-serialized ShimAttachment (update-composition).
+        /// JSON-serialized ShimAttachment (update-composition,
+        /// start-canary; eval's candidate, default the running canary).
         composition: option<string>,
+        /// Share of threads served by the canary, in (0, 1] (start-canary).
+        fraction: option<string>,
+        /// Labeled JSONL path in the workspace (eval).
+        data: option<string>,
+        /// respond | silent | signal:<name> | steer:<id> | gate:<id>
+        /// — the outcome that counts as predicting a positive label
+        /// (eval; default respond).
+        target: option<string>,
         /// Target store (add-shim, retire-shim, list-shims).
         store: option<string>,
         /// Shim id (add-shim, retire-shim).
@@ -586,4 +751,101 @@ mod tests {
         assert!(!ok);
         assert!(msg.contains("no workspace mounted"), "got: {msg}");
     }
+
+    async fn call(tool: &ShimStoreTool, xml: &str) -> (bool, String) {
+        parse(tool.handle(make_payload(xml), make_ctx()).await.unwrap())
+    }
+
+    fn composition_xml(action: &str, composition: &str, extra: &str) -> String {
+        format!(
+            "<ShimStore><action>{action}</action><name>bob</name><composition>{}</composition>{extra}</ShimStore>",
+            agentos_events::xml_escape(composition),
+        )
+    }
+
+    #[tokio::test]
+    async fn rollback_restores_previous_composition() {
+        let (_dir, tool) = fresh_tool();
+        call(&tool, "<ShimStore><action>create-store</action><name>bob</name></ShimStore>").await;
+        for gate in ["v1", "v2"] {
+            let composition = format!(r#"{{"gate_shims":["{gate}"]}}"#);
+            let (ok, body) = call(&tool, &composition_xml("update-composition", &composition, "")).await;
+            assert!(ok, "{body}");
+        }
+
+        let (ok, body) = call(&tool, "<ShimStore><action>rollback</action><name>bob</name></ShimStore>").await;
+        assert!(ok, "{body}");
+        let (_, body) = call(&tool, "<ShimStore><action>read-composition</action><name>bob</name></ShimStore>").await;
+        let parsed: ShimAttachment = serde_json::from_str(&body).unwrap();
+        assert_eq!(parsed.gate_shims, vec!["v1"]);
+
+        let (ok, body) = call(&tool, "<ShimStore><action>stats</action><name>bob</name></ShimStore>").await;
+        assert!(ok, "{body}");
+        let stats: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(stats["history"].as_array().unwrap().len(), 2);
+        assert!(stats["canary"].is_null());
+    }
+
+    #[tokio::test]
+    async fn canary_eval_then_promote() {
+        use agentos_cortex_shim::{DenseLayer, ShimFfn};
+
+        let (dir, tool) = fresh_tool();
+        call(&tool, "<ShimStore><action>create-store</action><name>bob</name></ShimStore>").await;
+
+        // A scalar gate on 2-d vectors: logit = 4 * x[0].
+        let ffn = ShimFfn::new(vec![DenseLayer::new(vec![4.0, 0.0], vec![0.0], 2, 1).unwrap()]).unwrap();
+        std::fs::write(dir.path().join("gate.onnx"), ffn.to_onnx()).unwrap();
+        let manifest = json!({
+            "id": "g",
+            "version": "1",
+            "phase": "gate",
+            "attachment": {"layer": "final", "pooling": "mean"},
+            "input_shape": {"hidden_dim": 2},
+            "output_shape": {"kind": "scalar"},
+        });
+        let xml = format!(
+            "<ShimStore><action>add-shim</action><store>bob</store><shim_id>g</shim_id>\
+             <manifest>{}</manifest><onnx_path>gate.onnx</onnx_path></ShimStore>",
+            agentos_events::xml_escape(&manifest.to_string()),
+        );
+        let (ok, body) = call(&tool, &xml).await;
+        assert!(ok, "{body}");
+
+        let silence_below = |gt: f32| {
+            json!({
+                "gate_shims": ["g"],
+                "shim_rules": [{"if": {"gate": "g", "gt": gt}, "then": {}}, {"else": {"silent": true}}],
+            })
+            .to_string()
+        };
+        let (ok, body) = call(&tool, &composition_xml("update-composition", &silence_below(0.5), "")).await;
+        assert!(ok, "{body}");
+        let xml = composition_xml("start-canary", &silence_below(0.2), "<fraction>0.5</fraction>");
+        let (ok, body) = call(&tool, &xml).await;
+        assert!(ok, "{body}");
+
+        let rows = [(-1.0, 0), (-0.3, 0), (-0.2, 1), (0.1, 1), (1.0, 1)];
+        let jsonl: String = rows
+            .iter()
+            .map(|(x, l)| format!("{{\"vector\":[{x},0.0],\"label\":{l}}}\n"))
+            .collect();
+        std::fs::write(dir.path().join("eval.jsonl"), jsonl).unwrap();
+        let xml = "<ShimStore><action>eval</action><name>bob</name><data>eval.jsonl</data></ShimStore>";
+        let (ok, body) = call(&tool, xml).await;
+        assert!(ok, "{body}");
+        let report: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(report["current"]["recall"], 2.0 / 3.0);
+        assert_eq!(report["candidate"]["recall"], 1.0);
+        assert_eq!(report["candidate"]["precision"], 0.75);
+
+        let (ok, body) = call(&tool, "<ShimStore><action>promote-canary</action><name>bob</name></ShimStore>").await;
+        assert!(ok, "{body}");
+        let (_, body) = call(&tool, "<ShimStore><action>read-composition</action><name>bob</name></ShimStore>").await;
+        let promoted: ShimAttachment = serde_json::from_str(&body).unwrap();
+        assert_eq!(promoted, serde_json::from_str::<ShimAttachment>(&silence_below(0.2)).unwrap());
+        let (ok, msg) = call(&tool, "<ShimStore><action>abort-canary</action><name>bob</name></ShimStore>").await;
+        assert!(!ok);
+        assert!(msg.contains("no running canary"), "{msg}");
+    }
 }
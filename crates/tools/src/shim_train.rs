@@ -1,20 +1,31 @@
-//! ShimTrainTool — invoke the Python shim trainer subprocess.
+//! ShimTrainTool — train a shim FFN, natively or via the Python trainer.
 //!
-//! Calls `python <script_path> --input <jsonl> --output-dir <dir> ...`
-//! and parses the resulting `metrics.json` so the shim-expert agent
-//! has structured visibility into training quality before deciding
-//! whether to register the shim.
+//! Either backend reads `(vector, label)` JSONL and writes `model.onnx`
+//! plus `metrics.json` into the output directory; the tool returns the
+//! parsed metrics so the shim-expert agent has structured visibility
+//! into training quality before deciding whether to register the shim.
 //!
-//! Python + torch must be installed on the host. The trainer script
-//! lives at `crates/cortex-shim/scripts/train_shim.py` and is configured
-//! by the AgentPipelineBuilder at register-tool time so deployments can
-//! point it elsewhere if needed (e.g. baked into a container image).
+//! - [`ShimTrainer::Native`] trains in-process with
+//!   `agentos_cortex_shim::train` and needs nothing on the host.
+//! - [`ShimTrainer::Python`] calls `python <script_path> --input <jsonl>
+//!   --output-dir <dir> ...`; Python + torch must be installed. The
+//!   script ships at `crates/cortex-shim/scripts/train_shim.py`.
+//!   Deployments can point `script_path` elsewhere, e.g. at a copy
+//!   baked into a container image.
+//!
+//! The backend is picked by the `shim-train` listener's `trainer:` block
+//! in the organism YAML: `backend: native` or `backend: python`, the
+//! latter with `python` and `script_path`.
+//! `AgentPipelineBuilder::with_shim_train_tools` registers the tool with
+//! that backend.
 
 use std::path::PathBuf;
 use std::time::Duration;
 
+use agentos_cortex_shim::train::{self, TrainConfig, METRICS_FILE, MODEL_FILE};
 use async_trait::async_trait;
 use rust_pipeline::prelude::*;
+use serde::Deserialize;
 use serde_json::json;
 use tokio::process::Command;
 use tokio::time::timeout;
@@ -25,23 +36,46 @@ use super::{extract_tag, ToolPeer, ToolResponse};
 /// seconds-to-minutes on CPU; 10 minutes is a generous ceiling.
 const TRAIN_TIMEOUT: Duration = Duration::from_secs(600);
 
-/// Tool that runs the Python shim trainer.
+/// Which trainer the tool runs. Deserializes from config as
+/// `{backend: native}` or `{backend: python, python: ..., script_path: ...}`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum ShimTrainer {
+    /// In-process Rust trainer; no Python needed.
+    Native,
+    /// The Python trainer script, run as a subprocess.
+    Python { python: String, script_path: PathBuf },
+}
+
+/// Tool that trains shims.
 ///
-/// Configured at register-tool time with the trainer script's path and
-/// the python interpreter to use.
+/// Configured at register-tool time with the [`ShimTrainer`] to use.
 #[derive(Clone, Debug)]
 pub struct ShimTrainTool {
-    python: String,
-    script_path: PathBuf,
+    trainer: ShimTrainer,
 }
 
 impl ShimTrainTool {
-    /// Construct with explicit paths.
+    /// Python trainer with explicit paths.
     pub fn new(python: impl Into<String>, script_path: PathBuf) -> Self {
-        Self {
+        Self::with_trainer(ShimTrainer::Python {
             python: python.into(),
             script_path,
-        }
+        })
+    }
+
+    /// Native in-process trainer.
+    pub fn native() -> Self {
+        Self::with_trainer(ShimTrainer::Native)
+    }
+
+    /// Use the configured trainer.
+    pub fn with_trainer(trainer: ShimTrainer) -> Self {
+        Self { trainer }
+    }
+
+    pub fn trainer(&self) -> &ShimTrainer {
+        &self.trainer
     }
 
     /// Default path: `<workspace_root>/crates/cortex-shim/scripts/train_shim.py`
@@ -58,10 +92,43 @@ impl ShimTrainTool {
     /// Execute the trainer and return either the parsed `metrics.json`
     /// content (as JSON string) or a structured error.
     async fn execute(&self, args: TrainArgs) -> Result<String, String> {
-        if !self.script_path.exists() {
+        let metrics = match &self.trainer {
+            ShimTrainer::Native => Self::execute_native(&args).await?,
+            ShimTrainer::Python {
+                python,
+                script_path,
+            } => Self::execute_python(python, script_path, &args).await?,
+        };
+        Ok(json!({
+            "metrics": metrics,
+            "model_path": args.output_dir.join(MODEL_FILE),
+            "metrics_path": args.output_dir.join(METRICS_FILE),
+        })
+        .to_string())
+    }
+
+    /// Train in-process. Training is CPU-bound, so it runs on the
+    /// blocking pool; its length is bounded by the epoch count.
+    async fn execute_native(args: &TrainArgs) -> Result<serde_json::Value, String> {
+        let config = args.train_config()?;
+        let input = args.input.clone();
+        let output_dir = args.output_dir.clone();
+        let metrics = tokio::task::spawn_blocking(move || train::train_to_dir(&input, &output_dir, &config))
+            .await
+            .map_err(|e| format!("trainer task failed: {e}"))?
+            .map_err(|e| format!("native trainer: {e}"))?;
+        serde_json::to_value(metrics).map_err(|e| format!("serialize metrics: {e}"))
+    }
+
+    async fn execute_python(
+        python: &str,
+        script_path: &std::path::Path,
+        args: &TrainArgs,
+    ) -> Result<serde_json::Value, String> {
+        if !script_path.exists() {
             return Err(format!(
                 "trainer script not found at {}",
-                self.script_path.display()
+                script_path.display()
             ));
         }
 
@@ -70,8 +137,8 @@ impl ShimTrainTool {
         std::fs::create_dir_all(&args.output_dir)
             .map_err(|e| format!("create output_dir: {e}"))?;
 
-        let mut cmd = Command::new(&self.python);
-        cmd.arg(&self.script_path)
+        let mut cmd = Command::new(python);
+        cmd.arg(script_path)
             .arg("--input")
             .arg(&args.input)
             .arg("--output-dir")
@@ -114,7 +181,7 @@ impl ShimTrainTool {
             ));
         }
 
-        let metrics_path = args.output_dir.join("metrics.json");
+        let metrics_path = args.output_dir.join(METRICS_FILE);
         let metrics = std::fs::read_to_string(&metrics_path).map_err(|e| {
             format!(
                 "trainer succeeded but metrics.json missing at {}: {e}",
@@ -123,15 +190,7 @@ impl ShimTrainTool {
         })?;
 
         // Validate it parses as JSON before handing back to the agent.
-        let parsed: serde_json::Value = serde_json::from_str(&metrics)
-            .map_err(|e| format!("metrics.json is not valid JSON: {e}"))?;
-
-        Ok(json!({
-            "metrics": parsed,
-            "model_path": args.output_dir.join("model.onnx"),
-            "metrics_path": metrics_path,
-        })
-        .to_string())
+        serde_json::from_str(&metrics).map_err(|e| format!("metrics.json is not valid JSON: {e}"))
     }
 }
 
@@ -146,6 +205,8 @@ struct TrainArgs {
     lr: Option<f64>,
     batch_size: Option<u32>,
     seed: Option<u32>,
+    /// Native trainer only; the Python script always runs every epoch.
+    patience: Option<u32>,
 }
 
 impl TrainArgs {
@@ -164,8 +225,33 @@ impl TrainArgs {
             lr: extract_tag(xml_str, "lr").and_then(|s| s.parse().ok()),
             batch_size: extract_tag(xml_str, "batch_size").and_then(|s| s.parse().ok()),
             seed: extract_tag(xml_str, "seed").and_then(|s| s.parse().ok()),
+            patience: extract_tag(xml_str, "patience").and_then(|s| s.parse().ok()),
         })
     }
+
+    /// Hyperparameters for the native trainer; unset ones keep the
+    /// Python script's defaults.
+    fn train_config(&self) -> Result<TrainConfig, String> {
+        let mut config = TrainConfig::default();
+        if let Some(h) = &self.hidden {
+            config.hidden = h
+                .split(',')
+                .map(str::trim)
+                .filter(|d| !d.is_empty())
+                .map(|d| d.parse().map_err(|_| format!("bad <hidden> width `{d}`")))
+                .collect::<Result<_, _>>()?;
+        }
+        config.input_dim = self.input_dim.map(|d| d as usize);
+        config.output_dim = self.output_dim.map_or(config.output_dim, |d| d as usize);
+        config.epochs = self.epochs.map_or(config.epochs, |e| e as usize);
+        config.lr = self.lr.map_or(config.lr, |lr| lr as f32);
+        config.batch_size = self.batch_size.map_or(config.batch_size, |b| b as usize);
+        config.seed = self.seed.map_or(config.seed, u64::from);
+        if let Some(p) = self.patience {
+            config.patience = Some(p as usize);
+        }
+        Ok(config)
+    }
 }
 
 #[async_trait]
@@ -199,10 +285,10 @@ impl ToolPeer for ShimTrainTool {
 
     fn wit(&self) -> &str {
         r#"
-/// Train a shim FFN by invoking the Python trainer subprocess. Reads
-/// (vector, label) JSONL, exports an ONNX file plus metrics.json. The
-/// shim-expert agent reads metrics.json to decide whether to register
-/// the trained shim with cortex.
+/// Train a shim FFN (natively or via the Python trainer, per config).
+/// Reads (vector, label) JSONL, exports an ONNX file plus metrics.json.
+/// The shim-expert agent reads metrics.json to decide whether to
+/// register the trained shim with cortex.
 interface shim-train {
     record request {
         /// path to the (vector, label) JSONL input
@@ -217,6 +303,9 @@ interface shim-train {
         lr: option<f64>,
         batch-size: option<u32>,
         seed: option<u32>,
+        /// native trainer: stop after this many epochs without a
+        /// better validation accuracy
+        patience: option<u32>,
     }
     invoke: func(req: request) -> result<string, string>;
 }
@@ -329,4 +418,34 @@ mod tests {
         assert!(ok, "{body}");
         assert!(body.contains("0.97"), "body: {body}");
     }
+
+    #[tokio::test]
+    async fn native_trainer_writes_model_and_metrics() {
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("data.jsonl");
+        let rows: String = (0..40)
+            .map(|i| {
+                let label = i % 2;
+                let v = if label == 1 { [1.0, 0.0] } else { [0.0, 1.0] };
+                format!("{}\n", json!({"vector": v, "label": label}))
+            })
+            .collect();
+        std::fs::write(&input, rows).unwrap();
+        let out = dir.path().join("out");
+
+        let tool = ShimTrainTool::with_trainer(serde_json::from_str(r#"{"backend":"native"}"#).unwrap());
+        assert_eq!(tool.trainer(), &ShimTrainer::Native);
+        let xml = format!(
+            "<ShimTrain><input>{}</input><output_dir>{}</output_dir><hidden>4</hidden><epochs>5</epochs></ShimTrain>",
+            input.display(),
+            out.display()
+        );
+        let (ok, body) = parse(tool.handle(make_payload(&xml), make_ctx()).await.unwrap());
+        assert!(ok, "{body}");
+        let result: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(result["metrics"]["hidden_dims"], json!([4]));
+        assert_eq!(result["metrics"]["split"]["test"], 2);
+        assert!(out.join("model.onnx").exists());
+        assert!(out.join("metrics.json").exists());
+    }
 }
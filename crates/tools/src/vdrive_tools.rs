@@ -1,5 +1,6 @@
 //! VDrive-backed tool implementations — sandboxed versions of file-read,
-//! file-write, file-edit, glob, and grep.
+//! file-write, file-edit, glob, and grep, plus file-move, file-copy,
+//! file-multi-edit and file-patch.
 //!
 //! Same WIT interfaces as the system tools. The LLM sees identical tool
 //! schemas and response formats. The only difference: all file paths are
@@ -8,6 +9,10 @@
 //! Each tool holds `Option<Arc<VDrive>>`. When `None` (no drive mounted),
 //! every operation returns "no storage mounted — use /vdrive mount <path>".
 //! This is the agent's only access to the filesystem.
+//!
+//! Writes and edits are stamped with the calling thread so a journaled
+//! drive can attribute them; `vdrive-undo` exposes the journal itself.
+//! Every operation runs under the calling agent's mount policy.
 
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -15,7 +20,7 @@ use tokio::sync::RwLock;
 use async_trait::async_trait;
 use rust_pipeline::prelude::*;
 
-use agentos_vdrive::VDrive;
+use agentos_vdrive::{Change, ChangeOp, TextEdit, VDrive};
 
 use super::{extract_tag, ToolPeer, ToolResponse};
 
@@ -31,12 +36,34 @@ pub fn empty_slot() -> DriveSlot {
 
 const NO_STORAGE: &str = "no storage mounted — use /vdrive mount <path> to mount a workspace";
 
+/// Commands run against the real directory, so on an overlay mount they
+/// would read stale files and write straight past the review step.
+pub const OVERLAY_NO_EXEC: &str =
+    "commands are disabled on overlay mounts — changes must go through /vdrive review first";
+
+/// Commands bypass the per-path checks too, so they can't honour a
+/// read-only mount, hidden paths or write budgets.
+pub const POLICY_NO_EXEC: &str =
+    "commands are disabled under this agent's mount policy — use the file tools instead";
+
+/// Why commands may not run on `drive`, if they may not.
+pub fn exec_refusal(drive: &VDrive) -> Option<&'static str> {
+    if drive.is_overlay() {
+        Some(OVERLAY_NO_EXEC)
+    } else if !drive.policy().is_unrestricted() {
+        Some(POLICY_NO_EXEC)
+    } else {
+        None
+    }
+}
+
 /// Helper: read the drive from a slot, returning an error response if empty.
+/// The handle enforces the calling agent's mount policy.
 macro_rules! require_drive {
-    ($slot:expr) => {{
+    ($slot:expr, $ctx:expr) => {{
         let guard = $slot.read().await;
-        match guard.clone() {
-            Some(d) => d,
+        match guard.as_ref() {
+            Some(d) => d.for_agent(&$ctx.from),
             None => {
                 return Ok(HandlerResponse::Reply {
                     payload_xml: ToolResponse::err(NO_STORAGE),
@@ -60,8 +87,8 @@ impl VDriveFileRead {
 
 #[async_trait]
 impl Handler for VDriveFileRead {
-    async fn handle(&self, payload: ValidatedPayload, _ctx: HandlerContext) -> HandlerResult {
-        let drive = require_drive!(self.slot);
+    async fn handle(&self, payload: ValidatedPayload, ctx: HandlerContext) -> HandlerResult {
+        let drive = require_drive!(self.slot, ctx);
         let xml_str = String::from_utf8_lossy(&payload.xml);
 
         let path = extract_tag(&xml_str, "path").unwrap_or_default();
@@ -127,8 +154,8 @@ impl VDriveFileWrite {
 
 #[async_trait]
 impl Handler for VDriveFileWrite {
-    async fn handle(&self, payload: ValidatedPayload, _ctx: HandlerContext) -> HandlerResult {
-        let drive = require_drive!(self.slot);
+    async fn handle(&self, payload: ValidatedPayload, ctx: HandlerContext) -> HandlerResult {
+        let drive = require_drive!(self.slot, ctx).for_thread(&ctx.thread_id);
         let xml_str = String::from_utf8_lossy(&payload.xml);
 
         let path = extract_tag(&xml_str, "path").unwrap_or_default();
@@ -190,8 +217,8 @@ impl VDriveFileEdit {
 
 #[async_trait]
 impl Handler for VDriveFileEdit {
-    async fn handle(&self, payload: ValidatedPayload, _ctx: HandlerContext) -> HandlerResult {
-        let drive = require_drive!(self.slot);
+    async fn handle(&self, payload: ValidatedPayload, ctx: HandlerContext) -> HandlerResult {
+        let drive = require_drive!(self.slot, ctx).for_thread(&ctx.thread_id);
         let xml_str = String::from_utf8_lossy(&payload.xml);
 
         let path = extract_tag(&xml_str, "path").unwrap_or_default();
@@ -227,19 +254,8 @@ impl Handler for VDriveFileEdit {
                     .map(|r| r.content)
                     .unwrap_or_default();
 
-                let diff = similar::TextDiff::from_lines(&before, &after);
-                let mut diff_output = String::new();
-                for change in diff.iter_all_changes() {
-                    let sign = match change.tag() {
-                        similar::ChangeTag::Delete => "-",
-                        similar::ChangeTag::Insert => "+",
-                        similar::ChangeTag::Equal => " ",
-                    };
-                    diff_output.push_str(&format!("{sign}{change}"));
-                }
-
                 Ok(HandlerResponse::Reply {
-                    payload_xml: ToolResponse::ok(&diff_output),
+                    payload_xml: ToolResponse::ok(&line_diff(&before, &after)),
                 })
             }
             Err(e) => Ok(HandlerResponse::Reply {
@@ -273,6 +289,291 @@ interface file-edit {
     }
 }
 
+/// Diff of a file's numbered contents before and after an edit.
+fn line_diff(before: &str, after: &str) -> String {
+    let diff = similar::TextDiff::from_lines(before, after);
+    let mut diff_output = String::new();
+    for change in diff.iter_all_changes() {
+        let sign = match change.tag() {
+            similar::ChangeTag::Delete => "-",
+            similar::ChangeTag::Insert => "+",
+            similar::ChangeTag::Equal => " ",
+        };
+        diff_output.push_str(&format!("{sign}{change}"));
+    }
+    diff_output
+}
+
+// ── VDrive File Multi-Edit ──
+
+pub struct VDriveFileMultiEdit {
+    slot: DriveSlot,
+}
+
+impl VDriveFileMultiEdit {
+    pub fn new(slot: DriveSlot) -> Self {
+        Self { slot }
+    }
+}
+
+#[async_trait]
+impl Handler for VDriveFileMultiEdit {
+    async fn handle(&self, payload: ValidatedPayload, ctx: HandlerContext) -> HandlerResult {
+        let drive = require_drive!(self.slot, ctx).for_thread(&ctx.thread_id);
+        let xml_str = String::from_utf8_lossy(&payload.xml);
+
+        let path = extract_tag(&xml_str, "path").unwrap_or_default();
+        if path.is_empty() {
+            return Ok(HandlerResponse::Reply {
+                payload_xml: ToolResponse::err("missing required <path>"),
+            });
+        }
+
+        // The list arrives as JSON text, like every list-typed field.
+        let edits_json = extract_tag(&xml_str, "edits").unwrap_or_default();
+        let edits: Vec<TextEdit> = match serde_json::from_str(&edits_json) {
+            Ok(edits) => edits,
+            Err(e) => {
+                return Ok(HandlerResponse::Reply {
+                    payload_xml: ToolResponse::err(&format!("invalid <edits>: {e}")),
+                });
+            }
+        };
+        if edits.is_empty() {
+            return Ok(HandlerResponse::Reply {
+                payload_xml: ToolResponse::err("<edits> is empty"),
+            });
+        }
+        if let Some(i) = edits.iter().position(|e| e.old_string.is_empty()) {
+            return Ok(HandlerResponse::Reply {
+                payload_xml: ToolResponse::err(&format!("edit {} has an empty old_string", i + 1)),
+            });
+        }
+
+        let before = match drive.read_file(&path, 1, usize::MAX) {
+            Ok(r) => r.content,
+            Err(e) => {
+                return Ok(HandlerResponse::Reply {
+                    payload_xml: ToolResponse::err(&e.to_string()),
+                });
+            }
+        };
+
+        match drive.multi_edit(&path, &edits) {
+            Ok(()) => {
+                let after = drive.read_file(&path, 1, usize::MAX)
+                    .map(|r| r.content)
+                    .unwrap_or_default();
+                Ok(HandlerResponse::Reply {
+                    payload_xml: ToolResponse::ok(&line_diff(&before, &after)),
+                })
+            }
+            Err(e) => Ok(HandlerResponse::Reply {
+                payload_xml: ToolResponse::err(&e.to_string()),
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolPeer for VDriveFileMultiEdit {
+    fn name(&self) -> &str {
+        "file-multi-edit"
+    }
+
+    fn wit(&self) -> &str {
+        r#"
+/// Apply several text replacements to one file in order, each seeing the result of the ones before. All-or-nothing: if any edit fails to match, the file is left untouched. Returns unified diff.
+interface file-multi-edit {
+    record edit {
+        /// The exact text to find (must be unique unless replace-all)
+        old-string: string,
+        /// The replacement text
+        new-string: string,
+        /// Replace every occurrence (default: false)
+        replace-all: option<bool>,
+    }
+    record request {
+        /// The file path to edit
+        path: string,
+        /// Replacements to apply, in order
+        edits: list<edit>,
+    }
+    multi-edit: func(req: request) -> result<string, string>;
+}
+"#
+    }
+}
+
+// ── VDrive File Patch ──
+
+pub struct VDriveFilePatch {
+    slot: DriveSlot,
+}
+
+impl VDriveFilePatch {
+    pub fn new(slot: DriveSlot) -> Self {
+        Self { slot }
+    }
+}
+
+#[async_trait]
+impl Handler for VDriveFilePatch {
+    async fn handle(&self, payload: ValidatedPayload, ctx: HandlerContext) -> HandlerResult {
+        let drive = require_drive!(self.slot, ctx).for_thread(&ctx.thread_id);
+        let xml_str = String::from_utf8_lossy(&payload.xml);
+
+        let patch = extract_tag(&xml_str, "patch").unwrap_or_default();
+        if patch.trim().is_empty() {
+            return Ok(HandlerResponse::Reply {
+                payload_xml: ToolResponse::err("missing required <patch>"),
+            });
+        }
+
+        // A rejected patch still carries the per-hunk report, so the agent
+        // can fix just the hunks that failed.
+        let payload_xml = match drive.apply_patch(&patch) {
+            Ok(report) if report.applied => ToolResponse::ok(&report.to_string()),
+            Ok(report) => ToolResponse::err(&report.to_string()),
+            Err(e) => ToolResponse::err(&e.to_string()),
+        };
+        Ok(HandlerResponse::Reply { payload_xml })
+    }
+}
+
+#[async_trait]
+impl ToolPeer for VDriveFilePatch {
+    fn name(&self) -> &str {
+        "file-patch"
+    }
+
+    fn wit(&self) -> &str {
+        r#"
+/// Apply a patch touching any number of files: a unified diff (--- a/x, +++ b/x, @@ hunks) or a *** Begin Patch block with *** Add File / *** Update File / *** Delete File sections. Hunks are located by their lines, so line numbers may be off and whitespace may differ. All-or-nothing: if any hunk can't be placed nothing is written. Returns a per-hunk report with rejection reasons.
+interface file-patch {
+    record request {
+        /// The patch text
+        patch: string,
+    }
+    patch: func(req: request) -> result<string, string>;
+}
+"#
+    }
+}
+
+// ── VDrive File Move / Copy ──
+
+/// Pull the required `<from>` and `<to>` out of a move or copy request.
+fn from_to(xml: &str) -> Result<(String, String), &'static str> {
+    let from = extract_tag(xml, "from").unwrap_or_default();
+    if from.is_empty() {
+        return Err("missing required <from>");
+    }
+    let to = extract_tag(xml, "to").unwrap_or_default();
+    if to.is_empty() {
+        return Err("missing required <to>");
+    }
+    Ok((from, to))
+}
+
+pub struct VDriveFileMove {
+    slot: DriveSlot,
+}
+
+impl VDriveFileMove {
+    pub fn new(slot: DriveSlot) -> Self {
+        Self { slot }
+    }
+}
+
+#[async_trait]
+impl Handler for VDriveFileMove {
+    async fn handle(&self, payload: ValidatedPayload, ctx: HandlerContext) -> HandlerResult {
+        let drive = require_drive!(self.slot, ctx).for_thread(&ctx.thread_id);
+        let xml_str = String::from_utf8_lossy(&payload.xml);
+
+        let payload_xml = match from_to(&xml_str) {
+            Ok((from, to)) => match drive.rename(&from, &to) {
+                Ok(()) => ToolResponse::ok(&format!("moved {from} to {to}")),
+                Err(e) => ToolResponse::err(&e.to_string()),
+            },
+            Err(msg) => ToolResponse::err(msg),
+        };
+        Ok(HandlerResponse::Reply { payload_xml })
+    }
+}
+
+#[async_trait]
+impl ToolPeer for VDriveFileMove {
+    fn name(&self) -> &str {
+        "file-move"
+    }
+
+    fn wit(&self) -> &str {
+        r#"
+/// Move or rename a file or directory. The destination must not exist; missing parent directories are created.
+interface file-move {
+    record request {
+        /// The file or directory to move
+        from: string,
+        /// The new path
+        to: string,
+    }
+    move: func(req: request) -> result<string, string>;
+}
+"#
+    }
+}
+
+pub struct VDriveFileCopy {
+    slot: DriveSlot,
+}
+
+impl VDriveFileCopy {
+    pub fn new(slot: DriveSlot) -> Self {
+        Self { slot }
+    }
+}
+
+#[async_trait]
+impl Handler for VDriveFileCopy {
+    async fn handle(&self, payload: ValidatedPayload, ctx: HandlerContext) -> HandlerResult {
+        let drive = require_drive!(self.slot, ctx).for_thread(&ctx.thread_id);
+        let xml_str = String::from_utf8_lossy(&payload.xml);
+
+        let payload_xml = match from_to(&xml_str) {
+            Ok((from, to)) => match drive.copy(&from, &to) {
+                Ok(()) => ToolResponse::ok(&format!("copied {from} to {to}")),
+                Err(e) => ToolResponse::err(&e.to_string()),
+            },
+            Err(msg) => ToolResponse::err(msg),
+        };
+        Ok(HandlerResponse::Reply { payload_xml })
+    }
+}
+
+#[async_trait]
+impl ToolPeer for VDriveFileCopy {
+    fn name(&self) -> &str {
+        "file-copy"
+    }
+
+    fn wit(&self) -> &str {
+        r#"
+/// Copy a file, or a directory and everything in it. The destination must not exist; missing parent directories are created.
+interface file-copy {
+    record request {
+        /// The file or directory to copy
+        from: string,
+        /// Where the copy goes
+        to: string,
+    }
+    copy: func(req: request) -> result<string, string>;
+}
+"#
+    }
+}
+
 // ── VDrive Glob ──
 
 pub struct VDriveGlob {
@@ -289,8 +590,8 @@ const GLOB_MAX_RESULTS: usize = 1000;
 
 #[async_trait]
 impl Handler for VDriveGlob {
-    async fn handle(&self, payload: ValidatedPayload, _ctx: HandlerContext) -> HandlerResult {
-        let drive = require_drive!(self.slot);
+    async fn handle(&self, payload: ValidatedPayload, ctx: HandlerContext) -> HandlerResult {
+        let drive = require_drive!(self.slot, ctx);
         let xml_str = String::from_utf8_lossy(&payload.xml);
 
         let pattern = extract_tag(&xml_str, "pattern").unwrap_or_default();
@@ -363,8 +664,8 @@ const GREP_MAX_MATCHES: usize = 500;
 
 #[async_trait]
 impl Handler for VDriveGrep {
-    async fn handle(&self, payload: ValidatedPayload, _ctx: HandlerContext) -> HandlerResult {
-        let drive = require_drive!(self.slot);
+    async fn handle(&self, payload: ValidatedPayload, ctx: HandlerContext) -> HandlerResult {
+        let drive = require_drive!(self.slot, ctx);
         let xml_str = String::from_utf8_lossy(&payload.xml);
 
         let pattern = extract_tag(&xml_str, "pattern").unwrap_or_default();
@@ -448,8 +749,8 @@ impl VDriveListDir {
 
 #[async_trait]
 impl Handler for VDriveListDir {
-    async fn handle(&self, payload: ValidatedPayload, _ctx: HandlerContext) -> HandlerResult {
-        let drive = require_drive!(self.slot);
+    async fn handle(&self, payload: ValidatedPayload, ctx: HandlerContext) -> HandlerResult {
+        let drive = require_drive!(self.slot, ctx);
         let xml_str = String::from_utf8_lossy(&payload.xml);
         let path = extract_tag(&xml_str, "path").unwrap_or_else(|| ".".into());
 
@@ -589,7 +890,12 @@ impl Handler for VDriveCommandExec {
             });
         }
 
-        let drive = require_drive!(self.slot);
+        let drive = require_drive!(self.slot, ctx);
+        if let Some(reason) = exec_refusal(&drive) {
+            return Ok(HandlerResponse::Reply {
+                payload_xml: ToolResponse::err(reason),
+            });
+        }
         let xml_str = String::from_utf8_lossy(&payload.xml);
 
         let command = extract_tag(&xml_str, "command").unwrap_or_default();
@@ -697,9 +1003,158 @@ interface bash {
     }
 }
 
+// ── VDrive Undo ──
+
+/// Checkpoint, inspect and roll back the drive's change journal.
+pub struct VDriveUndo {
+    slot: DriveSlot,
+}
+
+impl VDriveUndo {
+    pub fn new(slot: DriveSlot) -> Self {
+        Self { slot }
+    }
+
+    fn handle_list(drive: &VDrive, xml: &str) -> Result<String, String> {
+        let since = parse_checkpoint(xml)?;
+        let changes = drive.list_changes(since).map_err(|e| e.to_string())?;
+        let checkpoints = drive.checkpoints().map_err(|e| e.to_string())?;
+
+        let mut out = String::from("checkpoints:\n");
+        if checkpoints.is_empty() {
+            out.push_str("  (none)\n");
+        }
+        for cp in &checkpoints {
+            out.push_str(&format!("  #{} {} (after change {})\n", cp.id, cp.label, cp.after_seq));
+        }
+        out.push_str(&format!("changes ({}):\n", changes.len()));
+        for change in &changes {
+            out.push_str(&format!("  {}\n", describe_change(change)));
+        }
+        Ok(out)
+    }
+
+    fn handle_checkpoint(drive: &VDrive, xml: &str) -> Result<String, String> {
+        let label = extract_tag(xml, "label")
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "checkpoint".to_string());
+        let cp = drive.checkpoint(&label).map_err(|e| e.to_string())?;
+        Ok(format!("checkpoint #{} created", cp.id))
+    }
+
+    fn handle_revert(drive: &VDrive, xml: &str) -> Result<String, String> {
+        let id = parse_checkpoint(xml)?.ok_or("missing required <checkpoint>")?;
+        let undone = drive.revert_to(id).map_err(|e| e.to_string())?;
+        let mut paths: Vec<&str> = undone
+            .iter()
+            .filter(|c| c.op != ChangeOp::Mkdir)
+            .map(|c| c.path.as_str())
+            .collect();
+        paths.sort();
+        paths.dedup();
+        Ok(format!(
+            "reverted {} changes to checkpoint #{id}\n{}",
+            undone.len(),
+            paths.join("\n")
+        ))
+    }
+
+    fn handle_revert_file(drive: &VDrive, xml: &str) -> Result<String, String> {
+        let path = extract_tag(xml, "path")
+            .filter(|s| !s.is_empty())
+            .ok_or("missing required <path>")?;
+        let id = parse_checkpoint(xml)?.ok_or("missing required <checkpoint>")?;
+        match drive.revert_file(&path, id).map_err(|e| e.to_string())? {
+            0 => Ok(format!("{path} unchanged since checkpoint #{id}")),
+            n => Ok(format!("reverted {n} changes to {path}")),
+        }
+    }
+}
+
+fn parse_checkpoint(xml: &str) -> Result<Option<u64>, String> {
+    match extract_tag(xml, "checkpoint").filter(|s| !s.is_empty()) {
+        Some(s) => s
+            .trim()
+            .trim_start_matches('#')
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("invalid <checkpoint> '{s}'")),
+        None => Ok(None),
+    }
+}
+
+/// One-line summary of a journaled change, e.g. `#4 edit src/lib.rs`.
+pub fn describe_change(change: &Change) -> String {
+    let op = match change.op {
+        ChangeOp::Write => "write",
+        ChangeOp::Edit => "edit",
+        ChangeOp::Mkdir => "mkdir",
+        ChangeOp::Delete => "delete",
+        ChangeOp::DeleteDir => "rmdir",
+        ChangeOp::Rename => "move",
+        ChangeOp::Copy => "copy",
+        ChangeOp::Patch => "patch",
+    };
+    format!("#{} {op} {}", change.seq, change.path)
+}
+
+#[async_trait]
+impl Handler for VDriveUndo {
+    async fn handle(&self, payload: ValidatedPayload, ctx: HandlerContext) -> HandlerResult {
+        let drive = require_drive!(self.slot, ctx).for_thread(&ctx.thread_id);
+        let xml = String::from_utf8_lossy(&payload.xml).to_string();
+        let action = extract_tag(&xml, "action").unwrap_or_default();
+
+        let result = match action.as_str() {
+            "list" => Self::handle_list(&drive, &xml),
+            "checkpoint" => Self::handle_checkpoint(&drive, &xml),
+            "revert" => Self::handle_revert(&drive, &xml),
+            "revert-file" => Self::handle_revert_file(&drive, &xml),
+            "" => Err("missing required <action>".to_string()),
+            other => Err(format!(
+                "unknown action '{other}'; expected list|checkpoint|revert|revert-file"
+            )),
+        };
+
+        Ok(HandlerResponse::Reply {
+            payload_xml: match result {
+                Ok(body) => ToolResponse::ok(&body),
+                Err(e) => ToolResponse::err(&e),
+            },
+        })
+    }
+}
+
+#[async_trait]
+impl ToolPeer for VDriveUndo {
+    fn name(&self) -> &str {
+        "vdrive-undo"
+    }
+
+    fn wit(&self) -> &str {
+        r#"
+/// Undo your own file changes. Every file-write and file-edit is journaled; set a checkpoint before risky work, list what changed, and revert the whole drive or a single file to a checkpoint. Changes made by bash are not journaled.
+interface vdrive-undo {
+    record request {
+        /// list | checkpoint | revert | revert-file
+        action: string,
+        /// Checkpoint name (checkpoint only)
+        label: option<string>,
+        /// Checkpoint id: list changes since it (list), or restore to it (revert, revert-file)
+        checkpoint: option<u64>,
+        /// File to restore (revert-file only)
+        path: option<string>,
+    }
+    invoke: func(req: request) -> result<string, string>;
+}
+"#
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::xml_escape;
     use std::fs;
     use tempfile::TempDir;
 
@@ -812,6 +1267,92 @@ mod tests {
         assert!(fs::read_to_string(dir.path().join("code.rs")).unwrap().contains("fn baz()"));
     }
 
+    #[tokio::test]
+    async fn vdrive_multi_edit_file() {
+        let (dir, vd) = setup();
+        fs::write(dir.path().join("code.rs"), "fn foo() {}\nfn bar() {}\n").unwrap();
+
+        let tool = VDriveFileMultiEdit::new(vd);
+        let edits = r#"[{"old_string":"fn foo()","new_string":"fn baz()"},{"old_string":"fn","new_string":"pub fn","replace_all":true}]"#;
+        let xml = format!("<FileMultiEditRequest><path>code.rs</path><edits>{}</edits></FileMultiEditRequest>", xml_escape(edits));
+        let (ok, diff) = get_result(tool.handle(make_payload(&xml, "FileMultiEditRequest"), make_ctx("file-multi-edit")).await.unwrap());
+        assert!(ok, "{diff}");
+        assert!(diff.contains("+1| pub fn baz() {}"));
+        assert_eq!(fs::read_to_string(dir.path().join("code.rs")).unwrap(), "pub fn baz() {}\npub fn bar() {}\n");
+    }
+
+    #[tokio::test]
+    async fn vdrive_multi_edit_failure_leaves_file() {
+        let (dir, vd) = setup();
+        fs::write(dir.path().join("code.rs"), "fn foo() {}\n").unwrap();
+
+        let tool = VDriveFileMultiEdit::new(vd);
+        let edits = r#"[{"old_string":"foo","new_string":"baz"},{"old_string":"nope","new_string":"x"}]"#;
+        let xml = format!("<FileMultiEditRequest><path>code.rs</path><edits>{}</edits></FileMultiEditRequest>", xml_escape(edits));
+        let (ok, content) = get_result(tool.handle(make_payload(&xml, "FileMultiEditRequest"), make_ctx("file-multi-edit")).await.unwrap());
+        assert!(!ok);
+        assert!(content.contains("edit 2"), "{content}");
+        assert_eq!(fs::read_to_string(dir.path().join("code.rs")).unwrap(), "fn foo() {}\n");
+
+        let xml = "<FileMultiEditRequest><path>code.rs</path><edits>not json</edits></FileMultiEditRequest>";
+        let (ok, content) = get_result(tool.handle(make_payload(xml, "FileMultiEditRequest"), make_ctx("file-multi-edit")).await.unwrap());
+        assert!(!ok);
+        assert!(content.contains("invalid <edits>"));
+    }
+
+    // ── Patch ──
+
+    #[tokio::test]
+    async fn vdrive_patch_reports_hunks() {
+        let (dir, vd) = setup();
+        fs::write(dir.path().join("a.rs"), "fn a() {\n    one();\n}\n").unwrap();
+
+        let tool = VDriveFilePatch::new(vd);
+        let patch = "--- a/a.rs\n+++ b/a.rs\n@@ -1,3 +1,3 @@\n fn a() {\n-    one();\n+    two();\n }\n";
+        let xml = format!("<FilePatchRequest><patch>{}</patch></FilePatchRequest>", xml_escape(patch));
+        let (ok, content) = get_result(tool.handle(make_payload(&xml, "FilePatchRequest"), make_ctx("file-patch")).await.unwrap());
+        assert!(ok, "{content}");
+        assert!(content.contains("hunk 1: applied at line 1"));
+        assert!(fs::read_to_string(dir.path().join("a.rs")).unwrap().contains("two();"));
+
+        // The same patch no longer applies: rejected with a reason.
+        let (ok, content) = get_result(tool.handle(make_payload(&xml, "FilePatchRequest"), make_ctx("file-patch")).await.unwrap());
+        assert!(!ok);
+        assert!(content.contains("hunk 1: rejected"), "{content}");
+    }
+
+    // ── Move / Copy ──
+
+    #[tokio::test]
+    async fn vdrive_move_and_copy() {
+        let (dir, vd) = setup();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/a.rs"), "a").unwrap();
+
+        let copy = VDriveFileCopy::new(vd.clone());
+        let xml = "<FileCopyRequest><from>src</from><to>backup</to></FileCopyRequest>";
+        let (ok, _) = get_result(copy.handle(make_payload(xml, "FileCopyRequest"), make_ctx("file-copy")).await.unwrap());
+        assert!(ok);
+        assert_eq!(fs::read_to_string(dir.path().join("backup/a.rs")).unwrap(), "a");
+
+        let mv = VDriveFileMove::new(vd);
+        let xml = "<FileMoveRequest><from>src/a.rs</from><to>lib/b.rs</to></FileMoveRequest>";
+        let (ok, content) = get_result(mv.handle(make_payload(xml, "FileMoveRequest"), make_ctx("file-move")).await.unwrap());
+        assert!(ok);
+        assert!(content.contains("moved src/a.rs to lib/b.rs"));
+        assert!(!dir.path().join("src/a.rs").exists());
+        assert!(dir.path().join("lib/b.rs").exists());
+
+        let (ok, content) = get_result(mv.handle(make_payload(xml, "FileMoveRequest"), make_ctx("file-move")).await.unwrap());
+        assert!(!ok);
+        assert!(content.contains("not found"));
+
+        let xml = "<FileMoveRequest><from>backup</from></FileMoveRequest>";
+        let (ok, content) = get_result(mv.handle(make_payload(xml, "FileMoveRequest"), make_ctx("file-move")).await.unwrap());
+        assert!(!ok);
+        assert!(content.contains("missing required <to>"));
+    }
+
     // ── Glob ──
 
     #[tokio::test]
@@ -890,7 +1431,8 @@ mod tests {
             VDriveFileEdit::new(slot.clone()).wit(),
             VDriveGlob::new(slot.clone()).wit(),
             VDriveGrep::new(slot.clone()).wit(),
-            VDriveListDir::new(slot).wit(),
+            VDriveListDir::new(slot.clone()).wit(),
+            VDriveUndo::new(slot).wit(),
         ] {
             let iface = agentos_wit::parser::parse_wit(tool).unwrap();
             assert!(!iface.name.is_empty());
@@ -910,4 +1452,112 @@ mod tests {
         assert!(!ok);
         assert!(content.contains("no storage mounted"));
     }
+
+    // ── Mount policy ──
+
+    #[tokio::test]
+    async fn vdrive_policy_follows_caller() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("secrets")).unwrap();
+        fs::write(dir.path().join("secrets/key.txt"), "hunter2").unwrap();
+        let mut agents = std::collections::HashMap::new();
+        agents.insert("reviewer".to_string(), agentos_events::MountPolicy {
+            read_only: true,
+            ..Default::default()
+        });
+        let policies = agentos_events::MountPolicies {
+            base: agentos_events::MountPolicy {
+                deny: agentos_events::GlobRules::new(["secrets/**"]).unwrap(),
+                ..Default::default()
+            },
+            agents,
+        };
+        let vd = VDrive::open(dir.path()).unwrap().with_policies(policies);
+        let slot: DriveSlot = Arc::new(RwLock::new(Some(Arc::new(vd))));
+
+        let read = VDriveFileRead::new(slot.clone());
+        let xml = "<FileReadRequest><path>secrets/key.txt</path></FileReadRequest>";
+        let (ok, content) = get_result(read.handle(make_payload(xml, "FileReadRequest"), make_ctx("file-read")).await.unwrap());
+        assert!(!ok);
+        assert!(content.contains("not found"));
+
+        let write = VDriveFileWrite::new(slot);
+        let xml = "<FileWriteRequest><path>a.txt</path><content>x</content></FileWriteRequest>";
+        let mut ctx = make_ctx("file-write");
+        ctx.from = "reviewer".into();
+        let (ok, content) = get_result(write.handle(make_payload(xml, "FileWriteRequest"), ctx).await.unwrap());
+        assert!(!ok);
+        assert!(content.contains("read-only"));
+        let (ok, _) = get_result(write.handle(make_payload(xml, "FileWriteRequest"), make_ctx("file-write")).await.unwrap());
+        assert!(ok);
+    }
+
+    // ── Undo ──
+
+    fn setup_journaled() -> (TempDir, TempDir, DriveSlot) {
+        let dir = TempDir::new().unwrap();
+        let data = TempDir::new().unwrap();
+        let vd = VDrive::open(dir.path()).unwrap().with_journal(data.path()).unwrap();
+        (dir, data, Arc::new(RwLock::new(Some(Arc::new(vd)))))
+    }
+
+    async fn undo(slot: &DriveSlot, body: &str) -> (bool, String) {
+        let xml = format!("<VDriveUndoRequest>{body}</VDriveUndoRequest>");
+        let tool = VDriveUndo::new(slot.clone());
+        get_result(
+            tool.handle(make_payload(&xml, "VDriveUndoRequest"), make_ctx("vdrive-undo"))
+                .await
+                .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn vdrive_undo_reverts_tool_writes() {
+        let (dir, _data, slot) = setup_journaled();
+        fs::write(dir.path().join("a.txt"), "before").unwrap();
+
+        let (ok, content) = undo(&slot, "<action>checkpoint</action><label>start</label>").await;
+        assert!(ok, "{content}");
+        assert!(content.contains("#1"));
+
+        let write = VDriveFileWrite::new(slot.clone());
+        let xml = "<FileWriteRequest><path>a.txt</path><content>after</content></FileWriteRequest>";
+        write.handle(make_payload(xml, "FileWriteRequest"), make_ctx("file-write")).await.unwrap();
+
+        let (ok, content) = undo(&slot, "<action>list</action><checkpoint>1</checkpoint>").await;
+        assert!(ok);
+        assert!(content.contains("write a.txt"), "{content}");
+
+        let (ok, content) = undo(&slot, "<action>revert</action><checkpoint>1</checkpoint>").await;
+        assert!(ok, "{content}");
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "before");
+    }
+
+    #[tokio::test]
+    async fn vdrive_undo_stamps_thread() {
+        let (_dir, _data, slot) = setup_journaled();
+        let write = VDriveFileWrite::new(slot.clone());
+        let xml = "<FileWriteRequest><path>b.txt</path><content>x</content></FileWriteRequest>";
+        write.handle(make_payload(xml, "FileWriteRequest"), make_ctx("file-write")).await.unwrap();
+
+        let drive = slot.read().await.clone().unwrap();
+        let changes = drive.list_changes(None).unwrap();
+        assert_eq!(changes[0].thread.as_deref(), Some("t1"));
+    }
+
+    #[tokio::test]
+    async fn vdrive_undo_requires_journal_and_action() {
+        let (_dir, slot) = setup();
+        let (ok, content) = undo(&slot, "<action>list</action>").await;
+        assert!(!ok);
+        assert!(content.contains("no change journal"));
+
+        let (ok, content) = undo(&slot, "").await;
+        assert!(!ok);
+        assert!(content.contains("missing required <action>"));
+
+        let (ok, content) = undo(&slot, "<action>revert</action>").await;
+        assert!(!ok);
+        assert!(content.contains("missing required <checkpoint>"));
+    }
 }
@@ -0,0 +1,206 @@
+//! Grammars loaded at runtime from compiled tree-sitter parser libraries.
+//!
+//! Loading native code is opt-in: without the `external-grammars` cargo
+//! feature, [`Lang::register`] always fails. With it, only libraries
+//! inside the configured grammar directory are loaded.
+//!
+//! A registered grammar lives for the rest of the process: its library
+//! stays loaded and its queries are leaked, which keeps [`Lang`] `Copy`
+//! and lets its accessors keep returning `'static` data.
+
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+#[cfg(feature = "external-grammars")]
+use libloading::Library;
+use tree_sitter::Language;
+#[cfg(feature = "external-grammars")]
+use tree_sitter::{Parser, Query};
+#[cfg(feature = "external-grammars")]
+use tree_sitter_language::LanguageFn;
+
+use crate::languages::Lang;
+
+/// A grammar to load with [`Lang::register`].
+#[derive(Debug, Clone)]
+pub struct ExternalGrammar {
+    /// Language name, as reported in codebase maps.
+    pub name: String,
+    /// File extensions, with or without the leading dot.
+    pub extensions: Vec<String>,
+    /// Compiled parser library (`.so`, `.dylib` or `.dll`). Must resolve
+    /// to a file inside the grammar directory passed to [`Lang::register`].
+    pub library: PathBuf,
+    /// Exported language function; `None` means `tree_sitter_<name>`.
+    pub symbol: Option<String>,
+    /// Symbol query, following the conventions in [`crate::languages`].
+    pub symbol_query: String,
+    /// Reference query; `None` extracts no references.
+    pub reference_query: Option<String>,
+}
+
+/// What a [`Lang::External`] resolves to.
+#[derive(Clone)]
+pub(crate) struct Registered {
+    pub name: &'static str,
+    pub language: Language,
+    pub symbol_query: &'static str,
+    pub reference_query: &'static str,
+}
+
+struct Entry {
+    grammar: Registered,
+    extensions: Vec<String>,
+    /// Keeps the parser code mapped.
+    #[cfg(feature = "external-grammars")]
+    _library: Library,
+}
+
+/// Indexed by the id in [`Lang::External`]. Only ever appended to.
+static REGISTRY: RwLock<Vec<Entry>> = RwLock::new(Vec::new());
+
+#[cfg(not(feature = "external-grammars"))]
+pub(crate) fn register(grammar: ExternalGrammar, _grammar_dir: &Path) -> Result<Lang, String> {
+    Err(format!(
+        "cannot load {}: built without the `external-grammars` feature",
+        grammar.library.display()
+    ))
+}
+
+#[cfg(feature = "external-grammars")]
+pub(crate) fn register(grammar: ExternalGrammar, grammar_dir: &Path) -> Result<Lang, String> {
+    let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+    if let Some(id) = registry.iter().position(|e| e.grammar.name == grammar.name) {
+        return Ok(Lang::External(id as u16));
+    }
+    let id = u16::try_from(registry.len()).map_err(|_| "too many grammars registered".to_string())?;
+
+    let path = confine(&grammar.library, grammar_dir)?;
+    let symbol = grammar
+        .symbol
+        .clone()
+        .unwrap_or_else(|| format!("tree_sitter_{}", grammar.name.replace('-', "_")));
+    // SAFETY: loading a library runs its initializers with full process
+    // privileges. `path` is a canonical file inside the operator's grammar
+    // directory, which is trusted like the tool binaries the organism names.
+    let library = unsafe { Library::new(&path) }
+        .map_err(|e| format!("failed to load {}: {e}", path.display()))?;
+    // SAFETY: the ABI contract of a tree-sitter parser library is that
+    // `symbol` is `const TSLanguage *tree_sitter_<name>(void)`: no
+    // arguments, returns a pointer to a static language table. A wrong
+    // signature is undefined behaviour we cannot detect; `set_language`
+    // below at least rejects a table with an incompatible ABI version.
+    let language: Language = unsafe {
+        let func = library
+            .get::<unsafe extern "C" fn() -> *const ()>(symbol.as_bytes())
+            .map_err(|e| format!("{symbol} not found in {}: {e}", path.display()))?;
+        LanguageFn::from_raw(*func)
+    }
+    .into();
+
+    Parser::new()
+        .set_language(&language)
+        .map_err(|e| format!("incompatible grammar: {e}"))?;
+    Query::new(&language, &grammar.symbol_query)
+        .map_err(|e| format!("invalid symbol query: {e}"))?;
+    let reference_query = grammar.reference_query.unwrap_or_default();
+    Query::new(&language, &reference_query)
+        .map_err(|e| format!("invalid reference query: {e}"))?;
+
+    registry.push(Entry {
+        grammar: Registered {
+            name: leak(grammar.name),
+            language,
+            symbol_query: leak(grammar.symbol_query),
+            reference_query: leak(reference_query),
+        },
+        extensions: grammar
+            .extensions
+            .iter()
+            .map(|ext| ext.trim_start_matches('.').to_string())
+            .collect(),
+        _library: library,
+    });
+    Ok(Lang::External(id))
+}
+
+/// Resolve `library` (symlinks and `..` included) and require that it
+/// lands inside `grammar_dir`.
+#[cfg(feature = "external-grammars")]
+fn confine(library: &Path, grammar_dir: &Path) -> Result<PathBuf, String> {
+    let dir = grammar_dir
+        .canonicalize()
+        .map_err(|e| format!("grammar directory {}: {e}", grammar_dir.display()))?;
+    let path = library
+        .canonicalize()
+        .map_err(|e| format!("failed to load {}: {e}", library.display()))?;
+    if !path.starts_with(&dir) {
+        return Err(format!(
+            "{} is outside the grammar directory {}",
+            library.display(),
+            dir.display()
+        ));
+    }
+    Ok(path)
+}
+
+/// The grammar behind `Lang::External(id)`.
+pub(crate) fn get(id: u16) -> Registered {
+    let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    registry[id as usize].grammar.clone()
+}
+
+/// The registered grammar handling `ext`, if any.
+pub(crate) fn by_extension(ext: &str) -> Option<Lang> {
+    let registry = REGISTRY.read().unwrap_or_else(|e| e.into_inner());
+    registry
+        .iter()
+        .position(|e| e.extensions.iter().any(|x| x == ext))
+        .map(|id| Lang::External(id as u16))
+}
+
+#[cfg(feature = "external-grammars")]
+fn leak(s: String) -> &'static str {
+    Box::leak(s.into_boxed_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grammar(name: &str, ext: &str, library: PathBuf) -> ExternalGrammar {
+        ExternalGrammar {
+            name: name.into(),
+            extensions: vec![ext.into()],
+            library,
+            symbol: None,
+            symbol_query: String::new(),
+            reference_query: None,
+        }
+    }
+
+    #[test]
+    fn missing_library_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let library = dir.path().join("libtree-sitter-nonexistent.so");
+        let err = Lang::register(grammar("nonexistent", "nx", library), dir.path()).unwrap_err();
+        assert!(err.contains("cannot load") || err.contains("failed to load"), "{err}");
+        assert_eq!(Lang::from_extension("nx"), None);
+    }
+
+    #[cfg(feature = "external-grammars")]
+    #[test]
+    fn library_outside_grammar_dir_is_refused() {
+        let root = tempfile::tempdir().unwrap();
+        let grammars = root.path().join("grammars");
+        std::fs::create_dir(&grammars).unwrap();
+        let outside = root.path().join("libtree-sitter-stray.so");
+        std::fs::write(&outside, b"not a library").unwrap();
+
+        for library in [outside.clone(), grammars.join("../libtree-sitter-stray.so")] {
+            let err = Lang::register(grammar("stray", "sty", library), &grammars).unwrap_err();
+            assert!(err.contains("outside the grammar directory"), "{err}");
+        }
+        assert_eq!(Lang::from_extension("sty"), None);
+    }
+}
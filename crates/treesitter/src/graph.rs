@@ -0,0 +1,218 @@
+//! Symbol graph — cross-file queries over indexed references.
+//!
+//! Edges are resolved by name, from syntax rather than types: a call to
+//! `parse` links to every indexed definition named `parse`. Same-named
+//! symbols are conflated, but no call the grammar can see is missed, which
+//! is what an agent replacing a grep wants.
+
+use std::collections::BTreeMap;
+
+use super::references::ExtractedReference;
+use super::symbols::ExtractedSymbol;
+use super::CodeIndex;
+
+/// A function called from the symbol given to [`CodeIndex::callees`].
+#[derive(Debug, Clone)]
+pub struct Callee<'a> {
+    pub name: &'a str,
+    /// File of the first call site.
+    pub path: &'a str,
+    /// Line of the first call site.
+    pub line: usize,
+    /// Indexed definitions of the callee, as (file, symbol).
+    pub definitions: Vec<(&'a str, &'a ExtractedSymbol)>,
+}
+
+impl CodeIndex {
+    /// Every reference to `name`, optionally of one kind, by file and line.
+    /// An import matches if any segment of its path is `name`.
+    pub fn references(&self, name: &str, kind: Option<&str>) -> Vec<(&str, &ExtractedReference)> {
+        self.collect_refs(|r| kind.is_none_or(|k| r.kind == k) && mentions(r, name))
+    }
+
+    /// Call sites of `name`. Each reference's `scope` is the caller.
+    pub fn callers(&self, name: &str) -> Vec<(&str, &ExtractedReference)> {
+        self.collect_refs(|r| r.kind == "call" && r.name == name)
+    }
+
+    /// Functions called from within symbols named `name`, one entry per
+    /// callee, sorted by name.
+    pub fn callees(&self, name: &str) -> Vec<Callee<'_>> {
+        let mut callees: BTreeMap<&str, Callee<'_>> = BTreeMap::new();
+        for (path, r) in self.collect_refs(|r| r.kind == "call" && r.scope.as_deref() == Some(name)) {
+            callees.entry(r.name.as_str()).or_insert_with(|| Callee {
+                name: &r.name,
+                path,
+                line: r.line,
+                definitions: self.definitions(&r.name),
+            });
+        }
+        callees.into_values().collect()
+    }
+
+    /// Implementations of trait `name`, or subclasses of class `name`.
+    /// Each reference's `target` is the implementing type.
+    pub fn implementors(&self, name: &str) -> Vec<(&str, &ExtractedReference)> {
+        self.collect_refs(|r| r.kind == "impl" && r.name == name)
+    }
+
+    /// Imports of a module from other files. `module` is either a module
+    /// path (`store`, `crate::store`, `os.path`) or an indexed file, whose
+    /// module name is its stem, or its directory for `mod.rs` and
+    /// `__init__.py`.
+    pub fn dependents(&self, module: &str) -> Vec<(&str, &ExtractedReference)> {
+        let own_file = self.files.contains_key(module).then_some(module);
+        let name = match own_file {
+            Some(path) => module_name(path),
+            None => last_segment(module),
+        };
+        self.collect_refs(|r| r.kind == "import" && mentions(r, name))
+            .into_iter()
+            .filter(|(path, _)| Some(*path) != own_file)
+            .collect()
+    }
+
+    /// Indexed definitions named exactly `name`, by file and line.
+    fn definitions(&self, name: &str) -> Vec<(&str, &ExtractedSymbol)> {
+        let mut defs = Vec::new();
+        for (path, file) in &self.files {
+            for sym in &file.symbols {
+                if sym.name == name {
+                    defs.push((path.as_str(), sym));
+                }
+            }
+        }
+        defs.sort_by(|a, b| (a.0, a.1.start_line).cmp(&(b.0, b.1.start_line)));
+        defs
+    }
+
+    /// References passing `keep`, by file and line.
+    fn collect_refs(&self, keep: impl Fn(&ExtractedReference) -> bool) -> Vec<(&str, &ExtractedReference)> {
+        let mut refs = Vec::new();
+        for (path, file) in &self.files {
+            for r in &file.references {
+                if keep(r) {
+                    refs.push((path.as_str(), r));
+                }
+            }
+        }
+        refs.sort_by(|a, b| (a.0, a.1.line).cmp(&(b.0, b.1.line)));
+        refs
+    }
+}
+
+/// True if `r` refers to `name`: exactly, or as any segment of an import.
+fn mentions(r: &ExtractedReference, name: &str) -> bool {
+    if r.kind != "import" {
+        return r.name == name;
+    }
+    let name = last_segment(name);
+    r.name
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .any(|segment| segment == name)
+}
+
+/// Last segment of a `::`- or `.`-separated module path.
+fn last_segment(path: &str) -> &str {
+    path.rsplit([':', '.']).find(|s| !s.is_empty()).unwrap_or(path)
+}
+
+/// Module name of a source file path.
+fn module_name(path: &str) -> &str {
+    let mut parts = path.rsplit('/');
+    let file = parts.next().unwrap_or(path);
+    let stem = file.split('.').next().unwrap_or(file);
+    match stem {
+        "mod" | "__init__" => parts.next().unwrap_or(stem),
+        _ => stem,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::languages::Lang;
+    use crate::CodeIndex;
+
+    fn index() -> CodeIndex {
+        let mut idx = CodeIndex::new();
+        idx.index_source(
+            "src/store.rs",
+            Lang::Rust,
+            br#"
+pub trait Backend {
+    fn load(&self) -> Vec<u8>;
+}
+
+pub fn open_store() -> Store {
+    let data = read_all();
+    Store::new(data)
+}
+
+fn read_all() -> Vec<u8> { Vec::new() }
+"#,
+        )
+        .unwrap();
+        idx.index_source(
+            "src/main.rs",
+            Lang::Rust,
+            br#"
+mod store;
+use crate::store::{open_store, Backend};
+
+struct Disk;
+
+impl Backend for Disk {
+    fn load(&self) -> Vec<u8> { Vec::new() }
+}
+
+fn main() {
+    let s = open_store();
+}
+"#,
+        )
+        .unwrap();
+        idx
+    }
+
+    #[test]
+    fn callers_and_callees() {
+        let idx = index();
+        let callers = idx.callers("open_store");
+        assert_eq!(callers.len(), 1);
+        assert_eq!(callers[0].0, "src/main.rs");
+        assert_eq!(callers[0].1.scope.as_deref(), Some("main"));
+
+        let callees = idx.callees("open_store");
+        let names: Vec<&str> = callees.iter().map(|c| c.name).collect();
+        assert_eq!(names, vec!["new", "read_all"]);
+        let read_all = &callees[1];
+        assert_eq!(read_all.definitions.len(), 1);
+        assert_eq!(read_all.definitions[0].0, "src/store.rs");
+    }
+
+    #[test]
+    fn implementors_and_dependents() {
+        let idx = index();
+        let impls = idx.implementors("Backend");
+        assert_eq!(impls.len(), 1);
+        assert_eq!(impls[0].1.target.as_deref(), Some("Disk"));
+
+        for module in ["src/store.rs", "store", "crate::store"] {
+            let deps = idx.dependents(module);
+            assert!(!deps.is_empty(), "{module}");
+            assert!(deps.iter().all(|(path, _)| *path == "src/main.rs"));
+        }
+        assert!(idx.dependents("src/main.rs").is_empty());
+    }
+
+    #[test]
+    fn references_span_kinds() {
+        let idx = index();
+        let refs = idx.references("Backend", None);
+        let kinds: Vec<&str> = refs.iter().map(|(_, r)| r.kind.as_str()).collect();
+        assert!(kinds.contains(&"import"));
+        assert!(kinds.contains(&"impl"));
+        assert!(kinds.contains(&"type"));
+        assert!(idx.references("Backend", Some("call")).is_empty());
+    }
+}
@@ -1,6 +1,7 @@
 //! CodeIndexHandler — ToolPeer wrapping CodeIndex.
 //!
 //! Receives XML requests for indexing and search operations.
+//! SemanticSearchHandler answers `semantic-search` from a SemanticIndex.
 
 use std::sync::Arc;
 
@@ -8,6 +9,7 @@ use async_trait::async_trait;
 use rust_pipeline::prelude::*;
 use tokio::sync::Mutex;
 
+use super::semantic::SemanticIndex;
 use super::CodeIndex;
 use agentos_events::{ToolPeer, ToolResponse};
 
@@ -42,8 +44,12 @@ impl Handler for CodeIndexHandler {
                 let mut idx = self.index.lock().await;
                 match idx.index_directory(std::path::Path::new(&path)) {
                     Ok(stats) => ToolResponse::ok(&format!(
-                        "indexed {} files ({} symbols), skipped {}",
-                        stats.files_indexed, stats.total_symbols, stats.files_skipped
+                        "indexed {} files ({} unchanged, {} symbols), removed {}, skipped {}",
+                        stats.files_indexed + stats.files_unchanged,
+                        stats.files_unchanged,
+                        stats.total_symbols,
+                        stats.files_removed,
+                        stats.files_skipped
                     )),
                     Err(e) => ToolResponse::err(&e),
                 }
@@ -69,6 +75,72 @@ impl Handler for CodeIndexHandler {
                     xml
                 ))
             }
+            "references" | "callers" | "implementors" | "dependents" => {
+                let query = extract_tag(&xml_str, "query")
+                    .or_else(|| extract_tag(&xml_str, "path"))
+                    .unwrap_or_default();
+                let kind = extract_tag(&xml_str, "kind");
+                let idx = self.index.lock().await;
+                let refs = match action.as_str() {
+                    "references" => idx.references(&query, kind.as_deref()),
+                    "callers" => idx.callers(&query),
+                    "implementors" => idx.implementors(&query),
+                    _ => idx.dependents(&query),
+                };
+                let xml = refs
+                    .iter()
+                    .map(|(path, r)| {
+                        let scope = r
+                            .scope
+                            .as_ref()
+                            .map(|s| format!(" scope=\"{s}\""))
+                            .unwrap_or_default();
+                        let target = r
+                            .target
+                            .as_ref()
+                            .map(|t| format!(" target=\"{t}\""))
+                            .unwrap_or_default();
+                        format!(
+                            "<ref file=\"{}\" line=\"{}\" kind=\"{}\"{}{}>{}</ref>",
+                            path, r.line, r.kind, scope, target, r.name
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                ToolResponse::ok(&format!(
+                    "<{action} of=\"{}\" count=\"{}\">\n{}\n</{action}>",
+                    query,
+                    refs.len(),
+                    xml
+                ))
+            }
+            "callees" => {
+                let query = extract_tag(&xml_str, "query").unwrap_or_default();
+                let idx = self.index.lock().await;
+                let callees = idx.callees(&query);
+                let xml = callees
+                    .iter()
+                    .map(|c| {
+                        let defs = c
+                            .definitions
+                            .iter()
+                            .map(|(path, sym)| format!("{}:{}", path, sym.start_line))
+                            .collect::<Vec<_>>()
+                            .join(",");
+                        format!(
+                            "<callee file=\"{}\" line=\"{}\" defined=\"{}\">{}</callee>",
+                            c.path, c.line, defs, c.name
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                ToolResponse::ok(&format!(
+                    "<callees of=\"{}\" count=\"{}\">\n{}\n</callees>",
+                    query,
+                    callees.len(),
+                    xml
+                ))
+            }
             "codebase_map" => {
                 let idx = self.index.lock().await;
                 let map = idx.codebase_map();
@@ -107,16 +179,19 @@ impl ToolPeer for CodeIndexHandler {
 
     fn wit(&self) -> &str {
         r#"
-/// Tree-sitter code indexing: index files/directories, search symbols, or get a codebase map.
+/// Tree-sitter code indexing: index files/directories, search symbols, get a codebase map,
+/// or find usages: references, callers, callees, implementors and module dependents.
 interface codebase-index {
     record request {
         /// The indexing operation to perform
         action: string,
         /// File or directory path (for index_file, index_directory)
         path: option<string>,
-        /// Search query (for search action)
+        /// Symbol name (for search, references, callers, callees, implementors),
+        /// or module name or file path (for dependents)
         query: option<string>,
-        /// Symbol kind filter (for search action, e.g. 'function', 'struct')
+        /// Kind filter: symbol kind for search (e.g. 'function', 'struct'),
+        /// reference kind for references ('call', 'type', 'import', 'impl')
         kind: option<string>,
     }
     handle: func(req: request) -> result<string, string>;
@@ -125,6 +200,98 @@ interface codebase-index {
     }
 }
 
+/// Results returned when a request gives no limit.
+const DEFAULT_SEARCH_LIMIT: usize = 5;
+/// Most results one request may ask for.
+const MAX_SEARCH_LIMIT: usize = 20;
+
+/// Pipeline handler searching a SemanticIndex.
+pub struct SemanticSearchHandler {
+    index: Arc<Mutex<SemanticIndex>>,
+}
+
+impl SemanticSearchHandler {
+    pub fn new(index: Arc<Mutex<SemanticIndex>>) -> Self {
+        Self { index }
+    }
+}
+
+#[async_trait]
+impl Handler for SemanticSearchHandler {
+    async fn handle(&self, payload: ValidatedPayload, _ctx: HandlerContext) -> HandlerResult {
+        let xml_str = String::from_utf8_lossy(&payload.xml);
+        let query = extract_tag(&xml_str, "query").unwrap_or_default();
+        let response = if query.trim().is_empty() {
+            ToolResponse::err("missing query")
+        } else {
+            let limit = extract_tag(&xml_str, "limit")
+                .and_then(|l| l.trim().parse().ok())
+                .unwrap_or(DEFAULT_SEARCH_LIMIT)
+                .clamp(1, MAX_SEARCH_LIMIT);
+            let under = extract_tag(&xml_str, "path").filter(|p| !p.trim().is_empty());
+            let idx = self.index.lock().await;
+            let hits = idx.search(&query, limit, under.as_deref());
+            let xml = hits
+                .iter()
+                .map(|hit| {
+                    let symbol = hit
+                        .chunk
+                        .symbol
+                        .as_ref()
+                        .map(|s| format!(" symbol=\"{s}\""))
+                        .unwrap_or_default();
+                    let kind = hit
+                        .chunk
+                        .kind
+                        .as_ref()
+                        .map(|k| format!(" kind=\"{k}\""))
+                        .unwrap_or_default();
+                    format!(
+                        "<hit file=\"{}\" lines=\"{}-{}\"{}{} score=\"{:.3}\">\n{}\n</hit>",
+                        hit.path, hit.chunk.start_line, hit.chunk.end_line, symbol, kind, hit.score, hit.chunk.text
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            ToolResponse::ok(&format!(
+                "<results query=\"{}\" count=\"{}\">\n{}\n</results>",
+                query,
+                hits.len(),
+                xml
+            ))
+        };
+
+        Ok(HandlerResponse::Reply {
+            payload_xml: response,
+        })
+    }
+}
+
+#[async_trait]
+impl ToolPeer for SemanticSearchHandler {
+    fn name(&self) -> &str {
+        "semantic-search"
+    }
+
+    fn wit(&self) -> &str {
+        r#"
+/// Search the mounted drive's code and documents by meaning, not by name.
+/// Returns the best-matching snippets with their file and line range.
+interface semantic-search {
+    record request {
+        /// What you are looking for, in words (e.g. 'where retries are scheduled')
+        query: string,
+        /// Maximum number of snippets (default 5, at most 20)
+        limit: option<u32>,
+        /// Only search files under this directory
+        path: option<string>,
+    }
+    handle: func(req: request) -> result<string, string>;
+}
+"#
+    }
+}
+
 /// Extract text content between `<tag>` and `</tag>`.
 fn extract_tag(xml: &str, tag: &str) -> Option<String> {
     let open = format!("<{tag}>");
@@ -179,6 +346,43 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn handler_callers() {
+        let index = Arc::new(Mutex::new(CodeIndex::new()));
+        index
+            .lock()
+            .await
+            .index_source(
+                "test.rs",
+                crate::languages::Lang::Rust,
+                b"fn helper() {}\nfn run() { helper(); }",
+            )
+            .unwrap();
+
+        let handler = CodeIndexHandler::new(index);
+        let payload = ValidatedPayload {
+            xml: b"<CodeIndexRequest><action>callers</action><query>helper</query></CodeIndexRequest>"
+                .to_vec(),
+            tag: "CodeIndexRequest".into(),
+        };
+        let ctx = HandlerContext {
+            thread_id: "t1".into(),
+            from: "agent".into(),
+            own_name: "codebase-index".into(),
+        };
+
+        let result = handler.handle(payload, ctx).await.unwrap();
+        match result {
+            HandlerResponse::Reply { payload_xml } => {
+                let xml = String::from_utf8(payload_xml).unwrap();
+                assert!(xml.contains("<success>true</success>"));
+                assert!(xml.contains("count=&quot;1&quot;"));
+                assert!(xml.contains("scope=&quot;run&quot;"));
+            }
+            _ => panic!("expected Reply"),
+        }
+    }
+
     #[tokio::test]
     async fn handler_unknown_action() {
         let index = Arc::new(Mutex::new(CodeIndex::new()));
@@ -214,4 +418,45 @@ mod tests {
         assert_eq!(iface.request_tag(), "CodebaseIndexRequest");
         assert!(iface.request.fields.iter().any(|f| f.name == "action"));
     }
+
+    #[tokio::test]
+    async fn semantic_search_returns_snippets() {
+        let provider = Arc::new(agentos_embedding::tfidf::TfIdfProvider::hashed(&[], 256));
+        let index = Arc::new(Mutex::new(SemanticIndex::new(provider)));
+        index
+            .lock()
+            .await
+            .index_source(
+                "src/net.rs",
+                b"/// Retry a failed network request with backoff.\nfn retry_with_backoff() {}\n\nfn render_page() {}\n",
+            )
+            .unwrap();
+
+        let handler = SemanticSearchHandler::new(index);
+        let payload = ValidatedPayload {
+            xml: b"<SemanticSearchRequest><query>network retry backoff</query><limit>1</limit></SemanticSearchRequest>"
+                .to_vec(),
+            tag: "SemanticSearchRequest".into(),
+        };
+        let ctx = HandlerContext {
+            thread_id: "t1".into(),
+            from: "agent".into(),
+            own_name: "semantic-search".into(),
+        };
+
+        let result = handler.handle(payload, ctx).await.unwrap();
+        match result {
+            HandlerResponse::Reply { payload_xml } => {
+                let xml = String::from_utf8(payload_xml).unwrap();
+                assert!(xml.contains("<success>true</success>"));
+                assert!(xml.contains("count=&quot;1&quot;"));
+                assert!(xml.contains("lines=&quot;1-2&quot;"));
+                assert!(xml.contains("retry_with_backoff"));
+            }
+            _ => panic!("expected Reply"),
+        }
+
+        let iface = agentos_wit::parser::parse_wit(handler.wit()).unwrap();
+        assert_eq!(iface.request_tag(), "SemanticSearchRequest");
+    }
 }
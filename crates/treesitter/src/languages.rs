@@ -1,31 +1,103 @@
 //! Language grammars and query patterns for tree-sitter.
 //!
-//! Ported from ClaudeRLM. Supports Rust and Python initially.
+//! Ported from ClaudeRLM. Built in: Rust, Python, TypeScript/TSX,
+//! JavaScript, Go, C, C++, Java, and YAML/TOML keys. Further grammars can
+//! be loaded at runtime with [`Lang::register`].
+//!
+//! Symbol queries capture `@name` and end each pattern with `) @<kind>` on
+//! its own line; the kind is read back from that line.
+
+use std::path::Path;
 
 use tree_sitter::Language;
 
+use crate::external::{self, ExternalGrammar};
+
 /// Supported languages with their tree-sitter grammars and symbol queries.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Lang {
     Rust,
     Python,
+    TypeScript,
+    Tsx,
+    JavaScript,
+    Go,
+    C,
+    Cpp,
+    Java,
+    Yaml,
+    Toml,
+    /// A grammar loaded with [`Lang::register`].
+    External(u16),
 }
 
 impl Lang {
-    /// Detect language from file extension.
+    /// Every built-in language.
+    pub const BUILTIN: [Lang; 11] = [
+        Self::Rust,
+        Self::Python,
+        Self::TypeScript,
+        Self::Tsx,
+        Self::JavaScript,
+        Self::Go,
+        Self::C,
+        Self::Cpp,
+        Self::Java,
+        Self::Yaml,
+        Self::Toml,
+    ];
+
+    /// Detect language from file extension. Registered grammars take
+    /// precedence over built-in ones.
     pub fn from_extension(ext: &str) -> Option<Self> {
+        if let Some(lang) = external::by_extension(ext) {
+            return Some(lang);
+        }
         match ext {
             "rs" => Some(Self::Rust),
             "py" | "pyi" => Some(Self::Python),
+            "ts" | "mts" | "cts" => Some(Self::TypeScript),
+            "tsx" => Some(Self::Tsx),
+            "js" | "mjs" | "cjs" | "jsx" => Some(Self::JavaScript),
+            "go" => Some(Self::Go),
+            "c" | "h" => Some(Self::C),
+            "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => Some(Self::Cpp),
+            "java" => Some(Self::Java),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
             _ => None,
         }
     }
 
+    /// Detect language from a path's extension.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let ext = std::path::Path::new(path).extension()?.to_str()?;
+        Self::from_extension(ext)
+    }
+
+    /// Load a grammar from a compiled parser library and make it available
+    /// under its extensions. The library must live inside `grammar_dir`,
+    /// and loading needs the `external-grammars` feature. Registering a
+    /// name again returns the language registered first.
+    pub fn register(grammar: ExternalGrammar, grammar_dir: &Path) -> Result<Self, String> {
+        external::register(grammar, grammar_dir)
+    }
+
     /// Get the tree-sitter Language grammar.
     pub fn grammar(&self) -> Language {
         match self {
             Self::Rust => tree_sitter_rust::LANGUAGE.into(),
             Self::Python => tree_sitter_python::LANGUAGE.into(),
+            Self::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Self::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Self::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Self::Go => tree_sitter_go::LANGUAGE.into(),
+            Self::C => tree_sitter_c::LANGUAGE.into(),
+            Self::Cpp => tree_sitter_cpp::LANGUAGE.into(),
+            Self::Java => tree_sitter_java::LANGUAGE.into(),
+            Self::Yaml => tree_sitter_yaml::LANGUAGE.into(),
+            Self::Toml => tree_sitter_toml_ng::LANGUAGE.into(),
+            Self::External(id) => external::get(*id).language,
         }
     }
 
@@ -34,6 +106,36 @@ impl Lang {
         match self {
             Self::Rust => RUST_QUERY,
             Self::Python => PYTHON_QUERY,
+            Self::TypeScript | Self::Tsx => TYPESCRIPT_QUERY,
+            Self::JavaScript => JAVASCRIPT_QUERY,
+            Self::Go => GO_QUERY,
+            Self::C => C_QUERY,
+            Self::Cpp => CPP_QUERY,
+            Self::Java => JAVA_QUERY,
+            Self::Yaml => YAML_QUERY,
+            Self::Toml => TOML_QUERY,
+            Self::External(id) => external::get(*id).symbol_query,
+        }
+    }
+
+    /// Get the reference extraction query for this language.
+    ///
+    /// Captures: `@call` (callee name), `@type` (type usage), `@import`
+    /// (imported module path), and `@trait` with `@implementor` (trait
+    /// implementation or subclass).
+    pub fn reference_query(&self) -> &'static str {
+        match self {
+            Self::Rust => RUST_REFERENCE_QUERY,
+            Self::Python => PYTHON_REFERENCE_QUERY,
+            Self::TypeScript | Self::Tsx => TYPESCRIPT_REFERENCE_QUERY,
+            Self::JavaScript => JAVASCRIPT_REFERENCE_QUERY,
+            Self::Go => GO_REFERENCE_QUERY,
+            Self::C => C_REFERENCE_QUERY,
+            Self::Cpp => CPP_REFERENCE_QUERY,
+            Self::Java => JAVA_REFERENCE_QUERY,
+            // Config files define keys but reference nothing.
+            Self::Yaml | Self::Toml => "",
+            Self::External(id) => external::get(*id).reference_query,
         }
     }
 
@@ -42,6 +144,16 @@ impl Lang {
         match self {
             Self::Rust => "rust",
             Self::Python => "python",
+            Self::TypeScript => "typescript",
+            Self::Tsx => "tsx",
+            Self::JavaScript => "javascript",
+            Self::Go => "go",
+            Self::C => "c",
+            Self::Cpp => "cpp",
+            Self::Java => "java",
+            Self::Yaml => "yaml",
+            Self::Toml => "toml",
+            Self::External(id) => external::get(*id).name,
         }
     }
 }
@@ -118,3 +230,823 @@ const PYTHON_QUERY: &str = r#"
   )
 ) @class
 "#;
+
+const RUST_REFERENCE_QUERY: &str = r#"
+; Calls: plain, method, path and turbofish
+(call_expression
+  function: [
+    (identifier) @call
+    (field_expression field: (field_identifier) @call)
+    (scoped_identifier name: (identifier) @call)
+    (generic_function
+      function: [
+        (identifier) @call
+        (field_expression field: (field_identifier) @call)
+        (scoped_identifier name: (identifier) @call)
+      ])
+  ])
+
+; Macro calls
+(macro_invocation
+  macro: [
+    (identifier) @call
+    (scoped_identifier name: (identifier) @call)
+  ])
+
+; Type usages
+(type_identifier) @type
+
+; Imports and out-of-line modules
+(use_declaration argument: (_) @import)
+(mod_item name: (identifier) @import !body)
+
+; Trait implementations
+(impl_item
+  trait: [
+    (type_identifier) @trait
+    (scoped_type_identifier name: (type_identifier) @trait)
+    (generic_type type: (type_identifier) @trait)
+  ]
+  type: (_) @implementor)
+"#;
+
+const PYTHON_REFERENCE_QUERY: &str = r#"
+; Calls: plain and attribute
+(call
+  function: [
+    (identifier) @call
+    (attribute attribute: (identifier) @call)
+  ])
+
+; Imports
+(import_statement
+  name: [
+    (dotted_name) @import
+    (aliased_import name: (dotted_name) @import)
+  ])
+(import_from_statement
+  module_name: [
+    (dotted_name) @import
+    (relative_import) @import
+  ])
+
+; Subclasses
+(class_definition
+  name: (identifier) @implementor
+  superclasses: (argument_list
+    [
+      (identifier) @trait
+      (attribute) @trait
+    ]))
+"#;
+
+const JAVASCRIPT_QUERY: &str = r#"
+; Functions
+(function_declaration
+  name: (identifier) @name
+) @function
+
+; Generator functions
+(generator_function_declaration
+  name: (identifier) @name
+) @function
+
+; Functions bound to a const/let
+(lexical_declaration
+  (variable_declarator
+    name: (identifier) @name
+    value: [(arrow_function) (function_expression)])
+) @function
+
+; Classes
+(class_declaration
+  name: (identifier) @name
+) @class
+
+; Methods
+(method_definition
+  name: (property_identifier) @name
+) @method
+"#;
+
+const TYPESCRIPT_QUERY: &str = r#"
+; Functions
+(function_declaration
+  name: (identifier) @name
+) @function
+
+; Function overload signatures
+(function_signature
+  name: (identifier) @name
+) @function
+
+; Functions bound to a const/let
+(lexical_declaration
+  (variable_declarator
+    name: (identifier) @name
+    value: [(arrow_function) (function_expression)])
+) @function
+
+; Classes
+(class_declaration
+  name: (type_identifier) @name
+) @class
+
+; Abstract classes
+(abstract_class_declaration
+  name: (type_identifier) @name
+) @class
+
+; Interfaces
+(interface_declaration
+  name: (type_identifier) @name
+) @interface
+
+; Type aliases
+(type_alias_declaration
+  name: (type_identifier) @name
+) @type_alias
+
+; Enums
+(enum_declaration
+  name: (identifier) @name
+) @enum
+
+; Methods
+(method_definition
+  name: (property_identifier) @name
+) @method
+"#;
+
+const GO_QUERY: &str = r#"
+; Functions
+(function_declaration
+  name: (identifier) @name
+) @function
+
+; Methods
+(method_declaration
+  name: (field_identifier) @name
+) @method
+
+; Structs
+(type_spec
+  name: (type_identifier) @name
+  type: (struct_type)
+) @struct
+
+; Interfaces
+(type_spec
+  name: (type_identifier) @name
+  type: (interface_type)
+) @interface
+
+; Type aliases
+(type_alias
+  name: (type_identifier) @name
+) @type_alias
+
+; Constants
+(const_spec
+  name: (identifier) @name
+) @const
+"#;
+
+const C_QUERY: &str = r#"
+; Functions
+(function_definition
+  declarator: (function_declarator
+    declarator: (identifier) @name)
+) @function
+
+; Functions returning pointers
+(function_definition
+  declarator: (pointer_declarator
+    declarator: (function_declarator
+      declarator: (identifier) @name))
+) @function
+
+; Structs
+(struct_specifier
+  name: (type_identifier) @name
+  body: (field_declaration_list)
+) @struct
+
+; Unions
+(union_specifier
+  name: (type_identifier) @name
+  body: (field_declaration_list)
+) @union
+
+; Enums
+(enum_specifier
+  name: (type_identifier) @name
+  body: (enumerator_list)
+) @enum
+
+; Typedefs
+(type_definition
+  declarator: (type_identifier) @name
+) @type_alias
+
+; Macros
+(preproc_def
+  name: (identifier) @name
+) @macro
+
+; Function-like macros
+(preproc_function_def
+  name: (identifier) @name
+) @macro
+"#;
+
+const CPP_QUERY: &str = r#"
+; Functions and out-of-line methods
+(function_definition
+  declarator: (function_declarator
+    declarator: [
+      (identifier)
+      (field_identifier)
+      (qualified_identifier)
+      (destructor_name)
+      (operator_name)
+    ] @name)
+) @function
+
+; Functions returning pointers or references
+(function_definition
+  declarator: [
+    (pointer_declarator
+      declarator: (function_declarator
+        declarator: [(identifier) (qualified_identifier)] @name))
+    (reference_declarator
+      (function_declarator
+        declarator: [(identifier) (qualified_identifier)] @name))
+  ]
+) @function
+
+; Method declarations inside a class
+(field_declaration
+  declarator: (function_declarator
+    declarator: (field_identifier) @name)
+) @method
+
+; Classes
+(class_specifier
+  name: (type_identifier) @name
+  body: (field_declaration_list)
+) @class
+
+; Structs
+(struct_specifier
+  name: (type_identifier) @name
+  body: (field_declaration_list)
+) @struct
+
+; Enums
+(enum_specifier
+  name: (type_identifier) @name
+  body: (enumerator_list)
+) @enum
+
+; Namespaces
+(namespace_definition
+  name: (namespace_identifier) @name
+) @module
+
+; Typedefs
+(type_definition
+  declarator: (type_identifier) @name
+) @type_alias
+
+; Using aliases
+(alias_declaration
+  name: (type_identifier) @name
+) @type_alias
+
+; Macros
+(preproc_def
+  name: (identifier) @name
+) @macro
+"#;
+
+const JAVA_QUERY: &str = r#"
+; Classes
+(class_declaration
+  name: (identifier) @name
+) @class
+
+; Interfaces
+(interface_declaration
+  name: (identifier) @name
+) @interface
+
+; Enums
+(enum_declaration
+  name: (identifier) @name
+) @enum
+
+; Records
+(record_declaration
+  name: (identifier) @name
+) @record
+
+; Methods
+(method_declaration
+  name: (identifier) @name
+) @method
+
+; Constructors
+(constructor_declaration
+  name: (identifier) @name
+) @constructor
+"#;
+
+const YAML_QUERY: &str = r#"
+; Mapping keys
+(block_mapping_pair
+  key: (flow_node) @name
+) @key
+
+; Inline mapping keys
+(flow_pair
+  key: (flow_node) @name
+) @key
+"#;
+
+const TOML_QUERY: &str = r#"
+; Tables
+(table
+  [(bare_key) (dotted_key) (quoted_key)] @name
+) @table
+
+; Arrays of tables
+(table_array_element
+  [(bare_key) (dotted_key) (quoted_key)] @name
+) @table
+
+; Keys
+(pair
+  [(bare_key) (dotted_key) (quoted_key)] @name
+) @key
+"#;
+
+const JAVASCRIPT_REFERENCE_QUERY: &str = r#"
+(call_expression
+  function: [
+    (identifier) @call
+    (member_expression property: (property_identifier) @call)
+  ])
+
+(new_expression constructor: (identifier) @call)
+
+(import_statement source: (string) @import)
+
+(class_declaration
+  name: (identifier) @implementor
+  (class_heritage (identifier) @trait))
+"#;
+
+const TYPESCRIPT_REFERENCE_QUERY: &str = r#"
+(call_expression
+  function: [
+    (identifier) @call
+    (member_expression property: (property_identifier) @call)
+  ])
+
+(new_expression constructor: (identifier) @call)
+
+(type_identifier) @type
+
+(import_statement source: (string) @import)
+
+(class_declaration
+  name: (type_identifier) @implementor
+  (class_heritage
+    (extends_clause value: (identifier) @trait)))
+
+(class_declaration
+  name: (type_identifier) @implementor
+  (class_heritage
+    (implements_clause (type_identifier) @trait)))
+"#;
+
+const GO_REFERENCE_QUERY: &str = r#"
+(call_expression
+  function: [
+    (identifier) @call
+    (selector_expression field: (field_identifier) @call)
+  ])
+
+(type_identifier) @type
+
+(import_spec path: (interpreted_string_literal) @import)
+"#;
+
+const C_REFERENCE_QUERY: &str = r#"
+(call_expression
+  function: [
+    (identifier) @call
+    (field_expression field: (field_identifier) @call)
+  ])
+
+(type_identifier) @type
+
+(preproc_include path: [(string_literal) (system_lib_string)] @import)
+"#;
+
+const CPP_REFERENCE_QUERY: &str = r#"
+(call_expression
+  function: [
+    (identifier) @call
+    (field_expression field: (field_identifier) @call)
+    (qualified_identifier name: (identifier) @call)
+    (template_function name: (identifier) @call)
+  ])
+
+(type_identifier) @type
+
+(preproc_include path: [(string_literal) (system_lib_string)] @import)
+
+(class_specifier
+  name: (type_identifier) @implementor
+  (base_class_clause (type_identifier) @trait))
+
+(struct_specifier
+  name: (type_identifier) @implementor
+  (base_class_clause (type_identifier) @trait))
+"#;
+
+const JAVA_REFERENCE_QUERY: &str = r#"
+(method_invocation name: (identifier) @call)
+
+(object_creation_expression type: (type_identifier) @call)
+
+(type_identifier) @type
+
+(import_declaration (scoped_identifier) @import)
+
+(class_declaration
+  name: (identifier) @implementor
+  superclass: (superclass (type_identifier) @trait))
+
+(class_declaration
+  name: (identifier) @implementor
+  interfaces: (super_interfaces (type_list (type_identifier) @trait)))
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::references::{extract_references, ExtractedReference};
+    use crate::symbols::{self, extract_symbols, ExtractedSymbol};
+    use tree_sitter::Query;
+
+    fn symbols_of(lang: Lang, source: &str) -> Vec<ExtractedSymbol> {
+        extract_symbols(lang, source.as_bytes()).unwrap()
+    }
+
+    fn refs_of(lang: Lang, source: &str) -> Vec<ExtractedReference> {
+        let tree = symbols::parse(lang, source.as_bytes(), None).unwrap();
+        extract_references(lang, &tree, source.as_bytes()).unwrap()
+    }
+
+    /// Assert each `(name, kind)` was captured.
+    fn assert_symbols(lang: Lang, source: &str, expected: &[(&str, &str)]) {
+        let syms = symbols_of(lang, source);
+        for (name, kind) in expected {
+            assert!(
+                syms.iter().any(|s| s.name == *name && s.kind == *kind),
+                "{lang:?}: no {kind} {name} in {:?}",
+                syms.iter().map(|s| (&s.name, &s.kind)).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    /// Assert each `(kind, name, scope or impl target)` reference was captured.
+    fn assert_refs(lang: Lang, source: &str, expected: &[(&str, &str, Option<&str>)]) {
+        let refs = refs_of(lang, source);
+        for (kind, name, context) in expected {
+            assert!(
+                refs.iter().any(|r| {
+                    let actual = if r.kind == "impl" { &r.target } else { &r.scope };
+                    r.kind == *kind
+                        && r.name == *name
+                        && context.is_none_or(|c| actual.as_deref() == Some(c))
+                }),
+                "{lang:?}: no {kind} {name} ({context:?}) in {refs:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn builtin_queries_compile_and_every_pattern_has_a_kind() {
+        for lang in Lang::BUILTIN {
+            let grammar = lang.grammar();
+            let symbols = Query::new(&grammar, lang.symbol_query())
+                .unwrap_or_else(|e| panic!("{lang:?} symbol query: {e}"));
+            Query::new(&grammar, lang.reference_query())
+                .unwrap_or_else(|e| panic!("{lang:?} reference query: {e}"));
+            let kinds = lang
+                .symbol_query()
+                .lines()
+                .filter(|l| l.trim().starts_with(") @"))
+                .count();
+            assert_eq!(symbols.pattern_count(), kinds, "{lang:?}");
+        }
+    }
+
+    #[test]
+    fn detects_languages_by_extension() {
+        let cases = [
+            ("a.ts", Lang::TypeScript),
+            ("a.tsx", Lang::Tsx),
+            ("a.mjs", Lang::JavaScript),
+            ("a.jsx", Lang::JavaScript),
+            ("a.go", Lang::Go),
+            ("a.h", Lang::C),
+            ("a.hpp", Lang::Cpp),
+            ("A.java", Lang::Java),
+            ("a.yml", Lang::Yaml),
+            ("Cargo.toml", Lang::Toml),
+        ];
+        for (path, lang) in cases {
+            assert_eq!(Lang::from_path(path), Some(lang), "{path}");
+        }
+        assert_eq!(Lang::from_path("README.md"), None);
+    }
+
+    #[test]
+    fn typescript_captures() {
+        let source = r#"
+import { readFile } from "./fs";
+export interface Shape { area(): number; }
+type Id = string;
+enum Color { Red }
+export abstract class Base {}
+export class Circle extends Base implements Shape {
+  area(): number { return compute(this.r); }
+}
+export function compute(r: number): number { return r * r; }
+const double = (x: number) => x * 2;
+"#;
+        assert_symbols(
+            Lang::TypeScript,
+            source,
+            &[
+                ("Shape", "interface"),
+                ("Id", "type_alias"),
+                ("Color", "enum"),
+                ("Base", "class"),
+                ("Circle", "class"),
+                ("area", "method"),
+                ("compute", "function"),
+                ("double", "function"),
+            ],
+        );
+        assert_refs(
+            Lang::TypeScript,
+            source,
+            &[
+                ("import", "./fs", None),
+                ("call", "compute", Some("area")),
+                ("impl", "Base", Some("Circle")),
+                ("impl", "Shape", Some("Circle")),
+                ("type", "Shape", None),
+            ],
+        );
+        let circle = symbols_of(Lang::TypeScript, source);
+        let area = circle.iter().find(|s| s.name == "area").unwrap();
+        assert_eq!(area.parent_name.as_deref(), Some("Circle"));
+    }
+
+    #[test]
+    fn tsx_captures() {
+        assert_symbols(
+            Lang::Tsx,
+            "export function App() { return <div className=\"app\" />; }",
+            &[("App", "function")],
+        );
+    }
+
+    #[test]
+    fn javascript_captures() {
+        let source = r#"
+import helper from './helper.js';
+function main() { helper(); }
+function* gen() {}
+const add = (a, b) => a + b;
+class Widget extends Base {
+  render() { return new View(); }
+}
+"#;
+        assert_symbols(
+            Lang::JavaScript,
+            source,
+            &[
+                ("main", "function"),
+                ("gen", "function"),
+                ("add", "function"),
+                ("Widget", "class"),
+                ("render", "method"),
+            ],
+        );
+        assert_refs(
+            Lang::JavaScript,
+            source,
+            &[
+                ("import", "./helper.js", None),
+                ("call", "helper", Some("main")),
+                ("call", "View", Some("render")),
+                ("impl", "Base", Some("Widget")),
+            ],
+        );
+    }
+
+    #[test]
+    fn go_captures() {
+        let source = r#"
+package main
+
+import "fmt"
+
+type Server struct { port int }
+type Handler interface { Serve() }
+type ID = string
+const MaxConns = 10
+
+func (s *Server) Serve() { fmt.Println(s.port) }
+func main() { s := &Server{}; s.Serve() }
+"#;
+        assert_symbols(
+            Lang::Go,
+            source,
+            &[
+                ("Server", "struct"),
+                ("Handler", "interface"),
+                ("ID", "type_alias"),
+                ("MaxConns", "const"),
+                ("Serve", "method"),
+                ("main", "function"),
+            ],
+        );
+        assert_refs(
+            Lang::Go,
+            source,
+            &[
+                ("import", "fmt", None),
+                ("call", "Println", Some("Serve")),
+                ("call", "Serve", Some("main")),
+                ("type", "Server", None),
+            ],
+        );
+    }
+
+    #[test]
+    fn c_captures() {
+        let source = r#"
+#include <stdio.h>
+#include "util.h"
+#define MAX 10
+#define SQ(x) ((x)*(x))
+typedef struct point { int x; } point_t;
+enum color { RED };
+static char *name(void) { return "c"; }
+int main(void) { printf("%d", SQ(2)); return 0; }
+"#;
+        assert_symbols(
+            Lang::C,
+            source,
+            &[
+                ("MAX", "macro"),
+                ("SQ", "macro"),
+                ("point", "struct"),
+                ("point_t", "type_alias"),
+                ("color", "enum"),
+                ("name", "function"),
+                ("main", "function"),
+            ],
+        );
+        assert_refs(
+            Lang::C,
+            source,
+            &[
+                ("import", "stdio.h", None),
+                ("import", "util.h", None),
+                ("call", "printf", Some("main")),
+            ],
+        );
+    }
+
+    #[test]
+    fn cpp_captures() {
+        let source = r#"
+#include <vector>
+namespace geo {
+class Shape {
+public:
+  virtual double area() const;
+};
+class Square : public Shape {
+public:
+  double area() const override { return side * side; }
+  double side;
+};
+double total(const std::vector<Shape*>& shapes) { return helper(shapes); }
+}
+using Id = int;
+"#;
+        assert_symbols(
+            Lang::Cpp,
+            source,
+            &[
+                ("geo", "module"),
+                ("Shape", "class"),
+                ("area", "method"),
+                ("Square", "class"),
+                ("area", "function"),
+                ("total", "function"),
+                ("Id", "type_alias"),
+            ],
+        );
+        assert_refs(
+            Lang::Cpp,
+            source,
+            &[
+                ("import", "vector", None),
+                ("call", "helper", Some("total")),
+                ("impl", "Shape", Some("Square")),
+            ],
+        );
+    }
+
+    #[test]
+    fn java_captures() {
+        let source = r#"
+import java.util.List;
+public class Dog extends Animal implements Pet {
+    public Dog() { super(); }
+    public void bark() { System.out.println(sound()); }
+}
+interface Pet {}
+enum Size { SMALL }
+record Point(int x, int y) {}
+"#;
+        assert_symbols(
+            Lang::Java,
+            source,
+            &[
+                ("Dog", "class"),
+                ("Dog", "constructor"),
+                ("bark", "method"),
+                ("Pet", "interface"),
+                ("Size", "enum"),
+                ("Point", "record"),
+            ],
+        );
+        assert_refs(
+            Lang::Java,
+            source,
+            &[
+                ("import", "java.util.List", None),
+                ("call", "println", Some("bark")),
+                ("call", "sound", Some("bark")),
+                ("impl", "Animal", Some("Dog")),
+                ("impl", "Pet", Some("Dog")),
+            ],
+        );
+    }
+
+    #[test]
+    fn yaml_key_captures() {
+        let source = "server:\n  port: 8080\n  hosts: {primary: a}\nname: demo\n";
+        assert_symbols(
+            Lang::Yaml,
+            source,
+            &[("server", "key"), ("port", "key"), ("primary", "key"), ("name", "key")],
+        );
+        let syms = symbols_of(Lang::Yaml, source);
+        let port = syms.iter().find(|s| s.name == "port").unwrap();
+        assert_eq!(port.parent_name.as_deref(), Some("server"));
+        assert!(refs_of(Lang::Yaml, source).is_empty());
+    }
+
+    #[test]
+    fn toml_key_captures() {
+        let source = "title = \"demo\"\n[package]\nname = \"x\"\n[[bin]]\npath = \"main.rs\"\n";
+        assert_symbols(
+            Lang::Toml,
+            source,
+            &[
+                ("title", "key"),
+                ("package", "table"),
+                ("name", "key"),
+                ("bin", "table"),
+                ("path", "key"),
+            ],
+        );
+        let syms = symbols_of(Lang::Toml, source);
+        let name = syms.iter().find(|s| s.name == "name").unwrap();
+        assert_eq!(name.parent_name.as_deref(), Some("package"));
+    }
+}
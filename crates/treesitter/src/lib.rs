@@ -2,25 +2,97 @@
 //!
 //! Ported from ClaudeRLM. In-memory HashMap-backed (no SQLite).
 //! Indexed files can become context segments for the librarian.
+//!
+//! The index is incremental. Each file keeps the content hash its symbols
+//! came from, so re-indexing an unchanged file costs a hash, and recently
+//! parsed trees are cached so an edited file is re-parsed with
+//! tree-sitter's incremental parsing. An index made with
+//! [`CodeIndex::open`] persists under the data dir, and
+//! [`watch::IndexWatcher`] keeps it current as a VDrive changes.
+//!
+//! Besides definitions, each file's references (calls, type usages,
+//! imports, trait impls) are kept, and [`graph`] answers cross-file
+//! questions over them: usages, callers, callees, implementors and
+//! module dependents.
+//!
+//! [`semantic::SemanticIndex`] complements name lookups with search by
+//! meaning over embedded chunks of code and documents.
 
+pub mod external;
+pub mod graph;
 pub mod handler;
 pub mod languages;
+pub mod references;
+pub mod semantic;
 pub mod symbols;
+pub mod watch;
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
-use std::collections::HashMap;
-use std::path::Path;
+use agentos_vdrive::VDrive;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tree_sitter::{InputEdit, Point, Tree};
 
 use languages::Lang;
+use references::ExtractedReference;
 use symbols::ExtractedSymbol;
 
+/// Parsed trees kept for incremental re-parsing. Beyond this, an edited
+/// file that fell out of the cache is parsed from scratch.
+const MAX_CACHED_TREES: usize = 256;
+
+/// Bumped whenever the persisted layout or the symbol queries change, so
+/// an old index is discarded rather than trusted.
+const STORE_VERSION: u32 = 2;
+
+/// Directories never indexed: build output, vendored packages, and
+/// anything hidden (VCS metadata, the AgentOS data dir).
+fn is_ignored_dir(name: &str) -> bool {
+    name.starts_with('.') || matches!(name, "target" | "node_modules" | "__pycache__")
+}
+
+/// True if any component of `rel` is one [`is_ignored_dir`] skips.
+pub(crate) fn is_ignored_path(rel: &str) -> bool {
+    rel.split('/').any(|part| !part.is_empty() && is_ignored_dir(part))
+}
+
 /// Stats from indexing a directory.
 #[derive(Debug, Default)]
 pub struct IndexStats {
+    /// Files parsed because they were new or changed.
     pub files_indexed: usize,
+    /// Files whose content hash matched the index.
+    pub files_unchanged: usize,
     pub files_skipped: usize,
+    /// Indexed files that no longer exist.
+    pub files_removed: usize,
     pub total_symbols: usize,
 }
 
+impl IndexStats {
+    fn record(&mut self, outcome: Result<Update, String>) {
+        match outcome {
+            Ok(Update::Parsed(count)) => {
+                self.files_indexed += 1;
+                self.total_symbols += count;
+            }
+            Ok(Update::Unchanged(count)) => {
+                self.files_unchanged += 1;
+                self.total_symbols += count;
+            }
+            Err(_) => self.files_skipped += 1,
+        }
+    }
+}
+
+/// What indexing one file did.
+enum Update {
+    Parsed(usize),
+    Unchanged(usize),
+}
+
 /// An entry in the codebase map (file → symbol summary).
 #[derive(Debug, Clone)]
 pub struct FileMapEntry {
@@ -36,18 +108,101 @@ pub struct SymbolSummary {
     pub kind: String,
 }
 
-/// In-memory code index: file_path → extracted symbols.
+/// One indexed file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedFile {
+    language: String,
+    /// Hex SHA-256 of the source the symbols were extracted from.
+    hash: String,
+    symbols: Vec<ExtractedSymbol>,
+    references: Vec<ExtractedReference>,
+}
+
+/// On-disk form of a persisted index.
+#[derive(Serialize, Deserialize)]
+struct StoredIndex {
+    version: u32,
+    root: PathBuf,
+    files: HashMap<String, IndexedFile>,
+}
+
+/// Code index: file path → extracted symbols.
+///
+/// Paths under the index root are keyed relative to it (matching VDrive
+/// paths); anything else is keyed by the path as given.
 pub struct CodeIndex {
-    symbols: HashMap<String, Vec<ExtractedSymbol>>,
-    languages: HashMap<String, String>, // path → language name
+    files: HashMap<String, IndexedFile>,
+    /// Last parse of each recently indexed file, with its source.
+    trees: HashMap<String, (Tree, Vec<u8>)>,
+    root: Option<PathBuf>,
+    /// Where [`save`](Self::save) writes, for a persisted index.
+    store: Option<PathBuf>,
+    /// Changed since the last save.
+    dirty: bool,
 }
 
 impl CodeIndex {
     pub fn new() -> Self {
         Self {
-            symbols: HashMap::new(),
-            languages: HashMap::new(),
+            files: HashMap::new(),
+            trees: HashMap::new(),
+            root: None,
+            store: None,
+            dirty: false,
+        }
+    }
+
+    /// Open the persisted index for the tree at `root`, stored under
+    /// `{data_dir}/code-index/`. A missing, unreadable or outdated store
+    /// yields an empty index; the first [`save`](Self::save) replaces it.
+    pub fn open(data_dir: &Path, root: &Path) -> Self {
+        let store = data_dir
+            .join("code-index")
+            .join(format!("{}.json", &content_hash(root.to_string_lossy().as_bytes())[..16]));
+        let files = std::fs::read(&store)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<StoredIndex>(&bytes).ok())
+            .filter(|stored| stored.version == STORE_VERSION && stored.root == root)
+            .map(|stored| stored.files)
+            .unwrap_or_default();
+        Self {
+            files,
+            root: Some(root.to_path_buf()),
+            store: Some(store),
+            ..Self::new()
+        }
+    }
+
+    /// Root that file keys are relative to, if any.
+    pub fn root(&self) -> Option<&Path> {
+        self.root.as_deref()
+    }
+
+    /// Write the index to its store if it changed since the last save.
+    /// A no-op for an index made with [`new`](Self::new).
+    pub fn save(&mut self) -> Result<(), String> {
+        let (Some(store), Some(root)) = (&self.store, &self.root) else {
+            return Ok(());
+        };
+        if !self.dirty {
+            return Ok(());
+        }
+        let stored = StoredIndex {
+            version: STORE_VERSION,
+            root: root.clone(),
+            files: self.files.clone(),
+        };
+        let json = serde_json::to_vec(&stored).map_err(|e| format!("serialize index: {e}"))?;
+        if let Some(parent) = store.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("failed to create {}: {e}", parent.display()))?;
         }
+        let tmp = store.with_extension("json.tmp");
+        std::fs::write(&tmp, json).map_err(|e| format!("failed to write {}: {e}", tmp.display()))?;
+        std::fs::rename(&tmp, store)
+            .map_err(|e| format!("failed to write {}: {e}", store.display()))?;
+        self.dirty = false;
+        Ok(())
     }
 
     /// Index a single file. Returns the number of symbols found.
@@ -63,52 +218,101 @@ impl CodeIndex {
         let source =
             std::fs::read(path).map_err(|e| format!("failed to read {}: {e}", path.display()))?;
 
-        let extracted = symbols::extract_symbols(lang, &source)
-            .map_err(|e| format!("parse error for {}: {e}", path.display()))?;
-
-        let count = extracted.len();
-        let path_str = path.to_string_lossy().to_string();
-        self.languages
-            .insert(path_str.clone(), lang.name().to_string());
-        self.symbols.insert(path_str, extracted);
-        Ok(count)
+        let key = self.key_for(path);
+        match self.update(&key, lang, &source)? {
+            Update::Parsed(count) | Update::Unchanged(count) => Ok(count),
+        }
     }
 
     /// Index a source string directly (for testing / in-memory use).
     pub fn index_source(&mut self, path: &str, lang: Lang, source: &[u8]) -> Result<usize, String> {
-        let extracted =
-            symbols::extract_symbols(lang, source).map_err(|e| format!("parse error: {e}"))?;
-        let count = extracted.len();
-        self.languages
-            .insert(path.to_string(), lang.name().to_string());
-        self.symbols.insert(path.to_string(), extracted);
-        Ok(count)
+        match self.update(path, lang, source)? {
+            Update::Parsed(count) | Update::Unchanged(count) => Ok(count),
+        }
     }
 
-    /// Index all supported files in a directory (non-recursive for now).
+    /// Index all supported files under a directory, skipping hidden and
+    /// build directories. Unchanged files are not re-parsed, and files
+    /// that disappeared from the directory are dropped from the index.
     pub fn index_directory(&mut self, dir: &Path) -> Result<IndexStats, String> {
         let mut stats = IndexStats::default();
-
-        let entries = std::fs::read_dir(dir)
+        let mut found = Vec::new();
+        walk_directory(dir, &mut found)
             .map_err(|e| format!("failed to read dir {}: {e}", dir.display()))?;
 
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if !path.is_file() {
-                continue;
-            }
-            match self.index_file(&path) {
-                Ok(count) => {
-                    stats.files_indexed += 1;
-                    stats.total_symbols += count;
-                }
-                Err(_) => {
-                    stats.files_skipped += 1;
+        let mut seen = HashSet::new();
+        for (path, lang) in found {
+            let key = self.key_for(&path);
+            let outcome = std::fs::read(&path)
+                .map_err(|e| format!("failed to read {}: {e}", path.display()))
+                .and_then(|source| self.update(&key, lang, &source));
+            stats.record(outcome);
+            seen.insert(key);
+        }
+
+        let prefix = self.key_for(dir);
+        stats.files_removed = self.prune(&prefix, &seen);
+        Ok(stats)
+    }
+
+    /// Index all supported files under `dir` in a VDrive, seen through
+    /// its overlay and mount policy. Keys are drive-relative paths, so the
+    /// index root should be the drive root.
+    pub fn index_drive(&mut self, drive: &VDrive, dir: &str) -> Result<IndexStats, String> {
+        let dir = normalize_key(dir);
+        let mut stats = IndexStats::default();
+        let mut found = Vec::new();
+        walk_drive(drive, &dir, &Lang::from_path, &mut found).map_err(|e| format!("failed to read dir {dir}: {e}"))?;
+
+        let mut seen = HashSet::new();
+        for (path, lang) in found {
+            let outcome = drive
+                .read_bytes(&path)
+                .map_err(|e| format!("failed to read {path}: {e}"))
+                .and_then(|source| self.update(&path, lang, &source));
+            stats.record(outcome);
+            seen.insert(path);
+        }
+
+        stats.files_removed = self.prune(&dir, &seen);
+        Ok(stats)
+    }
+
+    /// Bring one drive path up to date after it changed: re-index a file,
+    /// re-walk a directory, or drop whatever was indexed under a path that
+    /// no longer exists. Returns true if the index changed.
+    pub fn refresh_from_drive(&mut self, drive: &VDrive, path: &str) -> bool {
+        let path = normalize_key(path);
+        if is_ignored_path(&path) {
+            return false;
+        }
+        match drive.stat(if path.is_empty() { "." } else { path.as_str() }) {
+            Ok(info) if info.is_dir => self
+                .index_drive(drive, &path)
+                .is_ok_and(|s| s.files_indexed + s.files_removed > 0),
+            Ok(_) => {
+                let Some(lang) = Lang::from_path(&path) else {
+                    return false;
+                };
+                match drive.read_bytes(&path) {
+                    Ok(source) => matches!(self.update(&path, lang, &source), Ok(Update::Parsed(_))),
+                    Err(_) => self.remove(&path) > 0,
                 }
             }
+            Err(_) => self.remove(&path) > 0,
         }
+    }
 
-        Ok(stats)
+    /// Drop `path`, or everything under it if it is a directory. Returns
+    /// the number of files removed.
+    pub fn remove(&mut self, path: &str) -> usize {
+        let path = normalize_key(path);
+        self.trees.retain(|key, _| !is_under(key, &path));
+        let before = self.files.len();
+        self.files.retain(|key, _| !is_under(key, &path));
+        let removed = before - self.files.len();
+        self.dirty |= removed > 0;
+        removed
     }
 
     /// Search for symbols by name (substring match) and optional kind filter.
@@ -116,8 +320,8 @@ impl CodeIndex {
         let query_lower = query.to_lowercase();
         let mut results = Vec::new();
 
-        for (path, syms) in &self.symbols {
-            for sym in syms {
+        for (path, file) in &self.files {
+            for sym in &file.symbols {
                 let name_match = sym.name.to_lowercase().contains(&query_lower);
                 let kind_match = kind.is_none_or(|k| sym.kind == k);
                 if name_match && kind_match {
@@ -132,15 +336,11 @@ impl CodeIndex {
     /// Build a codebase map: per-file symbol summaries.
     pub fn codebase_map(&self) -> Vec<FileMapEntry> {
         let mut entries: Vec<FileMapEntry> = self
-            .symbols
+            .files
             .iter()
-            .map(|(path, syms)| {
-                let language = self
-                    .languages
-                    .get(path)
-                    .cloned()
-                    .unwrap_or_else(|| "unknown".into());
-                let symbols = syms
+            .map(|(path, file)| {
+                let symbols = file
+                    .symbols
                     .iter()
                     .map(|s| SymbolSummary {
                         name: s.name.clone(),
@@ -149,7 +349,7 @@ impl CodeIndex {
                     .collect();
                 FileMapEntry {
                     path: path.clone(),
-                    language,
+                    language: file.language.clone(),
                     symbols,
                 }
             })
@@ -160,17 +360,83 @@ impl CodeIndex {
 
     /// Get symbols for a specific file.
     pub fn get_file_symbols(&self, path: &str) -> Option<&[ExtractedSymbol]> {
-        self.symbols.get(path).map(|v| v.as_slice())
+        self.files.get(path).map(|f| f.symbols.as_slice())
     }
 
     /// Number of indexed files.
     pub fn file_count(&self) -> usize {
-        self.symbols.len()
+        self.files.len()
     }
 
     /// Total number of symbols across all files.
     pub fn symbol_count(&self) -> usize {
-        self.symbols.values().map(|v| v.len()).sum()
+        self.files.values().map(|f| f.symbols.len()).sum()
+    }
+
+    /// Index `source` under `key` unless its hash matches what is already
+    /// indexed, reusing the cached tree of the previous version if any.
+    fn update(&mut self, key: &str, lang: Lang, source: &[u8]) -> Result<Update, String> {
+        let hash = content_hash(source);
+        if let Some(file) = self.files.get(key) {
+            if file.hash == hash {
+                return Ok(Update::Unchanged(file.symbols.len()));
+            }
+        }
+
+        let old_tree = self.trees.remove(key).map(|(mut tree, old_source)| {
+            tree.edit(&input_edit(&old_source, source));
+            tree
+        });
+        let tree = symbols::parse(lang, source, old_tree.as_ref())
+            .map_err(|e| format!("parse error: {e}"))?;
+        let extracted = symbols::extract_from_tree(lang, &tree, source)
+            .map_err(|e| format!("parse error: {e}"))?;
+        let references = references::extract_references(lang, &tree, source)
+            .map_err(|e| format!("parse error: {e}"))?;
+
+        if self.trees.len() >= MAX_CACHED_TREES {
+            if let Some(evict) = self.trees.keys().next().cloned() {
+                self.trees.remove(&evict);
+            }
+        }
+        self.trees.insert(key.to_string(), (tree, source.to_vec()));
+
+        let count = extracted.len();
+        self.files.insert(
+            key.to_string(),
+            IndexedFile {
+                language: lang.name().to_string(),
+                hash,
+                symbols: extracted,
+                references,
+            },
+        );
+        self.dirty = true;
+        Ok(Update::Parsed(count))
+    }
+
+    /// Drop files under `prefix` that are not in `seen`.
+    fn prune(&mut self, prefix: &str, seen: &HashSet<String>) -> usize {
+        let gone: Vec<String> = self
+            .files
+            .keys()
+            .filter(|key| is_under(key, prefix) && !seen.contains(*key))
+            .cloned()
+            .collect();
+        for key in &gone {
+            self.files.remove(key);
+            self.trees.remove(key);
+        }
+        self.dirty |= !gone.is_empty();
+        gone.len()
+    }
+
+    /// Index key for a filesystem path.
+    fn key_for(&self, path: &Path) -> String {
+        match self.root.as_deref().and_then(|root| path.strip_prefix(root).ok()) {
+            Some(rel) => normalize_key(&rel.to_string_lossy()),
+            None => path.to_string_lossy().to_string(),
+        }
     }
 }
 
@@ -180,6 +446,103 @@ impl Default for CodeIndex {
     }
 }
 
+/// Hex SHA-256 of `bytes`.
+fn content_hash(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+/// Forward slashes, no `./` prefix or trailing slash; the root is `""`.
+fn normalize_key(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let path = path.trim_start_matches("./").trim_end_matches('/');
+    if path == "." {
+        String::new()
+    } else {
+        path.to_string()
+    }
+}
+
+/// True if `key` is `prefix` or lies under it.
+fn is_under(key: &str, prefix: &str) -> bool {
+    prefix.is_empty()
+        || key
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// The edit turning `old` into `new`: everything between their common
+/// prefix and common suffix was replaced.
+fn input_edit(old: &[u8], new: &[u8]) -> InputEdit {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_end = old.len() - suffix;
+    let new_end = new.len() - suffix;
+    InputEdit {
+        start_byte: prefix,
+        old_end_byte: old_end,
+        new_end_byte: new_end,
+        start_position: point_at(old, prefix),
+        old_end_position: point_at(old, old_end),
+        new_end_position: point_at(new, new_end),
+    }
+}
+
+/// Row and byte column of `offset` in `source`.
+fn point_at(source: &[u8], offset: usize) -> Point {
+    let before = &source[..offset];
+    let row = before.iter().filter(|&&b| b == b'\n').count();
+    let line_start = before.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    Point::new(row, offset - line_start)
+}
+
+/// Collect supported source files under `dir`.
+fn walk_directory(dir: &Path, out: &mut Vec<(PathBuf, Lang)>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name();
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            if !is_ignored_dir(&name.to_string_lossy()) {
+                walk_directory(&path, out)?;
+            }
+        } else if file_type.is_file() {
+            if let Some(lang) = Lang::from_path(&name.to_string_lossy()) {
+                out.push((path, lang));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Collect the files under `dir` in a drive that `classify` accepts.
+fn walk_drive<T>(
+    drive: &VDrive,
+    dir: &str,
+    classify: &dyn Fn(&str) -> Option<T>,
+    out: &mut Vec<(String, T)>,
+) -> Result<(), String> {
+    let listing = drive
+        .list_dir(if dir.is_empty() { "." } else { dir })
+        .map_err(|e| e.to_string())?;
+    for entry in listing {
+        let name = entry.path.rsplit('/').next().unwrap_or(&entry.path);
+        if entry.is_dir {
+            if !is_ignored_dir(name) {
+                walk_drive(drive, &entry.path, classify, out)?;
+            }
+        } else if let Some(class) = classify(name) {
+            out.push((entry.path, class));
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -330,4 +693,88 @@ CONSTANT = 42
         assert!(idx.search("foo", None).is_empty());
         assert!(idx.codebase_map().is_empty());
     }
+
+    #[test]
+    fn reindexing_edited_source_reuses_tree() {
+        let mut idx = CodeIndex::new();
+        idx.index_source("test.rs", Lang::Rust, RUST_SOURCE).unwrap();
+        assert!(matches!(
+            idx.update("test.rs", Lang::Rust, RUST_SOURCE),
+            Ok(Update::Unchanged(_))
+        ));
+
+        let edited = String::from_utf8_lossy(RUST_SOURCE).replace("do_stuff", "do_more_stuff");
+        assert!(matches!(
+            idx.update("test.rs", Lang::Rust, edited.as_bytes()),
+            Ok(Update::Parsed(_))
+        ));
+        let syms = idx.get_file_symbols("test.rs").unwrap();
+        let func = syms.iter().find(|s| s.name == "do_more_stuff").unwrap();
+        assert_eq!(func.start_line, 15);
+        assert!(!syms.iter().any(|s| s.name == "do_stuff"));
+    }
+
+    #[test]
+    fn input_edit_spans_the_changed_bytes() {
+        let edit = input_edit(b"fn a() {}\nfn b() {}\n", b"fn a() {}\nfn bee() {}\n");
+        assert_eq!((edit.start_byte, edit.old_end_byte, edit.new_end_byte), (14, 14, 16));
+        assert_eq!(edit.start_position, Point::new(1, 4));
+        assert_eq!(edit.new_end_position, Point::new(1, 6));
+    }
+
+    #[test]
+    fn index_directory_recurses_and_prunes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src/nested")).unwrap();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "pub fn top() {}").unwrap();
+        std::fs::write(root.join("src/nested/deep.py"), "def deep(): pass").unwrap();
+        std::fs::write(root.join("target/gen.rs"), "fn generated() {}").unwrap();
+        std::fs::write(root.join("README.md"), "# hi").unwrap();
+
+        let data = tempfile::TempDir::new().unwrap();
+        let mut idx = CodeIndex::open(data.path(), root);
+        let stats = idx.index_directory(root).unwrap();
+        assert_eq!(stats.files_indexed, 2);
+        assert!(idx.get_file_symbols("src/nested/deep.py").is_some());
+        assert!(idx.search("generated", None).is_empty());
+
+        std::fs::remove_file(root.join("src/nested/deep.py")).unwrap();
+        let stats = idx.index_directory(root).unwrap();
+        assert_eq!((stats.files_indexed, stats.files_unchanged, stats.files_removed), (0, 1, 1));
+        assert_eq!(idx.file_count(), 1);
+    }
+
+    #[test]
+    fn persisted_index_skips_unchanged_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let data = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("main.rs"), RUST_SOURCE).unwrap();
+
+        let mut idx = CodeIndex::open(data.path(), dir.path());
+        idx.index_directory(dir.path()).unwrap();
+        idx.save().unwrap();
+
+        let mut reopened = CodeIndex::open(data.path(), dir.path());
+        assert!(reopened.get_file_symbols("main.rs").is_some());
+        let stats = reopened.index_directory(dir.path()).unwrap();
+        assert_eq!((stats.files_indexed, stats.files_unchanged), (0, 1));
+
+        // A different root gets its own store.
+        let other = tempfile::TempDir::new().unwrap();
+        assert_eq!(CodeIndex::open(data.path(), other.path()).file_count(), 0);
+    }
+
+    #[test]
+    fn remove_drops_directory_contents() {
+        let mut idx = CodeIndex::new();
+        idx.index_source("src/a.rs", Lang::Rust, b"fn a() {}").unwrap();
+        idx.index_source("src/sub/b.rs", Lang::Rust, b"fn b() {}").unwrap();
+        idx.index_source("srcx.rs", Lang::Rust, b"fn c() {}").unwrap();
+        assert_eq!(idx.remove("src/"), 2);
+        assert_eq!(idx.file_count(), 1);
+        assert!(is_ignored_path("target/debug/x.rs"));
+        assert!(!is_ignored_path("src/target.rs"));
+    }
 }
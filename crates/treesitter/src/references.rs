@@ -0,0 +1,226 @@
+//! Reference extraction — call sites, type usages, imports and trait
+//! implementations, the edges of the symbol graph.
+
+use serde::{Deserialize, Serialize};
+use tree_sitter::{Node, Query, QueryCursor, StreamingIterator, Tree};
+
+use super::languages::Lang;
+
+/// A use of some name in source code.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractedReference {
+    /// The referenced name: callee, type, trait/base class, or import path.
+    pub name: String,
+    /// `call`, `type`, `import` or `impl`.
+    pub kind: String,
+    pub line: usize,
+    /// Innermost enclosing function, impl or class. For a call, the caller.
+    pub scope: Option<String>,
+    /// For `impl`: the implementing type or subclass.
+    pub target: Option<String>,
+}
+
+/// Extract references from a tree parsed from `source`.
+pub fn extract_references(
+    lang: Lang,
+    tree: &Tree,
+    source: &[u8],
+) -> Result<Vec<ExtractedReference>, String> {
+    let grammar = lang.grammar();
+    let query = Query::new(&grammar, lang.reference_query())
+        .map_err(|e| format!("failed to compile query: {e}"))?;
+
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(&query, tree.root_node(), source);
+    let capture_names = query.capture_names();
+    let mut refs = Vec::new();
+
+    while let Some(m) = matches.next() {
+        let mut found = None;
+        let mut target = None;
+        for capture in m.captures {
+            let cap_name = capture_names[capture.index as usize];
+            if cap_name == "implementor" {
+                target = capture.node.utf8_text(source).ok().map(str::to_string);
+            } else {
+                found = Some((cap_name, capture.node));
+            }
+        }
+        let Some((cap_name, node)) = found else {
+            continue;
+        };
+        let kind = match cap_name {
+            "call" => "call",
+            "type" if is_definition_name(node) => continue,
+            "type" => "type",
+            "import" => "import",
+            "trait" => "impl",
+            _ => continue,
+        };
+        let Ok(mut name) = node.utf8_text(source) else {
+            continue;
+        };
+        if kind == "import" {
+            // `"./util"`, `<stdio.h>`
+            name = name.trim_matches(|c| matches!(c, '"' | '\'' | '`' | '<' | '>'));
+        }
+        refs.push(ExtractedReference {
+            name: name.to_string(),
+            kind: kind.to_string(),
+            line: node.start_position().row + 1,
+            scope: enclosing_scope(node, source),
+            target: if kind == "impl" { target.clone() } else { None },
+        });
+    }
+
+    Ok(refs)
+}
+
+/// True if `node` is the name of a type being defined rather than a use of it.
+fn is_definition_name(node: Node) -> bool {
+    let Some(p) = node.parent() else {
+        return false;
+    };
+    let field = match p.kind() {
+        "struct_item" | "enum_item" | "union_item" | "trait_item" | "type_item"
+        | "class_declaration" | "abstract_class_declaration" | "interface_declaration"
+        | "type_alias_declaration" | "type_spec" | "type_alias" | "alias_declaration" => "name",
+        // `struct foo x;` uses the type; only a body defines it.
+        "struct_specifier" | "union_specifier" | "enum_specifier" | "class_specifier"
+            if p.child_by_field_name("body").is_some() =>
+        {
+            "name"
+        }
+        "type_definition" => "declarator",
+        _ => return false,
+    };
+    p.child_by_field_name(field) == Some(node)
+}
+
+/// Name of the innermost function, impl, trait, class or module around `node`.
+fn enclosing_scope(node: Node, source: &[u8]) -> Option<String> {
+    let mut parent = node.parent();
+    while let Some(p) = parent {
+        let name_node = match p.kind() {
+            "function_item"
+            | "function_definition"
+            | "function_declaration"
+            | "generator_function_declaration"
+            | "method_definition"
+            | "method_declaration"
+            | "constructor_declaration"
+            | "trait_item"
+            | "mod_item"
+            | "class_definition"
+            | "class_declaration"
+            | "abstract_class_declaration"
+            | "interface_declaration"
+            | "class_specifier"
+            | "namespace_definition" => p
+                .child_by_field_name("name")
+                .or_else(|| declarator_name(p)),
+            "impl_item" => p.child_by_field_name("type"),
+            // `const f = () => ...`
+            "variable_declarator" => p
+                .child_by_field_name("value")
+                .filter(|v| matches!(v.kind(), "arrow_function" | "function_expression"))
+                .and_then(|_| p.child_by_field_name("name")),
+            _ => None,
+        };
+        if let Some(name_node) = name_node {
+            return name_node.utf8_text(source).ok().map(|s| s.to_string());
+        }
+        parent = p.parent();
+    }
+    None
+}
+
+/// The declared name of a C/C++ function definition, found by following
+/// its declarator chain (`*f(void)`, `Foo::bar() const`, ...).
+fn declarator_name(node: Node) -> Option<Node> {
+    let mut d = node.child_by_field_name("declarator")?;
+    loop {
+        match d.kind() {
+            "identifier" | "field_identifier" | "qualified_identifier" | "destructor_name"
+            | "operator_name" => return Some(d),
+            _ => d = d.child_by_field_name("declarator").or_else(|| d.named_child(0))?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symbols;
+
+    fn refs(lang: Lang, source: &str) -> Vec<ExtractedReference> {
+        let tree = symbols::parse(lang, source.as_bytes(), None).unwrap();
+        extract_references(lang, &tree, source.as_bytes()).unwrap()
+    }
+
+    fn find<'a>(refs: &'a [ExtractedReference], kind: &str, name: &str) -> Option<&'a ExtractedReference> {
+        refs.iter().find(|r| r.kind == kind && r.name == name)
+    }
+
+    #[test]
+    fn rust_calls_imports_and_impls() {
+        let refs = refs(
+            Lang::Rust,
+            r#"
+use std::collections::HashMap;
+mod helpers;
+
+struct Store { map: HashMap<String, u32> }
+
+impl Default for Store {
+    fn default() -> Self {
+        Store { map: build_map() }
+    }
+}
+
+fn build_map() -> HashMap<String, u32> {
+    let mut m = HashMap::new();
+    m.insert(String::new(), 1);
+    helpers::log!("built");
+    m
+}
+"#,
+        );
+
+        let call = find(&refs, "call", "build_map").unwrap();
+        assert_eq!(call.scope.as_deref(), Some("default"));
+        assert_eq!(call.line, 9);
+        assert_eq!(find(&refs, "call", "new").unwrap().scope.as_deref(), Some("build_map"));
+        assert!(find(&refs, "call", "insert").is_some());
+        assert!(find(&refs, "import", "std::collections::HashMap").is_some());
+        assert!(find(&refs, "import", "helpers").is_some());
+        assert!(find(&refs, "type", "HashMap").is_some());
+        // The struct's own name is a definition, not a reference.
+        assert!(!refs.iter().any(|r| r.name == "Store" && r.line == 5));
+
+        let imp = find(&refs, "impl", "Default").unwrap();
+        assert_eq!(imp.target.as_deref(), Some("Store"));
+    }
+
+    #[test]
+    fn python_calls_imports_and_subclasses() {
+        let refs = refs(
+            Lang::Python,
+            r#"
+import os.path
+from .models import Base
+
+class User(Base):
+    def save(self):
+        validate(self)
+        os.path.join("a", "b")
+"#,
+        );
+
+        assert!(find(&refs, "import", "os.path").is_some());
+        assert!(find(&refs, "import", ".models").is_some());
+        assert_eq!(find(&refs, "call", "validate").unwrap().scope.as_deref(), Some("save"));
+        assert!(find(&refs, "call", "join").is_some());
+        assert_eq!(find(&refs, "impl", "Base").unwrap().target.as_deref(), Some("User"));
+    }
+}
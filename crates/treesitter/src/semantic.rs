@@ -0,0 +1,688 @@
+//! Semantic search — embedded chunks of the files in a tree, found by
+//! meaning rather than by name.
+//!
+//! Source files are cut on symbol boundaries: each top-level definition is
+//! a chunk, with the comments and attributes directly above it, and a
+//! definition too long for one chunk is cut at its members instead.
+//! Documents are cut at headings and paragraph breaks. Every chunk is
+//! embedded with the index's [`EmbeddingProvider`] and placed in an HNSW
+//! graph for approximate nearest-neighbour search. Chunks are also kept in
+//! a BM25 index over their path, symbol and text, and a search fuses the
+//! two rankings, so exact identifiers rank well even when the embedding
+//! model does not know them.
+//!
+//! Like [`CodeIndex`](super::CodeIndex), the index persists under the data
+//! dir, skips files whose content hash is unchanged, and is kept current
+//! by [`IndexWatcher`](super::watch::IndexWatcher). The HNSW graph
+//! and BM25 index are not stored; they are rebuilt from the stored chunks
+//! and vectors on open.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use agentos_embedding::bm25::Bm25Index;
+use agentos_embedding::hnsw::Hnsw;
+use agentos_embedding::hybrid::HybridRanker;
+use agentos_embedding::tokenize::Tokenizer;
+use agentos_embedding::{Embedding, EmbeddingProvider, MatchResult};
+use agentos_vdrive::VDrive;
+use serde::{Deserialize, Serialize};
+
+use super::languages::Lang;
+use super::symbols::{self, ExtractedSymbol};
+use super::{content_hash, is_ignored_path, is_under, normalize_key, walk_drive, IndexStats, Update};
+
+/// Dimensions of the hashed TF-IDF vectors the runtime indexes with.
+pub const DEFAULT_DIMENSIONS: usize = 512;
+
+/// Longest chunk, in lines. Longer spans are cut into windows.
+const MAX_CHUNK_LINES: usize = 60;
+
+/// Search breadth in the HNSW graph.
+const SEARCH_EF: usize = 64;
+
+/// Bumped whenever the persisted layout or the chunking changes.
+const STORE_VERSION: u32 = 1;
+
+/// Extensions indexed as prose rather than code.
+const DOCUMENT_EXTENSIONS: &[&str] = &["md", "markdown", "txt", "rst", "adoc"];
+
+/// A contiguous span of a file, embedded as one vector.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chunk {
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Definition or document section the chunk belongs to.
+    pub symbol: Option<String>,
+    /// Kind of `symbol`: a symbol kind, or `section` for a heading.
+    pub kind: Option<String>,
+    pub text: String,
+}
+
+/// A search result.
+#[derive(Debug, Clone)]
+pub struct SemanticHit<'a> {
+    pub path: &'a str,
+    pub chunk: &'a Chunk,
+    /// Fused relevance of the lexical and dense rankings; higher is better.
+    pub score: f32,
+}
+
+/// One indexed file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedFile {
+    /// Hex SHA-256 of the content the chunks came from.
+    hash: String,
+    chunks: Vec<Chunk>,
+    /// One per chunk.
+    vectors: Vec<Embedding>,
+}
+
+/// On-disk form of a persisted index.
+#[derive(Serialize, Deserialize)]
+struct StoredIndex {
+    version: u32,
+    root: PathBuf,
+    /// [`EmbeddingProvider::model_id`] of the provider that made the vectors.
+    model: String,
+    files: HashMap<String, IndexedFile>,
+}
+
+/// Semantic index: file path → embedded chunks.
+///
+/// Keys are drive-relative paths, as in [`CodeIndex`](super::CodeIndex).
+pub struct SemanticIndex {
+    provider: Arc<dyn EmbeddingProvider>,
+    files: HashMap<String, IndexedFile>,
+    ann: Hnsw,
+    /// File and chunk number behind each graph node; `None` once removed.
+    nodes: Vec<Option<(String, usize)>>,
+    /// Chunks by [`chunk_name`], for lexical ranking.
+    lexical: Bm25Index,
+    ranker: HybridRanker,
+    root: Option<PathBuf>,
+    /// Where [`save`](Self::save) writes, for a persisted index.
+    store: Option<PathBuf>,
+    /// Changed since the last save.
+    dirty: bool,
+}
+
+impl SemanticIndex {
+    pub fn new(provider: Arc<dyn EmbeddingProvider>) -> Self {
+        Self {
+            provider,
+            files: HashMap::new(),
+            ann: Hnsw::new(),
+            nodes: Vec::new(),
+            lexical: lexical_index(),
+            ranker: HybridRanker::default(),
+            root: None,
+            store: None,
+            dirty: false,
+        }
+    }
+
+    /// Open the persisted index for the tree at `root`, stored under
+    /// `{data_dir}/semantic-index/`. A missing, unreadable or outdated
+    /// store yields an empty index. Vectors made by a different model are
+    /// re-embedded from the stored chunks.
+    pub fn open(data_dir: &Path, root: &Path, provider: Arc<dyn EmbeddingProvider>) -> Self {
+        let store = data_dir
+            .join("semantic-index")
+            .join(format!("{}.json", &content_hash(root.to_string_lossy().as_bytes())[..16]));
+        let stored = std::fs::read(&store)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<StoredIndex>(&bytes).ok())
+            .filter(|stored| stored.version == STORE_VERSION && stored.root == root);
+        let mut index = Self {
+            root: Some(root.to_path_buf()),
+            store: Some(store),
+            ..Self::new(provider)
+        };
+        if let Some(stored) = stored {
+            index.files = stored.files;
+            if stored.model != index.provider.model_id() {
+                for (path, file) in &mut index.files {
+                    file.vectors = file
+                        .chunks
+                        .iter()
+                        .map(|c| index.provider.embed(&embedding_text(path, c)))
+                        .collect();
+                }
+                index.dirty = true;
+            }
+            index.rebuild_graph();
+            let keys: Vec<String> = index.files.keys().cloned().collect();
+            for key in keys {
+                index.index_lexical(&key);
+            }
+        }
+        index
+    }
+
+    /// The provider chunks are embedded with.
+    pub fn provider(&self) -> Arc<dyn EmbeddingProvider> {
+        self.provider.clone()
+    }
+
+    /// Root that file keys are relative to, if any.
+    pub fn root(&self) -> Option<&Path> {
+        self.root.as_deref()
+    }
+
+    /// Write the index to its store if it changed since the last save.
+    /// A no-op for an index made with [`new`](Self::new).
+    pub fn save(&mut self) -> Result<(), String> {
+        let (Some(store), Some(root)) = (&self.store, &self.root) else {
+            return Ok(());
+        };
+        if !self.dirty {
+            return Ok(());
+        }
+        let stored = StoredIndex {
+            version: STORE_VERSION,
+            root: root.clone(),
+            model: self.provider.model_id(),
+            files: self.files.clone(),
+        };
+        let json = serde_json::to_vec(&stored).map_err(|e| format!("serialize index: {e}"))?;
+        if let Some(parent) = store.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("failed to create {}: {e}", parent.display()))?;
+        }
+        let tmp = store.with_extension("json.tmp");
+        std::fs::write(&tmp, json).map_err(|e| format!("failed to write {}: {e}", tmp.display()))?;
+        std::fs::rename(&tmp, store)
+            .map_err(|e| format!("failed to write {}: {e}", store.display()))?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Chunk and embed `source` under `path`. Returns the number of chunks.
+    pub fn index_source(&mut self, path: &str, source: &[u8]) -> Result<usize, String> {
+        match self.update(&normalize_key(path), source)? {
+            Update::Parsed(count) | Update::Unchanged(count) => Ok(count),
+        }
+    }
+
+    /// Index all code and documents under `dir` in a VDrive, dropping
+    /// files that disappeared. `total_symbols` in the result counts chunks.
+    pub fn index_drive(&mut self, drive: &VDrive, dir: &str) -> Result<IndexStats, String> {
+        let dir = normalize_key(dir);
+        let mut stats = IndexStats::default();
+        let mut found = Vec::new();
+        walk_drive(drive, &dir, &|name| is_indexable(name).then_some(()), &mut found)
+            .map_err(|e| format!("failed to read dir {dir}: {e}"))?;
+
+        let mut seen = HashSet::new();
+        for (path, ()) in found {
+            let outcome = drive
+                .read_bytes(&path)
+                .map_err(|e| format!("failed to read {path}: {e}"))
+                .and_then(|source| self.update(&path, &source));
+            stats.record(outcome);
+            seen.insert(path);
+        }
+
+        let gone: Vec<String> = self
+            .files
+            .keys()
+            .filter(|key| is_under(key, &dir) && !seen.contains(*key))
+            .cloned()
+            .collect();
+        for key in &gone {
+            self.drop_file(key);
+        }
+        stats.files_removed = gone.len();
+        Ok(stats)
+    }
+
+    /// Bring one drive path up to date after it changed. Returns true if
+    /// the index changed.
+    pub fn refresh_from_drive(&mut self, drive: &VDrive, path: &str) -> bool {
+        let path = normalize_key(path);
+        if is_ignored_path(&path) {
+            return false;
+        }
+        match drive.stat(if path.is_empty() { "." } else { path.as_str() }) {
+            Ok(info) if info.is_dir => self
+                .index_drive(drive, &path)
+                .is_ok_and(|s| s.files_indexed + s.files_removed > 0),
+            Ok(_) if !is_indexable(&path) => false,
+            Ok(_) => match drive.read_bytes(&path) {
+                Ok(source) => matches!(self.update(&path, &source), Ok(Update::Parsed(_))),
+                Err(_) => self.remove(&path) > 0,
+            },
+            Err(_) => self.remove(&path) > 0,
+        }
+    }
+
+    /// Drop `path`, or everything under it. Returns the number of files removed.
+    pub fn remove(&mut self, path: &str) -> usize {
+        let path = normalize_key(path);
+        let gone: Vec<String> = self.files.keys().filter(|key| is_under(key, &path)).cloned().collect();
+        for key in &gone {
+            self.drop_file(key);
+        }
+        gone.len()
+    }
+
+    /// The `limit` chunks most relevant to `query`, best first, optionally
+    /// only from files under `under`. Chunks sharing nothing with the
+    /// query are left out.
+    pub fn search(&self, query: &str, limit: usize, under: Option<&str>) -> Vec<SemanticHit<'_>> {
+        let under = under.map(normalize_key);
+        let in_scope = |path: &str| under.as_deref().is_none_or(|u| is_under(path, u));
+        let depth = self.ranker.depth.max(limit);
+
+        let vector = self.provider.embed(query);
+        let dense: Vec<MatchResult> = if vector.iter().all(|&x| x == 0.0) {
+            Vec::new()
+        } else {
+            // A path filter discards hits, so ask the graph for more.
+            let k = if under.is_some() { depth * 4 } else { depth };
+            self.ann
+                .search(&vector, k, SEARCH_EF)
+                .into_iter()
+                .filter(|&(_, score)| score > 0.0)
+                .filter_map(|(node, score)| {
+                    let (path, n) = self.nodes[node].as_ref()?;
+                    in_scope(path).then(|| MatchResult {
+                        name: chunk_name(path, *n),
+                        score,
+                    })
+                })
+                .take(depth)
+                .collect()
+        };
+        let lexical = self.lexical.search_top_k_matching(query, depth, &|name| {
+            parse_chunk_name(name).is_some_and(|(path, _)| in_scope(path))
+        });
+
+        self.ranker
+            .fuse(&lexical, &dense)
+            .into_iter()
+            .filter_map(|r| {
+                let (path, n) = parse_chunk_name(&r.name)?;
+                let (path, file) = self.files.get_key_value(path)?;
+                Some(SemanticHit {
+                    path,
+                    chunk: file.chunks.get(n)?,
+                    score: r.score,
+                })
+            })
+            .take(limit)
+            .collect()
+    }
+
+    /// Number of indexed files.
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Total number of chunks across all files.
+    pub fn chunk_count(&self) -> usize {
+        self.files.values().map(|f| f.chunks.len()).sum()
+    }
+
+    /// Chunks of an indexed file.
+    pub fn get_file_chunks(&self, path: &str) -> Option<&[Chunk]> {
+        self.files.get(path).map(|f| f.chunks.as_slice())
+    }
+
+    /// Re-chunk and re-embed `source` under `key` unless its hash matches.
+    fn update(&mut self, key: &str, source: &[u8]) -> Result<Update, String> {
+        let hash = content_hash(source);
+        if let Some(file) = self.files.get(key) {
+            if file.hash == hash {
+                return Ok(Update::Unchanged(file.chunks.len()));
+            }
+        }
+
+        let chunks = chunk_file(key, source)?;
+        let vectors: Vec<Embedding> = chunks
+            .iter()
+            .map(|c| self.provider.embed(&embedding_text(key, c)))
+            .collect();
+        self.drop_file(key);
+        for (n, vector) in vectors.iter().enumerate() {
+            let node = self.ann.insert(vector.clone());
+            debug_assert_eq!(node, self.nodes.len());
+            self.nodes.push(Some((key.to_string(), n)));
+        }
+        let count = chunks.len();
+        self.files.insert(key.to_string(), IndexedFile { hash, chunks, vectors });
+        self.index_lexical(key);
+        self.dirty = true;
+        Ok(Update::Parsed(count))
+    }
+
+    /// Remove a file and its graph nodes, rebuilding the graph once
+    /// removed nodes outnumber live ones.
+    fn drop_file(&mut self, key: &str) {
+        let Some(file) = self.files.remove(key) else {
+            return;
+        };
+        for n in 0..file.chunks.len() {
+            self.lexical.remove(&chunk_name(key, n));
+        }
+        for (node, entry) in self.nodes.iter_mut().enumerate() {
+            if entry.as_ref().is_some_and(|(path, _)| path == key) {
+                *entry = None;
+                self.ann.remove(node);
+            }
+        }
+        self.dirty = true;
+        if self.ann.deleted() > self.ann.len().max(64) {
+            self.rebuild_graph();
+        }
+    }
+
+    /// Add a file's chunks to the lexical index.
+    fn index_lexical(&mut self, key: &str) {
+        let Some(file) = self.files.get(key) else {
+            return;
+        };
+        for (n, chunk) in file.chunks.iter().enumerate() {
+            let symbol = chunk.symbol.as_deref().unwrap_or("");
+            self.lexical.register(
+                &chunk_name(key, n),
+                &[("path", key), ("symbol", symbol), ("text", &chunk.text)],
+            );
+        }
+    }
+
+    /// Build a fresh graph from the stored vectors.
+    fn rebuild_graph(&mut self) {
+        self.ann = Hnsw::new();
+        self.nodes.clear();
+        let mut keys: Vec<&String> = self.files.keys().collect();
+        keys.sort();
+        for key in keys {
+            for (n, vector) in self.files[key].vectors.iter().enumerate() {
+                self.ann.insert(vector.clone());
+                self.nodes.push(Some((key.clone(), n)));
+            }
+        }
+    }
+}
+
+/// The lexical index for chunks: stemmed, identifier-aware, with symbol
+/// names weighted above body text.
+fn lexical_index() -> Bm25Index {
+    Bm25Index::new(0.0)
+        .with_tokenizer(Tokenizer::new().with_stemming(true))
+        .with_field_weight("symbol", 2.0)
+}
+
+/// Name of chunk `n` of `path` in the lexical index.
+fn chunk_name(path: &str, n: usize) -> String {
+    format!("{path}#{n}")
+}
+
+fn parse_chunk_name(name: &str) -> Option<(&str, usize)> {
+    let (path, n) = name.rsplit_once('#')?;
+    Some((path, n.parse().ok()?))
+}
+
+/// True if `path` is code or a document the index takes.
+fn is_indexable(path: &str) -> bool {
+    Lang::from_path(path).is_some() || document_extension(path).is_some()
+}
+
+fn document_extension(path: &str) -> Option<&str> {
+    let ext = Path::new(path).extension()?.to_str()?;
+    DOCUMENT_EXTENSIONS.iter().copied().find(|d| d.eq_ignore_ascii_case(ext))
+}
+
+/// What gets embedded for a chunk: its text, labelled with where it lives.
+fn embedding_text(path: &str, chunk: &Chunk) -> String {
+    format!("{path} {}\n{}", chunk.symbol.as_deref().unwrap_or(""), chunk.text)
+}
+
+/// Cut a file into chunks, on symbol boundaries for code.
+fn chunk_file(path: &str, source: &[u8]) -> Result<Vec<Chunk>, String> {
+    let text = String::from_utf8_lossy(source);
+    let lines: Vec<&str> = text.lines().collect();
+    if let Some(lang) = Lang::from_path(path) {
+        let symbols = symbols::extract_symbols(lang, source)?;
+        Ok(chunk_code(&lines, &symbols))
+    } else if let Some(ext) = document_extension(path) {
+        Ok(chunk_document(&lines, matches!(ext, "md" | "markdown")))
+    } else {
+        Err(format!("unsupported file: {path}"))
+    }
+}
+
+/// A symbol's line span, 1-based and inclusive.
+type Span<'a> = (usize, usize, &'a ExtractedSymbol);
+
+fn chunk_code(lines: &[&str], symbols: &[ExtractedSymbol]) -> Vec<Chunk> {
+    let mut spans: Vec<Span> = symbols
+        .iter()
+        .filter(|s| s.start_line >= 1 && s.start_line <= s.end_line)
+        .map(|s| (s.start_line, s.end_line.min(lines.len()), s))
+        .collect();
+    // Outer spans before the spans they contain.
+    spans.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+    spans.dedup_by_key(|s| (s.0, s.1));
+    let mut out = Vec::new();
+    chunk_region(lines, &spans, 1, lines.len(), None, &mut out);
+    out
+}
+
+/// Chunk lines `start..=end`, given the spans lying within them. Lines
+/// outside any span belong to `owner`.
+fn chunk_region(
+    lines: &[&str],
+    spans: &[Span],
+    start: usize,
+    end: usize,
+    owner: Option<&ExtractedSymbol>,
+    out: &mut Vec<Chunk>,
+) {
+    let mut cursor = start;
+    let mut i = 0;
+    while i < spans.len() {
+        let (s, e, sym) = spans[i];
+        // Spans contained in this one follow it directly.
+        let mut j = i + 1;
+        while j < spans.len() && spans[j].0 <= e && spans[j].1 <= e {
+            j += 1;
+        }
+        if s < cursor {
+            // Overlaps the previous span without nesting in it.
+            i = j;
+            continue;
+        }
+        // Doc comments, attributes and decorators directly above.
+        let mut head = s;
+        while head > cursor && !lines[head - 2].trim().is_empty() {
+            head -= 1;
+        }
+        push_lines(lines, cursor, head - 1, owner.map(label), out);
+        if e - head < MAX_CHUNK_LINES || j == i + 1 {
+            push_lines(lines, head, e, Some(label(sym)), out);
+        } else {
+            chunk_region(lines, &spans[i + 1..j], head, e, Some(sym), out);
+        }
+        cursor = e + 1;
+        i = j;
+    }
+    push_lines(lines, cursor, end, owner.map(label), out);
+}
+
+fn chunk_document(lines: &[&str], markdown: bool) -> Vec<Chunk> {
+    let mut out = Vec::new();
+    let mut heading: Option<String> = None;
+    let mut start = 1;
+    for (i, line) in lines.iter().enumerate() {
+        let n = i + 1;
+        let is_heading = markdown && line.starts_with('#');
+        let len = n - start;
+        if is_heading || len >= MAX_CHUNK_LINES || (line.trim().is_empty() && len >= MAX_CHUNK_LINES / 2) {
+            push_lines(lines, start, n - 1, heading.as_deref().map(|h| (h, "section")), &mut out);
+            start = n;
+        }
+        if is_heading {
+            heading = Some(line.trim_start_matches('#').trim().to_string());
+        }
+    }
+    push_lines(lines, start, lines.len(), heading.as_deref().map(|h| (h, "section")), &mut out);
+    out
+}
+
+fn label(sym: &ExtractedSymbol) -> (&str, &str) {
+    (&sym.name, &sym.kind)
+}
+
+/// Add lines `start..=end` as chunks of at most [`MAX_CHUNK_LINES`],
+/// trimming blank edges and skipping spans with no words in them.
+fn push_lines(lines: &[&str], start: usize, end: usize, label: Option<(&str, &str)>, out: &mut Vec<Chunk>) {
+    let mut start = start.max(1);
+    let mut end = end.min(lines.len());
+    while start <= end && lines[start - 1].trim().is_empty() {
+        start += 1;
+    }
+    while end >= start && lines[end - 1].trim().is_empty() {
+        end -= 1;
+    }
+    while start <= end {
+        let stop = end.min(start + MAX_CHUNK_LINES - 1);
+        let text = lines[start - 1..stop].join("\n");
+        if text.chars().any(char::is_alphanumeric) {
+            out.push(Chunk {
+                start_line: start,
+                end_line: stop,
+                symbol: label.map(|(name, _)| name.to_string()),
+                kind: label.map(|(_, kind)| kind.to_string()),
+                text,
+            });
+        }
+        start = stop + 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agentos_embedding::tfidf::TfIdfProvider;
+    use tempfile::TempDir;
+
+    fn provider() -> Arc<dyn EmbeddingProvider> {
+        Arc::new(TfIdfProvider::hashed(&[], 256))
+    }
+
+    const SOURCE: &str = r#"use std::io;
+
+/// Load the configuration file from disk.
+#[inline]
+pub fn load_config(path: &str) -> io::Result<String> {
+    std::fs::read_to_string(path)
+}
+
+pub struct Connection {
+    retries: u32,
+}
+
+fn retry_network_request(conn: &Connection) -> bool {
+    conn.retries > 0
+}
+"#;
+
+    #[test]
+    fn code_chunks_follow_symbols() {
+        let chunks = chunk_file("src/lib.rs", SOURCE.as_bytes()).unwrap();
+        let names: Vec<Option<&str>> = chunks.iter().map(|c| c.symbol.as_deref()).collect();
+        assert_eq!(
+            names,
+            vec![None, Some("load_config"), Some("Connection"), Some("retry_network_request")]
+        );
+        let load = &chunks[1];
+        // The doc comment and attribute come with the function.
+        assert_eq!((load.start_line, load.end_line), (3, 7));
+        assert!(load.text.starts_with("/// Load"));
+    }
+
+    #[test]
+    fn long_symbols_split_at_members() {
+        let mut source = String::from("impl Big {\n");
+        for i in 0..30 {
+            source.push_str(&format!("    fn method_{i}(&self) -> u32 {{\n        {i}\n    }}\n"));
+        }
+        source.push_str("}\n");
+        let chunks = chunk_file("big.rs", source.as_bytes()).unwrap();
+        assert!(chunks.iter().any(|c| c.symbol.as_deref() == Some("method_7")));
+        assert!(chunks.iter().all(|c| c.end_line - c.start_line < MAX_CHUNK_LINES));
+    }
+
+    #[test]
+    fn markdown_chunks_by_heading() {
+        let doc = "# Intro\nWelcome.\n\n## Install\nRun the installer.\n";
+        let chunks = chunk_file("README.md", doc.as_bytes()).unwrap();
+        let names: Vec<&str> = chunks.iter().map(|c| c.symbol.as_deref().unwrap()).collect();
+        assert_eq!(names, vec!["Intro", "Install"]);
+        assert_eq!(chunks[1].start_line, 4);
+    }
+
+    #[test]
+    fn search_ranks_relevant_chunk_first() {
+        let mut idx = SemanticIndex::new(provider());
+        idx.index_source("src/lib.rs", SOURCE.as_bytes()).unwrap();
+        idx.index_source("docs/net.md", b"# Networking\nRequests are retried on failure.\n")
+            .unwrap();
+
+        let hits = idx.search("load configuration file", 3, None);
+        assert_eq!(hits[0].path, "src/lib.rs");
+        assert_eq!(hits[0].chunk.symbol.as_deref(), Some("load_config"));
+
+        let hits = idx.search("networking requests", 5, Some("docs"));
+        assert!(!hits.is_empty());
+        assert!(hits.iter().all(|h| h.path == "docs/net.md"));
+    }
+
+    #[test]
+    fn exact_identifiers_rank_lexically() {
+        // An empty-corpus dense model weighs every term alike; BM25 knows
+        // `retry_network_request` is rare.
+        let mut idx = SemanticIndex::new(provider());
+        idx.index_source("src/lib.rs", SOURCE.as_bytes()).unwrap();
+        let hits = idx.search("retryNetworkRequest", 1, None);
+        assert_eq!(hits[0].chunk.symbol.as_deref(), Some("retry_network_request"));
+    }
+
+    #[test]
+    fn updates_replace_old_chunks() {
+        let mut idx = SemanticIndex::new(provider());
+        idx.index_source("a.rs", b"fn parse_tokens() {}").unwrap();
+        idx.index_source("a.rs", b"fn render_html() {}").unwrap();
+        assert_eq!(idx.chunk_count(), 1);
+        let hits = idx.search("parse tokens", 5, None);
+        assert!(hits.iter().all(|h| h.chunk.symbol.as_deref() != Some("parse_tokens")));
+        assert_eq!(idx.remove("a.rs"), 1);
+        assert!(idx.search("render html", 5, None).is_empty());
+    }
+
+    #[test]
+    fn persisted_index_survives_reopen_and_model_change() {
+        let root = TempDir::new().unwrap();
+        let data = TempDir::new().unwrap();
+        std::fs::write(root.path().join("lib.rs"), SOURCE).unwrap();
+        let drive = VDrive::open(root.path()).unwrap();
+
+        let mut idx = SemanticIndex::open(data.path(), drive.root(), provider());
+        let stats = idx.index_drive(&drive, "").unwrap();
+        assert_eq!(stats.files_indexed, 1);
+        idx.save().unwrap();
+
+        let mut reopened = SemanticIndex::open(data.path(), drive.root(), provider());
+        assert_eq!(reopened.chunk_count(), idx.chunk_count());
+        assert_eq!(reopened.index_drive(&drive, "").unwrap().files_unchanged, 1);
+
+        let other = Arc::new(TfIdfProvider::hashed(&[], 128));
+        let remodelled = SemanticIndex::open(data.path(), drive.root(), other);
+        let hits = remodelled.search("load configuration", 1, None);
+        assert_eq!(hits[0].chunk.symbol.as_deref(), Some("load_config"));
+    }
+}
@@ -1,13 +1,16 @@
 //! Symbol extraction from source code using tree-sitter.
 //!
 //! Ported from ClaudeRLM with minor adaptations (anyhow → String errors).
+//! Parsing and extraction are separate steps so the index can hand
+//! tree-sitter the previous tree of an edited file.
 
-use tree_sitter::{Parser, Query, QueryCursor, StreamingIterator};
+use serde::{Deserialize, Serialize};
+use tree_sitter::{Parser, Query, QueryCursor, StreamingIterator, Tree};
 
 use super::languages::Lang;
 
 /// A symbol extracted from source code.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExtractedSymbol {
     pub name: String,
     pub kind: String,
@@ -20,17 +23,30 @@ pub struct ExtractedSymbol {
 
 /// Extract symbols from source code using tree-sitter.
 pub fn extract_symbols(lang: Lang, source: &[u8]) -> Result<Vec<ExtractedSymbol>, String> {
-    let grammar = lang.grammar();
+    let tree = parse(lang, source, None)?;
+    extract_from_tree(lang, &tree, source)
+}
 
+/// Parse `source`. `old_tree` must already have been
+/// [`edit`](Tree::edit)ed to match it; unchanged subtrees are reused.
+pub fn parse(lang: Lang, source: &[u8], old_tree: Option<&Tree>) -> Result<Tree, String> {
     let mut parser = Parser::new();
     parser
-        .set_language(&grammar)
+        .set_language(&lang.grammar())
         .map_err(|e| format!("failed to set language: {e}"))?;
 
-    let tree = parser
-        .parse(source, None)
-        .ok_or_else(|| "failed to parse source".to_string())?;
+    parser
+        .parse(source, old_tree)
+        .ok_or_else(|| "failed to parse source".to_string())
+}
 
+/// Extract symbols from a tree parsed from `source`.
+pub fn extract_from_tree(
+    lang: Lang,
+    tree: &Tree,
+    source: &[u8],
+) -> Result<Vec<ExtractedSymbol>, String> {
+    let grammar = lang.grammar();
     let query_str = lang.symbol_query();
     let query =
         Query::new(&grammar, query_str).map_err(|e| format!("failed to compile query: {e}"))?;
@@ -152,30 +168,32 @@ fn extract_doc_comment(node: tree_sitter::Node, source: &[u8]) -> Option<String>
     })
 }
 
-/// Find the parent symbol name if nested (e.g., method inside impl/class).
+/// Find the parent symbol name if nested (e.g., method inside impl/class,
+/// or key inside a YAML mapping or TOML table).
 fn find_parent_symbol(node: tree_sitter::Node, source: &[u8]) -> Option<String> {
     let mut parent = node.parent();
     while let Some(p) = parent {
-        let kind = p.kind();
-        let is_container = matches!(
-            kind,
+        let name_node = match p.kind() {
             "impl_item"
-                | "trait_item"
-                | "class_definition"
-                | "class_declaration"
-                | "class_specifier"
-                | "struct_item"
-                | "module"
-                | "namespace_definition"
-                | "interface_declaration"
-        );
-        if is_container {
-            if let Some(name_node) = p
+            | "trait_item"
+            | "class_definition"
+            | "class_declaration"
+            | "abstract_class_declaration"
+            | "class_specifier"
+            | "struct_item"
+            | "struct_specifier"
+            | "module"
+            | "namespace_definition"
+            | "interface_declaration"
+            | "enum_declaration" => p
                 .child_by_field_name("name")
-                .or_else(|| p.child_by_field_name("type"))
-            {
-                return name_node.utf8_text(source).ok().map(|s| s.to_string());
-            }
+                .or_else(|| p.child_by_field_name("type")),
+            "block_mapping_pair" | "flow_pair" => p.child_by_field_name("key"),
+            "table" | "table_array_element" => p.named_child(0),
+            _ => None,
+        };
+        if let Some(name_node) = name_node {
+            return name_node.utf8_text(source).ok().map(|s| s.to_string());
         }
         parent = p.parent();
     }
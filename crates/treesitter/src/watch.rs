@@ -0,0 +1,239 @@
+//! IndexWatcher — keeps a [`CodeIndex`] or [`SemanticIndex`] in step
+//! with a VDrive.
+//!
+//! Changes arrive from two sources. The drive's own change events cover
+//! everything agents do through the drive tools. `notify` events on the
+//! drive root cover edits made outside AgentOS, like an editor or a
+//! `git checkout`. Both feed one channel of drive-relative paths. The
+//! paths are debounced into batches, refreshed through the drive (so
+//! overlays and mount policies apply), and the index is saved after
+//! each batch.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::mpsc as std_mpsc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use agentos_vdrive::{DriveEvent, VDrive};
+use notify::{Config, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use super::semantic::SemanticIndex;
+use super::{is_ignored_path, CodeIndex, IndexStats};
+
+/// Quiet period that closes a batch of changed paths.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// An index [`IndexWatcher`] can keep current.
+pub trait DriveIndex: Send + 'static {
+    /// Name used in log messages.
+    const NAME: &'static str;
+    /// Index everything under `dir`, dropping what disappeared.
+    fn index_drive(&mut self, drive: &VDrive, dir: &str) -> Result<IndexStats, String>;
+    /// Bring one changed path up to date.
+    fn refresh_from_drive(&mut self, drive: &VDrive, path: &str) -> bool;
+    /// Persist the index.
+    fn save(&mut self) -> Result<(), String>;
+}
+
+impl DriveIndex for CodeIndex {
+    const NAME: &'static str = "code index";
+
+    fn index_drive(&mut self, drive: &VDrive, dir: &str) -> Result<IndexStats, String> {
+        CodeIndex::index_drive(self, drive, dir)
+    }
+
+    fn refresh_from_drive(&mut self, drive: &VDrive, path: &str) -> bool {
+        CodeIndex::refresh_from_drive(self, drive, path)
+    }
+
+    fn save(&mut self) -> Result<(), String> {
+        CodeIndex::save(self)
+    }
+}
+
+impl DriveIndex for SemanticIndex {
+    const NAME: &'static str = "semantic index";
+
+    fn index_drive(&mut self, drive: &VDrive, dir: &str) -> Result<IndexStats, String> {
+        SemanticIndex::index_drive(self, drive, dir)
+    }
+
+    fn refresh_from_drive(&mut self, drive: &VDrive, path: &str) -> bool {
+        SemanticIndex::refresh_from_drive(self, drive, path)
+    }
+
+    fn save(&mut self) -> Result<(), String> {
+        SemanticIndex::save(self)
+    }
+}
+
+/// Background task keeping an index current. Stops when dropped.
+pub struct IndexWatcher {
+    task: JoinHandle<()>,
+    /// Held so filesystem notifications keep flowing.
+    _watcher: Option<RecommendedWatcher>,
+}
+
+impl IndexWatcher {
+    /// Bring `index` up to date with `drive`, then keep it there. The
+    /// index root should be the drive root. Must be called within a
+    /// tokio runtime.
+    pub fn start<I: DriveIndex>(index: Arc<Mutex<I>>, drive: Arc<VDrive>) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        forward_drive_events(drive.subscribe(), tx.clone());
+        let watcher = match watch_root(drive.root(), tx) {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                warn!("{}: not watching {}: {e}", I::NAME, drive.root().display());
+                None
+            }
+        };
+        let task = tokio::spawn(run(index, drive, rx));
+        Self {
+            task,
+            _watcher: watcher,
+        }
+    }
+}
+
+impl Drop for IndexWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn run<I: DriveIndex>(
+    index: Arc<Mutex<I>>,
+    drive: Arc<VDrive>,
+    mut rx: mpsc::UnboundedReceiver<String>,
+) {
+    sync(&index, &drive, None).await;
+    while let Some(first) = rx.recv().await {
+        let mut batch = BTreeSet::from([first]);
+        while let Ok(Some(path)) = tokio::time::timeout(DEBOUNCE, rx.recv()).await {
+            batch.insert(path);
+        }
+        sync(&index, &drive, Some(batch)).await;
+    }
+}
+
+/// Refresh `paths`, or the whole drive for `None`, then save. Runs on the
+/// blocking pool since it reads and parses files.
+async fn sync<I: DriveIndex>(index: &Arc<Mutex<I>>, drive: &Arc<VDrive>, paths: Option<BTreeSet<String>>) {
+    let index = index.clone();
+    let drive = drive.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let mut index = index.blocking_lock();
+        match paths {
+            None => {
+                let stats = index.index_drive(&drive, "")?;
+                debug!(
+                    "{}: {} parsed, {} unchanged, {} removed, {} skipped",
+                    I::NAME, stats.files_indexed, stats.files_unchanged, stats.files_removed, stats.files_skipped
+                );
+            }
+            Some(paths) => {
+                for path in paths {
+                    index.refresh_from_drive(&drive, &path);
+                }
+            }
+        }
+        index.save()
+    })
+    .await;
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => warn!("{}: {e}", I::NAME),
+        Err(e) => warn!("{}: sync task failed: {e}", I::NAME),
+    }
+}
+
+/// Relay the drive's change events until either side goes away.
+fn forward_drive_events(events: std_mpsc::Receiver<DriveEvent>, tx: mpsc::UnboundedSender<String>) {
+    std::thread::spawn(move || loop {
+        match events.recv_timeout(Duration::from_secs(1)) {
+            Ok(event) => {
+                if tx.send(event.path).is_err() {
+                    break;
+                }
+            }
+            Err(std_mpsc::RecvTimeoutError::Timeout) => {
+                if tx.is_closed() {
+                    break;
+                }
+            }
+            Err(std_mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+    });
+}
+
+/// Watch the drive root recursively, sending changed paths relative to it.
+fn watch_root(root: &Path, tx: mpsc::UnboundedSender<String>) -> notify::Result<RecommendedWatcher> {
+    let base = root.to_path_buf();
+    let mut watcher = RecommendedWatcher::new(
+        move |res: notify::Result<notify::Event>| {
+            let Ok(event) = res else {
+                return;
+            };
+            if matches!(event.kind, EventKind::Access(_)) {
+                return;
+            }
+            for path in event.paths {
+                let Ok(rel) = path.strip_prefix(&base) else {
+                    continue;
+                };
+                let rel = rel.to_string_lossy().replace('\\', "/");
+                if !is_ignored_path(&rel) {
+                    let _ = tx.send(rel);
+                }
+            }
+        },
+        Config::default(),
+    )?;
+    watcher.watch(root, RecursiveMode::Recursive)?;
+    Ok(watcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Poll `check` for up to five seconds.
+    async fn eventually(index: &Arc<Mutex<CodeIndex>>, check: impl Fn(&CodeIndex) -> bool) -> bool {
+        for _ in 0..100 {
+            if check(&*index.lock().await) {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        false
+    }
+
+    #[tokio::test]
+    async fn follows_drive_writes_and_persists() {
+        let root = TempDir::new().unwrap();
+        let data = TempDir::new().unwrap();
+        std::fs::write(root.path().join("a.rs"), "fn alpha() {}").unwrap();
+        let drive = Arc::new(VDrive::open(root.path()).unwrap());
+        let index = Arc::new(Mutex::new(CodeIndex::open(data.path(), drive.root())));
+
+        let watcher = IndexWatcher::start(index.clone(), drive.clone());
+        assert!(eventually(&index, |idx| idx.get_file_symbols("a.rs").is_some()).await);
+
+        drive.write_file("src/b.rs", "pub struct Beta;").unwrap();
+        assert!(eventually(&index, |idx| !idx.search("Beta", None).is_empty()).await);
+
+        drive.delete_file("a.rs").unwrap();
+        assert!(eventually(&index, |idx| idx.get_file_symbols("a.rs").is_none()).await);
+        drop(watcher);
+
+        let reopened = CodeIndex::open(data.path(), drive.root());
+        assert_eq!(reopened.file_count(), 1);
+        assert!(reopened.get_file_symbols("src/b.rs").is_some());
+    }
+}
@@ -127,7 +127,7 @@ impl LanguageService for OrganismYamlService {
             }
             Context::AgentBlock => {
                 complete_keys(
-                    &["prompt", "max_tokens", "max_iterations", "max_agentic_iterations", "model", "permissions"],
+                    &["prompt", "max_tokens", "max_iterations", "max_agentic_iterations", "model", "permissions", "mount"],
                     trimmed,
                 )
             }
@@ -138,7 +138,7 @@ impl LanguageService for OrganismYamlService {
                 )
             }
             Context::Profile => {
-                complete_keys(&["linux_user", "listeners", "journal", "network"], trimmed)
+                complete_keys(&["linux_user", "listeners", "journal", "network", "mount"], trimmed)
             }
             Context::PortItem => {
                 complete_keys(&["port", "direction", "protocol", "hosts"], trimmed)
@@ -401,7 +401,7 @@ fn validate_profiles(
         return;
     };
 
-    let valid_fields = ["linux_user", "listeners", "journal", "network"];
+    let valid_fields = ["linux_user", "listeners", "journal", "network", "mount"];
 
     for (key, profile) in map {
         let profile_name = key.as_str().unwrap_or("<unnamed>");
@@ -765,6 +765,7 @@ fn hover_for_key(key: &str) -> Option<HoverInfo> {
         "buffer" => "Buffer node — callable tool interface + child pipeline spawn config.",
         "max_agentic_iterations" => "Maximum tool-call loop iterations. Default: `25`.",
         "permissions" => "Per-tool permission tiers: `auto`, `prompt` (default), or `deny`.",
+        "mount" => "Mounted-drive policy — `{ read_only, max_bytes_written, max_file_size, max_files, deny, readonly }`. Globs are relative to the drive root; on an agent it tightens the profile's.",
         "required" => "List of mandatory parameter names for the buffer tool interface.",
         "requires" => "Tools available inside the child pipeline (e.g., `[file-read, command-exec]`).",
         "max_concurrency" => "Maximum parallel child instances. Default: `5`.",
@@ -262,6 +262,7 @@ pub enum MenuAction {
     VDriveUnmount,
     VDriveCreate,
     VDriveInfo,
+    VDriveUndo,
     LoadFile,
     /// Open a file from the mounted workspace (VDrive).
     OpenFile,
@@ -583,6 +584,12 @@ pub struct TuiApp {
     /// Shared drive slot — the agent's sandboxed workspace.
     /// Shared with all VDrive tools so mount/unmount is instantly visible.
     pub drive_slot: agentos_tools::vdrive_tools::DriveSlot,
+    /// The pipeline's data dir, where mounted drives keep their change
+    /// journal and overlay layer. None = mounts are not journaled.
+    pub data_dir: Option<std::path::PathBuf>,
+    /// Mount policies from the organism, applied to every drive mounted
+    /// from the TUI.
+    pub mount_policies: agentos_events::MountPolicies,
     /// Cached D2 source for the Graph tab (generated from organism).
     pub graph_d2_source: String,
     /// Cached rendered lines for the Graph tab.
@@ -642,6 +649,7 @@ pub fn build_menu_items(
                 MenuDef::item("Unmount", MenuAction::VDriveUnmount),
                 MenuDef::item("Create Workspace", MenuAction::VDriveCreate),
                 MenuDef::item("Info", MenuAction::VDriveInfo),
+                MenuDef::item("Undo Last Turn", MenuAction::VDriveUndo),
             ],
         ),
         MenuDef::item("Save       ^S", MenuAction::Save),
@@ -886,6 +894,8 @@ impl TuiApp {
             input_scroll: 0,
             input_cursor_last: 0,
             drive_slot: agentos_tools::vdrive_tools::empty_slot(),
+            data_dir: None,
+            mount_policies: Default::default(),
             graph_d2_source: String::new(),
             graph_rendered_lines: Vec::new(),
             graph_rendered_width: 0,
@@ -1632,11 +1642,13 @@ mod tests {
         let mut app = TuiApp::new();
         app.update(TuiMessage::Pipeline(PipelineEvent::TokenUsage {
             thread_id: "t1".into(),
+            model: "claude-sonnet-4-6".into(),
             input_tokens: 100,
             output_tokens: 50,
         }));
         app.update(TuiMessage::Pipeline(PipelineEvent::TokenUsage {
             thread_id: "t2".into(),
+            model: "claude-sonnet-4-6".into(),
             input_tokens: 200,
             output_tokens: 100,
         }));
@@ -135,7 +135,7 @@ pub static COMMANDS: &[SlashCommand] = &[
     SlashCommand {
         name: "/vdrive",
         aliases: &[],
-        description: "Manage virtual drive workspaces (mount/unmount/create/info)",
+        description: "Manage virtual drive workspaces (mount/overlay/review/undo/...)",
         has_arg: true,
         args: &[],
         subcommands: &[
@@ -147,6 +147,14 @@ pub static COMMANDS: &[SlashCommand] = &[
                     kind: ArgKind::Free("folder path"),
                 }],
             },
+            SubcommandSpec {
+                name: "overlay",
+                description: "Mount a folder read-only; agent writes wait for /vdrive review",
+                args: &[ArgSpec {
+                    name: "path",
+                    kind: ArgKind::Free("folder path"),
+                }],
+            },
             SubcommandSpec {
                 name: "unmount",
                 description: "Unmount the current workspace",
@@ -165,6 +173,37 @@ pub static COMMANDS: &[SlashCommand] = &[
                 description: "Show info about the mounted workspace",
                 args: &[],
             },
+            SubcommandSpec {
+                name: "changes",
+                description: "List file changes since the last turn began",
+                args: &[],
+            },
+            SubcommandSpec {
+                name: "undo",
+                description: "Roll back the last agent turn's file changes",
+                args: &[],
+            },
+            SubcommandSpec {
+                name: "review",
+                description: "Show an overlay's pending changes as diffs",
+                args: &[],
+            },
+            SubcommandSpec {
+                name: "apply",
+                description: "Write overlay changes to the folder (all, or one path)",
+                args: &[ArgSpec {
+                    name: "path",
+                    kind: ArgKind::Free("file path (optional)"),
+                }],
+            },
+            SubcommandSpec {
+                name: "discard",
+                description: "Drop overlay changes (all, or one path)",
+                args: &[ArgSpec {
+                    name: "path",
+                    kind: ArgKind::Free("file path (optional)"),
+                }],
+            },
         ],
     },
     SlashCommand {
@@ -192,6 +231,14 @@ pub static COMMANDS: &[SlashCommand] = &[
             },
         ],
     },
+    SlashCommand {
+        name: "/metrics",
+        aliases: &[],
+        description: "Show pipeline metrics (LLM, tools, permissions, WAL, tiers, triggers)",
+        has_arg: false,
+        args: &[],
+        subcommands: &[],
+    },
 ];
 
 /// Return all commands whose name or alias prefix-matches the input.
@@ -399,6 +446,13 @@ pub async fn execute(
         "/provider" => {
             execute_provider(app, arg, arg2).await
         }
+        "/metrics" => {
+            let report = super::dashboard::pipeline_report(&agentos_telemetry::snapshot());
+            CommandResult {
+                feedback: Some(report.join("\n")),
+                handled: true,
+            }
+        }
         "/help" => {
             let mut lines = Vec::new();
             for cmd in COMMANDS {
@@ -446,6 +500,7 @@ pub fn push_feedback(app: &mut TuiApp, text: &str) {
 /// Handle `/vdrive` subcommands.
 async fn execute_vdrive(app: &mut TuiApp, subcommand: &str, arg: &str) -> CommandResult {
     use crate::vdrive;
+    use agentos_tools::vdrive_tools;
     use std::path::Path;
 
     match subcommand {
@@ -485,7 +540,9 @@ async fn execute_vdrive(app: &mut TuiApp, subcommand: &str, arg: &str) -> Comman
                 None
             };
 
-            match vdrive::mount(path) {
+            match vdrive::mount(path, app.data_dir.as_deref())
+                .map(|d| vdrive::apply_policies(d, &app.mount_policies))
+            {
                 Ok(drive) => {
                     let name = drive.name().to_string();
                     let root = drive.root().display().to_string();
@@ -539,7 +596,9 @@ async fn execute_vdrive(app: &mut TuiApp, subcommand: &str, arg: &str) -> Comman
                 }
             }
 
-            match vdrive::create_and_mount(name) {
+            match vdrive::create_and_mount(name, app.data_dir.as_deref())
+                .map(|(path, d)| (path, vdrive::apply_policies(d, &app.mount_policies)))
+            {
                 Ok((_path, drive)) => {
                     let dname = drive.name().to_string();
                     let root = drive.root().display().to_string();
@@ -589,16 +648,154 @@ async fn execute_vdrive(app: &mut TuiApp, subcommand: &str, arg: &str) -> Comman
                 }
             }
         }
+        "changes" => {
+            let drive = app.drive_slot.read().await.clone();
+            let Some(drive) = drive else {
+                return CommandResult {
+                    feedback: Some("No drive is currently mounted.".into()),
+                    handled: true,
+                };
+            };
+            let listing = drive.checkpoints().and_then(|cps| {
+                let since = cps.last().map(|cp| cp.id);
+                Ok((cps, drive.list_changes(since)?))
+            });
+            let feedback = match listing {
+                Ok((cps, changes)) => {
+                    let mut lines = vec![match cps.last() {
+                        Some(cp) => format!("Changes since #{} {}:", cp.id, cp.label),
+                        None => "Changes:".to_string(),
+                    }];
+                    if changes.is_empty() {
+                        lines.push("  (none)".into());
+                    }
+                    for change in &changes {
+                        lines.push(format!("  {}", vdrive_tools::describe_change(change)));
+                    }
+                    lines.join("\n")
+                }
+                Err(e) => format!("Cannot list changes: {e}"),
+            };
+            CommandResult {
+                feedback: Some(feedback),
+                handled: true,
+            }
+        }
+        "undo" => {
+            let drive = app.drive_slot.read().await.clone();
+            let Some(drive) = drive else {
+                return CommandResult {
+                    feedback: Some("No drive is currently mounted.".into()),
+                    handled: true,
+                };
+            };
+            let feedback = match vdrive::undo_last_turn(&drive) {
+                Ok(Some((cp, undone))) => {
+                    let mut lines = vec![format!(
+                        "Rolled back {} change(s) to checkpoint #{} ({})",
+                        undone.len(),
+                        cp.id,
+                        cp.label
+                    )];
+                    for change in undone.iter().rev() {
+                        lines.push(format!("  {}", vdrive_tools::describe_change(change)));
+                    }
+                    lines.join("\n")
+                }
+                Ok(None) => "Nothing to undo.".into(),
+                Err(e) => format!("Undo failed: {e}"),
+            };
+            CommandResult {
+                feedback: Some(feedback),
+                handled: true,
+            }
+        }
+        "overlay" => {
+            let path_str = arg.trim();
+            if path_str.is_empty() {
+                return CommandResult {
+                    feedback: Some("Usage: /vdrive overlay <folder-path>".into()),
+                    handled: true,
+                };
+            }
+            if app.drive_slot.read().await.is_some() {
+                return CommandResult {
+                    feedback: Some("A drive is already mounted. Use /vdrive unmount first.".into()),
+                    handled: true,
+                };
+            }
+            let Some(data_dir) = app.data_dir.clone() else {
+                return CommandResult {
+                    feedback: Some("Overlay mounts need a pipeline data directory.".into()),
+                    handled: true,
+                };
+            };
+            match vdrive::mount_overlay(Path::new(path_str), &data_dir)
+                .map(|d| vdrive::apply_policies(d, &app.mount_policies))
+            {
+                Ok(drive) => {
+                    let name = drive.name().to_string();
+                    let root = drive.root().display().to_string();
+                    let pending = drive.overlay_changes().map(|c| c.len()).unwrap_or(0);
+                    *app.drive_slot.write().await = Some(drive);
+                    let mut msg = format!(
+                        "Mounted overlay: {name} ({root})\nAgent writes are held for /vdrive review; shell commands are disabled."
+                    );
+                    if pending > 0 {
+                        msg.push_str(&format!("\n{pending} change(s) from an earlier session are pending."));
+                    }
+                    CommandResult {
+                        feedback: Some(msg),
+                        handled: true,
+                    }
+                }
+                Err(e) => CommandResult {
+                    feedback: Some(format!("Mount failed: {e}")),
+                    handled: true,
+                },
+            }
+        }
+        "review" | "apply" | "discard" => {
+            let drive = app.drive_slot.read().await.clone();
+            let Some(drive) = drive.filter(|d| d.is_overlay()) else {
+                return CommandResult {
+                    feedback: Some("No overlay mounted. Use /vdrive overlay <path>.".into()),
+                    handled: true,
+                };
+            };
+            let path = Some(arg.trim()).filter(|p| !p.is_empty());
+            let feedback = match subcommand {
+                "review" => match vdrive::review(&drive) {
+                    Ok(Some(text)) => format!(
+                        "{text}\nApply with /vdrive apply [path] or drop with /vdrive discard [path]."
+                    ),
+                    Ok(None) => "No pending changes.".into(),
+                    Err(e) => format!("Review failed: {e}"),
+                },
+                "apply" => match drive.apply_overlay(path) {
+                    Ok(applied) => format!("Applied {} change(s) to {}.", applied.len(), drive.root().display()),
+                    Err(e) => format!("Apply failed: {e}"),
+                },
+                _ => match drive.discard_overlay(path) {
+                    Ok(dropped) => format!("Discarded {} change(s).", dropped.len()),
+                    Err(e) => format!("Discard failed: {e}"),
+                },
+            };
+            CommandResult {
+                feedback: Some(feedback),
+                handled: true,
+            }
+        }
         "" => CommandResult {
             feedback: Some(
-                "Usage:\n  /vdrive mount <path>    Mount a folder as sandboxed workspace\n  /vdrive unmount         Unmount current workspace\n  /vdrive create <name>   Create a new workspace and mount it\n  /vdrive info            Show mounted drive or list workspaces"
+                "Usage:\n  /vdrive mount <path>    Mount a folder as sandboxed workspace\n  /vdrive overlay <path>  Mount a folder read-only; writes wait for review\n  /vdrive unmount         Unmount current workspace\n  /vdrive create <name>   Create a new workspace and mount it\n  /vdrive info            Show mounted drive or list workspaces\n  /vdrive changes         List file changes since the last turn began\n  /vdrive undo            Roll back the last agent turn's file changes\n  /vdrive review          Show pending overlay changes as diffs\n  /vdrive apply [path]    Write overlay changes to the folder\n  /vdrive discard [path]  Drop overlay changes"
                     .into(),
             ),
             handled: true,
         },
         other => CommandResult {
             feedback: Some(format!(
-                "Unknown /vdrive subcommand: {other}\nUsage: /vdrive mount|unmount|create|info"
+                "Unknown /vdrive subcommand: {other}\nUsage: /vdrive mount|overlay|unmount|create|info|changes|undo|review|apply|discard"
             )),
             handled: true,
         },
@@ -1075,6 +1272,14 @@ mod tests {
         assert!(text.contains("/help"));
     }
 
+    #[tokio::test]
+    async fn execute_metrics() {
+        let mut app = TuiApp::new();
+        let result = execute(&mut app, "/metrics", None).await;
+        assert!(result.handled);
+        assert!(!result.feedback.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn execute_unknown() {
         let mut app = TuiApp::new();
@@ -1205,4 +1410,31 @@ mod tests {
         assert!(result.handled);
         assert!(result.feedback.unwrap().contains("Unknown provider"));
     }
+
+    #[tokio::test]
+    async fn vdrive_overlay_review_apply() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.txt"), "old\n").unwrap();
+        let mut app = TuiApp::new();
+        app.data_dir = Some(data.path().to_path_buf());
+
+        let cmd = format!("/vdrive overlay {}", dir.path().display());
+        let result = execute(&mut app, &cmd, None).await;
+        assert!(result.feedback.unwrap().contains("Mounted overlay"));
+
+        let drive = app.drive_slot.read().await.clone().unwrap();
+        drive.write_file("a.txt", "new\n").unwrap();
+        assert_eq!(std::fs::read_to_string(dir.path().join("a.txt")).unwrap(), "old\n");
+
+        let review = execute(&mut app, "/vdrive review", None).await.feedback.unwrap();
+        assert!(review.contains("a.txt (modified)"), "{review}");
+        assert!(review.contains("+new"), "{review}");
+
+        let applied = execute(&mut app, "/vdrive apply a.txt", None).await.feedback.unwrap();
+        assert!(applied.contains("Applied 1"), "{applied}");
+        assert_eq!(std::fs::read_to_string(dir.path().join("a.txt")).unwrap(), "new\n");
+        let review = execute(&mut app, "/vdrive review", None).await.feedback.unwrap();
+        assert_eq!(review, "No pending changes.");
+    }
 }
@@ -1,4 +1,11 @@
-//! Dashboard utilities — status bar formatting.
+//! Dashboard utilities — status bar formatting and the `/metrics` report.
+//!
+//! The report reads the same Prometheus recorder the server renders at
+//! `/metrics` (see `agentos-telemetry`), so the numbers here and on a
+//! Grafana board come from one place.
+
+use agentos_telemetry as t;
+use agentos_telemetry::MetricsSnapshot;
 
 /// Format a byte count for human display.
 pub fn format_bytes(bytes: usize) -> String {
@@ -22,6 +29,125 @@ pub fn format_tokens(tokens: u64) -> String {
     }
 }
 
+/// Format a duration given in seconds for human display.
+pub fn format_duration(secs: f64) -> String {
+    if secs >= 1.0 {
+        format!("{secs:.1}s")
+    } else if secs >= 0.001 {
+        format!("{:.0}ms", secs * 1000.0)
+    } else {
+        format!("{:.0}µs", secs * 1_000_000.0)
+    }
+}
+
+fn mean_or_dash(snap: &MetricsSnapshot, histogram: &str, filter: &[(&str, &str)]) -> String {
+    snap.mean(histogram, filter)
+        .map(format_duration)
+        .unwrap_or_else(|| "—".into())
+}
+
+/// Render the pipeline metrics as plain-text lines for the chat log.
+pub fn pipeline_report(snap: &MetricsSnapshot) -> Vec<String> {
+    if snap.is_empty() {
+        return vec!["No pipeline metrics recorded yet.".into()];
+    }
+    let mut lines = Vec::new();
+
+    lines.push("LLM calls".into());
+    let agents = snap.label_values(t::LLM_CALLS_TOTAL, "agent");
+    if agents.is_empty() {
+        lines.push("  (none)".into());
+    }
+    for model in snap.label_values(t::LLM_CALLS_TOTAL, "model") {
+        for agent in &agents {
+            let f = [("model", model.as_str()), ("agent", agent.as_str())];
+            let calls = snap.sum(t::LLM_CALLS_TOTAL, &f);
+            if calls == 0.0 {
+                continue;
+            }
+            let errors = snap.sum(t::LLM_CALLS_TOTAL, &[f[0], f[1], ("status", t::STATUS_ERROR)]);
+            let tokens = snap.sum(t::LLM_CALL_TOKENS_TOTAL, &f);
+            lines.push(format!(
+                "  {model} · {agent}: {calls} calls, {errors} errors, avg {}, {} tokens",
+                mean_or_dash(snap, t::LLM_CALL_DURATION_SECONDS, &f),
+                format_tokens(tokens as u64),
+            ));
+        }
+    }
+
+    lines.push("Tool calls".into());
+    let tools = snap.label_values(t::TOOL_CALLS_TOTAL, "tool");
+    if tools.is_empty() {
+        lines.push("  (none)".into());
+    }
+    for tool in &tools {
+        let f = [("tool", tool.as_str())];
+        let calls = snap.sum(t::TOOL_CALLS_TOTAL, &f);
+        let failed = snap.sum(t::TOOL_CALLS_TOTAL, &[f[0], ("status", t::STATUS_ERROR)]);
+        lines.push(format!(
+            "  {tool}: {calls} calls, {failed} failed ({:.0}%), avg {}",
+            100.0 * failed / calls.max(1.0),
+            mean_or_dash(snap, t::TOOL_CALL_DURATION_SECONDS, &f),
+        ));
+    }
+
+    lines.push(format!(
+        "Permissions: {} prompts, {} denied by policy, {} denied by user",
+        snap.sum(t::PERMISSION_PROMPTS_TOTAL, &[]),
+        snap.sum(t::PERMISSION_DENIALS_TOTAL, &[("reason", t::DENIED_BY_POLICY)]),
+        snap.sum(t::PERMISSION_DENIALS_TOTAL, &[("reason", t::DENIED_BY_USER)]),
+    ));
+    lines.push(format!(
+        "Injections detected: {}",
+        snap.sum(t::INJECTIONS_DETECTED_TOTAL, &[])
+    ));
+    lines.push(format!(
+        "WAL: {} appends, avg {}, {} written",
+        snap.count(t::WAL_APPEND_DURATION_SECONDS, &[]),
+        mean_or_dash(snap, t::WAL_APPEND_DURATION_SECONDS, &[]),
+        format_bytes(snap.sum(&format!("{}_sum", t::WAL_APPEND_BYTES), &[]) as usize),
+    ));
+
+    let tiers = [t::TIER_ACTIVE, t::TIER_SHELVED, t::TIER_FOLDED];
+    lines.push(format!(
+        "Context: {}",
+        tiers
+            .iter()
+            .map(|tier| {
+                let bytes = snap.sum(t::CONTEXT_BYTES, &[("tier", tier)]) as usize;
+                format!("{tier} {}", format_bytes(bytes))
+            })
+            .collect::<Vec<_>>()
+            .join(" · ")
+    ));
+    lines.push(format!(
+        "Instances: {}",
+        tiers
+            .iter()
+            .map(|tier| format!("{tier} {}", snap.sum(t::PLATFORM_INSTANCES, &[("tier", tier)])))
+            .collect::<Vec<_>>()
+            .join(" · ")
+    ));
+
+    let triggers = snap.label_values(t::TRIGGER_FIRES_TOTAL, "trigger");
+    if triggers.is_empty() {
+        lines.push("Triggers: (none fired)".into());
+    } else {
+        lines.push(format!(
+            "Triggers: {}",
+            triggers
+                .iter()
+                .map(|name| format!(
+                    "{name} {}",
+                    snap.sum(t::TRIGGER_FIRES_TOTAL, &[("trigger", name)])
+                ))
+                .collect::<Vec<_>>()
+                .join(" · ")
+        ));
+    }
+    lines
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -50,4 +176,45 @@ mod tests {
     fn format_bytes_mb() {
         assert_eq!(format_bytes(1_572_864), "1.5 MB");
     }
+
+    #[test]
+    fn format_duration_units() {
+        assert_eq!(format_duration(2.34), "2.3s");
+        assert_eq!(format_duration(0.012), "12ms");
+        assert_eq!(format_duration(0.00025), "250µs");
+    }
+
+    #[test]
+    fn pipeline_report_summarizes_snapshot() {
+        let snap = MetricsSnapshot::parse(
+            r#"agentos_llm_calls_total{model="sonnet",agent="bob",status="ok"} 3
+agentos_llm_calls_total{model="sonnet",agent="bob",status="error"} 1
+agentos_llm_call_tokens_total{model="sonnet",agent="bob",direction="input"} 12000
+agentos_llm_call_tokens_total{model="sonnet",agent="bob",direction="output"} 400
+agentos_llm_call_duration_seconds_sum{model="sonnet",agent="bob",status="ok"} 6
+agentos_llm_call_duration_seconds_count{model="sonnet",agent="bob",status="ok"} 3
+agentos_llm_call_duration_seconds_sum{model="sonnet",agent="bob",status="error"} 2
+agentos_llm_call_duration_seconds_count{model="sonnet",agent="bob",status="error"} 1
+agentos_tool_calls_total{tool="grep",status="ok"} 3
+agentos_tool_calls_total{tool="grep",status="error"} 1
+agentos_tool_call_duration_seconds_sum{tool="grep",status="ok"} 0.04
+agentos_tool_call_duration_seconds_count{tool="grep",status="ok"} 4
+agentos_permission_denials_total{tool="bash",reason="user"} 2
+agentos_context_bytes{tier="active"} 2048
+agentos_trigger_fires_total{trigger="nightly"} 5
+"#,
+        );
+        let report = pipeline_report(&snap).join("\n");
+        assert!(report.contains("sonnet · bob: 4 calls, 1 errors, avg 2.0s, 12.4K tokens"), "{report}");
+        assert!(report.contains("grep: 4 calls, 1 failed (25%), avg 10ms"), "{report}");
+        assert!(report.contains("0 denied by policy, 2 denied by user"), "{report}");
+        assert!(report.contains("active 2.0 KB"), "{report}");
+        assert!(report.contains("nightly 5"), "{report}");
+    }
+
+    #[test]
+    fn pipeline_report_empty() {
+        let report = pipeline_report(&MetricsSnapshot::default());
+        assert_eq!(report, vec!["No pipeline metrics recorded yet."]);
+    }
 }
@@ -99,6 +99,7 @@ mod organism_tests {
             buffer: None,
             python: None,
             trigger: None,
+            shim_trainer: None,
         }
     }
 
@@ -105,6 +105,9 @@ fn dispatch_menu_action(app: &mut TuiApp, action: MenuAction) {
         MenuAction::VDriveInfo => {
             app.pending_command = Some("/vdrive info".to_string());
         }
+        MenuAction::VDriveUndo => {
+            app.pending_command = Some("/vdrive undo".to_string());
+        }
         MenuAction::LoadFile => {
             use super::app::{FilePickerPurpose, FilePickerState};
             let start = std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
@@ -1446,6 +1449,7 @@ mod tests {
         let config = ModelsConfig {
             providers,
             default: Some("sonnet".into()),
+            prices: HashMap::new(),
         };
 
         let items = build_menu_items(&[], false, Some(&config), Some("sonnet"));
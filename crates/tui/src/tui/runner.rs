@@ -115,6 +115,16 @@ pub async fn run_tui(
     let mut app = TuiApp::new();
     app.debug_mode = debug;
     app.drive_slot = drive_slot;
+    app.data_dir = Some(pipeline.data_dir().to_path_buf());
+    let profile = pipeline.organism().profile_names().into_iter().next().unwrap_or("default");
+    app.mount_policies = pipeline.organism().mount_policies(profile);
+    // The startup auto-mount predates the organism; bring it under policy.
+    {
+        let mut slot = app.drive_slot.write().await;
+        if let Some(drive) = slot.take() {
+            *slot = Some(crate::vdrive::apply_policies(drive, &app.mount_policies));
+        }
+    }
     app.llm_pool = pipeline.llm_pool();
     app.models_config = std::sync::Arc::new(tokio::sync::Mutex::new(models_config));
     app.agents_config = agents_config;
@@ -444,6 +454,13 @@ pub async fn run_tui(
                 } else {
                     app.selected_agent.as_deref()
                 };
+                // Checkpoint first so `/vdrive undo` can roll this turn back.
+                let drive = app.drive_slot.read().await.clone();
+                if let Some(drive) = drive {
+                    if let Err(e) = crate::vdrive::checkpoint_turn(&drive, &task) {
+                        super::commands::push_feedback(&mut app, &format!("⚠ vdrive checkpoint failed: {e}"));
+                    }
+                }
                 inject_task(pipeline, &kernel, &task, agent_name).await;
             }
         }
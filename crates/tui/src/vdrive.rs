@@ -5,13 +5,19 @@
 //! real git, real compilers. Containment comes from the WASM sandbox:
 //! tools can only access the mounted directory.
 //!
-//! This module handles the lifecycle (mount/unmount/create). The actual
-//! sandboxed file operations live in `agentos_vdrive::VDrive`.
+//! This module handles the lifecycle (mount/unmount/create), the per-turn
+//! checkpoints behind `/vdrive undo`, and overlay mounts with their
+//! `/vdrive review` summary. The actual sandboxed file operations live in
+//! `agentos_vdrive::VDrive`.
 
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use agentos_vdrive::VDrive;
+use agentos_events::MountPolicies;
+use agentos_vdrive::{Change, Checkpoint, OverlayChangeKind, VDrive};
+
+/// Label prefix for the checkpoint taken before each user task.
+const TURN_LABEL: &str = "turn: ";
 
 /// Default directory for created workspaces: `~/.agentos/workspaces/`.
 pub fn default_workspace_dir() -> PathBuf {
@@ -22,7 +28,10 @@ pub fn default_workspace_dir() -> PathBuf {
 }
 
 /// Mount an existing directory as a VDrive.
-pub fn mount(path: &Path) -> Result<Arc<VDrive>, String> {
+///
+/// With a `journal_dir` (the pipeline's data dir), every change the agent
+/// makes is journaled so turns can be undone.
+pub fn mount(path: &Path, journal_dir: Option<&Path>) -> Result<Arc<VDrive>, String> {
     if !path.exists() {
         return Err(format!("directory not found: {}", path.display()));
     }
@@ -30,11 +39,14 @@ pub fn mount(path: &Path) -> Result<Arc<VDrive>, String> {
         return Err(format!("not a directory: {}", path.display()));
     }
     let drive = VDrive::open(path).map_err(|e| format!("mount failed: {e}"))?;
-    Ok(Arc::new(drive))
+    attach_journal(drive, journal_dir)
 }
 
 /// Create a new empty workspace directory and mount it.
-pub fn create_and_mount(name: &str) -> Result<(PathBuf, Arc<VDrive>), String> {
+pub fn create_and_mount(
+    name: &str,
+    journal_dir: Option<&Path>,
+) -> Result<(PathBuf, Arc<VDrive>), String> {
     if name.is_empty() {
         return Err("workspace name cannot be empty".into());
     }
@@ -51,7 +63,96 @@ pub fn create_and_mount(name: &str) -> Result<(PathBuf, Arc<VDrive>), String> {
     }
 
     let drive = VDrive::create(&dir).map_err(|e| format!("create failed: {e}"))?;
-    Ok((dir, Arc::new(drive)))
+    Ok((dir, attach_journal(drive, journal_dir)?))
+}
+
+/// Mount an existing directory behind a copy-on-write overlay.
+///
+/// The agent's writes land in `{data_dir}/vdrive-overlay/<path-key>/`
+/// until applied with `/vdrive apply`; the folder itself is untouched.
+/// Remounting the same folder picks up any changes still pending.
+pub fn mount_overlay(path: &Path, data_dir: &Path) -> Result<Arc<VDrive>, String> {
+    let canonical = path
+        .canonicalize()
+        .map_err(|_| format!("directory not found: {}", path.display()))?;
+    let key: String = canonical
+        .to_string_lossy()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    let upper = data_dir.join("vdrive-overlay").join(key.trim_matches('_'));
+    let drive = VDrive::open_overlay(&canonical, &upper)
+        .map_err(|e| format!("overlay mount failed: {e}"))?;
+    attach_journal(drive, Some(data_dir))
+}
+
+/// Render pending overlay changes as one unified diff per file.
+/// Returns `None` when there is nothing to review.
+pub fn review(drive: &VDrive) -> Result<Option<String>, String> {
+    let changes = drive.overlay_changes().map_err(|e| e.to_string())?;
+    if changes.is_empty() {
+        return Ok(None);
+    }
+    let mut out = format!("{} pending change(s):\n", changes.len());
+    for change in &changes {
+        let tag = match change.kind {
+            OverlayChangeKind::Added => "added",
+            OverlayChangeKind::Modified => "modified",
+            OverlayChangeKind::Deleted => "deleted",
+        };
+        out.push_str(&format!("\n── {} ({tag}) ──\n", change.path));
+        match drive.overlay_diff(&change.path) {
+            Ok(diff) => out.push_str(&diff),
+            Err(e) => out.push_str(&format!("(no diff: {e})\n")),
+        }
+    }
+    Ok(Some(out))
+}
+
+/// Enforce the organism's mount policies on a mounted drive. Usage
+/// budgets start over with each mount.
+pub fn apply_policies(drive: Arc<VDrive>, policies: &MountPolicies) -> Arc<VDrive> {
+    if policies.is_unrestricted() {
+        return drive;
+    }
+    Arc::new((*drive).clone().with_policies(policies.clone()))
+}
+
+fn attach_journal(drive: VDrive, journal_dir: Option<&Path>) -> Result<Arc<VDrive>, String> {
+    let drive = match journal_dir {
+        Some(dir) => drive
+            .with_journal(dir)
+            .map_err(|e| format!("opening change journal: {e}"))?,
+        None => drive,
+    };
+    Ok(Arc::new(drive))
+}
+
+/// Checkpoint the drive before a user task so the agent's turn can be
+/// rolled back. No-op on drives without a journal.
+pub fn checkpoint_turn(drive: &VDrive, task: &str) -> Result<(), String> {
+    if !drive.has_journal() {
+        return Ok(());
+    }
+    let summary: String = task.lines().next().unwrap_or("").chars().take(60).collect();
+    drive
+        .checkpoint(&format!("{TURN_LABEL}{summary}"))
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Revert the most recent turn that changed anything. Returns the turn's
+/// checkpoint and the undone changes, or `None` if there is nothing to undo.
+pub fn undo_last_turn(drive: &VDrive) -> Result<Option<(Checkpoint, Vec<Change>)>, String> {
+    let checkpoints = drive.checkpoints().map_err(|e| e.to_string())?;
+    for cp in checkpoints.iter().rev() {
+        let changes = drive.list_changes(Some(cp.id)).map_err(|e| e.to_string())?;
+        if !changes.is_empty() {
+            let undone = drive.revert_to(cp.id).map_err(|e| e.to_string())?;
+            return Ok(Some((cp.clone(), undone)));
+        }
+    }
+    Ok(None)
 }
 
 /// List existing workspace directories.
@@ -102,13 +203,13 @@ mod tests {
     #[test]
     fn mount_existing_dir() {
         let dir = tempfile::tempdir().unwrap();
-        let drive = mount(dir.path()).unwrap();
+        let drive = mount(dir.path(), None).unwrap();
         assert!(drive.root().exists());
     }
 
     #[test]
     fn mount_nonexistent_errors() {
-        let result = mount(Path::new("/nonexistent/dir/12345"));
+        let result = mount(Path::new("/nonexistent/dir/12345"), None);
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("not found"));
     }
@@ -118,23 +219,91 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         let file = dir.path().join("afile");
         std::fs::write(&file, "hi").unwrap();
-        let result = mount(&file);
+        let result = mount(&file, None);
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("not a directory"));
     }
 
     #[test]
     fn create_rejects_empty_name() {
-        let result = create_and_mount("");
+        let result = create_and_mount("", None);
         assert!(result.is_err());
     }
 
     #[test]
     fn create_rejects_bad_chars() {
-        let result = create_and_mount("foo/bar");
+        let result = create_and_mount("foo/bar", None);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn undo_last_turn_reverts_latest_changed_turn() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = tempfile::tempdir().unwrap();
+        let drive = mount(dir.path(), Some(data.path())).unwrap();
+
+        checkpoint_turn(&drive, "write a").unwrap();
+        drive.write_file("a.txt", "a").unwrap();
+        checkpoint_turn(&drive, "write b").unwrap();
+        drive.write_file("b.txt", "b").unwrap();
+        // A turn that touched nothing is skipped over.
+        checkpoint_turn(&drive, "just chat").unwrap();
+
+        let (cp, undone) = undo_last_turn(&drive).unwrap().unwrap();
+        assert_eq!(cp.label, "turn: write b");
+        assert_eq!(undone.len(), 1);
+        assert!(!dir.path().join("b.txt").exists());
+        assert!(dir.path().join("a.txt").exists());
+
+        undo_last_turn(&drive).unwrap().unwrap();
+        assert!(!dir.path().join("a.txt").exists());
+        assert!(undo_last_turn(&drive).unwrap().is_none());
+    }
+
+    #[test]
+    fn checkpoint_turn_without_journal_is_noop() {
+        let dir = tempfile::tempdir().unwrap();
+        let drive = mount(dir.path(), None).unwrap();
+        checkpoint_turn(&drive, "task").unwrap();
+    }
+
+    #[test]
+    fn overlay_mount_and_review() {
+        let dir = tempfile::tempdir().unwrap();
+        let data = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+        let drive = mount_overlay(dir.path(), data.path()).unwrap();
+        assert!(drive.is_overlay());
+        assert!(drive.has_journal());
+        assert!(review(&drive).unwrap().is_none());
+
+        drive.write_file("main.rs", "fn main() { run() }\n").unwrap();
+        let text = review(&drive).unwrap().unwrap();
+        assert!(text.contains("main.rs (modified)"));
+        assert!(text.contains("+fn main() { run() }"));
+        assert_eq!(std::fs::read_to_string(dir.path().join("main.rs")).unwrap(), "fn main() {}\n");
+
+        // Pending changes survive a remount.
+        let again = mount_overlay(dir.path(), data.path()).unwrap();
+        assert_eq!(again.overlay_changes().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn policies_apply_to_mounted_drive() {
+        let dir = tempfile::tempdir().unwrap();
+        let drive = mount(dir.path(), None).unwrap();
+        let policies = MountPolicies {
+            base: agentos_events::MountPolicy {
+                read_only: true,
+                ..Default::default()
+            },
+            agents: Default::default(),
+        };
+        let drive = apply_policies(drive, &policies);
+        assert!(drive.write_file("a.txt", "x").is_err());
+        assert!(drive.policy().read_only);
+    }
+
     #[test]
     fn list_empty() {
         // Just verify it doesn't crash on nonexistent dir
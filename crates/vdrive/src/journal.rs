@@ -0,0 +1,760 @@
+//! Change journal — undo history for drive mutations.
+//!
+//! Every mutating op (`write_*`, `edit_file`, `multi_edit`, `mkdir`,
+//! `delete_*`, `rename`, `copy`, `apply_patch`) records what each path it touched looked
+//! like *before* the op. File contents are stored
+//! content-addressed under `blobs/<sha256>`, so rewriting the same file a
+//! hundred times costs one blob per distinct version. The log itself is
+//! `journal.jsonl`, append-only and replayed on open:
+//!
+//! ```text
+//! {data_dir}/vdrive-journal/{root-hash}/
+//!   journal.jsonl   change | checkpoint | revert records
+//!   blobs/<sha256>  prior file contents
+//! ```
+//!
+//! Checkpoints mark turn boundaries (the TUI sets one before each user
+//! task). Reverting walks the changes after a checkpoint newest-first and
+//! puts each path back the way it was; reverted changes are dropped from
+//! the journal, so undo is one-way, and blobs no remaining change refers
+//! to are deleted.
+//!
+//! Each change also records the state it left its path in. A revert
+//! first checks every path it would touch is still in that state; if
+//! something outside the journal changed one since, the revert is
+//! refused with [`VDriveError::RevertConflict`] and nothing is touched.
+//!
+//! Paths are restored one at a time, so a revert can fail partway. The
+//! revert record is only written once every path is back; until then
+//! the changes stay in the journal, and a retry treats paths already
+//! restored as done rather than as conflicts.
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{VDrive, VDriveError, VDriveResult};
+
+/// What kind of op a change recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeOp {
+    Write,
+    Edit,
+    Mkdir,
+    Delete,
+    DeleteDir,
+    Rename,
+    Copy,
+    Patch,
+}
+
+/// The state of a path before a change touched it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PriorState {
+    /// Nothing was there — undo removes the path.
+    Absent,
+    /// A file with this content blob.
+    File { blob: String, size: u64 },
+    /// An (empty) directory.
+    Dir,
+}
+
+/// One journaled mutation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Change {
+    /// Monotonic sequence number, never reused after a revert.
+    pub seq: u64,
+    /// Thread whose turn made the change, if the caller said.
+    pub thread: Option<String>,
+    pub op: ChangeOp,
+    /// Path relative to the drive root.
+    pub path: String,
+    pub prior: PriorState,
+    /// The state the change left the path in, as a content hash (the
+    /// blob isn't stored). `None` on changes journaled before this was
+    /// recorded; those are reverted unchecked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<PriorState>,
+    /// Unix millis.
+    pub at: u64,
+}
+
+/// A named point in the change history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub id: u64,
+    pub label: String,
+    pub thread: Option<String>,
+    /// Last change sequence number at checkpoint time.
+    pub after_seq: u64,
+    /// Unix millis.
+    pub at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+enum Record {
+    Change(Change),
+    Checkpoint(Checkpoint),
+    /// Changes after `after_seq` (only those to `path`, when set) were
+    /// undone.
+    Revert { after_seq: u64, path: Option<String> },
+}
+
+/// On-disk journal for one drive root.
+#[derive(Debug)]
+pub(crate) struct Journal {
+    dir: PathBuf,
+    log: File,
+    changes: Vec<Change>,
+    checkpoints: Vec<Checkpoint>,
+    last_seq: u64,
+    last_checkpoint: u64,
+}
+
+impl Journal {
+    /// Open (or create) the journal for `root` under `data_dir`.
+    pub(crate) fn open(data_dir: &Path, root: &Path) -> VDriveResult<Self> {
+        let key = hex::encode(Sha256::digest(root.to_string_lossy().as_bytes()));
+        let dir = data_dir.join("vdrive-journal").join(&key[..16]);
+        fs::create_dir_all(dir.join("blobs"))?;
+
+        let log_path = dir.join("journal.jsonl");
+        let mut journal = Self {
+            log: File::options().create(true).append(true).open(&log_path)?,
+            dir,
+            changes: Vec::new(),
+            checkpoints: Vec::new(),
+            last_seq: 0,
+            last_checkpoint: 0,
+        };
+        for line in BufReader::new(File::open(&log_path)?).lines() {
+            let line = line?;
+            // A torn final line from a crash is skipped, not fatal.
+            if let Ok(record) = serde_json::from_str::<Record>(&line) {
+                journal.apply(record);
+            }
+        }
+        Ok(journal)
+    }
+
+    fn apply(&mut self, record: Record) {
+        match record {
+            Record::Change(c) => {
+                self.last_seq = self.last_seq.max(c.seq);
+                self.changes.push(c);
+            }
+            Record::Checkpoint(cp) => {
+                self.last_checkpoint = self.last_checkpoint.max(cp.id);
+                self.checkpoints.push(cp);
+            }
+            Record::Revert { after_seq, path } => match path {
+                Some(p) => self.changes.retain(|c| c.seq <= after_seq || c.path != p),
+                None => {
+                    self.changes.retain(|c| c.seq <= after_seq);
+                    self.checkpoints.retain(|cp| cp.after_seq <= after_seq);
+                }
+            },
+        }
+    }
+
+    fn append(&mut self, record: Record) -> VDriveResult<()> {
+        let mut line = serde_json::to_vec(&record)
+            .map_err(|e| VDriveError::Journal(format!("encoding record: {e}")))?;
+        line.push(b'\n');
+        self.log.write_all(&line)?;
+        self.apply(record);
+        Ok(())
+    }
+
+    /// Snapshot what is at `abs` now, storing file contents as a blob.
+    pub(crate) fn capture(&self, abs: &Path) -> VDriveResult<PriorState> {
+        let (state, content) = observe(abs)?;
+        if let (PriorState::File { blob, .. }, Some(content)) = (&state, content) {
+            let blob_path = self.dir.join("blobs").join(blob);
+            if !blob_path.exists() {
+                fs::write(&blob_path, &content)?;
+            }
+        }
+        Ok(state)
+    }
+
+    pub(crate) fn record(
+        &mut self,
+        thread: Option<&str>,
+        op: ChangeOp,
+        path: String,
+        prior: PriorState,
+        after: PriorState,
+    ) -> VDriveResult<()> {
+        let change = Change {
+            seq: self.last_seq + 1,
+            thread: thread.map(str::to_string),
+            op,
+            path,
+            prior,
+            after: Some(after),
+            at: now_millis(),
+        };
+        self.append(Record::Change(change))
+    }
+
+    pub(crate) fn checkpoint(&mut self, label: &str, thread: Option<&str>) -> VDriveResult<Checkpoint> {
+        let cp = Checkpoint {
+            id: self.last_checkpoint + 1,
+            label: label.to_string(),
+            thread: thread.map(str::to_string),
+            after_seq: self.last_seq,
+            at: now_millis(),
+        };
+        self.append(Record::Checkpoint(cp.clone()))?;
+        Ok(cp)
+    }
+
+    pub(crate) fn checkpoints(&self) -> &[Checkpoint] {
+        &self.checkpoints
+    }
+
+    pub(crate) fn find_checkpoint(&self, id: u64) -> VDriveResult<Checkpoint> {
+        self.checkpoints
+            .iter()
+            .find(|cp| cp.id == id)
+            .cloned()
+            .ok_or_else(|| VDriveError::Journal(format!("no checkpoint {id}")))
+    }
+
+    pub(crate) fn changes_after(&self, after_seq: u64) -> Vec<Change> {
+        self.changes
+            .iter()
+            .filter(|c| c.seq > after_seq)
+            .cloned()
+            .collect()
+    }
+
+    /// Undo every change after `after_seq`, newest first.
+    pub(crate) fn revert_after(&mut self, root: &Path, after_seq: u64) -> VDriveResult<Vec<Change>> {
+        let undone = self.changes_after(after_seq);
+        check_unchanged(root, &undone)?;
+        for change in undone.iter().rev() {
+            self.restore(root, change)?;
+        }
+        self.append(Record::Revert { after_seq, path: None })?;
+        self.prune_blobs()?;
+        Ok(undone)
+    }
+
+    /// Put `path` back to how it was at `after_seq`. Returns the number
+    /// of changes undone (zero if the path was untouched since).
+    pub(crate) fn revert_path(&mut self, root: &Path, after_seq: u64, path: &str) -> VDriveResult<usize> {
+        let undone: Vec<Change> = self
+            .changes_after(after_seq)
+            .into_iter()
+            .filter(|c| c.path == path)
+            .collect();
+        // The earliest change holds the state as of the checkpoint.
+        let Some(first) = undone.first() else {
+            return Ok(0);
+        };
+        check_unchanged(root, &undone)?;
+        self.restore(root, first)?;
+        self.append(Record::Revert {
+            after_seq,
+            path: Some(path.to_string()),
+        })?;
+        self.prune_blobs()?;
+        Ok(undone.len())
+    }
+
+    /// Delete every blob no remaining change needs to restore from.
+    /// Returns how many were deleted.
+    fn prune_blobs(&self) -> VDriveResult<usize> {
+        let live: std::collections::HashSet<&str> = self
+            .changes
+            .iter()
+            .filter_map(|c| match &c.prior {
+                PriorState::File { blob, .. } => Some(blob.as_str()),
+                _ => None,
+            })
+            .collect();
+        let mut pruned = 0;
+        for entry in fs::read_dir(self.dir.join("blobs"))? {
+            let entry = entry?;
+            if !live.contains(entry.file_name().to_string_lossy().as_ref()) {
+                fs::remove_file(entry.path())?;
+                pruned += 1;
+            }
+        }
+        Ok(pruned)
+    }
+
+    fn restore(&self, root: &Path, change: &Change) -> VDriveResult<()> {
+        let abs = root.join(&change.path);
+        match &change.prior {
+            PriorState::Absent => {
+                if abs.is_dir() {
+                    // Only ever removes an empty dir: anything the user
+                    // put there since is left alone.
+                    let _ = fs::remove_dir(&abs);
+                } else if abs.exists() {
+                    fs::remove_file(&abs)?;
+                }
+            }
+            PriorState::File { blob, .. } => {
+                let content = fs::read(self.dir.join("blobs").join(blob)).map_err(|e| {
+                    VDriveError::Journal(format!("blob {blob} for {}: {e}", change.path))
+                })?;
+                if let Some(parent) = abs.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&abs, content)?;
+            }
+            PriorState::Dir => fs::create_dir_all(&abs)?,
+        }
+        Ok(())
+    }
+}
+
+impl VDrive {
+    /// Attach the change journal kept under `data_dir`. Reopening the
+    /// same root later picks up the existing history.
+    pub fn with_journal(mut self, data_dir: &Path) -> VDriveResult<Self> {
+        let journal = Journal::open(data_dir, self.write_root())?;
+        self.journal = Some(Arc::new(Mutex::new(journal)));
+        Ok(self)
+    }
+
+    /// A handle that stamps journaled changes with `thread_id`.
+    pub fn for_thread(&self, thread_id: &str) -> Self {
+        Self {
+            thread: Some(thread_id.to_string()),
+            ..self.clone()
+        }
+    }
+
+    /// True if mutations through this drive are journaled.
+    pub fn has_journal(&self) -> bool {
+        self.journal.is_some()
+    }
+
+    /// Mark the current point in the change history.
+    pub fn checkpoint(&self, label: &str) -> VDriveResult<Checkpoint> {
+        self.lock_journal()?.checkpoint(label, self.thread.as_deref())
+    }
+
+    /// All live checkpoints, oldest first.
+    pub fn checkpoints(&self) -> VDriveResult<Vec<Checkpoint>> {
+        Ok(self.lock_journal()?.checkpoints().to_vec())
+    }
+
+    /// Changes made after checkpoint `since` (all changes when `None`),
+    /// oldest first.
+    pub fn list_changes(&self, since: Option<u64>) -> VDriveResult<Vec<Change>> {
+        let journal = self.lock_journal()?;
+        let after_seq = match since {
+            Some(id) => journal.find_checkpoint(id)?.after_seq,
+            None => 0,
+        };
+        Ok(journal.changes_after(after_seq))
+    }
+
+    /// Undo every change made after `checkpoint`. Later checkpoints are
+    /// dropped; `checkpoint` itself stays. Returns the undone changes.
+    pub fn revert_to(&self, checkpoint: u64) -> VDriveResult<Vec<Change>> {
+        let mut journal = self.lock_journal()?;
+        let cp = journal.find_checkpoint(checkpoint)?;
+        let undone = journal.revert_after(self.write_root(), cp.after_seq)?;
+        drop(journal);
+        for change in &undone {
+            self.notify_changed(&change.path);
+        }
+        Ok(undone)
+    }
+
+    /// Restore one file to its state at `checkpoint`, leaving other
+    /// changes in place. Returns how many changes to it were undone.
+    pub fn revert_file(&self, path: &str, checkpoint: u64) -> VDriveResult<usize> {
+        let rel = self.relative(&self.resolve_new(path)?);
+        let mut journal = self.lock_journal()?;
+        let cp = journal.find_checkpoint(checkpoint)?;
+        let undone = journal.revert_path(self.write_root(), cp.after_seq, &rel)?;
+        drop(journal);
+        if undone > 0 {
+            self.notify_changed(&rel);
+        }
+        Ok(undone)
+    }
+
+    /// Run `apply` against `abs`, journaling the prior state if it
+    /// succeeds. The lock is held across the op so concurrent tools can't
+    /// interleave between capture and write.
+    pub(crate) fn journaled<T>(
+        &self,
+        op: ChangeOp,
+        abs: &Path,
+        apply: impl FnOnce() -> VDriveResult<T>,
+    ) -> VDriveResult<T> {
+        let Some(journal) = &self.journal else {
+            return apply();
+        };
+        let mut journal = journal.lock().unwrap_or_else(|e| e.into_inner());
+        // Creating `a/b/c.txt` in an empty drive also creates `a/` and
+        // `a/b/`. Each new dir is journaled top-down so undo, running
+        // newest-first, removes them bottom-up.
+        let start = if op == ChangeOp::Mkdir { Some(abs) } else { abs.parent() };
+        let new_dirs = start.map(|s| missing_dirs(s, self.write_root())).unwrap_or_default();
+        let prior = journal.capture(abs)?;
+        let out = apply()?;
+        let thread = self.thread.as_deref();
+        for dir in &new_dirs {
+            journal.record(thread, ChangeOp::Mkdir, self.relative(dir), PriorState::Absent, PriorState::Dir)?;
+        }
+        if op != ChangeOp::Mkdir {
+            journal.record(thread, op, self.relative(abs), prior, observe(abs)?.0)?;
+        }
+        Ok(out)
+    }
+
+    /// Run `apply`, which moves the tree at `from` to `to`, journaling
+    /// both ends: every entry under `to` as new, then every entry under
+    /// `from` with its prior content. `subs` lists the tree's entries
+    /// relative to `from`, parents first. Undo, running newest-first,
+    /// puts the source back before clearing the destination.
+    pub(crate) fn journaled_move<T>(
+        &self,
+        from: &Path,
+        to: &Path,
+        subs: &[PathBuf],
+        apply: impl FnOnce() -> VDriveResult<T>,
+    ) -> VDriveResult<T> {
+        let Some(journal) = &self.journal else {
+            return apply();
+        };
+        let mut journal = journal.lock().unwrap_or_else(|e| e.into_inner());
+        let new_dirs = to.parent().map(|p| missing_dirs(p, self.write_root())).unwrap_or_default();
+        let pairs: Vec<(PathBuf, PathBuf)> = std::iter::once((from.to_path_buf(), to.to_path_buf()))
+            .chain(subs.iter().map(|sub| (from.join(sub), to.join(sub))))
+            .collect();
+        let priors = pairs
+            .iter()
+            .map(|(src, _)| journal.capture(src))
+            .collect::<VDriveResult<Vec<_>>>()?;
+        let out = apply()?;
+        let thread = self.thread.as_deref();
+        for dir in &new_dirs {
+            journal.record(thread, ChangeOp::Mkdir, self.relative(dir), PriorState::Absent, PriorState::Dir)?;
+        }
+        for (_, dst) in &pairs {
+            journal.record(thread, ChangeOp::Rename, self.relative(dst), PriorState::Absent, observe(dst)?.0)?;
+        }
+        for ((src, _), prior) in pairs.iter().zip(priors) {
+            journal.record(thread, ChangeOp::Rename, self.relative(src), prior, observe(src)?.0)?;
+        }
+        Ok(out)
+    }
+
+    fn lock_journal(&self) -> VDriveResult<MutexGuard<'_, Journal>> {
+        let journal = self
+            .journal
+            .as_ref()
+            .ok_or_else(|| VDriveError::Journal("no change journal on this drive".into()))?;
+        Ok(journal.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+/// What is at `abs` now, with the file's content when it's a file.
+fn observe(abs: &Path) -> VDriveResult<(PriorState, Option<Vec<u8>>)> {
+    if abs.is_dir() {
+        return Ok((PriorState::Dir, None));
+    }
+    if !abs.exists() {
+        return Ok((PriorState::Absent, None));
+    }
+    let content = fs::read(abs)?;
+    let state = PriorState::File {
+        blob: hex::encode(Sha256::digest(&content)),
+        size: content.len() as u64,
+    };
+    Ok((state, Some(content)))
+}
+
+/// Refuse to revert `changes` (oldest first) if any path they touch is
+/// no longer in the state its newest change left it in. A path already
+/// in the state its oldest change found it in is fine too: an earlier
+/// attempt at this revert got that far before failing.
+fn check_unchanged(root: &Path, changes: &[Change]) -> VDriveResult<()> {
+    let mut seen = std::collections::HashSet::new();
+    let mut conflicts = Vec::new();
+    for change in changes.iter().rev() {
+        if !seen.insert(change.path.as_str()) {
+            continue;
+        }
+        let Some(after) = &change.after else {
+            continue;
+        };
+        let now = observe(&root.join(&change.path))?.0;
+        let Some(oldest) = changes.iter().find(|c| c.path == change.path) else {
+            continue;
+        };
+        if now != *after && now != oldest.prior {
+            conflicts.push(change.path.clone());
+        }
+    }
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    conflicts.sort();
+    Err(VDriveError::RevertConflict(conflicts))
+}
+
+/// Dirs from `start` upward that don't exist yet, outermost first.
+fn missing_dirs(start: &Path, root: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = start
+        .ancestors()
+        .take_while(|p| p.starts_with(root) && !p.exists())
+        .map(Path::to_path_buf)
+        .collect();
+    dirs.reverse();
+    dirs
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, TempDir, VDrive) {
+        let dir = TempDir::new().unwrap();
+        let data = TempDir::new().unwrap();
+        let vd = VDrive::open(dir.path()).unwrap().with_journal(data.path()).unwrap();
+        (dir, data, vd)
+    }
+
+    #[test]
+    fn no_journal_by_default() {
+        let dir = TempDir::new().unwrap();
+        let vd = VDrive::open(dir.path()).unwrap();
+        assert!(!vd.has_journal());
+        vd.write_file("a.txt", "x").unwrap();
+        assert!(matches!(vd.checkpoint("t"), Err(VDriveError::Journal(_))));
+    }
+
+    #[test]
+    fn revert_to_restores_every_op() {
+        let (dir, _data, vd) = setup();
+        fs::write(dir.path().join("keep.txt"), "original").unwrap();
+        fs::write(dir.path().join("gone.txt"), "doomed").unwrap();
+        fs::create_dir(dir.path().join("empty")).unwrap();
+
+        let cp = vd.checkpoint("turn 1").unwrap();
+        vd.edit_file("keep.txt", "original", "edited", false).unwrap();
+        vd.write_file("keep.txt", "overwritten").unwrap();
+        vd.write_file("new/deep/file.rs", "fn main() {}").unwrap();
+        vd.delete_file("gone.txt").unwrap();
+        vd.delete_dir("empty").unwrap();
+        assert_eq!(vd.list_changes(Some(cp.id)).unwrap().len(), 7);
+
+        let undone = vd.revert_to(cp.id).unwrap();
+        assert_eq!(undone.len(), 7);
+        assert_eq!(fs::read_to_string(dir.path().join("keep.txt")).unwrap(), "original");
+        assert_eq!(fs::read_to_string(dir.path().join("gone.txt")).unwrap(), "doomed");
+        assert!(dir.path().join("empty").is_dir());
+        assert!(!dir.path().join("new").exists());
+        assert!(vd.list_changes(Some(cp.id)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn revert_drops_later_checkpoints() {
+        let (_dir, _data, vd) = setup();
+        let first = vd.checkpoint("one").unwrap();
+        vd.write_file("a.txt", "a").unwrap();
+        vd.checkpoint("two").unwrap();
+        vd.revert_to(first.id).unwrap();
+        let ids: Vec<u64> = vd.checkpoints().unwrap().iter().map(|c| c.id).collect();
+        assert_eq!(ids, vec![first.id]);
+    }
+
+    #[test]
+    fn revert_file_leaves_other_changes() {
+        let (dir, _data, vd) = setup();
+        fs::write(dir.path().join("a.txt"), "a0").unwrap();
+        let cp = vd.checkpoint("t").unwrap();
+        vd.write_file("a.txt", "a1").unwrap();
+        vd.write_file("a.txt", "a2").unwrap();
+        vd.write_file("b.txt", "b1").unwrap();
+
+        assert_eq!(vd.revert_file("a.txt", cp.id).unwrap(), 2);
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "a0");
+        assert_eq!(fs::read_to_string(dir.path().join("b.txt")).unwrap(), "b1");
+        let left = vd.list_changes(Some(cp.id)).unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].path, "b.txt");
+        assert_eq!(vd.revert_file("a.txt", cp.id).unwrap(), 0);
+    }
+
+    #[test]
+    fn changes_carry_thread() {
+        let (_dir, _data, vd) = setup();
+        vd.for_thread("thread-1").write_file("a.txt", "a").unwrap();
+        vd.write_file("b.txt", "b").unwrap();
+        let changes = vd.list_changes(None).unwrap();
+        assert_eq!(changes[0].thread.as_deref(), Some("thread-1"));
+        assert_eq!(changes[1].thread, None);
+    }
+
+    #[test]
+    fn revert_undoes_rename_and_copy() {
+        let (dir, _data, vd) = setup();
+        fs::create_dir_all(dir.path().join("src/sub")).unwrap();
+        fs::write(dir.path().join("src/sub/a.rs"), "a").unwrap();
+        fs::write(dir.path().join("b.rs"), "b").unwrap();
+
+        let cp = vd.checkpoint("turn").unwrap();
+        vd.rename("src", "moved/src").unwrap();
+        vd.copy("b.rs", "c.rs").unwrap();
+        vd.multi_edit(
+            "b.rs",
+            &[crate::TextEdit {
+                old_string: "b".into(),
+                new_string: "bb".into(),
+                replace_all: false,
+            }],
+        )
+        .unwrap();
+
+        vd.revert_to(cp.id).unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("src/sub/a.rs")).unwrap(), "a");
+        assert_eq!(fs::read_to_string(dir.path().join("b.rs")).unwrap(), "b");
+        assert!(!dir.path().join("moved").exists());
+        assert!(!dir.path().join("c.rs").exists());
+    }
+
+    #[test]
+    fn failed_op_not_journaled() {
+        let (dir, _data, vd) = setup();
+        fs::write(dir.path().join("a.txt"), "abc").unwrap();
+        assert!(vd.edit_file("a.txt", "zzz", "y", false).is_err());
+        assert!(vd.list_changes(None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn identical_content_shares_blob() {
+        let (dir, data, vd) = setup();
+        fs::write(dir.path().join("a.txt"), "same").unwrap();
+        fs::write(dir.path().join("b.txt"), "same").unwrap();
+        vd.write_file("a.txt", "x").unwrap();
+        vd.write_file("b.txt", "x").unwrap();
+        let blobs = fs::read_dir(data.path().join("vdrive-journal"))
+            .unwrap()
+            .map(|e| fs::read_dir(e.unwrap().path().join("blobs")).unwrap().count())
+            .sum::<usize>();
+        assert_eq!(blobs, 1);
+    }
+
+    #[test]
+    fn journal_survives_reopen() {
+        let (dir, data, vd) = setup();
+        fs::write(dir.path().join("a.txt"), "before").unwrap();
+        let cp = vd.checkpoint("t").unwrap();
+        vd.write_file("a.txt", "after").unwrap();
+        drop(vd);
+
+        let vd = VDrive::open(dir.path()).unwrap().with_journal(data.path()).unwrap();
+        assert_eq!(vd.checkpoints().unwrap(), vec![cp.clone()]);
+        assert_eq!(vd.list_changes(Some(cp.id)).unwrap().len(), 1);
+        vd.revert_to(cp.id).unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "before");
+
+        let vd = VDrive::open(dir.path()).unwrap().with_journal(data.path()).unwrap();
+        assert!(vd.list_changes(None).unwrap().is_empty());
+        assert_eq!(vd.checkpoint("next").unwrap().id, cp.id + 1);
+    }
+
+    #[test]
+    fn revert_refuses_paths_changed_outside_the_journal() {
+        let (dir, _data, vd) = setup();
+        fs::write(dir.path().join("a.txt"), "a0").unwrap();
+        let cp = vd.checkpoint("t").unwrap();
+        vd.write_file("a.txt", "a1").unwrap();
+        vd.write_file("b.txt", "b1").unwrap();
+        fs::write(dir.path().join("a.txt"), "edited by hand").unwrap();
+
+        let err = vd.revert_to(cp.id).unwrap_err();
+        assert!(matches!(&err, VDriveError::RevertConflict(paths) if paths == &["a.txt"]), "{err}");
+        assert!(matches!(vd.revert_file("a.txt", cp.id), Err(VDriveError::RevertConflict(_))));
+        // Nothing was touched, and the history is still there.
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "edited by hand");
+        assert!(dir.path().join("b.txt").exists());
+        assert_eq!(vd.list_changes(Some(cp.id)).unwrap().len(), 2);
+
+        // Unrelated paths still revert one at a time.
+        assert_eq!(vd.revert_file("b.txt", cp.id).unwrap(), 1);
+        assert!(!dir.path().join("b.txt").exists());
+    }
+
+    #[test]
+    fn failed_revert_keeps_its_changes_and_can_be_retried() {
+        let (dir, data, vd) = setup();
+        fs::write(dir.path().join("a.txt"), "a0").unwrap();
+        let cp = vd.checkpoint("t").unwrap();
+        vd.write_file("a.txt", "a1").unwrap();
+        vd.write_file("b.txt", "b1").unwrap();
+
+        // Lose a0's blob so the revert fails after b.txt (newest) is undone.
+        let blob = fs::read_dir(data.path().join("vdrive-journal"))
+            .unwrap()
+            .map(|e| e.unwrap().path().join("blobs"))
+            .next()
+            .unwrap()
+            .join(hex::encode(Sha256::digest(b"a0")));
+        fs::rename(&blob, blob.with_extension("aside")).unwrap();
+        assert!(matches!(vd.revert_to(cp.id), Err(VDriveError::Journal(_))));
+        assert!(!dir.path().join("b.txt").exists());
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "a1");
+        assert_eq!(vd.list_changes(Some(cp.id)).unwrap().len(), 2);
+
+        fs::rename(blob.with_extension("aside"), &blob).unwrap();
+        assert_eq!(vd.revert_to(cp.id).unwrap().len(), 2);
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "a0");
+        assert!(vd.list_changes(Some(cp.id)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn revert_prunes_unreferenced_blobs() {
+        let (dir, data, vd) = setup();
+        let blobs = || {
+            fs::read_dir(data.path().join("vdrive-journal"))
+                .unwrap()
+                .map(|e| fs::read_dir(e.unwrap().path().join("blobs")).unwrap().count())
+                .sum::<usize>()
+        };
+        fs::write(dir.path().join("a.txt"), "a0").unwrap();
+        vd.write_file("a.txt", "a1").unwrap();
+        let cp = vd.checkpoint("t").unwrap();
+        vd.write_file("a.txt", "a2").unwrap();
+        vd.write_file("a.txt", "a3").unwrap();
+        assert_eq!(blobs(), 3);
+
+        vd.revert_to(cp.id).unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "a1");
+        assert_eq!(blobs(), 1, "only a0, which the change before the checkpoint still needs");
+    }
+
+    #[test]
+    fn unknown_checkpoint_errors() {
+        let (_dir, _data, vd) = setup();
+        assert!(matches!(vd.revert_to(42), Err(VDriveError::Journal(_))));
+    }
+}
@@ -7,12 +7,41 @@
 //! The agent clones a repo, points a VDrive at it, and all file tools
 //! operate through the drive. Real files on disk, real git, real compilers —
 //! but structurally contained.
+//!
+//! A drive opened [`with_journal`](VDrive::with_journal) also records every
+//! mutation so agent turns can be checkpointed and rolled back. One opened
+//! with [`open_overlay`](VDrive::open_overlay) never writes to its root at
+//! all until the user applies the pending changes.
+//!
+//! [`apply_patch`](VDrive::apply_patch) takes a multi-file unified diff
+//! and applies it all-or-nothing, locating each hunk with whitespace and
+//! context fuzz.
+//!
+//! Anything that mirrors the drive (the code index, say) can
+//! [`subscribe`](VDrive::subscribe) to the paths each operation changes.
+//!
+//! A [`MountPolicy`](agentos_events::MountPolicy) set with
+//! [`with_policy`](VDrive::with_policy) narrows what callers may see and
+//! change — read-only mounts and globs, hidden globs, and write budgets —
+//! and is enforced by every operation here.
 
+mod journal;
 mod ops;
+mod overlay;
+mod patch;
+mod policy;
 
 use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
 
+pub use journal::{Change, ChangeOp, Checkpoint, PriorState};
 pub use ops::*;
+pub use overlay::{OverlayChange, OverlayChangeKind};
+pub use patch::{FilePatchReport, Fuzz, HunkOutcome, PatchAction, PatchReport, MAX_CONTEXT_FUZZ};
+
+use journal::Journal;
+use policy::PolicyState;
 
 /// Errors from VDrive operations.
 #[derive(Debug, thiserror::Error)]
@@ -47,12 +76,54 @@ pub enum VDriveError {
     #[error("edit failed: old_string matches {count} times in {path} (must be unique or use replace_all)")]
     EditAmbiguous { path: String, count: usize },
 
+    #[error("edit {edit} failed, no edits applied: {source}")]
+    MultiEdit {
+        /// 1-based position of the failing edit.
+        edit: usize,
+        source: Box<VDriveError>,
+    },
+
+    #[error("invalid patch: {0}")]
+    InvalidPatch(String),
+
+    #[error("{source}; rolling back also failed for {}", failed.join(", "))]
+    RollbackFailed {
+        source: Box<VDriveError>,
+        /// `path: error` for each file that couldn't be put back.
+        failed: Vec<String>,
+    },
+
+    #[error("journal: {0}")]
+    Journal(String),
+
+    #[error("changed since it was journaled, not reverting: {}", .0.join(", "))]
+    RevertConflict(Vec<String>),
+
+    #[error("read-only under the mount policy: {0}")]
+    ReadOnly(String),
+
+    #[error("mount quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    #[error("drive is not an overlay")]
+    NotOverlay,
+
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
 }
 
 pub type VDriveResult<T> = Result<T, VDriveError>;
 
+/// A path some drive operation created, changed or removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DriveEvent {
+    /// Path relative to the drive root. May be a directory, in which case
+    /// anything under it may have changed.
+    pub path: String,
+    /// True if the path no longer exists in the drive's view.
+    pub removed: bool,
+}
+
 /// A sandboxed view of a real directory on disk.
 ///
 /// All path operations are resolved against `root`. Any attempt to
@@ -63,6 +134,18 @@ pub struct VDrive {
     root: PathBuf,
     /// Human-friendly name (basename of the root directory).
     name: String,
+    /// Change journal, shared by every clone of the drive.
+    journal: Option<Arc<Mutex<Journal>>>,
+    /// Thread id stamped on journaled changes made through this handle.
+    thread: Option<String>,
+    /// Canonical upper layer for overlay drives; writes land here.
+    upper: Option<PathBuf>,
+    /// Mount policies and usage, shared by every clone of the drive.
+    policy: Option<Arc<PolicyState>>,
+    /// Agent whose policy this handle enforces.
+    agent: Option<String>,
+    /// Receivers of [`DriveEvent`]s, shared by every clone of the drive.
+    subscribers: Arc<Mutex<Vec<mpsc::Sender<DriveEvent>>>>,
 }
 
 impl VDrive {
@@ -82,7 +165,16 @@ impl VDrive {
             .file_name()
             .map(|n| n.to_string_lossy().to_string())
             .unwrap_or_else(|| root.display().to_string());
-        Ok(Self { root, name })
+        Ok(Self {
+            root,
+            name,
+            journal: None,
+            thread: None,
+            upper: None,
+            policy: None,
+            agent: None,
+            subscribers: Arc::default(),
+        })
     }
 
     /// Create a new directory and open it as a VDrive.
@@ -111,7 +203,22 @@ impl VDrive {
     /// For paths to files that don't exist yet (e.g., file-write target),
     /// use `resolve_parent()` which validates the parent directory exists
     /// and the final component doesn't escape.
+    ///
+    /// On an overlay drive this is where the path's current content
+    /// lives: the upper copy if there is one, else the root's. Paths the
+    /// mount policy denies are reported as not found.
     pub fn resolve(&self, user_path: &str) -> VDriveResult<PathBuf> {
+        let resolved = match &self.upper {
+            Some(upper) => self.resolve_overlay(upper, user_path)?,
+            None => self.resolve_lower(user_path)?,
+        };
+        if self.is_denied(&self.relative(&resolved)) {
+            return Err(VDriveError::NotFound(user_path.to_string()));
+        }
+        Ok(resolved)
+    }
+
+    fn resolve_lower(&self, user_path: &str) -> VDriveResult<PathBuf> {
         let candidate = if Path::new(user_path).is_absolute() {
             PathBuf::from(user_path)
         } else {
@@ -131,70 +238,120 @@ impl VDrive {
 
     /// Resolve a path where the target (and parents) may not exist yet.
     ///
-    /// Walks up to the highest existing ancestor, canonicalizes it,
-    /// verifies it's within root, then appends the remaining segments.
     /// Used for write/mkdir operations that create intermediate dirs.
+    /// On an overlay drive the result is always in the upper layer.
     pub fn resolve_new(&self, user_path: &str) -> VDriveResult<PathBuf> {
-        let candidate = if Path::new(user_path).is_absolute() {
-            PathBuf::from(user_path)
-        } else {
-            self.root.join(user_path)
-        };
-
-        // Reject any path component that is ".."
-        for component in candidate.components() {
-            if let std::path::Component::ParentDir = component {
-                return Err(VDriveError::Escape(user_path.to_string()));
-            }
-        }
-
-        // Walk up to find the highest existing ancestor
-        let mut existing = candidate.as_path();
-        let mut tail_parts = Vec::new();
-        loop {
-            if existing.exists() {
-                break;
+        match &self.upper {
+            Some(upper) => {
+                let rel = self.logical_rel(user_path)?;
+                resolve_new_under(upper, &upper.join(rel), user_path)
             }
-            match (existing.parent(), existing.file_name()) {
-                (Some(parent), Some(name)) => {
-                    tail_parts.push(name.to_os_string());
-                    existing = parent;
-                }
-                _ => return Err(VDriveError::Escape(user_path.to_string())),
+            None => {
+                let candidate = if Path::new(user_path).is_absolute() {
+                    PathBuf::from(user_path)
+                } else {
+                    self.root.join(user_path)
+                };
+                resolve_new_under(&self.root, &candidate, user_path)
             }
         }
+    }
 
-        // Canonicalize the existing portion and verify it's in root
-        let resolved_base = existing.canonicalize().map_err(|_| {
-            VDriveError::NotFound(user_path.to_string())
-        })?;
-        if !resolved_base.starts_with(&self.root) {
-            return Err(VDriveError::Escape(user_path.to_string()));
-        }
+    /// Where mutations land: the upper layer of an overlay, else root.
+    pub(crate) fn write_root(&self) -> &Path {
+        self.upper.as_deref().unwrap_or(&self.root)
+    }
 
-        // Rebuild the full path with the non-existent tail
-        let mut result = resolved_base;
-        for part in tail_parts.into_iter().rev() {
-            let s = part.to_string_lossy();
-            if s == "." || s == ".." {
-                return Err(VDriveError::Escape(user_path.to_string()));
-            }
-            result = result.join(part);
-        }
+    /// Receive a [`DriveEvent`] for every path changed through this drive
+    /// or any clone of it, from now until the receiver is dropped.
+    pub fn subscribe(&self) -> mpsc::Receiver<DriveEvent> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.lock().unwrap_or_else(|e| e.into_inner()).push(tx);
+        rx
+    }
 
-        Ok(result)
+    /// Tell subscribers `user_path` changed.
+    pub(crate) fn notify_changed(&self, user_path: &str) {
+        let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+        if subscribers.is_empty() {
+            return;
+        }
+        let Ok(rel) = self.logical_rel(user_path) else {
+            return;
+        };
+        let path = rel.to_string_lossy().replace('\\', "/");
+        let event = DriveEvent {
+            removed: self.resolve(&path).is_err(),
+            path,
+        };
+        subscribers.retain(|tx| tx.send(event.clone()).is_ok());
     }
 
     /// Convert an absolute path back to a drive-relative path for display.
     pub fn relative(&self, abs_path: &Path) -> String {
+        let base = if self.in_upper(abs_path) {
+            self.write_root()
+        } else {
+            &self.root
+        };
         abs_path
-            .strip_prefix(&self.root)
+            .strip_prefix(base)
             .unwrap_or(abs_path)
             .to_string_lossy()
             .replace('\\', "/")
     }
 }
 
+/// Resolve `candidate` (which may not exist yet) and check it stays
+/// under `base`.
+///
+/// Walks up to the highest existing ancestor, canonicalizes it, verifies
+/// it's within `base`, then appends the remaining segments.
+fn resolve_new_under(base: &Path, candidate: &Path, user_path: &str) -> VDriveResult<PathBuf> {
+    // Reject any path component that is ".."
+    for component in candidate.components() {
+        if let std::path::Component::ParentDir = component {
+            return Err(VDriveError::Escape(user_path.to_string()));
+        }
+    }
+
+    // Walk up to find the highest existing ancestor
+    let mut existing = candidate;
+    let mut tail_parts = Vec::new();
+    loop {
+        if existing.exists() {
+            break;
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                tail_parts.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return Err(VDriveError::Escape(user_path.to_string())),
+        }
+    }
+
+    // Canonicalize the existing portion and verify it's in base
+    let resolved_base = existing.canonicalize().map_err(|_| {
+        VDriveError::NotFound(user_path.to_string())
+    })?;
+    if !resolved_base.starts_with(base) {
+        return Err(VDriveError::Escape(user_path.to_string()));
+    }
+
+    // Rebuild the full path with the non-existent tail
+    let mut result = resolved_base;
+    for part in tail_parts.into_iter().rev() {
+        let s = part.to_string_lossy();
+        if s == "." || s == ".." {
+            return Err(VDriveError::Escape(user_path.to_string()));
+        }
+        result = result.join(part);
+    }
+
+    Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -305,6 +462,17 @@ mod tests {
         assert_eq!(rel, "src/main.rs");
     }
 
+    #[test]
+    fn subscribers_see_changed_paths() {
+        let (_dir, vd) = setup();
+        let rx = vd.clone().subscribe();
+        vd.write_file("src/a.rs", "fn a() {}").unwrap();
+        vd.rename("src/a.rs", "src/b.rs").unwrap();
+        let events: Vec<DriveEvent> = rx.try_iter().collect();
+        let seen: Vec<(&str, bool)> = events.iter().map(|e| (e.path.as_str(), e.removed)).collect();
+        assert_eq!(seen, vec![("src/a.rs", false), ("src/a.rs", true), ("src/b.rs", false)]);
+    }
+
     #[cfg(unix)]
     #[test]
     fn symlink_escape_blocked() {
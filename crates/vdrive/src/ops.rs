@@ -1,8 +1,11 @@
 //! File operations on a VDrive — all sandboxed to the drive root.
 
 use std::fs;
+use std::path::{Path, PathBuf};
 
-use crate::{VDrive, VDriveError, VDriveResult};
+use serde::Deserialize;
+
+use crate::{ChangeOp, VDrive, VDriveError, VDriveResult};
 
 /// Metadata about a file or directory.
 #[derive(Debug, Clone)]
@@ -26,6 +29,16 @@ pub struct GrepMatch {
     pub line: String,
 }
 
+/// One replacement in a [`multi_edit`](VDrive::multi_edit).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TextEdit {
+    pub old_string: String,
+    pub new_string: String,
+    /// Replace every occurrence instead of requiring exactly one.
+    #[serde(default)]
+    pub replace_all: bool,
+}
+
 /// Result of a read operation.
 #[derive(Debug)]
 pub struct ReadResult {
@@ -98,22 +111,44 @@ impl VDrive {
     /// Write content to a file (creates or overwrites).
     /// Creates parent directories as needed.
     pub fn write_file(&self, path: &str, content: &str) -> VDriveResult<()> {
-        let resolved = self.resolve_new(path)?;
-        if let Some(parent) = resolved.parent() {
-            fs::create_dir_all(parent)?;
-        }
-        fs::write(&resolved, content)?;
-        Ok(())
+        self.write_bytes(path, content.as_bytes())
     }
 
     /// Write binary content to a file (creates or overwrites).
     /// Creates parent directories as needed.
     pub fn write_bytes(&self, path: &str, content: &[u8]) -> VDriveResult<()> {
-        let resolved = self.resolve_new(path)?;
-        if let Some(parent) = resolved.parent() {
-            fs::create_dir_all(parent)?;
+        self.write_as(ChangeOp::Write, path, content)
+    }
+
+    /// `write_bytes`, journaled as `op`.
+    pub(crate) fn write_as(&self, op: ChangeOp, path: &str, content: &[u8]) -> VDriveResult<()> {
+        let existing = self.resolve(path).ok();
+        if existing.as_ref().is_some_and(|p| p.is_dir()) {
+            return Err(VDriveError::IsDirectory(path.to_string()));
         }
-        fs::write(&resolved, content)?;
+        let resolved = self.resolve_new(path)?;
+        self.charge_write(&resolved, path, content.len() as u64, existing.is_none())?;
+        self.write_resolved(op, &resolved, path, content)
+    }
+
+    /// The write half of `write_as`, to an already resolved target and
+    /// without charging the budget. Rollbacks put content back this way.
+    pub(crate) fn write_resolved(
+        &self,
+        op: ChangeOp,
+        resolved: &Path,
+        path: &str,
+        content: &[u8],
+    ) -> VDriveResult<()> {
+        self.journaled(op, resolved, || {
+            if let Some(parent) = resolved.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(resolved, content)?;
+            Ok(())
+        })?;
+        self.clear_whiteouts(resolved)?;
+        self.notify_changed(path);
         Ok(())
     }
 
@@ -134,27 +169,59 @@ impl VDrive {
         }
 
         let content = fs::read_to_string(&resolved)?;
-        let count = content.matches(old_string).count();
+        let new_content = replace_text(&content, path, old_string, new_string, replace_all)?;
+        self.write_edited(path, new_content)
+    }
 
-        if count == 0 {
-            return Err(VDriveError::EditNotFound(path.to_string()));
+    /// Apply `edits` to one file in order, each seeing the result of the
+    /// ones before it. All-or-nothing: if any edit fails the file is left
+    /// untouched and the error names the failing edit.
+    pub fn multi_edit(&self, path: &str, edits: &[TextEdit]) -> VDriveResult<()> {
+        let resolved = self.resolve(path)?;
+        if resolved.is_dir() {
+            return Err(VDriveError::IsDirectory(path.to_string()));
         }
-        if count > 1 && !replace_all {
-            return Err(VDriveError::EditAmbiguous {
-                path: path.to_string(),
-                count,
-            });
+
+        let mut content = fs::read_to_string(&resolved)?;
+        for (i, edit) in edits.iter().enumerate() {
+            content = replace_text(&content, path, &edit.old_string, &edit.new_string, edit.replace_all)
+                .map_err(|e| VDriveError::MultiEdit {
+                    edit: i + 1,
+                    source: Box::new(e),
+                })?;
         }
+        self.write_edited(path, content)
+    }
 
-        let new_content = content.replace(old_string, new_string);
-        fs::write(&resolved, new_content)?;
+    /// Write the result of an edit back to `path`.
+    fn write_edited(&self, path: &str, new_content: String) -> VDriveResult<()> {
+        // On an overlay the edited copy goes to the upper layer.
+        let target = self.resolve_new(path)?;
+        self.charge_write(&target, path, new_content.len() as u64, false)?;
+        self.journaled(ChangeOp::Edit, &target, || {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            Ok(fs::write(&target, new_content)?)
+        })?;
+        self.notify_changed(path);
         Ok(())
     }
 
     // ── Glob ──
 
     /// Find files matching a glob pattern within the drive.
+    /// Paths the mount policy denies are left out.
     pub fn glob(&self, pattern: &str) -> VDriveResult<Vec<String>> {
+        let mut results = match &self.upper {
+            Some(upper) => self.overlay_glob(upper, pattern)?,
+            None => self.glob_lower(pattern)?,
+        };
+        results.retain(|p| !self.is_denied(p));
+        Ok(results)
+    }
+
+    fn glob_lower(&self, pattern: &str) -> VDriveResult<Vec<String>> {
         let full_pattern = self.root.join(pattern);
         let pattern_str = full_pattern.to_string_lossy().to_string();
 
@@ -244,15 +311,33 @@ impl VDrive {
 
     // ── List directory ──
 
-    /// List entries in a directory.
+    /// List entries in a directory, leaving out any the mount policy denies.
     pub fn list_dir(&self, path: &str) -> VDriveResult<Vec<EntryInfo>> {
         let resolved = self.resolve(path)?;
         if !resolved.is_dir() {
             return Err(VDriveError::NotDirectory(path.to_string()));
         }
+        let mut entries = match &self.upper {
+            Some(upper) => self.overlay_list_dir(upper, &self.logical_rel(path)?)?,
+            None => self.list_dir_lower(&resolved)?,
+        };
+        entries.retain(|e| !self.is_denied(&e.path));
+        Ok(entries)
+    }
+
+    /// `list_dir` without the policy filter, for ops that must see
+    /// everything they would move.
+    fn list_dir_raw(&self, path: &str) -> VDriveResult<Vec<EntryInfo>> {
+        match &self.upper {
+            Some(upper) => self.overlay_list_dir(upper, &self.logical_rel(path)?),
+            None => self.list_dir_lower(&self.resolve_lower(path)?),
+        }
+    }
+
+    fn list_dir_lower(&self, resolved: &Path) -> VDriveResult<Vec<EntryInfo>> {
 
         let mut entries = Vec::new();
-        for entry in fs::read_dir(&resolved)? {
+        for entry in fs::read_dir(resolved)? {
             let entry = entry?;
             let meta = entry.metadata()?;
             let abs_path = entry.path();
@@ -277,7 +362,10 @@ impl VDrive {
     /// Create a directory (and parents) within the drive.
     pub fn mkdir(&self, path: &str) -> VDriveResult<()> {
         let resolved = self.resolve_new(path)?;
-        fs::create_dir_all(&resolved)?;
+        self.check_mutable(&resolved, path)?;
+        self.journaled(ChangeOp::Mkdir, &resolved, || Ok(fs::create_dir_all(&resolved)?))?;
+        self.clear_whiteouts(&resolved)?;
+        self.notify_changed(path);
         Ok(())
     }
 
@@ -289,7 +377,12 @@ impl VDrive {
         if resolved.is_dir() {
             return Err(VDriveError::IsDirectory(path.to_string()));
         }
-        fs::remove_file(&resolved)?;
+        self.check_mutable(&resolved, path)?;
+        match &self.upper {
+            Some(upper) => self.overlay_remove(upper, path, &resolved, ChangeOp::Delete)?,
+            None => self.journaled(ChangeOp::Delete, &resolved, || Ok(fs::remove_file(&resolved)?))?,
+        }
+        self.notify_changed(path);
         Ok(())
     }
 
@@ -300,10 +393,225 @@ impl VDrive {
             return Err(VDriveError::NotDirectory(path.to_string()));
         }
         // Never allow deleting the root itself
-        if resolved == self.root {
+        if resolved == self.root || self.relative(&resolved).is_empty() {
             return Err(VDriveError::Escape("cannot delete drive root".to_string()));
         }
-        fs::remove_dir(&resolved)?;
+        self.check_mutable(&resolved, path)?;
+        if let Some(upper) = &self.upper {
+            // The merged view must be empty, as with remove_dir.
+            if !self.list_dir(path)?.is_empty() {
+                return Err(VDriveError::Io(std::io::Error::new(
+                    std::io::ErrorKind::DirectoryNotEmpty,
+                    format!("directory not empty: {path}"),
+                )));
+            }
+            self.overlay_remove(upper, path, &resolved, ChangeOp::DeleteDir)?;
+        } else {
+            self.journaled(ChangeOp::DeleteDir, &resolved, || Ok(fs::remove_dir(&resolved)?))?;
+        }
+        self.notify_changed(path);
+        Ok(())
+    }
+
+    // ── Rename / copy ──
+
+    /// Move a file or directory to `to`, which must not exist yet.
+    /// Missing parents of `to` are created.
+    ///
+    /// On a plain drive this is a single atomic `rename`. On an overlay
+    /// it is a copy into the upper layer followed by deleting the source
+    /// from the merged view, so a failure part way can leave both.
+    /// Moving a directory holding paths the policy hides or makes
+    /// read-only is refused.
+    pub fn rename(&self, from: &str, to: &str) -> VDriveResult<()> {
+        let src = self.resolve(from)?;
+        let dst = self.check_new_target(&src, from, to)?;
+
+        let mut entries = Vec::new();
+        let mut subs = Vec::new();
+        if src.is_dir() {
+            let base = self.relative(&src);
+            self.walk_tree(&base, false, &mut entries)?;
+            for entry in &entries {
+                if self.is_denied(&entry.path) {
+                    return Err(VDriveError::ReadOnly(from.to_string()));
+                }
+                let sub = sub_path(&base, &entry.path);
+                self.check_mutable(&dst.join(&sub), to)?;
+                subs.push(sub);
+            }
+        }
+        self.check_mutable(&src, from)?;
+        for entry in &entries {
+            self.check_mutable(&self.root.join(&entry.path), from)?;
+        }
+        self.check_mutable(&dst, to)?;
+
+        if self.upper.is_some() {
+            self.copy(from, to)?;
+            // Bottom-up, so each dir is empty by the time it goes.
+            for entry in entries.iter().rev() {
+                if entry.is_dir {
+                    self.delete_dir(&entry.path)?;
+                } else {
+                    self.delete_file(&entry.path)?;
+                }
+            }
+            return if src.is_dir() { self.delete_dir(from) } else { self.delete_file(from) };
+        }
+
+        self.journaled_move(&src, &dst, &subs, || {
+            if let Some(parent) = dst.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            Ok(fs::rename(&src, &dst)?)
+        })?;
+        self.notify_changed(from);
+        self.notify_changed(to);
+        Ok(())
+    }
+
+    /// Copy a file, or a directory recursively, to `to`, which must not
+    /// exist yet. Paths the policy hides are left out and symlinks are
+    /// skipped. Each copied file counts against the write budget.
+    ///
+    /// A directory copy is checked against the budget as a whole before
+    /// anything is written, and if it still fails part way, whatever it
+    /// created is deleted again and refunded, so no partial tree is left.
+    pub fn copy(&self, from: &str, to: &str) -> VDriveResult<()> {
+        let src = self.resolve(from)?;
+        self.check_new_target(&src, from, to)?;
+
+        if !src.is_dir() {
+            self.write_as(ChangeOp::Copy, to, &fs::read(&src)?)?;
+            return self.copy_permissions(&src, to);
+        }
+        let base = self.relative(&src);
+        let mut entries = Vec::new();
+        self.walk_tree(&base, true, &mut entries)?;
+        let targets: Vec<String> = entries
+            .iter()
+            .map(|entry| Path::new(to).join(sub_path(&base, &entry.path)).to_string_lossy().into_owned())
+            .collect();
+        let mut writes = Vec::new();
+        for (entry, target) in entries.iter().zip(&targets) {
+            if !entry.is_dir {
+                writes.push((target.as_str(), fs::metadata(self.resolve(&entry.path)?)?.len(), true));
+            }
+        }
+        self.check_budget(&writes)?;
+
+        let mut created = Vec::new();
+        if let Err(e) = self.copy_tree(to, &entries, &targets, &mut created) {
+            let failed = self.discard_created(created);
+            if failed.is_empty() {
+                return Err(e);
+            }
+            return Err(VDriveError::RollbackFailed {
+                source: Box::new(e),
+                failed,
+            });
+        }
+        Ok(())
+    }
+
+    /// Create `to` (and its missing parents) and copy `entries` to
+    /// `targets` under it, recording each path in `created` as it goes,
+    /// with the bytes charged for files.
+    fn copy_tree(
+        &self,
+        to: &str,
+        entries: &[EntryInfo],
+        targets: &[String],
+        created: &mut Vec<(String, Option<u64>)>,
+    ) -> VDriveResult<()> {
+        let mut missing: Vec<String> = Path::new(to)
+            .ancestors()
+            .map(|p| p.to_string_lossy().into_owned())
+            .take_while(|p| !p.is_empty() && !self.exists(p))
+            .collect();
+        missing.reverse();
+        self.mkdir(to)?;
+        created.extend(missing.into_iter().map(|p| (p, None)));
+        for (entry, target) in entries.iter().zip(targets) {
+            if entry.is_dir {
+                self.mkdir(target)?;
+                created.push((target.clone(), None));
+            } else {
+                let src = self.resolve(&entry.path)?;
+                let content = fs::read(&src)?;
+                self.write_as(ChangeOp::Copy, target, &content)?;
+                created.push((target.clone(), Some(content.len() as u64)));
+                self.copy_permissions(&src, target)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Give the copy at `to` the permission bits of `src`, so an
+    /// executable stays executable (and an overlay rename, which is a
+    /// copy, doesn't reset the mode).
+    fn copy_permissions(&self, src: &Path, to: &str) -> VDriveResult<()> {
+        Ok(fs::set_permissions(self.resolve(to)?, fs::metadata(src)?.permissions())?)
+    }
+
+    /// Delete what a failed copy created, newest first, refunding the
+    /// files' charges. Returns `path: error` for anything left behind.
+    fn discard_created(&self, created: Vec<(String, Option<u64>)>) -> Vec<String> {
+        created
+            .into_iter()
+            .rev()
+            .filter_map(|(path, charged)| {
+                let result = match charged {
+                    Some(len) => self.delete_file(&path).map(|()| self.refund_write(len, true)),
+                    None => self.delete_dir(&path),
+                };
+                result.err().map(|e| format!("{path}: {e}"))
+            })
+            .collect()
+    }
+
+    /// Resolve `to` for a rename or copy of `src`: it must not exist and
+    /// must not lie inside `src`.
+    fn check_new_target(&self, src: &Path, from: &str, to: &str) -> VDriveResult<PathBuf> {
+        if self.relative(src).is_empty() {
+            return Err(VDriveError::Escape(format!("cannot move or copy drive root: {from}")));
+        }
+        let dst = self.resolve_new(to)?;
+        // Hidden paths still block the target, so nothing is clobbered.
+        let taken = match &self.upper {
+            Some(upper) => self.resolve_overlay(upper, to).is_ok(),
+            None => dst.symlink_metadata().is_ok(),
+        };
+        if taken {
+            return Err(VDriveError::AlreadyExists(to.to_string()));
+        }
+        let src_rel = self.relative(src);
+        if Path::new(&self.relative(&dst)).starts_with(&src_rel) {
+            return Err(VDriveError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("cannot move or copy {from} into itself"),
+            )));
+        }
+        Ok(dst)
+    }
+
+    /// Every entry below the drive-relative dir `rel`, parents before
+    /// children. `filtered` leaves out what the policy hides. Symlinks
+    /// pointing outside the tree are skipped.
+    fn walk_tree(&self, rel: &str, filtered: bool, out: &mut Vec<EntryInfo>) -> VDriveResult<()> {
+        let entries = if filtered { self.list_dir(rel)? } else { self.list_dir_raw(rel)? };
+        for entry in entries {
+            if !Path::new(&entry.path).starts_with(rel) || entry.path == rel {
+                continue;
+            }
+            let is_dir = entry.is_dir;
+            let path = entry.path.clone();
+            out.push(entry);
+            if is_dir {
+                self.walk_tree(&path, filtered, out)?;
+            }
+        }
         Ok(())
     }
 
@@ -316,22 +624,7 @@ impl VDrive {
 
         let a_content = fs::read_to_string(&a_resolved)?;
         let b_content = fs::read_to_string(&b_resolved)?;
-
-        let diff = similar::TextDiff::from_lines(&a_content, &b_content);
-        let mut output = String::new();
-        for change in diff.iter_all_changes() {
-            let sign = match change.tag() {
-                similar::ChangeTag::Delete => "-",
-                similar::ChangeTag::Insert => "+",
-                similar::ChangeTag::Equal => " ",
-            };
-            output.push_str(sign);
-            output.push_str(change.value());
-            if !change.value().ends_with('\n') {
-                output.push('\n');
-            }
-        }
-        Ok(output)
+        Ok(unified_diff(&a_content, &b_content))
     }
 
     // ── Exists ──
@@ -342,6 +635,52 @@ impl VDrive {
     }
 }
 
+/// Replace `old_string` in `content`, which must match exactly once
+/// unless `replace_all`.
+fn replace_text(
+    content: &str,
+    path: &str,
+    old_string: &str,
+    new_string: &str,
+    replace_all: bool,
+) -> VDriveResult<String> {
+    let count = content.matches(old_string).count();
+    if count == 0 {
+        return Err(VDriveError::EditNotFound(path.to_string()));
+    }
+    if count > 1 && !replace_all {
+        return Err(VDriveError::EditAmbiguous {
+            path: path.to_string(),
+            count,
+        });
+    }
+    Ok(content.replace(old_string, new_string))
+}
+
+/// `path` relative to the dir `base` it was found under.
+fn sub_path(base: &str, path: &str) -> PathBuf {
+    Path::new(path).strip_prefix(base).map(Path::to_path_buf).unwrap_or_default()
+}
+
+/// Render a line diff of `a` → `b`, one `-`/`+`/` `-prefixed line each.
+pub(crate) fn unified_diff(a: &str, b: &str) -> String {
+    let diff = similar::TextDiff::from_lines(a, b);
+    let mut output = String::new();
+    for change in diff.iter_all_changes() {
+        let sign = match change.tag() {
+            similar::ChangeTag::Delete => "-",
+            similar::ChangeTag::Insert => "+",
+            similar::ChangeTag::Equal => " ",
+        };
+        output.push_str(sign);
+        output.push_str(change.value());
+        if !change.value().ends_with('\n') {
+            output.push('\n');
+        }
+    }
+    output
+}
+
 /// Check if a byte slice looks like binary (null bytes in first 8KB).
 fn is_binary(data: &[u8]) -> bool {
     let check_len = data.len().min(8192);
@@ -403,7 +742,7 @@ mod tests {
     #[test]
     fn read_binary_rejected() {
         let (dir, vd) = setup();
-        fs::write(dir.path().join("bin"), &[0x00, 0x01, 0xFF]).unwrap();
+        fs::write(dir.path().join("bin"), [0x00, 0x01, 0xFF]).unwrap();
         let result = vd.read_file("bin", 1, 100);
         assert!(matches!(result, Err(VDriveError::BinaryFile(_))));
     }
@@ -488,6 +827,109 @@ mod tests {
         assert!(result.content.contains("let y = 2"));
     }
 
+    fn edit(old: &str, new: &str) -> TextEdit {
+        TextEdit {
+            old_string: old.into(),
+            new_string: new.into(),
+            replace_all: false,
+        }
+    }
+
+    #[test]
+    fn multi_edit_applies_in_order() {
+        let (dir, vd) = setup();
+        fs::write(dir.path().join("code.rs"), "fn foo() {}\nfn bar() {}\n").unwrap();
+        // The second edit sees the first one's result.
+        vd.multi_edit("code.rs", &[edit("foo", "baz"), edit("fn baz", "pub fn baz")])
+            .unwrap();
+        let content = fs::read_to_string(dir.path().join("code.rs")).unwrap();
+        assert_eq!(content, "pub fn baz() {}\nfn bar() {}\n");
+    }
+
+    #[test]
+    fn multi_edit_is_all_or_nothing() {
+        let (dir, vd) = setup();
+        fs::write(dir.path().join("code.rs"), "fn foo() {}\n").unwrap();
+        let err = vd
+            .multi_edit("code.rs", &[edit("foo", "baz"), edit("missing", "x")])
+            .unwrap_err();
+        match err {
+            VDriveError::MultiEdit { edit, source } => {
+                assert_eq!(edit, 2);
+                assert!(matches!(*source, VDriveError::EditNotFound(_)));
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+        let content = fs::read_to_string(dir.path().join("code.rs")).unwrap();
+        assert_eq!(content, "fn foo() {}\n");
+    }
+
+    // ── Rename / copy tests ──
+
+    #[test]
+    fn rename_file_creates_parents() {
+        let (dir, vd) = setup();
+        fs::write(dir.path().join("a.txt"), "hello").unwrap();
+        vd.rename("a.txt", "moved/b.txt").unwrap();
+        assert!(!dir.path().join("a.txt").exists());
+        assert_eq!(fs::read_to_string(dir.path().join("moved/b.txt")).unwrap(), "hello");
+    }
+
+    #[test]
+    fn rename_dir_moves_tree() {
+        let (dir, vd) = setup();
+        fs::create_dir_all(dir.path().join("src/sub")).unwrap();
+        fs::write(dir.path().join("src/sub/x.rs"), "x").unwrap();
+        vd.rename("src", "lib").unwrap();
+        assert!(!dir.path().join("src").exists());
+        assert_eq!(fs::read_to_string(dir.path().join("lib/sub/x.rs")).unwrap(), "x");
+    }
+
+    #[test]
+    fn rename_refuses_existing_target_and_self_nesting() {
+        let (dir, vd) = setup();
+        fs::write(dir.path().join("a.txt"), "a").unwrap();
+        fs::write(dir.path().join("b.txt"), "b").unwrap();
+        fs::create_dir(dir.path().join("d")).unwrap();
+        assert!(matches!(vd.rename("a.txt", "b.txt"), Err(VDriveError::AlreadyExists(_))));
+        assert!(vd.rename("d", "d/inner").is_err());
+        assert!(vd.rename("a.txt", "../escape.txt").is_err());
+        assert_eq!(fs::read_to_string(dir.path().join("b.txt")).unwrap(), "b");
+    }
+
+    #[test]
+    fn copy_file_and_dir() {
+        let (dir, vd) = setup();
+        fs::create_dir_all(dir.path().join("src/sub")).unwrap();
+        fs::write(dir.path().join("src/a.rs"), "a").unwrap();
+        fs::write(dir.path().join("src/sub/b.rs"), "b").unwrap();
+
+        vd.copy("src/a.rs", "a_copy.rs").unwrap();
+        vd.copy("src", "backup/src").unwrap();
+
+        assert_eq!(fs::read_to_string(dir.path().join("a_copy.rs")).unwrap(), "a");
+        assert_eq!(fs::read_to_string(dir.path().join("backup/src/sub/b.rs")).unwrap(), "b");
+        assert!(dir.path().join("src/sub/b.rs").exists());
+        assert!(matches!(vd.copy("src", "backup/src"), Err(VDriveError::AlreadyExists(_))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_keeps_permission_bits() {
+        use std::os::unix::fs::PermissionsExt;
+        let (dir, vd) = setup();
+        let mode = |rel: &str| fs::metadata(dir.path().join(rel)).unwrap().permissions().mode() & 0o777;
+        fs::create_dir_all(dir.path().join("bin")).unwrap();
+        fs::write(dir.path().join("bin/run.sh"), "#!/bin/sh\n").unwrap();
+        fs::set_permissions(dir.path().join("bin/run.sh"), fs::Permissions::from_mode(0o751)).unwrap();
+
+        vd.copy("bin/run.sh", "run_copy.sh").unwrap();
+        vd.copy("bin", "tools").unwrap();
+
+        assert_eq!(mode("run_copy.sh"), 0o751);
+        assert_eq!(mode("tools/run.sh"), 0o751);
+    }
+
     // ── Glob tests ──
 
     #[test]
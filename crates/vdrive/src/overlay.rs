@@ -0,0 +1,711 @@
+//! Copy-on-write overlay — agents write to a scratch layer, not the checkout.
+//!
+//! An overlay drive has two directories: the *lower* root (the real
+//! workspace, never touched by drive ops) and an *upper* layer. Reads see
+//! the upper copy of a path if there is one, else fall through to lower.
+//! Writes always land in upper. Deleting a path that exists in lower
+//! leaves a whiteout marker instead:
+//!
+//! ```text
+//! upper/src/.wh.old.rs   src/old.rs is deleted
+//! upper/gen/.wh..opq     gen/ was deleted and recreated: hide lower's gen/*
+//! ```
+//!
+//! Markers never show through `glob`, `grep`, `list_dir` or `resolve`.
+//! [`VDrive::overlay_changes`] summarizes the upper layer against lower,
+//! and [`apply_overlay`](VDrive::apply_overlay) /
+//! [`discard_overlay`](VDrive::discard_overlay) settle it, wholesale or
+//! per file.
+
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::ops::unified_diff;
+use crate::{ChangeOp, EntryInfo, VDrive, VDriveError, VDriveResult};
+
+/// Filename prefix marking a deleted lower path.
+const WHITEOUT_PREFIX: &str = ".wh.";
+/// Marker in an upper dir that hides everything lower had under it.
+const OPAQUE: &str = ".wh..opq";
+
+/// How a path in the overlay differs from the lower root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlayChangeKind {
+    Added,
+    Modified,
+    Deleted,
+}
+
+/// One pending overlay change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverlayChange {
+    /// Path relative to the drive root.
+    pub path: String,
+    pub kind: OverlayChangeKind,
+}
+
+impl VDrive {
+    /// Open `root` read-only behind a writable `upper_dir` layer.
+    ///
+    /// `upper_dir` is created if missing; reopening an existing one picks
+    /// up its pending changes. Keep it outside `root` — anything under it
+    /// is hidden from the lower view.
+    pub fn open_overlay(root: &Path, upper_dir: &Path) -> VDriveResult<Self> {
+        let mut drive = Self::open(root)?;
+        fs::create_dir_all(upper_dir)?;
+        let upper = upper_dir.canonicalize()?;
+        if upper == drive.root {
+            return Err(VDriveError::Escape("overlay layer cannot be the drive root".into()));
+        }
+        drive.upper = Some(upper);
+        Ok(drive)
+    }
+
+    /// True if writes go to an overlay layer rather than the root.
+    pub fn is_overlay(&self) -> bool {
+        self.upper.is_some()
+    }
+
+    /// The overlay's upper layer, if this is an overlay drive.
+    pub fn upper(&self) -> Option<&Path> {
+        self.upper.as_deref()
+    }
+
+    /// Every pending change in the upper layer, sorted by path.
+    pub fn overlay_changes(&self) -> VDriveResult<Vec<OverlayChange>> {
+        let upper = self.require_upper()?;
+        let mut out = BTreeMap::new();
+        self.collect_changes(upper, Path::new(""), &mut out)?;
+        Ok(out
+            .into_iter()
+            .map(|(path, kind)| OverlayChange { path, kind })
+            .collect())
+    }
+
+    /// Unified diff of one path: lower root version vs overlay version.
+    /// A missing side diffs as empty.
+    pub fn overlay_diff(&self, path: &str) -> VDriveResult<String> {
+        self.require_upper()?;
+        let rel = self.logical_rel(path)?;
+        let lower = self.root.join(&rel);
+        let before = if lower.is_file() {
+            fs::read_to_string(&lower)?
+        } else {
+            String::new()
+        };
+        let after = match self.resolve(path) {
+            Ok(p) if p.is_file() => fs::read_to_string(&p)?,
+            _ => String::new(),
+        };
+        Ok(unified_diff(&before, &after))
+    }
+
+    /// Write pending changes through to the lower root — all of them, or
+    /// only `path`. Applied changes leave the upper layer. Returns what
+    /// was applied.
+    pub fn apply_overlay(&self, path: Option<&str>) -> VDriveResult<Vec<OverlayChange>> {
+        let upper = self.require_upper()?;
+        let changes = self.select_changes(path)?;
+        for change in &changes {
+            let lower = self.root.join(&change.path);
+            match change.kind {
+                OverlayChangeKind::Added | OverlayChangeKind::Modified => {
+                    if let Some(parent) = lower.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    fs::copy(upper.join(&change.path), &lower)?;
+                }
+                OverlayChangeKind::Deleted => {
+                    if lower.is_dir() {
+                        fs::remove_dir_all(&lower)?;
+                    } else if lower.exists() {
+                        fs::remove_file(&lower)?;
+                    }
+                }
+            }
+        }
+        self.drop_from_upper(path, &changes)?;
+        Ok(changes)
+    }
+
+    /// Throw pending changes away — all of them, or only `path`. Returns
+    /// what was discarded.
+    pub fn discard_overlay(&self, path: Option<&str>) -> VDriveResult<Vec<OverlayChange>> {
+        self.require_upper()?;
+        let changes = self.select_changes(path)?;
+        self.drop_from_upper(path, &changes)?;
+        for change in &changes {
+            self.notify_changed(&change.path);
+        }
+        Ok(changes)
+    }
+
+    fn require_upper(&self) -> VDriveResult<&Path> {
+        self.upper
+            .as_deref()
+            .ok_or(VDriveError::NotOverlay)
+    }
+
+    fn select_changes(&self, path: Option<&str>) -> VDriveResult<Vec<OverlayChange>> {
+        let mut changes = self.overlay_changes()?;
+        if let Some(p) = path {
+            let rel = rel_string(&self.logical_rel(p)?);
+            changes.retain(|c| c.path == rel);
+            if changes.is_empty() {
+                return Err(VDriveError::NotFound(format!("no pending change to {p}")));
+            }
+        }
+        Ok(changes)
+    }
+
+    fn drop_from_upper(&self, path: Option<&str>, changes: &[OverlayChange]) -> VDriveResult<()> {
+        let upper = self.require_upper()?;
+        if path.is_none() {
+            fs::remove_dir_all(upper)?;
+            fs::create_dir_all(upper)?;
+            return Ok(());
+        }
+        for change in changes {
+            let rel = Path::new(&change.path);
+            let copy = upper.join(rel);
+            if copy.is_file() {
+                fs::remove_file(&copy)?;
+            }
+            let marker = whiteout_marker(upper, rel);
+            if marker.exists() {
+                fs::remove_file(&marker)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn collect_changes(
+        &self,
+        upper: &Path,
+        rel: &Path,
+        out: &mut BTreeMap<String, OverlayChangeKind>,
+    ) -> VDriveResult<()> {
+        let dir = upper.join(rel);
+        if dir.join(OPAQUE).exists() {
+            // Everything lower had here is gone unless upper re-created it.
+            for lower_file in self.lower_files(rel)? {
+                if !upper.join(&lower_file).exists() {
+                    out.insert(rel_string(&lower_file), OverlayChangeKind::Deleted);
+                }
+            }
+        }
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let name_str = name.to_string_lossy();
+            if name_str == OPAQUE {
+                continue;
+            }
+            if let Some(target) = name_str.strip_prefix(WHITEOUT_PREFIX) {
+                let target = rel.join(target);
+                if self.root.join(&target).exists() {
+                    out.insert(rel_string(&target), OverlayChangeKind::Deleted);
+                }
+                continue;
+            }
+            let child = rel.join(&name);
+            if entry.file_type()?.is_dir() {
+                self.collect_changes(upper, &child, out)?;
+                continue;
+            }
+            let lower = self.root.join(&child);
+            let kind = if lower.is_file() && !self.lower_hidden(&child) {
+                if fs::read(&lower)? == fs::read(entry.path())? {
+                    continue;
+                }
+                OverlayChangeKind::Modified
+            } else {
+                OverlayChangeKind::Added
+            };
+            out.insert(rel_string(&child), kind);
+        }
+        Ok(())
+    }
+
+    /// Files under lower `rel`, recursively, as root-relative paths.
+    fn lower_files(&self, rel: &Path) -> VDriveResult<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let mut stack = vec![rel.to_path_buf()];
+        while let Some(dir) = stack.pop() {
+            let abs = self.root.join(&dir);
+            if !abs.is_dir() || self.in_upper(&abs) {
+                continue;
+            }
+            for entry in fs::read_dir(&abs)? {
+                let entry = entry?;
+                let child = dir.join(entry.file_name());
+                if entry.file_type()?.is_dir() {
+                    stack.push(child);
+                } else {
+                    files.push(child);
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    // ── Resolution (called from lib.rs / ops.rs) ──
+
+    /// Lexically normalize `user_path` to a root-relative path. `..`
+    /// may not climb above the root.
+    pub(crate) fn logical_rel(&self, user_path: &str) -> VDriveResult<PathBuf> {
+        let path = Path::new(user_path);
+        let path = if path.is_absolute() {
+            path.strip_prefix(&self.root)
+                .map_err(|_| VDriveError::Escape(user_path.to_string()))?
+        } else {
+            path
+        };
+        let mut rel = PathBuf::new();
+        for component in path.components() {
+            match component {
+                Component::Normal(part) => rel.push(part),
+                Component::CurDir => {}
+                Component::ParentDir if rel.pop() => {}
+                _ => return Err(VDriveError::Escape(user_path.to_string())),
+            }
+        }
+        Ok(rel)
+    }
+
+    /// Physical location of `user_path` in the merged view: the upper
+    /// copy if any, else the lower one unless whited out.
+    pub(crate) fn resolve_overlay(&self, upper: &Path, user_path: &str) -> VDriveResult<PathBuf> {
+        let rel = self.logical_rel(user_path)?;
+        if rel.components().any(|c| is_marker(c.as_os_str())) {
+            return Err(VDriveError::NotFound(user_path.to_string()));
+        }
+        let copy = upper.join(&rel);
+        if copy.symlink_metadata().is_ok() {
+            let resolved = copy
+                .canonicalize()
+                .map_err(|_| VDriveError::NotFound(user_path.to_string()))?;
+            if !resolved.starts_with(upper) {
+                return Err(VDriveError::Escape(user_path.to_string()));
+            }
+            return Ok(resolved);
+        }
+        if self.lower_hidden(&rel) {
+            return Err(VDriveError::NotFound(user_path.to_string()));
+        }
+        let resolved = self
+            .root
+            .join(&rel)
+            .canonicalize()
+            .map_err(|_| VDriveError::NotFound(user_path.to_string()))?;
+        if !resolved.starts_with(&self.root) || self.in_upper(&resolved) {
+            return Err(VDriveError::Escape(user_path.to_string()));
+        }
+        Ok(resolved)
+    }
+
+    /// True if lower's copy of `rel` is masked by a whiteout on it or an
+    /// ancestor, or by an opaque ancestor dir.
+    pub(crate) fn lower_hidden(&self, rel: &Path) -> bool {
+        let Some(upper) = &self.upper else {
+            return false;
+        };
+        let mut dir = upper.clone();
+        for component in rel.components() {
+            if dir.join(OPAQUE).exists() {
+                return true;
+            }
+            let name = component.as_os_str();
+            if dir.join(whiteout_name(name)).exists() {
+                return true;
+            }
+            dir.push(name);
+        }
+        false
+    }
+
+    /// True if `abs` lies in the upper layer (which may sit inside root).
+    pub(crate) fn in_upper(&self, abs: &Path) -> bool {
+        self.upper.as_ref().is_some_and(|u| abs.starts_with(u))
+    }
+
+    /// After writing `abs` in upper: drop whiteouts on it and its
+    /// ancestors, marking re-created dirs opaque so the deleted lower
+    /// contents stay deleted.
+    pub(crate) fn clear_whiteouts(&self, abs: &Path) -> VDriveResult<()> {
+        let Some(upper) = &self.upper else {
+            return Ok(());
+        };
+        let Ok(rel) = abs.strip_prefix(upper) else {
+            return Ok(());
+        };
+        let mut prefix = PathBuf::new();
+        for component in rel.components() {
+            prefix.push(component);
+            let marker = whiteout_marker(upper, &prefix);
+            if !marker.exists() {
+                continue;
+            }
+            self.journaled(ChangeOp::Delete, &marker, || Ok(fs::remove_file(&marker)?))?;
+            let recreated = upper.join(&prefix);
+            if recreated.is_dir() {
+                let opaque = recreated.join(OPAQUE);
+                self.journaled(ChangeOp::Write, &opaque, || Ok(fs::write(&opaque, b"")?))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Delete `user_path` (already resolved to `resolved`) in the merged
+    /// view: drop the upper copy, then whiteout whatever lower has.
+    pub(crate) fn overlay_remove(
+        &self,
+        upper: &Path,
+        user_path: &str,
+        resolved: &Path,
+        op: ChangeOp,
+    ) -> VDriveResult<()> {
+        let rel = self.logical_rel(user_path)?;
+        if resolved.starts_with(upper) {
+            self.journaled(op, resolved, || {
+                if resolved.is_dir() {
+                    fs::remove_dir_all(resolved)?;
+                } else {
+                    fs::remove_file(resolved)?;
+                }
+                Ok(())
+            })?;
+        }
+        if self.root.join(&rel).exists() && !self.lower_hidden(&rel) {
+            let marker = whiteout_marker(upper, &rel);
+            self.journaled(ChangeOp::Write, &marker, || {
+                if let Some(parent) = marker.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                Ok(fs::write(&marker, b"")?)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// `glob` over both layers, upper winning, markers and masked lower
+    /// paths dropped.
+    pub(crate) fn overlay_glob(&self, upper: &Path, pattern: &str) -> VDriveResult<Vec<String>> {
+        let mut merged = std::collections::BTreeSet::new();
+        for (base, is_upper) in [(upper, true), (self.root.as_path(), false)] {
+            for abs in glob_in(base, pattern)? {
+                if !is_upper && self.in_upper(&abs) {
+                    continue;
+                }
+                let Ok(rel) = abs.strip_prefix(base) else {
+                    continue;
+                };
+                if rel.components().any(|c| is_marker(c.as_os_str())) {
+                    continue;
+                }
+                if !is_upper && self.lower_hidden(rel) {
+                    continue;
+                }
+                merged.insert(rel_string(rel));
+            }
+        }
+        Ok(merged.into_iter().collect())
+    }
+
+    /// `list_dir` of the merged view of `rel`.
+    pub(crate) fn overlay_list_dir(&self, upper: &Path, rel: &Path) -> VDriveResult<Vec<EntryInfo>> {
+        let mut entries = BTreeMap::new();
+        let lower_dir = self.root.join(rel);
+        if lower_dir.is_dir() && !self.lower_hidden(rel) {
+            for entry in fs::read_dir(&lower_dir)? {
+                let entry = entry?;
+                let child = rel.join(entry.file_name());
+                let Ok(canonical) = entry.path().canonicalize() else {
+                    continue;
+                };
+                if !canonical.starts_with(&self.root)
+                    || self.in_upper(&canonical)
+                    || self.lower_hidden(&child)
+                {
+                    continue;
+                }
+                let meta = entry.metadata()?;
+                entries.insert(child.clone(), entry_info(&child, &meta));
+            }
+        }
+        let upper_dir = upper.join(rel);
+        if upper_dir.is_dir() {
+            for entry in fs::read_dir(&upper_dir)? {
+                let entry = entry?;
+                if is_marker(&entry.file_name()) {
+                    continue;
+                }
+                let child = rel.join(entry.file_name());
+                let meta = entry.metadata()?;
+                entries.insert(child.clone(), entry_info(&child, &meta));
+            }
+        }
+        Ok(entries.into_values().collect())
+    }
+}
+
+fn glob_in(base: &Path, pattern: &str) -> VDriveResult<Vec<PathBuf>> {
+    let full = base.join(pattern).to_string_lossy().to_string();
+    let entries = glob::glob(&full).map_err(|e| VDriveError::InvalidPattern(format!("{e}")))?;
+    let mut out = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|e| VDriveError::Io(e.into_error()))?;
+        if let Ok(canonical) = path.canonicalize() {
+            if canonical.starts_with(base) {
+                out.push(canonical);
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn entry_info(rel: &Path, meta: &fs::Metadata) -> EntryInfo {
+    EntryInfo {
+        path: rel_string(rel),
+        is_dir: meta.is_dir(),
+        size: meta.len(),
+    }
+}
+
+fn whiteout_name(name: &OsStr) -> String {
+    format!("{WHITEOUT_PREFIX}{}", name.to_string_lossy())
+}
+
+fn whiteout_marker(upper: &Path, rel: &Path) -> PathBuf {
+    let parent = rel.parent().unwrap_or(Path::new(""));
+    let name = rel.file_name().unwrap_or_default();
+    upper.join(parent).join(whiteout_name(name))
+}
+
+fn is_marker(name: &OsStr) -> bool {
+    name.to_string_lossy().starts_with(WHITEOUT_PREFIX)
+}
+
+fn rel_string(rel: &Path) -> String {
+    rel.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// (lower, upper-parent, drive). Lower starts with `src/lib.rs` and
+    /// `README.md`.
+    fn setup() -> (TempDir, TempDir, VDrive) {
+        let lower = TempDir::new().unwrap();
+        fs::create_dir(lower.path().join("src")).unwrap();
+        fs::write(lower.path().join("src/lib.rs"), "fn lib() {}\n").unwrap();
+        fs::write(lower.path().join("README.md"), "# readme\n").unwrap();
+        let scratch = TempDir::new().unwrap();
+        let vd = VDrive::open_overlay(lower.path(), &scratch.path().join("upper")).unwrap();
+        (lower, scratch, vd)
+    }
+
+    fn kinds(vd: &VDrive) -> Vec<(String, OverlayChangeKind)> {
+        vd.overlay_changes()
+            .unwrap()
+            .into_iter()
+            .map(|c| (c.path, c.kind))
+            .collect()
+    }
+
+    #[test]
+    fn writes_land_in_upper_only() {
+        let (lower, _scratch, vd) = setup();
+        vd.write_file("src/lib.rs", "fn changed() {}\n").unwrap();
+        vd.write_file("src/new.rs", "fn new() {}\n").unwrap();
+        vd.edit_file("README.md", "readme", "READ ME", false).unwrap();
+
+        assert_eq!(fs::read_to_string(lower.path().join("src/lib.rs")).unwrap(), "fn lib() {}\n");
+        assert!(!lower.path().join("src/new.rs").exists());
+        assert_eq!(fs::read_to_string(lower.path().join("README.md")).unwrap(), "# readme\n");
+
+        assert!(vd.read_file("src/lib.rs", 1, 100).unwrap().content.contains("changed"));
+        assert!(vd.read_file("README.md", 1, 100).unwrap().content.contains("READ ME"));
+        assert_eq!(
+            kinds(&vd),
+            vec![
+                ("README.md".into(), OverlayChangeKind::Modified),
+                ("src/lib.rs".into(), OverlayChangeKind::Modified),
+                ("src/new.rs".into(), OverlayChangeKind::Added),
+            ]
+        );
+    }
+
+    #[test]
+    fn delete_whites_out_lower() {
+        let (lower, _scratch, vd) = setup();
+        vd.delete_file("src/lib.rs").unwrap();
+
+        assert!(lower.path().join("src/lib.rs").exists());
+        assert!(!vd.exists("src/lib.rs"));
+        assert!(vd.glob("**/*.rs").unwrap().is_empty());
+        assert!(vd.list_dir("src").unwrap().is_empty());
+        assert!(vd.grep("lib", None, 10).unwrap().is_empty());
+        assert_eq!(kinds(&vd), vec![("src/lib.rs".into(), OverlayChangeKind::Deleted)]);
+
+        // Writing it again replaces the whiteout.
+        vd.write_file("src/lib.rs", "fn back() {}\n").unwrap();
+        assert!(vd.read_file("src/lib.rs", 1, 10).unwrap().content.contains("back"));
+        assert_eq!(kinds(&vd), vec![("src/lib.rs".into(), OverlayChangeKind::Modified)]);
+    }
+
+    #[test]
+    fn merged_views_hide_markers() {
+        let (_lower, _scratch, vd) = setup();
+        vd.write_file("src/extra.rs", "fn extra() {}\n").unwrap();
+        vd.delete_file("README.md").unwrap();
+
+        assert_eq!(vd.glob("**/*").unwrap(), vec!["src", "src/extra.rs", "src/lib.rs"]);
+        let names: Vec<String> = vd.list_dir(".").unwrap().into_iter().map(|e| e.path).collect();
+        assert_eq!(names, vec!["src"]);
+        let hits = vd.grep("fn ", None, 10).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert!(vd.resolve("src/.wh.nothing").is_err());
+    }
+
+    #[test]
+    fn recreated_dir_is_opaque() {
+        let (_lower, _scratch, vd) = setup();
+        vd.delete_file("src/lib.rs").unwrap();
+        vd.delete_dir("src").unwrap();
+        assert!(!vd.exists("src"));
+
+        vd.write_file("src/fresh.rs", "fn fresh() {}\n").unwrap();
+        let names: Vec<String> = vd.list_dir("src").unwrap().into_iter().map(|e| e.path).collect();
+        assert_eq!(names, vec!["src/fresh.rs"]);
+        assert_eq!(
+            kinds(&vd),
+            vec![
+                ("src/fresh.rs".into(), OverlayChangeKind::Added),
+                ("src/lib.rs".into(), OverlayChangeKind::Deleted),
+            ]
+        );
+    }
+
+    #[test]
+    fn delete_dir_requires_merged_empty() {
+        let (_lower, _scratch, vd) = setup();
+        assert!(matches!(vd.delete_dir("src"), Err(VDriveError::Io(_))));
+    }
+
+    #[test]
+    fn overlay_diff_against_lower() {
+        let (_lower, _scratch, vd) = setup();
+        vd.write_file("src/lib.rs", "fn lib2() {}\n").unwrap();
+        let diff = vd.overlay_diff("src/lib.rs").unwrap();
+        assert!(diff.contains("-fn lib() {}"));
+        assert!(diff.contains("+fn lib2() {}"));
+
+        vd.delete_file("README.md").unwrap();
+        assert!(vd.overlay_diff("README.md").unwrap().contains("-# readme"));
+    }
+
+    #[test]
+    fn apply_single_file() {
+        let (lower, _scratch, vd) = setup();
+        vd.write_file("src/lib.rs", "fn applied() {}\n").unwrap();
+        vd.write_file("notes.txt", "later").unwrap();
+
+        let applied = vd.apply_overlay(Some("src/lib.rs")).unwrap();
+        assert_eq!(applied.len(), 1);
+        assert_eq!(fs::read_to_string(lower.path().join("src/lib.rs")).unwrap(), "fn applied() {}\n");
+        assert!(!lower.path().join("notes.txt").exists());
+        assert_eq!(kinds(&vd), vec![("notes.txt".into(), OverlayChangeKind::Added)]);
+        assert!(matches!(vd.apply_overlay(Some("src/lib.rs")), Err(VDriveError::NotFound(_))));
+    }
+
+    #[test]
+    fn apply_all_then_upper_is_empty() {
+        let (lower, _scratch, vd) = setup();
+        vd.write_file("a/b.txt", "b").unwrap();
+        vd.delete_file("README.md").unwrap();
+
+        vd.apply_overlay(None).unwrap();
+        assert_eq!(fs::read_to_string(lower.path().join("a/b.txt")).unwrap(), "b");
+        assert!(!lower.path().join("README.md").exists());
+        assert!(vd.overlay_changes().unwrap().is_empty());
+        assert_eq!(fs::read_dir(vd.upper().unwrap()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn discard_restores_lower_view() {
+        let (lower, _scratch, vd) = setup();
+        vd.write_file("src/lib.rs", "fn nope() {}\n").unwrap();
+        vd.delete_file("README.md").unwrap();
+
+        vd.discard_overlay(Some("README.md")).unwrap();
+        assert!(vd.exists("README.md"));
+        assert_eq!(kinds(&vd), vec![("src/lib.rs".into(), OverlayChangeKind::Modified)]);
+
+        vd.discard_overlay(None).unwrap();
+        assert!(vd.read_file("src/lib.rs", 1, 10).unwrap().content.contains("fn lib()"));
+        assert_eq!(fs::read_to_string(lower.path().join("src/lib.rs")).unwrap(), "fn lib() {}\n");
+    }
+
+    #[test]
+    fn escape_blocked_in_overlay() {
+        let (_lower, _scratch, vd) = setup();
+        assert!(matches!(vd.write_file("../escape.txt", "x"), Err(VDriveError::Escape(_))));
+        assert!(vd.read_file("../../etc/passwd", 1, 10).is_err());
+    }
+
+    #[test]
+    fn journal_undo_in_overlay() {
+        let (lower, scratch, vd) = setup();
+        let vd = vd.with_journal(&scratch.path().join("data")).unwrap();
+        let cp = vd.checkpoint("t").unwrap();
+        vd.write_file("src/lib.rs", "fn changed() {}\n").unwrap();
+        vd.delete_file("README.md").unwrap();
+
+        vd.revert_to(cp.id).unwrap();
+        assert!(vd.overlay_changes().unwrap().is_empty());
+        assert!(vd.exists("README.md"));
+        assert_eq!(fs::read_to_string(lower.path().join("src/lib.rs")).unwrap(), "fn lib() {}\n");
+    }
+
+    #[test]
+    fn rename_in_overlay_leaves_lower() {
+        let (lower, _scratch, vd) = setup();
+        vd.rename("src", "lib").unwrap();
+        assert!(!vd.exists("src"));
+        assert!(vd.read_file("lib/lib.rs", 1, 10).unwrap().content.contains("fn lib()"));
+        assert!(lower.path().join("src/lib.rs").exists());
+        assert!(!lower.path().join("lib").exists());
+
+        vd.apply_overlay(None).unwrap();
+        assert!(!lower.path().join("src/lib.rs").exists());
+        assert_eq!(fs::read_to_string(lower.path().join("lib/lib.rs")).unwrap(), "fn lib() {}\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rename_in_overlay_keeps_permission_bits() {
+        use std::os::unix::fs::PermissionsExt;
+        let (lower, _scratch, vd) = setup();
+        let script = lower.path().join("src/build.sh");
+        fs::write(&script, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+
+        vd.rename("src/build.sh", "build.sh").unwrap();
+        vd.apply_overlay(None).unwrap();
+        let mode = fs::metadata(lower.path().join("build.sh")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+
+    #[test]
+    fn plain_drive_is_not_overlay() {
+        let dir = TempDir::new().unwrap();
+        let vd = VDrive::open(dir.path()).unwrap();
+        assert!(!vd.is_overlay());
+        assert!(matches!(vd.overlay_changes(), Err(VDriveError::NotOverlay)));
+    }
+}
@@ -0,0 +1,1120 @@
+//! Multi-file patches — unified diffs and `*** Begin Patch` blocks.
+//!
+//! [`VDrive::apply_patch`] takes either format:
+//!
+//! ```text
+//! --- a/src/lib.rs              *** Begin Patch
+//! +++ b/src/lib.rs              *** Update File: src/lib.rs
+//! @@ -10,3 +10,3 @@             @@ fn helper
+//!  fn helper() {                 fn helper() {
+//! -    old();                   -    old();
+//! +    new();                   +    new();
+//!  }                             }
+//!                               *** End Patch
+//! ```
+//!
+//! Hunks are located rather than trusted: line numbers are only a hint,
+//! and when the exact lines aren't there the search relaxes to ignoring
+//! trailing whitespace, then all whitespace, then dropping up to
+//! [`MAX_CONTEXT_FUZZ`] context lines from each end. Context lines keep
+//! the file's own text, so fuzzy matches never rewrite them.
+//!
+//! The patch is all-or-nothing. Every hunk of every file is matched in
+//! memory first; if any is rejected nothing is written and the report
+//! says why. Writes then go through the ordinary drive ops (so journal,
+//! overlay and mount policy all apply), and a write that still fails
+//! rolls back the files already written.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::{ChangeOp, VDrive, VDriveError, VDriveResult};
+
+/// Most context lines a hunk may lose from each end and still match.
+pub const MAX_CONTEXT_FUZZ: usize = 2;
+
+/// What a patch does to one file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchAction {
+    Add,
+    Delete,
+    Update,
+    /// Update, then move to `to`.
+    Move { to: String },
+}
+
+/// How far a hunk's match strayed from its text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Fuzz {
+    /// Matched only after ignoring trailing whitespace (1) or all
+    /// whitespace (2).
+    pub whitespace: u8,
+    /// Context lines dropped from each end to find a match.
+    pub context_dropped: usize,
+}
+
+/// The fate of one hunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HunkOutcome {
+    Applied {
+        /// 1-based line the hunk landed on.
+        line: usize,
+        /// Lines away from where its header said, when it had one.
+        offset: isize,
+        fuzz: Fuzz,
+    },
+    Rejected { reason: String },
+}
+
+/// Per-file result of a patch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilePatchReport {
+    pub path: String,
+    pub action: PatchAction,
+    /// One entry per hunk, in patch order.
+    pub hunks: Vec<HunkOutcome>,
+    /// Why the file as a whole was rejected (missing, already exists…).
+    pub error: Option<String>,
+}
+
+impl FilePatchReport {
+    fn is_ok(&self) -> bool {
+        self.error.is_none() && self.hunks.iter().all(|h| matches!(h, HunkOutcome::Applied { .. }))
+    }
+}
+
+/// Result of [`VDrive::apply_patch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchReport {
+    /// True if every file was written; false means nothing was.
+    pub applied: bool,
+    pub files: Vec<FilePatchReport>,
+}
+
+impl fmt::Display for PatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hunks: usize = self.files.iter().map(|r| r.hunks.len()).sum();
+        if self.applied {
+            writeln!(f, "patch applied: {} files, {hunks} hunks", self.files.len())?;
+        } else {
+            writeln!(f, "patch rejected, nothing was written")?;
+        }
+        for file in &self.files {
+            match &file.action {
+                PatchAction::Add => writeln!(f, "  A {}", file.path)?,
+                PatchAction::Delete => writeln!(f, "  D {}", file.path)?,
+                PatchAction::Update => writeln!(f, "  M {}", file.path)?,
+                PatchAction::Move { to } => writeln!(f, "  R {} -> {to}", file.path)?,
+            }
+            if let Some(err) = &file.error {
+                writeln!(f, "    rejected: {err}")?;
+            }
+            for (i, hunk) in file.hunks.iter().enumerate() {
+                write!(f, "    hunk {}: ", i + 1)?;
+                match hunk {
+                    HunkOutcome::Applied { line, offset, fuzz } => {
+                        write!(f, "applied at line {line}")?;
+                        let mut notes = Vec::new();
+                        if *offset != 0 {
+                            notes.push(format!("offset {offset:+}"));
+                        }
+                        match fuzz.whitespace {
+                            0 => {}
+                            1 => notes.push("ignoring trailing whitespace".into()),
+                            _ => notes.push("ignoring whitespace".into()),
+                        }
+                        if fuzz.context_dropped > 0 {
+                            notes.push(format!("fuzz {}", fuzz.context_dropped));
+                        }
+                        if !notes.is_empty() {
+                            write!(f, " ({})", notes.join(", "))?;
+                        }
+                        writeln!(f)?;
+                    }
+                    HunkOutcome::Rejected { reason } => writeln!(f, "rejected: {reason}")?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// ── Parsing ──
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+#[derive(Debug, Default)]
+struct Hunk {
+    /// 1-based old-side start line from an `@@ -l,s +l,s @@` header.
+    old_start: Option<usize>,
+    /// Text after a bare `@@` that names a line to search below.
+    anchor: Option<String>,
+    lines: Vec<HunkLine>,
+    /// Must match at the end of the file.
+    at_eof: bool,
+    /// The new side ends without a trailing newline.
+    no_newline: bool,
+}
+
+#[derive(Debug)]
+struct FilePatch {
+    path: String,
+    action: PatchAction,
+    hunks: Vec<Hunk>,
+}
+
+fn invalid(msg: impl Into<String>) -> VDriveError {
+    VDriveError::InvalidPatch(msg.into())
+}
+
+fn parse_patch(text: &str) -> VDriveResult<Vec<FilePatch>> {
+    let lines: Vec<&str> = text.lines().collect();
+    let files = if lines.iter().any(|l| l.starts_with("*** Begin Patch") || l.starts_with("*** Update File:")
+        || l.starts_with("*** Add File:") || l.starts_with("*** Delete File:"))
+    {
+        parse_envelope(&lines)?
+    } else {
+        parse_unified(&lines)?
+    };
+    if files.is_empty() {
+        return Err(invalid("no file changes found"));
+    }
+    let mut seen = HashSet::new();
+    for file in &files {
+        let to = match &file.action {
+            PatchAction::Move { to } => Some(to.as_str()),
+            _ => None,
+        };
+        for path in std::iter::once(file.path.as_str()).chain(to) {
+            if !seen.insert(path) {
+                return Err(invalid(format!("{path} appears more than once")));
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// `--- a/x` / `+++ b/x` headers followed by `@@` hunks. `diff --git`,
+/// `index` and other header noise is skipped.
+fn parse_unified(lines: &[&str]) -> VDriveResult<Vec<FilePatch>> {
+    let mut files = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let Some(old) = lines[i].strip_prefix("--- ") else {
+            i += 1;
+            continue;
+        };
+        let new = lines
+            .get(i + 1)
+            .and_then(|l| l.strip_prefix("+++ "))
+            .ok_or_else(|| invalid(format!("line {}: '---' header without '+++'", i + 1)))?;
+        let (old, new) = (header_path(old, "a/"), header_path(new, "b/"));
+        let (path, action) = match (old.as_str(), new.as_str()) {
+            ("/dev/null", "/dev/null") => return Err(invalid(format!("line {}: both sides are /dev/null", i + 1))),
+            ("/dev/null", _) => (new, PatchAction::Add),
+            (_, "/dev/null") => (old, PatchAction::Delete),
+            _ if old != new => (old, PatchAction::Move { to: new }),
+            _ => (old, PatchAction::Update),
+        };
+        i += 2;
+
+        let mut hunks = Vec::new();
+        while i < lines.len() && lines[i].starts_with("@@") {
+            let old_start = parse_range_start(lines[i])
+                .ok_or_else(|| invalid(format!("line {}: bad hunk header '{}'", i + 1, lines[i])))?;
+            let mut hunk = Hunk {
+                old_start: Some(old_start),
+                ..Default::default()
+            };
+            i = read_hunk_lines(lines, i + 1, &mut hunk);
+            hunks.push(hunk);
+        }
+        files.push(FilePatch { path, action, hunks });
+    }
+    Ok(files)
+}
+
+/// The `*** Begin Patch` / `*** Update File:` format.
+fn parse_envelope(lines: &[&str]) -> VDriveResult<Vec<FilePatch>> {
+    let mut files: Vec<FilePatch> = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        let header = |prefix: &str| line.strip_prefix(prefix).map(|p| p.trim().to_string());
+        if let Some(path) = header("*** Add File:") {
+            let mut hunk = Hunk::default();
+            i += 1;
+            while i < lines.len() && !lines[i].starts_with("*** ") {
+                let text = lines[i].strip_prefix('+').ok_or_else(|| {
+                    invalid(format!("line {}: added file lines must start with '+'", i + 1))
+                })?;
+                hunk.lines.push(HunkLine::Add(text.to_string()));
+                i += 1;
+            }
+            files.push(FilePatch {
+                path,
+                action: PatchAction::Add,
+                hunks: vec![hunk],
+            });
+        } else if let Some(path) = header("*** Delete File:") {
+            files.push(FilePatch {
+                path,
+                action: PatchAction::Delete,
+                hunks: Vec::new(),
+            });
+            i += 1;
+        } else if let Some(path) = header("*** Update File:") {
+            i += 1;
+            let mut action = PatchAction::Update;
+            if let Some(to) = lines.get(i).and_then(|l| l.strip_prefix("*** Move to:")) {
+                action = PatchAction::Move { to: to.trim().to_string() };
+                i += 1;
+            }
+            let mut hunks = Vec::new();
+            while i < lines.len() && !is_envelope_header(lines[i]) {
+                let mut hunk = Hunk::default();
+                if let Some(rest) = lines[i].strip_prefix("@@") {
+                    let anchor = rest.trim().trim_end_matches("@@").trim();
+                    if !anchor.is_empty() {
+                        hunk.anchor = Some(anchor.to_string());
+                    }
+                    i += 1;
+                }
+                let next = read_hunk_lines(lines, i, &mut hunk);
+                if let Some(stray) = lines.get(i).filter(|l| next == i && !l.starts_with("@@") && !is_envelope_header(l)) {
+                    if stray.trim() != "*** End of File" {
+                        return Err(invalid(format!("line {}: unexpected '{stray}'", i + 1)));
+                    }
+                }
+                i = next;
+                if lines.get(i).is_some_and(|l| l.trim() == "*** End of File") {
+                    hunk.at_eof = true;
+                    i += 1;
+                }
+                if !hunk.lines.is_empty() {
+                    hunks.push(hunk);
+                }
+            }
+            files.push(FilePatch { path, action, hunks });
+        } else if line.starts_with("*** Begin Patch") || line.starts_with("*** End Patch") || line.trim().is_empty() {
+            i += 1;
+        } else {
+            return Err(invalid(format!("line {}: unexpected '{line}'", i + 1)));
+        }
+    }
+    Ok(files)
+}
+
+fn is_envelope_header(line: &str) -> bool {
+    ["*** Add File:", "*** Delete File:", "*** Update File:", "*** End Patch"]
+        .iter()
+        .any(|h| line.starts_with(h))
+}
+
+/// Read ` `/`-`/`+` lines into `hunk` from `start`; returns the index of
+/// the first line that isn't part of it. Blank lines count as empty
+/// context, except trailing ones, which are usually just separators.
+fn read_hunk_lines(lines: &[&str], start: usize, hunk: &mut Hunk) -> usize {
+    let mut i = start;
+    while i < lines.len() {
+        let line = lines[i];
+        let next_is_new_file = line.starts_with("--- ") && lines.get(i + 1).is_some_and(|l| l.starts_with("+++ "));
+        if line.starts_with("@@") || line.starts_with("*** ") || line.starts_with("diff ") || next_is_new_file {
+            break;
+        }
+        match line.chars().next() {
+            Some(' ') => hunk.lines.push(HunkLine::Context(line[1..].to_string())),
+            Some('-') => hunk.lines.push(HunkLine::Remove(line[1..].to_string())),
+            Some('+') => hunk.lines.push(HunkLine::Add(line[1..].to_string())),
+            None => hunk.lines.push(HunkLine::Context(String::new())),
+            Some('\\') => {
+                // `\ No newline at end of file` after a line the new side keeps.
+                if !matches!(hunk.lines.last(), Some(HunkLine::Remove(_))) {
+                    hunk.no_newline = true;
+                }
+            }
+            _ => break,
+        }
+        i += 1;
+    }
+    while hunk.lines.last() == Some(&HunkLine::Context(String::new())) {
+        hunk.lines.pop();
+    }
+    i
+}
+
+/// Path from a `---`/`+++` header: drop any timestamp and the `a/`/`b/`
+/// prefix git adds.
+fn header_path(raw: &str, prefix: &str) -> String {
+    let path = raw.split('\t').next().unwrap_or(raw).trim();
+    path.strip_prefix(prefix).unwrap_or(path).to_string()
+}
+
+/// The old-side start line of `@@ -l,s +l,s @@`.
+fn parse_range_start(header: &str) -> Option<usize> {
+    let old = header.strip_prefix("@@")?.trim_start().strip_prefix('-')?;
+    let end = old.find(|c: char| !c.is_ascii_digit()).unwrap_or(old.len());
+    old[..end].parse().ok()
+}
+
+// ── Matching ──
+
+/// Line-comparison strictness, loosest last.
+const WHITESPACE_LEVELS: [u8; 3] = [0, 1, 2];
+
+fn lines_match(level: u8, file: &str, hunk: &str) -> bool {
+    match level {
+        0 => file == hunk,
+        1 => file.trim_end() == hunk.trim_end(),
+        _ => file.split_whitespace().eq(hunk.split_whitespace()),
+    }
+}
+
+/// A file's text as lines, remembering how to put it back together.
+struct TextFile {
+    lines: Vec<String>,
+    /// Whether each line ends in `\r\n`, so mixed files keep their mix.
+    crlf: Vec<bool>,
+    trailing_newline: bool,
+}
+
+impl TextFile {
+    fn parse(content: &str) -> Self {
+        let trailing_newline = content.ends_with('\n');
+        let body = content.strip_suffix('\n').unwrap_or(content);
+        let (lines, mut crlf): (Vec<String>, Vec<bool>) = if content.is_empty() {
+            (Vec::new(), Vec::new())
+        } else {
+            body.split('\n')
+                .map(|l| match l.strip_suffix('\r') {
+                    Some(l) => (l.to_string(), true),
+                    None => (l.to_string(), false),
+                })
+                .unzip()
+        };
+        // An unterminated last line ends like the one before it if a
+        // hunk appends past it.
+        if let (false, [.., before, last]) = (trailing_newline, crlf.as_mut_slice()) {
+            *last = *before;
+        }
+        Self { lines, crlf, trailing_newline }
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        for (i, (line, &crlf)) in self.lines.iter().zip(&self.crlf).enumerate() {
+            out.push_str(line);
+            if i + 1 < self.lines.len() || self.trailing_newline {
+                out.push_str(if crlf { "\r\n" } else { "\n" });
+            }
+        }
+        out
+    }
+}
+
+/// Apply `hunks` to `file` in order. Returns one outcome per hunk; the
+/// file is only meaningful if every outcome is `Applied`.
+fn apply_hunks(file: &mut TextFile, hunks: &[Hunk]) -> Vec<HunkOutcome> {
+    let mut outcomes = Vec::new();
+    let mut cursor = 0;
+    let mut delta: isize = 0;
+    for hunk in hunks {
+        match place_hunk(&file.lines, hunk, cursor, delta) {
+            Ok(placed) => {
+                let (new_lines, new_crlf): (Vec<String>, Vec<bool>) =
+                    placed.replacement(file, hunk).into_iter().unzip();
+                let new_len = new_lines.len();
+                file.lines.splice(placed.pos..placed.pos + placed.old_len, new_lines);
+                file.crlf.splice(placed.pos..placed.pos + placed.old_len, new_crlf);
+                if hunk.no_newline {
+                    file.trailing_newline = false;
+                }
+                delta += placed.offset + new_len as isize - placed.old_len as isize;
+                cursor = placed.pos + new_len;
+                outcomes.push(HunkOutcome::Applied {
+                    line: placed.pos + 1,
+                    offset: placed.offset,
+                    fuzz: placed.fuzz,
+                });
+            }
+            Err(reason) => outcomes.push(HunkOutcome::Rejected { reason }),
+        }
+    }
+    outcomes
+}
+
+/// Where a hunk matched.
+struct Placement {
+    pos: usize,
+    /// File lines the match covers.
+    old_len: usize,
+    /// Hunk lines left out at each end.
+    lead: usize,
+    trail: usize,
+    offset: isize,
+    fuzz: Fuzz,
+}
+
+impl Placement {
+    /// The lines that replace the matched ones, with their endings:
+    /// file text for context, hunk text for additions. An added line
+    /// ends like the last file line the hunk passed, kept or removed
+    /// (the first line, at the top of the file).
+    fn replacement(&self, file: &TextFile, hunk: &Hunk) -> Vec<(String, bool)> {
+        let mut file_line = self.pos;
+        let mut crlf = match self.pos {
+            0 => file.crlf.first().copied().unwrap_or(false),
+            pos => file.crlf[pos - 1],
+        };
+        let mut out = Vec::new();
+        for line in &hunk.lines[self.lead..hunk.lines.len() - self.trail] {
+            match line {
+                HunkLine::Context(_) => {
+                    crlf = file.crlf[file_line];
+                    out.push((file.lines[file_line].clone(), crlf));
+                    file_line += 1;
+                }
+                HunkLine::Remove(_) => {
+                    crlf = file.crlf[file_line];
+                    file_line += 1;
+                }
+                HunkLine::Add(text) => out.push((text.clone(), crlf)),
+            }
+        }
+        out
+    }
+}
+
+fn place_hunk(lines: &[String], hunk: &Hunk, cursor: usize, delta: isize) -> Result<Placement, String> {
+    let mut from = cursor;
+    if let Some(anchor) = &hunk.anchor {
+        let found = (cursor..lines.len())
+            .find(|&i| lines[i].trim() == anchor || lines[i].contains(anchor.as_str()))
+            .ok_or_else(|| format!("anchor '{anchor}' not found"))?;
+        // The anchor line may itself be the first context line.
+        from = found;
+    }
+    let hint = hunk.old_start.map(|s| (s.saturating_sub(1) as isize + delta).max(0) as usize);
+
+    let old: Vec<&str> = old_side(&hunk.lines);
+    if old.is_empty() {
+        // Pure insertion: at the hinted line, after the anchor, or at the end.
+        let pos = match (hint, &hunk.anchor) {
+            _ if hunk.at_eof => lines.len(),
+            (Some(h), _) if hunk.old_start != Some(0) => (h + 1).min(lines.len()),
+            (Some(_), _) => 0,
+            (None, Some(_)) => from + 1,
+            (None, None) => lines.len(),
+        };
+        return Ok(Placement {
+            pos,
+            old_len: 0,
+            lead: 0,
+            trail: 0,
+            offset: 0,
+            fuzz: Fuzz::default(),
+        });
+    }
+
+    let leading = hunk.lines.iter().take_while(|l| matches!(l, HunkLine::Context(_))).count();
+    let trailing = hunk.lines.iter().rev().take_while(|l| matches!(l, HunkLine::Context(_))).count();
+    let mut ambiguous = 0;
+    for dropped in 0..=MAX_CONTEXT_FUZZ {
+        let lead = dropped.min(leading);
+        let trail = dropped.min(trailing);
+        if dropped > 0 && lead + trail == 0 {
+            break;
+        }
+        let window = old_side(&hunk.lines[lead..hunk.lines.len() - trail]);
+        if window.is_empty() || window.len() > lines.len() {
+            continue;
+        }
+        for level in WHITESPACE_LEVELS {
+            let fits = |p: usize| window.iter().enumerate().all(|(k, w)| lines_match(level, &lines[p + k], w));
+            let last = lines.len() - window.len();
+            let candidates: Vec<usize> = if hunk.at_eof && trail == 0 {
+                (last >= from && fits(last)).then_some(last).into_iter().collect()
+            } else {
+                (from..=last).filter(|&p| fits(p)).collect()
+            };
+            let expected = hint.map(|h| h + lead);
+            let pos = match (candidates.as_slice(), expected) {
+                ([], _) => continue,
+                ([only], _) => *only,
+                (many, Some(e)) => *many.iter().min_by_key(|&&p| p.abs_diff(e)).unwrap(),
+                (many, None) => {
+                    ambiguous = many.len();
+                    continue;
+                }
+            };
+            return Ok(Placement {
+                pos,
+                old_len: window.len(),
+                lead,
+                trail,
+                offset: expected.map_or(0, |e| pos as isize - e as isize),
+                fuzz: Fuzz {
+                    whitespace: level,
+                    context_dropped: dropped,
+                },
+            });
+        }
+    }
+    let first = old[0];
+    if ambiguous > 0 {
+        Err(format!(
+            "context starting '{first}' matches {ambiguous} places; add more context lines or an @@ anchor"
+        ))
+    } else {
+        Err(format!(
+            "context starting '{first}' not found{}; re-read the file and regenerate this hunk",
+            if cursor > 0 { " after the previous hunk" } else { "" }
+        ))
+    }
+}
+
+/// The lines a hunk expects to find: its context and removals.
+fn old_side(lines: &[HunkLine]) -> Vec<&str> {
+    lines
+        .iter()
+        .filter_map(|l| match l {
+            HunkLine::Context(t) | HunkLine::Remove(t) => Some(t.as_str()),
+            HunkLine::Add(_) => None,
+        })
+        .collect()
+}
+
+// ── Applying ──
+
+/// What one file will become once the patch is written.
+enum Planned {
+    Write { path: String, content: String },
+    Delete { path: String },
+}
+
+/// How to put one written file back if a later write fails, and what
+/// the write was charged against the budget.
+enum Undo {
+    Restore { path: String, content: Vec<u8>, charged: u64 },
+    Remove { path: String, charged: u64 },
+}
+
+impl VDrive {
+    /// Apply a unified diff or `*** Begin Patch` block touching any
+    /// number of files. Returns `Ok` with a report either way: check
+    /// [`PatchReport::applied`]. Only a malformed patch is an `Err`.
+    pub fn apply_patch(&self, patch: &str) -> VDriveResult<PatchReport> {
+        let files = parse_patch(patch)?;
+        let mut reports = Vec::new();
+        let mut planned = Vec::new();
+        for file in &files {
+            let mut report = FilePatchReport {
+                path: file.path.clone(),
+                action: file.action.clone(),
+                hunks: Vec::new(),
+                error: None,
+            };
+            match self.plan_file(file, &mut report) {
+                Ok(mut steps) => planned.append(&mut steps),
+                Err(e) => report.error = Some(e),
+            }
+            reports.push(report);
+        }
+        let applied = reports.iter().all(FilePatchReport::is_ok);
+        let report = PatchReport {
+            applied,
+            files: reports,
+        };
+        if applied {
+            self.check_planned_budget(&planned)?;
+            self.write_planned(planned)?;
+        }
+        Ok(report)
+    }
+
+    /// Work out `file`'s new content in memory, recording hunk outcomes
+    /// in `report`. An `Err` rejects the whole file.
+    fn plan_file(&self, file: &FilePatch, report: &mut FilePatchReport) -> Result<Vec<Planned>, String> {
+        let exists = self.exists(&file.path);
+        match &file.action {
+            PatchAction::Add => {
+                if exists {
+                    return Err("file already exists".into());
+                }
+                let mut text = TextFile::parse("");
+                text.trailing_newline = true;
+                report.hunks = apply_hunks(&mut text, &file.hunks);
+                self.check_target(&file.path)?;
+                Ok(vec![Planned::Write {
+                    path: file.path.clone(),
+                    content: text.render(),
+                }])
+            }
+            PatchAction::Delete => {
+                if !exists {
+                    return Err("file not found".into());
+                }
+                self.check_target(&file.path)?;
+                Ok(vec![Planned::Delete {
+                    path: file.path.clone(),
+                }])
+            }
+            PatchAction::Update | PatchAction::Move { .. } => {
+                let raw = self.read_bytes(&file.path).map_err(|e| e.to_string())?;
+                let content = String::from_utf8(raw).map_err(|_| "not a UTF-8 text file".to_string())?;
+                let mut text = TextFile::parse(&content);
+                report.hunks = apply_hunks(&mut text, &file.hunks);
+                self.check_target(&file.path)?;
+                let new = text.render();
+                match &file.action {
+                    PatchAction::Move { to } => {
+                        if self.exists(to) {
+                            return Err(format!("move target {to} already exists"));
+                        }
+                        self.check_target(to)?;
+                        Ok(vec![
+                            Planned::Write {
+                                path: to.clone(),
+                                content: new,
+                            },
+                            Planned::Delete {
+                                path: file.path.clone(),
+                            },
+                        ])
+                    }
+                    _ => Ok(vec![Planned::Write {
+                        path: file.path.clone(),
+                        content: new,
+                    }]),
+                }
+            }
+        }
+    }
+
+    /// Reject a path the write would fail on anyway, before anything
+    /// is written.
+    fn check_target(&self, path: &str) -> Result<(), String> {
+        let abs = self.resolve_new(path).map_err(|e| e.to_string())?;
+        self.check_mutable(&abs, path).map_err(|e| e.to_string())
+    }
+
+    /// Fail before the first write if the planned writes don't fit the
+    /// mount budgets together.
+    fn check_planned_budget(&self, planned: &[Planned]) -> VDriveResult<()> {
+        let writes: Vec<(&str, u64, bool)> = planned
+            .iter()
+            .filter_map(|step| match step {
+                Planned::Write { path, content } => Some((path.as_str(), content.len() as u64, !self.exists(path))),
+                Planned::Delete { .. } => None,
+            })
+            .collect();
+        self.check_budget(&writes)
+    }
+
+    /// Write every planned file, undoing the earlier ones if one fails.
+    /// Undone writes are refunded and their restores aren't charged.
+    fn write_planned(&self, planned: Vec<Planned>) -> VDriveResult<()> {
+        let mut done = Vec::new();
+        for step in planned {
+            let result = match &step {
+                Planned::Write { path, content } => {
+                    let charged = content.len() as u64;
+                    let undo = match self.read_bytes(path) {
+                        Ok(content) => Undo::Restore {
+                            path: path.clone(),
+                            content,
+                            charged,
+                        },
+                        Err(_) => Undo::Remove {
+                            path: path.clone(),
+                            charged,
+                        },
+                    };
+                    self.write_as(ChangeOp::Patch, path, content.as_bytes()).map(|()| undo)
+                }
+                Planned::Delete { path } => self.read_bytes(path).and_then(|content| {
+                    self.delete_file(path)?;
+                    Ok(Undo::Restore {
+                        path: path.clone(),
+                        content,
+                        charged: 0,
+                    })
+                }),
+            };
+            match result {
+                Ok(undo) => done.push(undo),
+                Err(e) => {
+                    let failed: Vec<String> = done
+                        .into_iter()
+                        .rev()
+                        .filter_map(|undo| self.undo(undo).err())
+                        .collect();
+                    if failed.is_empty() {
+                        return Err(e);
+                    }
+                    return Err(VDriveError::RollbackFailed {
+                        source: Box::new(e),
+                        failed,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Put back one written file. On failure, returns the path and why.
+    fn undo(&self, undo: Undo) -> Result<(), String> {
+        let (path, result) = match undo {
+            Undo::Restore { path, content, charged } => {
+                let result = self
+                    .resolve_new(&path)
+                    .and_then(|abs| self.write_resolved(ChangeOp::Patch, &abs, &path, &content));
+                self.refund_write(charged, false);
+                (path, result)
+            }
+            Undo::Remove { path, charged } => {
+                let result = self.delete_file(&path);
+                self.refund_write(charged, true);
+                (path, result)
+            }
+        };
+        result.map_err(|e| format!("{path}: {e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn setup() -> (TempDir, VDrive) {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(
+            dir.path().join("src/lib.rs"),
+            "fn one() {\n    a();\n}\n\nfn two() {\n    b();\n}\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("README.md"), "# demo\n").unwrap();
+        let vd = VDrive::open(dir.path()).unwrap();
+        (dir, vd)
+    }
+
+    fn read(dir: &TempDir, path: &str) -> String {
+        fs::read_to_string(dir.path().join(path)).unwrap()
+    }
+
+    #[test]
+    fn unified_multi_file() {
+        let (dir, vd) = setup();
+        let patch = "\
+diff --git a/src/lib.rs b/src/lib.rs
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@
+ fn one() {
+-    a();
++    a2();
+ }
+@@ -5,3 +5,3 @@
+ fn two() {
+-    b();
++    b2();
+ }
+--- /dev/null
++++ b/NOTES.md
+@@ -0,0 +1,2 @@
++first
++second
+--- a/README.md
++++ /dev/null
+@@ -1 +0,0 @@
+-# demo
+";
+        let report = vd.apply_patch(patch).unwrap();
+        assert!(report.applied, "{report}");
+        assert_eq!(read(&dir, "src/lib.rs"), "fn one() {\n    a2();\n}\n\nfn two() {\n    b2();\n}\n");
+        assert_eq!(read(&dir, "NOTES.md"), "first\nsecond\n");
+        assert!(!dir.path().join("README.md").exists());
+    }
+
+    #[test]
+    fn envelope_format_with_anchor_and_move() {
+        let (dir, vd) = setup();
+        let patch = "\
+*** Begin Patch
+*** Update File: src/lib.rs
+*** Move to: src/core.rs
+@@ fn two
+-    b();
++    b3();
+*** Add File: src/new.rs
++fn new() {}
+*** End Patch
+";
+        let report = vd.apply_patch(patch).unwrap();
+        assert!(report.applied, "{report}");
+        assert!(!dir.path().join("src/lib.rs").exists());
+        assert!(read(&dir, "src/core.rs").contains("    b3();"));
+        assert_eq!(read(&dir, "src/new.rs"), "fn new() {}\n");
+    }
+
+    #[test]
+    fn fuzzy_whitespace_and_offset() {
+        let (dir, vd) = setup();
+        // Wrong line number, and the agent lost the indentation.
+        let patch = "\
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@
+ fn two() {
+-b();
++    b4();
+ }
+";
+        let report = vd.apply_patch(patch).unwrap();
+        assert!(report.applied, "{report}");
+        let HunkOutcome::Applied { line, offset, fuzz } = &report.files[0].hunks[0] else {
+            panic!("{report}");
+        };
+        assert_eq!((*line, *offset, fuzz.whitespace), (5, 4, 2));
+        assert!(read(&dir, "src/lib.rs").contains("fn two() {\n    b4();\n}"));
+        assert!(report.to_string().contains("offset +4"));
+    }
+
+    #[test]
+    fn stale_context_is_dropped() {
+        let (dir, vd) = setup();
+        let patch = "\
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,4 +1,4 @@
+ // a comment that is not there
+ fn one() {
+-    a();
++    a5();
+ }
+";
+        let report = vd.apply_patch(patch).unwrap();
+        assert!(report.applied, "{report}");
+        assert!(matches!(
+            report.files[0].hunks[0],
+            HunkOutcome::Applied { fuzz: Fuzz { context_dropped: 1, .. }, .. }
+        ));
+        assert!(read(&dir, "src/lib.rs").contains("a5();"));
+    }
+
+    #[test]
+    fn one_bad_hunk_rejects_everything() {
+        let (dir, vd) = setup();
+        let patch = "\
+*** Begin Patch
+*** Add File: added.txt
++hi
+*** Update File: src/lib.rs
+@@
+-    a();
++    a6();
+@@
+-    missing();
++    nope();
+*** End Patch
+";
+        let report = vd.apply_patch(patch).unwrap();
+        assert!(!report.applied);
+        assert!(matches!(report.files[1].hunks[0], HunkOutcome::Applied { .. }));
+        let HunkOutcome::Rejected { reason } = &report.files[1].hunks[1] else {
+            panic!("{report}");
+        };
+        assert!(reason.contains("missing();"));
+        assert!(report.to_string().contains("hunk 2: rejected"));
+        assert!(!dir.path().join("added.txt").exists());
+        assert!(read(&dir, "src/lib.rs").contains("    a();"));
+    }
+
+    #[test]
+    fn ambiguous_hunk_without_hint_rejected() {
+        let (_dir, vd) = setup();
+        let patch = "\
+*** Begin Patch
+*** Update File: src/lib.rs
+@@
+-}
++};
+*** End Patch
+";
+        let report = vd.apply_patch(patch).unwrap();
+        assert!(!report.applied);
+        let HunkOutcome::Rejected { reason } = &report.files[0].hunks[0] else {
+            panic!("{report}");
+        };
+        assert!(reason.contains("matches 2 places"));
+    }
+
+    #[test]
+    fn file_level_errors_and_malformed_patch() {
+        let (_dir, vd) = setup();
+        let report = vd
+            .apply_patch("*** Begin Patch\n*** Add File: README.md\n+x\n*** Delete File: gone.txt\n*** End Patch\n")
+            .unwrap();
+        assert!(!report.applied);
+        assert_eq!(report.files[0].error.as_deref(), Some("file already exists"));
+        assert_eq!(report.files[1].error.as_deref(), Some("file not found"));
+
+        assert!(matches!(vd.apply_patch("just some text"), Err(VDriveError::InvalidPatch(_))));
+    }
+
+    #[test]
+    fn crlf_and_missing_newline_preserved() {
+        let (dir, vd) = setup();
+        fs::write(dir.path().join("win.txt"), "a\r\nb\r\n").unwrap();
+        fs::write(dir.path().join("tail.txt"), "x\ny").unwrap();
+        let patch = "\
+--- a/win.txt
++++ b/win.txt
+@@ -1,2 +1,2 @@
+ a
+-b
++c
+--- a/tail.txt
++++ b/tail.txt
+@@ -1,2 +1,2 @@
+ x
+-y
+\\ No newline at end of file
++z
+\\ No newline at end of file
+";
+        let report = vd.apply_patch(patch).unwrap();
+        assert!(report.applied, "{report}");
+        assert_eq!(read(&dir, "win.txt"), "a\r\nc\r\n");
+        assert_eq!(read(&dir, "tail.txt"), "x\nz");
+    }
+
+    #[test]
+    fn mixed_line_endings_kept_per_line() {
+        let (dir, vd) = setup();
+        fs::write(dir.path().join("mixed.txt"), "a\nb\r\nc\nd\r\n").unwrap();
+        let patch = "\
+--- a/mixed.txt
++++ b/mixed.txt
+@@ -1,4 +1,5 @@
+ a
+-b
++b2
++b3
+ c
+ d
+";
+        let report = vd.apply_patch(patch).unwrap();
+        assert!(report.applied, "{report}");
+        // Untouched lines keep their own ending; new ones take the one
+        // of the line they replace.
+        assert_eq!(read(&dir, "mixed.txt"), "a\nb2\r\nb3\r\nc\nd\r\n");
+    }
+
+    #[test]
+    fn move_target_counts_as_a_patched_path() {
+        let (dir, vd) = setup();
+        let patch = "\
+*** Begin Patch
+*** Update File: src/lib.rs
+*** Move to: src/moved.rs
+*** Add File: src/moved.rs
++fn other() {}
+*** End Patch
+";
+        let err = vd.apply_patch(patch).unwrap_err();
+        assert!(matches!(&err, VDriveError::InvalidPatch(msg) if msg.contains("src/moved.rs")), "{err}");
+        assert!(dir.path().join("src/lib.rs").exists());
+        assert!(!dir.path().join("src/moved.rs").exists());
+    }
+
+    #[test]
+    fn failed_write_rolls_back() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "one\n").unwrap();
+        let vd = VDrive::open(dir.path())
+            .unwrap()
+            .with_policy(agentos_events::MountPolicy {
+                max_bytes_written: Some(8),
+                ..Default::default()
+            });
+        let patch = "\
+--- a/a.txt
++++ b/a.txt
+@@ -1 +1 @@
+-one
++two
+--- /dev/null
++++ b/big.txt
+@@ -0,0 +1 @@
++far too long for the budget
+";
+        assert!(matches!(vd.apply_patch(patch), Err(VDriveError::QuotaExceeded(_))));
+        assert_eq!(read(&dir, "a.txt"), "one\n");
+        assert!(!dir.path().join("big.txt").exists());
+    }
+
+    #[test]
+    fn budget_is_checked_for_the_whole_patch_before_writing() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "one\n").unwrap();
+        let vd = VDrive::open(dir.path())
+            .unwrap()
+            .with_policy(agentos_events::MountPolicy {
+                max_bytes_written: Some(12),
+                max_files: Some(1),
+                ..Default::default()
+            });
+        // Each file fits on its own; together they're 4 + 9 bytes.
+        let patch = "\
+--- a/a.txt
++++ b/a.txt
+@@ -1 +1 @@
+-one
++two
+--- /dev/null
++++ b/b.txt
+@@ -0,0 +1 @@
++12345678
+";
+        let err = vd.apply_patch(patch).unwrap_err();
+        assert!(matches!(err, VDriveError::QuotaExceeded(_)), "{err}");
+        assert_eq!(read(&dir, "a.txt"), "one\n");
+        assert!(!dir.path().join("b.txt").exists());
+        assert_eq!(vd.bytes_written(), 0, "a rejected patch charges nothing");
+
+        // Two new files against a one-file budget.
+        let patch = "\
+--- /dev/null
++++ b/c.txt
+@@ -0,0 +1 @@
++c
+--- /dev/null
++++ b/d.txt
+@@ -0,0 +1 @@
++d
+";
+        assert!(matches!(vd.apply_patch(patch), Err(VDriveError::QuotaExceeded(_))));
+        assert!(!dir.path().join("c.txt").exists());
+
+        // A patch that fits is written and charged once.
+        vd.write_file("a.txt", "one\n").unwrap();
+        let patch = "\
+--- a/a.txt
++++ b/a.txt
+@@ -1 +1 @@
+-one
++two
+";
+        assert!(vd.apply_patch(patch).unwrap().applied);
+        assert_eq!(vd.bytes_written(), 8);
+    }
+}
@@ -0,0 +1,340 @@
+//! Mount policy enforcement — what a drive lets its callers see and change.
+//!
+//! The policy types live in `agentos-events` ([`MountPolicy`]); this
+//! module applies them. `deny` globs are invisible (reads fail with
+//! not-found, `glob`, `grep` and `list_dir` skip them), read-only mounts
+//! and globs reject mutations, and writes are metered against per-file,
+//! per-session byte and new-file budgets.
+//!
+//! A drive carries one base policy (the profile's) plus per-agent
+//! policies. [`VDrive::for_agent`] selects the caller, whose effective
+//! policy is the tighter of the two. Usage is counted per agent for as
+//! long as the drive stays mounted.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use agentos_events::{MountPolicies, MountPolicy};
+
+use crate::{VDrive, VDriveError, VDriveResult};
+
+/// Bytes and new files written so far by one caller.
+#[derive(Debug, Default, Clone, Copy)]
+struct Usage {
+    bytes: u64,
+    files: u64,
+}
+
+/// Policies and usage shared by every clone of a drive.
+#[derive(Debug)]
+pub(crate) struct PolicyState {
+    policies: MountPolicies,
+    usage: Mutex<HashMap<String, Usage>>,
+}
+
+impl VDrive {
+    /// Enforce `policies` on this drive. Usage counters start at zero.
+    pub fn with_policies(mut self, policies: MountPolicies) -> Self {
+        self.policy = Some(Arc::new(PolicyState {
+            policies,
+            usage: Mutex::new(HashMap::new()),
+        }));
+        self
+    }
+
+    /// Enforce a single policy on every caller.
+    pub fn with_policy(self, policy: MountPolicy) -> Self {
+        self.with_policies(MountPolicies {
+            base: policy,
+            agents: HashMap::new(),
+        })
+    }
+
+    /// A handle whose operations are checked against `agent`'s policy.
+    pub fn for_agent(&self, agent: &str) -> Self {
+        Self {
+            agent: Some(agent.to_string()),
+            ..self.clone()
+        }
+    }
+
+    /// The policy this handle enforces.
+    pub fn policy(&self) -> MountPolicy {
+        match &self.policy {
+            Some(state) => state.policies.effective(self.agent.as_deref()),
+            None => MountPolicy::default(),
+        }
+    }
+
+    /// Bytes written through this handle's agent since the policy was set.
+    pub fn bytes_written(&self) -> u64 {
+        let Some(state) = &self.policy else {
+            return 0;
+        };
+        let usage = state.usage.lock().unwrap_or_else(|e| e.into_inner());
+        usage.get(self.usage_key()).map_or(0, |u| u.bytes)
+    }
+
+    fn usage_key(&self) -> &str {
+        self.agent.as_deref().unwrap_or("")
+    }
+
+    /// Whether the drive-relative path `rel` is hidden from this handle.
+    pub(crate) fn is_denied(&self, rel: &str) -> bool {
+        self.policy
+            .as_ref()
+            .is_some_and(|state| state.policies.is_denied(self.agent.as_deref(), rel))
+    }
+
+    /// Reject a mutation of `abs` if the policy makes it read-only.
+    pub(crate) fn check_mutable(&self, abs: &Path, user_path: &str) -> VDriveResult<()> {
+        let Some(state) = &self.policy else {
+            return Ok(());
+        };
+        if state.policies.is_read_only(self.agent.as_deref(), &self.relative(abs)) {
+            return Err(VDriveError::ReadOnly(user_path.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Check and record a write of `len` bytes to `abs`, creating a new
+    /// file when `creating`.
+    pub(crate) fn charge_write(
+        &self,
+        abs: &Path,
+        user_path: &str,
+        len: u64,
+        creating: bool,
+    ) -> VDriveResult<()> {
+        self.check_mutable(abs, user_path)?;
+        let Some(state) = &self.policy else {
+            return Ok(());
+        };
+        let policy = self.policy();
+        let mut usage = state.usage.lock().unwrap_or_else(|e| e.into_inner());
+        let used = usage.entry(self.usage_key().to_string()).or_default();
+        admit(&policy, used, user_path, len, creating)
+    }
+
+    /// Check that `writes` — `(user_path, len, creating)` each — fit the
+    /// budgets together, without charging anything, so a multi-file
+    /// operation can fail before its first write.
+    pub(crate) fn check_budget(&self, writes: &[(&str, u64, bool)]) -> VDriveResult<()> {
+        let Some(state) = &self.policy else {
+            return Ok(());
+        };
+        let policy = self.policy();
+        let mut used = {
+            let usage = state.usage.lock().unwrap_or_else(|e| e.into_inner());
+            usage.get(self.usage_key()).copied().unwrap_or_default()
+        };
+        for &(user_path, len, creating) in writes {
+            admit(&policy, &mut used, user_path, len, creating)?;
+        }
+        Ok(())
+    }
+
+    /// Give back what [`Self::charge_write`] took for a write that was
+    /// undone.
+    pub(crate) fn refund_write(&self, len: u64, created: bool) {
+        let Some(state) = &self.policy else {
+            return;
+        };
+        let mut usage = state.usage.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(used) = usage.get_mut(self.usage_key()) {
+            used.bytes = used.bytes.saturating_sub(len);
+            if created {
+                used.files = used.files.saturating_sub(1);
+            }
+        }
+    }
+}
+
+/// Check a write of `len` bytes against `policy` given `used` so far,
+/// and count it in `used` if it fits.
+fn admit(policy: &MountPolicy, used: &mut Usage, user_path: &str, len: u64, creating: bool) -> VDriveResult<()> {
+    if let Some(max) = policy.max_file_size {
+        if len > max {
+            return Err(VDriveError::QuotaExceeded(format!(
+                "{user_path} would be {len} bytes (max file size {max})"
+            )));
+        }
+    }
+    if let Some(max) = policy.max_bytes_written {
+        if used.bytes + len > max {
+            return Err(VDriveError::QuotaExceeded(format!(
+                "writing {len} bytes to {user_path} would exceed the \
+                 {max}-byte session budget ({} used)",
+                used.bytes
+            )));
+        }
+    }
+    if creating {
+        if let Some(max) = policy.max_files {
+            if used.files >= max {
+                return Err(VDriveError::QuotaExceeded(format!(
+                    "creating {user_path} would exceed the {max}-file session budget"
+                )));
+            }
+        }
+        used.files += 1;
+    }
+    used.bytes += len;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agentos_events::GlobRules;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn setup(policy: MountPolicy) -> (TempDir, VDrive) {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        fs::create_dir_all(dir.path().join("secrets")).unwrap();
+        fs::write(dir.path().join(".git/HEAD"), "ref: main\n").unwrap();
+        fs::write(dir.path().join("secrets/key.txt"), "hunter2\n").unwrap();
+        fs::write(dir.path().join("main.rs"), "fn main() {}\n").unwrap();
+        let vd = VDrive::open(dir.path()).unwrap().with_policy(policy);
+        (dir, vd)
+    }
+
+    #[test]
+    fn read_only_mount_rejects_mutations() {
+        let (_dir, vd) = setup(MountPolicy {
+            read_only: true,
+            ..Default::default()
+        });
+        assert!(vd.read_file("main.rs", 1, 10).is_ok());
+        assert!(matches!(vd.write_file("new.txt", "x"), Err(VDriveError::ReadOnly(_))));
+        assert!(matches!(
+            vd.edit_file("main.rs", "main", "start", false),
+            Err(VDriveError::ReadOnly(_))
+        ));
+        assert!(matches!(vd.mkdir("d"), Err(VDriveError::ReadOnly(_))));
+        assert!(matches!(vd.delete_file("main.rs"), Err(VDriveError::ReadOnly(_))));
+    }
+
+    #[test]
+    fn readonly_glob_protects_subtree() {
+        let (dir, vd) = setup(MountPolicy {
+            readonly: GlobRules::new([".git/**"]).unwrap(),
+            ..Default::default()
+        });
+        assert!(matches!(vd.write_file(".git/HEAD", "x"), Err(VDriveError::ReadOnly(_))));
+        assert!(matches!(vd.delete_dir(".git"), Err(VDriveError::ReadOnly(_))));
+        assert!(vd.read_file(".git/HEAD", 1, 10).is_ok());
+        vd.write_file("notes.txt", "ok").unwrap();
+        assert_eq!(fs::read_to_string(dir.path().join(".git/HEAD")).unwrap(), "ref: main\n");
+    }
+
+    #[test]
+    fn denied_paths_are_invisible() {
+        let (_dir, vd) = setup(MountPolicy {
+            deny: GlobRules::new(["secrets/**"]).unwrap(),
+            ..Default::default()
+        });
+        assert!(matches!(vd.read_file("secrets/key.txt", 1, 10), Err(VDriveError::NotFound(_))));
+        assert!(!vd.exists("secrets"));
+        assert!(vd.glob("**/*").unwrap().iter().all(|p| !p.starts_with("secrets")));
+        assert!(vd.list_dir(".").unwrap().iter().all(|e| e.path != "secrets"));
+        assert!(vd.grep("hunter2", None, 10).unwrap().is_empty());
+        assert!(vd.write_file("secrets/new.txt", "x").is_err());
+    }
+
+    #[test]
+    fn rename_and_copy_respect_policy() {
+        let (dir, vd) = setup(MountPolicy {
+            deny: GlobRules::new(["secrets/key.txt"]).unwrap(),
+            readonly: GlobRules::new([".git"]).unwrap(),
+            ..Default::default()
+        });
+        assert!(matches!(vd.rename(".git", "git"), Err(VDriveError::ReadOnly(_))));
+        assert!(matches!(vd.rename("main.rs", ".git/main.rs"), Err(VDriveError::ReadOnly(_))));
+        // Moving would drag the hidden file along; copying leaves it out.
+        assert!(matches!(vd.rename("secrets", "open"), Err(VDriveError::ReadOnly(_))));
+        vd.copy("secrets", "open").unwrap();
+        assert!(dir.path().join("open").is_dir());
+        assert!(!dir.path().join("open/key.txt").exists());
+        assert!(dir.path().join("secrets/key.txt").exists());
+    }
+
+    #[test]
+    fn size_and_budget_limits() {
+        let (_dir, vd) = setup(MountPolicy {
+            max_file_size: Some(10),
+            max_bytes_written: Some(15),
+            max_files: Some(1),
+            ..Default::default()
+        });
+        assert!(matches!(
+            vd.write_file("big.txt", "0123456789ab"),
+            Err(VDriveError::QuotaExceeded(_))
+        ));
+        vd.write_file("a.txt", "0123456789").unwrap();
+        assert_eq!(vd.bytes_written(), 10);
+        // Overwriting is not a new file, but still counts toward bytes.
+        assert!(matches!(vd.write_file("main.rs", "0123456"), Err(VDriveError::QuotaExceeded(_))));
+        vd.write_file("main.rs", "01234").unwrap();
+        vd.mkdir("d").unwrap();
+        let fresh = VDrive::open(vd.root()).unwrap().with_policy(MountPolicy {
+            max_files: Some(1),
+            ..Default::default()
+        });
+        fresh.write_file("b.txt", "b").unwrap();
+        assert!(matches!(fresh.write_file("c.txt", "c"), Err(VDriveError::QuotaExceeded(_))));
+    }
+
+    #[test]
+    fn failed_dir_copy_leaves_no_partial_tree() {
+        let (dir, vd) = setup(MountPolicy {
+            max_files: Some(2),
+            readonly: GlobRules::new(["backup/src/c.rs"]).unwrap(),
+            ..Default::default()
+        });
+        fs::create_dir_all(dir.path().join("src/sub")).unwrap();
+        for name in ["src/a.rs", "src/b.rs", "src/sub/c.rs"] {
+            fs::write(dir.path().join(name), "x").unwrap();
+        }
+        // Three files against a two-file budget: nothing is written.
+        assert!(matches!(vd.copy("src", "out/src"), Err(VDriveError::QuotaExceeded(_))));
+        assert!(!dir.path().join("out").exists());
+        assert_eq!(vd.bytes_written(), 0);
+
+        // Within budget, but a read-only target stops it after a.rs.
+        fs::remove_file(dir.path().join("src/sub/c.rs")).unwrap();
+        fs::write(dir.path().join("src/c.rs"), "x").unwrap();
+        fs::remove_file(dir.path().join("src/b.rs")).unwrap();
+        assert!(matches!(vd.copy("src", "backup/src"), Err(VDriveError::ReadOnly(_))));
+        assert!(!dir.path().join("backup").exists());
+        assert_eq!(vd.bytes_written(), 0);
+        vd.write_file("one.txt", "1").unwrap();
+        vd.write_file("two.txt", "2").unwrap();
+    }
+
+    #[test]
+    fn agent_policies_merge_with_base() {
+        let dir = TempDir::new().unwrap();
+        let mut agents = HashMap::new();
+        agents.insert("reviewer".to_string(), MountPolicy {
+            read_only: true,
+            ..Default::default()
+        });
+        let vd = VDrive::open(dir.path()).unwrap().with_policies(MountPolicies {
+            base: MountPolicy {
+                max_bytes_written: Some(4),
+                ..Default::default()
+            },
+            agents,
+        });
+        assert!(vd.for_agent("reviewer").write_file("a.txt", "x").is_err());
+        vd.for_agent("coder").write_file("a.txt", "1234").unwrap();
+        // Budgets are per agent.
+        vd.for_agent("other").write_file("b.txt", "1234").unwrap();
+        assert!(vd.for_agent("coder").write_file("c.txt", "5").is_err());
+        assert_eq!(vd.for_agent("coder").bytes_written(), 4);
+    }
+}
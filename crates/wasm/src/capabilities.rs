@@ -4,6 +4,12 @@
 //! Capabilities are granted via organism.yaml and enforced structurally
 //! by building the WasiCtx with only the granted imports.
 //!
+//! Filesystem grants also carry the mount policy of the profiles that
+//! reach the tool. A WASI preopen can only be read-only or read-write, so
+//! the policy is applied conservatively: any write restriction makes the
+//! preopen read-only, and `deny` rules refuse it outright (a preopen
+//! cannot hide part of a directory).
+//!
 //! Data types live in `agentos-events` for cross-crate access.
 //! The WasiCtx builder stays here (depends on wasmtime).
 
@@ -39,7 +45,16 @@ pub fn build_wasi_ctx(caps: &WasmCapabilities) -> Result<WasiCtx, WasmError> {
             )));
         }
 
-        let (dir_perms, file_perms) = if fs.read_only {
+        if !fs.mount.deny.is_empty() {
+            return Err(WasmError::Capability(format!(
+                "cannot preopen '{}': the mount policy hides {} and WASI preopens \
+                 cannot enforce deny rules",
+                fs.host_path,
+                fs.mount.deny.iter().collect::<Vec<_>>().join(", ")
+            )));
+        }
+
+        let (dir_perms, file_perms) = if fs.read_only || fs.mount.restricts_writes() {
             (DirPerms::READ, FilePerms::READ)
         } else {
             (DirPerms::all(), FilePerms::all())
@@ -114,6 +129,7 @@ mod tests {
                 host_path: dir.path().to_string_lossy().into_owned(),
                 guest_path: "/data".into(),
                 read_only: true,
+                mount: Default::default(),
             }],
             ..Default::default()
         };
@@ -129,6 +145,7 @@ mod tests {
                 host_path: dir.path().to_string_lossy().into_owned(),
                 guest_path: "/workspace".into(),
                 read_only: false,
+                mount: Default::default(),
             }],
             ..Default::default()
         };
@@ -136,6 +153,27 @@ mod tests {
         assert!(result.is_ok(), "read-write fs grant failed: {:?}", result.err());
     }
 
+    #[test]
+    fn build_wasi_ctx_refuses_denied_mount() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let caps = WasmCapabilities {
+            filesystem: vec![FsGrant {
+                host_path: dir.path().to_string_lossy().into_owned(),
+                guest_path: "/workspace".into(),
+                read_only: false,
+                mount: agentos_events::MountPolicy {
+                    deny: agentos_events::GlobRules::new(["secrets/**"]).unwrap(),
+                    ..Default::default()
+                },
+            }],
+            ..Default::default()
+        };
+        match build_wasi_ctx(&caps) {
+            Err(WasmError::Capability(msg)) => assert!(msg.contains("deny"), "unexpected: {msg}"),
+            other => panic!("expected Capability error, got: {:?}", other.err()),
+        }
+    }
+
     #[test]
     fn validate_missing_path() {
         let caps = WasmCapabilities {
@@ -143,6 +181,7 @@ mod tests {
                 host_path: "/nonexistent/path/that/does/not/exist".into(),
                 guest_path: "/data".into(),
                 read_only: true,
+                mount: Default::default(),
             }],
             ..Default::default()
         };
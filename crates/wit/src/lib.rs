@@ -24,13 +24,13 @@ pub struct ToolInterface {
 }
 
 /// Parsed record (collection of typed fields).
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ToolRecord {
     pub fields: Vec<ToolField>,
 }
 
 /// A single field in a record.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ToolField {
     /// Field name (e.g. "path", "offset").
     pub name: String,
@@ -53,6 +53,9 @@ pub enum ToolFieldType {
     F64,
     Option(Box<ToolFieldType>),
     List(Box<ToolFieldType>),
+    /// A record declared earlier in the same interface (e.g. the items of
+    /// a `list<edit>`).
+    Record(ToolRecord),
 }
 
 impl ToolInterface {
@@ -108,36 +111,10 @@ impl ToolInterface {
         let mut properties = serde_json::Map::new();
         let mut required = Vec::new();
 
-        for field in &self.request.fields {
-            let (is_required, json_type) = wit_to_json_schema(&field.field_type);
-            let field_name = wit_name_to_underscore(&field.name);
-
-            let mut prop = serde_json::Map::new();
-            prop.insert("type".into(), serde_json::Value::String(json_type));
-            if let Some(ref desc) = field.description {
-                prop.insert("description".into(), serde_json::Value::String(desc.clone()));
-            }
-            properties.insert(field_name.clone(), serde_json::Value::Object(prop));
-
-            if is_required {
-                required.push(serde_json::Value::String(field_name));
-            }
-        }
-
-        let mut schema = serde_json::Map::new();
-        schema.insert("type".into(), serde_json::Value::String("object".into()));
-        schema.insert(
-            "properties".into(),
-            serde_json::Value::Object(properties),
-        );
-        if !required.is_empty() {
-            schema.insert("required".into(), serde_json::Value::Array(required));
-        }
-
         ToolDefinition {
             name: self.name.clone(),
             description: self.description.clone(),
-            input_schema: serde_json::Value::Object(schema),
+            input_schema: serde_json::Value::Object(record_json_schema(&self.request)),
         }
     }
 
@@ -190,24 +167,69 @@ fn wit_to_field_schema(ty: &ToolFieldType) -> (bool, FieldType) {
             let (_, field_type) = wit_to_field_schema(inner);
             (false, field_type) // option = not required
         }
-        ToolFieldType::List(_) => (true, FieldType::String), // lists serialize as string content
+        // lists and records serialize as JSON string content
+        ToolFieldType::List(_) | ToolFieldType::Record(_) => (true, FieldType::String),
+    }
+}
+
+/// JSON Schema object for a record: `{ type: "object", properties, required }`.
+fn record_json_schema(record: &ToolRecord) -> JsonObject {
+    let mut properties = serde_json::Map::new();
+    let mut required = Vec::new();
+
+    for field in &record.fields {
+        let (is_required, mut prop) = wit_to_json_schema(&field.field_type);
+        let field_name = wit_name_to_underscore(&field.name);
+
+        if let Some(ref desc) = field.description {
+            prop.insert("description".into(), serde_json::Value::String(desc.clone()));
+        }
+        properties.insert(field_name.clone(), serde_json::Value::Object(prop));
+
+        if is_required {
+            required.push(serde_json::Value::String(field_name));
+        }
+    }
+
+    let mut schema = serde_json::Map::new();
+    schema.insert("type".into(), serde_json::Value::String("object".into()));
+    schema.insert(
+        "properties".into(),
+        serde_json::Value::Object(properties),
+    );
+    if !required.is_empty() {
+        schema.insert("required".into(), serde_json::Value::Array(required));
     }
+    schema
 }
 
-/// Map a WIT type to (required, json_schema_type_string) for ToolDefinition.
-fn wit_to_json_schema(ty: &ToolFieldType) -> (bool, String) {
+type JsonObject = serde_json::Map<String, serde_json::Value>;
+
+/// Map a WIT type to (required, JSON Schema) for ToolDefinition.
+fn wit_to_json_schema(ty: &ToolFieldType) -> (bool, JsonObject) {
+    let simple = |t: &str| {
+        let mut schema = JsonObject::new();
+        schema.insert("type".into(), serde_json::Value::String(t.into()));
+        schema
+    };
     match ty {
-        ToolFieldType::String => (true, "string".into()),
-        ToolFieldType::Bool => (true, "boolean".into()),
+        ToolFieldType::String => (true, simple("string")),
+        ToolFieldType::Bool => (true, simple("boolean")),
         ToolFieldType::U32 | ToolFieldType::U64 | ToolFieldType::S32 | ToolFieldType::S64 => {
-            (true, "integer".into())
+            (true, simple("integer"))
         }
-        ToolFieldType::F32 | ToolFieldType::F64 => (true, "number".into()),
+        ToolFieldType::F32 | ToolFieldType::F64 => (true, simple("number")),
         ToolFieldType::Option(inner) => {
-            let (_, json_type) = wit_to_json_schema(inner);
-            (false, json_type) // option = not required
+            let (_, schema) = wit_to_json_schema(inner);
+            (false, schema) // option = not required
+        }
+        ToolFieldType::List(inner) => {
+            let (_, items) = wit_to_json_schema(inner);
+            let mut schema = simple("array");
+            schema.insert("items".into(), serde_json::Value::Object(items));
+            (true, schema)
         }
-        ToolFieldType::List(_) => (true, "array".into()),
+        ToolFieldType::Record(record) => (true, record_json_schema(record)),
     }
 }
 
@@ -230,7 +252,8 @@ fn wit_to_codellm_type(
             let (_, codellm_type) = wit_to_codellm_type(inner)?;
             Some((false, codellm_type)) // option = not required
         }
-        ToolFieldType::List(_) => None, // no codeLlm representation
+        // no codeLlm representation
+        ToolFieldType::List(_) | ToolFieldType::Record(_) => None,
     }
 }
 
@@ -475,6 +498,38 @@ interface file-read {
         assert!(!required.contains(&serde_json::json!("offset")));
     }
 
+    #[test]
+    fn roundtrip_list_of_records_to_definition() {
+        let wit = r#"
+interface file-multi-edit {
+    record edit {
+        /// Text to find
+        old-string: string,
+        replace-all: option<bool>,
+    }
+    record request {
+        path: string,
+        edits: list<edit>,
+    }
+}
+"#;
+        let iface = parser::parse_wit(wit).unwrap();
+        let def = iface.to_tool_definition();
+
+        let edits = &def.input_schema["properties"]["edits"];
+        assert_eq!(edits["type"], "array");
+        assert_eq!(edits["items"]["type"], "object");
+        let item = &edits["items"]["properties"];
+        assert_eq!(item["old_string"]["type"], "string");
+        assert_eq!(item["old_string"]["description"], "Text to find");
+        assert_eq!(item["replace_all"]["type"], "boolean");
+        assert_eq!(edits["items"]["required"], serde_json::json!(["old_string"]));
+
+        // The payload schema carries the list as JSON text
+        let schema = iface.to_payload_schema();
+        assert!(schema.fields["edits"].required);
+    }
+
     #[test]
     fn roundtrip_parse_to_schema() {
         let wit = r#"
@@ -5,8 +5,12 @@
 //! - `record request { ... }` with typed fields
 //! - Primitive types: string, bool, u32, u64, s32, s64, f32, f64
 //! - Wrappers: `option<T>`, `list<T>`
+//! - Other records declared before use, e.g. `record edit { ... }` for a
+//!   `list<edit>` field
 //! - `func` declaration (parsed but not used beyond validation)
 
+use std::collections::HashMap;
+
 use super::{ToolField, ToolFieldType, ToolInterface, ToolRecord};
 
 /// Parse a WIT interface definition from text.
@@ -59,8 +63,9 @@ pub fn parse_wit(input: &str) -> Result<ToolInterface, String> {
         interface_doc.join(" ")
     };
 
-    // Parse body: expect `record request { ... }` and optionally a func line
-    let mut record: Option<ToolRecord> = None;
+    // Parse body: expect `record request { ... }`, any helper records it
+    // uses, and optionally a func line
+    let mut records: HashMap<String, ToolRecord> = HashMap::new();
 
     while let Some(line) = lines.next() {
         let trimmed = line.trim();
@@ -73,12 +78,13 @@ pub fn parse_wit(input: &str) -> Result<ToolInterface, String> {
         }
 
         if trimmed.starts_with("record ") {
-            record = Some(parse_record(trimmed, &mut lines)?);
+            let (record_name, record) = parse_record(trimmed, &mut lines, &records)?;
+            records.insert(record_name, record);
         }
         // Skip func declarations and other lines inside the interface
     }
 
-    let request = record.unwrap_or_else(|| ToolRecord {
+    let request = records.remove("request").unwrap_or_else(|| ToolRecord {
         fields: Vec::new(),
     });
 
@@ -89,19 +95,23 @@ pub fn parse_wit(input: &str) -> Result<ToolInterface, String> {
     })
 }
 
-/// Parse a `record <name> { ... }` block.
+/// Parse a `record <name> { ... }` block. Field types may name any
+/// record in `known`.
 fn parse_record(
     first_line: &str,
     lines: &mut std::iter::Peekable<std::str::Lines<'_>>,
-) -> Result<ToolRecord, String> {
+    known: &HashMap<String, ToolRecord>,
+) -> Result<(String, ToolRecord), String> {
     // first_line is like `record request {`
     let rest = first_line
         .strip_prefix("record ")
         .unwrap()
         .trim();
-    if !rest.ends_with('{') {
-        return Err(format!("expected '{{' after record name, found: {rest}"));
-    }
+    let name = rest
+        .strip_suffix('{')
+        .ok_or_else(|| format!("expected '{{' after record name, found: {rest}"))?
+        .trim()
+        .to_string();
 
     let mut fields = Vec::new();
     let mut field_doc = Vec::new();
@@ -131,7 +141,7 @@ fn parse_record(
 
         let field_name = field_name.trim().to_string();
         let type_str = type_str.trim();
-        let field_type = parse_type(type_str)?;
+        let field_type = parse_type(type_str, known)?;
 
         let description = if field_doc.is_empty() {
             None
@@ -148,11 +158,11 @@ fn parse_record(
         field_doc.clear();
     }
 
-    Ok(ToolRecord { fields })
+    Ok((name, ToolRecord { fields }))
 }
 
 /// Parse a WIT type string into a `ToolFieldType`.
-fn parse_type(s: &str) -> Result<ToolFieldType, String> {
+fn parse_type(s: &str, known: &HashMap<String, ToolRecord>) -> Result<ToolFieldType, String> {
     let s = s.trim();
     match s {
         "string" => Ok(ToolFieldType::String),
@@ -165,15 +175,18 @@ fn parse_type(s: &str) -> Result<ToolFieldType, String> {
         "f64" => Ok(ToolFieldType::F64),
         _ if s.starts_with("option<") && s.ends_with('>') => {
             let inner = &s[7..s.len() - 1];
-            let inner_type = parse_type(inner)?;
+            let inner_type = parse_type(inner, known)?;
             Ok(ToolFieldType::Option(Box::new(inner_type)))
         }
         _ if s.starts_with("list<") && s.ends_with('>') => {
             let inner = &s[5..s.len() - 1];
-            let inner_type = parse_type(inner)?;
+            let inner_type = parse_type(inner, known)?;
             Ok(ToolFieldType::List(Box::new(inner_type)))
         }
-        _ => Err(format!("unknown WIT type: {s}")),
+        _ => known
+            .get(s)
+            .map(|r| ToolFieldType::Record(r.clone()))
+            .ok_or_else(|| format!("unknown WIT type: {s}")),
     }
 }
 
@@ -399,6 +412,46 @@ interface bad {
         x: unknown_type,
     }
 }
+"#;
+        assert!(parse_wit(wit).is_err());
+    }
+
+    #[test]
+    fn parse_helper_record() {
+        let wit = r#"
+interface multi {
+    record edit {
+        old-string: string,
+        replace-all: option<bool>,
+    }
+    record request {
+        edits: list<edit>,
+    }
+}
+"#;
+        let iface = parse_wit(wit).unwrap();
+        assert_eq!(iface.request.fields.len(), 1);
+        let ToolFieldType::List(inner) = &iface.request.fields[0].field_type else {
+            panic!("expected list");
+        };
+        let ToolFieldType::Record(edit) = inner.as_ref() else {
+            panic!("expected record");
+        };
+        assert_eq!(edit.fields.len(), 2);
+        assert_eq!(edit.fields[0].name, "old-string");
+    }
+
+    #[test]
+    fn error_record_used_before_declared() {
+        let wit = r#"
+interface multi {
+    record request {
+        edits: list<edit>,
+    }
+    record edit {
+        old-string: string,
+    }
+}
 "#;
         assert!(parse_wit(wit).is_err());
     }
@@ -19,6 +19,7 @@ pub use agentos_platform as platform;
 pub use agentos_ports as ports;
 pub use agentos_routing as routing;
 pub use agentos_security as security;
+pub use agentos_telemetry as telemetry;
 pub use agentos_treesitter as treesitter;
 pub use agentos_wasm as wasm;
 pub use agentos_wit as wit;
@@ -3,6 +3,9 @@ use std::path::PathBuf;
 use anyhow::Result;
 use clap::Parser;
 use tracing::info;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
 
 use agentos::config::{AgentsConfig, ModelsConfig};
 use agentos::llm::LlmPool;
@@ -23,7 +26,7 @@ use agentos::tools::test_organism::TestOrganismTool;
 use agentos::tools::validate_organism::ValidateOrganismTool;
 use agentos::tools::vdrive_tools::{
     self, DriveSlot, VDriveFileRead, VDriveFileWrite, VDriveFileEdit,
-    VDriveGlob, VDriveGrep, VDriveListDir, VDriveCommandExec,
+    VDriveFileMultiEdit, VDriveFilePatch, VDriveFileMove, VDriveFileCopy, VDriveGlob, VDriveGrep, VDriveListDir, VDriveCommandExec, VDriveUndo,
 };
 use agentos::tui::run_tui;
 
@@ -64,7 +67,7 @@ prompts:
     When NOT to dispatch:
     - Simple questions you can answer directly ("what time is it?", "what does X mean?")
     - Math: use calc.
-    - Quick codebase lookups: use file-read, glob, grep, codebase-index yourself.
+    - Quick codebase lookups: use file-read, glob, grep, codebase-index, semantic-search yourself.
 
     Rules:
     - Never list your capabilities unprompted.
@@ -105,7 +108,8 @@ prompts:
     Your workflow:
     1. Read memory.md if it exists — understand project conventions and patterns.
     2. Survey the codebase: list-dir for structure, glob/grep for relevant files,
-       codebase-index for symbol maps, file-read for key modules.
+       codebase-index for symbol maps, semantic-search to find code by what it does,
+       file-read for key modules.
     3. Identify all files affected by the task, trace dependencies.
     4. Break the task into atomic steps. Each step should:
        - Name the specific files to read and modify
@@ -182,13 +186,14 @@ listeners:
         glob: auto
         grep: auto
         codebase-index: auto
+        semantic-search: auto
         list-agents: auto
         list-dir: auto
         dispatch: auto
         user: auto
         calc: auto
     librarian: true
-    peers: [file-read, glob, grep, list-dir, codebase-index, list-agents, user, dispatch, calc]
+    peers: [file-read, glob, grep, list-dir, codebase-index, semantic-search, list-agents, user, dispatch, calc]
 
   # Coder — hands-on coding specialist (top-level agent, dispatched by Bob)
   - name: coder
@@ -199,7 +204,7 @@ listeners:
       prompt: "no_paperclipper & coding_base"
       max_tokens: 4096
       max_agentic_iterations: 25
-    peers: [file-read, file-write, file-edit, glob, grep, list-dir, bash, cargo-test, cargo-build, cargo-check, cargo-clippy, git-status, git-diff, git-log, git-add, git-commit, git-push, codebase-index]
+    peers: [file-read, file-write, file-edit, file-multi-edit, file-patch, file-move, file-copy, glob, grep, list-dir, vdrive-undo, bash, cargo-test, cargo-build, cargo-check, cargo-clippy, git-status, git-diff, git-log, git-add, git-commit, git-push, codebase-index, semantic-search]
 
   # Plan Expert — top-level agent, dispatched by Bob
   - name: plan-expert
@@ -210,7 +215,7 @@ listeners:
       prompt: "no_paperclipper & plan_base"
       max_tokens: 4096
       max_agentic_iterations: 35
-    peers: [file-read, file-write, file-edit, glob, grep, list-dir, codebase-index, cargo-test, cargo-build, cargo-check, cargo-clippy, git-status, git-diff, git-log, git-add, git-commit, git-push, bash]
+    peers: [file-read, file-write, file-edit, file-multi-edit, file-patch, file-move, file-copy, glob, grep, list-dir, vdrive-undo, codebase-index, semantic-search, cargo-test, cargo-build, cargo-check, cargo-clippy, git-status, git-diff, git-log, git-add, git-commit, git-push, bash]
 
   # Agent Expert — top-level agent, dispatched by Bob
   - name: agent-expert
@@ -221,7 +226,7 @@ listeners:
       prompt: "no_paperclipper & agent_expert_base"
       max_tokens: 4096
       max_agentic_iterations: 25
-    peers: [file-read, file-write, file-edit, glob, grep, list-dir, validate-organism, test-organism, package-organism]
+    peers: [file-read, file-write, file-edit, file-multi-edit, file-patch, file-move, file-copy, glob, grep, list-dir, vdrive-undo, validate-organism, test-organism, package-organism]
 
   # Wiki Expert — top-level agent, dispatched by Bob
   - name: wiki-expert
@@ -232,7 +237,7 @@ listeners:
       prompt: "no_paperclipper & wiki_base"
       max_tokens: 4096
       max_agentic_iterations: 30
-    peers: [file-read, file-write, file-edit, glob, grep, list-dir, codebase-index]
+    peers: [file-read, file-write, file-edit, file-multi-edit, file-patch, file-move, file-copy, glob, grep, list-dir, vdrive-undo, codebase-index, semantic-search]
 
   # Infrastructure
   - name: llm-pool
@@ -257,6 +262,11 @@ listeners:
     handler: treesitter.handle
     description: "Tree-sitter code indexing"
 
+  - name: semantic-search
+    payload_class: treesitter.SemanticSearchRequest
+    handler: treesitter.semantic_search.handle
+    description: "Search code and docs by meaning"
+
   # Tools (registered in pipeline, declared here for profile/peer references)
   - name: file-read
     payload_class: tools.FileReadRequest
@@ -273,6 +283,26 @@ listeners:
     handler: tools.file_edit.handle
     description: "Edit files"
 
+  - name: file-multi-edit
+    payload_class: tools.FileMultiEditRequest
+    handler: tools.file_multi_edit.handle
+    description: "Apply several edits to one file"
+
+  - name: file-patch
+    payload_class: tools.FilePatchRequest
+    handler: tools.file_patch.handle
+    description: "Apply multi-file patches"
+
+  - name: file-move
+    payload_class: tools.FileMoveRequest
+    handler: tools.file_move.handle
+    description: "Move or rename files"
+
+  - name: file-copy
+    payload_class: tools.FileCopyRequest
+    handler: tools.file_copy.handle
+    description: "Copy files and directories"
+
   - name: glob
     payload_class: tools.GlobRequest
     handler: tools.glob.handle
@@ -288,6 +318,11 @@ listeners:
     handler: tools.list_dir.handle
     description: "List directory contents"
 
+  - name: vdrive-undo
+    payload_class: tools.VDriveUndoRequest
+    handler: tools.vdrive_undo.handle
+    description: "Checkpoint and roll back file changes"
+
   - name: cargo-test
     payload_class: tools.CargoTestRequest
     handler: tools.safe_commands.handle
@@ -388,7 +423,7 @@ listeners:
 profiles:
   default:
     linux_user: agentos
-    listeners: [bob, coder, plan-expert, agent-expert, wiki-expert, user, dispatch, file-read, file-write, file-edit, glob, grep, list-dir, cargo-test, cargo-build, cargo-check, cargo-clippy, git-status, git-diff, git-log, git-add, git-commit, git-push, bash, list-agents, validate-organism, test-organism, package-organism, compile-wasm, calc, codebase-index, llm-pool, librarian]
+    listeners: [bob, coder, plan-expert, agent-expert, wiki-expert, user, dispatch, file-read, file-write, file-edit, file-multi-edit, file-patch, file-move, file-copy, glob, grep, list-dir, vdrive-undo, cargo-test, cargo-build, cargo-check, cargo-clippy, git-status, git-diff, git-log, git-add, git-commit, git-push, bash, list-agents, validate-organism, test-organism, package-organism, compile-wasm, calc, codebase-index, semantic-search, llm-pool, librarian]
     network: [llm-pool]
     journal: retain_forever
 "#;
@@ -536,23 +571,42 @@ async fn main() -> Result<()> {
     let log_dir = PathBuf::from(&data_rel);
     std::fs::create_dir_all(&log_dir)?;
     let log_file = std::fs::File::create(log_dir.join("agentos.log"))?;
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive("agentos=info".parse()?),
+    // Request traces leave the process only when AGENTOS_TRACE_FILE or
+    // OTEL_EXPORTER_OTLP_ENDPOINT is set. The guard flushes on exit.
+    let (trace_layer, _trace_guard) = match agentos::telemetry::trace::TraceExport::from_env() {
+        Some(export) => {
+            let (layer, guard) =
+                agentos::telemetry::trace::layer(&export, "agentos").to_anyhow()?;
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(log_file)
+                .with_ansi(false)
+                .with_filter(
+                    tracing_subscriber::EnvFilter::from_default_env()
+                        .add_directive("agentos=info".parse()?),
+                ),
         )
-        .with_writer(log_file)
-        .with_ansi(false)
+        .with(trace_layer)
         .init();
 
     info!("AgentOS starting in {work_dir}");
 
+    // Pipeline metrics recorder — the TUI's `/metrics` dashboard reads
+    // it. Installed before the pipeline is built so startup WAL
+    // appends and instance restores are counted too.
+    agentos::telemetry::install();
+
     // Collect startup errors — TUI always opens, errors display as messages.
     let mut startup_errors: Vec<String> = Vec::new();
 
     // Auto-mount CWD as the agent's workspace if it looks like a project directory
     let drive_slot = vdrive_tools::empty_slot();
-    let auto_mount_msg = try_auto_mount(&drive_slot);
+    let auto_mount_msg = try_auto_mount(&drive_slot, &data_dir);
 
     // Parse organism config
     let yaml = if let Some(ref path) = cli.organism {
@@ -657,6 +711,10 @@ async fn main() -> Result<()> {
         std::time::Duration::from_secs(60),
     ));
     let _eviction_handle = shared_router.start_eviction_timer();
+    let _context_gauges = pipeline.spawn_gauge_sampler(std::time::Duration::from_secs(10));
+    // Follow /vdrive mounts so the code index tracks the mounted tree.
+    let _code_index_sync =
+        pipeline.spawn_code_index_sync(drive_slot.clone(), std::time::Duration::from_secs(1));
 
     // If the organism declared any triggers, drain trigger events into
     // the platform router. Without this loop, triggers fire but their
@@ -741,9 +799,14 @@ fn build_pipeline(
         .register_tool("file-read", VDriveFileRead::new(slot.clone()))?
         .register_tool("file-write", VDriveFileWrite::new(slot.clone()))?
         .register_tool("file-edit", VDriveFileEdit::new(slot.clone()))?
+        .register_tool("file-multi-edit", VDriveFileMultiEdit::new(slot.clone()))?
+        .register_tool("file-patch", VDriveFilePatch::new(slot.clone()))?
+        .register_tool("file-move", VDriveFileMove::new(slot.clone()))?
+        .register_tool("file-copy", VDriveFileCopy::new(slot.clone()))?
         .register_tool("glob", VDriveGlob::new(slot.clone()))?
         .register_tool("grep", VDriveGrep::new(slot.clone()))?
         .register_tool("list-dir", VDriveListDir::new(slot.clone()))?
+        .register_tool("vdrive-undo", VDriveUndo::new(slot.clone()))?
         .register_tool(
             "bash",
             VDriveCommandExec::new(slot.clone())
@@ -797,6 +860,9 @@ fn build_pipeline(
         .map_err(|e| format!("open ticket store at {}: {e}", data_dir.display()))?;
     builder = builder.register_tool("tickets", tickets_tool)?;
 
+    // Shim trainer (shim-train listeners; backend from their `trainer:` block)
+    builder = builder.with_shim_train_tools()?;
+
     // Python tools (handler: "python" listeners in organism)
     let wasm_dir = PathBuf::from(work_dir).join("tools").join("python-runtime");
     builder = builder.with_python_tools(&PathBuf::from(work_dir), &wasm_dir)?;
@@ -817,6 +883,7 @@ fn build_pipeline(
 /// Returns a message to show in the TUI on startup, or None if no mount.
 fn try_auto_mount(
     slot: &vdrive_tools::DriveSlot,
+    data_dir: &std::path::Path,
 ) -> Option<String> {
     use std::path::Path;
 
@@ -875,7 +942,7 @@ fn try_auto_mount(
     }
 
     // Mount it
-    match agentos::tui::vdrive::mount(&canonical) {
+    match agentos::tui::vdrive::mount(&canonical, Some(data_dir)) {
         Ok(drive) => {
             let name = drive.name().to_string();
             let root = drive.root().display().to_string();